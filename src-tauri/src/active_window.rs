@@ -0,0 +1,96 @@
+use std::path::Path;
+use windows::core::PWSTR;
+use windows::Win32::Foundation::{CloseHandle, HWND};
+use windows::Win32::System::Threading::{
+    AttachThreadInput, GetCurrentThreadId, OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32,
+    PROCESS_QUERY_LIMITED_INFORMATION,
+};
+use windows::Win32::UI::WindowsAndMessaging::{
+    GetForegroundWindow, GetWindowTextW, GetWindowThreadProcessId, IsIconic, IsWindow, SetForegroundWindow,
+    ShowWindow, SW_RESTORE,
+};
+
+/// The window the user is typing into
+#[derive(Debug, Clone)]
+pub struct ForegroundWindow {
+    /// Executable name without extension, e.g. "slack" or "chrome"
+    pub app_name: String,
+    pub title: String,
+}
+
+pub fn foreground() -> Option<ForegroundWindow> {
+    unsafe {
+        let hwnd = GetForegroundWindow();
+        if hwnd.0.is_null() {
+            return None;
+        }
+
+        let mut title = [0u16; 512];
+        let len = GetWindowTextW(hwnd, &mut title).max(0) as usize;
+        let title = String::from_utf16_lossy(&title[..len]);
+
+        let mut pid = 0u32;
+        GetWindowThreadProcessId(hwnd, Some(&mut pid));
+        let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid).ok()?;
+        let mut path = [0u16; 1024];
+        let mut path_len = path.len() as u32;
+        let queried = QueryFullProcessImageNameW(process, PROCESS_NAME_WIN32, PWSTR(path.as_mut_ptr()), &mut path_len);
+        let _ = CloseHandle(process);
+        queried.ok()?;
+
+        let path = String::from_utf16_lossy(&path[..path_len as usize]);
+        let app_name = Path::new(&path).file_stem()?.to_string_lossy().to_lowercase();
+        Some(ForegroundWindow { app_name, title })
+    }
+}
+
+/// Handle of the foreground window, to bring it back later with `activate`. None when it's
+/// one of Dicta's own windows. (A plain isize because HWND can't cross threads.)
+pub fn foreground_handle() -> Option<isize> {
+    unsafe {
+        let hwnd = GetForegroundWindow();
+        if hwnd.0.is_null() {
+            return None;
+        }
+        let mut pid = 0u32;
+        GetWindowThreadProcessId(hwnd, Some(&mut pid));
+        if pid == std::process::id() {
+            return None;
+        }
+        Some(hwnd.0 as isize)
+    }
+}
+
+/// Bring a window from `foreground_handle` back to the foreground. Windows only lets the
+/// foreground thread hand focus over, so this thread joins its input queue for the switch.
+pub fn activate(handle: isize) -> Result<(), String> {
+    unsafe {
+        let hwnd = HWND(handle as *mut _);
+        if !IsWindow(hwnd).as_bool() {
+            return Err("Window was closed".to_string());
+        }
+        let foreground = GetForegroundWindow();
+        if foreground == hwnd {
+            return Ok(());
+        }
+        if IsIconic(hwnd).as_bool() {
+            let _ = ShowWindow(hwnd, SW_RESTORE);
+        }
+
+        let current_thread = GetCurrentThreadId();
+        let foreground_thread = GetWindowThreadProcessId(foreground, None);
+        let attached = foreground_thread != 0
+            && foreground_thread != current_thread
+            && AttachThreadInput(current_thread, foreground_thread, true).as_bool();
+        let activated = SetForegroundWindow(hwnd).as_bool();
+        if attached {
+            let _ = AttachThreadInput(current_thread, foreground_thread, false);
+        }
+
+        if activated {
+            Ok(())
+        } else {
+            Err("SetForegroundWindow was refused".to_string())
+        }
+    }
+}
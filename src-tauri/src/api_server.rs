@@ -0,0 +1,304 @@
+use crate::{cancel_active_recording, set_session_prompt_model, toggle_recording, AppState, RecordingTrigger, PROMPT_MODEL_CYCLE};
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Query, Request, State};
+use axum::http::StatusCode;
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde_json::json;
+use std::sync::Arc;
+use tauri::{AppHandle, Manager};
+use tokio::sync::oneshot;
+
+pub const DEFAULT_API_PORT: u16 = 7379;
+/// History endpoint returns at most this many entries
+const MAX_HISTORY_LIMIT: usize = 500;
+/// How often WebSocket clients are checked for a status change
+const WS_STATUS_INTERVAL_MS: u64 = 250;
+/// Open connections (WebSocket clients) get this long to close on stop before the server is aborted
+const SHUTDOWN_GRACE_MS: u64 = 2000;
+
+#[derive(Clone)]
+struct ApiContext {
+    app: AppHandle,
+    token: Arc<String>,
+}
+
+/// Running server; dropping the handle without `stop` leaves it running
+pub struct ApiServerHandle {
+    pub port: u16,
+    shutdown: oneshot::Sender<()>,
+    task: tauri::async_runtime::JoinHandle<()>,
+}
+
+impl ApiServerHandle {
+    /// Shut the server down and wait until it has, so the port can be bound again right away
+    pub async fn stop(self) {
+        let _ = self.shutdown.send(());
+        let mut task = self.task;
+        if tokio::time::timeout(std::time::Duration::from_millis(SHUTDOWN_GRACE_MS), &mut task).await.is_err() {
+            task.abort();
+            let _ = task.await;
+        }
+        println!("🔌 Local API server on port {} stopped", self.port);
+    }
+}
+
+/// Random token clients must send as `Authorization: Bearer <token>`
+pub fn generate_token() -> String {
+    let mut bytes = [0u8; 24];
+    rand::RngCore::fill_bytes(&mut rand::rngs::OsRng, &mut bytes);
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Start the HTTP server on 127.0.0.1:`port` (never exposed beyond this machine)
+pub async fn start(app: AppHandle, port: u16, token: String) -> Result<ApiServerHandle, String> {
+    let listener = tokio::net::TcpListener::bind(("127.0.0.1", port))
+        .await
+        .map_err(|e| format!("Failed to bind port {}: {}", port, e))?;
+
+    let context = ApiContext { app, token: Arc::new(token) };
+    let router = Router::new()
+        .route("/status", get(status))
+        .route("/recording/start", post(start_recording))
+        .route("/recording/stop", post(stop_recording))
+        .route("/recording/toggle", post(toggle))
+        .route("/transcriptions/last", get(last_transcription))
+        .route("/transcriptions", get(history))
+        .route("/json/last", get(last_json_output))
+        .route("/mcp", post(mcp_endpoint))
+        .layer(middleware::from_fn_with_state(context.clone(), require_token))
+        // WebSocket clients (Stream Deck plugins, browsers) can't set headers: token goes in the query
+        .route("/ws", get(websocket))
+        .with_state(context);
+
+    let (shutdown, shutdown_rx) = oneshot::channel::<()>();
+    let task = tauri::async_runtime::spawn(async move {
+        let server = axum::serve(listener, router).with_graceful_shutdown(async {
+            let _ = shutdown_rx.await;
+        });
+        if let Err(e) = server.await {
+            eprintln!("❌ Local API server error: {}", e);
+        }
+    });
+
+    println!("🌐 Local API server listening on http://127.0.0.1:{}", port);
+    Ok(ApiServerHandle { port, shutdown, task })
+}
+
+async fn require_token(State(context): State<ApiContext>, request: Request, next: Next) -> Response {
+    let provided = request
+        .headers()
+        .get("Authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    if provided != Some(context.token.as_str()) {
+        return (StatusCode::UNAUTHORIZED, Json(json!({ "error": "Invalid or missing token" }))).into_response();
+    }
+    next.run(request).await
+}
+
+fn is_recording(app: &AppHandle) -> bool {
+    *app.state::<AppState>().is_recording.lock().unwrap()
+}
+
+fn status_json(app: &AppHandle) -> serde_json::Value {
+    let state = app.state::<AppState>();
+    let prompt_mode = state.prompt_mode.lock().unwrap().clone();
+    let tts_active = *state.tts_active.lock().unwrap();
+    let meeting_active = state.meeting.lock().unwrap().is_some();
+    json!({
+        "recording": is_recording(app),
+        "prompt_mode": prompt_mode,
+        "tts_active": tts_active,
+        "meeting_active": meeting_active,
+    })
+}
+
+async fn status(State(context): State<ApiContext>) -> Json<serde_json::Value> {
+    Json(status_json(&context.app))
+}
+
+async fn start_recording(State(context): State<ApiContext>) -> Json<serde_json::Value> {
+    if !is_recording(&context.app) {
+        toggle_recording(&context.app, RecordingTrigger::Transcribe);
+    }
+    Json(json!({ "recording": true }))
+}
+
+async fn stop_recording(State(context): State<ApiContext>) -> Json<serde_json::Value> {
+    if is_recording(&context.app) {
+        toggle_recording(&context.app, RecordingTrigger::Transcribe);
+    }
+    Json(json!({ "recording": false }))
+}
+
+async fn toggle(State(context): State<ApiContext>) -> Json<serde_json::Value> {
+    let was_recording = is_recording(&context.app);
+    toggle_recording(&context.app, RecordingTrigger::Transcribe);
+    Json(json!({ "recording": !was_recording }))
+}
+
+async fn last_transcription(State(context): State<ApiContext>) -> Response {
+    let state = context.app.state::<AppState>();
+    match state.database.load_recent_transcriptions(1) {
+        Ok(entries) => Json(json!({ "transcription": entries.into_iter().next() })).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() }))).into_response(),
+    }
+}
+
+/// Latest JSON output mode result (null until one is produced)
+async fn last_json_output(State(context): State<ApiContext>) -> Json<serde_json::Value> {
+    let last = context.app.state::<AppState>().last_json_output.lock().unwrap().clone();
+    match last {
+        Some((created_at, json)) => Json(json!({ "json": json, "created_at": created_at })),
+        None => Json(json!({ "json": null })),
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct HistoryQuery {
+    limit: Option<usize>,
+    q: Option<String>,   // Case-insensitive text filter
+    app: Option<String>, // Only entries pasted into this app, e.g. "slack"
+    language: Option<String>, // Only entries spoken in this language, e.g. "pt"
+}
+
+async fn history(State(context): State<ApiContext>, Query(query): Query<HistoryQuery>) -> Response {
+    let state = context.app.state::<AppState>();
+    let limit = query.limit.unwrap_or(50).min(MAX_HISTORY_LIMIT);
+    let entries = match &query.q {
+        Some(q) if !q.trim().is_empty() => {
+            let needle = q.trim().to_lowercase();
+            state.database.load_transcriptions(query.app.as_deref(), query.language.as_deref()).map(|entries| {
+                entries.into_iter().filter(|e| e.text.to_lowercase().contains(&needle)).take(limit).collect()
+            })
+        }
+        _ if query.app.is_some() || query.language.is_some() => state.database
+            .load_transcriptions(query.app.as_deref(), query.language.as_deref())
+            .map(|entries| entries.into_iter().take(limit).collect()),
+        _ => state.database.load_recent_transcriptions(limit),
+    };
+    match entries {
+        Ok(entries) => Json(json!({ "transcriptions": entries })).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() }))).into_response(),
+    }
+}
+
+/// Model Context Protocol endpoint (Streamable HTTP): lets external AI agents use dicta's tools
+async fn mcp_endpoint(State(context): State<ApiContext>, Json(body): Json<serde_json::Value>) -> Response {
+    let reply = match body {
+        serde_json::Value::Array(messages) => {
+            let replies: Vec<serde_json::Value> = messages.into_iter()
+                .filter_map(|m| crate::mcp::handle(&context.app, m))
+                .collect();
+            (!replies.is_empty()).then(|| serde_json::Value::Array(replies))
+        }
+        message => crate::mcp::handle(&context.app, message),
+    };
+    match reply {
+        Some(reply) => Json(reply).into_response(),
+        None => StatusCode::ACCEPTED.into_response(),
+    }
+}
+
+// --- WebSocket control channel ---
+
+#[derive(serde::Deserialize)]
+struct WsQuery {
+    token: Option<String>,
+}
+
+/// Client -> server message, e.g. {"command": "mode", "model": "gpt-4o-mini"}
+#[derive(serde::Deserialize)]
+struct WsCommand {
+    command: String,
+    model: Option<String>,
+}
+
+async fn websocket(State(context): State<ApiContext>, Query(query): Query<WsQuery>, ws: WebSocketUpgrade) -> Response {
+    if query.token.as_deref() != Some(context.token.as_str()) {
+        return (StatusCode::UNAUTHORIZED, Json(json!({ "error": "Invalid or missing token" }))).into_response();
+    }
+    ws.on_upgrade(move |socket| handle_socket(socket, context))
+}
+
+/// Push a `status` event whenever the state changes, a `json_output` event for each new
+/// JSON output mode result, and run commands sent by the client
+async fn handle_socket(mut socket: WebSocket, context: ApiContext) {
+    println!("🔌 WebSocket client connected");
+    let mut last_status: Option<serde_json::Value> = None;
+    // Results produced before connecting aren't replayed
+    let mut last_json_at = context.app.state::<AppState>().last_json_output.lock().unwrap().as_ref().map(|(at, _)| *at);
+    let mut ticker = tokio::time::interval(std::time::Duration::from_millis(WS_STATUS_INTERVAL_MS));
+
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {
+                let status = status_json(&context.app);
+                if last_status.as_ref() != Some(&status) {
+                    let event = json!({ "event": "status", "status": status });
+                    if socket.send(Message::Text(event.to_string())).await.is_err() {
+                        break;
+                    }
+                    last_status = Some(status);
+                }
+                let json_output = context.app.state::<AppState>().last_json_output.lock().unwrap().clone();
+                if let Some((created_at, json)) = json_output.filter(|(at, _)| Some(*at) != last_json_at) {
+                    let event = json!({ "event": "json_output", "json": json, "created_at": created_at });
+                    if socket.send(Message::Text(event.to_string())).await.is_err() {
+                        break;
+                    }
+                    last_json_at = Some(created_at);
+                }
+            }
+            message = socket.recv() => match message {
+                Some(Ok(Message::Text(text))) => {
+                    let reply = match serde_json::from_str::<WsCommand>(&text) {
+                        Ok(command) => run_command(&context.app, command),
+                        Err(e) => json!({ "event": "error", "message": format!("Invalid command: {}", e) }),
+                    };
+                    if socket.send(Message::Text(reply.to_string())).await.is_err() {
+                        break;
+                    }
+                }
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+    println!("🔌 WebSocket client disconnected");
+}
+
+fn run_command(app: &AppHandle, command: WsCommand) -> serde_json::Value {
+    let recording = is_recording(app);
+    match command.command.as_str() {
+        "start" if !recording => toggle_recording(app, RecordingTrigger::Transcribe),
+        "stop" if recording => toggle_recording(app, RecordingTrigger::Transcribe),
+        "start" | "stop" => {}
+        "toggle" => toggle_recording(app, RecordingTrigger::Transcribe),
+        "cancel" => {
+            cancel_active_recording(&app.state::<AppState>(), app);
+        }
+        "status" => return json!({ "event": "status", "status": status_json(app) }),
+        // Switch the running session's model, or start a recording in that mode
+        "mode" => {
+            let Some(model) = command.model.filter(|m| PROMPT_MODEL_CYCLE.contains(&m.as_str())) else {
+                return json!({ "event": "error", "message": format!("Unknown model, expected one of {:?}", PROMPT_MODEL_CYCLE) });
+            };
+            if recording {
+                if !set_session_prompt_model(app, &model) {
+                    return json!({ "event": "error", "message": "Recording widget not visible" });
+                }
+            } else if model == "transcribe-only" {
+                *app.state::<AppState>().prompt_mode.lock().unwrap() = None;
+                toggle_recording(app, RecordingTrigger::Transcribe);
+            } else {
+                toggle_recording(app, RecordingTrigger::PromptModel(model));
+            }
+        }
+        other => return json!({ "event": "error", "message": format!("Unknown command '{}'", other) }),
+    }
+    json!({ "event": "ack", "command": command.command })
+}
@@ -0,0 +1,91 @@
+use crate::audio::{self, AudioRecorder, ClipMeter, SessionStats};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Pause after the user's last words that ends their turn
+pub const END_OF_TURN: Duration = Duration::from_millis(1200);
+/// A turn without any speech for this long ends the conversation
+pub const IDLE_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Louder than a normal recording needs: during playback the mic also hears the TTS
+/// from the speakers, which must not interrupt itself
+const BARGE_IN_RMS: f32 = 0.05;
+/// Consecutive loud frames (~250 ms) that count as the user talking over the TTS
+const BARGE_IN_FRAMES: usize = 12;
+/// Voiced frames in one poll that count as speech in Whisper sessions (ignores clicks)
+const MIN_VOICED_FRAMES: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Turn {
+    Listening,
+    /// The user spoke and then paused for END_OF_TURN
+    Ended,
+    /// Nothing was said for IDLE_TIMEOUT
+    Idle,
+}
+
+/// Follows one assistant turn. Realtime/local sessions report speech through their VAD
+/// (`speaking`); Whisper sessions are checked by the energy of the recorded samples (`feed`).
+pub struct TurnDetector {
+    started: Instant,
+    last_voice: Option<Instant>,
+}
+
+impl TurnDetector {
+    pub fn new() -> Self {
+        Self { started: Instant::now(), last_voice: None }
+    }
+
+    pub fn speaking(&mut self) {
+        self.last_voice = Some(Instant::now());
+    }
+
+    /// Samples recorded since the last call
+    pub fn feed(&mut self, samples: &[f32]) {
+        if audio::voiced_frames(samples, audio::SPEECH_RMS_THRESHOLD) >= MIN_VOICED_FRAMES {
+            self.speaking();
+        }
+    }
+
+    pub fn state(&self) -> Turn {
+        match self.last_voice {
+            Some(at) if at.elapsed() >= END_OF_TURN => Turn::Ended,
+            Some(_) => Turn::Listening,
+            None if self.started.elapsed() >= IDLE_TIMEOUT => Turn::Idle,
+            None => Turn::Listening,
+        }
+    }
+}
+
+/// Listen to the mic while `playing` is true and call `on_speech` once if the user starts
+/// talking over the playback. Runs on its own thread with its own capture stream.
+pub fn watch_for_barge_in(device: Option<String>, playing: Arc<Mutex<bool>>, on_speech: impl FnOnce() + Send + 'static) {
+    std::thread::spawn(move || {
+        let recorder = AudioRecorder::new();
+        if let Err(e) = recorder.start_recording(device, Arc::new(ClipMeter::new()), Arc::new(SessionStats::new()), None) {
+            eprintln!("⚠️ Barge-in monitor failed to open the mic: {}", e);
+            return;
+        }
+        let mut checked = 0;
+        let mut loud_run = 0;
+        let mut heard = false;
+        while *playing.lock().unwrap() {
+            std::thread::sleep(Duration::from_millis(50));
+            let count = recorder.sample_count();
+            let samples = recorder.samples_range(checked, count);
+            checked += samples.len() - samples.len() % audio::SPEECH_FRAME_LEN; // Partial frame is re-read next time
+            for frame in samples.chunks_exact(audio::SPEECH_FRAME_LEN) {
+                loud_run = if audio::voiced_frames(frame, BARGE_IN_RMS) > 0 { loud_run + 1 } else { 0 };
+                heard |= loud_run >= BARGE_IN_FRAMES;
+            }
+            if heard {
+                println!("🗣️ Barge-in: user spoke over the TTS");
+                break;
+            }
+        }
+        recorder.stop_recording();
+        if heard {
+            on_speech();
+        }
+    });
+}
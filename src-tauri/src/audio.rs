@@ -1,7 +1,201 @@
+use crate::db::SessionDiagnostics;
+use crate::sidetone::{Sidetone, SidetoneSettings};
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
 
+/// Samples at or above this magnitude (full scale = 1.0) count as clipped
+const CLIP_LEVEL: f32 = 0.99;
+/// A callback block with at least this fraction of clipped samples is "clipping"
+const CLIP_BLOCK_RATIO: f32 = 0.01;
+/// Clipping has to last this long before the user is warned
+const CLIP_SUSTAINED: Duration = Duration::from_millis(300);
+/// Minimum time between two warnings
+const CLIP_WARNING_INTERVAL: Duration = Duration::from_secs(3);
+
+#[derive(Default)]
+struct ClipState {
+    total: u64,
+    clipped: u64,
+    streak: u64, // Consecutive samples in clipping blocks
+    last_warning: Option<Instant>,
+}
+
+/// Tracks clipped samples in the current recording and reports sustained clipping
+/// (mic too close or input gain too high) to a listener
+pub struct ClipMeter {
+    state: Mutex<ClipState>,
+    listener: Mutex<Option<Box<dyn Fn(f32) + Send>>>,
+}
+
+impl ClipMeter {
+    pub fn new() -> Self {
+        Self { state: Mutex::new(ClipState::default()), listener: Mutex::new(None) }
+    }
+
+    /// Called from the capture thread with the recording's clipped percentage so far
+    pub fn set_listener(&self, listener: impl Fn(f32) + Send + 'static) {
+        *self.listener.lock().unwrap() = Some(Box::new(listener));
+    }
+
+    /// Start counting for a new recording
+    pub fn reset(&self) {
+        *self.state.lock().unwrap() = ClipState::default();
+    }
+
+    /// Feed one capture callback block (samples normalized to -1.0..1.0, `rate` samples per second)
+    pub fn feed(&self, block: impl Iterator<Item = f32>, rate: u32) {
+        let (mut len, mut clipped) = (0u64, 0u64);
+        for sample in block {
+            len += 1;
+            if sample.abs() >= CLIP_LEVEL {
+                clipped += 1;
+            }
+        }
+        if len == 0 {
+            return;
+        }
+
+        let warn_percentage = {
+            let mut state = self.state.lock().unwrap();
+            state.total += len;
+            state.clipped += clipped;
+            if clipped as f32 / len as f32 >= CLIP_BLOCK_RATIO {
+                state.streak += len;
+            } else {
+                state.streak = 0;
+            }
+
+            let sustained = state.streak as f32 / rate as f32 >= CLIP_SUSTAINED.as_secs_f32();
+            let due = state.last_warning.map_or(true, |at| at.elapsed() >= CLIP_WARNING_INTERVAL);
+            if sustained && due {
+                state.last_warning = Some(Instant::now());
+                Some(state.clipped as f32 / state.total as f32 * 100.0)
+            } else {
+                None
+            }
+        };
+
+        if let Some(percentage) = warn_percentage {
+            if let Some(listener) = self.listener.lock().unwrap().as_ref() {
+                listener(percentage);
+            }
+        }
+    }
+
+    /// Share of clipped samples in the current recording, in percent
+    pub fn percentage(&self) -> f32 {
+        let state = self.state.lock().unwrap();
+        if state.total == 0 {
+            return 0.0;
+        }
+        state.clipped as f32 / state.total as f32 * 100.0
+    }
+}
+
+/// Callback timestamps jitter a little; only gaps longer than this count as dropped frames
+const DROP_TOLERANCE: Duration = Duration::from_millis(5);
+
+#[derive(Default)]
+struct StatsState {
+    diagnostics: SessionDiagnostics,
+    level_sum: f64,
+    samples: u64,
+    next_capture: Option<cpal::StreamInstant>, // Where the next callback should start if none were lost
+}
+
+/// Capture statistics of the current recording, saved next to its transcription
+pub struct SessionStats {
+    state: Mutex<StatsState>,
+}
+
+impl SessionStats {
+    pub fn new() -> Self {
+        Self { state: Mutex::new(StatsState::default()) }
+    }
+
+    /// Start counting for a new recording
+    pub fn reset(&self) {
+        *self.state.lock().unwrap() = StatsState::default();
+    }
+
+    /// Record how the stream was opened
+    pub fn set_stream(&self, device: String, native_sample_rate: u32, channels: u16, resampler: &str, exclusive: bool) {
+        let mut state = self.state.lock().unwrap();
+        state.diagnostics.device = device;
+        state.diagnostics.native_sample_rate = native_sample_rate;
+        state.diagnostics.channels = channels;
+        state.diagnostics.resampler = resampler.to_string();
+        state.diagnostics.exclusive = exclusive;
+    }
+
+    /// Feed one capture callback block (samples normalized to -1.0..1.0)
+    pub fn feed(&self, block: impl Iterator<Item = f32>) {
+        let (mut len, mut sum) = (0u64, 0f64);
+        for sample in block {
+            len += 1;
+            sum += sample.abs() as f64;
+        }
+        let mut state = self.state.lock().unwrap();
+        state.samples += len;
+        state.level_sum += sum;
+    }
+
+    /// Count frames lost between cpal callbacks from the callback's capture timestamp
+    pub fn track_callback(&self, capture: cpal::StreamInstant, frames: usize) {
+        let mut state = self.state.lock().unwrap();
+        let rate = state.diagnostics.native_sample_rate;
+        if rate == 0 {
+            return;
+        }
+        if let Some(gap) = state.next_capture.and_then(|expected| capture.duration_since(&expected)) {
+            if gap > DROP_TOLERANCE {
+                state.diagnostics.dropped_frames += (gap.as_secs_f64() * rate as f64) as u64;
+            }
+        }
+        state.next_capture = capture.add(Duration::from_secs_f64(frames as f64 / rate as f64));
+    }
+
+    /// Count frames the driver reports as lost (exclusive mode)
+    pub fn add_dropped_frames(&self, frames: u64) {
+        self.state.lock().unwrap().diagnostics.dropped_frames += frames;
+    }
+
+    pub fn snapshot(&self) -> SessionDiagnostics {
+        let state = self.state.lock().unwrap();
+        let mut diagnostics = state.diagnostics.clone();
+        if state.samples > 0 {
+            diagnostics.average_level = (state.level_sum / state.samples as f64) as f32;
+        }
+        diagnostics
+    }
+}
+
+/// Frames quieter than this RMS level (full scale = 1.0) don't count as speech
+pub const SPEECH_RMS_THRESHOLD: f32 = 0.01;
+/// Samples per analysis frame (~20 ms at 48 kHz)
+pub const SPEECH_FRAME_LEN: usize = 1024;
+/// A recording needs at least this many voiced frames (~200 ms) to be worth transcribing
+const MIN_VOICED_FRAMES: usize = 10;
+
+/// Energy-based check for whether a recording has anything to transcribe.
+/// Silent recordings make Whisper hallucinate ("Thank you for watching") and still cost a request.
+pub fn contains_speech(samples: &[f32]) -> bool {
+    voiced_frames(samples, SPEECH_RMS_THRESHOLD) >= MIN_VOICED_FRAMES
+}
+
+/// Number of SPEECH_FRAME_LEN frames of `samples` whose RMS reaches `rms_threshold`
+pub fn voiced_frames(samples: &[f32], rms_threshold: f32) -> usize {
+    samples
+        .chunks(SPEECH_FRAME_LEN)
+        .filter(|frame| {
+            let rms = (frame.iter().map(|s| s * s).sum::<f32>() / frame.len() as f32).sqrt();
+            rms >= rms_threshold
+        })
+        .count()
+}
+
 /// Get audio input device by name, or default if not found
 pub fn get_input_device_by_name(device_name: Option<&str>) -> Result<cpal::Device, String> {
     println!("🔍 DEBUG get_input_device_by_name: device_name = {:?}", device_name);
@@ -111,7 +305,8 @@ impl AudioRecorder {
         }
     }
 
-    pub fn start_recording(&self, device_name: Option<String>) -> Result<(), String> {
+    /// `sidetone`: monitoring settings when the mic should be played back while recording
+    pub fn start_recording(&self, device_name: Option<String>, clip_meter: Arc<ClipMeter>, stats: Arc<SessionStats>, sidetone: Option<SidetoneSettings>) -> Result<(), String> {
         let recording = self.recording.clone();
         let audio_data = self.audio_data.clone();
 
@@ -145,13 +340,21 @@ impl AudioRecorder {
             println!("📊 Channels: {}", config.channels());
 
             let recording_for_callback = recording.clone();
+            let native_rate = config.sample_rate().0;
             let channels = config.channels() as usize;
+            let sample_rate = config.sample_rate().0 * channels as u32;
+            // Samples go to Whisper as-is, in a WAV labelled 48kHz
+            stats.set_stream(host.name().unwrap_or_default(), config.sample_rate().0, config.channels(), "none (WAV at 48kHz)", false);
 
+            let audio_for_callback = audio_data.clone();
             let stream = match host.build_input_stream(
                 &config.into(),
-                move |data: &[f32], _: &cpal::InputCallbackInfo| {
+                move |data: &[f32], info: &cpal::InputCallbackInfo| {
                     if *recording_for_callback.lock().unwrap() {
-                        let mut audio = audio_data.lock().unwrap();
+                        clip_meter.feed(data.iter().copied(), sample_rate);
+                        stats.feed(data.iter().copied());
+                        stats.track_callback(info.timestamp().capture, data.len() / channels);
+                        let mut audio = audio_for_callback.lock().unwrap();
 
                         // Convert stereo/multi-channel to mono by averaging channels
                         if channels == 1 {
@@ -183,9 +386,19 @@ impl AudioRecorder {
 
             println!("🎤 Whisper: Audio stream thread started");
 
-            // Keep stream alive while recording
+            // Keep stream alive while recording, playing new samples back when monitoring
+            let mut sidetone = sidetone.as_ref().and_then(Sidetone::start);
+            let mut monitored = 0;
             while *recording.lock().unwrap() {
-                std::thread::sleep(std::time::Duration::from_millis(100));
+                match &mut sidetone {
+                    Some(sidetone) => {
+                        std::thread::sleep(std::time::Duration::from_millis(10));
+                        let audio = audio_data.lock().unwrap();
+                        sidetone.feed(audio[monitored.min(audio.len())..].iter().copied(), native_rate);
+                        monitored = audio.len();
+                    }
+                    None => std::thread::sleep(std::time::Duration::from_millis(100)),
+                }
             }
 
             // Drop stream to release microphone
@@ -196,6 +409,21 @@ impl AudioRecorder {
         Ok(())
     }
 
+    /// Number of mono samples captured so far in the current recording
+    pub fn sample_count(&self) -> usize {
+        self.audio_data.lock().unwrap().len()
+    }
+
+    /// Copy of the captured samples in `start..end` (clamped to what's been recorded)
+    pub fn samples_range(&self, start: usize, end: usize) -> Vec<f32> {
+        let audio = self.audio_data.lock().unwrap();
+        let end = end.min(audio.len());
+        if start >= end {
+            return Vec::new();
+        }
+        audio[start..end].to_vec()
+    }
+
     pub fn stop_recording(&self) -> Vec<f32> {
         *self.recording.lock().unwrap() = false;
 
@@ -324,8 +552,38 @@ impl StreamingAudioRecorder {
         }
     }
 
-    /// Start recording and return a channel to receive audio chunks
-    pub fn start_streaming(&mut self, device_name: Option<String>) -> Result<mpsc::UnboundedReceiver<Vec<i16>>, String> {
+    /// Start recording and return a channel to receive audio chunks.
+    /// With `exclusive`, the mic is opened in WASAPI exclusive mode with a minimal buffer for lower
+    /// capture latency, falling back to shared mode if that fails.
+    pub fn start_streaming(
+        &mut self,
+        device_name: Option<String>,
+        clip_meter: Arc<ClipMeter>,
+        stats: Arc<SessionStats>,
+        exclusive: bool,
+    ) -> Result<mpsc::UnboundedReceiver<Vec<i16>>, String> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.chunk_sender = Some(tx.clone());
+
+        let recording = self.recording.clone();
+        *recording.lock().unwrap() = true;
+
+        if exclusive {
+            let recording_for_handler = recording.clone();
+            let clip_meter = clip_meter.clone();
+            let stats_for_handler = stats.clone();
+            let tx = tx.clone();
+            let started = crate::exclusive_capture::start(device_name.clone(), recording.clone(), stats.clone(), move |format| {
+                realtime_chunk_handler(format.sample_rate, format.channels as usize, recording_for_handler, clip_meter, stats_for_handler, tx)
+            });
+            match started {
+                Ok(format) => {
+                    println!("✅ Streaming recording started in exclusive mode ({}Hz -> 24kHz)", format.sample_rate);
+                    return Ok(rx);
+                }
+                Err(e) => println!("⚠️ Exclusive mode unavailable ({}), using shared mode", e),
+            }
+        }
 
         let device = get_input_device_by_name(device_name.as_deref())?;
 
@@ -341,83 +599,18 @@ impl StreamingAudioRecorder {
         println!("📊 Target sample rate: 24000 Hz (for Realtime API)");
         println!("📊 Channels: {}", config.channels);
 
-        let (tx, rx) = mpsc::unbounded_channel();
-        self.chunk_sender = Some(tx.clone());
-
-        let recording = self.recording.clone();
-        *recording.lock().unwrap() = true;
-
         let channels = config.channels as usize;
+        stats.set_stream(device.name().unwrap_or_default(), native_rate, config.channels, realtime_resampler(native_rate), false);
+        let stats_for_callback = stats.clone();
+        let mut handler = realtime_chunk_handler(native_rate, channels, recording, clip_meter, stats, tx);
 
         // Build stream for i16 samples (PCM 16-bit)
         let stream = device
             .build_input_stream(
                 &config,
-                move |data: &[i16], _: &cpal::InputCallbackInfo| {
-                    if *recording.lock().unwrap() {
-                        // Convert stereo/multi-channel to mono
-                        let mono_data: Vec<i16> = if channels == 1 {
-                            data.to_vec()
-                        } else {
-                            data.chunks_exact(channels)
-                                .map(|frame| {
-                                    let sum: i32 = frame.iter().map(|&s| s as i32).sum();
-                                    (sum / channels as i32) as i16
-                                })
-                                .collect()
-                        };
-
-                        // Resample to 24kHz if needed
-                        let resampled: Vec<i16> = if native_rate == 24000 {
-                            // No resampling needed
-                            mono_data
-                        } else if native_rate > 24000 && native_rate % 24000 == 0 {
-                            // Downsample by decimation (e.g., 48kHz -> 24kHz)
-                            let step = (native_rate / 24000) as usize;
-                            mono_data.iter().step_by(step).copied().collect()
-                        } else if native_rate == 16000 {
-                            // Special case: 16kHz -> 24kHz (ratio 2:3)
-                            // Upsample by 3, then downsample by 2
-                            // Or simpler: linear interpolation
-                            let mut result = Vec::with_capacity((mono_data.len() * 3) / 2);
-                            for i in 0..mono_data.len() - 1 {
-                                let curr = mono_data[i];
-                                let next = mono_data[i + 1];
-                                // Output 3 samples for every 2 input samples
-                                result.push(curr);
-                                result.push(((curr as i32 * 2 + next as i32) / 3) as i16); // interpolate
-                                if i % 2 == 1 {
-                                    result.push(next);
-                                }
-                            }
-                            result
-                        } else {
-                            // Other rates - linear interpolation
-                            let ratio = 24000.0 / native_rate as f32;
-                            let output_len = (mono_data.len() as f32 * ratio) as usize;
-                            let mut result = Vec::with_capacity(output_len);
-
-                            for i in 0..output_len {
-                                let src_pos = i as f32 / ratio;
-                                let src_idx = src_pos as usize;
-
-                                if src_idx + 1 < mono_data.len() {
-                                    let frac = src_pos - src_idx as f32;
-                                    let sample = mono_data[src_idx] as f32 * (1.0 - frac) +
-                                                 mono_data[src_idx + 1] as f32 * frac;
-                                    result.push(sample as i16);
-                                } else if src_idx < mono_data.len() {
-                                    result.push(mono_data[src_idx]);
-                                }
-                            }
-                            result
-                        };
-
-                        // Send chunk through channel
-                        if !resampled.is_empty() {
-                            let _ = tx.send(resampled);
-                        }
-                    }
+                move |data: &[i16], info: &cpal::InputCallbackInfo| {
+                    stats_for_callback.track_callback(info.timestamp().capture, data.len() / channels);
+                    handler(data)
                 },
                 |err| eprintln!("Stream error: {}", err),
                 None,
@@ -446,6 +639,102 @@ impl StreamingAudioRecorder {
     }
 }
 
+/// Which branch of `realtime_chunk_handler` converts `native_rate` to 24kHz
+pub(crate) fn realtime_resampler(native_rate: u32) -> &'static str {
+    if native_rate == 24000 {
+        "none"
+    } else if native_rate > 24000 && native_rate % 24000 == 0 {
+        "decimation"
+    } else if native_rate == 16000 {
+        "16k->24k interpolation"
+    } else {
+        "linear interpolation"
+    }
+}
+
+/// Callback body for realtime capture: downmixes interleaved i16 input to mono, resamples it
+/// to 24kHz and sends it down `tx` while `recording` is set
+fn realtime_chunk_handler(
+    native_rate: u32,
+    channels: usize,
+    recording: Arc<Mutex<bool>>,
+    clip_meter: Arc<ClipMeter>,
+    stats: Arc<SessionStats>,
+    tx: mpsc::UnboundedSender<Vec<i16>>,
+) -> impl FnMut(&[i16]) + Send + 'static {
+    let sample_rate = native_rate * channels as u32;
+
+    move |data: &[i16]| {
+        if *recording.lock().unwrap() {
+            clip_meter.feed(data.iter().map(|&s| s as f32 / 32768.0), sample_rate);
+            stats.feed(data.iter().map(|&s| s as f32 / 32768.0));
+
+            // Convert stereo/multi-channel to mono
+            let mono_data: Vec<i16> = if channels == 1 {
+                data.to_vec()
+            } else {
+                data.chunks_exact(channels)
+                    .map(|frame| {
+                        let sum: i32 = frame.iter().map(|&s| s as i32).sum();
+                        (sum / channels as i32) as i16
+                    })
+                    .collect()
+            };
+
+            // Resample to 24kHz if needed
+            let resampled: Vec<i16> = if native_rate == 24000 {
+                // No resampling needed
+                mono_data
+            } else if native_rate > 24000 && native_rate % 24000 == 0 {
+                // Downsample by decimation (e.g., 48kHz -> 24kHz)
+                let step = (native_rate / 24000) as usize;
+                mono_data.iter().step_by(step).copied().collect()
+            } else if native_rate == 16000 {
+                // Special case: 16kHz -> 24kHz (ratio 2:3)
+                // Upsample by 3, then downsample by 2
+                // Or simpler: linear interpolation
+                let mut result = Vec::with_capacity((mono_data.len() * 3) / 2);
+                for i in 0..mono_data.len() - 1 {
+                    let curr = mono_data[i];
+                    let next = mono_data[i + 1];
+                    // Output 3 samples for every 2 input samples
+                    result.push(curr);
+                    result.push(((curr as i32 * 2 + next as i32) / 3) as i16); // interpolate
+                    if i % 2 == 1 {
+                        result.push(next);
+                    }
+                }
+                result
+            } else {
+                // Other rates - linear interpolation
+                let ratio = 24000.0 / native_rate as f32;
+                let output_len = (mono_data.len() as f32 * ratio) as usize;
+                let mut result = Vec::with_capacity(output_len);
+
+                for i in 0..output_len {
+                    let src_pos = i as f32 / ratio;
+                    let src_idx = src_pos as usize;
+
+                    if src_idx + 1 < mono_data.len() {
+                        let frac = src_pos - src_idx as f32;
+                        let sample = mono_data[src_idx] as f32 * (1.0 - frac) +
+                                     mono_data[src_idx + 1] as f32 * frac;
+                        result.push(sample as i16);
+                    } else if src_idx < mono_data.len() {
+                        result.push(mono_data[src_idx]);
+                    }
+                }
+                result
+            };
+
+            // Send chunk through channel
+            if !resampled.is_empty() {
+                let _ = tx.send(resampled);
+            }
+        }
+    }
+}
+
 /// Convert i16 PCM samples to bytes (little-endian)
 pub fn pcm_to_bytes(samples: &[i16]) -> Vec<u8> {
     samples
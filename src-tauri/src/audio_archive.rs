@@ -0,0 +1,59 @@
+use std::path::{Path, PathBuf};
+
+/// Recordings kept after they're transcribed (`archive_session_audio`): one WAV per session,
+/// named after the session id stored with its history entry, so the entry can be
+/// transcribed again later with another model.
+pub fn path(dir: &Path, session_id: &str) -> PathBuf {
+    dir.join(format!("{}.wav", session_id))
+}
+
+/// Archive a Whisper recording (f32 samples)
+pub fn save_f32(dir: &Path, session_id: &str, audio: &[f32], sample_rate: u32) -> Result<PathBuf, String> {
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate,
+        bits_per_sample: 32,
+        sample_format: hound::SampleFormat::Float,
+    };
+    write(dir, session_id, spec, audio.iter().copied())
+}
+
+/// Archive a live session's audio (i16 samples, as sent to the Realtime API)
+pub fn save_i16(dir: &Path, session_id: &str, audio: &[i16], sample_rate: u32) -> Result<PathBuf, String> {
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    write(dir, session_id, spec, audio.iter().copied())
+}
+
+fn write<S: hound::Sample>(dir: &Path, session_id: &str, spec: hound::WavSpec, samples: impl Iterator<Item = S>) -> Result<PathBuf, String> {
+    std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let path = path(dir, session_id);
+    let mut writer = hound::WavWriter::create(&path, spec).map_err(|e| format!("WAV create error: {}", e))?;
+    for sample in samples {
+        writer.write_sample(sample).map_err(|e| format!("WAV write error: {}", e))?;
+    }
+    writer.finalize().map_err(|e| format!("WAV finalize error: {}", e))?;
+    println!("🗄️ Archived session audio to {}", path.display());
+    Ok(path)
+}
+
+/// The archived audio of a session and its sample rate
+pub fn load(dir: &Path, session_id: &str) -> Result<(Vec<f32>, u32), String> {
+    let path = path(dir, session_id);
+    if !path.exists() {
+        return Err("Nenhum áudio arquivado para esta transcrição".to_string());
+    }
+    crate::queue::read_wav_to_f32_with_rate(path.to_str().ok_or("Invalid archive path")?)
+}
+
+/// Delete a session's archived audio, if any
+pub fn delete(dir: &Path, session_id: &str) {
+    let path = path(dir, session_id);
+    if path.exists() {
+        crate::queue::scrub_wav_file(&path.to_string_lossy());
+    }
+}
@@ -0,0 +1,152 @@
+use crate::audio::AudioRecorder;
+use crate::events::{emit_app_event, AppEvent};
+use crate::openai::OpenAIClient;
+use crate::realtime::SegmentTracker;
+use crate::session::SessionId;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use tauri::AppHandle;
+
+/// A chunk is cut once at least this much unflushed audio is buffered...
+const CHUNK_MIN_SECS: usize = 10;
+/// ...at the quietest point found before this limit
+const CHUNK_MAX_SECS: usize = 15;
+/// Window used to measure loudness when looking for a cut point
+const CUT_WINDOW_MS: usize = 100;
+/// Tails shorter than this are mostly silence/key noise and make Whisper hallucinate
+const MIN_TAIL_MS: usize = 300;
+
+/// Chunk results, emitted as `transcription-delta` in recording order
+struct Progress {
+    results: BTreeMap<usize, Option<String>>, // chunk index -> text (None = failed)
+    emitted: usize,                           // chunks [0, emitted) already sent to the frontend
+    segments: SegmentTracker,                 // Each chunk is shown as one finished turn, "chunk-{index}"
+}
+
+/// Pseudo-realtime Whisper session: flushes 10-15s chunks of the recording and
+/// transcribes them in parallel while recording continues, then stitches on stop
+pub struct ChunkedSession {
+    sample_rate: usize,
+    next_offset: usize, // Sample offset where the next chunk starts
+    next_index: usize,
+    prompt: Option<String>, // Context primer for the first chunk
+    progress: Arc<Mutex<Progress>>,
+    tasks: Vec<tauri::async_runtime::JoinHandle<()>>,
+    session_id: SessionId,
+}
+
+impl ChunkedSession {
+    pub fn new(sample_rate: u32, prompt: Option<String>, session_id: SessionId) -> Self {
+        Self {
+            sample_rate: sample_rate as usize,
+            next_offset: 0,
+            next_index: 0,
+            prompt,
+            progress: Arc::new(Mutex::new(Progress { results: BTreeMap::new(), emitted: 0, segments: SegmentTracker::default() })),
+            tasks: Vec::new(),
+            session_id,
+        }
+    }
+
+    /// Flush a chunk if enough audio is buffered. Called periodically while recording.
+    pub fn poll(&mut self, recorder: &AudioRecorder, openai: &Arc<OpenAIClient>, app: &AppHandle) {
+        let buffered = recorder.sample_count().saturating_sub(self.next_offset);
+        if buffered < CHUNK_MAX_SECS * self.sample_rate {
+            return;
+        }
+
+        let window = recorder.samples_range(self.next_offset, self.next_offset + buffered);
+        let cut = find_cut_point(&window, self.sample_rate);
+        let mut chunk = window;
+        chunk.truncate(cut);
+        self.next_offset += cut;
+        self.spawn_chunk(chunk, openai, app);
+    }
+
+    fn spawn_chunk(&mut self, chunk: Vec<f32>, openai: &Arc<OpenAIClient>, app: &AppHandle) {
+        let index = self.next_index;
+        self.next_index += 1;
+        let prompt = if index == 0 { self.prompt.take() } else { None };
+        println!("[{}] 🧩 Chunk {} flushed ({:.1}s)", self.session_id.short(), index, chunk.len() as f32 / self.sample_rate as f32);
+
+        let openai = openai.clone();
+        let app = app.clone();
+        let progress = self.progress.clone();
+        let sample_rate = self.sample_rate as u32;
+        let session_id = self.session_id;
+        self.tasks.push(tauri::async_runtime::spawn(async move {
+            let result = openai.transcribe_audio(chunk, sample_rate, prompt.as_deref()).await;
+            if let Err(e) = &result {
+                eprintln!("[{}] ❌ Chunk {} transcription failed: {}", session_id.short(), index, e);
+            }
+
+            let mut progress = progress.lock().unwrap();
+            progress.results.insert(index, result.ok());
+
+            // Emit every chunk that is now contiguous with what was already emitted
+            while let Some(Some(text)) = progress.results.get(&progress.emitted).cloned() {
+                if !text.is_empty() {
+                    let segment = progress.segments.completed(&format!("chunk-{}", progress.emitted), &text);
+                    let delta = if progress.emitted == 0 { text } else { format!(" {}", text) };
+                    let _ = emit_app_event(&app, AppEvent::TranscriptionDelta { session_id, delta, segment });
+                }
+                progress.emitted += 1;
+            }
+        }));
+    }
+
+    /// Transcribe the remaining tail, wait for all chunks and stitch them in order.
+    /// Errors if any chunk failed so the caller can fall back to the full recording.
+    pub async fn finish(mut self, audio: &[f32], openai: &Arc<OpenAIClient>, app: &AppHandle) -> Result<String, String> {
+        let tail = audio.get(self.next_offset..).unwrap_or(&[]).to_vec();
+        if tail.len() >= MIN_TAIL_MS * self.sample_rate / 1000 || self.next_index == 0 {
+            self.spawn_chunk(tail, openai, app);
+        }
+
+        for task in self.tasks.drain(..) {
+            task.await.map_err(|e| format!("Chunk task failed: {}", e))?;
+        }
+
+        let progress = self.progress.lock().unwrap();
+        let mut parts = Vec::with_capacity(progress.results.len());
+        for (index, result) in progress.results.iter() {
+            match result {
+                Some(text) => parts.push(text.trim().to_string()),
+                None => return Err(format!("Chunk {} failed", index)),
+            }
+        }
+        let text = parts.into_iter().filter(|p| !p.is_empty()).collect::<Vec<_>>().join(" ");
+        println!("[{}] 🧩 Stitched {} chunks: {}", self.session_id.short(), progress.results.len(), text);
+        Ok(text)
+    }
+
+    /// Abort in-flight chunk requests (recording cancelled)
+    pub fn abort(self) {
+        for task in self.tasks {
+            task.abort();
+        }
+    }
+}
+
+/// Quietest point between CHUNK_MIN_SECS and CHUNK_MAX_SECS, so cuts land between words
+pub fn find_cut_point(samples: &[f32], sample_rate: usize) -> usize {
+    let window = CUT_WINDOW_MS * sample_rate / 1000;
+    let start = CHUNK_MIN_SECS * sample_rate;
+    let end = (CHUNK_MAX_SECS * sample_rate).min(samples.len());
+    if start + window > end {
+        return end;
+    }
+
+    let mut best = end;
+    let mut best_energy = f32::MAX;
+    let mut pos = start;
+    while pos + window <= end {
+        let energy: f32 = samples[pos..pos + window].iter().map(|s| s * s).sum();
+        if energy < best_energy {
+            best_energy = energy;
+            best = pos + window / 2;
+        }
+        pos += window;
+    }
+    best
+}
@@ -0,0 +1,88 @@
+use windows::core::w;
+use windows::Win32::Foundation::{GlobalFree, HANDLE};
+use windows::Win32::System::DataExchange::{CloseClipboard, EmptyClipboard, OpenClipboard, RegisterClipboardFormatW, SetClipboardData};
+use windows::Win32::System::Memory::{GlobalAlloc, GlobalLock, GlobalUnlock, GMEM_MOVEABLE};
+
+const CF_UNICODETEXT: u32 = 13;
+
+/// Put text (and optionally HTML) on the clipboard marked so clipboard managers and the Windows
+/// clipboard history (Win+V) skip it: the paste only borrows the clipboard, and every dictation
+/// would otherwise end up in the history next to the user's own copies.
+/// The markers have to be set in the same write, before managers are notified of the change.
+pub fn write_excluded(text: &str, html: Option<&str>) -> Result<(), String> {
+    let wide: Vec<u16> = text.encode_utf16().chain(Some(0)).collect();
+    let wide_bytes: Vec<u8> = wide.iter().flat_map(|c| c.to_ne_bytes()).collect();
+
+    unsafe {
+        open()?;
+        let result = (|| {
+            EmptyClipboard().map_err(|e| format!("Failed to empty clipboard: {}", e))?;
+            set(CF_UNICODETEXT, &wide_bytes)?;
+            if let Some(html) = html {
+                let mut data = cf_html(html).into_bytes();
+                data.push(0);
+                set(RegisterClipboardFormatW(w!("HTML Format")), &data)?;
+            }
+            // Clipboard managers (Ditto, ClipboardFusion, ...)
+            set(RegisterClipboardFormatW(w!("ExcludeClipboardContentFromMonitorProcessing")), &0u32.to_ne_bytes())?;
+            // Windows clipboard history and cloud sync
+            set(RegisterClipboardFormatW(w!("CanIncludeInClipboardHistory")), &0u32.to_ne_bytes())?;
+            set(RegisterClipboardFormatW(w!("CanUploadToCloudClipboard")), &0u32.to_ne_bytes())?;
+            Ok(())
+        })();
+        let _ = CloseClipboard();
+        result
+    }
+}
+
+/// Another app may hold the clipboard for a moment
+unsafe fn open() -> Result<(), String> {
+    let mut attempts = 0;
+    loop {
+        match OpenClipboard(None) {
+            Ok(()) => return Ok(()),
+            Err(e) => {
+                attempts += 1;
+                if attempts >= 5 {
+                    return Err(format!("Failed to open clipboard: {}", e));
+                }
+                std::thread::sleep(std::time::Duration::from_millis(20));
+            }
+        }
+    }
+}
+
+/// Copy `data` into global memory and hand it to the open clipboard under `format`
+unsafe fn set(format: u32, data: &[u8]) -> Result<(), String> {
+    let memory = GlobalAlloc(GMEM_MOVEABLE, data.len()).map_err(|e| format!("Failed to allocate clipboard data: {}", e))?;
+    let ptr = GlobalLock(memory) as *mut u8;
+    if ptr.is_null() {
+        let _ = GlobalFree(memory);
+        return Err("Failed to lock clipboard data".to_string());
+    }
+    std::ptr::copy_nonoverlapping(data.as_ptr(), ptr, data.len());
+    let _ = GlobalUnlock(memory);
+    // On success the clipboard owns the memory
+    if let Err(e) = SetClipboardData(format, HANDLE(memory.0)) {
+        let _ = GlobalFree(memory);
+        return Err(format!("Failed to set clipboard data: {}", e));
+    }
+    Ok(())
+}
+
+/// Wrap an HTML fragment in the CF_HTML header, whose offsets count UTF-8 bytes
+fn cf_html(fragment: &str) -> String {
+    const PREFIX: &str = "<html><body><!--StartFragment-->";
+    const SUFFIX: &str = "<!--EndFragment--></body></html>";
+    let header = |start_html: usize, end_html: usize, start_fragment: usize, end_fragment: usize| {
+        format!(
+            "Version:0.9\r\nStartHTML:{:010}\r\nEndHTML:{:010}\r\nStartFragment:{:010}\r\nEndFragment:{:010}\r\n",
+            start_html, end_html, start_fragment, end_fragment
+        )
+    };
+    let start_html = header(0, 0, 0, 0).len();
+    let start_fragment = start_html + PREFIX.len();
+    let end_fragment = start_fragment + fragment.len();
+    let end_html = end_fragment + SUFFIX.len();
+    format!("{}{}{}{}", header(start_html, end_html, start_fragment, end_fragment), PREFIX, fragment, SUFFIX)
+}
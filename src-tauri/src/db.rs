@@ -1,5 +1,9 @@
+use crate::openai::WordConfidence;
+use crate::realtime::TranscriptSegment;
 use rusqlite::{Connection, Result};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 
@@ -12,6 +16,22 @@ pub struct TranscriptionEntry {
     pub model: Option<String>,
     pub cost_cents: Option<i64>, // hundredths of a cent for precision
     pub mode: Option<String>,    // "transcription" or "prompt"
+    #[serde(default)]
+    pub app_name: Option<String>, // Foreground app it was pasted into, e.g. "slack"
+    #[serde(default)]
+    pub window_title: Option<String>,
+    #[serde(default)]
+    pub clipping_pct: Option<f64>, // Share of clipped input samples, for diagnosing bad transcripts
+    #[serde(default)]
+    pub session_id: Option<String>, // Recording/prompt session that produced it (see session.rs)
+    #[serde(default)]
+    pub cleaned_text: Option<String>, // GPT-cleaned version made on demand; `text` keeps the original
+    #[serde(default)]
+    pub title: Option<String>, // Short label for the history list (see titles.rs)
+    #[serde(default)]
+    pub language: Option<String>, // Spoken language, ISO 639-1 when known (e.g. "pt")
+    #[serde(default)]
+    pub word_confidence: Option<Vec<WordConfidence>>, // Whisper's confidence in each word of `text`, when reported
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -21,6 +41,15 @@ pub struct ConversationMessage {
     pub timestamp: i64,
 }
 
+/// A conversation_history row, for browsing the conversation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConversationEntry {
+    pub id: i64,
+    pub role: String, // "user" or "assistant"
+    pub content: String,
+    pub timestamp: i64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StatsData {
     pub total_words: i64,
@@ -38,6 +67,148 @@ pub struct PendingQueueItem {
     pub model: String,
     pub created_at: i64,
     pub retry_count: i64,
+    pub session_id: Option<String>, // Session that failed and queued it
+}
+
+/// TTS pronunciation override: `word` is spoken as `replacement`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LexiconEntry {
+    pub id: i64,
+    pub word: String,
+    pub replacement: String,
+}
+
+/// Voice command: "<trigger> <phrase> ..." runs `action` with `value`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VoiceIntent {
+    pub id: i64,
+    pub phrase: String,
+    pub action: String, // 'open_app' | 'type_snippet' | 'shell'
+    pub value: String,  // target, snippet or command line; "{text}" = what was said after the phrase
+}
+
+/// Default behavior when a recording starts in `app_name` (executable name without extension, lowercase)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppRule {
+    pub id: i64,
+    pub app_name: String,
+    pub model: String,         // Prompt model, or "transcribe-only"
+    pub style: Option<String>, // Formatting applied to dictations, e.g. "formal"
+}
+
+/// Action item extracted from a transcription by GPT
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActionItem {
+    pub id: i64,
+    pub transcription_id: i64,
+    pub text: String,
+    pub created_at: i64,
+}
+
+/// Another version of a history entry's text, with where it came from. `kind` is "original"
+/// (as transcribed), "post_processed" (after restyling, numbers, filters and hooks), "cleaned",
+/// "edited" or "retranscription"; `model` is what produced it, when a model did.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranscriptionRevision {
+    pub id: i64,
+    pub transcription_id: i64,
+    pub text: String,
+    pub kind: String,
+    pub model: Option<String>,
+    pub created_at: i64,
+}
+
+/// Meeting mode recording: transcript grows in chunks, summaries are generated by GPT
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MeetingEntry {
+    pub id: i64,
+    pub started_at: i64,
+    pub ended_at: Option<i64>, // None while the meeting is still being recorded
+    pub transcript: String,
+    pub rolling_summary: String,       // Updated every few minutes during the meeting
+    pub final_summary: Option<String>,
+    pub action_items: Vec<String>,
+}
+
+/// How the audio of one recording was captured, kept to explain bad transcripts after the fact
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionDiagnostics {
+    pub device: String,
+    pub native_sample_rate: u32,
+    pub channels: u16,
+    pub resampler: String,  // Conversion applied before upload, e.g. "decimate 2:1"
+    pub exclusive: bool,    // WASAPI exclusive mode
+    pub average_level: f32, // Mean absolute sample value (full scale = 1.0)
+    pub dropped_frames: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SettingsProfile {
+    pub name: String,
+    pub settings: HashMap<String, String>, // setting key -> value snapshot
+    pub updated_at: i64,
+}
+
+/// Identity of a transcription across devices (local ids differ per machine)
+pub fn sync_key(timestamp: i64, text: &str) -> String {
+    let hash = Sha256::digest(text.as_bytes());
+    let hex: String = hash.iter().take(8).map(|b| format!("{:02x}", b)).collect();
+    format!("{}:{}", timestamp, hex)
+}
+
+/// Of the sessions of just deleted entries, those no remaining entry belongs to
+fn unreferenced_sessions(conn: &Connection, sessions: Vec<String>) -> Result<Vec<String>> {
+    let mut unreferenced: Vec<String> = Vec::new();
+    for session_id in sessions {
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM transcriptions WHERE session_id = ?1", [&session_id], |row| row.get(0))?;
+        if count == 0 && !unreferenced.contains(&session_id) {
+            unreferenced.push(session_id);
+        }
+    }
+    Ok(unreferenced)
+}
+
+/// Column list matching `transcription_from_row`
+const TRANSCRIPTION_COLUMNS: &str = "id, text, timestamp, duration_ms, model, cost_cents, mode, app_name, window_title, clipping_pct, session_id, cleaned_text, title, language, word_confidence";
+
+fn transcription_from_row(row: &rusqlite::Row) -> Result<TranscriptionEntry> {
+    Ok(TranscriptionEntry {
+        id: Some(row.get(0)?),
+        text: row.get(1)?,
+        timestamp: row.get(2)?,
+        duration_ms: row.get(3)?,
+        model: row.get(4)?,
+        cost_cents: row.get(5)?,
+        mode: row.get(6)?,
+        app_name: row.get(7)?,
+        window_title: row.get(8)?,
+        clipping_pct: row.get(9)?,
+        session_id: row.get(10)?,
+        cleaned_text: row.get(11)?,
+        title: row.get(12)?,
+        language: row.get(13)?,
+        word_confidence: row.get::<_, Option<String>>(14)?.and_then(|json| serde_json::from_str(&json).ok()),
+    })
+}
+
+fn revision_from_row(row: &rusqlite::Row) -> Result<TranscriptionRevision> {
+    Ok(TranscriptionRevision {
+        id: row.get(0)?,
+        transcription_id: row.get(1)?,
+        text: row.get(2)?,
+        kind: row.get(3)?,
+        model: row.get(4)?,
+        created_at: row.get(5)?,
+    })
+}
+
+fn app_rule_from_row(row: &rusqlite::Row) -> Result<AppRule> {
+    Ok(AppRule {
+        id: row.get(0)?,
+        app_name: row.get(1)?,
+        model: row.get(2)?,
+        style: row.get(3)?,
+    })
 }
 
 pub struct Database {
@@ -109,94 +280,765 @@ impl Database {
             println!("📦 Database migrated to schema version 1 (added stats columns)");
         }
 
-        if schema_version < 2 {
-            conn.execute(
-                "CREATE TABLE IF NOT EXISTS pending_queue (
-                    id          INTEGER PRIMARY KEY AUTOINCREMENT,
-                    mode        TEXT    NOT NULL,
-                    audio_path  TEXT,
-                    prompt_text TEXT,
-                    model       TEXT    NOT NULL,
-                    created_at  INTEGER NOT NULL,
-                    retry_count INTEGER NOT NULL DEFAULT 0
-                )",
-                [],
-            )?;
-            conn.execute(
-                "INSERT OR REPLACE INTO settings (key, value) VALUES ('schema_version', '2')",
-                [],
-            )?;
-            println!("📦 Database migrated to schema version 2 (added pending_queue)");
-        }
+        if schema_version < 2 {
+            conn.execute(
+                "CREATE TABLE IF NOT EXISTS pending_queue (
+                    id          INTEGER PRIMARY KEY AUTOINCREMENT,
+                    mode        TEXT    NOT NULL,
+                    audio_path  TEXT,
+                    prompt_text TEXT,
+                    model       TEXT    NOT NULL,
+                    created_at  INTEGER NOT NULL,
+                    retry_count INTEGER NOT NULL DEFAULT 0
+                )",
+                [],
+            )?;
+            conn.execute(
+                "INSERT OR REPLACE INTO settings (key, value) VALUES ('schema_version', '2')",
+                [],
+            )?;
+            println!("📦 Database migrated to schema version 2 (added pending_queue)");
+        }
+
+        if schema_version < 3 {
+            conn.execute(
+                "CREATE TABLE IF NOT EXISTS profiles (
+                    name          TEXT    PRIMARY KEY,
+                    settings_json TEXT    NOT NULL,
+                    updated_at    INTEGER NOT NULL
+                )",
+                [],
+            )?;
+            conn.execute(
+                "INSERT OR REPLACE INTO settings (key, value) VALUES ('schema_version', '3')",
+                [],
+            )?;
+            println!("📦 Database migrated to schema version 3 (added profiles)");
+        }
+
+        if schema_version < 4 {
+            conn.execute(
+                "CREATE TABLE IF NOT EXISTS tts_lexicon (
+                    id          INTEGER PRIMARY KEY AUTOINCREMENT,
+                    word        TEXT    NOT NULL UNIQUE COLLATE NOCASE,
+                    replacement TEXT    NOT NULL
+                )",
+                [],
+            )?;
+            conn.execute(
+                "INSERT OR REPLACE INTO settings (key, value) VALUES ('schema_version', '4')",
+                [],
+            )?;
+            println!("📦 Database migrated to schema version 4 (added tts_lexicon)");
+        }
+
+        if schema_version < 5 {
+            conn.execute(
+                "CREATE TABLE IF NOT EXISTS meetings (
+                    id                INTEGER PRIMARY KEY AUTOINCREMENT,
+                    started_at        INTEGER NOT NULL,
+                    ended_at          INTEGER,
+                    transcript        TEXT    NOT NULL DEFAULT '',
+                    rolling_summary   TEXT    NOT NULL DEFAULT '',
+                    final_summary     TEXT,
+                    action_items_json TEXT    NOT NULL DEFAULT '[]'
+                )",
+                [],
+            )?;
+            conn.execute(
+                "INSERT OR REPLACE INTO settings (key, value) VALUES ('schema_version', '5')",
+                [],
+            )?;
+            println!("📦 Database migrated to schema version 5 (added meetings)");
+        }
+
+        if schema_version < 6 {
+            conn.execute(
+                "CREATE TABLE IF NOT EXISTS action_items (
+                    id               INTEGER PRIMARY KEY AUTOINCREMENT,
+                    transcription_id INTEGER NOT NULL,
+                    text             TEXT    NOT NULL,
+                    created_at       INTEGER NOT NULL
+                )",
+                [],
+            )?;
+            conn.execute(
+                "CREATE INDEX IF NOT EXISTS idx_action_items_transcription ON action_items(transcription_id)",
+                [],
+            )?;
+            conn.execute(
+                "INSERT OR REPLACE INTO settings (key, value) VALUES ('schema_version', '6')",
+                [],
+            )?;
+            println!("📦 Database migrated to schema version 6 (added action_items)");
+        }
+
+        if schema_version < 7 {
+            conn.execute(
+                "CREATE TABLE IF NOT EXISTS sync_tombstones (
+                    key        TEXT    PRIMARY KEY,
+                    deleted_at INTEGER NOT NULL
+                )",
+                [],
+            )?;
+            conn.execute(
+                "INSERT OR REPLACE INTO settings (key, value) VALUES ('schema_version', '7')",
+                [],
+            )?;
+            println!("📦 Database migrated to schema version 7 (added sync_tombstones)");
+        }
+
+        if schema_version < 8 {
+            conn.execute("ALTER TABLE transcriptions ADD COLUMN app_name TEXT", [])?;
+            conn.execute("ALTER TABLE transcriptions ADD COLUMN window_title TEXT", [])?;
+            conn.execute(
+                "CREATE INDEX IF NOT EXISTS idx_transcriptions_app ON transcriptions(app_name)",
+                [],
+            )?;
+            conn.execute(
+                "INSERT OR REPLACE INTO settings (key, value) VALUES ('schema_version', '8')",
+                [],
+            )?;
+            println!("📦 Database migrated to schema version 8 (added app_name/window_title)");
+        }
+
+        if schema_version < 9 {
+            conn.execute("ALTER TABLE transcriptions ADD COLUMN clipping_pct REAL", [])?;
+            conn.execute(
+                "INSERT OR REPLACE INTO settings (key, value) VALUES ('schema_version', '9')",
+                [],
+            )?;
+            println!("📦 Database migrated to schema version 9 (added clipping_pct)");
+        }
+
+        if schema_version < 10 {
+            conn.execute(
+                "CREATE TABLE IF NOT EXISTS session_diagnostics (
+                    transcription_id   INTEGER PRIMARY KEY,
+                    device             TEXT    NOT NULL,
+                    native_sample_rate INTEGER NOT NULL,
+                    channels           INTEGER NOT NULL,
+                    resampler          TEXT    NOT NULL,
+                    exclusive          INTEGER NOT NULL,
+                    average_level      REAL    NOT NULL,
+                    dropped_frames     INTEGER NOT NULL
+                )",
+                [],
+            )?;
+            conn.execute(
+                "INSERT OR REPLACE INTO settings (key, value) VALUES ('schema_version', '10')",
+                [],
+            )?;
+            println!("📦 Database migrated to schema version 10 (added session_diagnostics)");
+        }
+
+        if schema_version < 11 {
+            conn.execute("ALTER TABLE transcriptions ADD COLUMN session_id TEXT", [])?;
+            conn.execute("ALTER TABLE pending_queue ADD COLUMN session_id TEXT", [])?;
+            conn.execute(
+                "INSERT OR REPLACE INTO settings (key, value) VALUES ('schema_version', '11')",
+                [],
+            )?;
+            println!("📦 Database migrated to schema version 11 (added session_id)");
+        }
+
+        if schema_version < 12 {
+            conn.execute(
+                "CREATE TABLE IF NOT EXISTS voice_intents (
+                    id     INTEGER PRIMARY KEY AUTOINCREMENT,
+                    phrase TEXT    NOT NULL UNIQUE COLLATE NOCASE,
+                    action TEXT    NOT NULL,
+                    value  TEXT    NOT NULL
+                )",
+                [],
+            )?;
+            conn.execute(
+                "INSERT OR REPLACE INTO settings (key, value) VALUES ('schema_version', '12')",
+                [],
+            )?;
+            println!("📦 Database migrated to schema version 12 (added voice_intents)");
+        }
+
+        if schema_version < 13 {
+            conn.execute(
+                "CREATE TABLE IF NOT EXISTS app_rules (
+                    id       INTEGER PRIMARY KEY AUTOINCREMENT,
+                    app_name TEXT    NOT NULL UNIQUE COLLATE NOCASE,
+                    model    TEXT    NOT NULL,
+                    style    TEXT
+                )",
+                [],
+            )?;
+            conn.execute(
+                "INSERT OR REPLACE INTO settings (key, value) VALUES ('schema_version', '13')",
+                [],
+            )?;
+            println!("📦 Database migrated to schema version 13 (added app_rules)");
+        }
+
+        if schema_version < 14 {
+            conn.execute(
+                "CREATE TABLE IF NOT EXISTS transcription_segments (
+                    id               INTEGER PRIMARY KEY AUTOINCREMENT,
+                    transcription_id INTEGER NOT NULL,
+                    position         INTEGER NOT NULL,
+                    text             TEXT    NOT NULL,
+                    timestamp        INTEGER NOT NULL
+                )",
+                [],
+            )?;
+            conn.execute(
+                "CREATE INDEX IF NOT EXISTS idx_segments_transcription ON transcription_segments(transcription_id)",
+                [],
+            )?;
+            conn.execute(
+                "INSERT OR REPLACE INTO settings (key, value) VALUES ('schema_version', '14')",
+                [],
+            )?;
+            println!("📦 Database migrated to schema version 14 (added transcription_segments)");
+        }
+
+        if schema_version < 15 {
+            conn.execute("ALTER TABLE transcriptions ADD COLUMN cleaned_text TEXT", [])?;
+            conn.execute(
+                "INSERT OR REPLACE INTO settings (key, value) VALUES ('schema_version', '15')",
+                [],
+            )?;
+            println!("📦 Database migrated to schema version 15 (added cleaned_text)");
+        }
+
+        if schema_version < 16 {
+            conn.execute("ALTER TABLE transcriptions ADD COLUMN title TEXT", [])?;
+            conn.execute(
+                "INSERT OR REPLACE INTO settings (key, value) VALUES ('schema_version', '16')",
+                [],
+            )?;
+            println!("📦 Database migrated to schema version 16 (added title)");
+        }
+
+        if schema_version < 17 {
+            conn.execute("ALTER TABLE transcriptions ADD COLUMN language TEXT", [])?;
+            conn.execute(
+                "INSERT OR REPLACE INTO settings (key, value) VALUES ('schema_version', '17')",
+                [],
+            )?;
+            println!("📦 Database migrated to schema version 17 (added language)");
+        }
+
+        if schema_version < 18 {
+            conn.execute(
+                "CREATE TABLE IF NOT EXISTS session_transcript_chunks (
+                    id         INTEGER PRIMARY KEY AUTOINCREMENT,
+                    session_id TEXT    NOT NULL,
+                    text       TEXT    NOT NULL
+                )",
+                [],
+            )?;
+            conn.execute(
+                "INSERT OR REPLACE INTO settings (key, value) VALUES ('schema_version', '18')",
+                [],
+            )?;
+            println!("📦 Database migrated to schema version 18 (added session_transcript_chunks)");
+        }
+
+        if schema_version < 19 {
+            conn.execute(
+                "CREATE TABLE IF NOT EXISTS transcription_revisions (
+                    id               INTEGER PRIMARY KEY AUTOINCREMENT,
+                    transcription_id INTEGER NOT NULL,
+                    text             TEXT    NOT NULL,
+                    kind             TEXT    NOT NULL,
+                    model            TEXT,
+                    created_at       INTEGER NOT NULL
+                )",
+                [],
+            )?;
+            conn.execute(
+                "CREATE INDEX IF NOT EXISTS idx_revisions_transcription ON transcription_revisions(transcription_id)",
+                [],
+            )?;
+            conn.execute(
+                "INSERT OR REPLACE INTO settings (key, value) VALUES ('schema_version', '19')",
+                [],
+            )?;
+            println!("📦 Database migrated to schema version 19 (added transcription_revisions)");
+        }
+
+        if schema_version < 20 {
+            conn.execute("ALTER TABLE transcription_segments ADD COLUMN item_id TEXT", [])?;
+            conn.execute(
+                "INSERT OR REPLACE INTO settings (key, value) VALUES ('schema_version', '20')",
+                [],
+            )?;
+            println!("📦 Database migrated to schema version 20 (added item_id to transcription_segments)");
+        }
+
+        if schema_version < 21 {
+            conn.execute("ALTER TABLE transcriptions ADD COLUMN word_confidence TEXT", [])?;
+            conn.execute(
+                "INSERT OR REPLACE INTO settings (key, value) VALUES ('schema_version', '21')",
+                [],
+            )?;
+            println!("📦 Database migrated to schema version 21 (added word_confidence)");
+        }
+
+        println!("✅ Database initialized");
+
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    /// Save a new transcription to the database
+    pub fn save_transcription(
+        &self,
+        text: &str,
+        timestamp: i64,
+        duration_ms: Option<i64>,
+        model: Option<&str>,
+        cost_cents: Option<i64>,
+        mode: Option<&str>,
+        session_id: Option<&str>,
+    ) -> Result<i64> {
+        let conn = self.conn.lock().unwrap();
+
+        conn.execute(
+            "INSERT INTO transcriptions (text, timestamp, duration_ms, model, cost_cents, mode, session_id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            rusqlite::params![text, timestamp, duration_ms, model, cost_cents, mode, session_id],
+        )?;
+
+        let id = conn.last_insert_rowid();
+        println!("💾 Saved transcription to DB (id: {}, model: {:?}, cost: {:?}, session: {:?})", id, model, cost_cents, session_id);
+
+        Ok(id)
+    }
+
+    /// Id of the latest entry with exactly `text` saved at or after `since` (dedup on save)
+    pub fn find_recent_duplicate(&self, text: &str, since: i64) -> Result<Option<i64>> {
+        let conn = self.conn.lock().unwrap();
+        let result = conn.query_row(
+            "SELECT id FROM transcriptions WHERE text = ?1 AND timestamp >= ?2 ORDER BY timestamp DESC LIMIT 1",
+            rusqlite::params![text, since],
+            |row| row.get(0),
+        );
+        match result {
+            Ok(id) => Ok(Some(id)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Add the cost of a request whose result was merged into entry `id`
+    pub fn add_transcription_cost(&self, id: i64, cost_cents: i64) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE transcriptions SET cost_cents = COALESCE(cost_cents, 0) + ?2 WHERE id = ?1",
+            rusqlite::params![id, cost_cents],
+        )?;
+        Ok(())
+    }
+
+    /// Collapse entries with exactly the same text into the oldest one, which gets the summed cost.
+    /// The removed copies get sync tombstones like deleted entries. Returns how many were removed,
+    /// and the sessions whose archived audio no entry uses anymore.
+    pub fn merge_duplicate_transcriptions(&self) -> Result<(usize, Vec<String>)> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let deleted_at = chrono::Utc::now().timestamp_millis();
+        let groups = tx
+            .prepare("SELECT MIN(id), text, SUM(cost_cents) FROM transcriptions GROUP BY text HAVING COUNT(*) > 1")?
+            .query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get::<_, Option<i64>>(2)?)))?
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut removed = 0;
+        let mut sessions = Vec::new();
+        for (keep_id, text, total_cost) in groups {
+            let copies = tx
+                .prepare("SELECT id, timestamp, session_id FROM transcriptions WHERE text = ?1 AND id != ?2")?
+                .query_map(rusqlite::params![text, keep_id], |row| {
+                    Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?, row.get::<_, Option<String>>(2)?))
+                })?
+                .collect::<Result<Vec<_>>>()?;
+            for (id, timestamp, session_id) in copies {
+                sessions.extend(session_id);
+                tx.execute(
+                    "INSERT OR REPLACE INTO sync_tombstones (key, deleted_at) VALUES (?1, ?2)",
+                    rusqlite::params![sync_key(timestamp, &text), deleted_at],
+                )?;
+                tx.execute("DELETE FROM transcriptions WHERE id = ?1", [id])?;
+                tx.execute("DELETE FROM action_items WHERE transcription_id = ?1", [id])?;
+                tx.execute("DELETE FROM session_diagnostics WHERE transcription_id = ?1", [id])?;
+                tx.execute("DELETE FROM transcription_segments WHERE transcription_id = ?1", [id])?;
+                tx.execute("DELETE FROM transcription_revisions WHERE transcription_id = ?1", [id])?;
+                removed += 1;
+            }
+            tx.execute(
+                "UPDATE transcriptions SET cost_cents = ?2 WHERE id = ?1",
+                rusqlite::params![keep_id, total_cost],
+            )?;
+        }
+        let orphaned = unreferenced_sessions(&tx, sessions)?;
+        tx.commit()?;
+        println!("🧹 Merged {} duplicate transcription(s)", removed);
+        Ok((removed, orphaned))
+    }
+
+    /// Load all transcriptions ordered by timestamp (most recent first), optionally only
+    /// those pasted into `app_name` (case-insensitive) and/or spoken in `language`
+    pub fn load_transcriptions(&self, app_name: Option<&str>, language: Option<&str>) -> Result<Vec<TranscriptionEntry>> {
+        let conn = self.conn.lock().unwrap();
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM transcriptions
+             WHERE (?1 IS NULL OR app_name = ?1 COLLATE NOCASE)
+               AND (?2 IS NULL OR language = ?2)
+             ORDER BY timestamp DESC",
+            TRANSCRIPTION_COLUMNS
+        ))?;
+
+        let entries = stmt
+            .query_map([app_name, language], transcription_from_row)?
+            .collect::<Result<Vec<_>>>()?;
+
+        println!("📚 Loaded {} transcriptions from DB", entries.len());
+
+        Ok(entries)
+    }
+
+    /// Load the most recent `limit` transcriptions (most recent first)
+    pub fn load_recent_transcriptions(&self, limit: usize) -> Result<Vec<TranscriptionEntry>> {
+        let conn = self.conn.lock().unwrap();
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM transcriptions ORDER BY timestamp DESC LIMIT ?1",
+            TRANSCRIPTION_COLUMNS
+        ))?;
+
+        let entries = stmt
+            .query_map([limit as i64], transcription_from_row)?
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(entries)
+    }
+
+    /// Load transcriptions with `from_ts <= timestamp <= to_ts` in chronological order
+    pub fn load_transcriptions_between(&self, from_ts: i64, to_ts: i64) -> Result<Vec<TranscriptionEntry>> {
+        let conn = self.conn.lock().unwrap();
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM transcriptions WHERE timestamp >= ?1 AND timestamp <= ?2 ORDER BY timestamp ASC",
+            TRANSCRIPTION_COLUMNS
+        ))?;
+
+        let entries = stmt
+            .query_map(rusqlite::params![from_ts, to_ts], transcription_from_row)?
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(entries)
+    }
+
+    /// Record the app a history entry was pasted into
+    pub fn set_transcription_window(&self, id: i64, app_name: &str, window_title: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+
+        conn.execute(
+            "UPDATE transcriptions SET app_name = ?1, window_title = ?2 WHERE id = ?3",
+            rusqlite::params![app_name, window_title, id],
+        )?;
+
+        Ok(())
+    }
+
+    /// Store the recording's clipping percentage with its history entry
+    pub fn set_transcription_language(&self, id: i64, language: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE transcriptions SET language = ?1 WHERE id = ?2",
+            rusqlite::params![language, id],
+        )?;
+        Ok(())
+    }
+
+    /// Store Whisper's per-word confidence with the history entry of its transcript
+    pub fn set_transcription_confidence(&self, id: i64, words: &[WordConfidence]) -> Result<()> {
+        let json = serde_json::to_string(words).unwrap_or_default();
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE transcriptions SET word_confidence = ?1 WHERE id = ?2",
+            rusqlite::params![json, id],
+        )?;
+        Ok(())
+    }
+
+    pub fn set_transcription_title(&self, id: i64, title: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE transcriptions SET title = ?1 WHERE id = ?2",
+            rusqlite::params![title, id],
+        )?;
+        Ok(())
+    }
+
+    /// Replace an entry's text (edits and restored revisions; the versions are kept as revisions)
+    pub fn set_transcription_text(&self, id: i64, text: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE transcriptions SET text = ?1 WHERE id = ?2",
+            rusqlite::params![text, id],
+        )?;
+        Ok(())
+    }
+
+    pub fn set_transcription_cleaned_text(&self, id: i64, cleaned_text: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE transcriptions SET cleaned_text = ?1 WHERE id = ?2",
+            rusqlite::params![cleaned_text, id],
+        )?;
+        Ok(())
+    }
+
+    pub fn set_transcription_clipping(&self, id: i64, clipping_pct: f64) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+
+        conn.execute(
+            "UPDATE transcriptions SET clipping_pct = ?1 WHERE id = ?2",
+            rusqlite::params![clipping_pct, id],
+        )?;
+
+        Ok(())
+    }
+
+    pub fn save_session_diagnostics(&self, transcription_id: i64, diagnostics: &SessionDiagnostics) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+
+        conn.execute(
+            "INSERT OR REPLACE INTO session_diagnostics
+             (transcription_id, device, native_sample_rate, channels, resampler, exclusive, average_level, dropped_frames)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            rusqlite::params![
+                transcription_id,
+                diagnostics.device,
+                diagnostics.native_sample_rate,
+                diagnostics.channels,
+                diagnostics.resampler,
+                diagnostics.exclusive,
+                diagnostics.average_level,
+                diagnostics.dropped_frames as i64,
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    /// Replace the turns stored for transcription `transcription_id`
+    pub fn save_transcription_segments(&self, transcription_id: i64, segments: &[TranscriptSegment]) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute("DELETE FROM transcription_segments WHERE transcription_id = ?1", [transcription_id])?;
+        for (position, segment) in segments.iter().enumerate() {
+            tx.execute(
+                "INSERT INTO transcription_segments (transcription_id, position, text, timestamp, item_id) VALUES (?1, ?2, ?3, ?4, ?5)",
+                rusqlite::params![transcription_id, position as i64, segment.text, segment.timestamp, segment.item_id],
+            )?;
+        }
+        tx.commit()
+    }
+
+    /// Keep another version of transcription `transcription_id` next to it (see TranscriptionRevision)
+    pub fn add_transcription_revision(&self, transcription_id: i64, text: &str, kind: &str, model: Option<&str>, created_at: i64) -> Result<i64> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO transcription_revisions (transcription_id, text, kind, model, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+            rusqlite::params![transcription_id, text, kind, model, created_at],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// Versions of an entry, oldest first
+    pub fn load_transcription_revisions(&self, transcription_id: i64) -> Result<Vec<TranscriptionRevision>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, transcription_id, text, kind, model, created_at FROM transcription_revisions
+             WHERE transcription_id = ?1 ORDER BY created_at, id",
+        )?;
+        let revisions = stmt
+            .query_map([transcription_id], revision_from_row)?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(revisions)
+    }
+
+    pub fn load_transcription_revision(&self, id: i64) -> Result<Option<TranscriptionRevision>> {
+        let conn = self.conn.lock().unwrap();
+        let result = conn.query_row(
+            "SELECT id, transcription_id, text, kind, model, created_at FROM transcription_revisions WHERE id = ?1",
+            [id],
+            revision_from_row,
+        );
+        match result {
+            Ok(revision) => Ok(Some(revision)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Move part of a live session's transcript out of memory (long dictations)
+    pub fn append_session_transcript_chunk(&self, session_id: &str, text: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO session_transcript_chunks (session_id, text) VALUES (?1, ?2)",
+            rusqlite::params![session_id, text],
+        )?;
+        Ok(())
+    }
+
+    /// The transcript flushed for `session_id` so far, in order; the chunks are deleted
+    pub fn take_session_transcript_chunks(&self, session_id: &str) -> Result<String> {
+        let conn = self.conn.lock().unwrap();
+        let text = {
+            let mut stmt = conn.prepare("SELECT text FROM session_transcript_chunks WHERE session_id = ?1 ORDER BY id")?;
+            let chunks = stmt
+                .query_map([session_id], |row| row.get::<_, String>(0))?
+                .collect::<Result<Vec<_>>>()?;
+            chunks.concat()
+        };
+        conn.execute("DELETE FROM session_transcript_chunks WHERE session_id = ?1", [session_id])?;
+        Ok(text)
+    }
+
+    /// Drop chunks left by sessions that never finished (crash, connection drop)
+    pub fn clear_session_transcript_chunks(&self) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM session_transcript_chunks", [])?;
+        Ok(())
+    }
+
+    /// Turns of a live session entry in spoken order (empty for batch recordings)
+    pub fn load_transcription_segments(&self, transcription_id: i64) -> Result<Vec<TranscriptSegment>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT text, timestamp, item_id, position FROM transcription_segments WHERE transcription_id = ?1 ORDER BY position",
+        )?;
+        // Stored turns are all completed; their position stands in for the sequence number
+        let segments = stmt
+            .query_map([transcription_id], |row| {
+                Ok(TranscriptSegment {
+                    item_id: row.get::<_, Option<String>>(2)?.unwrap_or_default(),
+                    seq: row.get::<_, i64>(3)? as u64,
+                    text: row.get(0)?,
+                    is_final: true,
+                    timestamp: row.get(1)?,
+                })
+            })?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(segments)
+    }
+
+    pub fn load_session_diagnostics(&self, transcription_id: i64) -> Result<Option<SessionDiagnostics>> {
+        let conn = self.conn.lock().unwrap();
 
-        println!("✅ Database initialized");
+        let result = conn.query_row(
+            "SELECT device, native_sample_rate, channels, resampler, exclusive, average_level, dropped_frames
+             FROM session_diagnostics WHERE transcription_id = ?1",
+            [transcription_id],
+            |row| {
+                Ok(SessionDiagnostics {
+                    device: row.get(0)?,
+                    native_sample_rate: row.get(1)?,
+                    channels: row.get(2)?,
+                    resampler: row.get(3)?,
+                    exclusive: row.get(4)?,
+                    average_level: row.get(5)?,
+                    dropped_frames: row.get::<_, i64>(6)? as u64,
+                })
+            },
+        );
 
-        Ok(Self {
-            conn: Arc::new(Mutex::new(conn)),
-        })
+        match result {
+            Ok(diagnostics) => Ok(Some(diagnostics)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
     }
 
-    /// Save a new transcription to the database
-    pub fn save_transcription(
-        &self,
-        text: &str,
-        timestamp: i64,
-        duration_ms: Option<i64>,
-        model: Option<&str>,
-        cost_cents: Option<i64>,
-        mode: Option<&str>,
-    ) -> Result<i64> {
+    /// Distinct apps that transcriptions were pasted into, most used first
+    pub fn load_history_apps(&self) -> Result<Vec<String>> {
         let conn = self.conn.lock().unwrap();
 
-        conn.execute(
-            "INSERT INTO transcriptions (text, timestamp, duration_ms, model, cost_cents, mode)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-            rusqlite::params![text, timestamp, duration_ms, model, cost_cents, mode],
+        let mut stmt = conn.prepare(
+            "SELECT app_name FROM transcriptions WHERE app_name IS NOT NULL
+             GROUP BY app_name ORDER BY COUNT(*) DESC",
         )?;
 
-        let id = conn.last_insert_rowid();
-        println!("💾 Saved transcription to DB (id: {}, model: {:?}, cost: {:?})", id, model, cost_cents);
+        let apps = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<Vec<String>>>()?;
 
-        Ok(id)
+        Ok(apps)
     }
 
-    /// Load all transcriptions ordered by timestamp (most recent first)
-    pub fn load_transcriptions(&self) -> Result<Vec<TranscriptionEntry>> {
+    /// Distinct languages of the history, most used first
+    pub fn load_history_languages(&self) -> Result<Vec<String>> {
         let conn = self.conn.lock().unwrap();
 
         let mut stmt = conn.prepare(
-            "SELECT id, text, timestamp, duration_ms, model, cost_cents, mode
-             FROM transcriptions ORDER BY timestamp DESC",
+            "SELECT language FROM transcriptions WHERE language IS NOT NULL
+             GROUP BY language ORDER BY COUNT(*) DESC",
         )?;
 
-        let entries = stmt
-            .query_map([], |row| {
-                Ok(TranscriptionEntry {
-                    id: Some(row.get(0)?),
-                    text: row.get(1)?,
-                    timestamp: row.get(2)?,
-                    duration_ms: row.get(3)?,
-                    model: row.get(4)?,
-                    cost_cents: row.get(5)?,
-                    mode: row.get(6)?,
-                })
-            })?
-            .collect::<Result<Vec<_>>>()?;
+        let languages = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<Vec<String>>>()?;
 
-        println!("📚 Loaded {} transcriptions from DB", entries.len());
+        Ok(languages)
+    }
 
-        Ok(entries)
+    /// Load a single transcription by ID
+    pub fn load_transcription(&self, id: i64) -> Result<Option<TranscriptionEntry>> {
+        let conn = self.conn.lock().unwrap();
+
+        let result = conn.query_row(
+            &format!("SELECT {} FROM transcriptions WHERE id = ?1", TRANSCRIPTION_COLUMNS),
+            [id],
+            transcription_from_row,
+        );
+
+        match result {
+            Ok(entry) => Ok(Some(entry)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
     }
 
-    /// Delete a transcription by ID
-    pub fn delete_transcription(&self, id: i64) -> Result<()> {
+    /// Delete a transcription by ID. Returns its session when no other entry uses the session's
+    /// archived audio.
+    pub fn delete_transcription(&self, id: i64) -> Result<Option<String>> {
         let conn = self.conn.lock().unwrap();
 
+        // Remember the deletion so sync removes it on other devices too
+        let deleted: Option<(i64, String, Option<String>)> = conn
+            .query_row("SELECT timestamp, text, session_id FROM transcriptions WHERE id = ?1", [id], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })
+            .ok();
+        if let Some((timestamp, text, _)) = &deleted {
+            conn.execute(
+                "INSERT OR REPLACE INTO sync_tombstones (key, deleted_at) VALUES (?1, ?2)",
+                rusqlite::params![sync_key(*timestamp, text), chrono::Utc::now().timestamp_millis()],
+            )?;
+        }
+
         conn.execute("DELETE FROM transcriptions WHERE id = ?1", [id])?;
+        conn.execute("DELETE FROM action_items WHERE transcription_id = ?1", [id])?;
+        conn.execute("DELETE FROM session_diagnostics WHERE transcription_id = ?1", [id])?;
+        conn.execute("DELETE FROM transcription_segments WHERE transcription_id = ?1", [id])?;
+        conn.execute("DELETE FROM transcription_revisions WHERE transcription_id = ?1", [id])?;
 
         println!("🗑️ Deleted transcription (id: {})", id);
 
-        Ok(())
+        let session = deleted.and_then(|(_, _, session_id)| session_id);
+        Ok(unreferenced_sessions(&conn, session.into_iter().collect())?.pop())
     }
 
     /// Get total count of transcriptions
@@ -241,6 +1083,337 @@ impl Database {
         }
     }
 
+    /// Delete a setting (falls back to the default on next load)
+    pub fn delete_setting(&self, key: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM settings WHERE key = ?1", [key])?;
+        Ok(())
+    }
+
+    /// Load all settings whose key is in `keys` or starts with one of `prefixes`
+    pub fn snapshot_settings(&self, keys: &[&str], prefixes: &[&str]) -> Result<HashMap<String, String>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT key, value FROM settings")?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(rows
+            .into_iter()
+            .filter(|(k, _)| keys.contains(&k.as_str()) || prefixes.iter().any(|p| k.starts_with(p)))
+            .collect())
+    }
+
+    /// Write `values` and delete `cleared` keys in a single transaction
+    pub fn replace_settings(&self, values: &HashMap<String, String>, cleared: &[String]) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        for key in cleared {
+            tx.execute("DELETE FROM settings WHERE key = ?1", [key])?;
+        }
+        for (key, value) in values {
+            tx.execute(
+                "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
+                [key, value],
+            )?;
+        }
+        tx.commit()
+    }
+
+    // --- Settings profiles ---
+
+    /// Create or overwrite a named profile
+    pub fn save_profile(&self, name: &str, settings: &HashMap<String, String>, updated_at: i64) -> Result<()> {
+        let json = serde_json::to_string(settings)
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO profiles (name, settings_json, updated_at) VALUES (?1, ?2, ?3)",
+            rusqlite::params![name, json, updated_at],
+        )?;
+        println!("💾 Saved profile '{}' ({} settings)", name, settings.len());
+        Ok(())
+    }
+
+    pub fn load_profiles(&self) -> Result<Vec<SettingsProfile>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT name, settings_json, updated_at FROM profiles ORDER BY name ASC",
+        )?;
+        let profiles = stmt
+            .query_map([], |row| {
+                let json: String = row.get(1)?;
+                Ok(SettingsProfile {
+                    name: row.get(0)?,
+                    settings: serde_json::from_str(&json).unwrap_or_default(),
+                    updated_at: row.get(2)?,
+                })
+            })?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(profiles)
+    }
+
+    pub fn load_profile(&self, name: &str) -> Result<Option<SettingsProfile>> {
+        Ok(self.load_profiles()?.into_iter().find(|p| p.name == name))
+    }
+
+    pub fn delete_profile(&self, name: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM profiles WHERE name = ?1", [name])?;
+        println!("🗑️ Deleted profile '{}'", name);
+        Ok(())
+    }
+
+    // --- TTS lexicon ---
+
+    pub fn load_lexicon(&self) -> Result<Vec<LexiconEntry>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT id, word, replacement FROM tts_lexicon ORDER BY word ASC")?;
+        let entries = stmt
+            .query_map([], |row| {
+                Ok(LexiconEntry {
+                    id: row.get(0)?,
+                    word: row.get(1)?,
+                    replacement: row.get(2)?,
+                })
+            })?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(entries)
+    }
+
+    /// Add a word or update its replacement (words are case-insensitive)
+    pub fn save_lexicon_entry(&self, word: &str, replacement: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO tts_lexicon (word, replacement) VALUES (?1, ?2)
+             ON CONFLICT(word) DO UPDATE SET replacement = excluded.replacement",
+            rusqlite::params![word, replacement],
+        )?;
+        Ok(())
+    }
+
+    pub fn delete_lexicon_entry(&self, id: i64) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM tts_lexicon WHERE id = ?1", [id])?;
+        Ok(())
+    }
+
+    // --- Voice command intents ---
+
+    pub fn load_voice_intents(&self) -> Result<Vec<VoiceIntent>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT id, phrase, action, value FROM voice_intents ORDER BY phrase ASC")?;
+        let intents = stmt
+            .query_map([], |row| {
+                Ok(VoiceIntent {
+                    id: row.get(0)?,
+                    phrase: row.get(1)?,
+                    action: row.get(2)?,
+                    value: row.get(3)?,
+                })
+            })?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(intents)
+    }
+
+    /// Add an intent or update the one with the same phrase (phrases are case-insensitive)
+    pub fn save_voice_intent(&self, phrase: &str, action: &str, value: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO voice_intents (phrase, action, value) VALUES (?1, ?2, ?3)
+             ON CONFLICT(phrase) DO UPDATE SET action = excluded.action, value = excluded.value",
+            rusqlite::params![phrase, action, value],
+        )?;
+        Ok(())
+    }
+
+    pub fn delete_voice_intent(&self, id: i64) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM voice_intents WHERE id = ?1", [id])?;
+        Ok(())
+    }
+
+    // --- Per-app rules ---
+
+    pub fn load_app_rules(&self) -> Result<Vec<AppRule>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT id, app_name, model, style FROM app_rules ORDER BY app_name ASC")?;
+        let rules = stmt.query_map([], app_rule_from_row)?.collect::<Result<Vec<_>>>()?;
+        Ok(rules)
+    }
+
+    pub fn find_app_rule(&self, app_name: &str) -> Result<Option<AppRule>> {
+        let conn = self.conn.lock().unwrap();
+        let result = conn.query_row(
+            "SELECT id, app_name, model, style FROM app_rules WHERE app_name = ?1",
+            [app_name],
+            app_rule_from_row,
+        );
+        match result {
+            Ok(rule) => Ok(Some(rule)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Add a rule or replace the one for the same app (app names are case-insensitive)
+    pub fn save_app_rule(&self, app_name: &str, model: &str, style: Option<&str>) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO app_rules (app_name, model, style) VALUES (?1, ?2, ?3)
+             ON CONFLICT(app_name) DO UPDATE SET model = excluded.model, style = excluded.style",
+            rusqlite::params![app_name, model, style],
+        )?;
+        Ok(())
+    }
+
+    pub fn delete_app_rule(&self, id: i64) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM app_rules WHERE id = ?1", [id])?;
+        Ok(())
+    }
+
+    // --- Sync tombstones ---
+
+    /// Deleted transcriptions: sync key -> deletion time
+    pub fn load_tombstones(&self) -> Result<HashMap<String, i64>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT key, deleted_at FROM sync_tombstones")?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)))?
+            .collect::<Result<HashMap<_, _>>>()?;
+        Ok(rows)
+    }
+
+    /// Replace all tombstones (after merging with the remote copy)
+    pub fn replace_tombstones(&self, tombstones: &HashMap<String, i64>) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute("DELETE FROM sync_tombstones", [])?;
+        for (key, deleted_at) in tombstones {
+            tx.execute(
+                "INSERT INTO sync_tombstones (key, deleted_at) VALUES (?1, ?2)",
+                rusqlite::params![key, deleted_at],
+            )?;
+        }
+        tx.commit()
+    }
+
+    // --- Action items ---
+
+    /// Replace the action items of a transcription (re-extracting overwrites the previous result)
+    pub fn save_action_items(&self, transcription_id: i64, items: &[String], created_at: i64) -> Result<Vec<ActionItem>> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute("DELETE FROM action_items WHERE transcription_id = ?1", [transcription_id])?;
+        let mut saved = Vec::with_capacity(items.len());
+        for text in items {
+            tx.execute(
+                "INSERT INTO action_items (transcription_id, text, created_at) VALUES (?1, ?2, ?3)",
+                rusqlite::params![transcription_id, text, created_at],
+            )?;
+            saved.push(ActionItem {
+                id: tx.last_insert_rowid(),
+                transcription_id,
+                text: text.clone(),
+                created_at,
+            });
+        }
+        tx.commit()?;
+        println!("💾 Saved {} action items for transcription {}", saved.len(), transcription_id);
+        Ok(saved)
+    }
+
+    pub fn load_action_items(&self, transcription_id: i64) -> Result<Vec<ActionItem>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, transcription_id, text, created_at FROM action_items
+             WHERE transcription_id = ?1 ORDER BY id ASC",
+        )?;
+        let items = stmt
+            .query_map([transcription_id], |row| {
+                Ok(ActionItem {
+                    id: row.get(0)?,
+                    transcription_id: row.get(1)?,
+                    text: row.get(2)?,
+                    created_at: row.get(3)?,
+                })
+            })?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(items)
+    }
+
+    // --- Meetings ---
+
+    pub fn create_meeting(&self, started_at: i64) -> Result<i64> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("INSERT INTO meetings (started_at) VALUES (?1)", [started_at])?;
+        let id = conn.last_insert_rowid();
+        println!("💾 Created meeting (id: {})", id);
+        Ok(id)
+    }
+
+    /// Save the transcript and rolling summary of a meeting in progress
+    pub fn update_meeting_progress(&self, id: i64, transcript: &str, rolling_summary: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE meetings SET transcript = ?1, rolling_summary = ?2 WHERE id = ?3",
+            rusqlite::params![transcript, rolling_summary, id],
+        )?;
+        Ok(())
+    }
+
+    pub fn finish_meeting(
+        &self,
+        id: i64,
+        ended_at: i64,
+        transcript: &str,
+        final_summary: Option<&str>,
+        action_items: &[String],
+    ) -> Result<()> {
+        let json = serde_json::to_string(action_items)
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE meetings SET ended_at = ?1, transcript = ?2, final_summary = ?3, action_items_json = ?4
+             WHERE id = ?5",
+            rusqlite::params![ended_at, transcript, final_summary, json, id],
+        )?;
+        println!("💾 Finished meeting (id: {}, {} action items)", id, action_items.len());
+        Ok(())
+    }
+
+    /// All meetings, most recent first
+    pub fn load_meetings(&self) -> Result<Vec<MeetingEntry>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, started_at, ended_at, transcript, rolling_summary, final_summary, action_items_json
+             FROM meetings ORDER BY started_at DESC",
+        )?;
+        let meetings = stmt
+            .query_map([], |row| {
+                let json: String = row.get(6)?;
+                Ok(MeetingEntry {
+                    id: row.get(0)?,
+                    started_at: row.get(1)?,
+                    ended_at: row.get(2)?,
+                    transcript: row.get(3)?,
+                    rolling_summary: row.get(4)?,
+                    final_summary: row.get(5)?,
+                    action_items: serde_json::from_str(&json).unwrap_or_default(),
+                })
+            })?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(meetings)
+    }
+
+    pub fn delete_meeting(&self, id: i64) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM meetings WHERE id = ?1", [id])?;
+        println!("🗑️ Deleted meeting (id: {})", id);
+        Ok(())
+    }
+
     /// Append a message to conversation history
     pub fn append_conversation(&self, role: &str, content: &str, timestamp: i64) -> Result<()> {
         let conn = self.conn.lock().unwrap();
@@ -276,6 +1449,33 @@ impl Database {
         Ok(messages)
     }
 
+    /// Page of conversation messages, newest first (`offset` skips the newest ones)
+    pub fn load_conversation_messages(&self, limit: usize, offset: usize) -> Result<Vec<ConversationEntry>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, role, content, timestamp FROM conversation_history
+             ORDER BY timestamp DESC, id DESC LIMIT ?1 OFFSET ?2",
+        )?;
+        let messages = stmt
+            .query_map(rusqlite::params![limit as i64, offset as i64], |row| {
+                Ok(ConversationEntry {
+                    id: row.get(0)?,
+                    role: row.get(1)?,
+                    content: row.get(2)?,
+                    timestamp: row.get(3)?,
+                })
+            })?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(messages)
+    }
+
+    pub fn delete_conversation_message(&self, id: i64) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM conversation_history WHERE id = ?1", [id])?;
+        println!("🗑️ Deleted conversation message (id: {})", id);
+        Ok(())
+    }
+
     /// Get the timestamp of the most recent conversation message
     pub fn last_conversation_timestamp(&self) -> Result<Option<i64>> {
         let conn = self.conn.lock().unwrap();
@@ -291,6 +1491,16 @@ impl Database {
         }
     }
 
+    /// Delete the prompt/response pair saved with `timestamp` (prompt at timestamp - 1)
+    pub fn delete_conversation_exchange(&self, timestamp: i64) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "DELETE FROM conversation_history WHERE timestamp IN (?1, ?2)",
+            [timestamp - 1, timestamp],
+        )?;
+        Ok(())
+    }
+
     /// Clear all conversation history
     pub fn clear_conversation_history(&self) -> Result<()> {
         let conn = self.conn.lock().unwrap();
@@ -299,44 +1509,67 @@ impl Database {
         Ok(())
     }
 
-    /// Clear all transcriptions
-    pub fn clear_transcriptions(&self) -> Result<()> {
-        let conn = self.conn.lock().unwrap();
-        conn.execute("DELETE FROM transcriptions", [])?;
+    /// Clear all transcriptions. Returns the sessions they belonged to, whose archived audio can go.
+    pub fn clear_transcriptions(&self) -> Result<Vec<String>> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let deleted_at = chrono::Utc::now().timestamp_millis();
+        let sessions = tx
+            .prepare("SELECT DISTINCT session_id FROM transcriptions WHERE session_id IS NOT NULL")?
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<_>>>()?;
+        {
+            let mut stmt = tx.prepare("SELECT timestamp, text FROM transcriptions")?;
+            let rows = stmt
+                .query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))?
+                .collect::<Result<Vec<_>>>()?;
+            for (timestamp, text) in rows {
+                tx.execute(
+                    "INSERT OR REPLACE INTO sync_tombstones (key, deleted_at) VALUES (?1, ?2)",
+                    rusqlite::params![sync_key(timestamp, &text), deleted_at],
+                )?;
+            }
+        }
+        tx.execute("DELETE FROM transcriptions", [])?;
+        tx.execute("DELETE FROM action_items", [])?;
+        tx.execute("DELETE FROM session_diagnostics", [])?;
+        tx.execute("DELETE FROM transcription_segments", [])?;
+        tx.execute("DELETE FROM transcription_revisions", [])?;
+        tx.commit()?;
         println!("🗑️ All transcriptions cleared");
-        Ok(())
+        Ok(sessions)
     }
 
-    /// Get statistics for a date range
-    pub fn get_stats(&self, from_ts: i64, to_ts: i64) -> Result<StatsData> {
+    /// Get statistics for a date range, optionally only for entries spoken in `language`
+    pub fn get_stats(&self, from_ts: i64, to_ts: i64, language: Option<&str>) -> Result<StatsData> {
         let conn = self.conn.lock().unwrap();
 
         let total_transcriptions: i64 = conn.query_row(
-            "SELECT COUNT(*) FROM transcriptions WHERE timestamp >= ?1 AND timestamp <= ?2",
-            rusqlite::params![from_ts, to_ts],
+            "SELECT COUNT(*) FROM transcriptions WHERE timestamp >= ?1 AND timestamp <= ?2 AND (?3 IS NULL OR language = ?3)",
+            rusqlite::params![from_ts, to_ts, language],
             |row| row.get(0),
         )?;
 
         let total_duration_ms: i64 = conn.query_row(
             "SELECT COALESCE(SUM(duration_ms), 0) FROM transcriptions
-             WHERE timestamp >= ?1 AND timestamp <= ?2",
-            rusqlite::params![from_ts, to_ts],
+             WHERE timestamp >= ?1 AND timestamp <= ?2 AND (?3 IS NULL OR language = ?3)",
+            rusqlite::params![from_ts, to_ts, language],
             |row| row.get(0),
         )?;
 
         let total_cost_cents: i64 = conn.query_row(
             "SELECT COALESCE(SUM(cost_cents), 0) FROM transcriptions
-             WHERE timestamp >= ?1 AND timestamp <= ?2",
-            rusqlite::params![from_ts, to_ts],
+             WHERE timestamp >= ?1 AND timestamp <= ?2 AND (?3 IS NULL OR language = ?3)",
+            rusqlite::params![from_ts, to_ts, language],
             |row| row.get(0),
         )?;
 
         // Word count: load texts and count in Rust
         let mut stmt = conn.prepare(
-            "SELECT text FROM transcriptions WHERE timestamp >= ?1 AND timestamp <= ?2",
+            "SELECT text FROM transcriptions WHERE timestamp >= ?1 AND timestamp <= ?2 AND (?3 IS NULL OR language = ?3)",
         )?;
         let texts: Vec<String> = stmt
-            .query_map(rusqlite::params![from_ts, to_ts], |row| row.get(0))?
+            .query_map(rusqlite::params![from_ts, to_ts, language], |row| row.get(0))?
             .collect::<Result<Vec<_>>>()?;
         let total_words: i64 = texts
             .iter()
@@ -360,22 +1593,23 @@ impl Database {
         prompt_text: Option<&str>,
         model: &str,
         created_at: i64,
+        session_id: Option<&str>,
     ) -> Result<i64> {
         let conn = self.conn.lock().unwrap();
         conn.execute(
-            "INSERT INTO pending_queue (mode, audio_path, prompt_text, model, created_at)
-             VALUES (?1, ?2, ?3, ?4, ?5)",
-            rusqlite::params![mode, audio_path, prompt_text, model, created_at],
+            "INSERT INTO pending_queue (mode, audio_path, prompt_text, model, created_at, session_id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            rusqlite::params![mode, audio_path, prompt_text, model, created_at, session_id],
         )?;
         let id = conn.last_insert_rowid();
-        println!("📋 Queued item id={} mode={} model={}", id, mode, model);
+        println!("📋 Queued item id={} mode={} model={} session={:?}", id, mode, model, session_id);
         Ok(id)
     }
 
     pub fn load_queue(&self) -> Result<Vec<PendingQueueItem>> {
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn.prepare(
-            "SELECT id, mode, audio_path, prompt_text, model, created_at, retry_count
+            "SELECT id, mode, audio_path, prompt_text, model, created_at, retry_count, session_id
              FROM pending_queue ORDER BY created_at ASC",
         )?;
         let items = stmt
@@ -388,6 +1622,7 @@ impl Database {
                     model: row.get(4)?,
                     created_at: row.get(5)?,
                     retry_count: row.get(6)?,
+                    session_id: row.get(7)?,
                 })
             })?
             .collect::<Result<Vec<_>>>()?;
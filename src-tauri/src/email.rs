@@ -0,0 +1,61 @@
+use crate::db::Database;
+use serde::{Deserialize, Serialize};
+
+const DEFAULT_MODEL: &str = "gpt-4o-mini";
+const DEFAULT_TONE: &str = "profissional e cordial";
+
+/// Email mode (Alt+Shift+W): the dictation is only what the email should say; the model
+/// writes it out with greeting, paragraphs and sign-off, and the email is pasted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmailSettings {
+    pub greeting: Option<String>, // e.g. "Olá" or "Prezados"; the model picks one when empty
+    pub sign_off: Option<String>, // Closing and signature, used as written ("Abraços,\nRaphael")
+    pub tone: String,             // Free text: "formal", "descontraído", ...
+    pub model: String,
+}
+
+impl EmailSettings {
+    pub fn load(database: &Database) -> Self {
+        let load = |key: &str| database.load_setting(key).ok().flatten().filter(|v| !v.trim().is_empty());
+        Self {
+            greeting: load("email_greeting"),
+            sign_off: load("email_sign_off"),
+            tone: load("email_tone").unwrap_or_else(|| DEFAULT_TONE.to_string()),
+            model: load("email_model").unwrap_or_else(|| DEFAULT_MODEL.to_string()),
+        }
+    }
+
+    pub fn save(&self, database: &Database) -> rusqlite::Result<()> {
+        database.save_setting("email_tone", self.tone.trim())?;
+        database.save_setting("email_model", self.model.trim())?;
+        for (key, value) in [("email_greeting", &self.greeting), ("email_sign_off", &self.sign_off)] {
+            match value.as_deref().map(str::trim).filter(|v| !v.is_empty()) {
+                Some(v) => database.save_setting(key, v)?,
+                None => database.delete_setting(key)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Prompt asking for the email. `window_title` is where it will be pasted: in a reply
+    /// it usually carries the subject ("Re: Orçamento - Gmail").
+    pub fn prompt(&self, dictation: &str, window_title: Option<&str>) -> String {
+        let mut prompt = format!(
+            "Write an email from the notes dictated below. Tone: {}. Write it in the language of the notes, \
+             with a greeting, short paragraphs and a sign-off. Keep every fact, name, date and number from the notes \
+             and add nothing they don't say. Reply with the email body only: no subject line, no quotes, no comments.",
+            self.tone.trim()
+        );
+        if let Some(greeting) = &self.greeting {
+            prompt.push_str(&format!("\n\nGreeting to use (fill in the recipient's name if the notes give it): {}", greeting.trim()));
+        }
+        if let Some(sign_off) = &self.sign_off {
+            prompt.push_str(&format!("\n\nEnd with exactly this sign-off:\n{}", sign_off.trim()));
+        }
+        if let Some(title) = window_title.map(str::trim).filter(|t| !t.is_empty()) {
+            prompt.push_str(&format!("\n\nTitle of the window the email is being written in, for context: {}", title));
+        }
+        prompt.push_str(&format!("\n\nNotes:\n\"\"\"\n{}\n\"\"\"", dictation.trim()));
+        prompt
+    }
+}
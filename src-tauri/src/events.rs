@@ -0,0 +1,100 @@
+use crate::db::MeetingEntry;
+use crate::rate_limit::RateLimitEvent;
+use crate::realtime::TranscriptSegment;
+use crate::session::SessionId;
+use crate::updates::UpdateInfo;
+use serde::Serialize;
+use tauri::{Emitter, Runtime};
+
+/// Every event the backend sends to the webviews. The variant name (kebab-case) is the event
+/// name the frontend listens to and the variant's data is the payload (null for unit variants).
+/// Events that belong to a recording/prompt session carry its `session_id`.
+/// Keep src/events.ts in sync when adding or changing a variant.
+#[derive(Clone, Serialize)]
+#[serde(tag = "type", content = "payload", rename_all = "kebab-case")]
+pub enum AppEvent {
+    // Recording lifecycle
+    ToggleRecording,
+    WidgetStopRecording,
+    RecordingCancelled,
+    RecordingOffline { session_id: SessionId, message: String },
+    RecordingError { session_id: SessionId, message: String },
+    NoSpeechDetected { session_id: SessionId },
+    TranscriptionDelta { session_id: SessionId, delta: String, segment: TranscriptSegment },
+    /// Every second while recording: live timer and time-limit countdown of the widget
+    RecordingElapsed {
+        session_id: SessionId,
+        elapsed_secs: u64,
+        mode: String, // "whisper" or "realtime"
+        warning_at_secs: u64, // The time-limit warning shows from here on
+        limit_secs: u64,      // The recording is stopped here
+    },
+    MicClipping(f32),
+    /// The mic of the live session (or of the meeting, with no session_id) was muted or
+    /// unmuted (Alt+Shift+K); the recording stays open
+    MicMuteToggled { session_id: Option<SessionId>, muted: bool },
+    BluetoothMicWarning {
+        device: String,
+        /// Non-Bluetooth mic recording switched to, when fallback is enabled
+        replacement: Option<String>,
+    },
+    ModelSelected(String),
+
+    // Results
+    /// session_id is None when history changed outside a session (delete, clear, import)
+    HistoryUpdated { session_id: Option<SessionId> },
+    /// Prompt/response messages were added or deleted (session_id as for HistoryUpdated)
+    ConversationUpdated { session_id: Option<SessionId> },
+    ReviewText(String),
+    PasteFailed { session_id: Option<SessionId>, text: String },
+    /// abort_current_request dropped the session's in-flight API call
+    RequestCancelled { session_id: SessionId },
+    /// "Paste when I click" is holding this result until the next click
+    ReadyToPaste(String),
+    /// A paste was refused because the focused field is a password box (the text is on the clipboard)
+    PasswordFieldFocused,
+    ResponseReady { session_id: Option<SessionId> },
+    ScreenshotAttached(String), // base64 PNG
+
+    // Offline queue and network
+    QueueUpdated(i64),
+    QueueFull,
+    QueueItemCompleted(i64),
+    NetworkOnline,
+    NetworkOffline,
+    RateLimited(RateLimitEvent),
+    UpdateAvailable(UpdateInfo),
+
+    // Settings toggled outside the main window (hotkeys, tray)
+    TtsToggled(bool),
+    TtsSpeedChanged(f32),
+    TtsToastShow(bool),
+    IncognitoToggled(bool),
+    AssistantModeChanged(bool),
+    ProfileSwitched(String),
+    SpendAlert {
+        spent_cents: i64, // hundredths of a cent, same unit as cost_cents
+        threshold_cents: i64,
+    },
+
+    // Meeting mode
+    MeetingStarted(i64),
+    MeetingTranscript(String),
+    MeetingSummary(String),
+    MeetingFinished(MeetingEntry),
+    KeywordDetected { keyword: String, context: String },
+}
+
+/// Emit `event` to all webviews (AppHandle) or a single one (WebviewWindow)
+pub fn emit_app_event<R: Runtime, E: Emitter<R>>(target: &E, event: AppEvent) -> tauri::Result<()> {
+    let mut fields = match serde_json::to_value(&event)? {
+        serde_json::Value::Object(fields) => fields,
+        _ => unreachable!("adjacently tagged enums serialize to objects"),
+    };
+    let name = match fields.remove("type") {
+        Some(serde_json::Value::String(name)) => name,
+        _ => unreachable!("adjacently tagged enums always carry their tag"),
+    };
+    let payload = fields.remove("payload").unwrap_or(serde_json::Value::Null);
+    target.emit(&name, payload)
+}
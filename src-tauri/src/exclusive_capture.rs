@@ -0,0 +1,217 @@
+use crate::audio::SessionStats;
+use crate::system_audio;
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::Duration;
+use windows::Win32::Foundation::{CloseHandle, HANDLE, WAIT_OBJECT_0};
+use windows::Win32::Media::Audio::*;
+use windows::Win32::System::Com::*;
+use windows::Win32::System::Threading::{CreateEventW, WaitForSingleObject};
+
+/// Formats tried in exclusive mode (no mixer means no format conversion), in order of preference
+const FORMATS: &[(u32, u16)] = &[(48000, 1), (48000, 2), (24000, 1), (44100, 1), (44100, 2), (16000, 1)];
+
+#[derive(Debug, Clone, Copy)]
+pub struct CaptureFormat {
+    pub sample_rate: u32,
+    pub channels: u16,
+}
+
+/// Open `device_name` in WASAPI exclusive mode with the device's minimum period (~3 ms on
+/// most hardware) and feed 16-bit interleaved samples to the handler built by `make_handler`
+/// until `recording` goes false. Fails (so the caller can fall back to shared mode) when
+/// the device is in use, exclusive mode is disabled for it, or it supports none of FORMATS.
+pub fn start<F, H>(
+    device_name: Option<String>,
+    recording: Arc<Mutex<bool>>,
+    stats: Arc<SessionStats>,
+    make_handler: F,
+) -> Result<CaptureFormat, String>
+where
+    F: FnOnce(CaptureFormat) -> H + Send + 'static,
+    H: FnMut(&[i16]),
+{
+    let (ready_tx, ready_rx) = mpsc::channel();
+
+    // COM objects stay on the thread that created them
+    std::thread::spawn(move || {
+        let session = match unsafe { open(device_name.as_deref()) } {
+            Ok(session) => session,
+            Err(e) => {
+                let _ = ready_tx.send(Err(e));
+                return;
+            }
+        };
+        let handler = make_handler(session.format);
+        stats.set_stream(
+            session.device_name.clone(),
+            session.format.sample_rate,
+            session.format.channels,
+            crate::audio::realtime_resampler(session.format.sample_rate),
+            true,
+        );
+        let _ = ready_tx.send(Ok(session.format));
+
+        if let Err(e) = unsafe { session.run(&recording, &stats, handler) } {
+            eprintln!("❌ Exclusive capture stopped: {}", e);
+        }
+        println!("🎤 Exclusive mode: Microphone released");
+    });
+
+    ready_rx
+        .recv_timeout(Duration::from_secs(2))
+        .map_err(|_| "Exclusive capture did not start".to_string())?
+}
+
+struct Session {
+    client: IAudioClient,
+    capture: IAudioCaptureClient,
+    event: HANDLE,
+    format: CaptureFormat,
+    device_name: String,
+}
+
+fn wave_format(sample_rate: u32, channels: u16) -> WAVEFORMATEX {
+    let block_align = channels * 2;
+    WAVEFORMATEX {
+        wFormatTag: WAVE_FORMAT_PCM as u16,
+        nChannels: channels,
+        nSamplesPerSec: sample_rate,
+        nAvgBytesPerSec: sample_rate * block_align as u32,
+        nBlockAlign: block_align,
+        wBitsPerSample: 16,
+        cbSize: 0,
+    }
+}
+
+unsafe fn open(device_name: Option<&str>) -> Result<Session, String> {
+    let _ = CoInitializeEx(None, COINIT_MULTITHREADED).ok();
+
+    let device = system_audio::capture_device(device_name)?;
+    let client: IAudioClient = device
+        .Activate(CLSCTX_ALL, None)
+        .map_err(|e| format!("Activate IAudioClient failed: {}", e))?;
+
+    let (sample_rate, channels) = FORMATS
+        .iter()
+        .copied()
+        .find(|&(rate, channels)| {
+            client
+                .IsFormatSupported(AUDCLNT_SHAREMODE_EXCLUSIVE, &wave_format(rate, channels), None)
+                .is_ok()
+        })
+        .ok_or("Device supports none of the exclusive-mode formats")?;
+    let format = wave_format(sample_rate, channels);
+
+    let mut min_period = 0i64;
+    client
+        .GetDevicePeriod(None, Some(&mut min_period))
+        .map_err(|e| format!("GetDevicePeriod failed: {}", e))?;
+
+    let client = match client.Initialize(
+        AUDCLNT_SHAREMODE_EXCLUSIVE,
+        AUDCLNT_STREAMFLAGS_EVENTCALLBACK,
+        min_period,
+        min_period,
+        &format,
+        None,
+    ) {
+        Ok(()) => client,
+        // Some drivers want the period rounded to their buffer alignment, which takes a fresh client
+        Err(e) if e.code() == AUDCLNT_E_BUFFER_SIZE_NOT_ALIGNED => {
+            let frames = client.GetBufferSize().map_err(|e| format!("GetBufferSize failed: {}", e))?;
+            let period = (10_000_000.0 * frames as f64 / sample_rate as f64 + 0.5) as i64;
+            let client: IAudioClient = device
+                .Activate(CLSCTX_ALL, None)
+                .map_err(|e| format!("Activate IAudioClient failed: {}", e))?;
+            client
+                .Initialize(AUDCLNT_SHAREMODE_EXCLUSIVE, AUDCLNT_STREAMFLAGS_EVENTCALLBACK, period, period, &format, None)
+                .map_err(|e| format!("Initialize (aligned) failed: {}", e))?;
+            client
+        }
+        Err(e) => return Err(format!("Initialize failed: {}", e)),
+    };
+
+    let event = CreateEventW(None, false, false, None).map_err(|e| format!("CreateEvent failed: {}", e))?;
+    if let Err(e) = client.SetEventHandle(event) {
+        let _ = CloseHandle(event);
+        return Err(format!("SetEventHandle failed: {}", e));
+    }
+    let capture: IAudioCaptureClient = match client.GetService() {
+        Ok(capture) => capture,
+        Err(e) => {
+            let _ = CloseHandle(event);
+            return Err(format!("GetService failed: {}", e));
+        }
+    };
+
+    println!(
+        "🎤 Exclusive mode: {} Hz, {} ch, period {:.1} ms",
+        sample_rate,
+        channels,
+        min_period as f64 / 10_000.0
+    );
+    Ok(Session {
+        client,
+        capture,
+        event,
+        format: CaptureFormat { sample_rate, channels },
+        device_name: system_audio::device_name(&device).unwrap_or_default(),
+    })
+}
+
+impl Session {
+    unsafe fn run(self, recording: &Mutex<bool>, stats: &SessionStats, mut handler: impl FnMut(&[i16])) -> Result<(), String> {
+        let result = self.capture_loop(recording, stats, &mut handler);
+        let _ = self.client.Stop();
+        let _ = CloseHandle(self.event);
+        result
+    }
+
+    unsafe fn capture_loop(&self, recording: &Mutex<bool>, stats: &SessionStats, handler: &mut impl FnMut(&[i16])) -> Result<(), String> {
+        self.client.Start().map_err(|e| format!("Start failed: {}", e))?;
+        let channels = self.format.channels as usize;
+        let mut next_position: Option<u64> = None;
+
+        while *recording.lock().unwrap() {
+            // Timeout so a stop is noticed even if the device stops signalling
+            if WaitForSingleObject(self.event, 100) != WAIT_OBJECT_0 {
+                continue;
+            }
+
+            loop {
+                let packet = self.capture.GetNextPacketSize().map_err(|e| format!("GetNextPacketSize failed: {}", e))?;
+                if packet == 0 {
+                    break;
+                }
+
+                let mut data = std::ptr::null_mut();
+                let mut frames = 0u32;
+                let mut flags = 0u32;
+                let mut position = 0u64;
+                self.capture
+                    .GetBuffer(&mut data, &mut frames, &mut flags, Some(&mut position), None)
+                    .map_err(|e| format!("GetBuffer failed: {}", e))?;
+
+                // The device position jumps ahead by the number of frames that overflowed the buffer
+                if let Some(expected) = next_position {
+                    if position > expected {
+                        stats.add_dropped_frames(position - expected);
+                    }
+                }
+                next_position = Some(position + frames as u64);
+
+                let len = frames as usize * channels;
+                if flags & AUDCLNT_BUFFERFLAGS_SILENT.0 as u32 != 0 || data.is_null() {
+                    handler(&vec![0i16; len]);
+                } else {
+                    handler(std::slice::from_raw_parts(data as *const i16, len));
+                }
+
+                self.capture
+                    .ReleaseBuffer(frames)
+                    .map_err(|e| format!("ReleaseBuffer failed: {}", e))?;
+            }
+        }
+        Ok(())
+    }
+}
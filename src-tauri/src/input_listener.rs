@@ -0,0 +1,260 @@
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+use std::sync::mpsc::{Receiver, Sender};
+use std::sync::Mutex;
+use windows::Win32::Foundation::{HINSTANCE, LPARAM, LRESULT, WPARAM};
+use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+use windows::core::PCWSTR;
+use windows::Win32::UI::WindowsAndMessaging::*;
+
+/// Push-to-talk input from sources outside the global-shortcut system
+/// (extra mouse buttons via a low-level hook, USB foot pedals via HID reports)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PttEvent {
+    Pressed,
+    Released,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MouseButton {
+    None,
+    Middle,
+    X1, // "Back" side button
+    X2, // "Forward" side button
+}
+
+impl MouseButton {
+    /// Parse the `ptt_mouse_button` setting ("none" | "middle" | "x1" | "x2")
+    pub fn from_setting(value: &str) -> Self {
+        match value {
+            "middle" => MouseButton::Middle,
+            "x1" => MouseButton::X1,
+            "x2" => MouseButton::X2,
+            _ => MouseButton::None,
+        }
+    }
+
+    fn to_u8(self) -> u8 {
+        match self {
+            MouseButton::None => 0,
+            MouseButton::Middle => 1,
+            MouseButton::X1 => 2,
+            MouseButton::X2 => 3,
+        }
+    }
+}
+
+static BOUND_MOUSE_BUTTON: AtomicU8 = AtomicU8::new(0);
+static MOUSE_SENDER: Mutex<Option<Sender<PttEvent>>> = Mutex::new(None);
+static HID_GENERATION: AtomicU64 = AtomicU64::new(0);
+static CLICK_WAITER: Mutex<Option<Sender<()>>> = Mutex::new(None);
+
+/// Change which mouse button triggers push-to-talk (takes effect immediately)
+pub fn set_mouse_button(button: MouseButton) {
+    BOUND_MOUSE_BUTTON.store(button.to_u8(), Ordering::SeqCst);
+    println!("🖱️ Push-to-talk mouse button: {:?}", button);
+}
+
+/// Get told about the next left click anywhere, once. The click itself goes through untouched;
+/// the message comes on release, when the clicked window has focus. Arming again (or
+/// `cancel_next_click`) drops the previous receiver's sender.
+pub fn next_click() -> Receiver<()> {
+    let (tx, rx) = std::sync::mpsc::channel();
+    *CLICK_WAITER.lock().unwrap() = Some(tx);
+    rx
+}
+
+pub fn cancel_next_click() {
+    CLICK_WAITER.lock().unwrap().take();
+}
+
+/// Low-level mouse hook: report presses of the bound button and swallow them
+/// so the app under the cursor doesn't also react (e.g. browser "back")
+unsafe extern "system" fn mouse_hook_proc(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    if code == HC_ACTION as i32 {
+        if wparam.0 as u32 == WM_LBUTTONUP {
+            if let Some(tx) = CLICK_WAITER.lock().unwrap().take() {
+                let _ = tx.send(());
+            }
+        }
+
+        let bound = BOUND_MOUSE_BUTTON.load(Ordering::SeqCst);
+        if bound != 0 {
+            let info = &*(lparam.0 as *const MSLLHOOKSTRUCT);
+            let xbutton = (info.mouseData >> 16) as u16;
+            let event = match (wparam.0 as u32, bound) {
+                (WM_MBUTTONDOWN, 1) => Some(PttEvent::Pressed),
+                (WM_MBUTTONUP, 1) => Some(PttEvent::Released),
+                (WM_XBUTTONDOWN, 2) if xbutton == XBUTTON1 => Some(PttEvent::Pressed),
+                (WM_XBUTTONUP, 2) if xbutton == XBUTTON1 => Some(PttEvent::Released),
+                (WM_XBUTTONDOWN, 3) if xbutton == XBUTTON2 => Some(PttEvent::Pressed),
+                (WM_XBUTTONUP, 3) if xbutton == XBUTTON2 => Some(PttEvent::Released),
+                _ => None,
+            };
+
+            if let Some(event) = event {
+                if let Some(tx) = MOUSE_SENDER.lock().unwrap().as_ref() {
+                    let _ = tx.send(event);
+                }
+                return LRESULT(1);
+            }
+        }
+    }
+    CallNextHookEx(HHOOK::default(), code, wparam, lparam)
+}
+
+/// Install the low-level mouse hook on a dedicated thread with its own message loop.
+/// The hook stays installed for the app's lifetime; `set_mouse_button` enables/disables it.
+pub fn start_mouse_hook(tx: Sender<PttEvent>) {
+    *MOUSE_SENDER.lock().unwrap() = Some(tx);
+
+    std::thread::spawn(|| unsafe {
+        let module = match GetModuleHandleW(PCWSTR::null()) {
+            Ok(m) => m,
+            Err(e) => {
+                eprintln!("❌ GetModuleHandleW failed: {}", e);
+                return;
+            }
+        };
+        let hook = match SetWindowsHookExW(WH_MOUSE_LL, Some(mouse_hook_proc), HINSTANCE::from(module), 0) {
+            Ok(h) => h,
+            Err(e) => {
+                eprintln!("❌ Failed to install mouse hook: {}", e);
+                return;
+            }
+        };
+        println!("🖱️ Mouse hook installed");
+
+        // Low-level hooks are called through this thread's message queue
+        let mut msg = MSG::default();
+        while GetMessageW(&mut msg, None, 0, 0).as_bool() {
+            let _ = TranslateMessage(&msg);
+            DispatchMessageW(&msg);
+        }
+
+        let _ = UnhookWindowsHookEx(hook);
+        println!("🖱️ Mouse hook removed");
+    });
+}
+
+/// Parse a "VID:PID" hex string (e.g. "05F3:00FF")
+pub fn parse_hid_id(value: &str) -> Option<(u16, u16)> {
+    let (vid, pid) = value.trim().split_once(':')?;
+    Some((
+        u16::from_str_radix(vid.trim(), 16).ok()?,
+        u16::from_str_radix(pid.trim(), 16).ok()?,
+    ))
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct HidDeviceInfo {
+    pub id: String, // "VID:PID", the format stored in the ptt_hid_device setting
+    pub name: String,
+}
+
+/// List connected HID devices (for choosing a foot pedal)
+pub fn list_hid_devices() -> Result<Vec<HidDeviceInfo>, String> {
+    let api = hidapi::HidApi::new().map_err(|e| format!("Failed to init HID: {}", e))?;
+    let mut devices: Vec<HidDeviceInfo> = api
+        .device_list()
+        .map(|d| HidDeviceInfo {
+            id: format!("{:04X}:{:04X}", d.vendor_id(), d.product_id()),
+            name: format!(
+                "{} {}",
+                d.manufacturer_string().unwrap_or(""),
+                d.product_string().unwrap_or("Unknown device")
+            )
+            .trim()
+            .to_string(),
+        })
+        .collect();
+    // A device shows up once per interface, not necessarily next to each other
+    devices.sort_by(|a, b| a.id.cmp(&b.id));
+    devices.dedup_by(|a, b| a.id == b.id);
+    Ok(devices)
+}
+
+/// Start reading a HID foot pedal. Any non-zero byte in a report counts as "pressed",
+/// which covers the common single/triple-pedal devices that report a button bitmask.
+/// On devices with numbered reports the leading report ID is skipped.
+/// Calling again (or `stop_hid_listener`) stops the previous listener thread.
+pub fn start_hid_listener(vendor_id: u16, product_id: u16, tx: Sender<PttEvent>) {
+    let generation = HID_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+
+    std::thread::spawn(move || {
+        let is_current = || HID_GENERATION.load(Ordering::SeqCst) == generation;
+        let mut pressed = false;
+
+        while is_current() {
+            let device = hidapi::HidApi::new().and_then(|api| api.open(vendor_id, product_id));
+            let device = match device {
+                Ok(d) => d,
+                Err(e) => {
+                    eprintln!("⚠️ HID pedal {:04X}:{:04X} not available: {}", vendor_id, product_id, e);
+                    // Retry periodically: the pedal may be plugged in later
+                    std::thread::sleep(std::time::Duration::from_secs(5));
+                    continue;
+                }
+            };
+            let data_start = if uses_report_ids(&device) { 1 } else { 0 };
+            println!("🦶 HID pedal {:04X}:{:04X} connected (numbered reports: {})", vendor_id, product_id, data_start == 1);
+
+            let mut buf = [0u8; 64];
+            while is_current() {
+                match device.read_timeout(&mut buf, 200) {
+                    Ok(0) => {} // Timeout, no report
+                    Ok(n) => {
+                        let now_pressed = buf[data_start.min(n)..n].iter().any(|b| *b != 0);
+                        if now_pressed != pressed {
+                            pressed = now_pressed;
+                            let _ = tx.send(if pressed { PttEvent::Pressed } else { PttEvent::Released });
+                        }
+                    }
+                    Err(e) => {
+                        eprintln!("⚠️ HID pedal read failed (disconnected?): {}", e);
+                        if pressed {
+                            pressed = false;
+                            let _ = tx.send(PttEvent::Released);
+                        }
+                        break;
+                    }
+                }
+            }
+        }
+        println!("🦶 HID pedal listener stopped");
+    });
+}
+
+/// Whether the device's reports start with a report ID: its report descriptor has a
+/// Report ID item. Walks the descriptor item by item so data bytes aren't mistaken for one.
+fn uses_report_ids(device: &hidapi::HidDevice) -> bool {
+    const REPORT_ID: u8 = 0x84; // Global item, tag 8
+    let mut descriptor = [0u8; hidapi::MAX_REPORT_DESCRIPTOR_SIZE];
+    let Ok(len) = device.get_report_descriptor(&mut descriptor) else {
+        return false;
+    };
+    let descriptor = &descriptor[..len];
+    let mut i = 0;
+    while i < descriptor.len() {
+        let prefix = descriptor[i];
+        if prefix == 0xFE {
+            // Long item: size byte, tag byte, data
+            let size = descriptor.get(i + 1).copied().unwrap_or(0) as usize;
+            i += 3 + size;
+            continue;
+        }
+        if prefix & 0xFC == REPORT_ID {
+            return true;
+        }
+        let size = match prefix & 0x03 {
+            3 => 4,
+            size => size as usize,
+        };
+        i += 1 + size;
+    }
+    false
+}
+
+/// Stop the HID pedal listener, if any
+pub fn stop_hid_listener() {
+    HID_GENERATION.fetch_add(1, Ordering::SeqCst);
+}
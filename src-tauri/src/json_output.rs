@@ -0,0 +1,95 @@
+use crate::db::Database;
+use crate::proxy::{self, ProxySettings};
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+
+const DEFAULT_MODEL: &str = "gpt-4o-mini";
+
+/// Generic command shape, for users who haven't written their own schema.
+/// Structured outputs (strict) require every property to be listed in `required`.
+pub const DEFAULT_SCHEMA: &str = r#"{
+  "type": "object",
+  "properties": {
+    "action": { "type": "string", "description": "What to do, as a short verb (e.g. create_task, send_message)" },
+    "target": { "type": "string", "description": "Who or what the action applies to, empty if none" },
+    "details": { "type": "string", "description": "Everything else that was said" }
+  },
+  "required": ["action", "target", "details"],
+  "additionalProperties": false
+}"#;
+
+/// JSON output mode (Alt+Shift+J): the dictation is turned into JSON matching `schema`
+/// and handed to automation (file, webhook, local API) instead of being pasted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonOutputSettings {
+    pub schema: String,              // JSON Schema of the object the model must return
+    pub model: String,               // Must support structured outputs
+    pub file_path: Option<String>,   // Each result is appended as one line (JSON Lines)
+    pub webhook_url: Option<String>, // Each result is POSTed as the request body
+}
+
+impl JsonOutputSettings {
+    pub fn load(database: &Database) -> Self {
+        let load = |key: &str| database.load_setting(key).ok().flatten().filter(|v| !v.trim().is_empty());
+        Self {
+            schema: load("json_output_schema").unwrap_or_else(|| DEFAULT_SCHEMA.to_string()),
+            model: load("json_output_model").unwrap_or_else(|| DEFAULT_MODEL.to_string()),
+            file_path: load("json_output_path"),
+            webhook_url: load("json_output_webhook"),
+        }
+    }
+
+    pub fn save(&self, database: &Database) -> rusqlite::Result<()> {
+        database.save_setting("json_output_schema", self.schema.trim())?;
+        database.save_setting("json_output_model", self.model.trim())?;
+        for (key, value) in [("json_output_path", &self.file_path), ("json_output_webhook", &self.webhook_url)] {
+            match value.as_deref().map(str::trim).filter(|v| !v.is_empty()) {
+                Some(v) => database.save_setting(key, v)?,
+                None => database.delete_setting(key)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// The schema as JSON; rejects anything that isn't an object schema
+    pub fn parsed_schema(&self) -> Result<serde_json::Value, String> {
+        let schema: serde_json::Value =
+            serde_json::from_str(&self.schema).map_err(|e| format!("Schema JSON inválido: {}", e))?;
+        if schema.get("type").and_then(|t| t.as_str()) != Some("object") {
+            return Err("O schema precisa ser um objeto (\"type\": \"object\")".to_string());
+        }
+        Ok(schema)
+    }
+}
+
+/// Append `json` as one line to `path` (created with its folders if missing)
+pub fn append_to_file(path: &str, json: &serde_json::Value) -> Result<PathBuf, String> {
+    let path = PathBuf::from(path);
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    writeln!(file, "{}", json).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+/// POST `json` to the webhook, through the configured proxy if any
+pub async fn post_webhook(url: &str, json: &serde_json::Value, proxy: Option<&ProxySettings>) -> Result<(), String> {
+    let response = proxy::http_client(proxy)
+        .post(url)
+        .json(json)
+        .timeout(std::time::Duration::from_secs(15))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to reach webhook: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("Webhook returned {}", response.status()));
+    }
+    Ok(())
+}
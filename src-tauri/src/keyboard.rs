@@ -0,0 +1,60 @@
+use enigo::{Direction, Enigo, Key, Keyboard, Settings};
+use serde::Serialize;
+use windows::Win32::UI::Input::KeyboardAndMouse::{GetKeyboardLayout, VkKeyScanExW, HKL};
+use windows::Win32::UI::WindowsAndMessaging::{GetForegroundWindow, GetWindowThreadProcessId};
+
+/// Keyboard layout of the window that will receive the keystrokes (each thread can have its own)
+fn foreground_layout() -> HKL {
+    unsafe {
+        let thread = GetWindowThreadProcessId(GetForegroundWindow(), None);
+        GetKeyboardLayout(thread)
+    }
+}
+
+/// Virtual-key code of the key that types `c` in `layout`. Ctrl shortcuts follow the layout
+/// (Ctrl+V is wherever "v" is on AZERTY, Dvorak...), while layouts without the letter
+/// (Cyrillic, Greek) keep the US key, which is what apps fall back to.
+fn vk_for_char(c: char, layout: HKL) -> u16 {
+    let us_key = c.to_ascii_uppercase() as u16; // VK_A..VK_Z match the ASCII capitals
+    let scan = unsafe { VkKeyScanExW(c as u16, layout) };
+    if scan == -1 {
+        return us_key;
+    }
+    let shift_state = (scan >> 8) & 0xff;
+    if shift_state & !1 != 0 {
+        return us_key; // Needs Ctrl/Alt (AltGr) to type, can't be combined with Ctrl
+    }
+    (scan & 0xff) as u16
+}
+
+/// Press Ctrl+`c` on the key that produces `c` in the foreground layout
+pub fn send_ctrl_shortcut(c: char) -> Result<(), String> {
+    let vk = vk_for_char(c, foreground_layout());
+    let mut enigo = Enigo::new(&Settings::default())
+        .map_err(|e| format!("Failed to create Enigo: {:?}", e))?;
+    enigo.key(Key::Control, Direction::Press)
+        .map_err(|e| format!("Failed to press Ctrl: {:?}", e))?;
+    let pressed = enigo.key(Key::Other(vk as u32), Direction::Click)
+        .map_err(|e| format!("Failed to press {}: {:?}", c.to_ascii_uppercase(), e));
+    // Always release Ctrl, even if the letter failed, so it doesn't stay stuck
+    enigo.key(Key::Control, Direction::Release)
+        .map_err(|e| format!("Failed to release Ctrl: {:?}", e))?;
+    pressed
+}
+
+/// What paste/copy will send in the foreground window, for troubleshooting layouts
+#[derive(Debug, Clone, Serialize)]
+pub struct LayoutReport {
+    pub layout: String,   // HKL in hex, e.g. "04160416" (pt-BR ABNT2), "040c040c" (fr-FR AZERTY)
+    pub paste_vk: String, // Virtual-key code sent with Ctrl for paste, e.g. "0x56"
+    pub copy_vk: String,
+}
+
+pub fn layout_report() -> LayoutReport {
+    let layout = foreground_layout();
+    LayoutReport {
+        layout: format!("{:08x}", layout.0 as usize as u32),
+        paste_vk: format!("0x{:02x}", vk_for_char('v', layout)),
+        copy_vk: format!("0x{:02x}", vk_for_char('c', layout)),
+    }
+}
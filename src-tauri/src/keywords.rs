@@ -0,0 +1,102 @@
+use crate::events::{emit_app_event, AppEvent};
+use tauri::AppHandle;
+use tauri_plugin_notification::NotificationExt;
+
+/// Characters of transcript sent along with an alert
+const CONTEXT_CHARS: usize = 80;
+
+/// Split the `watch_words` setting (comma or newline separated) into lowercase keywords
+pub fn parse_watch_words(setting: &str) -> Vec<String> {
+    let mut words: Vec<String> = Vec::new();
+    for word in setting.split([',', '\n']).map(|w| w.trim().to_lowercase()) {
+        if !word.is_empty() && !words.contains(&word) {
+            words.push(word);
+        }
+    }
+    words
+}
+
+/// Watches a stream of transcription deltas for keywords. Deltas are word fragments,
+/// so a keyword only matches once the word after it has started (whole-word match),
+/// or once the transcript ends (`finish`).
+pub struct KeywordSpotter {
+    keywords: Vec<String>,
+    notify: bool,
+    text: String,          // Lowercased transcript so far
+    last_match_end: usize, // Byte offset in `text`; matches must start after it
+}
+
+impl KeywordSpotter {
+    pub fn new(keywords: Vec<String>, notify: bool) -> Self {
+        Self { keywords, notify, text: String::new(), last_match_end: 0 }
+    }
+
+    /// Add a delta and alert (event + optional notification) for every keyword it completes
+    pub fn feed(&mut self, app: &AppHandle, delta: &str) {
+        if self.keywords.is_empty() {
+            return;
+        }
+        self.text.push_str(&delta.to_lowercase());
+        self.scan(app, delta.len(), false);
+    }
+
+    /// The transcript is over: a keyword that ends it counts as a whole word
+    pub fn finish(&mut self, app: &AppHandle) {
+        if self.keywords.is_empty() {
+            return;
+        }
+        self.scan(app, 0, true);
+    }
+
+    /// Report the matches in the last `added` bytes (plus the tail before them that a match can
+    /// span). `at_end` lets a match end the text.
+    fn scan(&mut self, app: &AppHandle, added: usize, at_end: bool) {
+        // Only rescan the tail that can still contain an unreported match
+        let longest = self.keywords.iter().map(|k| k.len()).max().unwrap_or(0);
+        let mut scan_from = self.last_match_end.max(self.text.len().saturating_sub(longest + added + 1));
+        while !self.text.is_char_boundary(scan_from) {
+            scan_from -= 1;
+        }
+
+        let mut found: Vec<(usize, usize, String)> = Vec::new();
+        for keyword in &self.keywords {
+            for (start, _) in self.text[scan_from..].match_indices(keyword.as_str()) {
+                let start = scan_from + start;
+                let end = start + keyword.len();
+                let before_ok = self.text[..start].chars().next_back().map_or(true, |c| !c.is_alphanumeric());
+                // Require the following character so "deadline" isn't reported while "deadlines" is still arriving
+                let after_ok = self.text[end..].chars().next().map_or(at_end, |c| !c.is_alphanumeric());
+                if before_ok && after_ok && start >= self.last_match_end {
+                    found.push((start, end, keyword.clone()));
+                }
+            }
+        }
+        found.sort();
+
+        for (start, end, keyword) in found {
+            if start < self.last_match_end {
+                continue; // Overlaps a keyword already reported
+            }
+            self.last_match_end = end;
+            let context = self.context(end);
+            println!("🔔 Keyword detected: '{}'", keyword);
+            let _ = emit_app_event(app, AppEvent::KeywordDetected { keyword: keyword.clone(), context: context.clone() });
+            if self.notify {
+                if let Err(e) = app.notification().builder()
+                    .title(format!("Dicta - \"{}\"", keyword))
+                    .body(context)
+                    .show()
+                {
+                    eprintln!("⚠️ Failed to show keyword notification: {}", e);
+                }
+            }
+        }
+    }
+
+    /// Transcript leading up to `end`
+    fn context(&self, end: usize) -> String {
+        let before = &self.text[..end];
+        let count = before.chars().count();
+        before.chars().skip(count.saturating_sub(CONTEXT_CHARS)).collect::<String>().trim().to_string()
+    }
+}
@@ -4,16 +4,63 @@ mod realtime;
 mod db;
 mod system_audio;
 mod queue;
-
-use tauri::{Emitter, Manager, State, AppHandle, PhysicalPosition};
-use tauri::menu::{Menu, MenuItem};
+mod audio_archive;
+mod input_listener;
+mod chunked;
+#[cfg(feature = "local-whisper")]
+mod local_whisper;
+#[cfg(not(feature = "local-whisper"))]
+#[path = "local_whisper_disabled.rs"]
+mod local_whisper;
+mod tts_cache;
+mod meeting;
+mod keywords;
+mod sync;
+mod api_server;
+mod mcp;
+mod note_writer;
+mod notion;
+mod screenshot;
+mod text_insert;
+mod clipboard_history;
+mod active_window;
+mod sound_cues;
+mod sidetone;
+mod quiet_hours;
+mod exclusive_capture;
+mod proxy;
+mod rate_limit;
+mod network;
+mod events;
+mod shortcuts;
+mod session;
+mod pipeline;
+mod updates;
+mod voice_commands;
+mod keyboard;
+mod plain_text;
+mod numbers;
+mod profanity;
+mod rich_text;
+mod json_output;
+mod email;
+mod plugins;
+mod text_diff;
+mod text_repair;
+mod titles;
+mod assistant;
+
+use tauri::{Listener, Manager, State, AppHandle, PhysicalPosition};
+use tauri::image::Image;
+use tauri::menu::{CheckMenuItem, IsMenuItem, Menu, MenuItem, PredefinedMenuItem, Submenu};
 use tauri::tray::{TrayIconBuilder, TrayIconEvent};
-use tauri_plugin_global_shortcut::{Code, Modifiers, Shortcut, GlobalShortcutExt};
+use tauri_plugin_global_shortcut::ShortcutState;
 use tauri_plugin_clipboard_manager::ClipboardExt;
+use tauri_plugin_notification::NotificationExt;
+use std::collections::{HashMap, VecDeque};
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
-use enigo::{Enigo, Key, Keyboard, Settings};
 
 fn ts() -> String {
     let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
@@ -31,10 +78,82 @@ macro_rules! tlog {
     };
 }
 
+/// tlog! tagged with the recording/prompt session it belongs to
+macro_rules! slog {
+    ($session:expr, $($arg:tt)*) => {
+        println!("{} [{}] {}", ts(), $session.short(), format!($($arg)*));
+    };
+}
+
+macro_rules! eslog {
+    ($session:expr, $($arg:tt)*) => {
+        eprintln!("{} [{}] {}", ts(), $session.short(), format!($($arg)*));
+    };
+}
+
 // Re-export TranscriptionEntry from db module
 use db::TranscriptionEntry;
+use events::{emit_app_event, AppEvent};
+use shortcuts::HotkeyAction;
+use session::SessionId;
+use titles::TitleMode;
+use json_output::JsonOutputSettings;
+use email::EmailSettings;
+use pipeline::{AppOutput, Capture, Delivery, DeliveryKind, DeliveryOrder, Pipeline, PromptRequest, RecordingRequest};
+
+/// Whether output is typed into the focused control via UI Automation instead of Ctrl+V
+fn uses_ui_automation(app: &AppHandle) -> bool {
+    app.try_state::<AppState>()
+        .and_then(|state| state.database.load_setting("insertion_method").ok().flatten())
+        .is_some_and(|v| v == "ui_automation")
+}
+
+/// Whether the clipboard writes of a paste are hidden from clipboard managers (default on)
+fn excludes_clipboard_history(app: &AppHandle) -> bool {
+    app.try_state::<AppState>()
+        .and_then(|state| state.database.load_setting("clipboard_history_exclusion").ok().flatten())
+        .map_or(true, |v| v != "false")
+}
+
+/// Temporary clipboard write of a paste (the text, then the user's clipboard put back)
+fn write_paste_clipboard(app: &AppHandle, text: &str, html: Option<&str>) -> Result<(), String> {
+    if excludes_clipboard_history(app) {
+        return clipboard_history::write_excluded(text, html);
+    }
+    match html {
+        Some(html) => app.clipboard().write_html(html, Some(text)),
+        None => app.clipboard().write_text(text),
+    }
+    .map_err(|e| e.to_string())
+}
 
 fn auto_paste_text(app: &AppHandle, text: &str) -> Result<(), String> {
+    paste_text(app, text, None)
+}
+
+/// Paste a markdown response with formatting: the clipboard gets rendered HTML for rich editors
+/// (Word, Gmail) and plain text for everything else (terminals, plain inputs)
+fn auto_paste_rich(app: &AppHandle, markdown: &str) -> Result<(), String> {
+    paste_text(app, &plain_text::normalize(markdown), Some(&rich_text::to_html(markdown)))
+}
+
+fn paste_text(app: &AppHandle, text: &str, html: Option<&str>) -> Result<(), String> {
+    if text_insert::focused_is_password() {
+        refuse_password_paste(app, text);
+        return Err(PASSWORD_FIELD_REFUSED.to_string());
+    }
+
+    // UI Automation leaves the clipboard alone and works in apps that block pasting
+    if uses_ui_automation(app) {
+        match text_insert::insert(text) {
+            Ok(()) => {
+                play_sound_cue(app, sound_cues::Cue::Paste);
+                return Ok(());
+            }
+            Err(e) => println!("⚠️ UI Automation insertion unavailable ({}), falling back to Ctrl+V", e),
+        }
+    }
+
     println!("🔄 Auto-pasting text...");
 
     // 1. Read current clipboard (with retry)
@@ -68,7 +187,7 @@ fn auto_paste_text(app: &AppHandle, text: &str) -> Result<(), String> {
     {
         let mut attempts = 0;
         loop {
-            match app.clipboard().write_text(text) {
+            match write_paste_clipboard(app, text, html) {
                 Ok(_) => {
                     println!("📋 Transcription written to clipboard");
                     break;
@@ -95,31 +214,38 @@ fn auto_paste_text(app: &AppHandle, text: &str) -> Result<(), String> {
     println!("⏳ Waiting 1000ms for keys to be released...");
     std::thread::sleep(Duration::from_millis(1000));
 
-    // 4. Simulate Ctrl+V
-    let mut enigo = Enigo::new(&Settings::default())
-        .map_err(|e| format!("Failed to create Enigo: {:?}", e))?;
-
-    enigo.key(Key::Control, enigo::Direction::Press)
-        .map_err(|e| format!("Failed to press Ctrl: {:?}", e))?;
-    enigo.key(Key::Unicode('v'), enigo::Direction::Click)
-        .map_err(|e| format!("Failed to press V: {:?}", e))?;
-    enigo.key(Key::Control, enigo::Direction::Release)
-        .map_err(|e| format!("Failed to release Ctrl: {:?}", e))?;
+    // 4. Simulate Ctrl+V (on the key that is "v" in the target window's keyboard layout)
+    let target_before = text_insert::focused_text();
+    keyboard::send_ctrl_shortcut('v')?;
 
     println!("⌨️ Simulated Ctrl+V");
 
-    // 5. Wait for paste to complete and check if clipboard changed
-    std::thread::sleep(Duration::from_millis(150));
+    // 5. Where the target control exposes its text, it tells for sure that the paste landed.
+    // Slow apps take a while to process it; when it doesn't change, fall back to the clipboard.
+    let verified = match target_before {
+        Some(before) => focused_text_changes(&before),
+        None => {
+            std::thread::sleep(Duration::from_millis(150)); // Wait for paste to complete
+            false
+        }
+    };
+    if verified {
+        println!("✅ Paste verified in the target control");
+        play_sound_cue(app, sound_cues::Cue::Paste);
+        write_paste_clipboard(app, &original_clipboard, None)
+            .map_err(|e| format!("Failed to restore clipboard: {}", e))?;
+        println!("♻️ Restored original clipboard");
+        return Ok(());
+    }
+    play_sound_cue(app, sound_cues::Cue::Paste);
 
-    // 6. Check if clipboard still has our transcribed text
+    // 6. Otherwise guess from the clipboard: if it still has our text, the paste likely succeeded.
+    // Only restore if clipboard was consumed (changed)
     let current_clipboard = app.clipboard().read_text()
         .map_err(|e| format!("Failed to read clipboard after paste: {}", e))?;
-
-    // If clipboard still has our text, the paste likely succeeded
-    // Only restore if clipboard was consumed (changed)
     if current_clipboard == text {
         println!("📋 Clipboard unchanged - paste likely succeeded, restoring original");
-        app.clipboard().write_text(&original_clipboard)
+        write_paste_clipboard(app, &original_clipboard, None)
             .map_err(|e| format!("Failed to restore clipboard: {}", e))?;
         println!("♻️ Restored original clipboard");
     } else {
@@ -129,716 +255,4328 @@ fn auto_paste_text(app: &AppHandle, text: &str) -> Result<(), String> {
     Ok(())
 }
 
-struct AppState {
-    audio_recorder: Arc<Mutex<audio::AudioRecorder>>,
-    openai_client: Arc<openai::OpenAIClient>,
-    realtime_client: Arc<realtime::RealtimeClient>,
-    database: Arc<db::Database>,
-    is_recording: Arc<Mutex<bool>>,
-    use_realtime: Arc<Mutex<bool>>, // Track which API to use
-    prompt_mode: Arc<Mutex<Option<String>>>, // Track prompt mode: None, Some("gpt-4o-mini"), or Some("gpt-4o")
-    current_session_transcript: Arc<Mutex<String>>, // Accumulate transcript for current session
-    last_transcription: Arc<Mutex<Option<String>>>,
-    paste_in_progress: Arc<Mutex<bool>>,
-    recording_start_time: Arc<Mutex<Option<Instant>>>,
-    speech_active: Arc<Mutex<bool>>, // Track if speech is currently being detected
-    last_speech_end: Arc<Mutex<Option<Instant>>>, // Track when last speech ended
-    last_transcription_time: Arc<Mutex<Option<Instant>>>, // Track when last transcription.completed arrived
-    tts_enabled: Arc<Mutex<bool>>,
-    tts_sink: Arc<Mutex<Option<rodio::Sink>>>,
-    tts_stream_handle: Arc<Mutex<Option<rodio::OutputStreamHandle>>>,
-    tts_active: Arc<Mutex<bool>>,
-    queue_dir: PathBuf,
-    streaming_stop_handle: Arc<Mutex<Option<audio::StreamingStopHandle>>>,
-}
-
-/// Strip markdown links, citations, and raw URLs from text for TTS playback.
-fn strip_links_for_tts(text: &str) -> String {
-    // 1. Markdown links [text](url) → text
-    let mut result = String::with_capacity(text.len());
-    let mut chars = text.chars().peekable();
-    while let Some(c) = chars.next() {
-        if c == '[' {
-            let mut link_text = String::new();
-            let mut found_close = false;
-            for inner in chars.by_ref() {
-                if inner == ']' { found_close = true; break; }
-                link_text.push(inner);
-            }
-            if found_close && chars.peek() == Some(&'(') {
-                chars.next(); // skip '('
-                let mut depth = 1;
-                for inner in chars.by_ref() {
-                    if inner == '(' { depth += 1; }
-                    if inner == ')' { depth -= 1; if depth == 0 { break; } }
-                }
-                // Only keep link text if it doesn't look like a domain
-                if !looks_like_url(&link_text) {
-                    result.push_str(&link_text);
-                }
-            } else {
-                result.push('[');
-                result.push_str(&link_text);
-                if found_close { result.push(']'); }
-            }
-        } else {
-            result.push(c);
-        }
-    }
-    // 2. Remove parenthesized content that looks like URLs/domains: (domain.com), (https://...)
-    let mut clean = String::with_capacity(result.len());
-    let mut i = 0;
-    while i < result.len() {
-        if result.as_bytes()[i] == b'(' {
-            let start = i + 1;
-            if let Some(end) = result[start..].find(')') {
-                let inner = result[start..start + end].trim();
-                if looks_like_url(inner) {
-                    i = start + end + 1;
-                    continue;
-                }
-            }
+/// How long the focused control is watched for the pasted text after Ctrl+V
+const PASTE_VERIFY_TIMEOUT: Duration = Duration::from_millis(1500);
+
+/// Whether the focused control's text changes from `before` within PASTE_VERIFY_TIMEOUT
+fn focused_text_changes(before: &str) -> bool {
+    let deadline = Instant::now() + PASTE_VERIFY_TIMEOUT;
+    while Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(50));
+        match text_insert::focused_text() {
+            Some(after) if after != before => return true,
+            Some(_) => {}
+            None => return false, // Focus moved to something without readable text
         }
-        clean.push(result.as_bytes()[i] as char);
-        i += 1;
     }
-    // 3. Remove remaining raw URLs
-    clean.split_whitespace()
-        .filter(|w| !looks_like_url(w))
-        .collect::<Vec<_>>()
-        .join(" ")
+    println!("⚠️ Target control unchanged after Ctrl+V, checking the clipboard instead");
+    false
 }
 
-/// Check if a string looks like a URL or domain name
-fn looks_like_url(s: &str) -> bool {
-    let s = s.trim_matches(|c: char| c == '(' || c == ')' || c == '[' || c == ']');
-    if s.starts_with("http://") || s.starts_with("https://") || s.starts_with("www.") {
-        return true;
-    }
-    // Match domain patterns like "site.com", "site.com.br", "site.org/path"
-    let tlds = [".com", ".org", ".net", ".br", ".io", ".dev", ".gov", ".edu", ".info", ".co"];
-    tlds.iter().any(|tld| s.contains(tld))
-}
+/// Error of a paste refused by `refuse_password_paste`. The user has been told already.
+const PASSWORD_FIELD_REFUSED: &str = "Focused field is a password box, not pasted";
 
-/// Play TTS audio in chunks (sentence by sentence) with visual widget feedback.
-/// Each chunk is generated and played sequentially so audio starts fast.
-/// Can be cancelled by setting tts_active to false.
-async fn play_tts_chunked(
-    app: AppHandle,
-    text: String,
-    openai: Arc<openai::OpenAIClient>,
-    tts_sink: Arc<Mutex<Option<rodio::Sink>>>,
-    _tts_stream_handle: Arc<Mutex<Option<rodio::OutputStreamHandle>>>,
-    tts_active: Arc<Mutex<bool>>,
-) {
-    // Set active flag
-    *tts_active.lock().unwrap() = true;
+/// The focus is on a password box: leave the text on the clipboard instead of pasting it there
+fn refuse_password_paste(app: &AppHandle, text: &str) {
+    println!("🔒 Focused field is a password box, not pasting");
+    let in_clipboard = app.clipboard().write_text(text).is_ok();
+    let _ = emit_app_event(app, AppEvent::PasswordFieldFocused);
+    let body = if in_clipboard {
+        "O campo selecionado é de senha, então o texto não foi colado. Ele está na área de transferência."
+    } else {
+        "O campo selecionado é de senha, então o texto não foi colado."
+    };
+    notify(app, NotificationKind::PasteFailed, "Dicta - Campo de senha", body);
+}
 
-    // Show TTS widget
-    if let Some(w) = app.get_webview_window("tts-widget") {
-        if let Ok(Some(monitor)) = app.primary_monitor() {
-            let screen = monitor.size();
-            let x = (screen.width as i32 - 100) / 2;
-            let y = screen.height as i32 - 32 - 120;
-            let _ = w.set_position(PhysicalPosition::new(x, y));
-        }
-        let _ = w.show();
-    }
+/// Copy the current selection in the focused app by simulating Ctrl+C,
+/// restoring the user's clipboard afterwards (counterpart of auto_paste_text)
+fn copy_selected_text(app: &AppHandle) -> Result<String, String> {
+    let original_clipboard = app.clipboard().read_text().unwrap_or_default();
 
-    // Strip markdown links and raw URLs so TTS doesn't read them
-    let clean_text = strip_links_for_tts(&text);
-    let chunks = openai::split_into_tts_chunks(&clean_text);
-    println!("🔊 TTS chunked playback: {} chunks", chunks.len());
+    // Clear first so we can tell whether the copy produced anything
+    app.clipboard().write_text("")
+        .map_err(|e| format!("Failed to clear clipboard: {}", e))?;
 
-    // Channel to send audio bytes from async context to the playback thread.
-    // The playback thread owns the OutputStream (not Send) and creates Sinks.
-    let (audio_tx, audio_rx) = std::sync::mpsc::channel::<Vec<u8>>();
-    let tts_active_for_thread = tts_active.clone();
+    // Let the user release the hotkey modifiers, otherwise the OS sees Alt+Shift+Ctrl+C
+    std::thread::sleep(Duration::from_millis(400));
 
-    // Dedicated playback thread — owns the OutputStream so it always uses
-    // the current default output device (not the one from app startup).
-    let playback_thread = std::thread::spawn(move || {
-        let (_stream, stream_handle) = match rodio::OutputStream::try_default() {
-            Ok(pair) => pair,
-            Err(e) => {
-                eprintln!("❌ Failed to open audio output for TTS: {}", e);
-                return;
-            }
-        };
+    keyboard::send_ctrl_shortcut('c')?;
+    println!("⌨️ Simulated Ctrl+C");
 
-        let mut current_sink: Option<rodio::Sink> = None;
+    // Wait for the target app to fill the clipboard
+    let mut selection = String::new();
+    for _ in 0..10 {
+        std::thread::sleep(Duration::from_millis(50));
+        selection = app.clipboard().read_text().unwrap_or_default();
+        if !selection.is_empty() {
+            break;
+        }
+    }
 
-        while let Ok(audio_bytes) = audio_rx.recv() {
-            // Check if cancelled
-            if !*tts_active_for_thread.lock().unwrap() { break; }
+    if let Err(e) = app.clipboard().write_text(&original_clipboard) {
+        eprintln!("⚠️ Failed to restore clipboard: {}", e);
+    } else {
+        println!("♻️ Restored original clipboard");
+    }
 
-            // Wait for previous chunk to finish
-            if let Some(ref sink) = current_sink {
-                while !sink.empty() {
-                    if !*tts_active_for_thread.lock().unwrap() { break; }
-                    std::thread::sleep(std::time::Duration::from_millis(50));
-                }
-                if !*tts_active_for_thread.lock().unwrap() { break; }
-            }
+    if selection.trim().is_empty() {
+        return Err("No text selected".to_string());
+    }
+    Ok(selection)
+}
 
-            // Stop previous and play new
-            if let Some(s) = current_sink.take() { s.stop(); }
+fn is_incognito(database: &db::Database) -> bool {
+    database.load_setting("incognito").ok().flatten().as_deref() == Some("true")
+}
 
-            if let Ok(src) = rodio::Decoder::new(std::io::Cursor::new(audio_bytes)) {
-                if let Ok(sink) = rodio::Sink::try_new(&stream_handle) {
-                    sink.append(src);
-                    current_sink = Some(sink);
-                }
-            }
+/// Save a history entry unless incognito mode is on (then nothing is stored and Ok(None) is returned)
+fn record_transcription(
+    database: &db::Database,
+    session_id: Option<SessionId>,
+    text: &str,
+    timestamp: i64,
+    duration_ms: Option<i64>,
+    model: Option<&str>,
+    cost_cents: Option<i64>,
+    mode: Option<&str>,
+) -> rusqlite::Result<Option<i64>> {
+    if is_incognito(database) {
+        println!("🕶️ Incognito: not saving to history");
+        return Ok(None);
+    }
+    // Same text again shortly after (repeated paste, retried queue item): keep one entry, count both costs
+    let window_secs = dedup_window_secs(database);
+    if window_secs > 0 {
+        if let Some(id) = database.find_recent_duplicate(text, timestamp - window_secs * 1000)? {
+            database.add_transcription_cost(id, cost_cents.unwrap_or(0))?;
+            println!("♻️ Duplicate of entry {} within {}s, merged instead of saved", id, window_secs);
+            return Ok(Some(id));
         }
+    }
+    let session_id = session_id.map(|id| id.to_string());
+    database.save_transcription(text, timestamp, duration_ms, model, cost_cents, mode, session_id.as_deref()).map(Some)
+}
 
-        // Wait for last chunk to finish playing
-        if let Some(ref sink) = current_sink {
-            while !sink.empty() {
-                if !*tts_active_for_thread.lock().unwrap() { break; }
-                std::thread::sleep(std::time::Duration::from_millis(50));
+/// Give a saved history entry its title per the `title_mode` setting (GPT titles are set in the background)
+fn generate_title(app: &AppHandle, id: i64, text: &str) {
+    let Some(state) = app.try_state::<AppState>() else { return };
+    match TitleMode::load(&state.database) {
+        TitleMode::Off => {}
+        TitleMode::Local => {
+            if let Err(e) = state.database.set_transcription_title(id, &titles::local_title(text)) {
+                eprintln!("⚠️ Failed to save title: {}", e);
             }
         }
-        // _stream drops here, releasing output device
-    });
-
-    for (i, chunk) in chunks.iter().enumerate() {
-        if !*tts_active.lock().unwrap() {
-            println!("🔇 TTS cancelled at chunk {}/{}", i + 1, chunks.len());
-            break;
-        }
-
-        println!("🔊 TTS chunk {}/{}: generating audio for {} chars...", i + 1, chunks.len(), chunk.len());
-
-        match openai.speak_text(chunk).await {
-            Ok(audio) => {
-                if !*tts_active.lock().unwrap() {
-                    println!("🔇 TTS cancelled after generating chunk {}", i + 1);
-                    break;
+        TitleMode::Gpt => {
+            let app = app.clone();
+            let openai = state.openai_client.clone();
+            let text = text.to_string();
+            tauri::async_runtime::spawn(async move {
+                let title = match openai.send_prompt(&titles::gpt_prompt(&text), "gpt-4o-mini", &[], None).await {
+                    Ok(title) => Some(titles::clean(&title)).filter(|t| !t.is_empty()),
+                    Err(e) => {
+                        eprintln!("⚠️ Title generation failed, using the first sentence: {}", e);
+                        None
+                    }
+                };
+                let title = title.unwrap_or_else(|| titles::local_title(&text));
+                let Some(state) = app.try_state::<AppState>() else { return };
+                if let Err(e) = state.database.set_transcription_title(id, &title) {
+                    eprintln!("⚠️ Failed to save title: {}", e);
+                    return;
                 }
-                println!("🔊 TTS chunk {}/{} sent to playback", i + 1, chunks.len());
-                if audio_tx.send(audio).is_err() {
-                    println!("🔇 Playback thread closed");
-                    break;
+                if let Some(window) = app.get_webview_window("main") {
+                    let _ = emit_app_event(&window, AppEvent::HistoryUpdated { session_id: None });
                 }
-            }
-            Err(e) => {
-                eprintln!("❌ TTS chunk {} failed: {}", i + 1, e);
-            }
+            });
         }
     }
+}
 
-    // Drop sender to signal playback thread there's no more data
-    drop(audio_tx);
+/// "off", "local" (first sentence) or "gpt"
+#[tauri::command]
+fn get_title_mode(state: State<'_, AppState>) -> Result<TitleMode, String> {
+    Ok(TitleMode::load(&state.database))
+}
 
-    // Wait for playback thread to finish
-    let _ = playback_thread.join();
+#[tauri::command]
+fn set_title_mode(state: State<'_, AppState>, mode: TitleMode) -> Result<(), String> {
+    state.database.save_setting("title_mode", mode.as_str())
+        .map_err(|e| format!("Failed to save title mode: {}", e))
+}
 
-    // Clean up
-    *tts_active.lock().unwrap() = false;
-    if let Some(w) = app.get_webview_window("tts-widget") {
-        let _ = w.hide();
-    }
-    println!("🔊 TTS chunked playback finished");
+/// Seconds within which an identical entry is merged into the previous one (0 = off)
+fn dedup_window_secs(database: &db::Database) -> i64 {
+    database.load_setting("dedup_window_secs").ok().flatten()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0)
 }
 
 #[tauri::command]
-async fn cancel_recording(state: State<'_, AppState>) -> Result<String, String> {
-    let mut is_recording = state.is_recording.lock().unwrap();
-    if !*is_recording {
-        return Err("Not recording".to_string());
-    }
+fn get_dedup_window(state: State<'_, AppState>) -> Result<i64, String> {
+    Ok(dedup_window_secs(&state.database))
+}
 
-    println!("❌ Cancelling recording...");
-    *is_recording = false;
+#[tauri::command]
+fn set_dedup_window(state: State<'_, AppState>, seconds: i64) -> Result<(), String> {
+    state.database.save_setting("dedup_window_secs", &seconds.max(0).to_string())
+        .map_err(|e| format!("Failed to save dedup window: {}", e))
+}
 
-    // Stop Whisper mode recorder (discard audio)
-    let recorder = state.audio_recorder.lock().unwrap();
-    let _ = recorder.stop_recording();
+/// Collapse history entries with exactly the same text, summing their costs. Returns how many were removed.
+#[tauri::command]
+fn merge_duplicates(app: AppHandle, state: State<'_, AppState>) -> Result<usize, String> {
+    let (removed, orphaned) = state.database.merge_duplicate_transcriptions()
+        .map_err(|e| format!("Failed to merge duplicates: {}", e))?;
+    for session_id in &orphaned {
+        audio_archive::delete(&state.archive_dir, session_id);
+    }
+    if removed > 0 {
+        if let Some(window) = app.get_webview_window("main") {
+            let _ = emit_app_event(&window, AppEvent::HistoryUpdated { session_id: None });
+        }
+        refresh_tray_menu(&app); // The tray lists recent entries
+    }
+    Ok(removed)
+}
 
-    // Stop realtime mode streaming (release microphone + kill WebSocket tasks)
-    if let Some(handle) = state.streaming_stop_handle.lock().unwrap().take() {
-        handle.stop();
-        println!("🔌 Realtime streaming stopped via cancel");
+/// Append a prompt/response pair to the conversation history unless incognito mode is on
+fn record_conversation(database: &db::Database, prompt: &str, response: &str, timestamp: i64) {
+    if is_incognito(database) {
+        return;
     }
+    let _ = database.append_conversation("user", prompt, timestamp - 1);
+    let _ = database.append_conversation("assistant", response, timestamp);
+}
 
-    // Restore system audio
-    if let Err(e) = system_audio::unmute_system_audio() {
-        eprintln!("⚠️ Failed to unmute system audio: {}", e);
+/// Deliver pipeline output: paste it directly, or open the review window first
+/// when review-before-paste is enabled (the user confirms with Enter).
+/// Sessions started for another output target go there instead.
+/// `rich` pastes `text` as formatted HTML (markdown responses); the review window always gets plain text.
+/// `entry` is the history entry of `text`, tagged with the app it's pasted into once it is.
+fn paste_or_review(app: &AppHandle, entry: Option<i64>, text: &str, rich: bool) -> Result<(), String> {
+    let target = app.try_state::<AppState>()
+        .map(|state| std::mem::replace(&mut *state.output_target.lock().unwrap(), OutputTarget::Paste))
+        .unwrap_or(OutputTarget::Paste);
+    match target {
+        // JSON sessions are handled by the pipeline; prompts switched to it still paste
+        OutputTarget::Paste | OutputTarget::Json | OutputTarget::Email => {}
+        OutputTarget::Note => return append_to_note(app, text),
+        OutputTarget::Notion => return send_to_notion(app, text),
+        OutputTarget::Assistant => return Ok(()),
     }
 
-    Ok("Recording cancelled".to_string())
+    let review = app.try_state::<AppState>()
+        .and_then(|state| state.database.load_setting("review_before_paste").ok().flatten())
+        .map(|v| v == "true")
+        .unwrap_or(false);
+    if !review {
+        if app.try_state::<AppState>().is_some_and(|state| paste_on_click(&state.database)) {
+            return paste_on_next_click(app, entry, text, rich);
+        }
+        restore_paste_target(app);
+        let result = if rich { auto_paste_rich(app, text) } else { auto_paste_text(app, text) };
+        if result.is_ok() {
+            tag_paste_window(app, entry);
+        }
+        return result;
+    }
+    if let Some(state) = app.try_state::<AppState>() {
+        *state.review_entry.lock().unwrap() = entry;
+    }
+
+    let window = app.get_webview_window("paste-review")
+        .ok_or("Review window not found")?;
+    if let Ok(Some(monitor)) = window.current_monitor() {
+        let screen_size = monitor.size();
+        let win_width = 420i32;
+        let win_height = 180i32;
+        let x = (screen_size.width as i32 - win_width) / 2;
+        let y = screen_size.height as i32 - win_height - 200;
+        let _ = window.set_position(PhysicalPosition::new(x, y));
+    }
+    emit_app_event(&window, AppEvent::ReviewText(text.to_string()))
+        .map_err(|e| format!("Failed to send text to review window: {}", e))?;
+    let _ = window.show();
+    let _ = window.set_focus();
+    println!("📝 Waiting for review before paste");
+    Ok(())
 }
 
-#[tauri::command]
-async fn start_recording_audio(state: State<'_, AppState>, app: AppHandle) -> Result<String, String> {
-    let mut is_recording = state.is_recording.lock().unwrap();
-    if *is_recording {
-        return Err("Already recording".to_string());
+/// Remember the app a history entry was pasted into, so the history can be filtered by app.
+/// Called right after the paste, when that app is in the foreground.
+fn tag_paste_window(app: &AppHandle, entry: Option<i64>) {
+    if let (Some(state), Some(entry), Some(window)) = (app.try_state::<AppState>(), entry, active_window::foreground()) {
+        if let Err(e) = state.database.set_transcription_window(entry, &window.app_name, &window.title) {
+            eprintln!("⚠️ Failed to record target app: {}", e);
+        }
     }
+}
 
-    println!("🎤 Starting audio recording...");
+/// A result held back by "paste when I click"
+struct PendingPaste {
+    text: String,
+    rich: bool,
+    entry: Option<i64>, // History entry of `text`
+}
 
-    // Set recording start time
-    *state.recording_start_time.lock().unwrap() = Some(Instant::now());
+/// How long a held-back result waits for the click. After that it's dropped (the paste-last
+/// hotkey can still paste it).
+const PASTE_ON_CLICK_TIMEOUT: Duration = Duration::from_secs(60);
 
-    // Get selected microphone from settings
-    let selected_mic = state.database.load_setting("selected_microphone")
-        .ok()
-        .flatten();
+fn paste_on_click(database: &db::Database) -> bool {
+    database.load_setting("paste_on_click").ok().flatten().is_some_and(|v| v == "true")
+}
 
-    let recorder = state.audio_recorder.lock().unwrap();
-    recorder.start_recording(selected_mic)?;
-    *is_recording = true;
-
-    // Mute system audio while recording (frontend already waited for start sound to finish)
-    if let Err(e) = system_audio::mute_system_audio() {
-        eprintln!("⚠️ Failed to mute system audio: {}", e);
-    }
-
-    // Spawn timer task for Whisper mode
-    let is_recording_flag = state.is_recording.clone();
-    let recording_start = state.recording_start_time.clone();
-    let app_clone = app.clone();
+/// Hold the result until the user's next click (or the paste-last hotkey) and paste it into
+/// whatever has focus then, so long requests don't race the user moving around
+fn paste_on_next_click(app: &AppHandle, entry: Option<i64>, text: &str, rich: bool) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    // Where the recording started doesn't matter here, the click picks the target
+    state.paste_target.lock().unwrap().take();
+    *state.pending_paste.lock().unwrap() = Some(PendingPaste { text: text.to_string(), rich, entry });
+    let mut clicks = input_listener::next_click();
 
-    tokio::spawn(async move {
-        let mut warning_shown = false;
-        let mut auto_stop_triggered = false;
+    let _ = emit_app_event(app, AppEvent::ReadyToPaste(text.to_string()));
+    notify(app, NotificationKind::PasteReady, "Dicta - Pronto para colar",
+        "Clique onde quer colar o texto (ou use o atalho de colar a última transcrição).");
+    println!("🖱️ Result ready, waiting for a click to paste");
 
+    let app_handle = app.clone();
+    std::thread::spawn(move || {
+        let state = app_handle.state::<AppState>();
+        let deadline = Instant::now() + PASTE_ON_CLICK_TIMEOUT;
         loop {
-            tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
-
-            if !*is_recording_flag.lock().unwrap() {
-                break;
+            match clicks.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                Ok(()) => {
+                    std::thread::sleep(Duration::from_millis(100)); // Let the click settle focus
+                    if active_window::foreground_handle().is_some() {
+                        break;
+                    }
+                    // Clicked one of Dicta's own windows (widget, toast): wait for the next one
+                    clicks = input_listener::next_click();
+                }
+                // Replaced by a newer result, or the hotkey already pasted it
+                Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => return,
+                Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {
+                    input_listener::cancel_next_click();
+                    if state.pending_paste.lock().unwrap().take().is_some() {
+                        println!("⌛ No click within {:?}, dropped the pending paste", PASTE_ON_CLICK_TIMEOUT);
+                    }
+                    return;
+                }
             }
+        }
 
-            if let Some(start_time) = *recording_start.lock().unwrap() {
-                let elapsed = start_time.elapsed();
-
-                // Show warning at 5 minutes
-                if elapsed >= Duration::from_secs(5 * 60) && !warning_shown {
-                    warning_shown = true;
-                    println!("⚠️ [WHISPER] 5 seconds elapsed, showing warning...");
-                    println!("⚠️ [WHISPER] Elapsed time: {:?}", elapsed);
-
-                    if let Some(warning) = app_clone.get_webview_window("warning-widget") {
-                        println!("⚠️ [WHISPER] Found warning widget");
-
-                        if let Some(widget) = app_clone.get_webview_window("recording-widget") {
-                            println!("⚠️ [WHISPER] Found recording widget");
-                            if let Ok(widget_pos) = widget.outer_position() {
-                                let warning_x = widget_pos.x - 77;
-                                let warning_y = widget_pos.y - 70;
-                                println!("⚠️ [WHISPER] Positioning warning at x:{}, y:{}", warning_x, warning_y);
-                                match warning.set_position(PhysicalPosition::new(warning_x, warning_y)) {
-                                    Ok(_) => println!("⚠️ [WHISPER] ✅ Position set successfully"),
-                                    Err(e) => println!("⚠️ [WHISPER] ❌ Failed to set position: {}", e),
-                                }
-                            }
-                        } else {
-                            println!("⚠️ [WHISPER] ❌ Recording widget not found for positioning");
-                        }
+        let Some(pending) = state.pending_paste.lock().unwrap().take() else { return };
+        paste_pending(&app_handle, pending);
+    });
+    Ok(())
+}
 
-                        match warning.show() {
-                            Ok(_) => {
-                                println!("⚠️ [WHISPER] ✅ Warning shown successfully");
-
-                                // Auto-hide warning after 4 seconds
-                                let warning_clone = warning.clone();
-                                tokio::spawn(async move {
-                                    tokio::time::sleep(tokio::time::Duration::from_secs(4)).await;
-                                    println!("⚠️ [WHISPER] Auto-hiding warning after 4 seconds");
-                                    match warning_clone.hide() {
-                                        Ok(_) => println!("⚠️ [WHISPER] ✅ Warning auto-hidden successfully"),
-                                        Err(e) => println!("⚠️ [WHISPER] ❌ Failed to auto-hide warning: {}", e),
-                                    }
-                                });
-                            },
-                            Err(e) => println!("⚠️ [WHISPER] ❌ Failed to show warning: {}", e),
-                        }
-                    } else {
-                        println!("⚠️ [WHISPER] ❌ Warning widget not found!");
-                    }
-                }
+fn paste_pending(app: &AppHandle, pending: PendingPaste) {
+    let result = if pending.rich { auto_paste_rich(app, &pending.text) } else { auto_paste_text(app, &pending.text) };
+    match result {
+        Ok(()) => {
+            println!("✅ Pending result pasted");
+            tag_paste_window(app, pending.entry);
+        }
+        Err(e) => report_paste_failure(app, None, &pending.text, &e),
+    }
+}
 
-                // Auto-stop at 6 minutes
-                if elapsed >= Duration::from_secs(6 * 60) && !auto_stop_triggered {
-                    auto_stop_triggered = true;
-                    println!("⏰ [WHISPER] 6 minutes limit reached, auto-stopping...");
-                    println!("⏰ [WHISPER] Elapsed time: {:?}", elapsed);
+#[tauri::command]
+fn get_paste_on_click(state: State<'_, AppState>) -> Result<bool, String> {
+    Ok(paste_on_click(&state.database))
+}
 
-                    // DON'T set is_recording = false here - let the frontend's stopRecording() do it
+#[tauri::command]
+fn set_paste_on_click(state: State<'_, AppState>, enabled: bool) -> Result<(), String> {
+    state.database.save_setting("paste_on_click", if enabled { "true" } else { "false" })
+        .map_err(|e| format!("Failed to save paste on click setting: {}", e))
+}
 
-                    if let Some(window) = app_clone.get_webview_window("main") {
-                        println!("⏰ [WHISPER] Found main window, emitting widget-stop-recording event");
-                        match window.emit("widget-stop-recording", ()) {
-                            Ok(_) => println!("⏰ [WHISPER] ✅ Event emitted successfully"),
-                            Err(e) => println!("⏰ [WHISPER] ❌ Failed to emit event: {}", e),
-                        }
-                    } else {
-                        println!("⏰ [WHISPER] ❌ Main window not found!");
-                    }
+/// Re-activate the window that was in front when the recording started, so a click elsewhere
+/// while the transcription was processing doesn't send the paste to the wrong app
+fn restore_paste_target(app: &AppHandle) {
+    let Some(state) = app.try_state::<AppState>() else { return };
+    let Some(target) = state.paste_target.lock().unwrap().take() else { return };
+    if !restore_focus_before_paste(&state.database) {
+        return;
+    }
+    match active_window::activate(target) {
+        Ok(()) => println!("🎯 Restored focus to the recording's window"),
+        Err(e) => println!("⚠️ Couldn't restore focus before pasting ({}), pasting into the current window", e),
+    }
+}
 
-                    // Hide recording widget
-                    if let Some(widget) = app_clone.get_webview_window("recording-widget") {
-                        println!("⏰ [WHISPER] Found recording widget, hiding it");
-                        match widget.hide() {
-                            Ok(_) => println!("⏰ [WHISPER] ✅ Widget hidden successfully"),
-                            Err(e) => println!("⏰ [WHISPER] ❌ Failed to hide widget: {}", e),
-                        }
-                    } else {
-                        println!("⏰ [WHISPER] ❌ Recording widget not found!");
-                    }
+fn restore_focus_before_paste(database: &db::Database) -> bool {
+    database.load_setting("restore_focus_before_paste").ok().flatten().map(|v| v == "true").unwrap_or(true)
+}
 
-                    // DON'T break - let the loop continue until frontend calls stop
-                }
+/// Append text to the markdown note configured in `append_note_path`
+fn append_to_note(app: &AppHandle, text: &str) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let path = state.database.load_setting("append_note_path")
+        .ok()
+        .flatten()
+        .ok_or("Note path is not configured")?;
+    let template = state.database.load_setting("append_note_template")
+        .ok()
+        .flatten()
+        .filter(|t| !t.trim().is_empty())
+        .unwrap_or_else(|| note_writer::DEFAULT_ENTRY_TEMPLATE.to_string());
+    note_writer::append(&path, &template, text)?;
+    Ok(())
+}
+
+/// Post text to the Notion database from the settings (in the background; failures are notified)
+fn send_to_notion(app: &AppHandle, text: &str) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let token = state.database.load_setting("notion_token").ok().flatten()
+        .ok_or("Notion token is not configured")?;
+    let database_id = state.database.load_setting("notion_database_id").ok().flatten()
+        .ok_or("Notion database is not configured")?;
+
+    let app_handle = app.clone();
+    let text = text.to_string();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = notion::create_page(&token, &database_id, &text).await {
+            eprintln!("❌ Failed to send to Notion: {}", e);
+            if let Err(e) = app_handle.notification().builder()
+                .title("Dicta - Notion")
+                .body("Não foi possível enviar para o Notion. O texto está no histórico.")
+                .show()
+            {
+                eprintln!("⚠️ Failed to show Notion notification: {}", e);
             }
         }
     });
+    Ok(())
+}
 
-    Ok("Recording started".to_string())
+#[derive(Clone, serde::Serialize)]
+struct NotionSettings {
+    token: Option<String>,
+    database_id: Option<String>,
 }
 
 #[tauri::command]
-async fn stop_recording_audio(state: State<'_, AppState>, app: tauri::AppHandle) -> Result<String, String> {
-    let mut is_recording = state.is_recording.lock().unwrap();
-    if !*is_recording {
+fn get_notion_settings(state: State<'_, AppState>) -> Result<NotionSettings, String> {
+    Ok(NotionSettings {
+        token: state.database.load_setting("notion_token").ok().flatten(),
+        database_id: state.database.load_setting("notion_database_id").ok().flatten(),
+    })
+}
+
+/// Notion integration token and the id of the database pages are created in
+#[tauri::command]
+fn set_notion_settings(state: State<'_, AppState>, token: Option<String>, database_id: Option<String>) -> Result<(), String> {
+    for (key, value) in [("notion_token", token), ("notion_database_id", database_id)] {
+        let result = match value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty()) {
+            Some(v) => state.database.save_setting(key, &v),
+            None => state.database.delete_setting(key),
+        };
+        result.map_err(|e| format!("Failed to save Notion settings: {}", e))?;
+    }
+    Ok(())
+}
+
+/// Choose where the session being recorded delivers its result ("paste", "note", "notion", "json" or "email")
+#[tauri::command]
+fn set_output_target(state: State<'_, AppState>, target: String) -> Result<(), String> {
+    let target = OutputTarget::from_name(&target).ok_or_else(|| format!("Unknown output target '{}'", target))?;
+    if !*state.is_recording.lock().unwrap() {
         return Err("Not recording".to_string());
     }
+    *state.output_target.lock().unwrap() = target;
+    println!("🎯 Output target for this session: {:?}", target);
+    Ok(())
+}
+
+/// Start a recording whose result goes to `target` instead of being pasted
+#[tauri::command]
+fn start_recording_to(app: AppHandle, state: State<'_, AppState>, target: String) -> Result<(), String> {
+    let target = OutputTarget::from_name(&target).ok_or_else(|| format!("Unknown output target '{}'", target))?;
+    if *state.is_recording.lock().unwrap() {
+        return Err("Already recording".to_string());
+    }
+    toggle_recording_to(&app, RecordingTrigger::Transcribe, target);
+    Ok(())
+}
 
-    println!("⏹️ Stopping audio recording...");
-    let recorder = state.audio_recorder.lock().unwrap();
-    let audio_data = recorder.stop_recording();
-    *is_recording = false;
+#[derive(Clone, serde::Serialize)]
+struct AppendNoteSettings {
+    path: Option<String>,
+    template: String,
+}
 
-    // Restore system audio
-    if let Err(e) = system_audio::unmute_system_audio() {
-        eprintln!("⚠️ Failed to unmute system audio: {}", e);
+#[tauri::command]
+fn get_append_note_settings(state: State<'_, AppState>) -> Result<AppendNoteSettings, String> {
+    Ok(AppendNoteSettings {
+        path: state.database.load_setting("append_note_path").ok().flatten(),
+        template: state.database.load_setting("append_note_template")
+            .ok()
+            .flatten()
+            .unwrap_or_else(|| note_writer::DEFAULT_ENTRY_TEMPLATE.to_string()),
+    })
+}
+
+/// Note file for append mode (supports {date}, {year}, {month}, {day}, {date:FORMAT})
+/// and the line written per dictation (same placeholders plus {time} and {text})
+#[tauri::command]
+fn set_append_note_settings(state: State<'_, AppState>, path: Option<String>, template: Option<String>) -> Result<(), String> {
+    let result = match path.filter(|p| !p.trim().is_empty()) {
+        Some(p) => state.database.save_setting("append_note_path", p.trim()),
+        None => state.database.delete_setting("append_note_path"),
+    };
+    result.map_err(|e| format!("Failed to save note path: {}", e))?;
+    let result = match template.filter(|t| !t.trim().is_empty()) {
+        Some(t) => state.database.save_setting("append_note_template", &t),
+        None => state.database.delete_setting("append_note_template"),
+    };
+    result.map_err(|e| format!("Failed to save note template: {}", e))
+}
+
+#[tauri::command]
+fn get_json_output_settings(state: State<'_, AppState>) -> Result<JsonOutputSettings, String> {
+    Ok(JsonOutputSettings::load(&state.database))
+}
+
+/// Schema, model and destinations (file, webhook) of JSON output mode
+#[tauri::command]
+fn set_json_output_settings(state: State<'_, AppState>, settings: JsonOutputSettings) -> Result<(), String> {
+    settings.parsed_schema()?;
+    settings.save(&state.database)
+        .map_err(|e| format!("Failed to save JSON output settings: {}", e))
+}
+
+#[tauri::command]
+fn get_email_settings(state: State<'_, AppState>) -> Result<EmailSettings, String> {
+    Ok(EmailSettings::load(&state.database))
+}
+
+/// Greeting, sign-off, tone and model of email mode
+#[tauri::command]
+fn set_email_settings(state: State<'_, AppState>, settings: EmailSettings) -> Result<(), String> {
+    settings.save(&state.database)
+        .map_err(|e| format!("Failed to save email settings: {}", e))
+}
+
+/// Plugins folder and the scripts found in it
+#[derive(Clone, serde::Serialize)]
+struct PluginsOverview {
+    dir: String,
+    plugins: Vec<plugins::PluginInfo>,
+}
+
+#[tauri::command]
+fn get_plugins(state: State<'_, AppState>) -> Result<PluginsOverview, String> {
+    Ok(PluginsOverview {
+        dir: state.plugins.dir().display().to_string(),
+        plugins: state.plugins.list(&state.database),
+    })
+}
+
+/// Recompile the scripts after they were added or edited
+#[tauri::command]
+fn reload_plugins(app: AppHandle, state: State<'_, AppState>) -> Result<PluginsOverview, String> {
+    state.plugins.load(&app);
+    get_plugins(state)
+}
+
+#[tauri::command]
+fn set_plugin_enabled(state: State<'_, AppState>, name: String, enabled: bool) -> Result<(), String> {
+    plugins::set_enabled(&state.database, &name, enabled)
+        .map_err(|e| format!("Failed to save plugin setting: {}", e))?;
+    println!("🧩 Plugin '{}' {}", name, if enabled { "enabled" } else { "disabled" });
+    Ok(())
+}
+
+/// Paste the (possibly edited) text from the review window
+#[tauri::command]
+async fn confirm_and_paste(state: State<'_, AppState>, app: AppHandle, text: String) -> Result<(), String> {
+    if let Some(window) = app.get_webview_window("paste-review") {
+        let _ = window.hide();
+    }
+    let entry = state.review_entry.lock().unwrap().take();
+    if text.trim().is_empty() {
+        return Ok(());
     }
 
-    // Capture recording duration for stats
-    let duration_ms = state.recording_start_time.lock().unwrap()
-        .map(|start| start.elapsed().as_millis() as i64);
+    // Paste-last hotkey should repeat what was actually pasted
+    *state.last_transcription.lock().unwrap() = Some(text.clone());
 
-    if audio_data.is_empty() {
-        return Err("No audio recorded".to_string());
+    // auto_paste_text waits before Ctrl+V, which gives focus time to return to the target app
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        let result = auto_paste_text(&app, &text);
+        if result.is_ok() {
+            tag_paste_window(&app, entry);
+        }
+        result
+    })
+    .await
+    .map_err(|e| format!("Paste task failed: {}", e))?;
+    if result.is_ok() {
+        println!("✅ Reviewed text pasted");
     }
+    result
+}
 
-    // Check if we're in prompt mode
-    let prompt_mode = state.prompt_mode.lock().unwrap().clone();
+#[tauri::command]
+fn get_review_before_paste(state: State<'_, AppState>) -> Result<bool, String> {
+    Ok(state.database.load_setting("review_before_paste")
+        .map_err(|e| format!("Failed to load review setting: {}", e))?
+        .map(|v| v == "true")
+        .unwrap_or(false))
+}
 
-    // Load conversation history before spawning (inactivity check happens here)
-    let conv_history = get_conversation_history(&state.database);
+#[tauri::command]
+fn set_review_before_paste(state: State<'_, AppState>, enabled: bool) -> Result<(), String> {
+    state.database.save_setting("review_before_paste", if enabled { "true" } else { "false" })
+        .map_err(|e| format!("Failed to save review setting: {}", e))
+}
 
-    // Transcribe (without post-processing for speed)
-    let openai = state.openai_client.clone();
-    let last_transcription = state.last_transcription.clone();
-    let database = state.database.clone();
-    let app_handle = app.clone();
-    let tts_enabled = state.tts_enabled.clone();
-    let tts_sink = state.tts_sink.clone();
-    let tts_stream_handle = state.tts_stream_handle.clone();
-    let tts_active = state.tts_active.clone();
-    let openai_for_tts = state.openai_client.clone();
-    let queue_dir = state.queue_dir.clone();
-    let audio_data_for_queue = audio_data.clone();
-    tokio::spawn(async move {
-        match openai.transcribe_audio(audio_data, 48000).await {
-            Ok(transcribed_text) => {
-                println!("✨ Transcribed: {}", transcribed_text);
-
-                // Check if we're in prompt mode
-                if let Some(model) = prompt_mode {
-                    println!("🤖 Prompt mode active with model: {}", model);
-
-                    // Send transcribed text as prompt to GPT
-                    match openai.send_prompt(&transcribed_text, &model, &conv_history, None).await {
-                        Ok(gpt_response) => {
-                            println!("✨ GPT Response: {}", gpt_response);
-
-                            // Save GPT response as last transcription
-                            *last_transcription.lock().unwrap() = Some(gpt_response.clone());
-
-                            // Save to database (save the GPT response, not the prompt)
-                            let timestamp = std::time::SystemTime::now()
-                                .duration_since(std::time::UNIX_EPOCH)
-                                .unwrap()
-                                .as_millis() as i64;
-                            let cost = estimate_cost_cents(&model, duration_ms, &gpt_response);
-
-                            if let Err(e) = database.save_transcription(&gpt_response, timestamp, duration_ms, Some(&model), Some(cost), Some("prompt")) {
-                                eprintln!("❌ Failed to save to database: {}", e);
-                            }
+#[tauri::command]
+fn get_restore_focus_before_paste(state: State<'_, AppState>) -> Result<bool, String> {
+    Ok(restore_focus_before_paste(&state.database))
+}
+
+#[tauri::command]
+fn set_restore_focus_before_paste(state: State<'_, AppState>, enabled: bool) -> Result<(), String> {
+    state.database.save_setting("restore_focus_before_paste", if enabled { "true" } else { "false" })
+        .map_err(|e| format!("Failed to save focus restore setting: {}", e))
+}
+
+/// "clipboard" (Ctrl+V, default) or "ui_automation"
+#[tauri::command]
+fn get_insertion_method(state: State<'_, AppState>) -> Result<String, String> {
+    Ok(state.database.load_setting("insertion_method")
+        .map_err(|e| format!("Failed to load insertion method: {}", e))?
+        .unwrap_or_else(|| "clipboard".to_string()))
+}
+
+#[tauri::command]
+fn set_insertion_method(state: State<'_, AppState>, method: String) -> Result<(), String> {
+    if method != "clipboard" && method != "ui_automation" {
+        return Err(format!("Unknown insertion method '{}'", method));
+    }
+    state.database.save_setting("insertion_method", &method)
+        .map_err(|e| format!("Failed to save insertion method: {}", e))
+}
+
+#[tauri::command]
+fn get_clipboard_history_exclusion(state: State<'_, AppState>) -> Result<bool, String> {
+    Ok(state.database.load_setting("clipboard_history_exclusion")
+        .map_err(|e| format!("Failed to load clipboard history setting: {}", e))?
+        .map_or(true, |v| v != "false"))
+}
+
+/// Keep pasted dictations out of clipboard managers and the Windows clipboard history (Win+V)
+#[tauri::command]
+fn set_clipboard_history_exclusion(state: State<'_, AppState>, enabled: bool) -> Result<(), String> {
+    state.database.save_setting("clipboard_history_exclusion", if enabled { "true" } else { "false" })
+        .map_err(|e| format!("Failed to save clipboard history setting: {}", e))
+}
+
+/// Strip markdown and normalize punctuation/whitespace before pasting, per pipeline mode
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+struct PlainTextSettings {
+    transcription: bool,
+    prompt: bool,
+}
+
+#[tauri::command]
+fn get_plain_text_settings(state: State<'_, AppState>) -> Result<PlainTextSettings, String> {
+    let enabled = |key: &str| state.database.load_setting(key).ok().flatten().is_some_and(|v| v == "true");
+    Ok(PlainTextSettings {
+        transcription: enabled("plain_text_transcription"),
+        prompt: enabled("plain_text_prompt"),
+    })
+}
+
+#[tauri::command]
+fn set_plain_text_settings(state: State<'_, AppState>, settings: PlainTextSettings) -> Result<(), String> {
+    for (key, enabled) in [("plain_text_transcription", settings.transcription), ("plain_text_prompt", settings.prompt)] {
+        state.database.save_setting(key, if enabled { "true" } else { "false" })
+            .map_err(|e| format!("Failed to save plain text settings: {}", e))?;
+    }
+    Ok(())
+}
+
+/// Spelled-out numbers in transcriptions: "off", "dates" ("vinte e cinco de março" → "25 de março")
+/// or "all" (every number in digits). Only languages with rules in `numbers` are affected.
+#[tauri::command]
+fn get_number_normalization(state: State<'_, AppState>) -> Result<String, String> {
+    Ok(state.database.load_setting("number_normalization")
+        .map_err(|e| format!("Failed to load number normalization: {}", e))?
+        .unwrap_or_else(|| "off".to_string()))
+}
+
+#[tauri::command]
+fn set_number_normalization(state: State<'_, AppState>, mode: String) -> Result<(), String> {
+    if !["off", "dates", "all"].contains(&mode.as_str()) {
+        return Err(format!("Unknown number normalization '{}'", mode));
+    }
+    state.database.save_setting("number_normalization", &mode)
+        .map_err(|e| format!("Failed to save number normalization: {}", e))
+}
+
+/// Mask profanity in transcriptions before saving and pasting: the built-in list of the
+/// transcript's language plus the user's own words
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+struct ProfanitySettings {
+    enabled: bool,
+    words: Vec<String>,
+}
+
+/// User's words for the profanity filter, one per line in the setting
+fn profanity_custom_words(database: &db::Database) -> Vec<String> {
+    database.load_setting("profanity_words").ok().flatten()
+        .map(|v| v.lines().map(|w| w.trim().to_string()).filter(|w| !w.is_empty()).collect())
+        .unwrap_or_default()
+}
+
+#[tauri::command]
+fn get_profanity_settings(state: State<'_, AppState>) -> Result<ProfanitySettings, String> {
+    let enabled = state.database.load_setting("profanity_filter")
+        .map_err(|e| format!("Failed to load profanity filter: {}", e))?
+        .is_some_and(|v| v == "true");
+    Ok(ProfanitySettings { enabled, words: profanity_custom_words(&state.database) })
+}
+
+#[tauri::command]
+fn set_profanity_settings(state: State<'_, AppState>, settings: ProfanitySettings) -> Result<(), String> {
+    state.database.save_setting("profanity_filter", if settings.enabled { "true" } else { "false" })
+        .map_err(|e| format!("Failed to save profanity filter: {}", e))?;
+    let words: Vec<&str> = settings.words.iter().map(|w| w.trim()).filter(|w| !w.is_empty()).collect();
+    state.database.save_setting("profanity_words", &words.join("\n"))
+        .map_err(|e| format!("Failed to save profanity filter: {}", e))
+}
+
+/// Paste GPT responses as formatted HTML (lists, bold, headings) alongside the plain text
+#[tauri::command]
+fn get_rich_text_paste(state: State<'_, AppState>) -> Result<bool, String> {
+    Ok(state.database.load_setting("rich_text_prompt")
+        .map_err(|e| format!("Failed to load rich text setting: {}", e))?
+        .map(|v| v == "true")
+        .unwrap_or(false))
+}
+
+#[tauri::command]
+fn set_rich_text_paste(state: State<'_, AppState>, enabled: bool) -> Result<(), String> {
+    state.database.save_setting("rich_text_prompt", if enabled { "true" } else { "false" })
+        .map_err(|e| format!("Failed to save rich text setting: {}", e))
+}
+
+/// Paste a test line into whatever is focused 3s from now and report the keyboard layout and
+/// keys used, to troubleshoot Ctrl+V on AZERTY/ABNT/Dvorak setups
+#[tauri::command]
+async fn test_paste_layout(app: AppHandle) -> Result<keyboard::LayoutReport, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        std::thread::sleep(Duration::from_secs(3)); // Time to click into a text field
+        let report = keyboard::layout_report();
+        println!("⌨️ Paste test: layout {}, Ctrl+{} to paste, Ctrl+{} to copy", report.layout, report.paste_vk, report.copy_vk);
+        auto_paste_text(&app, "Dicta: teste de colagem ✓")?;
+        Ok(report)
+    })
+    .await
+    .map_err(|e| format!("Paste test failed: {}", e))?
+}
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+struct SoundCueSettings {
+    enabled: bool,
+    volume: u8, // 0-100
+    /// Custom sound files replacing the built-in beeps
+    start_file: Option<String>,
+    stop_file: Option<String>,
+    cancel_file: Option<String>,
+    paste_file: Option<String>,
+}
+
+fn load_sound_cue_settings(database: &db::Database) -> SoundCueSettings {
+    let load = |key: &str| database.load_setting(key).ok().flatten().filter(|v| !v.is_empty());
+    SoundCueSettings {
+        enabled: load("sound_cues_enabled").map(|v| v == "true").unwrap_or(true),
+        volume: load("sound_cues_volume").and_then(|v| v.parse().ok()).unwrap_or(30).min(100),
+        start_file: load("sound_cue_start_file"),
+        stop_file: load("sound_cue_stop_file"),
+        cancel_file: load("sound_cue_cancel_file"),
+        paste_file: load("sound_cue_paste_file"),
+    }
+}
+
+/// Play a confirmation sound unless cues are disabled or it's quiet time. Returns how long it lasts.
+fn play_sound_cue(app: &AppHandle, cue: sound_cues::Cue) -> Duration {
+    let Some(state) = app.try_state::<AppState>() else { return Duration::ZERO };
+    let settings = load_sound_cue_settings(&state.database);
+    if !settings.enabled || is_quiet_time(app) {
+        return Duration::ZERO;
+    }
+    let file = match cue {
+        sound_cues::Cue::Start => settings.start_file,
+        sound_cues::Cue::Stop => settings.stop_file,
+        sound_cues::Cue::Cancel => settings.cancel_file,
+        sound_cues::Cue::Paste => settings.paste_file,
+    };
+    sound_cues::play(cue, settings.volume as f32 / 100.0, file.map(PathBuf::from))
+}
+
+#[tauri::command]
+fn get_sound_cue_settings(state: State<'_, AppState>) -> Result<SoundCueSettings, String> {
+    Ok(load_sound_cue_settings(&state.database))
+}
+
+#[tauri::command]
+fn set_sound_cue_settings(state: State<'_, AppState>, settings: SoundCueSettings) -> Result<(), String> {
+    let save = |key: &str, value: &str| state.database.save_setting(key, value)
+        .map_err(|e| format!("Failed to save sound cue settings: {}", e));
+    save("sound_cues_enabled", if settings.enabled { "true" } else { "false" })?;
+    save("sound_cues_volume", &settings.volume.min(100).to_string())?;
+    let files = [
+        ("sound_cue_start_file", settings.start_file),
+        ("sound_cue_stop_file", settings.stop_file),
+        ("sound_cue_cancel_file", settings.cancel_file),
+        ("sound_cue_paste_file", settings.paste_file),
+    ];
+    for (key, value) in files {
+        let result = match value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty()) {
+            Some(v) => state.database.save_setting(key, &v),
+            None => state.database.delete_setting(key),
+        };
+        result.map_err(|e| format!("Failed to save sound cue settings: {}", e))?;
+    }
+    Ok(())
+}
+
+struct AppState {
+    audio_recorder: Arc<Mutex<audio::AudioRecorder>>,
+    clip_meter: Arc<audio::ClipMeter>, // Clipping in the current recording (both modes)
+    session_stats: Arc<audio::SessionStats>, // Capture diagnostics of the current recording (both modes)
+    network: Arc<network::NetworkMonitor>,
+    session_id: Arc<Mutex<Option<SessionId>>>, // Session of the recording in progress (or last stopped)
+    openai_client: Arc<openai::OpenAIClient>,
+    delivery_order: Arc<DeliveryOrder>, // Pastes/TTS of concurrent sessions in the order they were submitted
+    in_flight: Arc<Mutex<Option<InFlightRequest>>>,
+    realtime_client: Arc<realtime::RealtimeClient>,
+    database: Arc<db::Database>,
+    is_recording: Arc<Mutex<bool>>,
+    use_realtime: Arc<Mutex<bool>>, // Track which API to use
+    prompt_mode: Arc<Mutex<Option<String>>>, // Track prompt mode: None, Some("gpt-4o-mini"), or Some("gpt-4o")
+    current_session_transcript: Arc<Mutex<String>>, // Accumulate transcript for current session
+    session_segments: Arc<Mutex<Vec<realtime::TranscriptSegment>>>, // Completed turns of the current live session
+    last_transcription: Arc<Mutex<Option<String>>>,
+    failed_paste: Arc<Mutex<Option<String>>>, // Text of the last auto-paste that didn't land, for retry_last_paste
+    paste_target: Arc<Mutex<Option<isize>>>, // Foreground window when the recording started (see restore_paste_target)
+    pending_paste: Arc<Mutex<Option<PendingPaste>>>, // Result waiting for a click (see paste_on_next_click)
+    review_entry: Arc<Mutex<Option<i64>>>, // History entry of the text in the review window
+    paste_in_progress: Arc<Mutex<bool>>,
+    recording_start_time: Arc<Mutex<Option<Instant>>>,
+    mic_mute: Arc<Mutex<MicMute>>, // Mic mute of the current live session
+    speech_active: Arc<Mutex<bool>>, // Track if speech is currently being detected
+    last_speech_end: Arc<Mutex<Option<Instant>>>, // Track when last speech ended
+    last_transcription_time: Arc<Mutex<Option<Instant>>>, // Track when last transcription.completed arrived
+    tts_enabled: Arc<Mutex<bool>>,
+    tts_sink: Arc<Mutex<Option<rodio::Sink>>>,
+    tts_stream_handle: Arc<Mutex<Option<rodio::OutputStreamHandle>>>,
+    tts_active: Arc<Mutex<bool>>,
+    tts_queue: Arc<Mutex<VecDeque<(TtsText, TtsVoice)>>>, // Texts waiting for the current TTS playback, with their voice
+    tts_skip: Arc<Mutex<bool>>,              // Skip request for the text being read
+    assistant_active: Arc<Mutex<bool>>,      // Hands-free assistant loop (Alt+Shift+V)
+    queue_dir: PathBuf,
+    archive_dir: PathBuf, // Archived session audio (`archive_session_audio`)
+    tts_cache_dir: PathBuf,
+    streaming_stop_handle: Arc<Mutex<Option<audio::StreamingStopHandle>>>,
+    shortcuts: shortcuts::ShortcutRegistry, // Registered global hotkeys and their actions
+    ptt_sender: Arc<Mutex<Option<std::sync::mpsc::Sender<input_listener::PttEvent>>>>, // Mouse/pedal push-to-talk events
+    chunked_session: Arc<Mutex<Option<chunked::ChunkedSession>>>, // Chunked Whisper mode in-flight chunks
+    local_whisper: Arc<Mutex<Option<Arc<local_whisper::LocalWhisper>>>>, // Loaded whisper.cpp model (offline backend)
+    live_session_local: Arc<Mutex<bool>>, // Current live session is transcribed by whisper.cpp (no API cost)
+    realtime_session: Arc<Mutex<Option<Arc<realtime::RealtimeSession>>>>, // Left by the last realtime recording for native prompt mode
+    idle_realtime_session: Arc<Mutex<Option<Arc<realtime::RealtimeSession>>>>, // Kept open after a recording for the next one to reuse
+    meeting: Arc<Mutex<Option<meeting::MeetingHandle>>>, // Meeting mode recording in progress
+    api_server: Arc<Mutex<Option<api_server::ApiServerHandle>>>, // Local REST API for integrations
+    output_target: Arc<Mutex<OutputTarget>>, // Where the current session's result goes (consumed on delivery)
+    screen_context: Arc<Mutex<Option<String>>>, // Screenshot (base64 PNG) sent with the current session's prompt (Alt+Shift+Q)
+    selection_context: Arc<Mutex<Option<String>>>, // Selected text the current session's instruction applies to (Alt+Shift+E)
+    dictation_style: Arc<Mutex<Option<String>>>, // Formatting the target app's rule applies to this session's transcript
+    plugins: Arc<plugins::PluginHost>, // User Rhai scripts hooked into the pipeline
+    last_json_output: Arc<Mutex<Option<(i64, serde_json::Value)>>>, // Latest JSON output mode result and when it was produced (ms)
+}
+
+/// Mic mute of the live session (Alt+Shift+K): captured frames are dropped before they're sent,
+/// while the session itself stays open
+#[derive(Default)]
+struct MicMute {
+    since: Option<Instant>, // Muted from this moment; None while capturing
+    total: Duration,        // Earlier muted stretches of the session
+}
+
+impl MicMute {
+    fn is_muted(&self) -> bool {
+        self.since.is_some()
+    }
+
+    fn set(&mut self, muted: bool) {
+        match (muted, self.since) {
+            (true, None) => self.since = Some(Instant::now()),
+            (false, Some(since)) => {
+                self.total += since.elapsed();
+                self.since = None;
+            }
+            _ => {}
+        }
+    }
+
+    /// Muted time so far, left out of the session's duration (and so its cost estimate)
+    fn muted_time(&self) -> Duration {
+        self.total + self.since.map_or(Duration::ZERO, |since| since.elapsed())
+    }
+}
+
+/// Destination of a session's output; set when the recording starts or while it runs
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum OutputTarget {
+    Paste,
+    /// Append to the configured markdown note (Alt+Shift+N)
+    Note,
+    /// Create a page in the configured Notion database
+    Notion,
+    /// Turn the dictation into JSON for automation (Alt+Shift+J)
+    Json,
+    /// Have the dictation written out as an email and paste it (Alt+Shift+W)
+    Email,
+    /// Assistant mode turn: the response is only read aloud
+    Assistant,
+}
+
+impl OutputTarget {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "paste" => Some(Self::Paste),
+            "note" => Some(Self::Note),
+            "notion" => Some(Self::Notion),
+            "json" => Some(Self::Json),
+            "email" => Some(Self::Email),
+            _ => None,
+        }
+    }
+}
+
+/// Strip markdown links, citations, and raw URLs from text for TTS playback.
+fn strip_links_for_tts(text: &str) -> String {
+    // 1. Markdown links [text](url) → text
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '[' {
+            let mut link_text = String::new();
+            let mut found_close = false;
+            for inner in chars.by_ref() {
+                if inner == ']' { found_close = true; break; }
+                link_text.push(inner);
+            }
+            if found_close && chars.peek() == Some(&'(') {
+                chars.next(); // skip '('
+                let mut depth = 1;
+                for inner in chars.by_ref() {
+                    if inner == '(' { depth += 1; }
+                    if inner == ')' { depth -= 1; if depth == 0 { break; } }
+                }
+                // Only keep link text if it doesn't look like a domain
+                if !looks_like_url(&link_text) {
+                    result.push_str(&link_text);
+                }
+            } else {
+                result.push('[');
+                result.push_str(&link_text);
+                if found_close { result.push(']'); }
+            }
+        } else {
+            result.push(c);
+        }
+    }
+    // 2. Remove parenthesized content that looks like URLs/domains: (domain.com), (https://...)
+    let mut clean = String::with_capacity(result.len());
+    let mut i = 0;
+    while i < result.len() {
+        if result.as_bytes()[i] == b'(' {
+            let start = i + 1;
+            if let Some(end) = result[start..].find(')') {
+                let inner = result[start..start + end].trim();
+                if looks_like_url(inner) {
+                    i = start + end + 1;
+                    continue;
+                }
+            }
+        }
+        clean.push(result.as_bytes()[i] as char);
+        i += 1;
+    }
+    // 3. Remove remaining raw URLs
+    clean.split_whitespace()
+        .filter(|w| !looks_like_url(w))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Replace lexicon words (whole words, case-insensitive) with their spoken form
+fn apply_tts_lexicon(text: &str, lexicon: &[db::LexiconEntry]) -> String {
+    let mut result = text.to_string();
+    for entry in lexicon {
+        if !entry.word.trim().is_empty() {
+            result = replace_whole_word(&result, entry.word.trim(), &entry.replacement);
+        }
+    }
+    result
+}
+
+fn replace_whole_word(text: &str, word: &str, replacement: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let pattern: Vec<char> = word.chars().collect();
+    let mut out = String::with_capacity(text.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let end = i + pattern.len();
+        let matches = end <= chars.len()
+            && (i == 0 || !chars[i - 1].is_alphanumeric())
+            && (end == chars.len() || !chars[end].is_alphanumeric())
+            && chars[i..end].iter().zip(&pattern).all(|(a, b)| a.to_lowercase().eq(b.to_lowercase()));
+        if matches {
+            out.push_str(replacement);
+            i = end;
+        } else {
+            out.push(chars[i]);
+            i += 1;
+        }
+    }
+    out
+}
+
+/// Check if a string looks like a URL or domain name
+fn looks_like_url(s: &str) -> bool {
+    let s = s.trim_matches(|c: char| c == '(' || c == ')' || c == '[' || c == ']');
+    if s.starts_with("http://") || s.starts_with("https://") || s.starts_with("www.") {
+        return true;
+    }
+    // Match domain patterns like "site.com", "site.com.br", "site.org/path"
+    let tlds = [".com", ".org", ".net", ".br", ".io", ".dev", ".gov", ".edu", ".info", ".co"];
+    tlds.iter().any(|tld| s.contains(tld))
+}
+
+/// TTS for one chunk, served from the on-disk cache when the same text was spoken before
+async fn speak_text_cached(app: &AppHandle, openai: &openai::OpenAIClient, text: &str, voice: &TtsVoice) -> Result<Vec<u8>, String> {
+    let state = app.state::<AppState>();
+    let speed = voice.speed.unwrap_or_else(|| tts_speed(&state.database));
+    // Speed changes the audio, so it's part of the cache key (1.0 keeps the old keys)
+    let voice_key = if speed == DEFAULT_TTS_SPEED { voice.voice.clone() } else { format!("{}@{}", voice.voice, speed) };
+    if let Some(audio) = tts_cache::load(&state.tts_cache_dir, text, &voice_key, openai::TTS_MODEL) {
+        return Ok(audio);
+    }
+
+    let audio = openai.speak_text(text, &voice.voice, speed).await?;
+    let max_mb = state.database.load_setting("tts_cache_max_mb")
+        .ok()
+        .flatten()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(tts_cache::DEFAULT_MAX_CACHE_MB);
+    if max_mb > 0 {
+        tts_cache::store(&state.tts_cache_dir, text, &voice_key, openai::TTS_MODEL, &audio, max_mb * 1024 * 1024);
+    }
+    Ok(audio)
+}
+
+#[tauri::command]
+fn get_tts_lexicon(state: State<'_, AppState>) -> Result<Vec<db::LexiconEntry>, String> {
+    state.database.load_lexicon()
+        .map_err(|e| format!("Failed to load lexicon: {}", e))
+}
+
+/// Add or update a pronunciation: `word` will be spoken as `replacement`
+#[tauri::command]
+fn save_tts_lexicon_entry(state: State<'_, AppState>, word: String, replacement: String) -> Result<(), String> {
+    let word = word.trim();
+    if word.is_empty() {
+        return Err("Word is empty".to_string());
+    }
+    state.database.save_lexicon_entry(word, replacement.trim())
+        .map_err(|e| format!("Failed to save lexicon entry: {}", e))
+}
+
+#[tauri::command]
+fn delete_tts_lexicon_entry(state: State<'_, AppState>, id: i64) -> Result<(), String> {
+    state.database.delete_lexicon_entry(id)
+        .map_err(|e| format!("Failed to delete lexicon entry: {}", e))
+}
+
+#[tauri::command]
+fn get_app_rules(state: State<'_, AppState>) -> Result<Vec<db::AppRule>, String> {
+    state.database.load_app_rules()
+        .map_err(|e| format!("Failed to load app rules: {}", e))
+}
+
+/// Set what Ctrl+Space does in `app_name` (e.g. "code", "winword"): `model` is a prompt model or
+/// "transcribe-only", `style` an optional formatting for dictations ("formal")
+#[tauri::command]
+fn save_app_rule(state: State<'_, AppState>, app_name: String, model: String, style: Option<String>) -> Result<(), String> {
+    let app_name = app_name.trim().trim_end_matches(".exe").to_lowercase();
+    if app_name.is_empty() {
+        return Err("App name is empty".to_string());
+    }
+    let style = style.as_deref().map(str::trim).filter(|s| !s.is_empty());
+    state.database.save_app_rule(&app_name, model.trim(), style)
+        .map_err(|e| format!("Failed to save app rule: {}", e))
+}
+
+#[tauri::command]
+fn delete_app_rule(state: State<'_, AppState>, id: i64) -> Result<(), String> {
+    state.database.delete_app_rule(id)
+        .map_err(|e| format!("Failed to delete app rule: {}", e))
+}
+
+#[tauri::command]
+fn get_voice_command_settings(state: State<'_, AppState>) -> Result<voice_commands::VoiceCommandSettings, String> {
+    Ok(voice_commands::VoiceCommandSettings::load(&state.database))
+}
+
+#[tauri::command]
+fn set_voice_command_settings(state: State<'_, AppState>, settings: voice_commands::VoiceCommandSettings) -> Result<(), String> {
+    settings.save(&state.database)
+        .map_err(|e| format!("Failed to save voice command settings: {}", e))
+}
+
+#[tauri::command]
+fn get_voice_intents(state: State<'_, AppState>) -> Result<Vec<db::VoiceIntent>, String> {
+    state.database.load_voice_intents()
+        .map_err(|e| format!("Failed to load voice intents: {}", e))
+}
+
+/// Add or update the intent for `phrase` (action: open_app, type_snippet or shell)
+#[tauri::command]
+fn save_voice_intent(state: State<'_, AppState>, phrase: String, action: String, value: String) -> Result<(), String> {
+    let phrase = phrase.trim();
+    if phrase.is_empty() {
+        return Err("Phrase is empty".to_string());
+    }
+    voice_commands::IntentAction::parse(&action)?;
+    state.database.save_voice_intent(phrase, &action, value.trim())
+        .map_err(|e| format!("Failed to save voice intent: {}", e))
+}
+
+#[tauri::command]
+fn delete_voice_intent(state: State<'_, AppState>, id: i64) -> Result<(), String> {
+    state.database.delete_voice_intent(id)
+        .map_err(|e| format!("Failed to delete voice intent: {}", e))
+}
+
+const DEFAULT_TTS_SPEED: f32 = 1.0;
+const TTS_SPEED_STEP: f32 = 0.25; // Per press of the speed hotkeys
+
+/// Speech speed for every TTS request (OpenAI `speed`, 1.0 = normal)
+fn tts_speed(database: &db::Database) -> f32 {
+    database.load_setting("tts_speed")
+        .ok()
+        .flatten()
+        .and_then(|v| v.parse::<f32>().ok())
+        .map(|v| v.clamp(openai::TTS_SPEED_RANGE.0, openai::TTS_SPEED_RANGE.1))
+        .unwrap_or(DEFAULT_TTS_SPEED)
+}
+
+/// Save the speech speed (clamped to what the API accepts) and tell the main window
+fn apply_tts_speed(app: &AppHandle, database: &db::Database, speed: f32) -> Result<f32, String> {
+    // Round to the hotkey step so repeated presses don't accumulate float noise
+    let speed = ((speed / TTS_SPEED_STEP).round() * TTS_SPEED_STEP).clamp(openai::TTS_SPEED_RANGE.0, openai::TTS_SPEED_RANGE.1);
+    database.save_setting("tts_speed", &speed.to_string())
+        .map_err(|e| format!("Failed to save TTS speed: {}", e))?;
+    println!("🔊 TTS speed set to {:.2}x", speed);
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = emit_app_event(&window, AppEvent::TtsSpeedChanged(speed));
+    }
+    Ok(speed)
+}
+
+#[tauri::command]
+fn get_tts_speed(state: State<'_, AppState>) -> Result<f32, String> {
+    Ok(tts_speed(&state.database))
+}
+
+#[tauri::command]
+fn set_tts_speed(app: AppHandle, state: State<'_, AppState>, speed: f32) -> Result<f32, String> {
+    apply_tts_speed(&app, &state.database, speed)
+}
+
+/// Speed hotkeys: one step faster (`faster`) or slower, applied from the next TTS chunk on
+fn step_tts_speed(app: &AppHandle, faster: bool) {
+    let Some(state) = app.try_state::<AppState>() else { return };
+    let step = if faster { TTS_SPEED_STEP } else { -TTS_SPEED_STEP };
+    if let Err(e) = apply_tts_speed(app, &state.database, tts_speed(&state.database) + step) {
+        eprintln!("⚠️ {}", e);
+    }
+}
+
+/// Voice one text is read with: the default voice or the one bound to a prompt model
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+struct TtsVoice {
+    voice: String,
+    /// Fixed speed for this voice; None follows the global speed (and its hotkeys)
+    #[serde(default)]
+    speed: Option<f32>,
+}
+
+/// Voice used when no prompt model binding applies (`tts_voice` setting)
+fn tts_default_voice(database: &db::Database) -> String {
+    database.load_setting("tts_voice")
+        .ok()
+        .flatten()
+        .filter(|v| openai::TTS_VOICES.contains(&v.as_str()))
+        .unwrap_or_else(|| openai::TTS_VOICE.to_string())
+}
+
+/// Voices bound to prompt models (`tts_model_voices` setting, JSON keyed by model)
+fn tts_model_voices(database: &db::Database) -> HashMap<String, TtsVoice> {
+    database.load_setting("tts_model_voices")
+        .ok()
+        .flatten()
+        .and_then(|v| serde_json::from_str(&v).ok())
+        .unwrap_or_default()
+}
+
+/// Voice for text produced by `model` (None = not a prompt response)
+fn tts_voice_for(database: &db::Database, model: Option<&str>) -> TtsVoice {
+    model
+        .and_then(|model| tts_model_voices(database).remove(model))
+        .filter(|bound| openai::TTS_VOICES.contains(&bound.voice.as_str()))
+        .unwrap_or_else(|| TtsVoice { voice: tts_default_voice(database), speed: None })
+}
+
+#[tauri::command]
+fn get_tts_voices() -> Vec<&'static str> {
+    openai::TTS_VOICES.to_vec()
+}
+
+#[tauri::command]
+fn get_tts_voice(state: State<'_, AppState>) -> Result<String, String> {
+    Ok(tts_default_voice(&state.database))
+}
+
+#[tauri::command]
+fn set_tts_voice(state: State<'_, AppState>, voice: String) -> Result<(), String> {
+    if !openai::TTS_VOICES.contains(&voice.as_str()) {
+        return Err(format!("Unknown TTS voice: {}", voice));
+    }
+    state.database.save_setting("tts_voice", &voice)
+        .map_err(|e| format!("Failed to save TTS voice: {}", e))
+}
+
+#[tauri::command]
+fn get_tts_model_voices(state: State<'_, AppState>) -> Result<HashMap<String, TtsVoice>, String> {
+    Ok(tts_model_voices(&state.database))
+}
+
+/// Bind a voice (and optionally a fixed speed) to a prompt model; `voice: None` removes the binding
+#[tauri::command]
+fn set_tts_model_voice(state: State<'_, AppState>, model: String, voice: Option<String>, speed: Option<f32>) -> Result<(), String> {
+    let mut voices = tts_model_voices(&state.database);
+    match voice {
+        Some(voice) => {
+            if !openai::TTS_VOICES.contains(&voice.as_str()) {
+                return Err(format!("Unknown TTS voice: {}", voice));
+            }
+            let speed = speed.map(|v| v.clamp(openai::TTS_SPEED_RANGE.0, openai::TTS_SPEED_RANGE.1));
+            voices.insert(model, TtsVoice { voice, speed });
+        }
+        None => {
+            voices.remove(&model);
+        }
+    }
+    let json = serde_json::to_string(&voices)
+        .map_err(|e| format!("Failed to serialize TTS voices: {}", e))?;
+    state.database.save_setting("tts_model_voices", &json)
+        .map_err(|e| format!("Failed to save TTS voices: {}", e))
+}
+
+const DEFAULT_TTS_DUCK_LEVEL: u32 = 30;
+
+/// Volume (percent of current) other apps are lowered to during TTS; 100 = no ducking
+fn tts_duck_level(app: &AppHandle) -> u32 {
+    app.state::<AppState>().database.load_setting("tts_duck_level")
+        .ok()
+        .flatten()
+        .and_then(|v| v.parse::<u32>().ok())
+        .map(|v| v.min(100))
+        .unwrap_or(DEFAULT_TTS_DUCK_LEVEL)
+}
+
+#[tauri::command]
+fn get_tts_duck_level(app: AppHandle) -> Result<u32, String> {
+    Ok(tts_duck_level(&app))
+}
+
+/// Set how far other apps are lowered during TTS (percent, 100 disables ducking)
+#[tauri::command]
+fn set_tts_duck_level(state: State<'_, AppState>, level: u32) -> Result<(), String> {
+    state.database.save_setting("tts_duck_level", &level.min(100).to_string())
+        .map_err(|e| format!("Failed to save TTS duck level: {}", e))
+}
+
+/// Which session results are read aloud automatically when TTS is on
+#[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize)]
+struct TtsAutoPlay {
+    transcriptions: bool,    // Plain dictations (off by default: it would just repeat the user)
+    prompt_responses: bool,
+    min_chars: usize,        // Shorter results stay silent (0 = speak everything)
+}
+
+impl TtsAutoPlay {
+    fn load(database: &db::Database) -> Self {
+        let flag = |key: &str, default: bool| match database.load_setting(key).ok().flatten().as_deref() {
+            Some(v) => v == "true",
+            None => default,
+        };
+        Self {
+            transcriptions: flag("tts_speak_transcriptions", false),
+            prompt_responses: flag("tts_speak_responses", true),
+            min_chars: database.load_setting("tts_min_chars").ok().flatten()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0),
+        }
+    }
+
+    fn save(&self, database: &db::Database) -> rusqlite::Result<()> {
+        database.save_setting("tts_speak_transcriptions", if self.transcriptions { "true" } else { "false" })?;
+        database.save_setting("tts_speak_responses", if self.prompt_responses { "true" } else { "false" })?;
+        database.save_setting("tts_min_chars", &self.min_chars.to_string())
+    }
+
+    fn allows(&self, kind: DeliveryKind, text: &str) -> bool {
+        let enabled = match kind {
+            DeliveryKind::Transcription => self.transcriptions,
+            DeliveryKind::Prompt => self.prompt_responses,
+        };
+        enabled && text.trim().chars().count() >= self.min_chars
+    }
+}
+
+#[tauri::command]
+fn get_tts_auto_play(state: State<'_, AppState>) -> Result<TtsAutoPlay, String> {
+    Ok(TtsAutoPlay::load(&state.database))
+}
+
+#[tauri::command]
+fn set_tts_auto_play(state: State<'_, AppState>, settings: TtsAutoPlay) -> Result<(), String> {
+    settings.save(&state.database)
+        .map_err(|e| format!("Failed to save TTS auto-play settings: {}", e))
+}
+
+/// What one TTS playback reads
+enum TtsText {
+    Full(String),
+    /// A response still streaming: the text received so far, then the rest as it's generated
+    Streaming(String, tokio::sync::mpsc::UnboundedReceiver<String>),
+}
+
+/// Speak arbitrary text through the chunked TTS pipeline in the default voice
+fn speak_aloud(app: &AppHandle, state: &AppState, text: String) {
+    let voice = tts_voice_for(&state.database, None);
+    speak_aloud_as(app, state, TtsText::Full(text), voice);
+}
+
+/// Speak `text` in `voice`. With the TTS queue on, text arriving while something is
+/// being read waits for it instead of playing over it.
+fn speak_aloud_as(app: &AppHandle, state: &AppState, text: TtsText, voice: TtsVoice) {
+    if tts_queue_enabled(&state.database) {
+        let mut queue = state.tts_queue.lock().unwrap();
+        let mut active = state.tts_active.lock().unwrap();
+        if *active {
+            queue.push_back((text, voice));
+            println!("🔊 TTS busy, text queued ({} waiting)", queue.len());
+            return;
+        }
+        *active = true; // Claimed now so a second text right behind this one gets queued
+    }
+    tauri::async_runtime::spawn(play_tts_chunked(
+        app.clone(),
+        text,
+        voice,
+        state.openai_client.clone(),
+        state.tts_sink.clone(),
+        state.tts_stream_handle.clone(),
+        state.tts_active.clone(),
+        state.tts_queue.clone(),
+        state.tts_skip.clone(),
+    ));
+}
+
+/// Read a response aloud while it streams: text sent to the returned channel is spoken as it
+/// arrives, once `min_chars` have come in (a response ending shorter stays silent)
+fn speak_streamed(app: &AppHandle, voice: TtsVoice, min_chars: usize) -> tokio::sync::mpsc::UnboundedSender<String> {
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<String>();
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let mut received = String::new();
+        loop {
+            let Some(text) = rx.recv().await else { return };
+            if !received.is_empty() {
+                received.push(' ');
+            }
+            received.push_str(&text);
+            if received.chars().count() >= min_chars {
+                break;
+            }
+        }
+        println!("🔊 Speaking the response while it streams");
+        let state = app.state::<AppState>();
+        speak_aloud_as(&app, &state, TtsText::Streaming(received, rx), voice);
+    });
+    tx
+}
+
+/// Whether prompt responses are read while they stream (`tts_stream_responses`, on by default)
+fn tts_streaming_enabled(database: &db::Database) -> bool {
+    database.load_setting("tts_stream_responses").ok().flatten().as_deref() != Some("false")
+}
+
+#[tauri::command]
+fn get_tts_stream_responses(state: State<'_, AppState>) -> Result<bool, String> {
+    Ok(tts_streaming_enabled(&state.database))
+}
+
+#[tauri::command]
+fn set_tts_stream_responses(state: State<'_, AppState>, enabled: bool) -> Result<(), String> {
+    state.database.save_setting("tts_stream_responses", if enabled { "true" } else { "false" })
+        .map_err(|e| format!("Failed to save TTS streaming setting: {}", e))
+}
+
+fn tts_queue_enabled(database: &db::Database) -> bool {
+    database.load_setting("tts_queue").ok().flatten().as_deref() == Some("true")
+}
+
+#[tauri::command]
+fn get_tts_queue(state: State<'_, AppState>) -> Result<bool, String> {
+    Ok(tts_queue_enabled(&state.database))
+}
+
+/// Read back-to-back texts one after the other instead of overlapping them
+#[tauri::command]
+fn set_tts_queue(state: State<'_, AppState>, enabled: bool) -> Result<(), String> {
+    state.database.save_setting("tts_queue", if enabled { "true" } else { "false" })
+        .map_err(|e| format!("Failed to save TTS queue setting: {}", e))
+}
+
+/// Stop reading the current text and go on with the next queued one
+#[tauri::command]
+fn skip_current_tts(state: State<'_, AppState>) -> Result<(), String> {
+    if !*state.tts_active.lock().unwrap() {
+        return Err("Nada sendo lido".to_string());
+    }
+    *state.tts_skip.lock().unwrap() = true;
+    Ok(())
+}
+
+/// Read the clipboard aloud, or stop playback if TTS is already speaking (like Alt+Shift+S)
+fn read_clipboard_aloud_inner(app: &AppHandle) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    if *state.tts_active.lock().unwrap() {
+        stop_tts(&state, app);
+        println!("🔇 TTS playback stopped");
+        return Ok(());
+    }
+    let text = app.clipboard().read_text()
+        .map_err(|e| format!("Failed to read clipboard: {}", e))?;
+    if text.trim().is_empty() {
+        return Err("Clipboard is empty".to_string());
+    }
+    let preview: String = text.chars().take(50).collect();
+    println!("🔊 Reading clipboard via TTS: {}...", preview);
+    speak_aloud(app, &state, text);
+    Ok(())
+}
+
+/// Copy the selection of the focused app and read it aloud, or stop playback if TTS is speaking
+fn speak_selection_inner(app: &AppHandle) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    if *state.tts_active.lock().unwrap() {
+        stop_tts(&state, app);
+        println!("🔇 TTS playback stopped");
+        return Ok(());
+    }
+    let text = copy_selected_text(app)?;
+    let preview: String = text.chars().take(50).collect();
+    println!("🔊 Reading selection via TTS: {}...", preview);
+    speak_aloud(app, &state, text);
+    Ok(())
+}
+
+/// Read the text selected in any app aloud (lightweight screen reader)
+#[tauri::command]
+async fn speak_selection(app: AppHandle) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || speak_selection_inner(&app))
+        .await
+        .map_err(|e| format!("Speak selection task failed: {}", e))?
+}
+
+const FIX_GRAMMAR_PROMPT: &str = "Correct the grammar, spelling and punctuation of the text below. Keep its meaning, \
+language, tone and formatting; do not rephrase parts that are already correct. Respond with ONLY the corrected text.";
+
+/// Copy the selection of the focused app, correct it via GPT and paste the result over it
+async fn fix_selection_grammar_inner(app: AppHandle) -> Result<(), String> {
+    let copy_app = app.clone();
+    let selection = tauri::async_runtime::spawn_blocking(move || copy_selected_text(&copy_app))
+        .await
+        .map_err(|e| format!("Copy selection task failed: {}", e))??;
+
+    let state = app.state::<AppState>();
+    let prompt = format!("{}\n\n{}", FIX_GRAMMAR_PROMPT, selection);
+    let corrected = state.openai_client.send_prompt(&prompt, "gpt-4o-mini", &[], None).await?;
+    println!("✍️ Grammar fixed ({} -> {} chars)", selection.len(), corrected.len());
+
+    *state.last_transcription.lock().unwrap() = Some(corrected.clone());
+    // The selection is still active in the target app, so pasting replaces it
+    auto_paste_text(&app, &corrected)
+}
+
+/// Correct the grammar of the text selected in any app, in place
+#[tauri::command]
+async fn fix_selection_grammar(app: AppHandle) -> Result<(), String> {
+    fix_selection_grammar_inner(app).await
+}
+
+/// Read the current clipboard text aloud (e.g. to proofread something written by hand)
+#[tauri::command]
+fn read_clipboard_aloud(app: AppHandle) -> Result<(), String> {
+    read_clipboard_aloud_inner(&app)
+}
+
+#[derive(Clone, serde::Serialize)]
+struct TtsCacheInfo {
+    size_bytes: u64,
+    max_mb: u64, // 0 = caching disabled
+}
+
+#[tauri::command]
+fn get_tts_cache_info(state: State<'_, AppState>) -> Result<TtsCacheInfo, String> {
+    let max_mb = state.database.load_setting("tts_cache_max_mb")
+        .map_err(|e| format!("Failed to load TTS cache limit: {}", e))?
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(tts_cache::DEFAULT_MAX_CACHE_MB);
+    Ok(TtsCacheInfo { size_bytes: tts_cache::size(&state.tts_cache_dir), max_mb })
+}
+
+/// Set the TTS cache size limit in MB (0 disables caching)
+#[tauri::command]
+fn set_tts_cache_max_mb(state: State<'_, AppState>, max_mb: u64) -> Result<(), String> {
+    state.database.save_setting("tts_cache_max_mb", &max_mb.to_string())
+        .map_err(|e| format!("Failed to save TTS cache limit: {}", e))
+}
+
+#[tauri::command]
+fn clear_tts_cache(state: State<'_, AppState>) -> Result<(), String> {
+    tts_cache::clear(&state.tts_cache_dir)
+}
+
+/// Play TTS audio in chunks (sentence by sentence) with visual widget feedback.
+/// Each chunk is generated and played sequentially so audio starts fast.
+/// Texts added to `tts_queue` meanwhile (TTS queue setting) are read after this one.
+/// Can be cancelled by setting tts_active to false; `tts_skip` drops the text being heard.
+async fn play_tts_chunked(
+    app: AppHandle,
+    text: TtsText,
+    voice: TtsVoice,
+    openai: Arc<openai::OpenAIClient>,
+    tts_sink: Arc<Mutex<Option<rodio::Sink>>>,
+    _tts_stream_handle: Arc<Mutex<Option<rodio::OutputStreamHandle>>>,
+    tts_active: Arc<Mutex<bool>>,
+    tts_queue: Arc<Mutex<VecDeque<(TtsText, TtsVoice)>>>,
+    tts_skip: Arc<Mutex<bool>>,
+) {
+    // Set active flag
+    *tts_active.lock().unwrap() = true;
+    *tts_skip.lock().unwrap() = false;
+
+    // Assistant mode: talking over the answer stops it, and the next turn starts right away
+    if assistant_active(&app) {
+        let database = app.state::<AppState>().database.clone();
+        let device = resolve_input_device(&app, &database, preferred_microphone(&database));
+        let app_for_barge_in = app.clone();
+        assistant::watch_for_barge_in(device, tts_active.clone(), move || {
+            let state = app_for_barge_in.state::<AppState>();
+            stop_tts(&state, &app_for_barge_in);
+        });
+    }
+
+    // Lower other apps (music, videos) so the response is audible
+    let duck_level = tts_duck_level(&app);
+    if duck_level < 100 {
+        if let Err(e) = system_audio::duck_system_audio(system_audio::DuckOwner::Tts, duck_level as f32 / 100.0) {
+            eprintln!("⚠️ Failed to duck system audio: {}", e);
+        }
+    }
+
+    // Show TTS widget
+    if let Some(w) = app.get_webview_window("tts-widget") {
+        if let Ok(Some(monitor)) = app.primary_monitor() {
+            let screen = monitor.size();
+            let x = (screen.width as i32 - 100) / 2;
+            let y = screen.height as i32 - 32 - 120;
+            let _ = w.set_position(PhysicalPosition::new(x, y));
+        }
+        let _ = w.show();
+    }
+
+    let mut next = (text, voice);
+    loop {
+        play_tts_texts(&app, next, &openai, &tts_active, &tts_queue, &tts_skip).await;
+
+        // A text queued while the last chunk was still playing starts a new round. Otherwise
+        // go idle under the queue lock, so speak_aloud either queued before this or starts anew.
+        let queued = {
+            let mut queue = tts_queue.lock().unwrap();
+            let queued = queue.pop_front().filter(|_| *tts_active.lock().unwrap());
+            if queued.is_none() {
+                queue.clear();
+                *tts_active.lock().unwrap() = false;
+            }
+            queued
+        };
+        match queued {
+            Some(queued) => next = queued,
+            None => break,
+        }
+    }
+
+    // Clean up
+    if let Err(e) = system_audio::restore_ducked_audio(system_audio::DuckOwner::Tts) {
+        eprintln!("⚠️ Failed to restore ducked audio: {}", e);
+    }
+    if let Some(w) = app.get_webview_window("tts-widget") {
+        let _ = w.hide();
+    }
+    println!("🔊 TTS chunked playback finished");
+
+    // Assistant mode: listen for the follow-up
+    if assistant_active(&app) {
+        start_assistant_turn(&app);
+    }
+}
+
+/// Generate and play `text` in its voice, then whatever is queued while it's being read, through one sink.
+/// Texts are numbered in reading order so a skip can drop the chunks of the one being heard.
+/// A streaming text is chunked piece by piece as its sentences arrive.
+async fn play_tts_texts(
+    app: &AppHandle,
+    text: (TtsText, TtsVoice),
+    openai: &openai::OpenAIClient,
+    tts_active: &Arc<Mutex<bool>>,
+    tts_queue: &Mutex<VecDeque<(TtsText, TtsVoice)>>,
+    tts_skip: &Arc<Mutex<bool>>,
+) {
+    let lexicon = app.state::<AppState>().database.load_lexicon().unwrap_or_default();
+    let skip_below = Arc::new(Mutex::new(0usize)); // Texts numbered lower were skipped
+
+    // Channel to send audio bytes from async context to the playback thread.
+    // The playback thread owns the OutputStream (not Send) and the Sink.
+    let (audio_tx, audio_rx) = std::sync::mpsc::channel::<(usize, Vec<u8>)>();
+    let tts_active_for_thread = tts_active.clone();
+    let tts_skip_for_thread = tts_skip.clone();
+    let skip_below_for_thread = skip_below.clone();
+
+    // Dedicated playback thread — owns the OutputStream so it always uses
+    // the current default output device (not the one from app startup).
+    let playback_thread = std::thread::spawn(move || {
+        let (_stream, stream_handle) = match rodio::OutputStream::try_default() {
+            Ok(pair) => pair,
+            Err(e) => {
+                eprintln!("❌ Failed to open audio output for TTS: {}", e);
+                return;
+            }
+        };
+        let new_sink = || rodio::Sink::try_new(&stream_handle).map_err(|e| eprintln!("❌ Failed to create TTS sink: {}", e));
+        let Ok(mut sink) = new_sink() else { return };
+        let append = |sink: &rodio::Sink, audio: &[u8]| match rodio::Decoder::new(std::io::Cursor::new(audio.to_vec())) {
+            Ok(src) => {
+                sink.append(src);
+                true
+            }
+            Err(e) => {
+                eprintln!("❌ Failed to decode TTS chunk: {}", e);
+                false
+            }
+        };
+
+        // Chunks in the sink, the one playing first (kept to rebuild the sink after a skip)
+        let mut queued: VecDeque<(usize, Vec<u8>)> = VecDeque::new();
+        let mut generating = true;
+        while *tts_active_for_thread.lock().unwrap() {
+            // Take new chunks without blocking so skips are handled while audio plays
+            loop {
+                match audio_rx.try_recv() {
+                    Ok((index, audio)) => {
+                        if index >= *skip_below_for_thread.lock().unwrap() && append(&sink, &audio) {
+                            queued.push_back((index, audio));
+                        }
+                    }
+                    Err(std::sync::mpsc::TryRecvError::Empty) => break,
+                    Err(std::sync::mpsc::TryRecvError::Disconnected) => {
+                        generating = false;
+                        break;
+                    }
+                }
+            }
+            while queued.len() > sink.len() {
+                queued.pop_front(); // Finished playing
+            }
+
+            if !queued.is_empty() && std::mem::take(&mut *tts_skip_for_thread.lock().unwrap()) {
+                let playing = queued[0].0;
+                *skip_below_for_thread.lock().unwrap() = playing + 1;
+                queued.retain(|(index, _)| *index > playing);
+                sink.stop();
+                let Ok(fresh) = new_sink() else { break };
+                sink = fresh;
+                queued.retain(|(_, audio)| append(&sink, audio));
+                println!("⏭️ TTS text {} skipped", playing + 1);
+            }
+
+            if !generating && sink.empty() {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(50));
+        }
+        sink.stop();
+        // _stream drops here, releasing output device
+    });
+
+    let mut next_text = Some(text);
+    let mut index = 0;
+    while let Some((text, voice)) = next_text.take() {
+        let (first, mut rest) = match text {
+            TtsText::Full(text) => (text, None),
+            TtsText::Streaming(text, rest) => (text, Some(rest)),
+        };
+        let mut piece = Some(first);
+        'text: while let Some(text) = piece.take() {
+            // Strip markdown links and raw URLs so TTS doesn't read them
+            let clean_text = strip_links_for_tts(&text);
+            // Apply the user's pronunciation lexicon (product names, loanwords)
+            let clean_text = apply_tts_lexicon(&clean_text, &lexicon);
+            let chunks = openai::split_into_tts_chunks(&clean_text);
+            println!("🔊 TTS chunked playback: {} chunks", chunks.len());
+
+            for (i, chunk) in chunks.iter().enumerate() {
+                if !*tts_active.lock().unwrap() {
+                    println!("🔇 TTS cancelled at chunk {}/{}", i + 1, chunks.len());
+                    break 'text;
+                }
+                if *skip_below.lock().unwrap() > index {
+                    break 'text; // Skipped while it was being generated
+                }
+
+                println!("🔊 TTS chunk {}/{}: generating audio for {} chars...", i + 1, chunks.len(), chunk.len());
+
+                match speak_text_cached(app, openai, chunk, &voice).await {
+                    Ok(audio) => {
+                        if !*tts_active.lock().unwrap() {
+                            println!("🔇 TTS cancelled after generating chunk {}", i + 1);
+                            break 'text;
+                        }
+                        println!("🔊 TTS chunk {}/{} sent to playback", i + 1, chunks.len());
+                        if audio_tx.send((index, audio)).is_err() {
+                            println!("🔇 Playback thread closed");
+                            break 'text;
+                        }
+                    }
+                    Err(e) => {
+                        eprintln!("❌ TTS chunk {} failed: {}", i + 1, e);
+                    }
+                }
+            }
+
+            // Next sentences of a streaming response (None once it's complete)
+            if let Some(rest) = rest.as_mut() {
+                piece = rest.recv().await;
+            }
+        }
+
+        if !*tts_active.lock().unwrap() {
+            break;
+        }
+        next_text = tts_queue.lock().unwrap().pop_front();
+        index += 1;
+    }
+
+    // Drop sender to signal playback thread there's no more data
+    drop(audio_tx);
+
+    // Wait for playback thread to finish
+    let _ = playback_thread.join();
+}
+
+/// Stop the active recording (either mode) and discard it. Returns false if not recording.
+fn cancel_active_recording(state: &AppState, app: &AppHandle) -> bool {
+    let mut is_recording = state.is_recording.lock().unwrap();
+    if !*is_recording {
+        return false;
+    }
+
+    println!("❌ Cancelling recording...");
+    *is_recording = false;
+    update_tray_recording(app, false);
+
+    // No stop handler will consume the prompt mode, output target or attached context
+    *state.prompt_mode.lock().unwrap() = None;
+    *state.output_target.lock().unwrap() = OutputTarget::Paste;
+    *state.screen_context.lock().unwrap() = None;
+    *state.selection_context.lock().unwrap() = None;
+    *state.dictation_style.lock().unwrap() = None;
+
+    // Stop Whisper mode recorder (discard audio)
+    let recorder = state.audio_recorder.lock().unwrap();
+    let _ = recorder.stop_recording();
+    if let Some(session) = state.chunked_session.lock().unwrap().take() {
+        session.abort();
+    }
+
+    // Stop realtime mode streaming (release microphone + kill WebSocket tasks)
+    if let Some(handle) = state.streaming_stop_handle.lock().unwrap().take() {
+        handle.stop();
+        println!("🔌 Realtime streaming stopped via cancel");
+    }
+
+    // Restore system audio
+    if let Err(e) = restore_system_audio() {
+        eprintln!("⚠️ Failed to unmute system audio: {}", e);
+    }
+    play_sound_cue(app, sound_cues::Cue::Cancel);
+
+    true
+}
+
+#[tauri::command]
+async fn cancel_recording(state: State<'_, AppState>, app: AppHandle) -> Result<String, String> {
+    if assistant_active(&app) {
+        apply_assistant_mode(&app, false);
+    }
+    if !cancel_active_recording(&state, &app) {
+        return Err("Not recording".to_string());
+    }
+    Ok("Recording cancelled".to_string())
+}
+
+/// Cancel chunked TTS playback and hide the TTS widget
+fn stop_tts(state: &AppState, app: &AppHandle) {
+    // Cancel chunked playback loop and drop what was waiting to be read
+    state.tts_queue.lock().unwrap().clear();
+    *state.tts_active.lock().unwrap() = false;
+    // Stop current sink
+    if let Some(sink) = state.tts_sink.lock().unwrap().take() {
+        sink.stop();
+        println!("🔇 TTS playback stopped");
+    }
+    // Hide widget
+    if let Some(w) = app.get_webview_window("tts-widget") {
+        let _ = w.hide();
+    }
+}
+
+/// Panic button: cancel any recording, stop TTS and hide the widgets
+fn abort_everything(app: &AppHandle) {
+    let Some(state) = app.try_state::<AppState>() else { return };
+
+    if assistant_active(app) {
+        apply_assistant_mode(app, false);
+    }
+
+    if cancel_active_recording(&state, app) {
+        let _ = emit_app_event(app, AppEvent::RecordingCancelled);
+    }
+    stop_tts(&state, app);
+
+    for label in ["recording-widget", "warning-widget"] {
+        if let Some(w) = app.get_webview_window(label) {
+            let _ = w.hide();
+        }
+    }
+}
+
+#[tauri::command]
+fn get_hotkeys(state: State<'_, AppState>) -> Result<Vec<shortcuts::HotkeyBinding>, String> {
+    Ok(state.shortcuts.bindings())
+}
+
+/// Remap a hotkey action (e.g. "Ctrl+Alt+Escape"); an empty combo restores the default
+#[tauri::command]
+fn set_hotkey(state: State<'_, AppState>, app: AppHandle, action: HotkeyAction, hotkey: String) -> Result<(), String> {
+    let hotkey = hotkey.trim();
+    let effective = if hotkey.is_empty() { action.default_hotkey() } else { hotkey };
+    state.shortcuts.bind(&app, action, effective)?;
+    println!("⌨️ {:?} hotkey: {}", action, effective);
+    let result = if hotkey.is_empty() {
+        state.database.delete_setting(&action.setting_key())
+    } else {
+        state.database.save_setting(&action.setting_key(), hotkey)
+    };
+    result.map_err(|e| format!("Failed to save hotkey: {}", e))
+}
+
+#[tauri::command]
+fn get_cancel_hotkey(state: State<'_, AppState>) -> Result<String, String> {
+    Ok(HotkeyAction::Cancel.load_hotkey(&state.database))
+}
+
+/// Change the cancel hotkey (e.g. "Ctrl+Alt+Escape")
+#[tauri::command]
+fn set_cancel_hotkey(state: State<'_, AppState>, app: AppHandle, hotkey: String) -> Result<(), String> {
+    set_hotkey(state, app, HotkeyAction::Cancel, hotkey)
+}
+
+// --- Mouse button / foot pedal push-to-talk ---
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct PttSettings {
+    mouse_button: String,       // "none" | "middle" | "x1" | "x2"
+    hid_device: Option<String>, // Foot pedal as "VID:PID", None to disable
+    mode: String,               // "hold" (record while pressed) | "toggle" (press to start/stop)
+}
+
+fn load_ptt_settings(database: &db::Database) -> PttSettings {
+    let load = |key: &str| database.load_setting(key).ok().flatten();
+    PttSettings {
+        mouse_button: load("ptt_mouse_button").unwrap_or_else(|| "none".to_string()),
+        hid_device: load("ptt_hid_device").filter(|d| !d.is_empty()),
+        mode: load("ptt_mode").unwrap_or_else(|| "hold".to_string()),
+    }
+}
+
+/// Point the mouse hook and HID listener at the currently saved bindings
+fn apply_ptt_settings(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let settings = load_ptt_settings(&state.database);
+
+    input_listener::set_mouse_button(input_listener::MouseButton::from_setting(&settings.mouse_button));
+
+    let sender = state.ptt_sender.lock().unwrap().clone();
+    match (settings.hid_device.as_deref().and_then(input_listener::parse_hid_id), sender) {
+        (Some((vid, pid)), Some(tx)) => input_listener::start_hid_listener(vid, pid, tx),
+        _ => input_listener::stop_hid_listener(),
+    }
+}
+
+/// React to a push-to-talk press/release from the mouse hook or foot pedal
+fn handle_ptt_event(app: &AppHandle, event: input_listener::PttEvent, held_since_start: &mut bool) {
+    let state = app.state::<AppState>();
+    let hold_mode = load_ptt_settings(&state.database).mode != "toggle";
+    let is_recording = *state.is_recording.lock().unwrap();
+
+    match event {
+        input_listener::PttEvent::Pressed => {
+            if hold_mode && is_recording {
+                return;
+            }
+            println!("🦶 Push-to-talk pressed ({})", if hold_mode { "hold" } else { "toggle" });
+            *held_since_start = hold_mode && !is_recording;
+            toggle_recording(app, RecordingTrigger::Transcribe);
+        }
+        input_listener::PttEvent::Released => {
+            if !hold_mode || !std::mem::take(held_since_start) {
+                return;
+            }
+            // The frontend starts recording asynchronously (start sound first), so a quick
+            // tap can be released before is_recording flips; wait for it before stopping
+            let deadline = Instant::now() + Duration::from_secs(3);
+            while !*state.is_recording.lock().unwrap() {
+                if Instant::now() > deadline {
+                    println!("⚠️ Push-to-talk released but recording never started");
+                    return;
+                }
+                std::thread::sleep(Duration::from_millis(50));
+            }
+            println!("🦶 Push-to-talk released");
+            toggle_recording(app, RecordingTrigger::Transcribe);
+        }
+    }
+}
+
+#[tauri::command]
+fn get_ptt_settings(state: State<'_, AppState>) -> Result<PttSettings, String> {
+    Ok(load_ptt_settings(&state.database))
+}
+
+#[tauri::command]
+fn set_ptt_settings(state: State<'_, AppState>, app: AppHandle, settings: PttSettings) -> Result<(), String> {
+    if !matches!(settings.mouse_button.as_str(), "none" | "middle" | "x1" | "x2") {
+        return Err(format!("Invalid mouse button: {}", settings.mouse_button));
+    }
+    if !matches!(settings.mode.as_str(), "hold" | "toggle") {
+        return Err(format!("Invalid push-to-talk mode: {}", settings.mode));
+    }
+    let hid_device = settings.hid_device.as_deref().map(str::trim).filter(|d| !d.is_empty());
+    if let Some(device) = hid_device {
+        if input_listener::parse_hid_id(device).is_none() {
+            return Err(format!("Dispositivo HID inválido '{}' (use VID:PID)", device));
+        }
+    }
+
+    state.database.save_setting("ptt_mouse_button", &settings.mouse_button)
+        .and_then(|_| state.database.save_setting("ptt_mode", &settings.mode))
+        .and_then(|_| state.database.save_setting("ptt_hid_device", hid_device.unwrap_or("")))
+        .map_err(|e| format!("Failed to save push-to-talk settings: {}", e))?;
+
+    apply_ptt_settings(&app);
+    Ok(())
+}
+
+/// List connected HID devices so the user can pick a foot pedal
+#[tauri::command]
+fn list_hid_devices() -> Result<Vec<input_listener::HidDeviceInfo>, String> {
+    input_listener::list_hid_devices()
+}
+
+#[tauri::command]
+async fn start_recording_audio(state: State<'_, AppState>, app: AppHandle) -> Result<String, String> {
+    // Let the start cue finish before system audio gets muted
+    if !*state.is_recording.lock().unwrap() {
+        tokio::time::sleep(play_sound_cue(&app, sound_cues::Cue::Start)).await;
+    }
+
+    let mut is_recording = state.is_recording.lock().unwrap();
+    if *is_recording {
+        return Err("Already recording".to_string());
+    }
+
+    let session_id = SessionId::new();
+    *state.session_id.lock().unwrap() = Some(session_id);
+    slog!(session_id, "🎤 Starting audio recording (session {})...", session_id);
+
+    // Set recording start time, and remember where the result should be pasted
+    *state.recording_start_time.lock().unwrap() = Some(Instant::now());
+    *state.paste_target.lock().unwrap() = active_window::foreground_handle();
+
+    // Get selected microphone from settings
+    let selected_mic = preferred_microphone(&state.database);
+    let selected_mic = resolve_input_device(&app, &state.database, selected_mic);
+
+    let recorder = state.audio_recorder.lock().unwrap();
+    state.clip_meter.reset();
+    state.session_stats.reset();
+    let sidetone = Some(sidetone::SidetoneSettings::load(&state.database)).filter(|s| s.enabled);
+    recorder.start_recording(selected_mic, state.clip_meter.clone(), state.session_stats.clone(), sidetone)?;
+    *is_recording = true;
+    update_tray_recording(&app, true);
+
+    // Mute system audio while recording (the start cue already finished)
+    if let Err(e) = silence_system_audio(&state.database) {
+        eslog!(session_id, "⚠️ Failed to mute system audio: {}", e);
+    }
+
+    // Chunked mode: transcribe 10-15s chunks while recording continues
+    if state.database.load_setting("whisper_chunked").ok().flatten().as_deref() == Some("true") {
+        let prompt_mode = state.prompt_mode.lock().unwrap().is_some();
+        let conv_history = get_conversation_history(&state.database);
+        let whisper_prompt = whisper_prompt_context(&state.database, prompt_mode, &conv_history);
+        *state.chunked_session.lock().unwrap() = Some(chunked::ChunkedSession::new(48000, whisper_prompt, session_id));
+        slog!(session_id, "🧩 Chunked Whisper mode active");
+
+        let is_recording_flag = state.is_recording.clone();
+        let app_clone = app.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                if !*is_recording_flag.lock().unwrap() {
+                    break;
+                }
+                let state = app_clone.state::<AppState>();
+                let recorder = state.audio_recorder.lock().unwrap();
+                if let Some(session) = state.chunked_session.lock().unwrap().as_mut() {
+                    session.poll(&recorder, &state.openai_client, &app_clone);
+                }
+            }
+        });
+    }
+
+    // Spawn timer task for Whisper mode
+    let is_recording_flag = state.is_recording.clone();
+    let recording_start = state.recording_start_time.clone();
+    let app_clone = app.clone();
+    let mut timer = RecordingTimer::new(&state.database, session_id, "whisper");
+
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+
+            if !*is_recording_flag.lock().unwrap() {
+                break;
+            }
+
+            if let Some(start_time) = *recording_start.lock().unwrap() {
+                timer.tick(&app_clone, start_time.elapsed());
+            }
+        }
+    });
+
+    Ok("Recording started".to_string())
+}
+
+const DEFAULT_MAX_RECORDING_SECS: u64 = 6 * 60;
+/// Share of the maximum length after which the time-limit warning shows
+const RECORDING_WARNING_FRACTION: f64 = 0.8;
+
+/// How long a recording may run before it's stopped automatically
+fn max_recording_length(database: &db::Database) -> Duration {
+    let secs = database.load_setting("max_recording_secs")
+        .ok()
+        .flatten()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(DEFAULT_MAX_RECORDING_SECS)
+        .max(60);
+    Duration::from_secs(secs)
+}
+
+/// Time-limit bookkeeping shared by the Whisper and realtime monitor loops: a heartbeat
+/// every elapsed second, the warning widget at the warning threshold and an automatic
+/// stop at the limit
+struct RecordingTimer {
+    session_id: SessionId,
+    mode: &'static str,
+    warning_at: Duration,
+    limit: Duration,
+    heartbeat_secs: u64,
+    warning_shown: bool,
+    auto_stop_triggered: bool,
+}
+
+impl RecordingTimer {
+    fn new(database: &db::Database, session_id: SessionId, mode: &'static str) -> Self {
+        let limit = max_recording_length(database);
+        Self {
+            session_id,
+            mode,
+            warning_at: limit.mul_f64(RECORDING_WARNING_FRACTION),
+            limit,
+            heartbeat_secs: 0,
+            warning_shown: false,
+            auto_stop_triggered: false,
+        }
+    }
+
+    fn tick(&mut self, app: &AppHandle, elapsed: Duration) {
+        let session_id = self.session_id;
+        let tag = self.mode.to_uppercase();
+
+        if elapsed.as_secs() > self.heartbeat_secs {
+            self.heartbeat_secs = elapsed.as_secs();
+            let _ = emit_app_event(app, AppEvent::RecordingElapsed {
+                session_id,
+                elapsed_secs: elapsed.as_secs(),
+                mode: self.mode.to_string(),
+                warning_at_secs: self.warning_at.as_secs(),
+                limit_secs: self.limit.as_secs(),
+            });
+        }
+
+        if elapsed >= self.warning_at && !self.warning_shown {
+            self.warning_shown = true;
+            slog!(session_id, "⚠️ [{}] {:?} of {:?} elapsed, showing warning...", tag, elapsed, self.limit);
+            show_recording_warning(app, session_id, &tag);
+        }
+
+        if elapsed >= self.limit && !self.auto_stop_triggered {
+            self.auto_stop_triggered = true;
+            slog!(session_id, "⏰ [{}] {:?} limit reached, auto-stopping...", tag, self.limit);
+            slog!(session_id, "⏰ [{}] Elapsed time: {:?}", tag, elapsed);
+
+            // DON'T set is_recording = false here - let the frontend's stopRecording() do it,
+            // which handles transcription save, paste, etc. The monitor loop keeps running
+            // until then.
+            if let Some(window) = app.get_webview_window("main") {
+                slog!(session_id, "⏰ [{}] Found main window, emitting widget-stop-recording event", tag);
+                match emit_app_event(&window, AppEvent::WidgetStopRecording) {
+                    Ok(_) => slog!(session_id, "⏰ [{}] ✅ Event emitted successfully", tag),
+                    Err(e) => slog!(session_id, "⏰ [{}] ❌ Failed to emit event: {}", tag, e),
+                }
+            } else {
+                slog!(session_id, "⏰ [{}] ❌ Main window not found!", tag);
+            }
+
+            // Hide recording widget
+            if let Some(widget) = app.get_webview_window("recording-widget") {
+                slog!(session_id, "⏰ [{}] Found recording widget, hiding it", tag);
+                match widget.hide() {
+                    Ok(_) => slog!(session_id, "⏰ [{}] ✅ Widget hidden successfully", tag),
+                    Err(e) => slog!(session_id, "⏰ [{}] ❌ Failed to hide widget: {}", tag, e),
+                }
+            } else {
+                slog!(session_id, "⏰ [{}] ❌ Recording widget not found!", tag);
+            }
+        }
+    }
+}
+
+/// Show the time-limit warning above the recording widget for a few seconds
+fn show_recording_warning(app: &AppHandle, session_id: SessionId, tag: &str) {
+    let Some(warning) = app.get_webview_window("warning-widget") else {
+        slog!(session_id, "⚠️ [{}] ❌ Warning widget not found!", tag);
+        return;
+    };
+
+    if let Some(widget) = app.get_webview_window("recording-widget") {
+        if let Ok(widget_pos) = widget.outer_position() {
+            // Centered above the widget
+            let warning_x = widget_pos.x - 77;
+            let warning_y = widget_pos.y - 70;
+            slog!(session_id, "⚠️ [{}] Positioning warning at x:{}, y:{}", tag, warning_x, warning_y);
+            if let Err(e) = warning.set_position(PhysicalPosition::new(warning_x, warning_y)) {
+                slog!(session_id, "⚠️ [{}] ❌ Failed to set position: {}", tag, e);
+            }
+        }
+    } else {
+        slog!(session_id, "⚠️ [{}] ❌ Recording widget not found for positioning", tag);
+    }
+
+    match warning.show() {
+        Ok(_) => {
+            slog!(session_id, "⚠️ [{}] ✅ Warning shown successfully", tag);
+
+            // Auto-hide warning after 4 seconds
+            let tag = tag.to_string();
+            tokio::spawn(async move {
+                tokio::time::sleep(tokio::time::Duration::from_secs(4)).await;
+                match warning.hide() {
+                    Ok(_) => slog!(session_id, "⚠️ [{}] ✅ Warning auto-hidden successfully", tag),
+                    Err(e) => slog!(session_id, "⚠️ [{}] ❌ Failed to auto-hide warning: {}", tag, e),
+                }
+            });
+        },
+        Err(e) => slog!(session_id, "⚠️ [{}] ❌ Failed to show warning: {}", tag, e),
+    }
+}
+
+#[tauri::command]
+fn get_max_recording_secs(state: State<'_, AppState>) -> Result<u64, String> {
+    Ok(max_recording_length(&state.database).as_secs())
+}
+
+#[tauri::command]
+fn set_max_recording_secs(state: State<'_, AppState>, secs: u64) -> Result<(), String> {
+    state.database.save_setting("max_recording_secs", &secs.max(60).to_string())
+        .map_err(|e| format!("Failed to save max recording length: {}", e))
+}
+
+/// The stopped recording had nothing to transcribe; nothing was sent or saved
+fn emit_no_speech(app: &AppHandle, session_id: SessionId) {
+    slog!(session_id, "🔇 No speech detected, skipping transcription");
+    let _ = emit_app_event(app, AppEvent::NoSpeechDetected { session_id });
+}
+
+#[tauri::command]
+async fn stop_recording_audio(state: State<'_, AppState>, app: tauri::AppHandle) -> Result<String, String> {
+    let mut is_recording = state.is_recording.lock().unwrap();
+    if !*is_recording {
+        return Err("Not recording".to_string());
+    }
+    let session_id = state.session_id.lock().unwrap().unwrap_or_else(SessionId::new);
+
+    slog!(session_id, "⏹️ Stopping audio recording...");
+    let recorder = state.audio_recorder.lock().unwrap();
+    let audio_data = recorder.stop_recording();
+    *is_recording = false;
+    update_tray_recording(&app, false);
+
+    // Restore system audio
+    if let Err(e) = restore_system_audio() {
+        eslog!(session_id, "⚠️ Failed to unmute system audio: {}", e);
+    }
+    play_sound_cue(&app, sound_cues::Cue::Stop);
+
+    // Capture recording duration for stats
+    let duration_ms = state.recording_start_time.lock().unwrap()
+        .map(|start| start.elapsed().as_millis() as i64);
+
+    if audio_data.is_empty() {
+        return Err("No audio recorded".to_string());
+    }
+
+    // Check if we're in prompt mode
+    let prompt_mode = state.prompt_mode.lock().unwrap().clone();
+    let screen_context = state.screen_context.lock().unwrap().take();
+    let selection_context = state.selection_context.lock().unwrap().take();
+    let dictation_style = state.dictation_style.lock().unwrap().take();
+    let clipping_pct = state.clip_meter.percentage();
+    let diagnostics = state.session_stats.snapshot();
+
+    // Don't spend a Whisper request (or a history entry) on silence
+    if !audio::contains_speech(&audio_data) {
+        if let Some(session) = state.chunked_session.lock().unwrap().take() {
+            session.abort();
+        }
+        emit_no_speech(&app, session_id);
+        return Ok("No speech detected".to_string());
+    }
+
+    if archive_session_audio(&state.database) {
+        if let Err(e) = audio_archive::save_f32(&state.archive_dir, &session_id.to_string(), &audio_data, 48000) {
+            eslog!(session_id, "⚠️ Failed to archive the recording: {}", e);
+        }
+    }
+
+    // Load conversation history before spawning (inactivity check happens here)
+    let conv_history = get_conversation_history(&state.database);
+    let whisper_prompt = whisper_prompt_context(&state.database, prompt_mode.is_some(), &conv_history);
+
+    // Transcribe (without post-processing for speed)
+    let openai = state.openai_client.clone();
+    let app_handle = app.clone();
+    let audio_data_for_queue = audio_data.clone();
+    let chunked_session = state.chunked_session.lock().unwrap().take();
+    let pipeline = Pipeline::new(state.openai_client.clone(), AppOutput::new(&app), Some(session_id))
+        .in_order(&state.delivery_order);
+    let request = RecordingRequest {
+        prompt_model: prompt_mode,
+        selection: selection_context,
+        style: dictation_style,
+        screen_context,
+        history: conv_history,
+        duration_ms,
+        capture: Some(Capture { clipping_pct, diagnostics }),
+    };
+    let audio_for_cancel = audio_data.clone();
+    spawn_in_flight(&state, session_id, None, Some(audio_for_cancel), async move {
+        let transcription = match chunked_session {
+            Some(session) => match session.finish(&audio_data, &openai, &app_handle).await {
+                Ok(text) => Ok(openai::Transcript { text, language: openai.language(), words: Vec::new() }),
+                Err(e) => {
+                    eslog!(session_id, "⚠️ Chunked transcription incomplete ({}), transcribing full recording", e);
+                    pipeline.transcribe(audio_data, 48000, whisper_prompt.as_deref()).await
+                }
+            },
+            None => pipeline.transcribe(audio_data, 48000, whisper_prompt.as_deref()).await,
+        };
+        pipeline.finish_recording(transcription, audio_data_for_queue, request).await;
+    });
+
+    Ok("Recording stopped, processing...".to_string())
+}
+
+#[tauri::command]
+fn get_last_transcription(state: State<'_, AppState>) -> Result<String, String> {
+    let last = state.last_transcription.lock().unwrap();
+    match &*last {
+        Some(text) => Ok(text.clone()),
+        None => Err("No transcription available".to_string()),
+    }
+}
+
+#[tauri::command]
+fn get_transcription_history(state: State<'_, AppState>, app_name: Option<String>, language: Option<String>) -> Result<Vec<TranscriptionEntry>, String> {
+    state.database.load_transcriptions(app_name.as_deref(), language.as_deref())
+        .map_err(|e| format!("Failed to load history: {}", e))
+}
+
+/// Languages present in the history, most used first (for the history/stats filters)
+#[tauri::command]
+fn get_history_languages(state: State<'_, AppState>) -> Result<Vec<String>, String> {
+    state.database.load_history_languages()
+        .map_err(|e| format!("Failed to load history languages: {}", e))
+}
+
+/// Conversation messages (prompts and responses), newest first; `offset` pages further back
+#[tauri::command]
+fn get_conversation_messages(state: State<'_, AppState>, limit: Option<usize>, offset: Option<usize>) -> Result<Vec<db::ConversationEntry>, String> {
+    state.database.load_conversation_messages(limit.unwrap_or(50), offset.unwrap_or(0))
+        .map_err(|e| format!("Failed to load conversation: {}", e))
+}
+
+#[tauri::command]
+fn delete_conversation_message(app: AppHandle, state: State<'_, AppState>, id: i64) -> Result<(), String> {
+    state.database.delete_conversation_message(id)
+        .map_err(|e| format!("Failed to delete conversation message: {}", e))?;
+    emit_conversation_updated(&app, None);
+    Ok(())
+}
+
+/// Entries saved without post-processing have no revisions yet: keep their text as the
+/// "original" before another version is added or replaces it
+fn keep_original_revision(database: &db::Database, entry: &db::TranscriptionEntry) -> Result<(), String> {
+    let id = entry.id.ok_or("History entry has no id")?;
+    let revisions = database.load_transcription_revisions(id)
+        .map_err(|e| format!("Failed to load revisions: {}", e))?;
+    if revisions.is_empty() {
+        database.add_transcription_revision(id, &entry.text, "original", entry.model.as_deref(), entry.timestamp)
+            .map_err(|e| format!("Failed to save revision: {}", e))?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+fn list_transcription_revisions(state: State<'_, AppState>, id: i64) -> Result<Vec<db::TranscriptionRevision>, String> {
+    state.database.load_transcription_revisions(id)
+        .map_err(|e| format!("Failed to load revisions: {}", e))
+}
+
+/// Replace a history entry's text with the user's edit; every earlier version stays listed
+#[tauri::command]
+fn edit_transcription(app: AppHandle, state: State<'_, AppState>, id: i64, text: String) -> Result<(), String> {
+    let entry = state.database.load_transcription(id)
+        .map_err(|e| format!("Failed to load transcription: {}", e))?
+        .ok_or_else(|| format!("Transcription {} not found", id))?;
+    if entry.text == text {
+        return Ok(());
+    }
+    keep_original_revision(&state.database, &entry)?;
+    state.database.add_transcription_revision(id, &text, "edited", None, now_ms())
+        .map_err(|e| format!("Failed to save revision: {}", e))?;
+    state.database.set_transcription_text(id, &text)
+        .map_err(|e| format!("Failed to save transcription: {}", e))?;
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = emit_app_event(&window, AppEvent::HistoryUpdated { session_id: None });
+    }
+    Ok(())
+}
+
+/// Make a revision the entry's text again (the current text is already one of its revisions)
+#[tauri::command]
+fn restore_transcription_revision(app: AppHandle, state: State<'_, AppState>, revision_id: i64) -> Result<(), String> {
+    let revision = state.database.load_transcription_revision(revision_id)
+        .map_err(|e| format!("Failed to load revision: {}", e))?
+        .ok_or_else(|| format!("Revision {} not found", revision_id))?;
+    let entry = state.database.load_transcription(revision.transcription_id)
+        .map_err(|e| format!("Failed to load transcription: {}", e))?
+        .ok_or_else(|| format!("Transcription {} not found", revision.transcription_id))?;
+    keep_original_revision(&state.database, &entry)?;
+    state.database.set_transcription_text(revision.transcription_id, &revision.text)
+        .map_err(|e| format!("Failed to restore revision: {}", e))?;
+    println!("⏪ Restored {} revision {} of entry {}", revision.kind, revision_id, revision.transcription_id);
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = emit_app_event(&window, AppEvent::HistoryUpdated { session_id: None });
+    }
+    Ok(())
+}
+
+#[derive(Clone, serde::Serialize)]
+struct CleanupResult {
+    original: String,
+    cleaned: String,
+    diff: Vec<text_diff::Change>,
+}
+
+/// Clean up a history entry with GPT (grammar, punctuation, filler words) on demand.
+/// The cleaned text is stored alongside the original, which stays untouched.
+#[tauri::command]
+async fn cleanup_transcription(app: AppHandle, state: State<'_, AppState>, id: i64) -> Result<CleanupResult, String> {
+    let entry = state.database.load_transcription(id)
+        .map_err(|e| format!("Failed to load transcription: {}", e))?
+        .ok_or_else(|| format!("Transcription {} not found", id))?;
+    let cleaned = state.openai_client.post_process(&entry.text).await?;
+    if cleaned.is_empty() {
+        return Err("Cleanup returned no text".to_string());
+    }
+    state.database.set_transcription_cleaned_text(id, &cleaned)
+        .map_err(|e| format!("Failed to save cleaned text: {}", e))?;
+    keep_original_revision(&state.database, &entry)?;
+    state.database.add_transcription_revision(id, &cleaned, "cleaned", Some("gpt-4o-mini"), now_ms())
+        .map_err(|e| format!("Failed to save revision: {}", e))?;
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = emit_app_event(&window, AppEvent::HistoryUpdated { session_id: None });
+    }
+    Ok(CleanupResult {
+        diff: text_diff::word_diff(&entry.text, &cleaned),
+        original: entry.text,
+        cleaned,
+    })
+}
+
+/// Turns of a live session entry with their timestamps, to show it as paragraphs
+#[tauri::command]
+fn get_transcription_segments(state: State<'_, AppState>, id: i64) -> Result<Vec<realtime::TranscriptSegment>, String> {
+    state.database.load_transcription_segments(id)
+        .map_err(|e| format!("Failed to load session turns: {}", e))
+}
+
+/// How the audio of a transcription was captured (device, sample rate, resampling, level, drops)
+#[tauri::command]
+fn get_session_diagnostics(state: State<'_, AppState>, id: i64) -> Result<Option<db::SessionDiagnostics>, String> {
+    state.database.load_session_diagnostics(id)
+        .map_err(|e| format!("Failed to load session diagnostics: {}", e))
+}
+
+/// Apps that dictations were pasted into, for the history filter
+#[tauri::command]
+fn get_history_apps(state: State<'_, AppState>) -> Result<Vec<String>, String> {
+    state.database.load_history_apps()
+        .map_err(|e| format!("Failed to load history apps: {}", e))
+}
+
+#[tauri::command]
+fn copy_to_clipboard(app: AppHandle, text: String) -> Result<(), String> {
+    app.clipboard().write_text(text)
+        .map_err(|e| format!("Failed to write to clipboard: {}", e))
+}
+
+#[tauri::command]
+fn set_use_realtime(state: State<'_, AppState>, use_realtime: bool) -> Result<(), String> {
+    *state.use_realtime.lock().unwrap() = use_realtime;
+    println!("🔄 Switched to {} mode", if use_realtime { "Realtime" } else { "Whisper" });
+    Ok(())
+}
+
+#[tauri::command]
+fn get_use_realtime(state: State<'_, AppState>) -> Result<bool, String> {
+    Ok(*state.use_realtime.lock().unwrap())
+}
+
+#[tauri::command]
+fn list_microphones() -> Result<Vec<String>, String> {
+    use cpal::traits::{DeviceTrait, HostTrait};
+
+    let host = cpal::default_host();
+    let devices: Vec<String> = host
+        .input_devices()
+        .map_err(|e| format!("Failed to get input devices: {}", e))?
+        .filter_map(|device| device.name().ok())
+        .collect();
+
+    Ok(devices)
+}
+
+#[tauri::command]
+fn set_selected_microphone(state: State<'_, AppState>, device_name: String) -> Result<(), String> {
+    state.database.save_setting("selected_microphone", &device_name)
+        .map_err(|e| format!("Failed to save microphone setting: {}", e))?;
+    // The endpoint ID finds the mic again when Windows renames it (other port, other language)
+    let endpoint = system_audio::input_endpoints().ok()
+        .and_then(|endpoints| endpoints.into_iter().find(|e| e.name.trim() == device_name.trim()));
+    let saved = match endpoint.filter(|e| !e.id.is_empty()) {
+        Some(endpoint) => state.database.save_setting("selected_microphone_id", &endpoint.id),
+        None => state.database.delete_setting("selected_microphone_id"),
+    };
+    saved.map_err(|e| format!("Failed to save microphone setting: {}", e))?;
+    println!("🎤 Selected microphone: {}", device_name);
+    Ok(())
+}
+
+#[tauri::command]
+fn get_selected_microphone(state: State<'_, AppState>) -> Result<Option<String>, String> {
+    let Some(selected) = state.database.load_setting("selected_microphone")
+        .map_err(|e| format!("Failed to load microphone setting: {}", e))? else {
+        return Ok(None);
+    };
+    // Under its current name, so the settings list shows it selected after a rename
+    let id = state.database.load_setting("selected_microphone_id").ok().flatten();
+    let endpoints = system_audio::input_endpoints().unwrap_or_default();
+    Ok(Some(system_audio::find_input(&endpoints, id.as_deref(), &selected).map_or(selected, |e| e.name.clone())))
+}
+
+/// Silence playback while recording: mute the output, or, with sidetone on (it plays through
+/// that output), turn every other app down to zero instead
+fn silence_system_audio(database: &db::Database) -> Result<(), String> {
+    if sidetone::SidetoneSettings::load(database).enabled {
+        system_audio::duck_system_audio(system_audio::DuckOwner::Recording, 0.0)
+    } else {
+        system_audio::mute_system_audio()
+    }
+}
+
+/// Undo silence_system_audio (whichever way it went; the other restore has nothing to do).
+/// Both steps always run so a failed unmute can't leave other apps at zero.
+fn restore_system_audio() -> Result<(), String> {
+    let unmuted = system_audio::unmute_system_audio();
+    let restored = system_audio::restore_ducked_audio(system_audio::DuckOwner::Recording);
+    match (unmuted, restored) {
+        (Err(a), Err(b)) => Err(format!("{}; {}", a, b)),
+        (Err(e), _) | (_, Err(e)) => Err(e),
+        _ => Ok(()),
+    }
+}
+
+#[tauri::command]
+fn get_sidetone_settings(state: State<'_, AppState>) -> Result<sidetone::SidetoneSettings, String> {
+    Ok(sidetone::SidetoneSettings::load(&state.database))
+}
+
+/// Play the mic back through the output while recording (takes effect on the next recording)
+#[tauri::command]
+fn set_sidetone_settings(state: State<'_, AppState>, settings: sidetone::SidetoneSettings) -> Result<(), String> {
+    settings.save(&state.database)
+        .map_err(|e| format!("Failed to save sidetone settings: {}", e))
+}
+
+/// Microphones to record from, most preferred first (`microphone_priority`, one per line)
+fn microphone_priority(database: &db::Database) -> Vec<String> {
+    database.load_setting("microphone_priority").ok().flatten()
+        .map(|v| v.lines().map(str::trim).filter(|l| !l.is_empty()).map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+/// Microphone for a new session: the first one of the priority list that is connected,
+/// otherwise `selected_microphone` (None = system default). Saved mics are looked up by
+/// endpoint ID and then by name, and the device's current name is returned.
+fn preferred_microphone(database: &db::Database) -> Option<String> {
+    let selected = database.load_setting("selected_microphone").ok().flatten();
+    let endpoints = match system_audio::input_endpoints() {
+        Ok(endpoints) => endpoints,
+        Err(e) => {
+            eprintln!("⚠️ Failed to list capture endpoints: {}", e);
+            return selected;
+        }
+    };
+
+    let priority = microphone_priority(database);
+    if let Some(endpoint) = priority.iter().find_map(|name| system_audio::find_input(&endpoints, None, name)) {
+        println!("🎤 Using {} (first connected microphone of the priority list)", endpoint.name);
+        return Some(endpoint.name.clone());
+    }
+    if !priority.is_empty() {
+        println!("⚠️ No microphone of the priority list is connected, using the selected one");
+    }
+
+    let selected = selected?;
+    let id = database.load_setting("selected_microphone_id").ok().flatten();
+    match system_audio::find_input(&endpoints, id.as_deref(), &selected) {
+        Some(endpoint) if endpoint.name != selected => {
+            println!("🎤 Selected microphone {} is now named {}", selected, endpoint.name);
+            Some(endpoint.name.clone())
+        }
+        _ => Some(selected),
+    }
+}
+
+#[tauri::command]
+fn get_microphone_priority(state: State<'_, AppState>) -> Result<Vec<String>, String> {
+    Ok(microphone_priority(&state.database))
+}
+
+/// Ordered list of preferred microphones; an empty list records from `selected_microphone`
+#[tauri::command]
+fn set_microphone_priority(state: State<'_, AppState>, devices: Vec<String>) -> Result<(), String> {
+    let devices: Vec<&str> = devices.iter().map(|d| d.trim()).filter(|d| !d.is_empty()).collect();
+    let result = if devices.is_empty() {
+        state.database.delete_setting("microphone_priority")
+    } else {
+        state.database.save_setting("microphone_priority", &devices.join("\n"))
+    };
+    result.map_err(|e| format!("Failed to save microphone priority: {}", e))
+}
+
+/// Opening a Bluetooth headset's mic drops it to the hands-free profile (8-16 kHz mono),
+/// which ruins both transcription and whatever is playing. Warn about it and, with
+/// `bluetooth_mic_fallback` on, record from the first non-Bluetooth input instead.
+fn resolve_input_device(app: &AppHandle, database: &db::Database, selected: Option<String>) -> Option<String> {
+    let endpoints = match system_audio::input_endpoints() {
+        Ok(endpoints) => endpoints,
+        Err(e) => {
+            eprintln!("⚠️ Failed to list capture endpoints: {}", e);
+            return selected;
+        }
+    };
+
+    let device = match selected.clone().or_else(system_audio::default_input_name) {
+        Some(device) => device,
+        None => return selected,
+    };
+    if !endpoints.iter().any(|e| e.bluetooth && e.name == device) {
+        return selected;
+    }
+
+    let fallback = database.load_setting("bluetooth_mic_fallback").ok().flatten().as_deref() == Some("true");
+    let replacement = if fallback {
+        endpoints.iter().find(|e| !e.bluetooth).map(|e| e.name.clone())
+    } else {
+        None
+    };
+
+    match &replacement {
+        Some(name) => println!("🎧 {} is a Bluetooth mic, recording from {} instead", device, name),
+        None => println!("🎧 {} is a Bluetooth mic, audio will drop to hands-free quality", device),
+    }
+    let _ = emit_app_event(app, AppEvent::BluetoothMicWarning { device, replacement: replacement.clone() });
+
+    replacement.or(selected)
+}
+
+#[tauri::command]
+fn get_exclusive_capture(state: State<'_, AppState>) -> Result<bool, String> {
+    Ok(state.database.load_setting("realtime_exclusive_mode")
+        .map_err(|e| format!("Failed to load exclusive mode setting: {}", e))?
+        .as_deref() == Some("true"))
+}
+
+#[tauri::command]
+fn set_exclusive_capture(state: State<'_, AppState>, enabled: bool) -> Result<(), String> {
+    state.database.save_setting("realtime_exclusive_mode", if enabled { "true" } else { "false" })
+        .map_err(|e| format!("Failed to save exclusive mode setting: {}", e))
+}
+
+#[tauri::command]
+fn get_bluetooth_mic_fallback(state: State<'_, AppState>) -> Result<bool, String> {
+    Ok(state.database.load_setting("bluetooth_mic_fallback")
+        .map_err(|e| format!("Failed to load Bluetooth mic setting: {}", e))?
+        .as_deref() == Some("true"))
+}
+
+#[tauri::command]
+fn set_bluetooth_mic_fallback(state: State<'_, AppState>, enabled: bool) -> Result<(), String> {
+    state.database.save_setting("bluetooth_mic_fallback", if enabled { "true" } else { "false" })
+        .map_err(|e| format!("Failed to save Bluetooth mic setting: {}", e))
+}
+
+#[tauri::command]
+fn set_selected_prompt_model(state: State<'_, AppState>, model: String, save_as_default: Option<bool>) -> Result<(), String> {
+    // Save as current session model
+    state.database.save_setting("selected_prompt_model", &model)
+        .map_err(|e| format!("Failed to save prompt model setting: {}", e))?;
+
+    // Only save as user_prompt_model (for Ctrl+Shift+Space) when explicitly requested
+    // This prevents Ctrl+B or Ctrl+Alt+Space from overwriting the user's preferred model
+    if save_as_default.unwrap_or(false) && model != "transcribe-only" {
+        state.database.save_setting("user_prompt_model", &model)
+            .map_err(|e| format!("Failed to save user prompt model: {}", e))?;
+        println!("💾 Selected prompt model: {} (also saved as user_prompt_model)", model);
+    } else {
+        println!("💾 Selected prompt model: {} (user_prompt_model unchanged)", model);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+fn get_selected_prompt_model(state: State<'_, AppState>) -> Result<Option<String>, String> {
+    state.database.load_setting("selected_prompt_model")
+        .map_err(|e| format!("Failed to load prompt model setting: {}", e))
+}
+
+#[tauri::command]
+fn get_current_recording_mode(state: State<'_, AppState>) -> Result<String, String> {
+    // Return the model that should be pre-selected based on current prompt_mode
+    let prompt_mode = state.prompt_mode.lock().unwrap().clone();
+
+    let model = match prompt_mode.as_deref() {
+        Some("gpt-4o-mini") => "gpt-4o-mini".to_string(),
+        Some("gpt-4.1") => "gpt-4.1".to_string(),
+        None => "transcribe-only".to_string(),
+        Some(other) => {
+            println!("⚠️ Unknown prompt mode: {}, defaulting to transcribe-only", other);
+            "transcribe-only".to_string()
+        }
+    };
+
+    println!("📋 get_current_recording_mode: prompt_mode={:?}, returning model={}", prompt_mode, model);
+    Ok(model)
+}
+
+/// How a recording toggle was triggered (hotkey or tray), which decides the prompt model
+enum RecordingTrigger {
+    /// Plain transcription (Ctrl+Space); keeps a prompt mode that was already set
+    Transcribe,
+    /// The user's chosen prompt model (Ctrl+Shift+Space)
+    UserPromptModel,
+    /// A fixed prompt model (Ctrl+Alt+Space)
+    PromptModel(String),
+}
+
+/// Where the recording widget appears
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+struct WidgetPlacement {
+    /// "<top|center|bottom>-<left|center|right>", or "center"
+    anchor: String,
+    /// Distance in pixels from the anchored edges (a shift for centered axes)
+    offset_x: i32,
+    offset_y: i32,
+    /// "current" (the widget's monitor), "cursor" (follow the mouse), "primary" or a monitor name
+    monitor: String,
+}
+
+impl Default for WidgetPlacement {
+    fn default() -> Self {
+        // Bottom-center, with room above the taskbar
+        Self { anchor: "bottom-center".to_string(), offset_x: 0, offset_y: 200, monitor: "current".to_string() }
+    }
+}
+
+impl WidgetPlacement {
+    /// Top-left corner of a `width` x `height` window on `monitor`
+    fn position(&self, monitor: &tauri::Monitor, width: i32, height: i32) -> (i32, i32) {
+        let origin = monitor.position();
+        let size = monitor.size();
+        let (vertical, horizontal) = match self.anchor.split_once('-') {
+            Some((v, h)) => (v, h),
+            None => (self.anchor.as_str(), "center"),
+        };
+        let x = match horizontal {
+            "left" => self.offset_x,
+            "right" => size.width as i32 - width - self.offset_x,
+            _ => (size.width as i32 - width) / 2 + self.offset_x,
+        };
+        let y = match vertical {
+            "top" => self.offset_y,
+            "center" => (size.height as i32 - height) / 2 + self.offset_y,
+            _ => size.height as i32 - height - self.offset_y,
+        };
+        (origin.x + x, origin.y + y)
+    }
+}
+
+fn load_widget_placement(database: &db::Database) -> WidgetPlacement {
+    let load = |key: &str| database.load_setting(key).ok().flatten();
+    let default = WidgetPlacement::default();
+    WidgetPlacement {
+        anchor: load("widget_anchor").unwrap_or(default.anchor),
+        offset_x: load("widget_offset_x").and_then(|v| v.parse().ok()).unwrap_or(default.offset_x),
+        offset_y: load("widget_offset_y").and_then(|v| v.parse().ok()).unwrap_or(default.offset_y),
+        monitor: load("widget_monitor").unwrap_or(default.monitor),
+    }
+}
+
+/// Resolve the placement's monitor setting, falling back to the widget's current monitor
+fn widget_monitor(app: &AppHandle, widget: &tauri::WebviewWindow, setting: &str) -> Option<tauri::Monitor> {
+    let chosen = match setting {
+        "current" => None,
+        "cursor" => app.cursor_position().ok()
+            .and_then(|cursor| app.monitor_from_point(cursor.x, cursor.y).ok().flatten()),
+        "primary" => app.primary_monitor().ok().flatten(),
+        name => app.available_monitors().ok()
+            .and_then(|monitors| monitors.into_iter().find(|m| m.name().is_some_and(|n| n == name))),
+    };
+    chosen.or_else(|| widget.current_monitor().ok().flatten())
+}
+
+#[tauri::command]
+fn get_widget_placement(state: State<'_, AppState>) -> Result<WidgetPlacement, String> {
+    Ok(load_widget_placement(&state.database))
+}
+
+#[tauri::command]
+fn set_widget_placement(state: State<'_, AppState>, placement: WidgetPlacement) -> Result<(), String> {
+    let values = [
+        ("widget_anchor", placement.anchor),
+        ("widget_offset_x", placement.offset_x.to_string()),
+        ("widget_offset_y", placement.offset_y.to_string()),
+        ("widget_monitor", placement.monitor),
+    ];
+    for (key, value) in values {
+        state.database.save_setting(key, &value)
+            .map_err(|e| format!("Failed to save widget placement: {}", e))?;
+    }
+    Ok(())
+}
+
+/// Names of the connected monitors, for the widget monitor setting
+#[tauri::command]
+fn get_monitor_names(app: AppHandle) -> Result<Vec<String>, String> {
+    let monitors = app.available_monitors()
+        .map_err(|e| format!("Failed to list monitors: {}", e))?;
+    Ok(monitors.iter().filter_map(|m| m.name().cloned()).collect())
+}
+
+/// Show the recording widget where the placement settings put it and tell it the active model
+fn show_recording_widget(app: &AppHandle, model: String) {
+    if let Some(widget) = app.get_webview_window("recording-widget") {
+        let placement = app.try_state::<AppState>()
+            .map(|state| load_widget_placement(&state.database))
+            .unwrap_or_default();
+        if let Some(monitor) = widget_monitor(app, &widget, &placement.monitor) {
+            let widget_width = 155;
+            let widget_height = 120; // Height increased for combo box
+            let (x, y) = placement.position(&monitor, widget_width, widget_height);
+            let _ = widget.set_position(PhysicalPosition::new(x, y));
+        }
+        let _ = widget.show();
+        // Tell widget which model is active
+        let _ = emit_app_event(&widget, AppEvent::ModelSelected(model));
+    }
+}
+
+/// Show the prompt input window (Ctrl+B) centered above the taskbar
+fn show_prompt_input(app: &AppHandle) {
+    if let Some(prompt_window) = app.get_webview_window("prompt-input") {
+        if let Ok(monitor) = prompt_window.current_monitor() {
+            if let Some(monitor) = monitor {
+                let screen_size = monitor.size();
+                let win_width = 400i32;
+                let win_height = 160i32;
+                let x = (screen_size.width as i32 - win_width) / 2;
+                let y = screen_size.height as i32 - win_height - 200;
+                let _ = prompt_window.set_position(PhysicalPosition::new(x, y));
+            }
+        }
+        let _ = prompt_window.show();
+        let _ = prompt_window.set_focus();
+    }
+}
+
+/// Capture the screen ("full": monitor under the cursor), the foreground "window",
+/// or a "region" (x, y, width, height in physical pixels). Returns a base64 PNG for `send_text_prompt`.
+#[tauri::command]
+fn capture_screenshot(mode: String, x: Option<i32>, y: Option<i32>, width: Option<i32>, height: Option<i32>) -> Result<String, String> {
+    screenshot::capture(screenshot::CaptureArea::parse(&mode, x, y, width, height)?)
+}
+
+/// Like toggle_recording, but a session started here delivers its output to `target`
+fn toggle_recording_to(app: &AppHandle, trigger: RecordingTrigger, target: OutputTarget) {
+    let was_recording = app.try_state::<AppState>()
+        .map(|state| *state.is_recording.lock().unwrap())
+        .unwrap_or(false);
+    toggle_recording(app, trigger);
+    if !was_recording {
+        if let Some(state) = app.try_state::<AppState>() {
+            *state.output_target.lock().unwrap() = target;
+            println!("🎯 Output target for this session: {:?}", target);
+        }
+    }
+}
+
+fn assistant_active(app: &AppHandle) -> bool {
+    app.try_state::<AppState>().is_some_and(|state| *state.assistant_active.lock().unwrap())
+}
+
+/// Hands-free assistant: record a question, read the answer aloud, then listen again for the
+/// follow-up. Talking over the answer stops it; a turn with nothing said ends the loop.
+fn apply_assistant_mode(app: &AppHandle, enabled: bool) {
+    let Some(state) = app.try_state::<AppState>() else { return };
+    *state.assistant_active.lock().unwrap() = enabled;
+    println!("🎙️ Assistant mode {}", if enabled { "on" } else { "off" });
+    let _ = emit_app_event(app, AppEvent::AssistantModeChanged(enabled));
+    if enabled {
+        start_assistant_turn(app);
+    }
+}
+
+#[tauri::command]
+fn get_assistant_mode(app: AppHandle) -> Result<bool, String> {
+    Ok(assistant_active(&app))
+}
+
+#[tauri::command]
+fn set_assistant_mode(app: AppHandle, enabled: bool) -> Result<(), String> {
+    apply_assistant_mode(&app, enabled);
+    Ok(())
+}
+
+/// Open the mic for the user's next turn and stop the recording when they pause
+fn start_assistant_turn(app: &AppHandle) {
+    let Some(state) = app.try_state::<AppState>() else { return };
+    if *state.is_recording.lock().unwrap() {
+        return;
+    }
+    toggle_recording_to(app, RecordingTrigger::UserPromptModel, OutputTarget::Assistant);
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let state = app.state::<AppState>();
+        // The frontend starts the recording when it gets the toggle event
+        let requested = Instant::now();
+        while !*state.is_recording.lock().unwrap() {
+            if requested.elapsed() > Duration::from_secs(5) {
+                eprintln!("⚠️ Assistant turn didn't start recording");
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+
+        let mut turn = assistant::TurnDetector::new();
+        let mut checked = state.audio_recorder.lock().unwrap().sample_count();
+        loop {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            if !*state.is_recording.lock().unwrap() || !*state.assistant_active.lock().unwrap() {
+                return;
+            }
+            // Realtime/local sessions have a VAD; Whisper sessions are judged by the new samples
+            if *state.speech_active.lock().unwrap() {
+                turn.speaking();
+            }
+            let samples = {
+                let recorder = state.audio_recorder.lock().unwrap();
+                recorder.samples_range(checked, recorder.sample_count())
+            };
+            checked += samples.len();
+            turn.feed(&samples);
+
+            match turn.state() {
+                assistant::Turn::Listening => {}
+                assistant::Turn::Ended => {
+                    println!("🎙️ Assistant: end of turn");
+                    toggle_recording(&app, RecordingTrigger::Transcribe);
+                    return;
+                }
+                assistant::Turn::Idle => {
+                    println!("🎙️ Assistant: nothing said, leaving assistant mode");
+                    apply_assistant_mode(&app, false);
+                    if cancel_active_recording(&state, &app) {
+                        let _ = emit_app_event(&app, AppEvent::RecordingCancelled);
+                    }
+                    if let Some(widget) = app.get_webview_window("recording-widget") {
+                        let _ = widget.hide();
+                    }
+                    return;
+                }
+            }
+        }
+    });
+}
+
+/// Screenshot the foreground window and start a prompt session that sends it along with
+/// the dictated question ("what does this error mean?"). Pressed again, stops the session.
+fn ask_about_screen(app: &AppHandle) {
+    let Some(state) = app.try_state::<AppState>() else { return };
+    if *state.is_recording.lock().unwrap() {
+        toggle_recording(app, RecordingTrigger::Transcribe);
+        return;
+    }
+    // Capture before the recording widget appears on top of the window
+    let image = match screenshot::capture(screenshot::CaptureArea::Window) {
+        Ok(image) => image,
+        Err(e) => {
+            eprintln!("❌ Screenshot failed: {}", e);
+            return;
+        }
+    };
+    toggle_recording(app, RecordingTrigger::UserPromptModel);
+    *state.screen_context.lock().unwrap() = Some(image);
+    println!("🖼️ Screenshot attached to this prompt session");
+}
+
+/// Copy the selection in the focused app and start a prompt session whose dictated
+/// instruction ("make this more formal") is applied to it. Pressed again, stops the session.
+fn edit_selection(app: &AppHandle) {
+    let Some(state) = app.try_state::<AppState>() else { return };
+    if *state.is_recording.lock().unwrap() {
+        toggle_recording(app, RecordingTrigger::Transcribe);
+        return;
+    }
+    let selection = match copy_selected_text(app) {
+        Ok(selection) => selection,
+        Err(e) => {
+            println!("⚠️ {}", e);
+            return;
+        }
+    };
+    toggle_recording(app, RecordingTrigger::UserPromptModel);
+    println!("✂️ Selection attached to this prompt session ({} chars)", selection.len());
+    *state.selection_context.lock().unwrap() = Some(selection);
+}
+
+/// Rule for the app in the foreground, which is where this session's text will go
+fn foreground_app_rule(database: &db::Database) -> Option<db::AppRule> {
+    let window = active_window::foreground()?;
+    database.find_app_rule(&window.app_name).ok().flatten()
+}
+
+/// Prompt for an instruction dictated about selected text (plain instruction otherwise)
+fn prompt_with_selection(instruction: &str, selection: Option<&str>) -> String {
+    match selection {
+        Some(selection) => format!(
+            "{}\n\nApply the instruction above to the text below. Reply with the resulting text only, without quotes or comments.\n\n\"\"\"\n{}\n\"\"\"",
+            instruction.trim(),
+            selection.trim()
+        ),
+        None => instruction.to_string(),
+    }
+}
+
+/// Start or stop dictation: prepare prompt mode and the widget, then let the
+/// frontend run the actual start/stop (it plays the start sound first)
+fn toggle_recording(app: &AppHandle, trigger: RecordingTrigger) {
+    if let Some(state) = app.try_state::<AppState>() {
+        let is_recording = *state.is_recording.lock().unwrap();
+
+        if !is_recording {
+            *state.output_target.lock().unwrap() = OutputTarget::Paste;
+            *state.screen_context.lock().unwrap() = None;
+            *state.selection_context.lock().unwrap() = None;
+            *state.dictation_style.lock().unwrap() = None;
+            let prompt_model = match trigger {
+                RecordingTrigger::Transcribe => None,
+                // Ctrl+Shift+Space uses the model the user picked in the combo box for prompt sessions
+                RecordingTrigger::UserPromptModel => Some(
+                    state.database.load_setting("user_prompt_model")
+                        .ok()
+                        .flatten()
+                        .unwrap_or_else(|| "gpt-4o-mini".to_string()),
+                ),
+                RecordingTrigger::PromptModel(model) => Some(model),
+            };
+
+            let widget_model = match prompt_model {
+                Some(model) => {
+                    // Save as current session model
+                    let _ = state.database.save_setting("selected_prompt_model", &model);
+                    *state.prompt_mode.lock().unwrap() = Some(model.clone());
+                    println!("🤖 Prompt mode enabled: {} (saved to DB)", model);
+                    model
+                }
+                None => {
+                    // Check if prompt mode was already set by Ctrl+Shift+Space or Ctrl+Alt+Space
+                    let current_prompt_mode = state.prompt_mode.lock().unwrap().clone();
+                    match (current_prompt_mode, foreground_app_rule(&state.database)) {
+                        // The app being dictated into has its own default (VS Code → gpt-4.1, Word → formal)
+                        (None, Some(rule)) => {
+                            println!("🎯 App rule for {}: {} (style: {:?})", rule.app_name, rule.model, rule.style);
+                            let _ = state.database.save_setting("selected_prompt_model", &rule.model);
+                            if rule.model != "transcribe-only" {
+                                *state.prompt_mode.lock().unwrap() = Some(rule.model.clone());
+                            }
+                            *state.dictation_style.lock().unwrap() = rule.style;
+                            rule.model
+                        }
+                        (None, None) => {
+                            println!("📝 Starting - setting prompt mode to None (normal transcription)");
+                            let _ = state.database.save_setting("selected_prompt_model", "transcribe-only");
+                            "transcribe-only".to_string()
+                        }
+                        (Some(model), _) => {
+                            println!("⚠️ Starting but prompt_mode already set to {:?} - keeping it", model);
+                            model
+                        }
+                    }
+                }
+            };
+
+            show_recording_widget(app, widget_model);
+        } else {
+            // Stopping recording - DON'T clear prompt_mode here
+            // It will be cleared in stop_realtime_recording after being used
+            let current_prompt_mode = state.prompt_mode.lock().unwrap().clone();
+            println!("🛑 Stopping recording - prompt_mode = {:?} will be used in stop handler", current_prompt_mode);
+
+            if let Some(widget) = app.get_webview_window("recording-widget") {
+                let _ = widget.hide();
+            }
+        }
+    }
+
+    // Emit event to frontend
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = emit_app_event(&window, AppEvent::ToggleRecording);
+    }
+}
+
+/// Prompt models in the order the cycle hotkey steps through them
+const PROMPT_MODEL_CYCLE: &[&str] = &["transcribe-only", "gpt-4o-mini", "gpt-4.1"];
+
+/// Advance `selected_prompt_model` to the next model while the recording widget is visible.
+/// Both stop handlers read the latest value, so switching mid-recording applies to this session.
+fn cycle_prompt_model(app: &AppHandle) {
+    let Some(state) = app.try_state::<AppState>() else { return };
+    let current = state.database.load_setting("selected_prompt_model")
+        .ok()
+        .flatten()
+        .unwrap_or_else(|| "transcribe-only".to_string());
+    let next_index = PROMPT_MODEL_CYCLE.iter()
+        .position(|m| *m == current)
+        .map(|i| (i + 1) % PROMPT_MODEL_CYCLE.len())
+        .unwrap_or(0);
+    let next = PROMPT_MODEL_CYCLE[next_index];
+
+    if set_session_prompt_model(app, next) {
+        println!("🔁 Prompt model cycled: {} → {}", current, next);
+    }
+}
+
+/// Switch the model of the session being recorded ("transcribe-only" = plain transcription).
+/// Ignored unless the recording widget is visible; returns whether the switch happened.
+fn set_session_prompt_model(app: &AppHandle, model: &str) -> bool {
+    let Some(state) = app.try_state::<AppState>() else { return false };
+    let Some(widget) = app.get_webview_window("recording-widget") else { return false };
+    if !widget.is_visible().unwrap_or(false) {
+        println!("⏭️ Model switch ignored (widget not visible)");
+        return false;
+    }
+
+    let _ = state.database.save_setting("selected_prompt_model", model);
+    // Whisper mode reads prompt_mode instead of the DB value
+    *state.prompt_mode.lock().unwrap() = if model == "transcribe-only" { None } else { Some(model.to_string()) };
+    let _ = emit_app_event(&widget, AppEvent::ModelSelected(model.to_string()));
+    true
+}
+
+// Removed start_pre_buffering - pre-buffering logic moved to audio capture
+
+/// Load conversation history, clearing it first if inactive for 30+ minutes.
+fn get_conversation_history(database: &db::Database) -> Vec<db::ConversationMessage> {
+    const INACTIVITY_MS: i64 = 30 * 60 * 1000; // 30 minutes
+
+    if let Ok(Some(last_ts)) = database.last_conversation_timestamp() {
+        let now_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as i64;
+        if now_ms - last_ts > INACTIVITY_MS {
+            tlog!("🕐 Conversation inactive >30min, clearing history");
+            let _ = database.clear_conversation_history();
+            return vec![];
+        }
+    }
+
+    database.load_conversation_history(6).unwrap_or_default()
+}
+
+/// Model name stored with batch transcriptions ("whisper" for whisper-1, as before)
+fn transcription_model_label(openai: &openai::OpenAIClient) -> String {
+    match openai.transcription_model().as_str() {
+        "whisper-1" => "whisper".to_string(),
+        model => model.to_string(),
+    }
+}
+
+/// The whisper.cpp model to use for live transcription, or None when the OpenAI backend is selected.
+/// The model is loaded on first use and cached until the configured path changes.
+fn local_whisper_backend(
+    database: &db::Database,
+    cache: &Mutex<Option<Arc<local_whisper::LocalWhisper>>>,
+) -> Result<Option<Arc<local_whisper::LocalWhisper>>, String> {
+    if database.load_setting("realtime_backend").ok().flatten().as_deref() != Some("local") {
+        return Ok(None);
+    }
+    local_whisper_model(database, cache).map(Some)
+}
+
+/// The configured whisper.cpp model, loaded on first use and cached until its path changes
+fn local_whisper_model(
+    database: &db::Database,
+    cache: &Mutex<Option<Arc<local_whisper::LocalWhisper>>>,
+) -> Result<Arc<local_whisper::LocalWhisper>, String> {
+    let path = database.load_setting("local_whisper_model").ok().flatten()
+        .filter(|p| !p.is_empty())
+        .ok_or("No local Whisper model configured")?;
+    let path = PathBuf::from(path);
+
+    let mut cache = cache.lock().unwrap();
+    if let Some(whisper) = cache.as_ref().filter(|w| w.path() == path) {
+        return Ok(whisper.clone());
+    }
+    let whisper = Arc::new(local_whisper::LocalWhisper::load(&path)?);
+    *cache = Some(whisper.clone());
+    Ok(whisper)
+}
+
+/// Noise reduction requested from the Realtime API: "near_field", "far_field" or None (off)
+fn realtime_noise_reduction(database: &db::Database) -> Option<&'static str> {
+    match database.load_setting("realtime_noise_reduction").ok().flatten().as_deref() {
+        Some("near_field") => Some("near_field"),
+        Some("far_field") => Some("far_field"),
+        _ => None,
+    }
+}
+
+#[tauri::command]
+fn get_realtime_noise_reduction(state: State<'_, AppState>) -> Result<String, String> {
+    Ok(realtime_noise_reduction(&state.database).unwrap_or("off").to_string())
+}
+
+/// "off", "near_field" or "far_field"; applies from the next realtime recording
+#[tauri::command]
+fn set_realtime_noise_reduction(state: State<'_, AppState>, mode: String) -> Result<(), String> {
+    if !["off", "near_field", "far_field"].contains(&mode.as_str()) {
+        return Err(format!("Unknown noise reduction mode: {}", mode));
+    }
+    state.database.save_setting("realtime_noise_reduction", &mode)
+        .map_err(|e| format!("Failed to save noise reduction: {}", e))
+}
+
+/// Live transcripts longer than this are moved to the database
+const TRANSCRIPT_FLUSH_CHARS: usize = 16 * 1024;
+
+/// Add a delta to the live session transcript. Past TRANSCRIPT_FLUSH_CHARS the text so far
+/// goes to the database, so a very long dictation doesn't keep growing in memory.
+fn append_session_transcript(transcript: &Mutex<String>, database: &db::Database, session_id: SessionId, delta: &str) {
+    let mut transcript = transcript.lock().unwrap();
+    transcript.push_str(delta);
+    if transcript.len() >= TRANSCRIPT_FLUSH_CHARS {
+        match database.append_session_transcript_chunk(&session_id.to_string(), &transcript) {
+            Ok(()) => transcript.clear(),
+            Err(e) => eslog!(session_id, "⚠️ Failed to flush the session transcript: {}", e),
+        }
+    }
+}
+
+/// Full transcript of the live session: the chunks flushed to the database, then the rest
+fn take_session_transcript(state: &AppState, session_id: SessionId) -> String {
+    let flushed = state.database.take_session_transcript_chunks(&session_id.to_string()).unwrap_or_else(|e| {
+        eslog!(session_id, "⚠️ Failed to load the flushed transcript: {}", e);
+        String::new()
+    });
+    flushed + state.current_session_transcript.lock().unwrap().as_str()
+}
+
+/// Whether realtime prompts are answered by the Realtime session itself (`realtime_native_prompt`)
+fn realtime_native_prompt(database: &db::Database) -> bool {
+    database.load_setting("realtime_native_prompt").ok().flatten().as_deref() == Some("true")
+}
+
+/// Native prompt mode is on and the session will be a prompt (not transcribe-only)
+fn native_prompt_wanted(database: &db::Database) -> bool {
+    realtime_native_prompt(database)
+        && database.load_setting("selected_prompt_model").ok().flatten()
+            .is_some_and(|model| model != "transcribe-only")
+}
+
+/// The session a realtime recording leaves for native prompt mode. Its task stores it right
+/// after the stop, once the final transcription is in, so give it a moment.
+async fn take_realtime_session(slot: &Mutex<Option<Arc<realtime::RealtimeSession>>>) -> Option<Arc<realtime::RealtimeSession>> {
+    let deadline = Instant::now() + Duration::from_secs(2);
+    loop {
+        let session = slot.lock().unwrap().take();
+        if session.is_some() || Instant::now() >= deadline {
+            return session;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+}
+
+const DEFAULT_REALTIME_SESSION_GRACE_SECS: u64 = 60;
+
+/// How long a realtime session stays open after a recording for the next one to reuse (0 = off)
+fn realtime_session_grace(database: &db::Database) -> Duration {
+    let secs = database.load_setting("realtime_session_grace_secs")
+        .ok()
+        .flatten()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(DEFAULT_REALTIME_SESSION_GRACE_SECS);
+    Duration::from_secs(secs)
+}
+
+/// Keep a finished recording's session open so the next dictation skips connecting and
+/// configuring. It's closed once the grace period passes without a new recording.
+fn park_realtime_session(app: &AppHandle, session: Arc<realtime::RealtimeSession>) {
+    let Some(state) = app.try_state::<AppState>() else { return };
+    let grace = realtime_session_grace(&state.database);
+    if grace.is_zero() {
+        return;
+    }
+    let slot = state.idle_realtime_session.clone();
+    *slot.lock().unwrap() = Some(session.clone());
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(grace).await;
+        let expired = {
+            let mut slot = slot.lock().unwrap();
+            if slot.as_ref().is_some_and(|parked| Arc::ptr_eq(parked, &session)) { slot.take() } else { None }
+        };
+        if let Some(expired) = expired {
+            let _ = expired.close().await;
+            println!("🔌 Idle realtime session closed after {}s", grace.as_secs());
+        }
+    });
+}
+
+/// The session parked by the last recording, reset for a new one (None = connect anew)
+async fn reuse_realtime_session(slot: &Mutex<Option<Arc<realtime::RealtimeSession>>>) -> Option<Arc<realtime::RealtimeSession>> {
+    let session = slot.lock().unwrap().take()?;
+    match session.reset().await {
+        Ok(()) => {
+            println!("♻️ Reusing the realtime session of the last recording");
+            Some(session)
+        }
+        Err(e) => {
+            eprintln!("⚠️ Kept realtime session unusable, reconnecting: {}", e);
+            None
+        }
+    }
+}
+
+#[tauri::command]
+fn get_realtime_session_grace(state: State<'_, AppState>) -> Result<u64, String> {
+    Ok(realtime_session_grace(&state.database).as_secs())
+}
+
+#[tauri::command]
+fn set_realtime_session_grace(state: State<'_, AppState>, secs: u64) -> Result<(), String> {
+    state.database.save_setting("realtime_session_grace_secs", &secs.to_string())
+        .map_err(|e| format!("Failed to save session grace period: {}", e))
+}
+
+/// Whether live transcripts get their spacing and sentence capitalization repaired (default on)
+fn realtime_text_repair(database: &db::Database) -> bool {
+    database.load_setting("realtime_text_repair").ok().flatten().as_deref() != Some("false")
+}
+
+#[tauri::command]
+fn get_realtime_text_repair(state: State<'_, AppState>) -> Result<bool, String> {
+    Ok(realtime_text_repair(&state.database))
+}
+
+/// Repair the joined Realtime deltas before saving and pasting: a space after punctuation,
+/// none before it, and a capital letter at the start of each sentence
+#[tauri::command]
+fn set_realtime_text_repair(state: State<'_, AppState>, enabled: bool) -> Result<(), String> {
+    state.database.save_setting("realtime_text_repair", if enabled { "true" } else { "false" })
+        .map_err(|e| format!("Failed to save text repair setting: {}", e))
+}
+
+#[tauri::command]
+fn get_realtime_native_prompt(state: State<'_, AppState>) -> Result<bool, String> {
+    Ok(realtime_native_prompt(&state.database))
+}
+
+/// Answer realtime prompts with the Realtime model (audio in, text out) instead of sending
+/// the transcript to the Responses API: one round trip less, but no web search
+#[tauri::command]
+fn set_realtime_native_prompt(state: State<'_, AppState>, enabled: bool) -> Result<(), String> {
+    state.database.save_setting("realtime_native_prompt", if enabled { "true" } else { "false" })
+        .map_err(|e| format!("Failed to save native prompt setting: {}", e))
+}
+
+#[tauri::command]
+fn get_realtime_backend(state: State<'_, AppState>) -> Result<String, String> {
+    Ok(state.database.load_setting("realtime_backend")
+        .map_err(|e| format!("Failed to load realtime backend: {}", e))?
+        .unwrap_or_else(|| "openai".to_string()))
+}
+
+/// Returned when the local backend is chosen in a build without the `local-whisper` feature
+const LOCAL_WHISPER_UNAVAILABLE: &str = "Whisper local não está disponível nesta versão";
+
+/// Live transcription backend: "openai" (Realtime API) or "local" (whisper.cpp, offline)
+#[tauri::command]
+fn set_realtime_backend(state: State<'_, AppState>, backend: String) -> Result<(), String> {
+    if backend == "local" && !cfg!(feature = "local-whisper") {
+        return Err(LOCAL_WHISPER_UNAVAILABLE.to_string());
+    }
+    if backend != "openai" && backend != "local" {
+        return Err(format!("Unknown realtime backend: {}", backend));
+    }
+    state.database.save_setting("realtime_backend", &backend)
+        .map_err(|e| format!("Failed to save realtime backend: {}", e))
+}
+
+#[tauri::command]
+fn get_local_whisper_model(state: State<'_, AppState>) -> Result<Option<String>, String> {
+    state.database.load_setting("local_whisper_model")
+        .map_err(|e| format!("Failed to load local model path: {}", e))
+}
+
+/// Path to a whisper.cpp ggml model (e.g. ggml-base.bin)
+#[tauri::command]
+fn set_local_whisper_model(state: State<'_, AppState>, path: String) -> Result<(), String> {
+    if !cfg!(feature = "local-whisper") {
+        return Err(LOCAL_WHISPER_UNAVAILABLE.to_string());
+    }
+    let path = path.trim();
+    if !std::path::Path::new(path).is_file() {
+        return Err(format!("Arquivo de modelo não encontrado: {}", path));
+    }
+    state.database.save_setting("local_whisper_model", path)
+        .map_err(|e| format!("Failed to save local model path: {}", e))
+}
+
+#[tauri::command]
+fn get_whisper_chunked(state: State<'_, AppState>) -> Result<bool, String> {
+    Ok(state.database.load_setting("whisper_chunked")
+        .map_err(|e| format!("Failed to load chunked mode setting: {}", e))?
+        .map(|v| v == "true")
+        .unwrap_or(false))
+}
+
+/// Chunked Whisper mode: live deltas without the Realtime API cost
+#[tauri::command]
+fn set_whisper_chunked(state: State<'_, AppState>, enabled: bool) -> Result<(), String> {
+    state.database.save_setting("whisper_chunked", if enabled { "true" } else { "false" })
+        .map_err(|e| format!("Failed to save chunked mode setting: {}", e))
+}
+
+/// Ask GPT for the action items in a stored transcription and save them linked to the entry
+#[tauri::command]
+async fn extract_action_items(state: State<'_, AppState>, transcription_id: i64) -> Result<Vec<db::ActionItem>, String> {
+    let entry = state.database.load_transcription(transcription_id)
+        .map_err(|e| format!("Failed to load transcription: {}", e))?
+        .ok_or("Transcription not found")?;
+
+    let model = state.database.load_setting("user_prompt_model")
+        .ok()
+        .flatten()
+        .unwrap_or_else(|| "gpt-4o-mini".to_string());
+    let prompt = format!("{}\n\nTranscript:\n{}", meeting::ACTION_ITEMS_PROMPT, entry.text);
+    let response = state.openai_client.send_prompt(&prompt, &model, &[], None).await?;
+    let items = meeting::parse_action_items(&response);
+    println!("✅ Extracted {} action items from transcription {}", items.len(), transcription_id);
+
+    state.database.save_action_items(transcription_id, &items, now_ms())
+        .map_err(|e| format!("Failed to save action items: {}", e))
+}
+
+#[tauri::command]
+fn get_action_items(state: State<'_, AppState>, transcription_id: i64) -> Result<Vec<db::ActionItem>, String> {
+    state.database.load_action_items(transcription_id)
+        .map_err(|e| format!("Failed to load action items: {}", e))
+}
+
+/// Keyword spotter for a new live or meeting session, configured from the watch words settings
+fn keyword_spotter(database: &db::Database) -> keywords::KeywordSpotter {
+    let words = database.load_setting("watch_words")
+        .ok()
+        .flatten()
+        .map(|w| keywords::parse_watch_words(&w))
+        .unwrap_or_default();
+    let notify = database.load_setting("watch_words_notify").ok().flatten().as_deref() == Some("true");
+    keywords::KeywordSpotter::new(words, notify)
+}
+
+#[tauri::command]
+fn get_watch_words(state: State<'_, AppState>) -> Result<Vec<String>, String> {
+    Ok(state.database.load_setting("watch_words")
+        .map_err(|e| format!("Failed to load watch words: {}", e))?
+        .map(|w| keywords::parse_watch_words(&w))
+        .unwrap_or_default())
+}
+
+/// Words that trigger a `keyword-detected` alert when they show up in a live or meeting transcription
+#[tauri::command]
+fn set_watch_words(state: State<'_, AppState>, words: Vec<String>) -> Result<(), String> {
+    let words = keywords::parse_watch_words(&words.join("\n"));
+    state.database.save_setting("watch_words", &words.join("\n"))
+        .map_err(|e| format!("Failed to save watch words: {}", e))
+}
+
+#[tauri::command]
+fn get_watch_words_notify(state: State<'_, AppState>) -> Result<bool, String> {
+    Ok(state.database.load_setting("watch_words_notify")
+        .map_err(|e| format!("Failed to load setting: {}", e))?
+        .as_deref() == Some("true"))
+}
+
+#[tauri::command]
+fn set_watch_words_notify(state: State<'_, AppState>, enabled: bool) -> Result<(), String> {
+    state.database.save_setting("watch_words_notify", if enabled { "true" } else { "false" })
+        .map_err(|e| format!("Failed to save setting: {}", e))
+}
+
+fn meeting_summary_interval(database: &db::Database) -> u64 {
+    database.load_setting("meeting_summary_interval_min")
+        .ok()
+        .flatten()
+        .and_then(|v| v.parse::<u64>().ok())
+        .filter(|&v| v > 0)
+        .unwrap_or(meeting::DEFAULT_SUMMARY_INTERVAL_MINS)
+}
+
+/// Start meeting mode: records mic + system audio until stopped, transcribing in chunks
+/// and keeping a rolling summary. Returns the meeting id.
+fn start_meeting_inner(app: &AppHandle) -> Result<i64, String> {
+    let state = app.state::<AppState>();
+    let id = {
+        let mut meeting = state.meeting.lock().unwrap();
+        if meeting.is_some() {
+            return Err("Meeting already in progress".to_string());
+        }
+
+        let options = meeting::MeetingOptions {
+            mic_device: preferred_microphone(&state.database),
+            summary_interval: Duration::from_secs(meeting_summary_interval(&state.database) * 60),
+            summary_model: state.database.load_setting("user_prompt_model")
+                .ok()
+                .flatten()
+                .unwrap_or_else(|| "gpt-4o-mini".to_string()),
+            keyword_spotter: keyword_spotter(&state.database),
+        };
+        let handle = meeting::start_meeting(app.clone(), state.openai_client.clone(), state.database.clone(), options)?;
+        let id = handle.id;
+        *meeting = Some(handle);
+        id
+    };
+    let _ = emit_app_event(app, AppEvent::MeetingStarted(id));
+    refresh_tray_menu(app);
+    Ok(id)
+}
+
+/// Stop meeting mode and wait for the final summary and action items
+async fn stop_meeting_inner(app: &AppHandle) -> Result<db::MeetingEntry, String> {
+    let handle = app.state::<AppState>().meeting.lock().unwrap().take()
+        .ok_or("No meeting in progress")?;
+    if handle.mic_muted() {
+        let _ = emit_app_event(app, AppEvent::MicMuteToggled { session_id: None, muted: false });
+    }
+    refresh_tray_menu(app);
+    handle.stop().await
+}
+
+#[tauri::command]
+fn start_meeting(app: AppHandle) -> Result<i64, String> {
+    start_meeting_inner(&app)
+}
+
+#[tauri::command]
+async fn stop_meeting(app: AppHandle) -> Result<db::MeetingEntry, String> {
+    stop_meeting_inner(&app).await
+}
+
+#[tauri::command]
+fn is_meeting_active(state: State<'_, AppState>) -> bool {
+    state.meeting.lock().unwrap().is_some()
+}
+
+#[tauri::command]
+fn get_meetings(state: State<'_, AppState>) -> Result<Vec<db::MeetingEntry>, String> {
+    state.database.load_meetings()
+        .map_err(|e| format!("Failed to load meetings: {}", e))
+}
+
+#[tauri::command]
+fn delete_meeting(state: State<'_, AppState>, id: i64) -> Result<(), String> {
+    if state.meeting.lock().unwrap().as_ref().is_some_and(|m| m.id == id) {
+        return Err("Meeting is still in progress".to_string());
+    }
+    state.database.delete_meeting(id)
+        .map_err(|e| format!("Failed to delete meeting: {}", e))
+}
+
+#[tauri::command]
+fn get_meeting_summary_interval(state: State<'_, AppState>) -> Result<u64, String> {
+    Ok(meeting_summary_interval(&state.database))
+}
+
+/// Minutes between rolling summaries while a meeting is recorded
+#[tauri::command]
+fn set_meeting_summary_interval(state: State<'_, AppState>, minutes: u64) -> Result<(), String> {
+    if minutes == 0 {
+        return Err("Interval must be at least 1 minute".to_string());
+    }
+    state.database.save_setting("meeting_summary_interval_min", &minutes.to_string())
+        .map_err(|e| format!("Failed to save meeting summary interval: {}", e))
+}
+
+#[tauri::command]
+fn get_transcription_model(state: State<'_, AppState>) -> Result<String, String> {
+    Ok(state.openai_client.transcription_model())
+}
+
+/// Select the batch transcription model: whisper-1, gpt-4o-transcribe or gpt-4o-mini-transcribe
+#[tauri::command]
+fn set_transcription_model(state: State<'_, AppState>, model: String) -> Result<(), String> {
+    if !openai::TRANSCRIPTION_MODELS.contains(&model.as_str()) {
+        return Err(format!("Unknown transcription model: {}", model));
+    }
+    state.database.save_setting("transcription_model", &model)
+        .map_err(|e| format!("Failed to save transcription model: {}", e))?;
+    reload_runtime_settings(&state);
+    Ok(())
+}
+
+/// Whether recordings are kept after transcription so they can be re-transcribed (off by
+/// default, never while incognito)
+fn archive_session_audio(database: &db::Database) -> bool {
+    database.load_setting("archive_session_audio").ok().flatten().as_deref() == Some("true") && !is_incognito(database)
+}
+
+#[tauri::command]
+fn get_archive_session_audio(state: State<'_, AppState>) -> Result<bool, String> {
+    Ok(state.database.load_setting("archive_session_audio").ok().flatten().as_deref() == Some("true"))
+}
+
+#[tauri::command]
+fn set_archive_session_audio(state: State<'_, AppState>, enabled: bool) -> Result<(), String> {
+    state.database.save_setting("archive_session_audio", if enabled { "true" } else { "false" })
+        .map_err(|e| format!("Failed to save audio archiving setting: {}", e))
+}
+
+/// Transcribe a history entry again from its archived audio, with "local" (whisper.cpp) or one of
+/// the OpenAI transcription models. The result is stored as a revision of the entry, which is
+/// left as it was, and returned for comparison.
+#[tauri::command]
+async fn retranscribe(state: State<'_, AppState>, id: i64, model: String) -> Result<String, String> {
+    let entry = state.database.load_transcription(id)
+        .map_err(|e| format!("Failed to load transcription: {}", e))?
+        .ok_or("Transcrição não encontrada")?;
+    keep_original_revision(&state.database, &entry)?;
+    let session_id = entry.session_id.ok_or("Nenhum áudio arquivado para esta transcrição")?;
+    let (audio, sample_rate) = audio_archive::load(&state.archive_dir, &session_id)?;
+    let language = state.openai_client.language().unwrap_or_default();
+
+    let text = if model == "local" {
+        let database = state.database.clone();
+        let cache = state.local_whisper.clone();
+        tokio::task::spawn_blocking(move || {
+            let whisper = local_whisper_model(&database, &cache)?;
+            whisper.transcribe(&local_whisper::resample(&audio, sample_rate), &language, "")
+        })
+        .await
+        .map_err(|e| format!("Local transcription task failed: {}", e))??
+    } else if openai::TRANSCRIPTION_MODELS.contains(&model.as_str()) {
+        state.openai_client.transcribe_with_model(audio, sample_rate, None, &model).await?.text
+    } else {
+        return Err(format!("Unknown transcription model: {}", model));
+    };
+
+    state.database.add_transcription_revision(id, &text, "retranscription", Some(&model), now_ms())
+        .map_err(|e| format!("Failed to save revision: {}", e))?;
+    println!("🔁 Re-transcribed entry {} with {}", id, model);
+    Ok(text)
+}
+
+/// Max characters of context sent as the Whisper prompt (Whisper only reads the last ~224 tokens)
+const WHISPER_PROMPT_MAX_CHARS: usize = 800;
+/// Older dictations are unrelated to the current one, so they aren't used for priming
+const WHISPER_PROMPT_MAX_AGE_MS: i64 = 10 * 60 * 1000;
+
+/// Recent text to prime Whisper with: the last user turn of the conversation in prompt mode,
+/// otherwise the last plain transcription (if recent). None when priming is disabled.
+fn whisper_prompt_context(database: &db::Database, prompt_mode: bool, conv_history: &[db::ConversationMessage]) -> Option<String> {
+    if database.load_setting("whisper_priming").ok().flatten().as_deref() == Some("false") {
+        return None;
+    }
+
+    let context = if prompt_mode {
+        conv_history.iter().rev().find(|m| m.role == "user").map(|m| m.content.clone())
+    } else {
+        database.load_recent_transcriptions(1).ok()?
+            .into_iter()
+            .find(|e| e.mode.as_deref() == Some("transcription") && now_ms() - e.timestamp < WHISPER_PROMPT_MAX_AGE_MS)
+            .map(|e| e.text)
+    }?;
+
+    // Keep the tail (what directly precedes the new dictation), starting at a word boundary
+    let char_count = context.chars().count();
+    if char_count <= WHISPER_PROMPT_MAX_CHARS {
+        return Some(context);
+    }
+    let tail: String = context.chars().skip(char_count - WHISPER_PROMPT_MAX_CHARS).collect();
+    Some(match tail.find(' ') {
+        Some(i) => tail[i + 1..].to_string(),
+        None => tail,
+    })
+}
+
+#[tauri::command]
+fn get_whisper_priming(state: State<'_, AppState>) -> Result<bool, String> {
+    Ok(state.database.load_setting("whisper_priming")
+        .map_err(|e| format!("Failed to load priming setting: {}", e))?
+        .map(|v| v != "false")
+        .unwrap_or(true))
+}
+
+#[tauri::command]
+fn set_whisper_priming(state: State<'_, AppState>, enabled: bool) -> Result<(), String> {
+    state.database.save_setting("whisper_priming", if enabled { "true" } else { "false" })
+        .map_err(|e| format!("Failed to save priming setting: {}", e))
+}
+
+fn now_ms() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as i64
+}
+
+/// Unix timestamp (ms) of today's local midnight
+fn local_day_start_ms() -> i64 {
+    let today = chrono::Local::now().date_naive();
+    today
+        .and_hms_opt(0, 0, 0)
+        .and_then(|midnight| midnight.and_local_timezone(chrono::Local).earliest())
+        .map(|dt| dt.timestamp_millis())
+        .unwrap_or(0)
+}
+
+const DAILY_DIGEST_PROMPT: &str = "Below are all the notes and messages I dictated today, with the time of each one. \
+Write an end-of-day recap: group related items by topic, highlight decisions, open questions and things I still need \
+to do. Plain text, in the same language as the notes. Respond with ONLY the recap.";
+
+/// Summarize today's transcriptions into a digest via GPT and store it in the history (mode "digest")
+async fn generate_daily_digest_inner(app: &AppHandle, database: &db::Database, openai: &openai::OpenAIClient) -> Result<String, String> {
+    let entries = database.load_transcriptions_between(local_day_start_ms(), now_ms())
+        .map_err(|e| format!("Failed to load today's transcriptions: {}", e))?;
+    let notes: Vec<String> = entries.iter()
+        .filter(|e| e.mode.as_deref() != Some("digest"))
+        .filter_map(|e| {
+            let time = chrono::DateTime::from_timestamp_millis(e.timestamp)?.with_timezone(&chrono::Local);
+            Some(format!("[{}] {}", time.format("%H:%M"), e.text.trim()))
+        })
+        .collect();
+    if notes.is_empty() {
+        return Err("No transcriptions today".to_string());
+    }
+
+    let model = database.load_setting("user_prompt_model")
+        .ok()
+        .flatten()
+        .unwrap_or_else(|| "gpt-4o-mini".to_string());
+    println!("📰 Generating daily digest from {} transcriptions...", notes.len());
+    let prompt = format!("{}\n\n{}", DAILY_DIGEST_PROMPT, notes.join("\n"));
+    let digest = openai.send_prompt(&prompt, &model, &[], None).await?;
+
+    if let Err(e) = database.save_transcription(&digest, now_ms(), None, Some(&model), None, Some("digest"), None) {
+        eprintln!("❌ Failed to save digest: {}", e);
+    }
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = emit_app_event(&window, AppEvent::HistoryUpdated { session_id: None });
+    }
+    Ok(digest)
+}
+
+/// Generate today's digest now. With `paste`, the digest is also pasted into the focused app.
+#[tauri::command]
+async fn generate_daily_digest(app: AppHandle, state: State<'_, AppState>, paste: Option<bool>) -> Result<String, String> {
+    let digest = generate_daily_digest_inner(&app, &state.database, &state.openai_client).await?;
+    if paste.unwrap_or(false) {
+        let app_handle = app.clone();
+        let text = digest.clone();
+        tauri::async_runtime::spawn_blocking(move || auto_paste_text(&app_handle, &text))
+            .await
+            .map_err(|e| format!("Paste task failed: {}", e))??;
+    }
+    Ok(digest)
+}
+
+const SUMMARIZE_ENTRIES_PROMPT: &str = "Below are notes and messages I dictated, with the date and time of each one. \
+Condense them into one summary: merge what repeats, keep decisions, facts and things I still need to do. \
+Plain text, in the same language as the notes. Respond with ONLY the summary.";
+
+/// Summarize the selected history entries into a new "summary" entry (e.g. a day of scattered voice notes)
+#[tauri::command]
+async fn summarize_entries(app: AppHandle, state: State<'_, AppState>, ids: Vec<i64>) -> Result<String, String> {
+    let mut entries = Vec::new();
+    for id in ids {
+        let entry = state.database.load_transcription(id)
+            .map_err(|e| format!("Failed to load transcription: {}", e))?
+            .ok_or_else(|| format!("Transcription {} not found", id))?;
+        entries.push(entry);
+    }
+    if entries.is_empty() {
+        return Err("No entries selected".to_string());
+    }
+    entries.sort_by_key(|e| e.timestamp);
+    let notes: Vec<String> = entries.iter()
+        .filter_map(|e| {
+            let time = chrono::DateTime::from_timestamp_millis(e.timestamp)?.with_timezone(&chrono::Local);
+            Some(format!("[{}] {}", time.format("%d/%m %H:%M"), e.text.trim()))
+        })
+        .collect();
+
+    let model = state.database.load_setting("user_prompt_model")
+        .ok()
+        .flatten()
+        .unwrap_or_else(|| "gpt-4o-mini".to_string());
+    println!("🧾 Summarizing {} history entries...", notes.len());
+    let prompt = format!("{}\n\n{}", SUMMARIZE_ENTRIES_PROMPT, notes.join("\n"));
+    let summary = state.openai_client.send_prompt(&prompt, &model, &[], None).await?;
+
+    let cost = estimate_cost_cents(&model, None, &summary);
+    if let Err(e) = record_transcription(&state.database, None, &summary, now_ms(), None, Some(&model), Some(cost), Some("summary")) {
+        eprintln!("❌ Failed to save summary: {}", e);
+    }
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = emit_app_event(&window, AppEvent::HistoryUpdated { session_id: None });
+    }
+    Ok(summary)
+}
+
+/// Scheduled digest time ("HH:MM", local) or None when disabled
+#[tauri::command]
+fn get_daily_digest_time(state: State<'_, AppState>) -> Result<Option<String>, String> {
+    state.database.load_setting("daily_digest_time")
+        .map_err(|e| format!("Failed to load digest time: {}", e))
+}
+
+#[tauri::command]
+fn set_daily_digest_time(state: State<'_, AppState>, time: Option<String>) -> Result<(), String> {
+    let result = match time.filter(|t| !t.trim().is_empty()) {
+        Some(t) => {
+            chrono::NaiveTime::parse_from_str(t.trim(), "%H:%M")
+                .map_err(|_| format!("Invalid time '{}', expected HH:MM", t))?;
+            state.database.save_setting("daily_digest_time", t.trim())
+        }
+        None => state.database.delete_setting("daily_digest_time"),
+    };
+    result.map_err(|e| format!("Failed to save digest time: {}", e))
+}
+
+/// Generate the scheduled digest once the configured time has passed (at most once per day)
+async fn check_daily_digest(app: &AppHandle, database: &db::Database, openai: &openai::OpenAIClient) {
+    let Some(time) = database.load_setting("daily_digest_time")
+        .ok()
+        .flatten()
+        .and_then(|t| chrono::NaiveTime::parse_from_str(&t, "%H:%M").ok())
+    else {
+        return; // Scheduled digest disabled
+    };
+
+    let now = chrono::Local::now();
+    if now.time() < time {
+        return;
+    }
+    let today = now.format("%Y-%m-%d").to_string();
+    if database.load_setting("daily_digest_last_day").ok().flatten().as_deref() == Some(today.as_str()) {
+        return;
+    }
+    // Mark the day first so a failing request doesn't retry every minute
+    let _ = database.save_setting("daily_digest_last_day", &today);
+
+    match generate_daily_digest_inner(app, database, openai).await {
+        Ok(_) => {
+            if let Err(e) = app.notification().builder()
+                .title("Dicta - Resumo do dia")
+                .body("O resumo de hoje está pronto no histórico.")
+                .show()
+            {
+                eprintln!("⚠️ Failed to show digest notification: {}", e);
+            }
+        }
+        Err(e) => eprintln!("⚠️ Scheduled daily digest skipped: {}", e),
+    }
+}
+
+/// Compare today's summed cost against the configured alert threshold.
+/// Alerts at most once per day; the alerted day is persisted so restarts don't re-alert.
+fn check_daily_spend(app: &AppHandle, database: &db::Database) {
+    let threshold = match database.load_setting("spend_alert_threshold")
+        .ok()
+        .flatten()
+        .and_then(|v| v.parse::<i64>().ok())
+    {
+        Some(t) if t > 0 => t,
+        _ => return, // Alerts disabled
+    };
+
+    let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+    if database.load_setting("spend_alert_last_day").ok().flatten().as_deref() == Some(today.as_str()) {
+        return;
+    }
+
+    let spent = match database.get_stats(local_day_start_ms(), now_ms(), None) {
+        Ok(stats) => stats.total_cost_cents,
+        Err(e) => {
+            eprintln!("⚠️ Failed to compute daily spend: {}", e);
+            return;
+        }
+    };
+
+    if spent < threshold {
+        return;
+    }
+
+    tlog!("💸 Daily spend {} crossed alert threshold {}", spent, threshold);
+    let _ = database.save_setting("spend_alert_last_day", &today);
+    let _ = emit_app_event(app, AppEvent::SpendAlert { spent_cents: spent, threshold_cents: threshold });
+
+    let body = format!(
+        "Gasto de hoje: US$ {:.2} (limite: US$ {:.2})",
+        spent as f64 / 10_000.0,
+        threshold as f64 / 10_000.0
+    );
+    notify(app, NotificationKind::Budget, "Dicta - Alerta de gastos", &body);
+}
+
+fn emit_queue_updated(app: &AppHandle, database: &db::Database) {
+    let count = database.count_queue().unwrap_or(0);
+    tlog!("Queue updated, {} items pending", count);
+    let _ = emit_app_event(app, AppEvent::QueueUpdated(count));
+}
 
-                            // Save to conversation history
-                            let _ = database.append_conversation("user", &transcribed_text, timestamp - 1);
-                            let _ = database.append_conversation("assistant", &gpt_response, timestamp);
+fn emit_queue_full(app: &AppHandle) {
+    tlog!("Queue full, dropping new item");
+    let _ = emit_app_event(app, AppEvent::QueueFull);
+    notify(app, NotificationKind::Queued, "Dicta - Fila cheia", "A transcrição falhou e a fila de pendentes está cheia.");
+}
 
-                            // Notify frontend
-                            if let Some(window) = app_handle.get_webview_window("main") {
-                                let _ = window.emit("history-updated", ());
-                            }
+/// A failed request was saved to the offline queue: refresh the UI and tell the user
+fn emit_item_queued(app: &AppHandle, database: &db::Database) {
+    emit_queue_updated(app, database);
+    notify(app, NotificationKind::Queued, "Dicta - Salvo na fila", "A transcrição falhou e será tentada de novo quando a conexão voltar.");
+}
 
-                            // Auto-paste GPT response
-                            match auto_paste_text(&app_handle, &gpt_response) {
-                                Ok(_) => println!("✅ GPT response auto-pasted successfully"),
-                                Err(e) => {
-                                    eprintln!("⚠️ Auto-paste failed: {}", e);
-                                    if let Some(window) = app_handle.get_webview_window("main") {
-                                        let _ = window.emit("paste-failed", ());
-                                    }
-                                }
-                            }
+/// Categories of toast notifications the user can turn off in settings
+#[derive(Clone, Copy)]
+enum NotificationKind {
+    PasteFailed,
+    PasteReady,
+    Queued,
+    Budget,
+    VoiceCommand,
+}
 
-                            // Notification sound
-                            if let Some(window) = app_handle.get_webview_window("main") {
-                                let _ = window.emit("response-ready", ());
-                            }
+impl NotificationKind {
+    fn setting_key(self) -> &'static str {
+        match self {
+            NotificationKind::PasteFailed => "notify_paste_failed",
+            NotificationKind::PasteReady => "notify_paste_ready",
+            NotificationKind::Queued => "notify_queued",
+            NotificationKind::Budget => "notify_budget",
+            NotificationKind::VoiceCommand => "notify_voice_command",
+        }
+    }
+}
 
-                            // TTS (chunked)
-                            if *tts_enabled.lock().unwrap() {
-                                tauri::async_runtime::spawn(play_tts_chunked(
-                                    app_handle.clone(), gpt_response.clone(),
-                                    openai_for_tts.clone(), tts_sink.clone(),
-                                    tts_stream_handle.clone(), tts_active.clone(),
-                                ));
-                            }
-                        }
-                        Err(e) => {
-                            eprintln!("❌ GPT prompt error: {}", e);
-                            let count = database.count_queue().unwrap_or(0);
-                            if count < queue::MAX_QUEUE_SIZE {
-                                let _ = database.enqueue_item(
-                                    "whisper-prompt",
-                                    None,
-                                    Some(&transcribed_text),
-                                    &model,
-                                    now_ms(),
-                                );
-                                emit_queue_updated(&app_handle, &database);
-                            } else {
-                                emit_queue_full(&app_handle);
-                            }
-                        }
-                    }
-                } else {
-                    // Normal transcription mode
-                    // Save last transcription
-                    *last_transcription.lock().unwrap() = Some(transcribed_text.clone());
-
-                    // Save to database
-                    let timestamp = std::time::SystemTime::now()
-                        .duration_since(std::time::UNIX_EPOCH)
-                        .unwrap()
-                        .as_millis() as i64;
-                    let cost = estimate_cost_cents("whisper", duration_ms, &transcribed_text);
-
-                    if let Err(e) = database.save_transcription(&transcribed_text, timestamp, duration_ms, Some("whisper"), Some(cost), Some("transcription")) {
-                        eprintln!("❌ Failed to save to database: {}", e);
-                    }
+fn notification_enabled(database: &db::Database, kind: NotificationKind) -> bool {
+    database.load_setting(kind.setting_key()).ok().flatten().map(|v| v == "true").unwrap_or(true)
+}
 
-                    // Notify frontend that history was updated
-                    if let Some(window) = app_handle.get_webview_window("main") {
-                        let _ = window.emit("history-updated", ());
-                    }
+/// Show a Windows toast unless the user disabled this kind of notification
+fn notify(app: &AppHandle, kind: NotificationKind, title: &str, body: &str) {
+    if let Some(state) = app.try_state::<AppState>() {
+        if !notification_enabled(&state.database, kind) {
+            return;
+        }
+    }
+    if let Err(e) = app.notification().builder().title(title).body(body).show() {
+        eprintln!("⚠️ Failed to show notification: {}", e);
+    }
+}
 
-                    // Auto-paste: save clipboard, paste, restore
-                    match auto_paste_text(&app_handle, &transcribed_text) {
-                        Ok(_) => println!("✅ Text auto-pasted successfully"),
-                        Err(e) => {
-                            eprintln!("⚠️ Auto-paste failed: {}", e);
-                            // Notify frontend of failure
-                            if let Some(window) = app_handle.get_webview_window("main") {
-                                let _ = window.emit("paste-failed", ());
-                            }
-                        }
-                    }
+/// Auto-paste failed: leave the text in the clipboard so the user can paste it by hand
+fn report_paste_failure(app: &AppHandle, session_id: Option<SessionId>, text: &str, error: &str) {
+    // Already notified, and retrying would only be refused again
+    if error == PASSWORD_FIELD_REFUSED {
+        return;
+    }
+    match session_id {
+        Some(id) => eslog!(id, "⚠️ Auto-paste failed: {}", error),
+        None => eprintln!("⚠️ Auto-paste failed: {}", error),
+    }
+    let in_clipboard = app.clipboard().write_text(text).is_ok();
+    if let Some(state) = app.try_state::<AppState>() {
+        *state.failed_paste.lock().unwrap() = Some(text.to_string());
+    }
+    refresh_tray_menu(app); // Offers "Tentar colar de novo"
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = emit_app_event(&window, AppEvent::PasteFailed { session_id, text: text.to_string() });
+    }
+    let body = if in_clipboard {
+        "Não foi possível colar. O texto está na área de transferência (Ctrl+V), ou clique em \"Tentar colar de novo\" no ícone da bandeja."
+    } else {
+        "Não foi possível colar. Clique em \"Tentar colar de novo\" no ícone da bandeja."
+    };
+    notify(app, NotificationKind::PasteFailed, "Dicta - Falha ao colar", body);
+}
 
-                    // Notification sound
-                    if let Some(window) = app_handle.get_webview_window("main") {
-                        let _ = window.emit("response-ready", ());
-                    }
+/// Paste the text of the last failed auto-paste again (failure banner, tray)
+fn retry_failed_paste(app: &AppHandle) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let text = state.failed_paste.lock().unwrap().clone()
+        .ok_or("No failed paste to retry")?;
 
-                    // TTS skipped for transcribe-only (would just repeat what user said)
-                }
-            }
-            Err(e) => {
-                eprintln!("❌ Transcription error: {}", e);
-                let count = database.count_queue().unwrap_or(0);
-                if count < queue::MAX_QUEUE_SIZE {
-                    let mode = if prompt_mode.is_some() { "whisper-prompt" } else { "whisper-transcribe" };
-                    let model_name = prompt_mode.as_deref().unwrap_or("whisper");
-                    match queue::save_audio_to_wav(audio_data_for_queue, &queue_dir) {
-                        Ok(wav_path) => {
-                            let _ = database.enqueue_item(
-                                mode,
-                                Some(wav_path.to_str().unwrap_or("")),
-                                None,
-                                model_name,
-                                now_ms(),
-                            );
-                            emit_queue_updated(&app_handle, &database);
-                        }
-                        Err(wav_err) => eprintln!("❌ Failed to save audio to queue: {}", wav_err),
-                    }
-                } else {
-                    emit_queue_full(&app_handle);
-                }
-            }
+    // Clicked in the main window, the paste would land in Dicta itself: get it out of the way
+    // so focus goes back to the app the text was meant for
+    if let Some(window) = app.get_webview_window("main") {
+        if window.is_focused().unwrap_or(false) {
+            let _ = window.minimize();
         }
-    });
+    }
 
-    Ok("Recording stopped, processing...".to_string())
+    match auto_paste_text(app, &text) {
+        Ok(()) => {
+            println!("✅ Failed paste retried successfully");
+            *state.failed_paste.lock().unwrap() = None;
+            refresh_tray_menu(app);
+            Ok(())
+        }
+        Err(e) => {
+            report_paste_failure(app, None, &text, &e);
+            Err(e)
+        }
+    }
 }
 
 #[tauri::command]
-fn get_last_transcription(state: State<'_, AppState>) -> Result<String, String> {
-    let last = state.last_transcription.lock().unwrap();
-    match &*last {
-        Some(text) => Ok(text.clone()),
-        None => Err("No transcription available".to_string()),
+async fn retry_last_paste(app: AppHandle) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || retry_failed_paste(&app))
+        .await
+        .map_err(|e| format!("Paste task failed: {}", e))?
+}
+
+/// Tell the main window a result was delivered (it plays the notification sound), unless it's quiet time
+/// Tell the main window the conversation changed (session_id is None outside a session)
+fn emit_conversation_updated(app: &AppHandle, session_id: Option<SessionId>) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = emit_app_event(&window, AppEvent::ConversationUpdated { session_id });
     }
 }
 
-#[tauri::command]
-fn get_transcription_history(state: State<'_, AppState>) -> Result<Vec<TranscriptionEntry>, String> {
-    state.database.load_transcriptions()
-        .map_err(|e| format!("Failed to load history: {}", e))
+fn emit_response_ready(app: &AppHandle, session_id: Option<SessionId>) {
+    if is_quiet_time(app) {
+        return;
+    }
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = emit_app_event(&window, AppEvent::ResponseReady { session_id });
+    }
+}
+
+/// Quiet hours: sounds and TTS auto-play are suppressed, transcription and pasting still work
+fn is_quiet_time(app: &AppHandle) -> bool {
+    let Some(state) = app.try_state::<AppState>() else { return false };
+    let settings = load_quiet_hours(&state.database);
+    let scheduled = match (&settings.start, &settings.end) {
+        (Some(start), Some(end)) => {
+            let parse = |t: &str| chrono::NaiveTime::parse_from_str(t, "%H:%M").ok();
+            match (parse(start), parse(end)) {
+                (Some(start), Some(end)) => quiet_hours::in_window(chrono::Local::now().time(), start, end),
+                _ => false,
+            }
+        }
+        _ => false,
+    };
+    scheduled || (settings.follow_system && quiet_hours::system_do_not_disturb())
+}
+
+/// Timeout settings in seconds; "0" means no limit, missing means the default
+fn load_request_timeouts(database: &db::Database) -> openai::RequestTimeouts {
+    let defaults = openai::RequestTimeouts::default();
+    let load = |key: &str, default: Option<u64>| match database.load_setting(key).ok().flatten().and_then(|v| v.parse::<u64>().ok()) {
+        Some(0) => None,
+        Some(secs) => Some(secs),
+        None => default,
+    };
+    openai::RequestTimeouts {
+        transcription_secs: load("timeout_transcription_secs", defaults.transcription_secs),
+        response_secs: load("timeout_response_secs", defaults.response_secs),
+        tts_secs: load("timeout_tts_secs", defaults.tts_secs),
+    }
 }
 
 #[tauri::command]
-fn copy_to_clipboard(app: AppHandle, text: String) -> Result<(), String> {
-    app.clipboard().write_text(text)
-        .map_err(|e| format!("Failed to write to clipboard: {}", e))
+fn get_request_timeouts(state: State<'_, AppState>) -> Result<openai::RequestTimeouts, String> {
+    Ok(load_request_timeouts(&state.database))
 }
 
 #[tauri::command]
-fn set_use_realtime(state: State<'_, AppState>, use_realtime: bool) -> Result<(), String> {
-    *state.use_realtime.lock().unwrap() = use_realtime;
-    println!("🔄 Switched to {} mode", if use_realtime { "Realtime" } else { "Whisper" });
+fn set_request_timeouts(state: State<'_, AppState>, timeouts: openai::RequestTimeouts) -> Result<(), String> {
+    let values = [
+        ("timeout_transcription_secs", timeouts.transcription_secs),
+        ("timeout_response_secs", timeouts.response_secs),
+        ("timeout_tts_secs", timeouts.tts_secs),
+    ];
+    for (key, secs) in values {
+        state.database.save_setting(key, &secs.unwrap_or(0).to_string())
+            .map_err(|e| format!("Failed to save request timeouts: {}", e))?;
+    }
+    state.openai_client.set_timeouts(timeouts);
     Ok(())
 }
 
+/// Whisper decoding settings; a missing or empty no-speech threshold keeps every segment
+fn load_whisper_decoding(database: &db::Database) -> openai::WhisperDecoding {
+    let load = |key: &str| database.load_setting(key).ok().flatten();
+    let defaults = openai::WhisperDecoding::default();
+    openai::WhisperDecoding {
+        temperature: load("whisper_temperature")
+            .and_then(|v| v.parse::<f32>().ok())
+            .map(|t| t.clamp(0.0, 1.0))
+            .unwrap_or(defaults.temperature),
+        temperature_sweep: load("whisper_temperature_sweep").map(|v| v == "true").unwrap_or(defaults.temperature_sweep),
+        no_speech_threshold: load("whisper_no_speech_threshold")
+            .and_then(|v| v.parse::<f32>().ok())
+            .map(|t| t.clamp(0.0, 1.0)),
+        confidence_threshold: load("whisper_confidence_threshold")
+            .and_then(|v| v.parse::<f32>().ok())
+            .map(|t| t.clamp(0.0, 1.0))
+            .unwrap_or(defaults.confidence_threshold),
+        keep_low_confidence: load("whisper_keep_low_confidence").map(|v| v == "true").unwrap_or(defaults.keep_low_confidence),
+    }
+}
+
 #[tauri::command]
-fn get_use_realtime(state: State<'_, AppState>) -> Result<bool, String> {
-    Ok(*state.use_realtime.lock().unwrap())
+fn get_whisper_decoding(state: State<'_, AppState>) -> Result<openai::WhisperDecoding, String> {
+    Ok(load_whisper_decoding(&state.database))
 }
 
 #[tauri::command]
-fn list_microphones() -> Result<Vec<String>, String> {
-    use cpal::traits::{DeviceTrait, HostTrait};
+fn set_whisper_decoding(state: State<'_, AppState>, decoding: openai::WhisperDecoding) -> Result<(), String> {
+    let values = [
+        ("whisper_temperature", decoding.temperature.to_string()),
+        ("whisper_temperature_sweep", decoding.temperature_sweep.to_string()),
+        ("whisper_no_speech_threshold", decoding.no_speech_threshold.map(|t| t.to_string()).unwrap_or_default()),
+        ("whisper_confidence_threshold", decoding.confidence_threshold.to_string()),
+        ("whisper_keep_low_confidence", decoding.keep_low_confidence.to_string()),
+    ];
+    for (key, value) in values {
+        state.database.save_setting(key, &value)
+            .map_err(|e| format!("Failed to save Whisper decoding settings: {}", e))?;
+    }
+    state.openai_client.set_whisper_decoding(load_whisper_decoding(&state.database));
+    Ok(())
+}
 
-    let host = cpal::default_host();
-    let devices: Vec<String> = host
-        .input_devices()
-        .map_err(|e| format!("Failed to get input devices: {}", e))?
-        .filter_map(|device| device.name().ok())
-        .collect();
+/// Maximum number of pending items in the offline queue
+fn queue_max_items(database: &db::Database) -> i64 {
+    database.load_setting("queue_max_items").ok().flatten()
+        .and_then(|v| v.parse::<i64>().ok())
+        .filter(|&n| n > 0)
+        .unwrap_or(queue::DEFAULT_MAX_QUEUE_SIZE)
+}
 
-    Ok(devices)
+/// Disk budget for queued WAVs, in MB
+fn queue_max_disk_mb(database: &db::Database) -> u64 {
+    database.load_setting("queue_max_disk_mb").ok().flatten()
+        .and_then(|v| v.parse::<u64>().ok())
+        .filter(|&mb| mb > 0)
+        .unwrap_or(queue::DEFAULT_MAX_DISK_MB)
 }
 
-#[tauri::command]
-fn set_selected_microphone(state: State<'_, AppState>, device_name: String) -> Result<(), String> {
-    state.database.save_setting("selected_microphone", &device_name)
-        .map_err(|e| format!("Failed to save microphone setting: {}", e))?;
-    println!("🎤 Selected microphone: {}", device_name);
-    Ok(())
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct QueueLimits {
+    max_items: i64,
+    max_disk_mb: u64,
 }
 
 #[tauri::command]
-fn get_selected_microphone(state: State<'_, AppState>) -> Result<Option<String>, String> {
-    state.database.load_setting("selected_microphone")
-        .map_err(|e| format!("Failed to load microphone setting: {}", e))
+fn get_queue_limits(state: State<'_, AppState>) -> Result<QueueLimits, String> {
+    Ok(QueueLimits {
+        max_items: queue_max_items(&state.database),
+        max_disk_mb: queue_max_disk_mb(&state.database),
+    })
 }
 
 #[tauri::command]
-fn set_selected_prompt_model(state: State<'_, AppState>, model: String, save_as_default: Option<bool>) -> Result<(), String> {
-    // Save as current session model
-    state.database.save_setting("selected_prompt_model", &model)
-        .map_err(|e| format!("Failed to save prompt model setting: {}", e))?;
-
-    // Only save as user_prompt_model (for Ctrl+Shift+Space) when explicitly requested
-    // This prevents Ctrl+B or Ctrl+Alt+Space from overwriting the user's preferred model
-    if save_as_default.unwrap_or(false) && model != "transcribe-only" {
-        state.database.save_setting("user_prompt_model", &model)
-            .map_err(|e| format!("Failed to save user prompt model: {}", e))?;
-        println!("💾 Selected prompt model: {} (also saved as user_prompt_model)", model);
-    } else {
-        println!("💾 Selected prompt model: {} (user_prompt_model unchanged)", model);
+fn set_queue_limits(state: State<'_, AppState>, app: AppHandle, limits: QueueLimits) -> Result<(), String> {
+    if limits.max_items < 1 || limits.max_disk_mb < 1 {
+        return Err("Queue limits must be at least 1".to_string());
     }
+    state.database.save_setting("queue_max_items", &limits.max_items.to_string())
+        .map_err(|e| format!("Failed to save queue limits: {}", e))?;
+    state.database.save_setting("queue_max_disk_mb", &limits.max_disk_mb.to_string())
+        .map_err(|e| format!("Failed to save queue limits: {}", e))?;
+    cleanup_queue_files(&app, &state.database, &state.queue_dir);
     Ok(())
 }
 
+/// Remove orphaned queue WAVs and enforce the disk budget; items whose audio had to go
+/// are dropped from the queue as well
+fn cleanup_queue_files(app: &AppHandle, database: &db::Database, queue_dir: &std::path::Path) {
+    let items = match database.load_queue() {
+        Ok(items) => items,
+        Err(e) => {
+            eprintln!("⚠️ Queue cleanup skipped, failed to load queue: {}", e);
+            return;
+        }
+    };
+    let referenced: Vec<String> = items.iter().filter_map(|item| item.audio_path.clone()).collect();
+    let evicted = queue::cleanup_dir(queue_dir, &referenced, queue_max_disk_mb(database) * 1024 * 1024);
+    if evicted.is_empty() {
+        return;
+    }
+    for item in items.iter().filter(|item| item.audio_path.as_ref().is_some_and(|p| evicted.contains(p))) {
+        let _ = database.delete_queue_item(item.id);
+    }
+    emit_queue_updated(app, database);
+}
+
+/// Proxy for OpenAI traffic, None when no proxy URL is set
+fn load_proxy_settings(database: &db::Database) -> Option<proxy::ProxySettings> {
+    let url = database.load_setting("proxy_url").ok().flatten().filter(|u| !u.trim().is_empty())?;
+    Some(proxy::ProxySettings {
+        url,
+        username: database.load_setting("proxy_username").ok().flatten(),
+        password: database.load_setting("proxy_password").ok().flatten(),
+        bypass: database.load_setting("proxy_bypass").ok().flatten().unwrap_or_default(),
+    })
+}
+
 #[tauri::command]
-fn get_selected_prompt_model(state: State<'_, AppState>) -> Result<Option<String>, String> {
-    state.database.load_setting("selected_prompt_model")
-        .map_err(|e| format!("Failed to load prompt model setting: {}", e))
+fn get_proxy_settings(state: State<'_, AppState>) -> Result<proxy::ProxySettings, String> {
+    Ok(load_proxy_settings(&state.database).unwrap_or_default())
 }
 
+/// Save the proxy and apply it to new OpenAI requests and Realtime connections (empty URL = direct)
 #[tauri::command]
-fn get_current_recording_mode(state: State<'_, AppState>) -> Result<String, String> {
-    // Return the model that should be pre-selected based on current prompt_mode
-    let prompt_mode = state.prompt_mode.lock().unwrap().clone();
+fn set_proxy_settings(state: State<'_, AppState>, settings: proxy::ProxySettings) -> Result<(), String> {
+    if !settings.url.trim().is_empty() {
+        settings.validate()?;
+    }
 
-    let model = match prompt_mode.as_deref() {
-        Some("gpt-4o-mini") => "gpt-4o-mini".to_string(),
-        Some("gpt-4.1") => "gpt-4.1".to_string(),
-        None => "transcribe-only".to_string(),
-        Some(other) => {
-            println!("⚠️ Unknown prompt mode: {}, defaulting to transcribe-only", other);
-            "transcribe-only".to_string()
-        }
-    };
+    let values = [
+        ("proxy_url", Some(settings.url.trim().to_string())),
+        ("proxy_username", settings.username.clone()),
+        ("proxy_password", settings.password.clone()),
+        ("proxy_bypass", Some(settings.bypass.trim().to_string())),
+    ];
+    for (key, value) in values {
+        let result = match value.filter(|v| !v.is_empty()) {
+            Some(v) => state.database.save_setting(key, &v),
+            None => state.database.delete_setting(key),
+        };
+        result.map_err(|e| format!("Failed to save proxy settings: {}", e))?;
+    }
 
-    println!("📋 get_current_recording_mode: prompt_mode={:?}, returning model={}", prompt_mode, model);
-    Ok(model)
+    let proxy = load_proxy_settings(&state.database);
+    state.openai_client.set_proxy(proxy.as_ref());
+    state.realtime_client.set_proxy(proxy.clone());
+    match proxy {
+        Some(proxy) => println!("🌐 Proxy set to {}", proxy.url),
+        None => println!("🌐 Proxy disabled"),
+    }
+    Ok(())
 }
 
-// Removed start_pre_buffering - pre-buffering logic moved to audio capture
-
-/// Load conversation history, clearing it first if inactive for 30+ minutes.
-fn get_conversation_history(database: &db::Database) -> Vec<db::ConversationMessage> {
-    const INACTIVITY_MS: i64 = 30 * 60 * 1000; // 30 minutes
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+struct QuietHoursSettings {
+    start: Option<String>, // "HH:MM"; no window unless both are set
+    end: Option<String>,
+    /// Also be quiet while Windows suppresses notifications (Focus Assist, presentations, full-screen apps)
+    follow_system: bool,
+}
 
-    if let Ok(Some(last_ts)) = database.last_conversation_timestamp() {
-        let now_ms = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_millis() as i64;
-        if now_ms - last_ts > INACTIVITY_MS {
-            tlog!("🕐 Conversation inactive >30min, clearing history");
-            let _ = database.clear_conversation_history();
-            return vec![];
-        }
+fn load_quiet_hours(database: &db::Database) -> QuietHoursSettings {
+    QuietHoursSettings {
+        start: database.load_setting("quiet_hours_start").ok().flatten(),
+        end: database.load_setting("quiet_hours_end").ok().flatten(),
+        follow_system: database.load_setting("quiet_hours_follow_system").ok().flatten().is_some_and(|v| v == "true"),
     }
+}
 
-    database.load_conversation_history(6).unwrap_or_default()
+#[tauri::command]
+fn get_quiet_hours(state: State<'_, AppState>) -> Result<QuietHoursSettings, String> {
+    Ok(load_quiet_hours(&state.database))
 }
 
-fn now_ms() -> i64 {
-    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as i64
+#[tauri::command]
+fn set_quiet_hours(state: State<'_, AppState>, settings: QuietHoursSettings) -> Result<(), String> {
+    for (key, time) in [("quiet_hours_start", settings.start), ("quiet_hours_end", settings.end)] {
+        let result = match time.filter(|t| !t.trim().is_empty()) {
+            Some(t) => {
+                chrono::NaiveTime::parse_from_str(t.trim(), "%H:%M")
+                    .map_err(|_| format!("Invalid time '{}', expected HH:MM", t))?;
+                state.database.save_setting(key, t.trim())
+            }
+            None => state.database.delete_setting(key),
+        };
+        result.map_err(|e| format!("Failed to save quiet hours: {}", e))?;
+    }
+    state.database.save_setting("quiet_hours_follow_system", if settings.follow_system { "true" } else { "false" })
+        .map_err(|e| format!("Failed to save quiet hours: {}", e))
 }
 
-fn emit_queue_updated(app: &AppHandle, database: &db::Database) {
-    let count = database.count_queue().unwrap_or(0);
-    tlog!("Queue updated, {} items pending", count);
-    let _ = app.emit("queue-updated", count);
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+struct NotificationSettings {
+    paste_failed: bool,
+    paste_ready: bool,
+    queued: bool,
+    budget: bool,
+    voice_command: bool,
 }
 
-fn emit_queue_full(app: &AppHandle) {
-    tlog!("Queue full (3 items), dropping new item");
-    let _ = app.emit("queue-full", ());
+#[tauri::command]
+fn get_notification_settings(state: State<'_, AppState>) -> Result<NotificationSettings, String> {
+    Ok(NotificationSettings {
+        paste_failed: notification_enabled(&state.database, NotificationKind::PasteFailed),
+        paste_ready: notification_enabled(&state.database, NotificationKind::PasteReady),
+        queued: notification_enabled(&state.database, NotificationKind::Queued),
+        budget: notification_enabled(&state.database, NotificationKind::Budget),
+        voice_command: notification_enabled(&state.database, NotificationKind::VoiceCommand),
+    })
+}
+
+#[tauri::command]
+fn set_notification_settings(state: State<'_, AppState>, settings: NotificationSettings) -> Result<(), String> {
+    let values = [
+        (NotificationKind::PasteFailed, settings.paste_failed),
+        (NotificationKind::PasteReady, settings.paste_ready),
+        (NotificationKind::Queued, settings.queued),
+        (NotificationKind::Budget, settings.budget),
+        (NotificationKind::VoiceCommand, settings.voice_command),
+    ];
+    for (kind, enabled) in values {
+        state.database.save_setting(kind.setting_key(), if enabled { "true" } else { "false" })
+            .map_err(|e| format!("Failed to save notification settings: {}", e))?;
+    }
+    Ok(())
 }
 
 /// Estimate cost in hundredths of a cent based on model and usage
 fn estimate_cost_cents(model: &str, duration_ms: Option<i64>, text: &str) -> i64 {
     match model {
-        "whisper" | "realtime" => {
+        "whisper" | "realtime" | "gpt-4o-transcribe" => {
             // $0.006/min of audio
             let minutes = duration_ms.unwrap_or(0) as f64 / 60_000.0;
             (minutes * 0.006 * 10_000.0) as i64
         }
+        "gpt-4o-mini-transcribe" => {
+            // $0.003/min of audio
+            let minutes = duration_ms.unwrap_or(0) as f64 / 60_000.0;
+            (minutes * 0.003 * 10_000.0) as i64
+        }
         "gpt-4o-mini" => {
             // ~$0.60/1M output tokens, ~4 chars/token
             let tokens = text.len() as f64 / 4.0;
@@ -853,85 +4591,131 @@ fn estimate_cost_cents(model: &str, duration_ms: Option<i64>, text: &str) -> i64
     }
 }
 
-#[tauri::command]
-async fn send_text_prompt(state: State<'_, AppState>, app: AppHandle, prompt: String, model: String, image_data: Option<String>) -> Result<(), String> {
-    println!("{} 🤖 send_text_prompt called - model: {}, image: {}, prompt: {}", ts(), model, image_data.is_some(), prompt.chars().take(80).collect::<String>());
-
-    let openai = state.openai_client.clone();
-    let database = state.database.clone();
-    let last_transcription = state.last_transcription.clone();
-    let app_handle = app.clone();
-    let tts_enabled = state.tts_enabled.clone();
-    let tts_sink = state.tts_sink.clone();
-    let tts_stream_handle = state.tts_stream_handle.clone();
-    let tts_active = state.tts_active.clone();
-    let openai_for_tts = state.openai_client.clone();
-
-    // Load conversation history before spawning
-    let conv_history = get_conversation_history(&state.database);
-
-    tokio::spawn(async move {
-        match openai.send_prompt(&prompt, &model, &conv_history, image_data.as_deref()).await {
-            Ok(response) => {
-                println!("{} ✅ Text prompt response: {}", ts(), response.chars().take(80).collect::<String>());
-                let timestamp = std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .unwrap()
-                    .as_millis() as i64;
-
-                // Save to transcription history (for Alt+Shift+Z)
-                let cost = estimate_cost_cents(&model, None, &response);
-                if let Err(e) = database.save_transcription(&response, timestamp, None, Some(&model), Some(cost), Some("prompt")) {
-                    eprintln!("❌ Failed to save text prompt response: {}", e);
-                }
-
-                // Save to conversation history
-                let _ = database.append_conversation("user", &prompt, timestamp - 1);
-                let _ = database.append_conversation("assistant", &response, timestamp);
-
-                *last_transcription.lock().unwrap() = Some(response.clone());
+/// The newest session still waiting on the API, which abort_current_request can cancel
+struct InFlightRequest {
+    session_id: SessionId,
+    task: tokio::task::AbortHandle,
+    input: Option<String>, // Prompt or transcript being worked on, kept in history when cancelled
+    audio: Option<Vec<f32>>, // Recording still to be transcribed (48 kHz), archived when cancelled
+}
 
-                // Notify frontend to refresh history
-                if let Some(window) = app_handle.get_webview_window("main") {
-                    let _ = window.emit("history-updated", ());
-                }
+/// Text of the history entry left by a recording cancelled before it was transcribed
+const CANCELLED_RECORDING_TEXT: &str = "(gravação cancelada antes da transcrição)";
+
+/// Run a session's API calls and delivery as a task that abort_current_request can cancel
+fn spawn_in_flight<F>(state: &AppState, session_id: SessionId, input: Option<String>, audio: Option<Vec<f32>>, work: F)
+where
+    F: std::future::Future<Output = ()> + Send + 'static,
+{
+    let slot = state.in_flight.clone();
+    let task = tokio::spawn(async move {
+        work.await;
+        let mut in_flight = slot.lock().unwrap();
+        if in_flight.as_ref().is_some_and(|request| request.session_id == session_id) {
+            *in_flight = None;
+        }
+    });
+    *state.in_flight.lock().unwrap() = Some(InFlightRequest { session_id, task: task.abort_handle(), input, audio });
+}
 
-                // Auto-paste response
-                if let Err(e) = auto_paste_text(&app_handle, &response) {
-                    eprintln!("❌ Failed to paste text prompt response: {}", e);
-                }
+/// Mute or unmute the mic of the live session (or else the meeting) in progress without ending it
+fn toggle_mic_mute_inner(app: &AppHandle) -> Result<bool, String> {
+    let state = app.state::<AppState>();
+    if !*state.is_recording.lock().unwrap() || !*state.use_realtime.lock().unwrap() {
+        // A meeting captures the mic with its own streams
+        let meeting = state.meeting.lock().unwrap();
+        let meeting = meeting.as_ref().ok_or("No live session to mute")?;
+        let muted = !meeting.mic_muted();
+        meeting.set_mic_muted(muted);
+        println!("{} (meeting {})", if muted { "🔇 Mic muted" } else { "🎙️ Mic unmuted" }, meeting.id);
+        let _ = emit_app_event(app, AppEvent::MicMuteToggled { session_id: None, muted });
+        return Ok(muted);
+    }
+    let session_id = state.session_id.lock().unwrap().ok_or("No live session to mute")?;
+    let muted = {
+        let mut mute = state.mic_mute.lock().unwrap();
+        let muted = !mute.is_muted();
+        mute.set(muted);
+        muted
+    };
+    slog!(session_id, "{}", if muted { "🔇 Mic muted" } else { "🎙️ Mic unmuted" });
+    let _ = emit_app_event(app, AppEvent::MicMuteToggled { session_id: Some(session_id), muted });
+    Ok(muted)
+}
 
-                // Notify frontend that response is ready (for notification sound)
-                if let Some(window) = app_handle.get_webview_window("main") {
-                    let _ = window.emit("response-ready", ());
-                }
+#[tauri::command]
+fn toggle_mic_mute(app: AppHandle) -> Result<bool, String> {
+    toggle_mic_mute_inner(&app)
+}
 
-                // TTS (chunked)
-                if *tts_enabled.lock().unwrap() {
-                    tauri::async_runtime::spawn(play_tts_chunked(
-                        app_handle.clone(), response.clone(),
-                        openai_for_tts.clone(), tts_sink.clone(),
-                        tts_stream_handle.clone(), tts_active.clone(),
-                    ));
-                }
-            }
-            Err(e) => {
-                eprintln!("❌ Text prompt failed: {}", e);
-                let count = database.count_queue().unwrap_or(0);
-                if count < queue::MAX_QUEUE_SIZE {
-                    let _ = database.enqueue_item(
-                        "text-prompt",
-                        None,
-                        Some(&prompt),
-                        &model,
-                        now_ms(),
-                    );
-                    emit_queue_updated(&app_handle, &database);
-                } else {
-                    emit_queue_full(&app_handle);
+/// Cancel the session that is still waiting on the API (dropping the request mid-flight).
+/// What it was working on stays in the history, marked as cancelled. A recording that wasn't
+/// transcribed yet is archived with it, so it can still be transcribed from the history.
+fn abort_current_request_inner(app: &AppHandle) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let request = state.in_flight.lock().unwrap().take()
+        .filter(|request| !request.task.is_finished())
+        .ok_or("No request in progress")?;
+    request.task.abort();
+    let session_id = request.session_id;
+    slog!(session_id, "🛑 Request cancelled by the user");
+
+    let input = match (request.input.filter(|text| !text.trim().is_empty()), request.audio) {
+        (Some(input), _) => Some(input),
+        (None, Some(audio)) => {
+            // Already there when archive_session_audio is on
+            let name = session_id.to_string();
+            let archived = audio_archive::path(&state.archive_dir, &name).exists()
+                || match audio_archive::save_f32(&state.archive_dir, &name, &audio, 48000) {
+                    Ok(_) => true,
+                    Err(e) => {
+                        eslog!(session_id, "⚠️ Failed to archive the cancelled recording: {}", e);
+                        false
+                    }
+                };
+            archived.then(|| CANCELLED_RECORDING_TEXT.to_string())
+        }
+        (None, None) => None,
+    };
+    if let Some(input) = input {
+        match record_transcription(&state.database, Some(session_id), &input, now_ms(), None, None, None, Some("cancelled")) {
+            Ok(_) => {
+                if let Some(window) = app.get_webview_window("main") {
+                    let _ = emit_app_event(&window, AppEvent::HistoryUpdated { session_id: Some(session_id) });
                 }
             }
+            Err(e) => eslog!(session_id, "⚠️ Failed to save the cancelled request: {}", e),
         }
+    }
+    let _ = emit_app_event(app, AppEvent::RequestCancelled { session_id });
+    Ok(())
+}
+
+#[tauri::command]
+fn abort_current_request(app: AppHandle) -> Result<(), String> {
+    abort_current_request_inner(&app)
+}
+
+#[tauri::command]
+async fn send_text_prompt(state: State<'_, AppState>, app: AppHandle, prompt: String, model: String, image_data: Option<String>) -> Result<(), String> {
+    let session_id = SessionId::new();
+    slog!(session_id, "🤖 send_text_prompt called - model: {}, image: {}, prompt: {}", model, image_data.is_some(), prompt.chars().take(80).collect::<String>());
+
+    let pipeline = Pipeline::new(state.openai_client.clone(), AppOutput::new(&app), Some(session_id))
+        .in_order(&state.delivery_order);
+    let request = PromptRequest {
+        prompt: prompt.clone(),
+        model,
+        history: get_conversation_history(&state.database),
+        image: image_data,
+        duration_ms: None,
+        capture: None,
+        queue_mode: "text-prompt",
+        speak: true,
+        language: None, // Typed, not spoken
+    };
+    spawn_in_flight(&state, session_id, Some(prompt), None, async move {
+        pipeline.prompt_or_queue(request).await;
     });
 
     Ok(())
@@ -939,39 +4723,54 @@ async fn send_text_prompt(state: State<'_, AppState>, app: AppHandle, prompt: St
 
 #[tauri::command]
 async fn start_realtime_recording(state: State<'_, AppState>, app: AppHandle) -> Result<String, String> {
+    // Let the start cue finish before system audio gets muted
+    if !*state.is_recording.lock().unwrap() {
+        tokio::time::sleep(play_sound_cue(&app, sound_cues::Cue::Start)).await;
+    }
+
     let mut is_recording = state.is_recording.lock().unwrap();
     if *is_recording {
         return Err("Already recording".to_string());
     }
 
-    println!("🎤 Starting realtime transcription...");
+    let session_id = SessionId::new();
+    *state.session_id.lock().unwrap() = Some(session_id);
+    slog!(session_id, "🎤 Starting realtime transcription (session {})...", session_id);
     *is_recording = true;
+    update_tray_recording(&app, true);
 
-    // Mute system audio while recording (frontend already waited for start sound to finish)
-    if let Err(e) = system_audio::mute_system_audio() {
-        eprintln!("⚠️ Failed to mute system audio: {}", e);
+    // Mute system audio while recording (the start cue already finished)
+    if let Err(e) = silence_system_audio(&state.database) {
+        eslog!(session_id, "⚠️ Failed to mute system audio: {}", e);
     }
 
-    // Set recording start time
+    // Set recording start time, and remember where the result should be pasted
     *state.recording_start_time.lock().unwrap() = Some(Instant::now());
+    *state.paste_target.lock().unwrap() = active_window::foreground_handle();
 
     drop(is_recording);
 
     // Reset current session transcript and speech state
     *state.current_session_transcript.lock().unwrap() = String::new();
+    state.session_segments.lock().unwrap().clear();
+    if let Err(e) = state.database.clear_session_transcript_chunks() {
+        eprintln!("⚠️ Failed to clear old transcript chunks: {}", e);
+    }
     *state.speech_active.lock().unwrap() = false;
     *state.last_speech_end.lock().unwrap() = None;
     *state.last_transcription_time.lock().unwrap() = None;
+    *state.realtime_session.lock().unwrap() = None;
+    *state.mic_mute.lock().unwrap() = MicMute::default();
 
     // Get selected microphone from settings
-    let selected_mic = state.database.load_setting("selected_microphone")
-        .ok()
-        .flatten();
+    let selected_mic = preferred_microphone(&state.database);
+    let selected_mic = resolve_input_device(&app, &state.database, selected_mic);
 
-    println!("🔍 DEBUG: selected_mic from DB = {:?}", selected_mic);
+    slog!(session_id, "🔍 DEBUG: selected_mic from DB = {:?}", selected_mic);
 
     let realtime_client = state.realtime_client.clone();
     let current_session_transcript = state.current_session_transcript.clone();
+    let session_segments = state.session_segments.clone();
     let is_recording_flag = state.is_recording.clone();
     let recording_start = state.recording_start_time.clone();
     let speech_active_for_listener = state.speech_active.clone();
@@ -982,8 +4781,21 @@ async fn start_realtime_recording(state: State<'_, AppState>, app: AppHandle) ->
     let last_transcription_time_for_stop = state.last_transcription_time.clone();
     let app_handle = app.clone();
     let queue_dir_for_spawn = state.queue_dir.clone();
+    let archive_dir_for_spawn = state.archive_dir.clone();
     let database_for_spawn = state.database.clone();
     let stop_handle_state = state.streaming_stop_handle.clone();
+    let mic_mute = state.mic_mute.clone();
+    let sidetone_settings = sidetone::SidetoneSettings::load(&state.database);
+    let local_whisper_cache = state.local_whisper.clone();
+    let live_session_local = state.live_session_local.clone();
+    let realtime_session_slot = state.realtime_session.clone();
+    let idle_session_slot = state.idle_realtime_session.clone();
+    *live_session_local.lock().unwrap() = false;
+    let clip_meter = state.clip_meter.clone();
+    clip_meter.reset();
+    let session_stats = state.session_stats.clone();
+    session_stats.reset();
+    let exclusive_capture = state.database.load_setting("realtime_exclusive_mode").ok().flatten().as_deref() == Some("true");
 
     tokio::spawn(async move {
         // === 1. Start microphone FIRST (before WebSocket connect) ===
@@ -995,32 +4807,39 @@ async fn start_realtime_recording(state: State<'_, AppState>, app: AppHandle) ->
         let is_recording_for_audio = is_recording_flag.clone();
         let selected_mic_for_thread = selected_mic.clone();
         let stop_handle_state_for_thread = stop_handle_state.clone();
-
-        println!("🔍 DEBUG: selected_mic_for_thread = {:?}", selected_mic_for_thread);
+        let app_for_audio_thread = app_handle.clone();
+        slog!(session_id, "🔍 DEBUG: selected_mic_for_thread = {:?}", selected_mic_for_thread);
 
         std::thread::spawn(move || {
-            println!("🔍 DEBUG: Inside thread, selected_mic = {:?}", selected_mic_for_thread);
+            slog!(session_id, "🔍 DEBUG: Inside thread, selected_mic = {:?}", selected_mic_for_thread);
             let mut streaming_recorder = audio::StreamingAudioRecorder::new();
 
-            let mut local_audio_rx = match streaming_recorder.start_streaming(selected_mic_for_thread) {
+            let mut local_audio_rx = match streaming_recorder.start_streaming(selected_mic_for_thread, clip_meter, session_stats, exclusive_capture) {
                 Ok(rx) => rx,
                 Err(e) => {
-                    eprintln!("❌ Failed to start streaming: {}", e);
+                    eslog!(session_id, "❌ Failed to start streaming: {}", e);
                     *is_recording_for_audio.lock().unwrap() = false;
+                    update_tray_recording(&app_for_audio_thread, false);
                     return;
                 }
             };
 
             *stop_handle_state_for_thread.lock().unwrap() = Some(streaming_recorder.stop_handle());
+            let mut sidetone = sidetone::Sidetone::start(&sidetone_settings);
 
             loop {
                 if !*is_recording_for_audio.lock().unwrap() {
-                    println!("🛑 Audio thread detected stop signal");
+                    slog!(session_id, "🛑 Audio thread detected stop signal");
                     break;
                 }
 
                 match local_audio_rx.try_recv() {
+                    // Muted: nothing reaches the transcription, the queue buffer or the archive
+                    Ok(_) if mic_mute.lock().unwrap().is_muted() => {}
                     Ok(chunk) => {
+                        if let Some(sidetone) = &mut sidetone {
+                            sidetone.feed(chunk.iter().map(|&s| s as f32 / 32768.0), 24000);
+                        }
                         buffer_for_audio_thread.lock().unwrap().extend_from_slice(&chunk);
                         // audio_tx.send may fail if receiver is dropped (e.g. connect failed) — that's ok
                         let _ = audio_tx.send(chunk);
@@ -1029,69 +4848,146 @@ async fn start_realtime_recording(state: State<'_, AppState>, app: AppHandle) ->
                         std::thread::sleep(std::time::Duration::from_millis(10));
                     }
                     Err(tokio::sync::mpsc::error::TryRecvError::Disconnected) => {
-                        println!("🛑 Audio channel disconnected");
+                        slog!(session_id, "🛑 Audio channel disconnected");
                         break;
                     }
                 }
             }
 
             streaming_recorder.stop_streaming();
-            println!("🎤 Audio thread finished");
+            slog!(session_id, "🎤 Audio thread finished");
         });
 
         // Helper closure: save buffered audio to queue
         let save_buffer_to_queue = |buffer: &Arc<Mutex<Vec<i16>>>, queue_dir: &std::path::PathBuf, db: &Arc<crate::db::Database>, app: &AppHandle| {
             let buf = buffer.lock().unwrap();
             if !buf.is_empty() {
-                println!("💾 Saving {} samples of buffered audio to queue", buf.len());
+                slog!(session_id, "💾 Saving {} samples of buffered audio to queue", buf.len());
                 match queue::save_audio_i16_to_wav(&buf, 24000, queue_dir) {
                     Ok(wav_path) => {
                         let count = db.count_queue().unwrap_or(0);
-                        if count < queue::MAX_QUEUE_SIZE {
+                        if count < queue_max_items(db) {
                             let _ = db.enqueue_item(
                                 "realtime-audio",
                                 Some(wav_path.to_str().unwrap_or("")),
                                 None,
                                 "whisper",
                                 now_ms(),
+                                Some(&session_id.to_string()),
                             );
-                            emit_queue_updated(app, db);
+                            emit_item_queued(app, db);
                         } else {
                             emit_queue_full(app);
                             queue::delete_wav_file(wav_path.to_str().unwrap_or(""));
                         }
                     }
-                    Err(e) => eprintln!("❌ Failed to save audio buffer: {}", e),
+                    Err(e) => eslog!(session_id, "❌ Failed to save audio buffer: {}", e),
                 }
             }
         };
 
-        // === 2. Connect to Realtime API (mic is already recording) ===
-        match realtime_client.connect().await {
+        // === 2a. Local whisper.cpp backend (fully offline, no time limit) ===
+        let local_backend = {
+            let db = database_for_spawn.clone();
+            let cache = local_whisper_cache.clone();
+            tokio::task::spawn_blocking(move || local_whisper_backend(&db, &cache))
+                .await
+                .unwrap_or_else(|e| Err(format!("Model loading task failed: {}", e)))
+        };
+        match local_backend {
+            Ok(Some(whisper)) => {
+                slog!(session_id, "🧠 Using local whisper.cpp streaming backend");
+                *live_session_local.lock().unwrap() = true;
+                let language = database_for_spawn.load_setting("language")
+                    .ok()
+                    .flatten()
+                    .unwrap_or_else(|| openai::DEFAULT_LANGUAGE.to_string());
+                let app_for_local = app_handle.clone();
+                let database_for_local = database_for_spawn.clone();
+
+                let mut spotter = keyword_spotter(&database_for_spawn);
+                let mut tracker = realtime::SegmentTracker::default();
+                let mut utterances = 0;
+
+                let worker = tokio::task::spawn_blocking(move || {
+                    let mut streamer = local_whisper::LocalStreamer::new(whisper, language);
+                    // Same contract as the Realtime listener: accumulate, emit delta, mark completion
+                    let mut emit_delta = |delta: String| {
+                        slog!(session_id, "📝 Delta: {}", delta);
+                        append_session_transcript(&current_session_transcript, &database_for_local, session_id, &delta);
+                        // whisper.cpp emits whole utterances, so each delta is also a completed turn
+                        utterances += 1;
+                        let segment = tracker.completed(&format!("local-{}", utterances), &delta);
+                        session_segments.lock().unwrap().push(segment.clone());
+                        spotter.feed(&app_for_local, &delta);
+                        if let Some(window) = app_for_local.get_webview_window("main") {
+                            let _ = emit_app_event(&window, AppEvent::TranscriptionDelta { session_id, delta, segment });
+                        }
+                        *last_speech_end_for_listener.lock().unwrap() = Some(Instant::now());
+                        *last_transcription_time_for_listener.lock().unwrap() = Some(Instant::now());
+                    };
+
+                    // The channel closes when the audio thread exits (user stopped)
+                    while let Some(chunk) = audio_rx.blocking_recv() {
+                        if let Some(delta) = streamer.push(&chunk) {
+                            emit_delta(delta);
+                        }
+                        *speech_active_for_listener.lock().unwrap() = streamer.has_pending_speech();
+                    }
+
+                    if let Some(delta) = streamer.flush() {
+                        emit_delta(delta);
+                    }
+                    spotter.finish(&app_for_local);
+                    *speech_active_for_listener.lock().unwrap() = false;
+                });
+
+                if let Err(e) = worker.await {
+                    eslog!(session_id, "❌ Local transcription worker failed: {}", e);
+                }
+                slog!(session_id, "✅ Local session cleanup complete");
+                return;
+            }
+            Ok(None) => {}
+            Err(e) => {
+                eslog!(session_id, "⚠️ Local Whisper backend unavailable, falling back to Realtime API: {}", e);
+                if let Some(window) = app_handle.get_webview_window("main") {
+                    let _ = emit_app_event(&window, AppEvent::RecordingError { session_id, message: format!("Modelo local indisponível: {}", e) });
+                }
+            }
+        }
+
+        // === 2. Connect to Realtime API (mic is already recording), or reuse the session kept open by the last recording ===
+        let connected = match reuse_realtime_session(&idle_session_slot).await {
+            Some(session) => Ok(session),
+            None => realtime_client.connect().await.map(Arc::new),
+        };
+        match connected {
             Ok(session) => {
-                println!("✅ Connected to Realtime API");
+                slog!(session_id, "✅ Connected to Realtime API");
 
                 let connection_lost = Arc::new(Mutex::new(false));
                 let connection_lost_for_audio = connection_lost.clone();
 
-                // Configure session
-                if let Err(e) = session.configure_transcription().await {
-                    eprintln!("❌ Failed to configure session: {}", e);
+                // Configure session (again when reused: the settings may have changed)
+                if let Err(e) = session.configure_transcription(realtime_noise_reduction(&database_for_spawn)).await {
+                    eslog!(session_id, "❌ Failed to configure session: {}", e);
                     *is_recording_flag.lock().unwrap() = false;
-                    if let Err(ue) = system_audio::unmute_system_audio() {
-                        eprintln!("⚠️ Failed to unmute on error: {}", ue);
+                    update_tray_recording(&app_handle, false);
+                    if let Err(ue) = restore_system_audio() {
+                        eslog!(session_id, "⚠️ Failed to unmute on error: {}", ue);
                     }
                     // Wait briefly for audio thread to capture some samples before saving
                     tokio::time::sleep(tokio::time::Duration::from_millis(300)).await;
                     save_buffer_to_queue(&local_audio_buffer, &queue_dir_for_spawn, &database_for_spawn, &app_handle);
                     if let Some(window) = app_handle.get_webview_window("main") {
-                        let _ = window.emit("recording-error", format!("Falha na configuração: {}", e));
+                        let _ = emit_app_event(&window, AppEvent::RecordingError { session_id, message: format!("Falha na configuração: {}", e) });
                     }
                     return;
                 }
 
                 // Clone session for sending audio
-                let session_clone = Arc::new(session);
+                let session_clone = session;
                 let session_for_audio = session_clone.clone();
                 let session_for_commit = session_clone.clone();
 
@@ -1100,56 +4996,81 @@ async fn start_realtime_recording(state: State<'_, AppState>, app: AppHandle) ->
                     while let Some(audio_chunk) = audio_rx.recv().await {
                         let audio_bytes = audio::pcm_to_bytes(&audio_chunk);
                         if let Err(e) = session_for_audio.send_audio(&audio_bytes).await {
-                            eprintln!("❌ Failed to send audio: {}", e);
+                            eslog!(session_id, "❌ Failed to send audio: {}", e);
                             *connection_lost_for_audio.lock().unwrap() = true;
                             break;
                         }
                     }
-                    println!("🛑 Audio streaming finished");
+                    slog!(session_id, "🛑 Audio streaming finished");
                 });
 
                 // Clone for the event listener
                 let is_recording_flag_check = is_recording_flag.clone();
                 let app_for_listen = app_handle.clone();
+                let database_for_listen = database_for_spawn.clone();
+                let session_for_release = session_clone.clone();
+                let spotter = Arc::new(Mutex::new(keyword_spotter(&database_for_spawn)));
+                let spotter_for_listen = spotter.clone();
+                let mut tracker = realtime::SegmentTracker::default();
 
                 // Listen for transcription events with periodic stop check
                 let listen_task = tokio::spawn(async move {
                     let _ = session_clone
                         .listen_for_events(|event| match event {
                             realtime::TranscriptionEvent::Delta(delta) => {
-                                println!("📝 Delta: {}", delta.delta);
+                                slog!(session_id, "📝 Delta: {}", delta.delta);
 
                                 // Accumulate in session transcript
-                                current_session_transcript.lock().unwrap().push_str(&delta.delta);
+                                append_session_transcript(&current_session_transcript, &database_for_listen, session_id, &delta.delta);
+                                spotter_for_listen.lock().unwrap().feed(&app_for_listen, &delta.delta);
 
                                 // Emit delta to frontend for live display
+                                let segment = tracker.delta(&delta.item_id, &delta.delta);
                                 if let Some(window) = app_for_listen.get_webview_window("main") {
-                                    let _ = window.emit("transcription-delta", delta.delta.clone());
+                                    let _ = emit_app_event(&window, AppEvent::TranscriptionDelta { session_id, delta: delta.delta, segment });
                                 }
                             }
-                            realtime::TranscriptionEvent::Completed(_completed) => {
+                            realtime::TranscriptionEvent::Completed(completed) => {
                                 // Don't auto-paste on each VAD completion - wait for user to stop
-                                println!("✨ Turn completed (VAD detected pause)");
+                                slog!(session_id, "✨ Turn completed (VAD detected pause)");
+                                // The completed transcript replaces the turn's deltas in the preview
+                                let segment = tracker.completed(&completed.item_id, &completed.transcript);
+                                if let Some(window) = app_for_listen.get_webview_window("main") {
+                                    let _ = emit_app_event(&window, AppEvent::TranscriptionDelta { session_id, delta: String::new(), segment: segment.clone() });
+                                }
+                                if !segment.text.is_empty() {
+                                    session_segments.lock().unwrap().push(segment);
+                                }
                                 *last_transcription_time_for_listener.lock().unwrap() = Some(Instant::now());
+
+                                // The turn is transcribed, so the server can drop its audio.
+                                // Native prompt mode still needs every turn to answer them.
+                                if !native_prompt_wanted(&database_for_listen) {
+                                    let session = session_for_release.clone();
+                                    tokio::spawn(async move {
+                                        if let Err(e) = session.release_turn(&completed.item_id).await {
+                                            eslog!(session_id, "⚠️ Failed to release transcribed turn: {}", e);
+                                        }
+                                    });
+                                }
                             }
                             realtime::TranscriptionEvent::SpeechStarted => {
                                 *speech_active_for_listener.lock().unwrap() = true;
-                                println!("🗣️ Speech tracking: ACTIVE");
+                                slog!(session_id, "🗣️ Speech tracking: ACTIVE");
                             }
                             realtime::TranscriptionEvent::SpeechStopped => {
                                 *speech_active_for_listener.lock().unwrap() = false;
                                 *last_speech_end_for_listener.lock().unwrap() = Some(Instant::now());
-                                println!("🔇 Speech tracking: STOPPED");
+                                slog!(session_id, "🔇 Speech tracking: STOPPED");
                             }
                         })
                         .await;
                 });
 
                 // Poll for stop signal and check time limit
-                println!("👀 Monitoring for stop signal and time limit...");
+                slog!(session_id, "👀 Monitoring for stop signal and time limit...");
                 let app_for_warning = app_handle.clone();
-                let mut warning_shown = false;
-                let mut auto_stop_triggered = false;
+                let mut timer = RecordingTimer::new(&database_for_spawn, session_id, "realtime");
 
                 loop {
                     tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
@@ -1157,108 +5078,30 @@ async fn start_realtime_recording(state: State<'_, AppState>, app: AppHandle) ->
                     let still_recording = *is_recording_flag_check.lock().unwrap();
 
                     if !still_recording {
-                        println!("🛑 Stop signal detected (is_recording = false), waiting for last transcriptions...");
+                        slog!(session_id, "🛑 Stop signal detected (is_recording = false), waiting for last transcriptions...");
                         break;
                     }
 
                     // Check recording duration
                     if let Some(start_time) = *recording_start.lock().unwrap() {
-                        let elapsed = start_time.elapsed();
-
-                        // Show warning at 5 minutes
-                        if elapsed >= Duration::from_secs(5 * 60) && !warning_shown {
-                            warning_shown = true;
-                            println!("⚠️ [REALTIME] 5 seconds elapsed, showing warning...");
-                            println!("⚠️ [REALTIME] Elapsed time: {:?}", elapsed);
-
-                            if let Some(warning) = app_for_warning.get_webview_window("warning-widget") {
-                                println!("⚠️ [REALTIME] Found warning widget");
-
-                                if let Some(widget) = app_for_warning.get_webview_window("recording-widget") {
-                                    println!("⚠️ [REALTIME] Found recording widget");
-                                    if let Ok(widget_pos) = widget.outer_position() {
-                                        // Position warning above widget
-                                        let warning_x = widget_pos.x - 77; // Center warning above widget
-                                        let warning_y = widget_pos.y - 70; // 10px above widget
-                                        println!("⚠️ [REALTIME] Positioning warning at x:{}, y:{}", warning_x, warning_y);
-                                        match warning.set_position(PhysicalPosition::new(warning_x, warning_y)) {
-                                            Ok(_) => println!("⚠️ [REALTIME] ✅ Position set successfully"),
-                                            Err(e) => println!("⚠️ [REALTIME] ❌ Failed to set position: {}", e),
-                                        }
-                                    }
-                                } else {
-                                    println!("⚠️ [REALTIME] ❌ Recording widget not found for positioning");
-                                }
-
-                                match warning.show() {
-                                    Ok(_) => {
-                                        println!("⚠️ [REALTIME] ✅ Warning shown successfully");
-
-                                        // Auto-hide warning after 4 seconds
-                                        let warning_clone = warning.clone();
-                                        tokio::spawn(async move {
-                                            tokio::time::sleep(tokio::time::Duration::from_secs(4)).await;
-                                            println!("⚠️ [REALTIME] Auto-hiding warning after 4 seconds");
-                                            match warning_clone.hide() {
-                                                Ok(_) => println!("⚠️ [REALTIME] ✅ Warning auto-hidden successfully"),
-                                                Err(e) => println!("⚠️ [REALTIME] ❌ Failed to auto-hide warning: {}", e),
-                                            }
-                                        });
-                                    },
-                                    Err(e) => println!("⚠️ [REALTIME] ❌ Failed to show warning: {}", e),
-                                }
-                            } else {
-                                println!("⚠️ [REALTIME] ❌ Warning widget not found!");
-                            }
-                        }
-
-                        // Auto-stop at 6 minutes
-                        if elapsed >= Duration::from_secs(6 * 60) && !auto_stop_triggered {
-                            auto_stop_triggered = true;
-                            println!("⏰ [REALTIME] 6 minutes limit reached, auto-stopping...");
-                            println!("⏰ [REALTIME] Elapsed time: {:?}", elapsed);
-
-                            // DON'T set is_recording = false here - let the frontend's stopRecording() do it
-                            // This prevents the "Not recording" error
-
-                            // Emit event to frontend to trigger full stop (which handles transcription save, paste, etc)
-                            if let Some(window) = app_for_warning.get_webview_window("main") {
-                                println!("⏰ [REALTIME] Emitting widget-stop-recording event to frontend");
-                                match window.emit("widget-stop-recording", ()) {
-                                    Ok(_) => println!("⏰ [REALTIME] ✅ Event emitted successfully"),
-                                    Err(e) => println!("⏰ [REALTIME] ❌ Failed to emit event: {}", e),
-                                }
-                            }
-
-                            // Hide recording widget
-                            if let Some(widget) = app_for_warning.get_webview_window("recording-widget") {
-                                println!("⏰ [REALTIME] Found recording widget, hiding it");
-                                match widget.hide() {
-                                    Ok(_) => println!("⏰ [REALTIME] ✅ Widget hidden successfully"),
-                                    Err(e) => println!("⏰ [REALTIME] ❌ Failed to hide widget: {}", e),
-                                }
-                            } else {
-                                println!("⏰ [REALTIME] ❌ Recording widget not found!");
-                            }
-
-                            // DON'T break - let the loop continue until frontend calls stop
-                            // which will set is_recording = false and trigger the break at line 478
-                        }
+                        timer.tick(&app_for_warning, start_time.elapsed());
                     }
 
                     if listen_task.is_finished() {
-                        println!("🛑 Listen task finished unexpectedly (connection dropped)");
+                        slog!(session_id, "🛑 Listen task finished unexpectedly (connection dropped)");
                         audio_task.abort();
                         listen_task.abort();
+                        spotter.lock().unwrap().finish(&app_handle);
                         *is_recording_flag.lock().unwrap() = false;
-                        if let Err(ue) = system_audio::unmute_system_audio() {
-                            eprintln!("⚠️ Failed to unmute on connection drop: {}", ue);
+                        update_tray_recording(&app_handle, false);
+                        if let Err(ue) = restore_system_audio() {
+                            eslog!(session_id, "⚠️ Failed to unmute on connection drop: {}", ue);
                         }
                         save_buffer_to_queue(&local_audio_buffer, &queue_dir_for_spawn, &database_for_spawn, &app_handle);
                         if let Some(window) = app_handle.get_webview_window("main") {
-                            let _ = window.emit("recording-error", "Conexão perdida durante gravação".to_string());
+                            let _ = emit_app_event(&window, AppEvent::RecordingError { session_id, message: "Conexão perdida durante gravação".to_string() });
                         }
-                        println!("✅ Connection-drop cleanup complete");
+                        slog!(session_id, "✅ Connection-drop cleanup complete");
                         return;
                     }
                 }
@@ -1267,7 +5110,7 @@ async fn start_realtime_recording(state: State<'_, AppState>, app: AppHandle) ->
                 // 1. Force-commit the audio buffer so API processes whatever was in-flight
                 // 2. Wait for transcription.completed to arrive (not speech_stopped which may not come)
                 // 3. Timeout quickly if nothing was in-flight
-                println!("🎙️ Mic stopped, committing buffer and waiting for final transcription...");
+                slog!(session_id, "🎙️ Mic stopped, committing buffer and waiting for final transcription...");
 
                 let stop_time = Instant::now();
 
@@ -1278,9 +5121,9 @@ async fn start_realtime_recording(state: State<'_, AppState>, app: AppHandle) ->
 
                 if had_any_speech {
                     // Explicitly commit the buffer - forces API to transcribe whatever audio is buffered
-                    println!("{} 🔔 Committing audio buffer to force transcription of in-flight audio...", ts());
+                    slog!(session_id, "🔔 Committing audio buffer to force transcription of in-flight audio...");
                     if let Err(e) = session_for_commit.commit_audio().await {
-                        println!("⚠️ commit_audio failed (may be ok if VAD already committed): {}", e);
+                        slog!(session_id, "⚠️ commit_audio failed (may be ok if VAD already committed): {}", e);
                     }
 
                     // Wait for a NEW transcription.completed to arrive after our stop time
@@ -1300,43 +5143,57 @@ async fn start_realtime_recording(state: State<'_, AppState>, app: AppHandle) ->
                         };
 
                         if new_transcription_arrived {
-                            println!("{} ✅ Final transcription arrived ({:.0}ms after stop)", ts(), elapsed.as_millis());
+                            slog!(session_id, "✅ Final transcription arrived ({:.0}ms after stop)", elapsed.as_millis());
                             // Small buffer to ensure the text is accumulated
                             tokio::time::sleep(tokio::time::Duration::from_millis(150)).await;
                             break;
                         }
 
                         if elapsed > max_wait {
-                            println!("{} ⏱️ No new transcription after {:.0}ms - was speech fully sent before stop?", ts(), elapsed.as_millis());
+                            slog!(session_id, "⏱️ No new transcription after {:.0}ms - was speech fully sent before stop?", elapsed.as_millis());
                             break;
                         }
                     }
                 } else {
-                    println!("📭 No speech detected during recording, stopping immediately");
+                    slog!(session_id, "📭 No speech detected during recording, stopping immediately");
                 }
 
                 // Now abort the tasks
-                println!("🛑 Aborting audio and listen tasks...");
+                slog!(session_id, "🛑 Aborting audio and listen tasks...");
                 audio_task.abort();
                 listen_task.abort();
+                spotter.lock().unwrap().finish(&app_handle);
+
+                if archive_session_audio(&database_for_spawn) {
+                    let audio = local_audio_buffer.lock().unwrap();
+                    if let Err(e) = audio_archive::save_i16(&archive_dir_for_spawn, &session_id.to_string(), &audio, 24000) {
+                        eslog!(session_id, "⚠️ Failed to archive the session audio: {}", e);
+                    }
+                }
 
                 // If connection was lost during recording, save audio buffer to queue
                 if *connection_lost.lock().unwrap() {
-                    println!("⚠️ Connection was lost during recording, saving audio buffer to queue");
+                    slog!(session_id, "⚠️ Connection was lost during recording, saving audio buffer to queue");
                     save_buffer_to_queue(&local_audio_buffer, &queue_dir_for_spawn, &database_for_spawn, &app_handle);
+                } else if native_prompt_wanted(&database_for_spawn) {
+                    // The listener is stopped, so the stop handler can ask this session to answer
+                    slog!(session_id, "🤖 Keeping the session for a native prompt answer");
+                    *realtime_session_slot.lock().unwrap() = Some(session_for_commit.clone());
+                } else {
+                    park_realtime_session(&app_handle, session_for_commit.clone());
                 }
 
-                println!("✅ Session cleanup complete");
+                slog!(session_id, "✅ Session cleanup complete");
                 *is_recording_flag.lock().unwrap() = false;
             }
             Err(e) => {
-                eprintln!("❌ Failed to connect to Realtime API: {}", e);
+                eslog!(session_id, "❌ Failed to connect to Realtime API: {}", e);
                 // Mic is already recording — DON'T stop it yet.
                 // Keep recording locally so audio is saved to queue.
                 // Wait for user to press Ctrl+Space (which sets is_recording=false).
-                println!("📡 No connection — recording locally until user stops...");
+                slog!(session_id, "📡 No connection — recording locally until user stops...");
                 if let Some(window) = app_handle.get_webview_window("main") {
-                    let _ = window.emit("recording-offline", "Sem conexão — gravando localmente".to_string());
+                    let _ = emit_app_event(&window, AppEvent::RecordingOffline { session_id, message: "Sem conexão — gravando localmente".to_string() });
                 }
 
                 // Poll until the user stops recording
@@ -1348,12 +5205,12 @@ async fn start_realtime_recording(state: State<'_, AppState>, app: AppHandle) ->
                 }
 
                 // Now save the buffered audio to queue
-                if let Err(ue) = system_audio::unmute_system_audio() {
-                    eprintln!("⚠️ Failed to unmute on connect error: {}", ue);
+                if let Err(ue) = restore_system_audio() {
+                    eslog!(session_id, "⚠️ Failed to unmute on connect error: {}", ue);
                 }
                 save_buffer_to_queue(&local_audio_buffer, &queue_dir_for_spawn, &database_for_spawn, &app_handle);
                 if let Some(window) = app_handle.get_webview_window("main") {
-                    let _ = window.emit("recording-error", format!("Sem conexão — áudio salvo na fila: {}", e));
+                    let _ = emit_app_event(&window, AppEvent::RecordingError { session_id, message: format!("Sem conexão — áudio salvo na fila: {}", e) });
                 }
             }
         }
@@ -1364,23 +5221,25 @@ async fn start_realtime_recording(state: State<'_, AppState>, app: AppHandle) ->
 
 #[tauri::command]
 async fn stop_realtime_recording(state: State<'_, AppState>, app: AppHandle) -> Result<String, String> {
-    println!("📞 stop_realtime_recording called");
+    let session_id = state.session_id.lock().unwrap().unwrap_or_else(SessionId::new);
+    slog!(session_id, "📞 stop_realtime_recording called");
 
     {
         let mut is_recording = state.is_recording.lock().unwrap();
         if !*is_recording {
-            println!("⚠️ Not recording (is_recording already false)");
+            slog!(session_id, "⚠️ Not recording (is_recording already false)");
             // Defensive unmute in case spawn exited without unmuting
             drop(is_recording);
-            if let Err(e) = system_audio::unmute_system_audio() {
-                eprintln!("⚠️ Failed to defensive unmute: {}", e);
+            if let Err(e) = restore_system_audio() {
+                eslog!(session_id, "⚠️ Failed to defensive unmute: {}", e);
             }
             return Err("Not recording".to_string());
         }
 
-        println!("⏹️ Setting is_recording = false...");
+        slog!(session_id, "⏹️ Setting is_recording = false...");
         *is_recording = false;
-        println!("✅ is_recording is now false");
+        slog!(session_id, "✅ is_recording is now false");
+        update_tray_recording(&app, false);
     } // Drop lock before await
 
     // Immediately release the microphone (don't wait for audio thread to notice)
@@ -1389,18 +5248,20 @@ async fn stop_realtime_recording(state: State<'_, AppState>, app: AppHandle) ->
     }
 
     // Restore system audio
-    if let Err(e) = system_audio::unmute_system_audio() {
-        eprintln!("⚠️ Failed to unmute system audio: {}", e);
+    if let Err(e) = restore_system_audio() {
+        eslog!(session_id, "⚠️ Failed to unmute system audio: {}", e);
     }
+    play_sound_cue(&app, sound_cues::Cue::Stop);
 
-    // Capture recording duration for stats
+    // Capture recording duration for stats, without the time the mic was muted
+    let muted = state.mic_mute.lock().unwrap().muted_time();
     let duration_ms = state.recording_start_time.lock().unwrap()
-        .map(|start| start.elapsed().as_millis() as i64);
+        .map(|start| start.elapsed().saturating_sub(muted).as_millis() as i64);
 
     // Wait for the internal spawn task to finish cleanup.
     // The spawn signals completion by setting is_recording_flag=false (different from AppState.is_recording).
     // We wait up to 5s for the spawn to finish its commit+transcription wait.
-    println!("⏳ Waiting for final transcription after stop...");
+    slog!(session_id, "⏳ Waiting for final transcription after stop...");
     {
         let wait_start = Instant::now();
         let transcription_at_stop = state.last_transcription_time.lock().unwrap().clone();
@@ -1420,179 +5281,150 @@ async fn stop_realtime_recording(state: State<'_, AppState>, app: AppHandle) ->
                 };
 
                 if new_arrived {
-                    println!("{} ✅ Transcription received, reading transcript now", ts());
+                    slog!(session_id, "✅ Transcription received, reading transcript now");
                     tokio::time::sleep(tokio::time::Duration::from_millis(200)).await;
                     break;
                 }
 
                 if wait_start.elapsed() > max_wait {
-                    println!("{} ⏱️ Timeout waiting for transcription ({:.0}ms)", ts(), wait_start.elapsed().as_millis());
+                    slog!(session_id, "⏱️ Timeout waiting for transcription ({:.0}ms)", wait_start.elapsed().as_millis());
                     break;
                 }
             }
         } else {
-            println!("📭 No speech, proceeding immediately");
+            slog!(session_id, "📭 No speech, proceeding immediately");
         }
     }
 
     // Get accumulated transcript
-    println!("📝 Getting accumulated transcript...");
-    let transcript = state.current_session_transcript.lock().unwrap().clone();
-    println!("📝 Transcript length: {} characters", transcript.len());
+    slog!(session_id, "📝 Getting accumulated transcript...");
+    let mut transcript = take_session_transcript(&state, session_id);
+    slog!(session_id, "📝 Transcript length: {} characters", transcript.len());
+    let mut segments = std::mem::take(&mut *state.session_segments.lock().unwrap());
+    let mut original = None;
+    if realtime_text_repair(&state.database) {
+        let repaired = text_repair::repair(&transcript);
+        if repaired != transcript {
+            original = Some(std::mem::replace(&mut transcript, repaired));
+        }
+        for segment in &mut segments {
+            segment.text = text_repair::repair(&segment.text);
+        }
+    }
+    let screen_context = state.screen_context.lock().unwrap().take();
+    let selection_context = state.selection_context.lock().unwrap().take();
+    let dictation_style = state.dictation_style.lock().unwrap().take();
+    let clipping_pct = state.clip_meter.percentage();
+    let diagnostics = state.session_stats.snapshot();
 
     // Check selected model in database FIRST (allows changing model during any recording)
     let (should_use_prompt, selected_model) = {
         let mut pm = state.prompt_mode.lock().unwrap();
         let mode = pm.clone();
-        println!("🔍 DEBUG: prompt_mode at start of stop_realtime_recording = {:?}", mode);
+        slog!(session_id, "🔍 DEBUG: prompt_mode at start of stop_realtime_recording = {:?}", mode);
 
         // Always check the current selected model in database
         let current_model = state.database.load_setting("selected_prompt_model")
             .ok()
             .flatten()
             .unwrap_or_else(|| {
-                println!("⚠️ No model found in settings, defaulting to transcribe-only");
+                slog!(session_id, "⚠️ No model found in settings, defaulting to transcribe-only");
                 "transcribe-only".to_string()
             });
-        println!("🔍 DEBUG: Current selected model in database = '{}'", current_model);
+        slog!(session_id, "🔍 DEBUG: Current selected model in database = '{}'", current_model);
 
         *pm = None; // Clear for next recording
 
         // If model is "transcribe-only", treat as normal transcription (no prompt)
         if current_model == "transcribe-only" {
-            println!("📝 Model is 'transcribe-only' - will NOT send to GPT");
+            slog!(session_id, "📝 Model is 'transcribe-only' - will NOT send to GPT");
             (false, String::new())
         } else {
-            println!("🤖 Model is '{}' - WILL send to GPT (regardless of how recording started)", current_model);
+            slog!(session_id, "🤖 Model is '{}' - WILL send to GPT (regardless of how recording started)", current_model);
             (true, current_model)
         }
     };
 
-    println!("🎯 Final decision: should_use_prompt = {}, selected_model = '{}'", should_use_prompt, selected_model);
+    slog!(session_id, "🎯 Final decision: should_use_prompt = {}, selected_model = '{}'", should_use_prompt, selected_model);
+
+    if transcript.trim().is_empty() {
+        emit_no_speech(&app, session_id);
+        if should_use_prompt && realtime_native_prompt(&state.database) {
+            // Nothing to answer: the session kept for it goes to the next recording instead
+            let slot = state.realtime_session.clone();
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Some(session) = take_realtime_session(&slot).await {
+                    park_realtime_session(&app, session);
+                }
+            });
+        }
+    } else {
+        let pipeline = Pipeline::new(state.openai_client.clone(), AppOutput::new(&app), Some(session_id))
+            .in_order(&state.delivery_order);
+        let capture = Some(Capture { clipping_pct, diagnostics });
+        let transcript = pipeline.transcript(transcript);
 
-    if !transcript.is_empty() {
         // Check if we need to send to GPT first
         if should_use_prompt {
-            println!("🤖 [REALTIME] Prompt mode active with model: {}", selected_model);
-
-            // Load conversation history before spawning
-            let conv_history = get_conversation_history(&state.database);
-
-            // Send transcript as prompt to GPT
-            let openai = state.openai_client.clone();
-            let database = state.database.clone();
-            let last_transcription = state.last_transcription.clone();
-            let app_clone = app.clone();
-            let transcript_clone = transcript.clone();
-            let tts_enabled_rt = state.tts_enabled.clone();
-            let tts_sink_rt = state.tts_sink.clone();
-            let tts_handle_rt = state.tts_stream_handle.clone();
-            let tts_active_rt = state.tts_active.clone();
-            let openai_tts_rt = state.openai_client.clone();
-
-            tokio::spawn(async move {
-                match openai.send_prompt(&transcript_clone, &selected_model, &conv_history, None).await {
-                    Ok(gpt_response) => {
-                        println!("✨ GPT Response: {}", gpt_response);
-
-                        // Save GPT response to database (not the transcript)
-                        let timestamp = std::time::SystemTime::now()
-                            .duration_since(std::time::UNIX_EPOCH)
-                            .unwrap()
-                            .as_millis() as i64;
-
-                        let cost = estimate_cost_cents(&selected_model, duration_ms, &gpt_response);
-                        if let Err(e) = database.save_transcription(&gpt_response, timestamp, duration_ms, Some(&selected_model), Some(cost), Some("prompt")) {
-                            eprintln!("❌ Failed to save to database: {}", e);
-                        }
-
-                        // Save to conversation history
-                        let _ = database.append_conversation("user", &transcript_clone, timestamp - 1);
-                        let _ = database.append_conversation("assistant", &gpt_response, timestamp);
-
-                        // Update last transcription with GPT response
-                        *last_transcription.lock().unwrap() = Some(gpt_response.clone());
-
-                        // Notify frontend
-                        if let Some(window) = app_clone.get_webview_window("main") {
-                            let _ = window.emit("history-updated", ());
-                        }
-
-                        // Auto-paste GPT response
-                        match auto_paste_text(&app_clone, &gpt_response) {
-                            Ok(_) => println!("✅ GPT response auto-pasted"),
-                            Err(e) => eprintln!("⚠️ Auto-paste failed: {}", e),
-                        }
-
-                        // Notification sound
-                        if let Some(window) = app_clone.get_webview_window("main") {
-                            let _ = window.emit("response-ready", ());
-                        }
-
-                        // TTS (chunked)
-                        if *tts_enabled_rt.lock().unwrap() {
-                            tauri::async_runtime::spawn(play_tts_chunked(
-                                app_clone.clone(), gpt_response.clone(),
-                                openai_tts_rt.clone(), tts_sink_rt.clone(),
-                                tts_handle_rt.clone(), tts_active_rt.clone(),
-                            ));
-                        }
+            slog!(session_id, "🤖 [REALTIME] Prompt mode active with model: {}", selected_model);
+
+            // Native prompt mode answers from the session's audio. Screenshots and selections
+            // still go through the Responses API, which gets them along with the transcript;
+            // the session is then kept for the next recording.
+            let native_session = if realtime_native_prompt(&state.database) {
+                match take_realtime_session(&state.realtime_session).await {
+                    Some(session) if screen_context.is_some() || selection_context.is_some() => {
+                        park_realtime_session(&app, session);
+                        None
                     }
-                    Err(e) => {
-                        eprintln!("❌ GPT prompt error: {}", e);
-                        let count = database.count_queue().unwrap_or(0);
-                        if count < queue::MAX_QUEUE_SIZE {
-                            let _ = database.enqueue_item(
-                                "realtime-prompt",
-                                None,
-                                Some(&transcript_clone),
-                                &selected_model,
-                                now_ms(),
-                            );
-                            emit_queue_updated(&app_clone, &database);
-                        } else {
-                            emit_queue_full(&app_clone);
-                        }
+                    session => session,
+                }
+            } else {
+                None
+            };
+            let instructions = state.openai_client.system_prompt();
+            let app_for_prompt = app.clone();
+            let prompt = prompt_with_selection(&transcript, selection_context.as_deref());
+            let request = PromptRequest {
+                prompt: prompt.clone(),
+                model: selected_model,
+                history: get_conversation_history(&state.database),
+                image: screen_context,
+                duration_ms,
+                capture,
+                queue_mode: "realtime-prompt",
+                speak: true,
+                language: state.openai_client.language(),
+            };
+            spawn_in_flight(&state, session_id, Some(prompt), None, async move {
+                match native_session {
+                    Some(session) => {
+                        pipeline.prompt_realtime(request, &session, &instructions).await;
+                        park_realtime_session(&app_for_prompt, session);
                     }
+                    None => pipeline.prompt_or_queue(request).await,
                 }
             });
         } else {
-            // Normal mode: just paste the transcript
-            // Save to database (single entry for entire session)
-            let timestamp = std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_millis() as i64;
-
-            let cost = estimate_cost_cents("realtime", duration_ms, &transcript);
-            if let Err(e) = state.database.save_transcription(&transcript, timestamp, duration_ms, Some("realtime"), Some(cost), Some("transcription")) {
-                eprintln!("❌ Failed to save to database: {}", e);
-            }
-
-            // Update last transcription
-            *state.last_transcription.lock().unwrap() = Some(transcript.clone());
-
-            // Notify frontend
-            if let Some(window) = app.get_webview_window("main") {
-                let _ = window.emit("history-updated", ());
-            }
-
-            // Auto-paste the full session transcript
-            let app_clone = app.clone();
-            let text_clone = transcript.clone();
-            let app_for_sound = app.clone();
-            std::thread::spawn(move || {
-                match auto_paste_text(&app_clone, &text_clone) {
-                    Ok(_) => println!("✅ Session transcript auto-pasted"),
-                    Err(e) => eprintln!("⚠️ Auto-paste failed: {}", e),
-                }
-
-                // Notification sound
-                if let Some(window) = app_for_sound.get_webview_window("main") {
-                    let _ = window.emit("response-ready", ());
-                }
-
-                // TTS skipped for transcribe-only (would just repeat what user said)
+            // Normal mode: save and paste the full session transcript (single entry for the session)
+            let live_model = if *state.live_session_local.lock().unwrap() { "local-whisper" } else { "realtime" };
+            let delivery = Delivery {
+                text: transcript,
+                model: live_model.to_string(),
+                kind: DeliveryKind::Transcription,
+                prompt: None,
+                duration_ms,
+                capture,
+                speak: true,
+                segments,
+                language: state.openai_client.language(),
+                original,
+                words: Vec::new(),
+            };
+            let input = Some(delivery.text.clone());
+            spawn_in_flight(&state, session_id, input, None, async move {
+                pipeline.dictation(delivery, dictation_style).await;
             });
         }
     }
@@ -1601,8 +5433,8 @@ async fn stop_realtime_recording(state: State<'_, AppState>, app: AppHandle) ->
 }
 
 #[tauri::command]
-async fn get_statistics(state: State<'_, AppState>, from_ts: i64, to_ts: i64) -> Result<db::StatsData, String> {
-    state.database.get_stats(from_ts, to_ts)
+async fn get_statistics(state: State<'_, AppState>, from_ts: i64, to_ts: i64, language: Option<String>) -> Result<db::StatsData, String> {
+    state.database.get_stats(from_ts, to_ts, language.as_deref())
         .map_err(|e| format!("Failed to get stats: {}", e))
 }
 
@@ -1613,17 +5445,22 @@ fn get_tts_enabled(state: State<'_, AppState>) -> Result<bool, String> {
 
 #[tauri::command]
 fn set_tts_enabled(state: State<'_, AppState>, app: AppHandle, enabled: bool) -> Result<(), String> {
+    apply_tts_enabled(&app, &state, enabled)
+}
+
+/// Persist the TTS toggle and notify main window, recording widget, toast and tray
+fn apply_tts_enabled(app: &AppHandle, state: &AppState, enabled: bool) -> Result<(), String> {
     *state.tts_enabled.lock().unwrap() = enabled;
     state.database.save_setting("tts_enabled", if enabled { "true" } else { "false" })
         .map_err(|e| format!("Failed to save TTS setting: {}", e))?;
     println!("🔊 TTS {}", if enabled { "enabled" } else { "disabled" });
     // Notify frontend
     if let Some(window) = app.get_webview_window("main") {
-        let _ = window.emit("tts-toggled", enabled);
+        let _ = emit_app_event(&window, AppEvent::TtsToggled(enabled));
     }
     // Also notify recording widget
     if let Some(widget) = app.get_webview_window("recording-widget") {
-        let _ = widget.emit("tts-toggled", enabled);
+        let _ = emit_app_event(&widget, AppEvent::TtsToggled(enabled));
     }
     // Show toast
     if let Some(toast) = app.get_webview_window("tts-toast") {
@@ -1633,27 +5470,576 @@ fn set_tts_enabled(state: State<'_, AppState>, app: AppHandle, enabled: bool) ->
             let y = screen.height as i32 - 40 - 300;
             let _ = toast.set_position(PhysicalPosition::new(x, y));
         }
-        let _ = toast.emit("tts-toast-show", enabled);
+        let _ = emit_app_event(&toast, AppEvent::TtsToastShow(enabled));
         let _ = toast.show();
     }
-    Ok(())
+    refresh_tray_menu(app);
+    Ok(())
+}
+
+#[tauri::command]
+fn get_network_status(state: State<'_, AppState>) -> Result<bool, String> {
+    Ok(state.network.is_online())
+}
+
+/// Look for a newer build on the configured update channel
+#[tauri::command]
+async fn check_for_updates(state: State<'_, AppState>, app: AppHandle) -> Result<Option<updates::UpdateInfo>, String> {
+    let channel = updates::UpdateChannel::load(&state.database);
+    let update = updates::check(&app, channel).await?;
+    Ok(update.map(|update| updates::info(&update, channel)))
+}
+
+/// Download and install the newest build of the configured channel, then restart into it
+#[tauri::command]
+async fn install_update(state: State<'_, AppState>, app: AppHandle) -> Result<(), String> {
+    let channel = updates::UpdateChannel::load(&state.database);
+    let update = updates::check(&app, channel).await?
+        .ok_or_else(|| "Nenhuma atualização disponível".to_string())?;
+    println!("🔄 Installing update {} ({} channel)", update.version, channel.as_str());
+    updates::install(update).await?;
+    app.restart()
+}
+
+#[tauri::command]
+fn get_update_channel(state: State<'_, AppState>) -> Result<updates::UpdateChannel, String> {
+    Ok(updates::UpdateChannel::load(&state.database))
+}
+
+#[tauri::command]
+fn set_update_channel(state: State<'_, AppState>, channel: updates::UpdateChannel) -> Result<(), String> {
+    state.database.save_setting("update_channel", channel.as_str())
+        .map_err(|e| format!("Failed to save update channel: {}", e))?;
+    println!("🔄 Update channel: {}", channel.as_str());
+    Ok(())
+}
+
+#[tauri::command]
+fn get_incognito(state: State<'_, AppState>) -> Result<bool, String> {
+    Ok(is_incognito(&state.database))
+}
+
+#[tauri::command]
+fn set_incognito(state: State<'_, AppState>, app: AppHandle, enabled: bool) -> Result<(), String> {
+    apply_incognito(&app, &state, enabled)
+}
+
+/// Persist the incognito toggle and notify main window, recording widget and tray
+fn apply_incognito(app: &AppHandle, state: &AppState, enabled: bool) -> Result<(), String> {
+    state.database.save_setting("incognito", if enabled { "true" } else { "false" })
+        .map_err(|e| format!("Failed to save incognito setting: {}", e))?;
+    println!("🕶️ Incognito {}", if enabled { "enabled" } else { "disabled" });
+    for label in ["main", "recording-widget"] {
+        if let Some(window) = app.get_webview_window(label) {
+            let _ = emit_app_event(&window, AppEvent::IncognitoToggled(enabled));
+        }
+    }
+    refresh_tray_menu(app);
+    Ok(())
+}
+
+#[tauri::command]
+fn stop_tts_playback(state: State<'_, AppState>, app: AppHandle) -> Result<(), String> {
+    stop_tts(&state, &app);
+    Ok(())
+}
+
+#[tauri::command]
+fn get_spend_alert_threshold(state: State<'_, AppState>) -> Result<Option<i64>, String> {
+    let value = state.database.load_setting("spend_alert_threshold")
+        .map_err(|e| format!("Failed to load spend alert threshold: {}", e))?;
+    Ok(value.and_then(|v| v.parse::<i64>().ok()).filter(|t| *t > 0))
+}
+
+/// Set the daily spend alert threshold (hundredths of a cent). None or 0 disables alerts.
+#[tauri::command]
+fn set_spend_alert_threshold(state: State<'_, AppState>, app: AppHandle, threshold: Option<i64>) -> Result<(), String> {
+    let value = threshold.filter(|t| *t > 0).map(|t| t.to_string()).unwrap_or_default();
+    state.database.save_setting("spend_alert_threshold", &value)
+        .map_err(|e| format!("Failed to save spend alert threshold: {}", e))?;
+    // Allow a new alert today under the new threshold
+    state.database.save_setting("spend_alert_last_day", "")
+        .map_err(|e| format!("Failed to reset spend alert: {}", e))?;
+    println!("💸 Spend alert threshold: {:?}", threshold);
+    check_daily_spend(&app, &state.database);
+    Ok(())
+}
+
+// --- Settings profiles ---
+
+/// Setting keys captured by settings profiles (plus any key matching PROFILE_SETTING_PREFIXES)
+const PROFILE_SETTING_KEYS: &[&str] = &[
+    "selected_microphone",
+    "selected_microphone_id",
+    "microphone_priority",
+    "bluetooth_mic_fallback",
+    "sidetone_enabled",
+    "sidetone_volume",
+    "sidetone_latency_ms",
+    "realtime_exclusive_mode",
+    "review_before_paste",
+    "restore_focus_before_paste",
+    "paste_on_click",
+    "insertion_method",
+    "clipboard_history_exclusion",
+    "whisper_priming",
+    "whisper_temperature",
+    "whisper_temperature_sweep",
+    "whisper_no_speech_threshold",
+    "whisper_confidence_threshold",
+    "whisper_keep_low_confidence",
+    "transcription_model",
+    "whisper_chunked",
+    "realtime_backend",
+    "realtime_native_prompt",
+    "realtime_noise_reduction",
+    "language",
+    "user_prompt_model",
+    "selected_prompt_model",
+    "system_prompt",
+    "tts_voice",
+    "tts_speed",
+    "tts_model_voices",
+];
+const PROFILE_SETTING_PREFIXES: &[&str] = &["hotkey_", "ptt_", "widget_"];
+
+#[derive(Clone, serde::Serialize)]
+struct ApiServerSettings {
+    enabled: bool,
+    port: u16,
+    token: String,
+}
+
+fn load_api_server_settings(database: &db::Database) -> ApiServerSettings {
+    let token = match database.load_setting("api_server_token").ok().flatten() {
+        Some(token) => token,
+        None => {
+            let token = api_server::generate_token();
+            let _ = database.save_setting("api_server_token", &token);
+            token
+        }
+    };
+    ApiServerSettings {
+        enabled: database.load_setting("api_server_enabled").ok().flatten().as_deref() == Some("true"),
+        port: database.load_setting("api_server_port")
+            .ok()
+            .flatten()
+            .and_then(|v| v.parse::<u16>().ok())
+            .unwrap_or(api_server::DEFAULT_API_PORT),
+        token,
+    }
+}
+
+/// (Re)start or stop the local API server to match the saved settings
+async fn apply_api_server_settings(app: &AppHandle) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let running = state.api_server.lock().unwrap().take();
+    if let Some(server) = running {
+        server.stop().await;
+    }
+
+    let settings = load_api_server_settings(&state.database);
+    if !settings.enabled {
+        return Ok(());
+    }
+    let server = api_server::start(app.clone(), settings.port, settings.token).await?;
+    *state.api_server.lock().unwrap() = Some(server);
+    Ok(())
+}
+
+#[tauri::command]
+fn get_api_server_settings(state: State<'_, AppState>) -> Result<ApiServerSettings, String> {
+    Ok(load_api_server_settings(&state.database))
+}
+
+/// Enable/disable the localhost REST API (for AutoHotkey, Stream Deck, scripts)
+#[tauri::command]
+async fn set_api_server_settings(app: AppHandle, enabled: bool, port: u16) -> Result<(), String> {
+    if port < 1024 {
+        return Err("Port must be 1024 or higher".to_string());
+    }
+    {
+        let state = app.state::<AppState>();
+        state.database.save_setting("api_server_enabled", if enabled { "true" } else { "false" })
+            .map_err(|e| format!("Failed to save API server setting: {}", e))?;
+        state.database.save_setting("api_server_port", &port.to_string())
+            .map_err(|e| format!("Failed to save API server port: {}", e))?;
+    }
+    apply_api_server_settings(&app).await
+}
+
+/// Invalidate the current API token and issue a new one
+#[tauri::command]
+async fn regenerate_api_token(app: AppHandle) -> Result<String, String> {
+    let token = api_server::generate_token();
+    app.state::<AppState>().database.save_setting("api_server_token", &token)
+        .map_err(|e| format!("Failed to save API token: {}", e))?;
+    apply_api_server_settings(&app).await?;
+    Ok(token)
+}
+
+/// Settings shared across devices by sync (device-specific ones like the microphone and hotkeys stay local)
+const SYNC_SETTING_KEYS: &[&str] = &[
+    "review_before_paste",
+    "whisper_priming",
+    "transcription_model",
+    "language",
+    "user_prompt_model",
+    "system_prompt",
+    "watch_words",
+    "watch_words_notify",
+    "daily_digest_time",
+    "tts_duck_level",
+];
+
+fn load_sync_config(database: &db::Database) -> sync::SyncConfig {
+    database.load_setting("sync_config")
+        .ok()
+        .flatten()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+#[tauri::command]
+fn get_sync_config(state: State<'_, AppState>) -> Result<sync::SyncConfigView, String> {
+    Ok(load_sync_config(&state.database).view())
+}
+
+/// Empty `password`/`passphrase` keep the stored ones: set them only to change them
+#[tauri::command]
+fn set_sync_config(state: State<'_, AppState>, mut config: sync::SyncConfig) -> Result<(), String> {
+    config.keep_secrets(&load_sync_config(&state.database));
+    if config.enabled && (config.url.trim().is_empty() || config.passphrase.is_empty()) {
+        return Err("Sync needs a server URL and a passphrase".to_string());
+    }
+    if config.enabled && config.backend == sync::SyncBackend::S3 && config.bucket.trim().is_empty() {
+        return Err("S3 sync needs a bucket".to_string());
+    }
+    let json = serde_json::to_string(&config).map_err(|e| e.to_string())?;
+    state.database.save_setting("sync_config", &json)
+        .map_err(|e| format!("Failed to save sync config: {}", e))
+}
+
+/// Sync with the configured backend and apply anything that came from other devices
+async fn sync_now_inner(app: &AppHandle) -> Result<sync::SyncReport, String> {
+    let state = app.state::<AppState>();
+    let config = load_sync_config(&state.database);
+    if !config.enabled {
+        return Err("Sync is disabled".to_string());
+    }
+
+    let report = sync::run(&state.database, &state.archive_dir, &config, SYNC_SETTING_KEYS).await?;
+    let _ = state.database.save_setting("sync_last_at", &now_ms().to_string());
+
+    if report.settings_updated > 0 {
+        reload_runtime_settings(&state);
+    }
+    if report.downloaded > 0 || report.deleted > 0 {
+        if let Some(window) = app.get_webview_window("main") {
+            let _ = emit_app_event(&window, AppEvent::HistoryUpdated { session_id: None });
+        }
+        refresh_tray_menu(app);
+    }
+    Ok(report)
+}
+
+#[tauri::command]
+async fn sync_now(app: AppHandle) -> Result<sync::SyncReport, String> {
+    sync_now_inner(&app).await
+}
+
+/// Run a background sync when enabled and the interval has passed since the last one
+async fn check_periodic_sync(app: &AppHandle) {
+    let (enabled, interval, last) = {
+        let state = app.state::<AppState>();
+        let setting = |key: &str| state.database.load_setting(key).ok().flatten().and_then(|v| v.parse::<i64>().ok());
+        (
+            load_sync_config(&state.database).enabled,
+            setting("sync_interval_min").filter(|&m| m > 0).unwrap_or(sync::DEFAULT_SYNC_INTERVAL_MINS),
+            setting("sync_last_at").unwrap_or(0),
+        )
+    };
+    if !enabled || now_ms() - last < interval * 60 * 1000 {
+        return;
+    }
+    if let Err(e) = sync_now_inner(app).await {
+        eprintln!("⚠️ Background sync failed: {}", e);
+        // Wait a full interval before retrying
+        let _ = app.state::<AppState>().database.save_setting("sync_last_at", &now_ms().to_string());
+    }
+}
+
+/// Push persisted settings into in-memory runtime state
+fn reload_runtime_settings(state: &AppState) {
+    let language = state.database.load_setting("language").ok().flatten();
+    let system_prompt = state.database.load_setting("system_prompt").ok().flatten();
+    let transcription_model = state.database.load_setting("transcription_model").ok().flatten();
+    state.openai_client.apply_config(language, system_prompt, transcription_model);
+    state.openai_client.set_whisper_decoding(load_whisper_decoding(&state.database));
+}
+
+/// Switch to a saved profile: write its settings in one transaction, then rebuild runtime state.
+/// Holds the recording lock throughout so a recording can't start with half-applied settings.
+fn apply_profile(app: &AppHandle, state: &AppState, name: &str) -> Result<(), String> {
+    let is_recording = state.is_recording.lock().unwrap();
+    if *is_recording {
+        return Err("Não é possível trocar de perfil durante a gravação".to_string());
+    }
+
+    let profile = state.database.load_profile(name)
+        .map_err(|e| format!("Failed to load profile: {}", e))?
+        .ok_or_else(|| format!("Profile '{}' not found", name))?;
+
+    // Keys the profile doesn't define are cleared so nothing leaks from the previous profile
+    let current = state.database.snapshot_settings(PROFILE_SETTING_KEYS, PROFILE_SETTING_PREFIXES)
+        .map_err(|e| format!("Failed to read settings: {}", e))?;
+    let cleared: Vec<String> = PROFILE_SETTING_KEYS.iter()
+        .map(|k| k.to_string())
+        .chain(current.into_keys())
+        .filter(|k| !profile.settings.contains_key(k))
+        .collect();
+
+    let mut values = profile.settings.clone();
+    values.insert("active_profile".to_string(), profile.name.clone());
+    state.database.replace_settings(&values, &cleared)
+        .map_err(|e| format!("Failed to apply profile: {}", e))?;
+
+    reload_runtime_settings(state);
+    *state.prompt_mode.lock().unwrap() = None;
+    drop(is_recording);
+
+    state.shortcuts.load_all(app, &state.database);
+    apply_ptt_settings(app);
+
+    println!("👤 Switched to profile '{}'", profile.name);
+    let _ = emit_app_event(app, AppEvent::ProfileSwitched(profile.name.clone()));
+    refresh_tray_menu(app);
+    Ok(())
+}
+
+#[tauri::command]
+fn list_profiles(state: State<'_, AppState>) -> Result<Vec<db::SettingsProfile>, String> {
+    state.database.load_profiles()
+        .map_err(|e| format!("Failed to load profiles: {}", e))
+}
+
+/// Save the current settings as a named profile (overwrites an existing one)
+#[tauri::command]
+fn save_profile(state: State<'_, AppState>, app: AppHandle, name: String) -> Result<(), String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Profile name is empty".to_string());
+    }
+    let settings = state.database.snapshot_settings(PROFILE_SETTING_KEYS, PROFILE_SETTING_PREFIXES)
+        .map_err(|e| format!("Failed to read settings: {}", e))?;
+    state.database.save_profile(&name, &settings, now_ms())
+        .map_err(|e| format!("Failed to save profile: {}", e))?;
+    state.database.save_setting("active_profile", &name)
+        .map_err(|e| format!("Failed to save active profile: {}", e))?;
+    refresh_tray_menu(&app);
+    Ok(())
+}
+
+#[tauri::command]
+fn delete_profile(state: State<'_, AppState>, app: AppHandle, name: String) -> Result<(), String> {
+    state.database.delete_profile(&name)
+        .map_err(|e| format!("Failed to delete profile: {}", e))?;
+    if state.database.load_setting("active_profile").ok().flatten().as_deref() == Some(name.as_str()) {
+        let _ = state.database.delete_setting("active_profile");
+    }
+    refresh_tray_menu(&app);
+    Ok(())
+}
+
+#[tauri::command]
+fn switch_profile(state: State<'_, AppState>, app: AppHandle, name: String) -> Result<(), String> {
+    apply_profile(&app, &state, &name)
+}
+
+#[tauri::command]
+fn get_active_profile(state: State<'_, AppState>) -> Result<Option<String>, String> {
+    state.database.load_setting("active_profile")
+        .map_err(|e| format!("Failed to load active profile: {}", e))
+}
+
+#[tauri::command]
+fn get_language(state: State<'_, AppState>) -> Result<String, String> {
+    Ok(state.database.load_setting("language")
+        .map_err(|e| format!("Failed to load language: {}", e))?
+        .unwrap_or_else(|| openai::DEFAULT_LANGUAGE.to_string()))
+}
+
+/// Set the Whisper language hint (e.g. "pt", "en"). Empty string means auto-detect.
+#[tauri::command]
+fn set_language(state: State<'_, AppState>, language: String) -> Result<(), String> {
+    state.database.save_setting("language", language.trim())
+        .map_err(|e| format!("Failed to save language: {}", e))?;
+    reload_runtime_settings(&state);
+    Ok(())
+}
+
+#[tauri::command]
+fn get_system_prompt(state: State<'_, AppState>) -> Result<String, String> {
+    Ok(state.database.load_setting("system_prompt")
+        .map_err(|e| format!("Failed to load system prompt: {}", e))?
+        .unwrap_or_else(|| openai::DEFAULT_SYSTEM_PROMPT.to_string()))
+}
+
+/// Set the system prompt for prompt mode. None restores the default.
+#[tauri::command]
+fn set_system_prompt(state: State<'_, AppState>, prompt: Option<String>) -> Result<(), String> {
+    let result = match prompt.filter(|p| !p.trim().is_empty()) {
+        Some(p) => state.database.save_setting("system_prompt", &p),
+        None => state.database.delete_setting("system_prompt"),
+    };
+    result.map_err(|e| format!("Failed to save system prompt: {}", e))?;
+    reload_runtime_settings(&state);
+    Ok(())
+}
+
+// --- Tray ---
+
+/// Build the tray menu. Called at startup and whenever its dynamic parts change.
+fn build_tray_menu(app: &AppHandle, recording: bool) -> tauri::Result<Menu<tauri::Wry>> {
+    let toggle_item = MenuItem::with_id(
+        app,
+        "toggle_dictation",
+        if recording { "Parar ditado" } else { "Iniciar ditado" },
+        true,
+        None::<&str>,
+    )?;
+    let prompt_item = MenuItem::with_id(app, "prompt_mode", "Modo prompt", !recording, None::<&str>)?;
+    let show_item = MenuItem::with_id(app, "show", "Abrir Dicta", true, None::<&str>)?;
+    let forget_item = MenuItem::with_id(app, "forget_last", "Esquecer último ditado", true, None::<&str>)?;
+    let quit_item = MenuItem::with_id(app, "quit", "Sair", true, None::<&str>)?;
+    let retry_paste_item = MenuItem::with_id(app, "retry_paste", "Tentar colar de novo", true, None::<&str>)?;
+
+    let state = app.try_state::<AppState>();
+    let failed_paste = state.as_ref().is_some_and(|s| s.failed_paste.lock().unwrap().is_some());
+    let meeting_active = state.as_ref().map(|s| s.meeting.lock().unwrap().is_some()).unwrap_or(false);
+    let meeting_item = MenuItem::with_id(
+        app,
+        "toggle_meeting",
+        if meeting_active { "Encerrar reunião" } else { "Iniciar reunião" },
+        true,
+        None::<&str>,
+    )?;
+    let tts_enabled = state.as_ref().map(|s| *s.tts_enabled.lock().unwrap()).unwrap_or(false);
+    let tts_item = CheckMenuItem::with_id(app, "toggle_tts", "Leitura em voz alta", true, tts_enabled, None::<&str>)?;
+    let incognito = state.as_ref().is_some_and(|s| is_incognito(&s.database));
+    let incognito_item = CheckMenuItem::with_id(app, "toggle_incognito", "Modo anônimo", true, incognito, None::<&str>)?;
+
+    // Last three history entries (click to paste)
+    let recent = state.as_ref()
+        .and_then(|s| s.database.load_recent_transcriptions(3).ok())
+        .unwrap_or_default();
+    let history_items = recent.iter()
+        .filter_map(|entry| entry.id.map(|id| (id, entry)))
+        .map(|(id, entry)| {
+            let mut label: String = entry.text.chars().take(40).collect();
+            if entry.text.chars().count() > 40 {
+                label.push('…');
+            }
+            MenuItem::with_id(app, format!("history:{}", id), label, true, None::<&str>)
+        })
+        .collect::<tauri::Result<Vec<_>>>()?;
+
+    let (profiles, active_profile) = match &state {
+        Some(state) => (
+            state.database.load_profiles().unwrap_or_default(),
+            state.database.load_setting("active_profile").ok().flatten(),
+        ),
+        None => (vec![], None),
+    };
+    let profile_items = profiles.iter()
+        .map(|p| CheckMenuItem::with_id(
+            app,
+            format!("profile:{}", p.name),
+            &p.name,
+            true,
+            active_profile.as_deref() == Some(p.name.as_str()),
+            None::<&str>,
+        ))
+        .collect::<tauri::Result<Vec<_>>>()?;
+    let profile_refs: Vec<&dyn IsMenuItem<tauri::Wry>> = profile_items.iter()
+        .map(|i| i as &dyn IsMenuItem<tauri::Wry>)
+        .collect();
+    let profiles_menu = Submenu::with_items(app, "Perfil", !profile_refs.is_empty(), &profile_refs)?;
+
+    let separator_actions = PredefinedMenuItem::separator(app)?;
+    let separator_history = PredefinedMenuItem::separator(app)?;
+
+    let mut items: Vec<&dyn IsMenuItem<tauri::Wry>> = vec![&toggle_item, &prompt_item, &meeting_item, &tts_item, &incognito_item, &separator_actions];
+    if failed_paste {
+        items.insert(0, &retry_paste_item);
+    }
+    if !history_items.is_empty() {
+        items.extend(history_items.iter().map(|i| i as &dyn IsMenuItem<tauri::Wry>));
+        items.push(&forget_item);
+        items.push(&separator_history);
+    }
+    items.push(&profiles_menu);
+    items.push(&show_item);
+    items.push(&quit_item);
+
+    Menu::with_items(app, &items)
+}
+
+/// Tray icon variant shown while recording: the app icon tinted red
+fn recording_tray_icon(app: &AppHandle) -> Option<Image<'static>> {
+    let icon = app.default_window_icon()?;
+    let mut rgba = icon.rgba().to_vec();
+    for px in rgba.chunks_exact_mut(4) {
+        px[0] = px[0].max(220);
+        px[1] /= 3;
+        px[2] /= 3;
+    }
+    Some(Image::new_owned(rgba, icon.width(), icon.height()))
 }
 
-#[tauri::command]
-fn stop_tts_playback(state: State<'_, AppState>, app: AppHandle) -> Result<(), String> {
-    // Cancel chunked playback loop
-    *state.tts_active.lock().unwrap() = false;
-    // Stop current sink
-    let mut sink_guard = state.tts_sink.lock().unwrap();
-    if let Some(sink) = sink_guard.take() {
-        sink.stop();
-        println!("🔇 TTS playback stopped");
+/// Update tray icon, tooltip and menu for the given recording state
+/// Bring the main window to the front (tray click, second app launch)
+fn show_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
     }
-    // Hide widget
-    if let Some(w) = app.get_webview_window("tts-widget") {
-        let _ = w.hide();
+}
+
+fn update_tray_recording(app: &AppHandle, recording: bool) {
+    let Some(tray) = app.tray_by_id("main-tray") else { return };
+
+    let icon = if recording {
+        recording_tray_icon(app)
+    } else {
+        app.default_window_icon().cloned()
+    };
+    let _ = tray.set_icon(icon);
+    let incognito = app.try_state::<AppState>().is_some_and(|state| is_incognito(&state.database));
+    let tooltip = match (recording, incognito) {
+        (true, true) => "Dicta - Gravando (modo anônimo)...",
+        (true, false) => "Dicta - Gravando...",
+        (false, true) => "Dicta - Modo anônimo",
+        (false, false) => "Dicta - Voice Transcription",
+    };
+    let _ = tray.set_tooltip(Some(tooltip));
+
+    match build_tray_menu(app, recording) {
+        Ok(menu) => {
+            let _ = tray.set_menu(Some(menu));
+        }
+        Err(e) => eprintln!("⚠️ Failed to rebuild tray menu: {}", e),
     }
-    Ok(())
+}
+
+/// Rebuild the tray menu with the current recording state.
+/// Must not be called while holding the is_recording lock.
+fn refresh_tray_menu(app: &AppHandle) {
+    let recording = app.try_state::<AppState>()
+        .map(|state| *state.is_recording.lock().unwrap())
+        .unwrap_or(false);
+    update_tray_recording(app, recording);
 }
 
 // --- Queue commands ---
@@ -1672,11 +6058,10 @@ fn get_queue_items(state: State<'_, AppState>) -> Result<Vec<db::PendingQueueIte
 async fn retry_pending_queue(state: State<'_, AppState>, app: AppHandle) -> Result<(), String> {
     let database = state.database.clone();
     let openai = state.openai_client.clone();
-    let last_transcription = state.last_transcription.clone();
     let app_handle = app.clone();
 
     tokio::spawn(async move {
-        process_retry_queue(database, openai, last_transcription, app_handle).await;
+        process_retry_queue(database, openai, app_handle).await;
     });
 
     Ok(())
@@ -1696,11 +6081,68 @@ async fn delete_single_queue_item(state: State<'_, AppState>, app: AppHandle, id
     Ok(())
 }
 
+/// "Oops, I dictated a password": remove the most recent dictation from history and conversation
+/// history, or from the offline queue if it never got transcribed (its WAV is overwritten first)
+fn forget_last_dictation(app: &AppHandle) -> Result<(), String> {
+    let state = app.try_state::<AppState>().ok_or("App state not available")?;
+    let database = &state.database;
+
+    let last_entry = database.load_recent_transcriptions(1)
+        .map_err(|e| format!("Failed to load history: {}", e))?
+        .into_iter()
+        .next();
+    let last_queued = database.load_queue()
+        .map_err(|e| format!("Failed to load queue: {}", e))?
+        .into_iter()
+        .max_by_key(|item| item.created_at);
+    let entry_timestamp = last_entry.as_ref().map_or(i64::MIN, |entry| entry.timestamp);
+
+    match (last_entry, last_queued) {
+        (_, Some(item)) if item.created_at > entry_timestamp => {
+            if let Some(ref path) = item.audio_path {
+                queue::scrub_wav_file(path);
+            }
+            database.delete_queue_item(item.id)
+                .map_err(|e| format!("Failed to delete queue item: {}", e))?;
+            emit_queue_updated(app, database);
+            println!("🧹 Forgot last dictation (queue item {})", item.id);
+        }
+        (Some(entry), _) => {
+            let id = entry.id.ok_or("History entry has no id")?;
+            let orphaned = database.delete_transcription(id)
+                .map_err(|e| format!("Failed to delete transcription: {}", e))?;
+            if let Some(session_id) = &orphaned {
+                audio_archive::delete(&state.archive_dir, session_id);
+            }
+            database.delete_conversation_exchange(entry.timestamp)
+                .map_err(|e| format!("Failed to delete conversation: {}", e))?;
+            emit_conversation_updated(app, None);
+            let mut last = state.last_transcription.lock().unwrap();
+            if last.as_deref() == Some(entry.text.as_str()) {
+                *last = None;
+            }
+            drop(last);
+            if let Some(window) = app.get_webview_window("main") {
+                let _ = emit_app_event(&window, AppEvent::HistoryUpdated { session_id: None });
+            }
+            println!("🧹 Forgot last dictation (transcription {})", id);
+        }
+        _ => return Err("Nothing to forget".to_string()),
+    }
+
+    refresh_tray_menu(app); // The tray lists recent entries
+    Ok(())
+}
+
+#[tauri::command]
+fn forget_last_transcription(app: AppHandle) -> Result<(), String> {
+    forget_last_dictation(&app)
+}
+
 #[tauri::command]
 async fn retry_single_queue_item(state: State<'_, AppState>, app: AppHandle, id: i64) -> Result<(), String> {
     let database = state.database.clone();
     let openai = state.openai_client.clone();
-    let last_transcription = state.last_transcription.clone();
     let app_handle = app.clone();
 
     let items = database.load_queue().map_err(|e| e.to_string())?;
@@ -1708,7 +6150,7 @@ async fn retry_single_queue_item(state: State<'_, AppState>, app: AppHandle, id:
         .ok_or_else(|| "Item não encontrado na fila".to_string())?;
 
     tokio::spawn(async move {
-        let result = process_single_queue_item(&item, &database, &openai, &last_transcription, &app_handle).await;
+        let result = process_single_queue_item(&item, &database, &openai, &app_handle).await;
         handle_queue_item_result(result, &item, &database, &app_handle);
     });
 
@@ -1749,64 +6191,51 @@ async fn process_single_queue_item(
     item: &db::PendingQueueItem,
     database: &Arc<db::Database>,
     openai: &Arc<openai::OpenAIClient>,
-    last_transcription: &Arc<Mutex<Option<String>>>,
     app: &AppHandle,
 ) -> Result<(), String> {
+    let session_id = item.session_id.as_deref().and_then(|id| id.parse::<SessionId>().ok());
+    let pipeline = Pipeline::new(openai.clone(), AppOutput::new(app), session_id);
     match item.mode.as_str() {
-        "whisper-transcribe" => {
-            let path = item.audio_path.as_ref().ok_or("No audio path for whisper-transcribe item")?;
-            let audio = queue::read_wav_to_f32(path)?;
-            let text = openai.transcribe_audio(audio, 48000).await?;
-            tlog!("Queue retry: whisper-transcribe succeeded for id={}", item.id);
-            let ts = now_ms();
-            let cost = estimate_cost_cents("whisper", None, &text);
-            let _ = database.save_transcription(&text, ts, None, Some("whisper"), Some(cost), Some("transcription"));
-            *last_transcription.lock().unwrap() = Some(text.clone());
-            if let Some(window) = app.get_webview_window("main") {
-                let _ = window.emit("history-updated", ());
-            }
-            let _ = auto_paste_text(app, &text);
-            if let Some(window) = app.get_webview_window("main") {
-                let _ = window.emit("response-ready", ());
-            }
-            Ok(())
-        }
-        "realtime-audio" => {
-            let path = item.audio_path.as_ref().ok_or("No audio path for realtime-audio item")?;
-            let (audio, sample_rate) = queue::read_wav_to_f32_with_rate(path)?;
-            let text = openai.transcribe_audio(audio, sample_rate).await?;
-            tlog!("Queue retry: realtime-audio succeeded for id={}", item.id);
-            let ts = now_ms();
-            let cost = estimate_cost_cents("whisper", None, &text);
-            let _ = database.save_transcription(&text, ts, None, Some("whisper"), Some(cost), Some("transcription"));
-            *last_transcription.lock().unwrap() = Some(text.clone());
-            if let Some(window) = app.get_webview_window("main") {
-                let _ = window.emit("history-updated", ());
-            }
-            let _ = auto_paste_text(app, &text);
-            if let Some(window) = app.get_webview_window("main") {
-                let _ = window.emit("response-ready", ());
-            }
+        "whisper-transcribe" | "realtime-audio" => {
+            let path = item.audio_path.as_ref().ok_or_else(|| format!("No audio path for {} item", item.mode))?;
+            let (audio, sample_rate) = if item.mode == "realtime-audio" {
+                queue::read_wav_to_f32_with_rate(path)?
+            } else {
+                (queue::read_wav_to_f32(path)?, 48000)
+            };
+            let transcript = pipeline.transcribe(audio, sample_rate, None).await?;
+            let text = pipeline.transcript(transcript.text);
+            tlog!("Queue retry: {} succeeded for id={} (session {:?})", item.mode, item.id, item.session_id);
+            pipeline.deliver(Delivery {
+                text,
+                model: transcription_model_label(openai),
+                kind: DeliveryKind::Transcription,
+                prompt: None,
+                duration_ms: None,
+                capture: None,
+                speak: false,
+                segments: Vec::new(),
+                language: transcript.language,
+                original: None,
+                words: Vec::new(),
+            })
+            .await;
             Ok(())
         }
         "whisper-prompt" | "text-prompt" | "realtime-prompt" => {
             let text = item.prompt_text.as_ref().ok_or("No prompt text for queue item")?;
-            let conv_history = get_conversation_history(database);
-            let response = openai.send_prompt(text, &item.model, &conv_history, None).await?;
-            tlog!("Queue retry: {} succeeded for id={}", item.mode, item.id);
-            let ts = now_ms();
-            let cost = estimate_cost_cents(&item.model, None, &response);
-            let _ = database.save_transcription(&response, ts, None, Some(&item.model), Some(cost), Some("prompt"));
-            let _ = database.append_conversation("user", text, ts - 1);
-            let _ = database.append_conversation("assistant", &response, ts);
-            *last_transcription.lock().unwrap() = Some(response.clone());
-            if let Some(window) = app.get_webview_window("main") {
-                let _ = window.emit("history-updated", ());
-            }
-            let _ = auto_paste_text(app, &response);
-            if let Some(window) = app.get_webview_window("main") {
-                let _ = window.emit("response-ready", ());
-            }
+            pipeline.prompt(PromptRequest {
+                prompt: text.clone(),
+                model: item.model.clone(),
+                history: get_conversation_history(database),
+                image: None,
+                duration_ms: None,
+                capture: None,
+                queue_mode: "text-prompt", // Unused: failed retries stay in the queue
+                speak: false,
+                language: None,
+            }).await?;
+            tlog!("Queue retry: {} succeeded for id={} (session {:?})", item.mode, item.id, item.session_id);
             Ok(())
         }
         _ => Err(format!("Unknown queue mode: {}", item.mode)),
@@ -1826,7 +6255,7 @@ fn handle_queue_item_result(
             if let Some(ref path) = item.audio_path {
                 queue::delete_wav_file(path);
             }
-            let _ = app.emit("queue-item-completed", item.id);
+            let _ = emit_app_event(app, AppEvent::QueueItemCompleted(item.id));
             emit_queue_updated(app, database);
         }
         Err(e) => {
@@ -1841,7 +6270,6 @@ fn handle_queue_item_result(
 async fn process_retry_queue(
     database: Arc<db::Database>,
     openai: Arc<openai::OpenAIClient>,
-    last_transcription: Arc<Mutex<Option<String>>>,
     app: AppHandle,
 ) {
     if !queue::is_online() {
@@ -1864,7 +6292,7 @@ async fn process_retry_queue(
     tlog!("Queue retry: processing {} items", items.len());
 
     for item in items {
-        let result = process_single_queue_item(&item, &database, &openai, &last_transcription, &app).await;
+        let result = process_single_queue_item(&item, &database, &openai, &app).await;
         handle_queue_item_result(result, &item, &database, &app);
     }
 }
@@ -1940,20 +6368,53 @@ pub fn run() {
     let queue_dir = app_data_dir.join("queue");
     std::fs::create_dir_all(&queue_dir).ok();
     println!("📁 Queue directory: {}", queue_dir.display());
+    let archive_dir = app_data_dir.join("audio_archive");
+
+    // Initialize TTS cache directory
+    let tts_cache_dir = app_data_dir.join("tts_cache");
+    std::fs::create_dir_all(&tts_cache_dir).ok();
+
+    // Initialize OpenAI client with persisted language / system prompt
+    let openai_client = openai::OpenAIClient::new(api_key.clone());
+    openai_client.apply_config(
+        database.load_setting("language").ok().flatten(),
+        database.load_setting("system_prompt").ok().flatten(),
+        database.load_setting("transcription_model").ok().flatten(),
+    );
+    openai_client.set_timeouts(load_request_timeouts(&database));
+    openai_client.set_whisper_decoding(load_whisper_decoding(&database));
+    let realtime_client = realtime::RealtimeClient::new(api_key);
+    if let Some(proxy) = load_proxy_settings(&database) {
+        println!("🌐 Using proxy {}", proxy.url);
+        openai_client.set_proxy(Some(&proxy));
+        realtime_client.set_proxy(Some(proxy));
+    }
 
     // Initialize app state
     let app_state = AppState {
         audio_recorder: Arc::new(Mutex::new(audio::AudioRecorder::new())),
-        openai_client: Arc::new(openai::OpenAIClient::new(api_key.clone())),
-        realtime_client: Arc::new(realtime::RealtimeClient::new(api_key)),
+        clip_meter: Arc::new(audio::ClipMeter::new()),
+        session_stats: Arc::new(audio::SessionStats::new()),
+        network: Arc::new(network::NetworkMonitor::new()),
+        session_id: Arc::new(Mutex::new(None)),
+        openai_client: Arc::new(openai_client),
+        delivery_order: Arc::new(DeliveryOrder::default()),
+        in_flight: Arc::new(Mutex::new(None)),
+        realtime_client: Arc::new(realtime_client),
         database,
         is_recording: Arc::new(Mutex::new(false)),
         use_realtime: Arc::new(Mutex::new(true)), // Default to Realtime API
         prompt_mode: Arc::new(Mutex::new(None)),
         current_session_transcript: Arc::new(Mutex::new(String::new())),
+        session_segments: Arc::new(Mutex::new(Vec::new())),
         last_transcription: Arc::new(Mutex::new(None)),
+        failed_paste: Arc::new(Mutex::new(None)),
+        paste_target: Arc::new(Mutex::new(None)),
+        pending_paste: Arc::new(Mutex::new(None)),
+        review_entry: Arc::new(Mutex::new(None)),
         paste_in_progress: Arc::new(Mutex::new(false)),
         recording_start_time: Arc::new(Mutex::new(None)),
+        mic_mute: Arc::new(Mutex::new(MicMute::default())),
         speech_active: Arc::new(Mutex::new(false)),
         last_speech_end: Arc::new(Mutex::new(None)),
         last_transcription_time: Arc::new(Mutex::new(None)),
@@ -1961,8 +6422,28 @@ pub fn run() {
         tts_sink: Arc::new(Mutex::new(None)),
         tts_stream_handle: Arc::new(Mutex::new(tts_stream_handle_val)),
         tts_active: Arc::new(Mutex::new(false)),
+        tts_queue: Arc::new(Mutex::new(VecDeque::new())),
+        tts_skip: Arc::new(Mutex::new(false)),
+        assistant_active: Arc::new(Mutex::new(false)),
         queue_dir,
+        archive_dir,
+        tts_cache_dir,
         streaming_stop_handle: Arc::new(Mutex::new(None)),
+        shortcuts: shortcuts::ShortcutRegistry::new(),
+        ptt_sender: Arc::new(Mutex::new(None)),
+        chunked_session: Arc::new(Mutex::new(None)),
+        local_whisper: Arc::new(Mutex::new(None)),
+        live_session_local: Arc::new(Mutex::new(false)),
+        realtime_session: Arc::new(Mutex::new(None)),
+        idle_realtime_session: Arc::new(Mutex::new(None)),
+        meeting: Arc::new(Mutex::new(None)),
+        api_server: Arc::new(Mutex::new(None)),
+        output_target: Arc::new(Mutex::new(OutputTarget::Paste)),
+        screen_context: Arc::new(Mutex::new(None)),
+        selection_context: Arc::new(Mutex::new(None)),
+        dictation_style: Arc::new(Mutex::new(None)),
+        last_json_output: Arc::new(Mutex::new(None)),
+        plugins: Arc::new(plugins::PluginHost::new(app_data_dir.join("plugins"))),
     };
 
     // Debounce: prevent multiple triggers when keys are held down
@@ -1973,6 +6454,11 @@ pub fn run() {
     let last_paste_trigger_clone = last_paste_trigger.clone();
 
     tauri::Builder::default()
+        // Must be registered first: a second launch exits here and just brings this instance forward
+        .plugin(tauri_plugin_single_instance::init(|app, _args, _cwd| {
+            println!("🪟 Another instance was launched, showing this one instead");
+            show_main_window(app);
+        }))
         .manage(app_state)
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_updater::Builder::new().build())
@@ -1981,342 +6467,242 @@ pub fn run() {
             tauri_plugin_global_shortcut::Builder::new()
                 .with_handler(move |app, shortcut, event| {
                     // Only handle key press events, ignore key release
-                    let event_str = format!("{:?}", event);
-                    if !event_str.contains("Pressed") {
-                        return; // Ignore Released events
+                    if event.state() != ShortcutState::Pressed {
+                        return;
                     }
 
-                    // Check which shortcut was pressed
-                    let shortcut_str = format!("{:?}", shortcut);
-
-                    if shortcut_str.contains("Space") && shortcut_str.contains("CONTROL") && shortcut_str.contains("SHIFT") {
-                        // Ctrl+Shift+Space: Toggle recording with selected prompt model
-                        let mut last = last_recording_trigger_clone.lock().unwrap();
-                        let now = Instant::now();
-
-                        if now.duration_since(*last) > Duration::from_millis(100) {
-                            *last = now;
-                            println!("🔥 Hotkey pressed: Ctrl+Shift+Space (Prompt mode)");
-
-                            if let Some(state) = app.try_state::<AppState>() {
-                                let is_recording = *state.is_recording.lock().unwrap();
-
-                                if !is_recording {
-                                    // Ctrl+Shift+Space: use the user's chosen prompt model (separate key)
-                                    // This is the model the user picked in the combo box for prompt sessions
-                                    let model = state.database.load_setting("user_prompt_model")
-                                        .ok()
-                                        .flatten()
-                                        .unwrap_or_else(|| "gpt-4o-mini".to_string());
-
-                                    // Save as current session model
-                                    let _ = state.database.save_setting("selected_prompt_model", &model);
-
-                                    *state.prompt_mode.lock().unwrap() = Some(model.clone());
-                                    println!("🤖 Prompt mode enabled: {} (saved to DB)", model);
-
-                                    // Show widget
-                                    if let Some(widget) = app.get_webview_window("recording-widget") {
-                                        if let Ok(monitor) = widget.current_monitor() {
-                                            if let Some(monitor) = monitor {
-                                                let screen_size = monitor.size();
-                                                let widget_width = 155;
-                                                let widget_height = 120; // Height increased for combo box
-                                                let bottom_margin = 200; // More space from taskbar
-
-                                                let x = (screen_size.width as i32 - widget_width) / 2;
-                                                let y = screen_size.height as i32 - widget_height - bottom_margin;
-
-                                                let _ = widget.set_position(PhysicalPosition::new(x, y));
-                                            }
-                                        }
-                                        let _ = widget.show();
-                                        // Tell widget which model is active
-                                        let _ = widget.emit("model-selected", model.clone());
-                                    }
-                                } else {
-                                    // Stopping recording - DON'T clear prompt_mode here
-                                    // It will be cleared in stop_realtime_recording after being used
-                                    println!("🛑 [Ctrl+Shift+Space] Stopping - prompt_mode will be used in stop handler");
+                    let Some(action) = app.try_state::<AppState>().and_then(|state| state.shortcuts.action_for(shortcut)) else {
+                        return;
+                    };
+                    tlog!("🔥 Hotkey pressed: {:?}", action);
+
+                    match action {
+                        HotkeyAction::Cancel => abort_everything(app),
+                        HotkeyAction::PromptSelectedModel => {
+                            // Toggle recording with selected prompt model
+                            let mut last = last_recording_trigger_clone.lock().unwrap();
+                            let now = Instant::now();
 
-                                    if let Some(widget) = app.get_webview_window("recording-widget") {
-                                        let _ = widget.hide();
-                                    }
-                                }
+                            if now.duration_since(*last) > Duration::from_millis(100) {
+                                *last = now;
+                                toggle_recording(app, RecordingTrigger::UserPromptModel);
+                            } else {
+                                println!("⏭️ Hotkey ignored (debounce)");
                             }
+                        }
+                        HotkeyAction::PromptGpt41 => {
+                            // Toggle recording with GPT-4o prompt mode
+                            let mut last = last_recording_trigger_clone.lock().unwrap();
+                            let now = Instant::now();
 
-                            // Emit event to frontend
-                            if let Some(window) = app.get_webview_window("main") {
-                                let _ = window.emit("toggle-recording", ());
+                            if now.duration_since(*last) > Duration::from_millis(100) {
+                                *last = now;
+                                toggle_recording(app, RecordingTrigger::PromptModel("gpt-4.1".to_string()));
+                            } else {
+                                println!("⏭️ Hotkey ignored (debounce)");
                             }
-                        } else {
-                            println!("⏭️ Ctrl+Shift+Space ignored (debounce)");
                         }
-                    } else if shortcut_str.contains("Space") && shortcut_str.contains("CONTROL") && shortcut_str.contains("ALT") {
-                        // Ctrl+Alt+Space: Toggle recording with GPT-4o prompt mode
-                        let mut last = last_recording_trigger_clone.lock().unwrap();
-                        let now = Instant::now();
-
-                        if now.duration_since(*last) > Duration::from_millis(100) {
-                            *last = now;
-                            println!("🔥 Hotkey pressed: Ctrl+Alt+Space (GPT-4o mode)");
-
-                            if let Some(state) = app.try_state::<AppState>() {
-                                let is_recording = *state.is_recording.lock().unwrap();
-
-                                if !is_recording {
-                                    // Set prompt mode to gpt-4.1 and save to database
-                                    let _ = state.database.save_setting("selected_prompt_model", "gpt-4.1");
-                                    *state.prompt_mode.lock().unwrap() = Some("gpt-4.1".to_string());
-                                    println!("🤖 Prompt mode enabled: gpt-4.1 (saved to DB)");
-
-                                    // Show widget
-                                    if let Some(widget) = app.get_webview_window("recording-widget") {
-                                        if let Ok(monitor) = widget.current_monitor() {
-                                            if let Some(monitor) = monitor {
-                                                let screen_size = monitor.size();
-                                                let widget_width = 155;
-                                                let widget_height = 120; // Height increased for combo box
-                                                let bottom_margin = 200; // More space from taskbar
-
-                                                let x = (screen_size.width as i32 - widget_width) / 2;
-                                                let y = screen_size.height as i32 - widget_height - bottom_margin;
-
-                                                let _ = widget.set_position(PhysicalPosition::new(x, y));
-                                            }
-                                        }
-                                        let _ = widget.show();
-                                        // Tell widget which model is active
-                                        let _ = widget.emit("model-selected", "gpt-4.1".to_string());
-                                    }
-                                } else {
-                                    // Stopping recording - DON'T clear prompt_mode here
-                                    // It will be cleared in stop_realtime_recording after being used
-                                    println!("🛑 [Ctrl+Alt+Space] Stopping - prompt_mode (gpt-4.1) will be used in stop handler");
+                        HotkeyAction::Transcribe => {
+                            // Toggle recording (with minimal debounce for safety)
+                            let mut last = last_recording_trigger_clone.lock().unwrap();
+                            let now = Instant::now();
 
-                                    if let Some(widget) = app.get_webview_window("recording-widget") {
-                                        let _ = widget.hide();
+                            // Only trigger if 100ms have passed (minimal debounce, since we filter Pressed)
+                            if now.duration_since(*last) > Duration::from_millis(100) {
+                                *last = now;
+                                toggle_recording(app, RecordingTrigger::Transcribe);
+                            } else {
+                                println!("⏭️ Hotkey ignored (debounce - too fast)");
+                            }
+                        }
+                        HotkeyAction::PromptInput => {
+                            // Open prompt input window
+                            show_prompt_input(app);
+                        }
+                        HotkeyAction::EditSelection => {
+                            // Dictate an instruction to apply to the selected text
+                            let app_handle = app.clone();
+                            std::thread::spawn(move || edit_selection(&app_handle));
+                        }
+                        HotkeyAction::AskAboutScreen => {
+                            // Ask a spoken question about the active window
+                            ask_about_screen(app);
+                        }
+                        HotkeyAction::ScreenshotPrompt => {
+                            // Screenshot the current monitor and open the prompt window with it attached
+                            match screenshot::capture(screenshot::CaptureArea::Screen) {
+                                Ok(image) => {
+                                    if let Some(prompt_window) = app.get_webview_window("prompt-input") {
+                                        let _ = emit_app_event(&prompt_window, AppEvent::ScreenshotAttached(image));
                                     }
+                                    show_prompt_input(app);
                                 }
+                                Err(e) => eprintln!("❌ Screenshot failed: {}", e),
                             }
-
-                            // Emit event to frontend
-                            if let Some(window) = app.get_webview_window("main") {
-                                let _ = window.emit("toggle-recording", ());
+                        }
+                        HotkeyAction::ToggleTts => {
+                            // Toggle TTS
+                            if let Some(state) = app.try_state::<AppState>() {
+                                let new_val = !*state.tts_enabled.lock().unwrap();
+                                if let Err(e) = apply_tts_enabled(app, &state, new_val) {
+                                    eprintln!("⚠️ {}", e);
+                                }
                             }
-                        } else {
-                            println!("⏭️ Ctrl+Alt+Space ignored (debounce)");
                         }
-                    } else if shortcut_str.contains("Space") {
-                        // Ctrl+Space: Toggle recording (with minimal debounce for safety)
-                        let mut last = last_recording_trigger_clone.lock().unwrap();
-                        let now = Instant::now();
-
-                        // Only trigger if 100ms have passed (minimal debounce, since we filter Pressed)
-                        if now.duration_since(*last) > Duration::from_millis(100) {
-                            *last = now;
-                            println!("🔥 Hotkey pressed: Ctrl+Space");
-
-                            // Show/hide widget based on recording state
+                        HotkeyAction::TtsAction => {
+                            // Stop TTS playback or read last message
                             if let Some(state) = app.try_state::<AppState>() {
-                                let is_recording = *state.is_recording.lock().unwrap();
-
-                                if !is_recording {
-                                    // Check if prompt mode was already set by Ctrl+Shift+Space or Ctrl+Alt+Space
-                                    let current_prompt_mode = state.prompt_mode.lock().unwrap().clone();
-
-                                    // Determine which model to show in widget
-                                    let widget_model = if current_prompt_mode.is_none() {
-                                        println!("📝 Ctrl+Space starting - setting prompt mode to None (normal transcription)");
-                                        let _ = state.database.save_setting("selected_prompt_model", "transcribe-only");
-                                        *state.prompt_mode.lock().unwrap() = None;
-                                        "transcribe-only".to_string()
-                                    } else {
-                                        println!("⚠️ Ctrl+Space starting but prompt_mode already set to {:?} - keeping it", current_prompt_mode);
-                                        current_prompt_mode.clone().unwrap_or_else(|| "transcribe-only".to_string())
-                                    };
-
-                                    // Starting recording - show widget
-                                    if let Some(widget) = app.get_webview_window("recording-widget") {
-                                        // Position widget at bottom-center of screen
-                                        if let Ok(monitor) = widget.current_monitor() {
-                                            if let Some(monitor) = monitor {
-                                                let screen_size = monitor.size();
-                                                let widget_width = 155;
-                                                let widget_height = 120; // Height increased for combo box
-                                                let bottom_margin = 200; // More space from taskbar
-
-                                                let x = (screen_size.width as i32 - widget_width) / 2;
-                                                let y = screen_size.height as i32 - widget_height - bottom_margin;
-
-                                                let _ = widget.set_position(PhysicalPosition::new(x, y));
-                                            }
-                                        }
-                                        let _ = widget.show();
-                                        // Tell widget which model is active
-                                        let _ = widget.emit("model-selected", widget_model);
-                                    }
+                                let is_active = *state.tts_active.lock().unwrap();
+
+                                if is_active {
+                                    // Stop current chunked playback
+                                    stop_tts(&state, app);
+                                    println!("🔇 TTS playback stopped via hotkey");
                                 } else {
-                                    // Stopping recording with Ctrl+Space
-                                    let current_prompt_mode = state.prompt_mode.lock().unwrap().clone();
-                                    println!("🛑 [Ctrl+Space] Stopping recording - prompt_mode = {:?}", current_prompt_mode);
-                                    println!("📌 Prompt mode will be preserved for stop_realtime_recording");
-
-                                    // Hide widget
-                                    if let Some(widget) = app.get_webview_window("recording-widget") {
-                                        let _ = widget.hide();
+                                    // Read last message aloud (chunked)
+                                    let last_text = state.last_transcription.lock().unwrap().clone();
+                                    if let Some(text) = last_text {
+                                        let preview: String = text.chars().take(50).collect();
+                                        println!("🔊 Reading last message via TTS: {}...", preview);
+                                        speak_aloud(app, &state, text);
+                                    } else {
+                                        println!("⚠️ No message to read aloud");
                                     }
                                 }
                             }
-
-                            // Emit event to frontend
-                            if let Some(window) = app.get_webview_window("main") {
-                                let _ = window.emit("toggle-recording", ());
+                        }
+                        HotkeyAction::ReadClipboard => {
+                            // Read clipboard aloud
+                            if let Err(e) = read_clipboard_aloud_inner(app) {
+                                println!("⚠️ {}", e);
                             }
-                        } else {
-                            println!("⏭️ Ctrl+Space ignored (debounce - too fast)");
                         }
-                    } else if shortcut_str.contains("KeyB") && shortcut_str.contains("CONTROL") {
-                        // Ctrl+B: Open prompt input window
-                        tlog!("🔥 Hotkey pressed: Ctrl+B");
-                        if let Some(prompt_window) = app.get_webview_window("prompt-input") {
-                            if let Ok(monitor) = prompt_window.current_monitor() {
-                                if let Some(monitor) = monitor {
-                                    let screen_size = monitor.size();
-                                    let win_width = 400i32;
-                                    let win_height = 160i32;
-                                    let x = (screen_size.width as i32 - win_width) / 2;
-                                    let y = screen_size.height as i32 - win_height - 200;
-                                    let _ = prompt_window.set_position(PhysicalPosition::new(x, y));
+                        HotkeyAction::ReadSelection => {
+                            // Read selected text aloud (simulated copy)
+                            let app_handle = app.clone();
+                            std::thread::spawn(move || {
+                                if let Err(e) = speak_selection_inner(&app_handle) {
+                                    println!("⚠️ {}", e);
                                 }
-                            }
-                            let _ = prompt_window.show();
-                            let _ = prompt_window.set_focus();
+                            });
                         }
-                    } else if shortcut_str.contains("KeyS") && shortcut_str.contains("CONTROL") && shortcut_str.contains("ALT") {
-                        // Ctrl+Alt+S: Toggle TTS
-                        tlog!("🔥 Hotkey pressed: Ctrl+Alt+S (Toggle TTS)");
-                        if let Some(state) = app.try_state::<AppState>() {
-                            let new_val = {
-                                let mut enabled = state.tts_enabled.lock().unwrap();
-                                *enabled = !*enabled;
-                                *enabled
-                            };
-                            let _ = state.database.save_setting("tts_enabled", if new_val { "true" } else { "false" });
-                            println!("🔊 TTS toggled: {}", if new_val { "ON" } else { "OFF" });
-                            if let Some(window) = app.get_webview_window("main") {
-                                let _ = window.emit("tts-toggled", new_val);
-                            }
-                            // Also emit to recording widget
-                            if let Some(widget) = app.get_webview_window("recording-widget") {
-                                let _ = widget.emit("tts-toggled", new_val);
-                            }
-                            // Show toast
-                            if let Some(toast) = app.get_webview_window("tts-toast") {
-                                if let Ok(Some(monitor)) = app.primary_monitor() {
-                                    let screen = monitor.size();
-                                    let x = (screen.width as i32 - 140) / 2;
-                                    let y = screen.height as i32 - 40 - 300;
-                                    let _ = toast.set_position(PhysicalPosition::new(x, y));
+                        HotkeyAction::FixGrammar => {
+                            // Fix the grammar of the selected text in place
+                            let app_handle = app.clone();
+                            tauri::async_runtime::spawn(async move {
+                                if let Err(e) = fix_selection_grammar_inner(app_handle).await {
+                                    println!("⚠️ {}", e);
                                 }
-                                let _ = toast.emit("tts-toast-show", new_val);
-                                let _ = toast.show();
+                            });
+                        }
+                        HotkeyAction::AbortRequest => {
+                            if let Err(e) = abort_current_request_inner(app) {
+                                println!("⚠️ {}", e);
                             }
                         }
-                    } else if shortcut_str.contains("KeyS") && shortcut_str.contains("ALT") && shortcut_str.contains("SHIFT") {
-                        // Alt+Shift+S: Stop TTS playback or read last message
-                        tlog!("🔥 Hotkey pressed: Alt+Shift+S (TTS action)");
-                        if let Some(state) = app.try_state::<AppState>() {
-                            let is_active = *state.tts_active.lock().unwrap();
-
-                            if is_active {
-                                // Stop current chunked playback
-                                *state.tts_active.lock().unwrap() = false;
-                                {
-                                    let mut sink_guard = state.tts_sink.lock().unwrap();
-                                    if let Some(sink) = sink_guard.take() {
-                                        sink.stop();
-                                    }
-                                }
-                                if let Some(w) = app.get_webview_window("tts-widget") {
-                                    let _ = w.hide();
-                                }
-                                println!("🔇 TTS playback stopped via Alt+Shift+S");
-                            } else {
-                                // Read last message aloud (chunked)
-                                let last_text = state.last_transcription.lock().unwrap().clone();
-                                if let Some(text) = last_text {
-                                    let preview: String = text.chars().take(50).collect();
-                                    println!("🔊 Reading last message via TTS: {}...", preview);
-                                    let openai = state.openai_client.clone();
-                                    let tts_sink = state.tts_sink.clone();
-                                    let tts_handle = state.tts_stream_handle.clone();
-                                    let tts_active = state.tts_active.clone();
-                                    let app_clone = app.clone();
-                                    tauri::async_runtime::spawn(play_tts_chunked(
-                                        app_clone, text, openai, tts_sink, tts_handle, tts_active,
-                                    ));
-                                } else {
-                                    println!("⚠️ No message to read aloud");
-                                }
+                        HotkeyAction::MuteMic => {
+                            if let Err(e) = toggle_mic_mute_inner(app) {
+                                println!("⚠️ {}", e);
                             }
                         }
-                    } else if shortcut_str.contains("KeyZ") {
-                        // Alt+Shift+Z: Get last transcription from history and paste it
-                        tlog!("🔥 Hotkey pressed: Alt+Shift+Z");
-
-                        // Get app state
-                        if let Some(state) = app.try_state::<AppState>() {
-                            // Check if paste is already in progress
-                            let mut paste_in_progress = state.paste_in_progress.lock().unwrap();
-                            if *paste_in_progress {
-                                println!("⏭️ Alt+Shift+Z ignored (paste already in progress)");
-                                return;
+                        HotkeyAction::ToggleIncognito => {
+                            // Toggle incognito (nothing is saved to history)
+                            if let Some(state) = app.try_state::<AppState>() {
+                                let new_val = !is_incognito(&state.database);
+                                if let Err(e) = apply_incognito(app, &state, new_val) {
+                                    eprintln!("⚠️ {}", e);
+                                }
                             }
+                        }
+                        HotkeyAction::AppendToNote => {
+                            // Dictate into the configured note instead of pasting
+                            toggle_recording_to(app, RecordingTrigger::Transcribe, OutputTarget::Note);
+                        }
+                        HotkeyAction::TtsSpeedUp => step_tts_speed(app, true),
+                        HotkeyAction::TtsSpeedDown => step_tts_speed(app, false),
+                        HotkeyAction::AssistantMode => apply_assistant_mode(app, !assistant_active(app)),
+                        HotkeyAction::JsonOutput => {
+                            // Dictate a command that becomes JSON for automation instead of pasting
+                            toggle_recording_to(app, RecordingTrigger::Transcribe, OutputTarget::Json);
+                        }
+                        HotkeyAction::EmailMode => {
+                            // Dictate what the email should say; it's written out and pasted
+                            toggle_recording_to(app, RecordingTrigger::Transcribe, OutputTarget::Email);
+                        }
+                        HotkeyAction::CycleModel => {
+                            // Cycle prompt model while recording
+                            cycle_prompt_model(app);
+                        }
+                        HotkeyAction::PasteLast => {
+                            // Get last transcription from history and paste it
 
-                            // Check debounce (increased to account for the paste operation duration)
-                            let mut last_paste = last_paste_trigger_clone.lock().unwrap();
-                            let now = Instant::now();
-                            // Total paste operation takes ~600ms (delay) + ~300ms (restore) = ~900ms
-                            // We use 1000ms debounce to be safe
-                            if now.duration_since(*last_paste) < Duration::from_millis(1000) {
-                                println!("⏭️ Alt+Shift+Z ignored (debounce - paste takes ~900ms)");
-                                return;
-                            }
+                            // Get app state
+                            if let Some(state) = app.try_state::<AppState>() {
+                                // Check if paste is already in progress
+                                let mut paste_in_progress = state.paste_in_progress.lock().unwrap();
+                                if *paste_in_progress {
+                                    println!("⏭️ Hotkey ignored (paste already in progress)");
+                                    return;
+                                }
 
-                            *last_paste = now;
-                            drop(last_paste); // Release debounce lock
+                                // Check debounce (increased to account for the paste operation duration)
+                                let mut last_paste = last_paste_trigger_clone.lock().unwrap();
+                                let now = Instant::now();
+                                // Total paste operation takes ~600ms (delay) + ~300ms (restore) = ~900ms
+                                // We use 1000ms debounce to be safe
+                                if now.duration_since(*last_paste) < Duration::from_millis(1000) {
+                                    println!("⏭️ Hotkey ignored (debounce - paste takes ~900ms)");
+                                    return;
+                                }
 
-                            // Get last transcription from database
-                            match state.database.load_transcriptions() {
-                                Ok(history) if !history.is_empty() => {
-                                    let last_entry = &history[0]; // First entry is most recent
-                                    println!("📋 Pasting last transcription from history: {}", last_entry.text);
-                                    let text_clone = last_entry.text.clone();
+                                *last_paste = now;
+                                drop(last_paste); // Release debounce lock
 
-                                    // Mark paste as in progress
+                                // A result waiting for a click: the hotkey confirms it instead
+                                if let Some(pending) = state.pending_paste.lock().unwrap().take() {
+                                    input_listener::cancel_next_click();
                                     *paste_in_progress = true;
-                                    drop(paste_in_progress); // Release lock before spawning thread
-
+                                    drop(paste_in_progress);
                                     let app_handle = app.app_handle().clone();
                                     let paste_flag = state.paste_in_progress.clone();
-
-                                    // auto_paste_text handles: save clipboard, copy text, paste (Ctrl+V), restore clipboard
                                     std::thread::spawn(move || {
-                                        // Small delay to ensure clipboard is ready
-                                        std::thread::sleep(std::time::Duration::from_millis(100));
-
-                                        if let Err(e) = auto_paste_text(&app_handle, &text_clone) {
-                                            eprintln!("❌ Failed to paste: {}", e);
-                                        }
-                                        // Mark paste as complete
+                                        paste_pending(&app_handle, pending);
                                         *paste_flag.lock().unwrap() = false;
-                                        println!("✅ Paste operation completed");
                                     });
+                                    return;
                                 }
-                                _ => {
-                                    *paste_in_progress = false; // Reset flag if no transcription available
-                                    println!("⚠️ No transcription available to paste");
+
+                                // Get last transcription from database
+                                match state.database.load_transcriptions(None, None) {
+                                    Ok(history) if !history.is_empty() => {
+                                        let last_entry = &history[0]; // First entry is most recent
+                                        println!("📋 Pasting last transcription from history: {}", last_entry.text);
+                                        let text_clone = last_entry.text.clone();
+
+                                        // Mark paste as in progress
+                                        *paste_in_progress = true;
+                                        drop(paste_in_progress); // Release lock before spawning thread
+
+                                        let app_handle = app.app_handle().clone();
+                                        let paste_flag = state.paste_in_progress.clone();
+
+                                        // auto_paste_text handles: save clipboard, copy text, paste (Ctrl+V), restore clipboard
+                                        std::thread::spawn(move || {
+                                            // Small delay to ensure clipboard is ready
+                                            std::thread::sleep(std::time::Duration::from_millis(100));
+
+                                            if let Err(e) = auto_paste_text(&app_handle, &text_clone) {
+                                                eprintln!("❌ Failed to paste: {}", e);
+                                            }
+                                            // Mark paste as complete
+                                            *paste_flag.lock().unwrap() = false;
+                                            println!("✅ Paste operation completed");
+                                        });
+                                    }
+                                    _ => {
+                                        *paste_in_progress = false; // Reset flag if no transcription available
+                                        println!("⚠️ No transcription available to paste");
+                                    }
                                 }
                             }
                         }
@@ -2325,12 +6711,32 @@ pub fn run() {
                 .build()
         )
         .plugin(tauri_plugin_clipboard_manager::init())
+        .plugin(tauri_plugin_notification::init())
         .invoke_handler(tauri::generate_handler![
             start_recording_audio,
             stop_recording_audio,
             cancel_recording,
             get_last_transcription,
+            retry_last_paste,
+            abort_current_request,
+            toggle_mic_mute,
             get_transcription_history,
+            get_widget_placement,
+            set_widget_placement,
+            get_monitor_names,
+            get_history_apps,
+            get_history_languages,
+            get_title_mode,
+            set_title_mode,
+            get_dedup_window,
+            set_dedup_window,
+            merge_duplicates,
+            get_conversation_messages,
+            delete_conversation_message,
+            get_transcription_segments,
+            cleanup_transcription,
+            summarize_entries,
+            get_session_diagnostics,
             copy_to_clipboard,
             start_realtime_recording,
             stop_realtime_recording,
@@ -2339,6 +6745,30 @@ pub fn run() {
             list_microphones,
             set_selected_microphone,
             get_selected_microphone,
+            get_microphone_priority,
+            set_microphone_priority,
+            get_sidetone_settings,
+            set_sidetone_settings,
+            get_bluetooth_mic_fallback,
+            set_bluetooth_mic_fallback,
+            get_exclusive_capture,
+            get_proxy_settings,
+            set_proxy_settings,
+            get_request_timeouts,
+            set_request_timeouts,
+            get_whisper_decoding,
+            set_whisper_decoding,
+            get_queue_limits,
+            get_network_status,
+            check_for_updates,
+            install_update,
+            get_update_channel,
+            set_update_channel,
+            set_queue_limits,
+            get_incognito,
+            set_incognito,
+            forget_last_transcription,
+            set_exclusive_capture,
             set_selected_prompt_model,
             get_selected_prompt_model,
             get_current_recording_mode,
@@ -2346,19 +6776,204 @@ pub fn run() {
             get_statistics,
             get_tts_enabled,
             set_tts_enabled,
+            get_tts_auto_play,
+            get_tts_speed,
+            set_tts_speed,
+            set_tts_auto_play,
             stop_tts_playback,
+            skip_current_tts,
+            get_assistant_mode,
+            set_assistant_mode,
+            get_tts_queue,
+            set_tts_queue,
+            get_tts_stream_responses,
+            set_tts_stream_responses,
+            get_tts_voices,
+            get_tts_voice,
+            set_tts_voice,
+            get_tts_model_voices,
+            set_tts_model_voice,
             get_queue_count,
             get_queue_items,
             retry_pending_queue,
             delete_single_queue_item,
             retry_single_queue_item,
-            play_queue_audio
+            play_queue_audio,
+            get_spend_alert_threshold,
+            set_spend_alert_threshold,
+            list_profiles,
+            save_profile,
+            delete_profile,
+            switch_profile,
+            get_active_profile,
+            get_language,
+            set_language,
+            get_system_prompt,
+            set_system_prompt,
+            get_hotkeys,
+            set_hotkey,
+            get_cancel_hotkey,
+            get_tts_cache_info,
+            set_tts_cache_max_mb,
+            clear_tts_cache,
+            read_clipboard_aloud,
+            speak_selection,
+            get_tts_duck_level,
+            set_tts_duck_level,
+            get_tts_lexicon,
+            save_tts_lexicon_entry,
+            delete_tts_lexicon_entry,
+            get_app_rules,
+            save_app_rule,
+            delete_app_rule,
+            get_voice_command_settings,
+            set_voice_command_settings,
+            get_voice_intents,
+            save_voice_intent,
+            delete_voice_intent,
+            extract_action_items,
+            generate_daily_digest,
+            get_daily_digest_time,
+            set_daily_digest_time,
+            get_action_items,
+            get_watch_words,
+            set_watch_words,
+            get_watch_words_notify,
+            set_watch_words_notify,
+            capture_screenshot,
+            fix_selection_grammar,
+            get_notion_settings,
+            set_notion_settings,
+            set_output_target,
+            start_recording_to,
+            get_append_note_settings,
+            set_append_note_settings,
+            get_api_server_settings,
+            set_api_server_settings,
+            regenerate_api_token,
+            get_sync_config,
+            set_sync_config,
+            sync_now,
+            start_meeting,
+            stop_meeting,
+            is_meeting_active,
+            get_meetings,
+            delete_meeting,
+            get_meeting_summary_interval,
+            set_meeting_summary_interval,
+            confirm_and_paste,
+            get_review_before_paste,
+            set_review_before_paste,
+            get_restore_focus_before_paste,
+            set_restore_focus_before_paste,
+            get_paste_on_click,
+            set_paste_on_click,
+            get_insertion_method,
+            set_insertion_method,
+            get_clipboard_history_exclusion,
+            set_clipboard_history_exclusion,
+            test_paste_layout,
+            get_plain_text_settings,
+            set_plain_text_settings,
+            get_number_normalization,
+            set_number_normalization,
+            get_profanity_settings,
+            set_profanity_settings,
+            get_rich_text_paste,
+            set_rich_text_paste,
+            get_json_output_settings,
+            set_json_output_settings,
+            get_email_settings,
+            set_email_settings,
+            get_plugins,
+            reload_plugins,
+            set_plugin_enabled,
+            get_sound_cue_settings,
+            get_notification_settings,
+            get_quiet_hours,
+            set_quiet_hours,
+            set_notification_settings,
+            set_sound_cue_settings,
+            get_whisper_priming,
+            set_whisper_priming,
+            get_archive_session_audio,
+            set_archive_session_audio,
+            retranscribe,
+            list_transcription_revisions,
+            edit_transcription,
+            restore_transcription_revision,
+            get_transcription_model,
+            set_transcription_model,
+            get_whisper_chunked,
+            set_whisper_chunked,
+            get_realtime_backend,
+            get_realtime_native_prompt,
+            get_realtime_text_repair,
+            set_realtime_text_repair,
+            set_realtime_native_prompt,
+            get_realtime_session_grace,
+            set_realtime_session_grace,
+            get_max_recording_secs,
+            set_max_recording_secs,
+            get_realtime_noise_reduction,
+            set_realtime_noise_reduction,
+            set_realtime_backend,
+            get_local_whisper_model,
+            set_local_whisper_model,
+            get_ptt_settings,
+            set_ptt_settings,
+            list_hid_devices,
+            set_cancel_hotkey
         ])
         .setup(|app| {
+            // Tell the recording widget when the mic input is clipping
+            let clip_app = app.handle().clone();
+            app.state::<AppState>().clip_meter.set_listener(move |percentage| {
+                tlog!("📢 Mic clipping ({:.1}% of samples so far)", percentage);
+                let _ = emit_app_event(&clip_app, AppEvent::MicClipping(percentage));
+            });
+
+            // Watch connectivity so the UI can show offline state
+            let network_app = app.handle().clone();
+            app.state::<AppState>().network.start(move |online| {
+                let _ = emit_app_event(&network_app, if online { AppEvent::NetworkOnline } else { AppEvent::NetworkOffline });
+            });
+
+            // Tell the UI when OpenAI requests are being held back by rate limits
+            let rate_limit_app = app.handle().clone();
+            app.state::<AppState>().openai_client.set_rate_limit_listener(move |event| {
+                let _ = emit_app_event(&rate_limit_app, AppEvent::RateLimited(event));
+            });
+
+            // Other apps' volumes saved while ducking: put back what a crash left lowered, and keep
+            // them in the settings from now on (with sidetone they're at zero during recordings)
+            let database = app.state::<AppState>().database.clone();
+            let leftover = database.load_setting("ducked_volumes").ok().flatten()
+                .and_then(|json| serde_json::from_str::<Vec<(String, f32)>>(&json).ok())
+                .unwrap_or_default();
+            if !leftover.is_empty() {
+                match system_audio::restore_saved_volumes(&leftover) {
+                    Ok(count) => println!("🔊 Restored {} app volumes left ducked by the last run", count),
+                    Err(e) => eprintln!("⚠️ Failed to restore ducked app volumes: {}", e),
+                }
+                let _ = database.delete_setting("ducked_volumes");
+            }
+            system_audio::set_ducking_listener(move |originals| {
+                let saved = if originals.is_empty() {
+                    database.delete_setting("ducked_volumes")
+                } else {
+                    database.save_setting("ducked_volumes", &serde_json::to_string(originals).unwrap_or_default())
+                };
+                if let Err(e) = saved {
+                    eprintln!("⚠️ Failed to save ducked app volumes: {}", e);
+                }
+            });
+
+            // Compile the user's plugin scripts (each stays off until enabled in settings)
+            app.state::<AppState>().plugins.load(app.handle());
+
             // Create tray menu
-            let show_item = MenuItem::with_id(app, "show", "Abrir Dicta", true, None::<&str>)?;
-            let quit_item = MenuItem::with_id(app, "quit", "Sair", true, None::<&str>)?;
-            let menu = Menu::with_items(app, &[&show_item, &quit_item])?;
+            let menu = build_tray_menu(app.handle(), false)?;
 
             // Build system tray
             let _tray = TrayIconBuilder::with_id("main-tray")
@@ -2366,28 +6981,97 @@ pub fn run() {
                 .icon(app.default_window_icon().unwrap().clone())
                 .menu(&menu)
                 .on_menu_event(|app, event| match event.id.as_ref() {
-                    "show" => {
-                        if let Some(window) = app.get_webview_window("main") {
-                            let _ = window.show();
-                            let _ = window.set_focus();
-                        }
-                    }
+                    "show" => show_main_window(app),
                     "quit" => {
                         app.exit(0);
                     }
+                    "toggle_dictation" => toggle_recording(app, RecordingTrigger::Transcribe),
+                    "prompt_mode" => toggle_recording(app, RecordingTrigger::UserPromptModel),
+                    "toggle_meeting" => {
+                        let active = app.try_state::<AppState>()
+                            .map(|state| state.meeting.lock().unwrap().is_some())
+                            .unwrap_or(false);
+                        if active {
+                            let app_handle = app.clone();
+                            tauri::async_runtime::spawn(async move {
+                                if let Err(e) = stop_meeting_inner(&app_handle).await {
+                                    eprintln!("❌ Failed to stop meeting: {}", e);
+                                }
+                            });
+                        } else if let Err(e) = start_meeting_inner(app) {
+                            eprintln!("❌ Failed to start meeting: {}", e);
+                        }
+                    }
+                    "toggle_tts" => {
+                        if let Some(state) = app.try_state::<AppState>() {
+                            let new_val = !*state.tts_enabled.lock().unwrap();
+                            if let Err(e) = apply_tts_enabled(app, &state, new_val) {
+                                eprintln!("⚠️ {}", e);
+                            }
+                        }
+                    }
+                    "retry_paste" => {
+                        let app_handle = app.clone();
+                        std::thread::spawn(move || {
+                            if let Err(e) = retry_failed_paste(&app_handle) {
+                                eprintln!("❌ Failed to retry paste: {}", e);
+                            }
+                        });
+                    }
+                    "forget_last" => {
+                        if let Err(e) = forget_last_dictation(app) {
+                            eprintln!("⚠️ {}", e);
+                        }
+                    }
+                    "toggle_incognito" => {
+                        if let Some(state) = app.try_state::<AppState>() {
+                            let new_val = !is_incognito(&state.database);
+                            if let Err(e) = apply_incognito(app, &state, new_val) {
+                                eprintln!("⚠️ {}", e);
+                            }
+                        }
+                    }
+                    id if id.starts_with("history:") => {
+                        let Some(state) = app.try_state::<AppState>() else { return };
+                        let Ok(entry_id) = id["history:".len()..].parse::<i64>() else { return };
+                        match state.database.load_transcription(entry_id) {
+                            Ok(Some(entry)) => {
+                                println!("📋 Pasting history entry {} from tray", entry_id);
+                                let app_handle = app.clone();
+                                std::thread::spawn(move || {
+                                    if let Err(e) = auto_paste_text(&app_handle, &entry.text) {
+                                        eprintln!("❌ Failed to paste: {}", e);
+                                    }
+                                });
+                            }
+                            _ => println!("⚠️ History entry {} not found", entry_id),
+                        }
+                    }
+                    id if id.starts_with("profile:") => {
+                        let name = &id["profile:".len()..];
+                        if let Some(state) = app.try_state::<AppState>() {
+                            if let Err(e) = apply_profile(app, &state, name) {
+                                eprintln!("⚠️ Failed to switch profile: {}", e);
+                                // Rebuild to undo the check mark toggled by the click
+                                refresh_tray_menu(app);
+                            }
+                        }
+                    }
                     _ => {}
                 })
                 .on_tray_icon_event(|tray, event| {
                     if let TrayIconEvent::Click { button: tauri::tray::MouseButton::Left, .. } = event {
-                        let app = tray.app_handle();
-                        if let Some(window) = app.get_webview_window("main") {
-                            let _ = window.show();
-                            let _ = window.set_focus();
-                        }
+                        show_main_window(tray.app_handle());
                     }
                 })
                 .build(app)?;
 
+            // Keep the tray's recent-history items in sync
+            {
+                let app_for_tray = app.handle().clone();
+                app.listen_any("history-updated", move |_| refresh_tray_menu(&app_for_tray));
+            }
+
             // Handle window close event - minimize to tray instead of closing
             if let Some(window) = app.get_webview_window("main") {
                 let window_clone = window.clone();
@@ -2402,22 +7086,28 @@ pub fn run() {
             }
 
             // Clear any stale mute from a previous crash
-            let _ = system_audio::unmute_system_audio();
-
-            // Register global hotkeys (use .ok() to avoid crash if shortcut is already taken)
-            let shortcuts = [
-                Shortcut::new(Some(Modifiers::CONTROL), Code::Space),
-                Shortcut::new(Some(Modifiers::CONTROL | Modifiers::SHIFT), Code::Space),
-                Shortcut::new(Some(Modifiers::CONTROL | Modifiers::ALT), Code::Space),
-                Shortcut::new(Some(Modifiers::ALT | Modifiers::SHIFT), Code::KeyZ),
-                Shortcut::new(Some(Modifiers::CONTROL), Code::KeyB),
-                Shortcut::new(Some(Modifiers::CONTROL | Modifiers::ALT), Code::KeyS),
-                Shortcut::new(Some(Modifiers::ALT | Modifiers::SHIFT), Code::KeyS),
-            ];
-            for shortcut in &shortcuts {
-                if let Err(e) = app.global_shortcut().register(*shortcut) {
-                    eprintln!("⚠️ Failed to register shortcut {:?}: {}", shortcut, e);
-                }
+            let _ = restore_system_audio();
+
+            // Register global hotkeys (a combo that's already taken is logged and left unbound)
+            {
+                let state = app.state::<AppState>();
+                state.shortcuts.load_all(app.handle(), &state.database);
+            }
+
+            // Push-to-talk from extra mouse buttons / USB foot pedal
+            {
+                let (ptt_tx, ptt_rx) = std::sync::mpsc::channel();
+                *app.state::<AppState>().ptt_sender.lock().unwrap() = Some(ptt_tx.clone());
+                input_listener::start_mouse_hook(ptt_tx);
+                apply_ptt_settings(app.handle());
+
+                let app_for_ptt = app.handle().clone();
+                std::thread::spawn(move || {
+                    let mut held_since_start = false;
+                    for event in ptt_rx {
+                        handle_ptt_event(&app_for_ptt, event, &mut held_since_start);
+                    }
+                });
             }
 
             // Start background queue retry loop (every 30 seconds)
@@ -2425,30 +7115,125 @@ pub fn run() {
                 let state = app.state::<AppState>();
                 let db_for_queue = state.database.clone();
                 let openai_for_queue = state.openai_client.clone();
-                let last_tx_for_queue = state.last_transcription.clone();
                 let app_for_queue = app.handle().clone();
+                let network_for_queue = state.network.clone();
 
                 tauri::async_runtime::spawn(async move {
                     loop {
-                        tokio::time::sleep(Duration::from_secs(30)).await;
+                        // Don't wait out the interval when connectivity just came back
+                        tokio::select! {
+                            _ = tokio::time::sleep(Duration::from_secs(30)) => {}
+                            _ = network_for_queue.wait_online() => tlog!("Network back online, retrying queue now"),
+                        }
                         process_retry_queue(
                             db_for_queue.clone(),
                             openai_for_queue.clone(),
-                            last_tx_for_queue.clone(),
                             app_for_queue.clone(),
                         ).await;
                     }
                 });
             }
 
+            // Look for updates shortly after startup, then every few hours
+            {
+                let state = app.state::<AppState>();
+                let db_for_updates = state.database.clone();
+                let network_for_updates = state.network.clone();
+                let app_for_updates = app.handle().clone();
+
+                tauri::async_runtime::spawn(async move {
+                    tokio::time::sleep(Duration::from_secs(30)).await;
+                    let mut notified: Option<String> = None;
+                    loop {
+                        if network_for_updates.is_online() {
+                            let channel = updates::UpdateChannel::load(&db_for_updates);
+                            match updates::check(&app_for_updates, channel).await {
+                                // Tell the UI once per version
+                                Ok(Some(update)) if notified.as_deref() != Some(update.version.as_str()) => {
+                                    tlog!("🔄 Update available: {} ({} channel)", update.version, channel.as_str());
+                                    notified = Some(update.version.clone());
+                                    let _ = emit_app_event(&app_for_updates, AppEvent::UpdateAvailable(updates::info(&update, channel)));
+                                }
+                                Ok(_) => {}
+                                Err(e) => eprintln!("⚠️ {}", e),
+                            }
+                        }
+                        tokio::time::sleep(updates::CHECK_INTERVAL).await;
+                    }
+                });
+            }
+
+            // Queue WAV cleanup at startup, then every 10 minutes
+            {
+                let state = app.state::<AppState>();
+                let db_for_cleanup = state.database.clone();
+                let queue_dir_for_cleanup = state.queue_dir.clone();
+                let app_for_cleanup = app.handle().clone();
+
+                tauri::async_runtime::spawn(async move {
+                    loop {
+                        cleanup_queue_files(&app_for_cleanup, &db_for_cleanup, &queue_dir_for_cleanup);
+                        tokio::time::sleep(Duration::from_secs(600)).await;
+                    }
+                });
+            }
+
+            // Start background daily spend check (every 60 seconds)
+            {
+                let state = app.state::<AppState>();
+                let db_for_spend = state.database.clone();
+                let app_for_spend = app.handle().clone();
+
+                tauri::async_runtime::spawn(async move {
+                    loop {
+                        check_daily_spend(&app_for_spend, &db_for_spend);
+                        tokio::time::sleep(Duration::from_secs(60)).await;
+                    }
+                });
+            }
+
+            // Start the local API server if enabled
+            {
+                let app_for_api = app.handle().clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = apply_api_server_settings(&app_for_api).await {
+                        eprintln!("⚠️ Failed to start local API server: {}", e);
+                    }
+                });
+            }
+
+            // Start background sync check (every 60 seconds)
+            {
+                let app_for_sync = app.handle().clone();
+                tauri::async_runtime::spawn(async move {
+                    loop {
+                        tokio::time::sleep(Duration::from_secs(60)).await;
+                        check_periodic_sync(&app_for_sync).await;
+                    }
+                });
+            }
+
+            // Start background daily digest check (every 60 seconds)
+            {
+                let state = app.state::<AppState>();
+                let db_for_digest = state.database.clone();
+                let openai_for_digest = state.openai_client.clone();
+                let app_for_digest = app.handle().clone();
+
+                tauri::async_runtime::spawn(async move {
+                    loop {
+                        tokio::time::sleep(Duration::from_secs(60)).await;
+                        check_daily_digest(&app_for_digest, &db_for_digest, &openai_for_digest).await;
+                    }
+                });
+            }
+
             println!("✅ Dicta is running!");
-            println!("📌 Press Ctrl+Space to start/stop recording");
-            println!("📌 Press Ctrl+Shift+Space for GPT-4o-mini prompt mode");
-            println!("📌 Press Ctrl+Alt+Space for GPT-4.1 prompt mode");
-            println!("📌 Press Alt+Shift+Z to paste last transcription");
-            println!("📌 Press Ctrl+B to open prompt input window");
-            println!("📌 Press Ctrl+Alt+S to toggle TTS");
-            println!("📌 Press Ctrl+S to stop TTS / read last message");
+            for binding in app.state::<AppState>().shortcuts.bindings() {
+                if !binding.hotkey.is_empty() {
+                    println!("📌 Press {} to {}", binding.hotkey, binding.action.description());
+                }
+            }
             println!("🔑 OpenAI API key loaded");
 
             Ok(())
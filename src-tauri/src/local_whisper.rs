@@ -0,0 +1,221 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
+
+/// whisper.cpp expects 16 kHz mono f32
+const WHISPER_SAMPLE_RATE: usize = 16000;
+/// StreamingAudioRecorder delivers 24 kHz mono i16 (the Realtime API format)
+const INPUT_SAMPLE_RATE: usize = 24000;
+
+/// Don't transcribe a segment until it has at least this much audio
+const MIN_SEGMENT_MS: usize = 2000;
+/// Force a transcription if the speaker never pauses
+const MAX_SEGMENT_MS: usize = 15000;
+/// Trailing window checked for a pause (like whisper.cpp stream's vad_last_ms)
+const VAD_LAST_MS: usize = 600;
+/// Pause when the trailing window is this much quieter than the whole segment (vad_thold)
+const VAD_THRESHOLD: f32 = 0.6;
+/// Mean amplitude below this is treated as silence
+const SILENCE_FLOOR: f32 = 0.004;
+/// Characters of committed text used as the prompt for the next segment
+const PROMPT_TAIL_CHARS: usize = 400;
+
+/// A loaded whisper.cpp model (ggml .bin). Loading takes a while, so it's cached in AppState.
+pub struct LocalWhisper {
+    path: PathBuf,
+    context: WhisperContext,
+}
+
+impl LocalWhisper {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        println!("🧠 Loading local Whisper model: {}", path.display());
+        let path_str = path.to_str().ok_or("Invalid model path")?;
+        let context = WhisperContext::new_with_params(path_str, WhisperContextParameters::default())
+            .map_err(|e| format!("Failed to load Whisper model: {}", e))?;
+        println!("✅ Local Whisper model loaded");
+        Ok(Self { path: path.to_path_buf(), context })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Transcribe 16 kHz mono audio. Empty language = auto-detect.
+    pub fn transcribe(&self, samples: &[f32], language: &str, prompt: &str) -> Result<String, String> {
+        let mut state = self.context.create_state()
+            .map_err(|e| format!("Failed to create Whisper state: {}", e))?;
+
+        let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+        params.set_language(Some(if language.is_empty() { "auto" } else { language }));
+        if !prompt.is_empty() {
+            params.set_initial_prompt(prompt);
+        }
+        params.set_n_threads(std::thread::available_parallelism().map(|n| n.get() as i32).unwrap_or(4).min(8));
+        params.set_no_context(true);
+        params.set_suppress_blank(true);
+        params.set_print_special(false);
+        params.set_print_progress(false);
+        params.set_print_realtime(false);
+        params.set_print_timestamps(false);
+
+        state.full(params, samples)
+            .map_err(|e| format!("Whisper inference failed: {}", e))?;
+
+        let segments = state.full_n_segments()
+            .map_err(|e| format!("Failed to read segments: {}", e))?;
+        let mut text = String::new();
+        for i in 0..segments {
+            let segment = state.full_get_segment_text(i)
+                .map_err(|e| format!("Failed to read segment {}: {}", i, e))?;
+            text.push_str(&segment);
+        }
+        Ok(text.trim().to_string())
+    }
+}
+
+/// Linear resampling of a whole recording to the 16 kHz whisper.cpp expects
+pub fn resample(samples: &[f32], sample_rate: u32) -> Vec<f32> {
+    if sample_rate as usize == WHISPER_SAMPLE_RATE {
+        return samples.to_vec();
+    }
+    let step = sample_rate as f64 / WHISPER_SAMPLE_RATE as f64;
+    let len = (samples.len() as f64 / step) as usize;
+    (0..len)
+        .map(|i| {
+            let pos = i as f64 * step;
+            let j = pos as usize;
+            let frac = (pos - j as f64) as f32;
+            let next = samples.get(j + 1).copied().unwrap_or(samples[j]);
+            samples[j] * (1.0 - frac) + next * frac
+        })
+        .collect()
+}
+
+/// Streaming transcription on top of whisper.cpp: buffers microphone chunks and
+/// transcribes a segment whenever the speaker pauses (whisper.cpp stream's VAD mode),
+/// returning the new text as an append-only delta
+pub struct LocalStreamer {
+    whisper: Arc<LocalWhisper>,
+    language: String,
+    buffer: Vec<f32>,   // 16 kHz audio of the current segment
+    committed: String,  // Text transcribed so far (primes the next segment)
+    resample_pos: f64,  // Fractional read position carried between input chunks
+    last_input: Option<f32>,
+}
+
+impl LocalStreamer {
+    pub fn new(whisper: Arc<LocalWhisper>, language: String) -> Self {
+        Self {
+            whisper,
+            language,
+            buffer: Vec::new(),
+            committed: String::new(),
+            resample_pos: 0.0,
+            last_input: None,
+        }
+    }
+
+    /// Add a 24 kHz i16 chunk; returns a delta when a segment was completed
+    pub fn push(&mut self, chunk: &[i16]) -> Option<String> {
+        self.resample_into_buffer(chunk);
+
+        let min = MIN_SEGMENT_MS * WHISPER_SAMPLE_RATE / 1000;
+        let max = MAX_SEGMENT_MS * WHISPER_SAMPLE_RATE / 1000;
+        if self.buffer.len() < min {
+            return None;
+        }
+
+        let energy_all = mean_abs(&self.buffer);
+        if energy_all < SILENCE_FLOOR {
+            // Nothing but silence so far: drop it, keeping a short lead-in
+            let keep = VAD_LAST_MS * WHISPER_SAMPLE_RATE / 1000;
+            let drain = self.buffer.len() - keep;
+            self.buffer.drain(..drain);
+            return None;
+        }
+
+        let last = VAD_LAST_MS * WHISPER_SAMPLE_RATE / 1000;
+        let energy_last = mean_abs(&self.buffer[self.buffer.len() - last..]);
+        let paused = energy_last < VAD_THRESHOLD * energy_all;
+        if paused || self.buffer.len() >= max {
+            return self.transcribe_segment();
+        }
+        None
+    }
+
+    /// Transcribe whatever is buffered (recording stopped)
+    pub fn flush(&mut self) -> Option<String> {
+        if self.buffer.len() < WHISPER_SAMPLE_RATE / 4 || mean_abs(&self.buffer) < SILENCE_FLOOR {
+            self.buffer.clear();
+            return None;
+        }
+        self.transcribe_segment()
+    }
+
+    /// True while the current segment contains speech that hasn't been transcribed yet
+    pub fn has_pending_speech(&self) -> bool {
+        !self.buffer.is_empty() && mean_abs(&self.buffer) >= SILENCE_FLOOR
+    }
+
+    fn transcribe_segment(&mut self) -> Option<String> {
+        let segment = std::mem::take(&mut self.buffer);
+        let prompt = tail_chars(&self.committed, PROMPT_TAIL_CHARS);
+
+        let started = std::time::Instant::now();
+        let text = match self.whisper.transcribe(&segment, &self.language, &prompt) {
+            Ok(text) => text,
+            Err(e) => {
+                eprintln!("❌ Local transcription failed: {}", e);
+                return None;
+            }
+        };
+        println!(
+            "🧠 Local segment ({:.1}s audio) transcribed in {}ms: {}",
+            segment.len() as f32 / WHISPER_SAMPLE_RATE as f32,
+            started.elapsed().as_millis(),
+            text
+        );
+
+        if text.is_empty() {
+            return None;
+        }
+        let delta = if self.committed.is_empty() { text } else { format!(" {}", text) };
+        self.committed.push_str(&delta);
+        Some(delta)
+    }
+
+    /// Linear resampling 24 kHz -> 16 kHz, continuous across chunk boundaries
+    fn resample_into_buffer(&mut self, chunk: &[i16]) {
+        let step = INPUT_SAMPLE_RATE as f64 / WHISPER_SAMPLE_RATE as f64;
+        let input: Vec<f32> = self.last_input.into_iter()
+            .chain(chunk.iter().map(|&s| s as f32 / 32768.0))
+            .collect();
+        if input.len() < 2 {
+            self.last_input = input.last().copied();
+            return;
+        }
+
+        while self.resample_pos + 1.0 < input.len() as f64 {
+            let i = self.resample_pos as usize;
+            let frac = (self.resample_pos - i as f64) as f32;
+            self.buffer.push(input[i] * (1.0 - frac) + input[i + 1] * frac);
+            self.resample_pos += step;
+        }
+
+        // Re-base the position on the last input sample, which starts the next chunk
+        self.resample_pos -= (input.len() - 1) as f64;
+        self.last_input = input.last().copied();
+    }
+}
+
+fn mean_abs(samples: &[f32]) -> f32 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    samples.iter().map(|s| s.abs()).sum::<f32>() / samples.len() as f32
+}
+
+fn tail_chars(text: &str, max: usize) -> String {
+    let count = text.chars().count();
+    text.chars().skip(count.saturating_sub(max)).collect()
+}
@@ -0,0 +1,48 @@
+use std::path::Path;
+use std::sync::Arc;
+
+/// Stand-in for `local_whisper` in builds without the `local-whisper` feature (whisper.cpp
+/// needs cmake and clang to build). No model ever loads, so the local backend reports itself
+/// unavailable and live sessions fall back to the Realtime API.
+pub enum LocalWhisper {}
+
+impl LocalWhisper {
+    pub fn load(_path: &Path) -> Result<Self, String> {
+        Err(crate::LOCAL_WHISPER_UNAVAILABLE.to_string())
+    }
+
+    pub fn path(&self) -> &Path {
+        match *self {}
+    }
+
+    pub fn transcribe(&self, _samples: &[f32], _language: &str, _prompt: &str) -> Result<String, String> {
+        match *self {}
+    }
+}
+
+/// Never reached without a loaded model; kept so callers build the same either way
+pub fn resample(samples: &[f32], _sample_rate: u32) -> Vec<f32> {
+    samples.to_vec()
+}
+
+pub struct LocalStreamer {
+    whisper: Arc<LocalWhisper>,
+}
+
+impl LocalStreamer {
+    pub fn new(whisper: Arc<LocalWhisper>, _language: String) -> Self {
+        Self { whisper }
+    }
+
+    pub fn push(&mut self, _chunk: &[i16]) -> Option<String> {
+        match *self.whisper {}
+    }
+
+    pub fn flush(&mut self) -> Option<String> {
+        match *self.whisper {}
+    }
+
+    pub fn has_pending_speech(&self) -> bool {
+        match *self.whisper {}
+    }
+}
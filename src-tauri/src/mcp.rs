@@ -0,0 +1,104 @@
+use crate::{speak_aloud, AppState};
+use serde_json::{json, Value};
+use tauri::{AppHandle, Manager};
+
+/// MCP revision implemented (Streamable HTTP transport, JSON responses only)
+const PROTOCOL_VERSION: &str = "2025-03-26";
+const MAX_SEARCH_RESULTS: usize = 50;
+
+/// Handle one JSON-RPC message from an MCP client. Notifications return None (HTTP 202).
+pub fn handle(app: &AppHandle, message: Value) -> Option<Value> {
+    let id = message.get("id").cloned()?; // No id = notification (e.g. notifications/initialized)
+    let method = message["method"].as_str().unwrap_or_default();
+    let params = &message["params"];
+
+    let result = match method {
+        "initialize" => Ok(json!({
+            "protocolVersion": PROTOCOL_VERSION,
+            "capabilities": { "tools": {} },
+            "serverInfo": { "name": "dicta", "version": app.package_info().version.to_string() },
+        })),
+        "ping" => Ok(json!({})),
+        "tools/list" => Ok(json!({ "tools": tool_definitions() })),
+        "tools/call" => call_tool(app, params["name"].as_str().unwrap_or_default(), &params["arguments"]),
+        _ => Err((-32601, format!("Method not found: {}", method))),
+    };
+
+    Some(match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err((code, message)) => json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } }),
+    })
+}
+
+fn tool_definitions() -> Value {
+    json!([
+        {
+            "name": "get_last_transcription",
+            "description": "Return the text of the user's most recent dictation in Dicta.",
+            "inputSchema": { "type": "object", "properties": {} },
+        },
+        {
+            "name": "search_history",
+            "description": "Search the user's dictation history (case-insensitive text match), most recent first.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "query": { "type": "string", "description": "Text to look for" },
+                    "limit": { "type": "integer", "description": "Maximum results (default 10, max 50)" },
+                },
+                "required": ["query"],
+            },
+        },
+        {
+            "name": "speak_text",
+            "description": "Read text aloud to the user with Dicta's text-to-speech voice.",
+            "inputSchema": {
+                "type": "object",
+                "properties": { "text": { "type": "string", "description": "Text to speak" } },
+                "required": ["text"],
+            },
+        },
+    ])
+}
+
+/// Run a tool. Tool failures are reported in the result (isError) so the agent can see them.
+fn call_tool(app: &AppHandle, name: &str, arguments: &Value) -> Result<Value, (i32, String)> {
+    let state = app.state::<AppState>();
+    let outcome: Result<String, String> = match name {
+        "get_last_transcription" => state.database.load_recent_transcriptions(1)
+            .map_err(|e| format!("Failed to load history: {}", e))
+            .and_then(|entries| entries.into_iter().next().map(|e| e.text).ok_or("No transcriptions yet".to_string())),
+        "search_history" => {
+            let query = arguments["query"].as_str().unwrap_or_default().trim().to_lowercase();
+            if query.is_empty() {
+                return Err((-32602, "Missing 'query'".to_string()));
+            }
+            let limit = arguments["limit"].as_u64().map(|l| l as usize).unwrap_or(10).min(MAX_SEARCH_RESULTS);
+            state.database.load_transcriptions(None, None)
+                .map_err(|e| format!("Failed to load history: {}", e))
+                .map(|entries| {
+                    let matches: Vec<Value> = entries.into_iter()
+                        .filter(|e| e.text.to_lowercase().contains(&query))
+                        .take(limit)
+                        .map(|e| json!({ "id": e.id, "timestamp": e.timestamp, "mode": e.mode, "text": e.text }))
+                        .collect();
+                    Value::Array(matches).to_string()
+                })
+        }
+        "speak_text" => {
+            let text = arguments["text"].as_str().unwrap_or_default().trim().to_string();
+            if text.is_empty() {
+                return Err((-32602, "Missing 'text'".to_string()));
+            }
+            speak_aloud(app, &state, text);
+            Ok("Speaking".to_string())
+        }
+        _ => return Err((-32602, format!("Unknown tool: {}", name))),
+    };
+
+    println!("🧰 MCP tool call: {} (ok: {})", name, outcome.is_ok());
+    Ok(match outcome {
+        Ok(text) => json!({ "content": [{ "type": "text", "text": text }], "isError": false }),
+        Err(message) => json!({ "content": [{ "type": "text", "text": message }], "isError": true }),
+    })
+}
@@ -0,0 +1,463 @@
+use crate::audio::get_input_device_by_name;
+use crate::chunked::find_cut_point;
+use crate::db::{Database, MeetingEntry};
+use crate::keywords::KeywordSpotter;
+use crate::openai::OpenAIClient;
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use crate::events::{emit_app_event, AppEvent};
+use tauri::AppHandle;
+
+/// Both sources are resampled to 16 kHz mono before mixing (plenty for speech, keeps long meetings small)
+const MEETING_SAMPLE_RATE: usize = 16000;
+/// A chunk is flushed once this much audio is buffered (cut at the quietest point, see find_cut_point)
+const FLUSH_SECS: usize = 15;
+/// Audio younger than this may still receive samples from the slower source, so it isn't read yet
+const SETTLE_MS: usize = 500;
+/// A source that falls this far behind the wall clock is re-synced (loopback delivers nothing during silence)
+const RESYNC_MS: usize = 200;
+/// Tails shorter than this are skipped on stop
+const MIN_TAIL_MS: usize = 300;
+/// Wait before retrying a chunk whose transcription failed
+const RETRY_DELAY: Duration = Duration::from_secs(10);
+/// Characters of transcript used to prime the next chunk
+const PROMPT_TAIL_CHARS: usize = 400;
+
+pub const DEFAULT_SUMMARY_INTERVAL_MINS: u64 = 5;
+
+const ROLLING_SUMMARY_PROMPT: &str = "You are keeping notes of a meeting that is still in progress. \
+Update the current summary with the new part of the transcript. Keep it concise, in plain text, \
+in the same language as the transcript. Respond with ONLY the updated summary.";
+
+const FINAL_SUMMARY_PROMPT: &str = "The meeting below has ended. Using the notes taken so far and the last part \
+of the transcript, write the final summary: topics discussed and decisions made. Plain text, in the same \
+language as the transcript. Respond with ONLY the summary.";
+
+pub const ACTION_ITEMS_PROMPT: &str = "Extract the action items (tasks someone committed to or was asked to do) \
+from the transcript below, in the same language as the transcript. Include the owner and deadline when mentioned. \
+Respond with ONLY a JSON array of strings, or [] if there are none.";
+
+/// Mixed mic + loopback audio. Indices are absolute sample positions since the start of the meeting;
+/// samples before `base` have been transcribed and dropped.
+struct MixBuffer {
+    started: Instant,
+    base: usize,
+    samples: Vec<f32>,
+}
+
+impl MixBuffer {
+    /// Absolute position matching the wall clock
+    fn clock_position(&self) -> usize {
+        (self.started.elapsed().as_secs_f64() * MEETING_SAMPLE_RATE as f64) as usize
+    }
+}
+
+/// Per-source state: resampling position and where the source writes into the mix
+struct SourceWriter {
+    channels: usize,
+    step: f64,
+    resample_pos: f64,
+    last_input: Option<f32>,
+    cursor: Option<usize>,
+}
+
+impl SourceWriter {
+    fn new(sample_rate: u32, channels: usize) -> Self {
+        Self {
+            channels,
+            step: sample_rate as f64 / MEETING_SAMPLE_RATE as f64,
+            resample_pos: 0.0,
+            last_input: None,
+            cursor: None,
+        }
+    }
+
+    fn write(&mut self, mix: &Mutex<MixBuffer>, data: &[f32]) {
+        let input: Vec<f32> = self.last_input.into_iter()
+            .chain(data.chunks_exact(self.channels).map(|frame| frame.iter().sum::<f32>() / self.channels as f32))
+            .collect();
+        if input.len() < 2 {
+            self.last_input = input.last().copied();
+            return;
+        }
+
+        // Linear resampling, continuous across callbacks
+        let mut resampled = Vec::with_capacity((input.len() as f64 / self.step) as usize + 1);
+        while self.resample_pos + 1.0 < input.len() as f64 {
+            let i = self.resample_pos as usize;
+            let frac = (self.resample_pos - i as f64) as f32;
+            resampled.push(input[i] * (1.0 - frac) + input[i + 1] * frac);
+            self.resample_pos += self.step;
+        }
+        self.resample_pos -= (input.len() - 1) as f64;
+        self.last_input = input.last().copied();
+
+        let mut mix = mix.lock().unwrap();
+        let target = mix.clock_position().saturating_sub(resampled.len());
+        let resync = RESYNC_MS * MEETING_SAMPLE_RATE / 1000;
+        let cursor = match self.cursor {
+            Some(cursor) if cursor + resync >= target => cursor,
+            _ => target,
+        };
+        self.cursor = Some(cursor + resampled.len());
+
+        let end = cursor + resampled.len();
+        if end <= mix.base {
+            return; // Already transcribed
+        }
+        let needed = end - mix.base;
+        if mix.samples.len() < needed {
+            mix.samples.resize(needed, 0.0);
+        }
+        let base = mix.base;
+        for (offset, sample) in resampled.into_iter().enumerate() {
+            if let Some(slot) = (cursor + offset).checked_sub(base).and_then(|i| mix.samples.get_mut(i)) {
+                *slot = (*slot + sample).clamp(-1.0, 1.0);
+            }
+        }
+    }
+}
+
+/// Records the microphone and the system output (WASAPI loopback) into one mono track
+struct MeetingCapture {
+    running: Arc<Mutex<bool>>,
+    mix: Arc<Mutex<MixBuffer>>,
+}
+
+impl MeetingCapture {
+    /// Mic samples are dropped while `mic_muted` is set; the system audio keeps recording
+    fn start(mic_device: Option<String>, mic_muted: Arc<AtomicBool>) -> Result<Self, String> {
+        let running = Arc::new(Mutex::new(true));
+        let mix = Arc::new(Mutex::new(MixBuffer { started: Instant::now(), base: 0, samples: Vec::new() }));
+
+        let mic = get_input_device_by_name(mic_device.as_deref())?;
+        let mic_config = mic.default_input_config()
+            .map_err(|e| format!("Failed to get microphone config: {}", e))?;
+        spawn_source("microphone", mic, mic_config, mix.clone(), running.clone(), Some(mic_muted));
+
+        // Building an input stream on an output device gives a loopback capture on WASAPI
+        let host = cpal::default_host();
+        match host.default_output_device().map(|d| (d.default_output_config(), d)) {
+            Some((Ok(config), device)) => spawn_source("system audio", device, config, mix.clone(), running.clone(), None),
+            Some((Err(e), _)) => eprintln!("⚠️ Meeting: no loopback config, recording microphone only: {}", e),
+            None => eprintln!("⚠️ Meeting: no output device, recording microphone only"),
+        }
+
+        Ok(Self { running, mix })
+    }
+
+    /// Absolute position up to which the mix is complete
+    fn settled_end(&self) -> usize {
+        let mix = self.mix.lock().unwrap();
+        let settled = mix.clock_position().saturating_sub(SETTLE_MS * MEETING_SAMPLE_RATE / 1000);
+        settled.min(mix.base + mix.samples.len())
+    }
+
+    /// Absolute position of the last captured sample
+    fn end(&self) -> usize {
+        let mix = self.mix.lock().unwrap();
+        mix.base + mix.samples.len()
+    }
+
+    fn read(&self, start: usize, end: usize) -> Vec<f32> {
+        let mix = self.mix.lock().unwrap();
+        let start = start.saturating_sub(mix.base).min(mix.samples.len());
+        let end = end.saturating_sub(mix.base).min(mix.samples.len());
+        mix.samples[start..end.max(start)].to_vec()
+    }
+
+    /// Drop audio that has been transcribed
+    fn discard_before(&self, position: usize) {
+        let mut mix = self.mix.lock().unwrap();
+        let count = position.saturating_sub(mix.base).min(mix.samples.len());
+        mix.samples.drain(..count);
+        mix.base += count;
+    }
+
+    fn stop(&self) {
+        *self.running.lock().unwrap() = false;
+    }
+}
+
+/// Capture one device on its own thread (cpal streams aren't Send) until the meeting stops
+fn spawn_source(
+    label: &'static str,
+    device: cpal::Device,
+    config: cpal::SupportedStreamConfig,
+    mix: Arc<Mutex<MixBuffer>>,
+    running: Arc<Mutex<bool>>,
+    muted: Option<Arc<AtomicBool>>,
+) {
+    std::thread::spawn(move || {
+        if config.sample_format() != cpal::SampleFormat::F32 {
+            eprintln!("⚠️ Meeting: {} uses {:?} samples, skipping", label, config.sample_format());
+            return;
+        }
+        println!(
+            "🎙️ Meeting: capturing {} ({}, {} Hz, {} ch)",
+            label,
+            device.name().unwrap_or_default(),
+            config.sample_rate().0,
+            config.channels()
+        );
+
+        let mut writer = SourceWriter::new(config.sample_rate().0, config.channels() as usize);
+        let running_for_callback = running.clone();
+        let stream = match device.build_input_stream(
+            &config.into(),
+            move |data: &[f32], _: &cpal::InputCallbackInfo| {
+                let muted = muted.as_ref().is_some_and(|muted| muted.load(Ordering::Relaxed));
+                if *running_for_callback.lock().unwrap() && !muted {
+                    writer.write(&mix, data);
+                }
+            },
+            move |err| eprintln!("Meeting {} stream error: {}", label, err),
+            None,
+        ) {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("❌ Meeting: failed to capture {}: {}", label, e);
+                return;
+            }
+        };
+
+        if let Err(e) = stream.play() {
+            eprintln!("❌ Meeting: failed to start {} stream: {}", label, e);
+            return;
+        }
+
+        while *running.lock().unwrap() {
+            std::thread::sleep(Duration::from_millis(100));
+        }
+        drop(stream);
+        println!("🎙️ Meeting: {} released", label);
+    });
+}
+
+pub struct MeetingOptions {
+    pub mic_device: Option<String>,
+    pub summary_interval: Duration,
+    pub summary_model: String,
+    pub keyword_spotter: KeywordSpotter,
+}
+
+/// A meeting being recorded; `stop` finishes it and returns the saved entry
+pub struct MeetingHandle {
+    pub id: i64,
+    stopping: Arc<AtomicBool>,
+    mic_muted: Arc<AtomicBool>,
+    task: tauri::async_runtime::JoinHandle<Result<MeetingEntry, String>>,
+}
+
+impl MeetingHandle {
+    pub fn mic_muted(&self) -> bool {
+        self.mic_muted.load(Ordering::SeqCst)
+    }
+
+    /// Mute or unmute the meeting's microphone (the system audio is still recorded)
+    pub fn set_mic_muted(&self, muted: bool) {
+        self.mic_muted.store(muted, Ordering::SeqCst);
+    }
+
+    /// Stop recording, transcribe what's left and generate the final summary and action items
+    pub async fn stop(self) -> Result<MeetingEntry, String> {
+        self.stopping.store(true, Ordering::SeqCst);
+        self.task.await.map_err(|e| format!("Meeting task failed: {}", e))?
+    }
+}
+
+pub fn start_meeting(
+    app: AppHandle,
+    openai: Arc<OpenAIClient>,
+    database: Arc<Database>,
+    options: MeetingOptions,
+) -> Result<MeetingHandle, String> {
+    let started_at = chrono::Utc::now().timestamp_millis();
+    let id = database.create_meeting(started_at)
+        .map_err(|e| format!("Failed to create meeting: {}", e))?;
+    let mic_muted = Arc::new(AtomicBool::new(false));
+    let capture = MeetingCapture::start(options.mic_device.clone(), mic_muted.clone())?;
+    println!("📅 Meeting {} started (summary every {}s)", id, options.summary_interval.as_secs());
+
+    let stopping = Arc::new(AtomicBool::new(false));
+    let stopping_for_task = stopping.clone();
+    let task = tauri::async_runtime::spawn(async move {
+        run_meeting(id, started_at, capture, stopping_for_task, app, openai, database, options).await
+    });
+
+    Ok(MeetingHandle { id, stopping, mic_muted, task })
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn run_meeting(
+    id: i64,
+    started_at: i64,
+    capture: MeetingCapture,
+    stopping: Arc<AtomicBool>,
+    app: AppHandle,
+    openai: Arc<OpenAIClient>,
+    database: Arc<Database>,
+    options: MeetingOptions,
+) -> Result<MeetingEntry, String> {
+    let mut offset = 0usize;
+    let mut transcript = String::new();
+    let mut rolling_summary = String::new();
+    let mut summarized_len = 0usize; // Bytes of transcript already folded into rolling_summary
+    let mut last_summary = Instant::now();
+    let mut retry_at: Option<Instant> = None;
+    let MeetingOptions { summary_interval, summary_model, mut keyword_spotter, .. } = options;
+
+    loop {
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        let stopping = stopping.load(Ordering::SeqCst);
+        if stopping {
+            capture.stop();
+        }
+
+        // 1. Transcribe the next chunk once enough audio is buffered (everything that's left on stop)
+        let end = if stopping { capture.end() } else { capture.settled_end() };
+        let buffered = end.saturating_sub(offset);
+        let ready = buffered >= FLUSH_SECS * MEETING_SAMPLE_RATE
+            || (stopping && buffered >= MIN_TAIL_MS * MEETING_SAMPLE_RATE / 1000);
+        let retry_wait = retry_at.is_some_and(|at| Instant::now() < at);
+
+        if ready && !retry_wait {
+            let window = capture.read(offset, end);
+            let cut = if window.len() > FLUSH_SECS * MEETING_SAMPLE_RATE {
+                find_cut_point(&window, MEETING_SAMPLE_RATE)
+            } else {
+                window.len()
+            };
+            let mut chunk = window;
+            chunk.truncate(cut);
+
+            let prompt = tail_chars(&transcript, PROMPT_TAIL_CHARS);
+            let prompt = (!prompt.is_empty()).then_some(prompt);
+            match openai.transcribe_audio(chunk, MEETING_SAMPLE_RATE as u32, prompt.as_deref()).await {
+                Ok(text) => {
+                    retry_at = None;
+                    offset += cut;
+                    capture.discard_before(offset);
+                    let text = text.trim();
+                    if !text.is_empty() {
+                        let delta = if transcript.is_empty() { text.to_string() } else { format!(" {}", text) };
+                        transcript.push_str(&delta);
+                        keyword_spotter.feed(&app, &delta);
+                        let _ = emit_app_event(&app, AppEvent::MeetingTranscript(delta));
+                        if let Err(e) = database.update_meeting_progress(id, &transcript, &rolling_summary) {
+                            eprintln!("⚠️ Failed to save meeting progress: {}", e);
+                        }
+                    }
+                }
+                Err(e) => {
+                    eprintln!("❌ Meeting chunk transcription failed: {}", e);
+                    if stopping {
+                        // Don't hold up the final summary; the untranscribed tail is lost
+                        break;
+                    }
+                    retry_at = Some(Instant::now() + RETRY_DELAY);
+                }
+            }
+            continue;
+        }
+
+        if stopping && !ready {
+            break;
+        }
+
+        // 2. Fold the new part of the transcript into the rolling summary every few minutes
+        if last_summary.elapsed() >= summary_interval && transcript.len() > summarized_len {
+            last_summary = Instant::now();
+            let prompt = format!(
+                "{}\n\nCurrent summary:\n{}\n\nNew transcript:\n{}",
+                ROLLING_SUMMARY_PROMPT,
+                if rolling_summary.is_empty() { "(none yet)" } else { &rolling_summary },
+                &transcript[summarized_len..]
+            );
+            match openai.send_prompt(&prompt, &summary_model, &[], None).await {
+                Ok(summary) => {
+                    rolling_summary = summary;
+                    summarized_len = transcript.len();
+                    let _ = emit_app_event(&app, AppEvent::MeetingSummary(rolling_summary.clone()));
+                    if let Err(e) = database.update_meeting_progress(id, &transcript, &rolling_summary) {
+                        eprintln!("⚠️ Failed to save meeting progress: {}", e);
+                    }
+                    println!("📝 Meeting {} rolling summary updated", id);
+                }
+                Err(e) => eprintln!("❌ Meeting rolling summary failed: {}", e),
+            }
+        }
+    }
+    keyword_spotter.finish(&app);
+
+    // 3. Final summary and action items
+    let ended_at = chrono::Utc::now().timestamp_millis();
+    let (final_summary, action_items) = if transcript.trim().is_empty() {
+        (None, Vec::new())
+    } else {
+        let summary_prompt = format!(
+            "{}\n\nNotes so far:\n{}\n\nLast part of the transcript:\n{}",
+            FINAL_SUMMARY_PROMPT,
+            if rolling_summary.is_empty() { "(none)" } else { &rolling_summary },
+            &transcript[summarized_len..]
+        );
+        let final_summary = match openai.send_prompt(&summary_prompt, &summary_model, &[], None).await {
+            Ok(summary) => Some(summary),
+            Err(e) => {
+                eprintln!("❌ Meeting final summary failed: {}", e);
+                (!rolling_summary.is_empty()).then(|| rolling_summary.clone())
+            }
+        };
+
+        let items_prompt = format!("{}\n\nTranscript:\n{}", ACTION_ITEMS_PROMPT, transcript);
+        let action_items = match openai.send_prompt(&items_prompt, &summary_model, &[], None).await {
+            Ok(response) => parse_action_items(&response),
+            Err(e) => {
+                eprintln!("❌ Meeting action items failed: {}", e);
+                Vec::new()
+            }
+        };
+        (final_summary, action_items)
+    };
+
+    database.finish_meeting(id, ended_at, &transcript, final_summary.as_deref(), &action_items)
+        .map_err(|e| format!("Failed to save meeting: {}", e))?;
+
+    let entry = MeetingEntry {
+        id,
+        started_at,
+        ended_at: Some(ended_at),
+        transcript,
+        rolling_summary,
+        final_summary,
+        action_items,
+    };
+    let _ = emit_app_event(&app, AppEvent::MeetingFinished(entry.clone()));
+    println!("📅 Meeting {} finished ({} action items)", id, entry.action_items.len());
+    Ok(entry)
+}
+
+/// Parse the model's JSON array of action items, falling back to one item per line
+pub fn parse_action_items(response: &str) -> Vec<String> {
+    let json = match (response.find('['), response.rfind(']')) {
+        (Some(start), Some(end)) if start < end => &response[start..=end],
+        _ => response,
+    };
+    if let Ok(items) = serde_json::from_str::<Vec<String>>(json) {
+        return items.into_iter().map(|i| i.trim().to_string()).filter(|i| !i.is_empty()).collect();
+    }
+
+    response
+        .lines()
+        .map(|line| line.trim().trim_start_matches(['-', '*', '•']).trim())
+        .filter(|line| !line.is_empty() && *line != "[]")
+        .map(|line| line.to_string())
+        .collect()
+}
+
+fn tail_chars(text: &str, max: usize) -> String {
+    let count = text.chars().count();
+    text.chars().skip(count.saturating_sub(max)).collect()
+}
@@ -0,0 +1,71 @@
+use crate::queue;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::Duration;
+use tokio::sync::Notify;
+use windows::Win32::NetworkManagement::IpHelper::NotifyAddrChange;
+
+/// Re-probe this often even without notifications (an upstream outage doesn't change local addresses)
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+/// Adapters need a moment to get a route after their address changes
+const SETTLE_DELAY: Duration = Duration::from_secs(2);
+
+/// Tracks connectivity by probing on OS address-change notifications and on a slow poll
+pub struct NetworkMonitor {
+    online: AtomicBool,
+    came_online: Notify,
+}
+
+impl NetworkMonitor {
+    pub fn new() -> Self {
+        Self { online: AtomicBool::new(true), came_online: Notify::new() }
+    }
+
+    pub fn is_online(&self) -> bool {
+        self.online.load(Ordering::SeqCst)
+    }
+
+    /// Resolves when connectivity comes back (immediately if it came back since the last call)
+    pub async fn wait_online(&self) {
+        self.came_online.notified().await;
+    }
+
+    /// Start watching; `on_change` runs on the monitor thread whenever the status flips
+    pub fn start(self: &Arc<Self>, on_change: impl Fn(bool) + Send + 'static) {
+        let (change_tx, change_rx) = mpsc::channel();
+
+        // NotifyAddrChange with no handle/overlapped blocks until any adapter's IP address changes
+        std::thread::spawn(move || loop {
+            let result = unsafe { NotifyAddrChange(std::ptr::null_mut(), std::ptr::null()) };
+            if result != 0 {
+                eprintln!("⚠️ Network change notifications unavailable (error {}), polling only", result);
+                return;
+            }
+            if change_tx.send(()).is_err() {
+                return;
+            }
+        });
+
+        let monitor = self.clone();
+        std::thread::spawn(move || loop {
+            let online = queue::is_online();
+            if monitor.online.swap(online, Ordering::SeqCst) != online {
+                println!("{}", if online { "🌐 Network online" } else { "📡 Network offline" });
+                if online {
+                    monitor.came_online.notify_one();
+                }
+                on_change(online);
+            }
+
+            match change_rx.recv_timeout(POLL_INTERVAL) {
+                Ok(()) => {
+                    std::thread::sleep(SETTLE_DELAY);
+                    while change_rx.try_recv().is_ok() {} // Collapse bursts of changes into one probe
+                }
+                Err(mpsc::RecvTimeoutError::Timeout) => {}
+                // Watcher gave up; keep polling
+                Err(mpsc::RecvTimeoutError::Disconnected) => std::thread::sleep(POLL_INTERVAL),
+            }
+        });
+    }
+}
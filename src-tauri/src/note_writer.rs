@@ -0,0 +1,75 @@
+use chrono::{DateTime, Local};
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Line appended per dictation when no template is configured
+pub const DEFAULT_ENTRY_TEMPLATE: &str = "- {time} {text}";
+
+/// Expand `{date}`, `{time}`, `{year}`, `{month}`, `{day}`, `{date:<chrono format>}` and `{text}`.
+/// Unknown placeholders are kept as-is.
+pub fn expand_template(template: &str, now: &DateTime<Local>, text: &str) -> String {
+    let mut out = String::with_capacity(template.len() + text.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let Some(len) = rest[start..].find('}') else {
+            rest = &rest[start..];
+            break;
+        };
+        let token = &rest[start + 1..start + len];
+        match token {
+            "date" => out.push_str(&now.format("%Y-%m-%d").to_string()),
+            "time" => out.push_str(&now.format("%H:%M").to_string()),
+            "year" => out.push_str(&now.format("%Y").to_string()),
+            "month" => out.push_str(&now.format("%m").to_string()),
+            "day" => out.push_str(&now.format("%d").to_string()),
+            "text" => out.push_str(text),
+            _ => match token.strip_prefix("date:") {
+                Some(format) => {
+                    use std::fmt::Write as _;
+                    // Invalid chrono formats make Display fail; keep the placeholder then
+                    if write!(out, "{}", now.format(format)).is_err() {
+                        out.push_str(&rest[start..=start + len]);
+                    }
+                }
+                None => out.push_str(&rest[start..=start + len]),
+            },
+        }
+        rest = &rest[start + len + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Append `text` to the note at `path_template` (e.g. `C:\Vault\Daily\{date}.md`),
+/// creating the file and its folders if needed. Returns the file written.
+pub fn append(path_template: &str, entry_template: &str, text: &str) -> Result<PathBuf, String> {
+    let now = Local::now();
+    let path = PathBuf::from(expand_template(path_template.trim(), &now, ""));
+    if path.as_os_str().is_empty() {
+        return Err("Note path is not configured".to_string());
+    }
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create note folder: {}", e))?;
+    }
+
+    // Start on a new line if the note doesn't end with one
+    let needs_newline = std::fs::read(&path)
+        .map(|existing| existing.last().is_some_and(|&b| b != b'\n'))
+        .unwrap_or(false);
+    let mut entry = expand_template(entry_template, &now, text.trim());
+    if needs_newline {
+        entry.insert(0, '\n');
+    }
+    entry.push('\n');
+
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| format!("Failed to open note: {}", e))?;
+    file.write_all(entry.as_bytes()).map_err(|e| format!("Failed to write note: {}", e))?;
+
+    println!("📓 Appended {} chars to {}", text.len(), path.display());
+    Ok(path)
+}
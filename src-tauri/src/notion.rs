@@ -0,0 +1,97 @@
+use serde_json::json;
+
+const NOTION_API: &str = "https://api.notion.com/v1";
+const NOTION_VERSION: &str = "2022-06-28";
+/// Notion rejects rich text objects longer than this
+const MAX_TEXT_LEN: usize = 2000;
+/// ...and more than this many blocks in one request
+const MAX_BLOCKS: usize = 100;
+const TITLE_CHARS: usize = 60;
+
+/// Create a page in the Notion database with the transcription as its body. Returns the page URL.
+pub async fn create_page(token: &str, database_id: &str, text: &str) -> Result<String, String> {
+    let client = reqwest::Client::new();
+    let title_property = title_property(&client, token, database_id).await?;
+
+    let children: Vec<serde_json::Value> = paragraphs(text)
+        .into_iter()
+        .take(MAX_BLOCKS)
+        .map(|p| json!({
+            "object": "block",
+            "type": "paragraph",
+            "paragraph": { "rich_text": [{ "type": "text", "text": { "content": p } }] },
+        }))
+        .collect();
+    let body = json!({
+        "parent": { "database_id": database_id },
+        "properties": {
+            title_property: { "title": [{ "type": "text", "text": { "content": page_title(text) } }] },
+        },
+        "children": children,
+    });
+
+    let response = client
+        .post(format!("{}/pages", NOTION_API))
+        .bearer_auth(token)
+        .header("Notion-Version", NOTION_VERSION)
+        .json(&body)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send request: {}", e))?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(format!("Notion API error ({}): {}", status, error_text));
+    }
+
+    let page: serde_json::Value = response.json().await.map_err(|e| format!("Failed to parse response: {}", e))?;
+    let url = page["url"].as_str().unwrap_or_default().to_string();
+    println!("📤 Created Notion page: {}", url);
+    Ok(url)
+}
+
+/// Name of the database's title column (it's "Name" by default but can be renamed)
+async fn title_property(client: &reqwest::Client, token: &str, database_id: &str) -> Result<String, String> {
+    let response = client
+        .get(format!("{}/databases/{}", NOTION_API, database_id))
+        .bearer_auth(token)
+        .header("Notion-Version", NOTION_VERSION)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send request: {}", e))?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(format!("Notion API error ({}): {}", status, error_text));
+    }
+
+    let database: serde_json::Value = response.json().await.map_err(|e| format!("Failed to parse response: {}", e))?;
+    database["properties"]
+        .as_object()
+        .and_then(|props| props.iter().find(|(_, p)| p["type"] == "title"))
+        .map(|(name, _)| name.clone())
+        .ok_or_else(|| "Notion database has no title property".to_string())
+}
+
+/// First line of the text, shortened
+fn page_title(text: &str) -> String {
+    let first_line = text.lines().find(|l| !l.trim().is_empty()).unwrap_or("Dicta").trim();
+    if first_line.chars().count() <= TITLE_CHARS {
+        return first_line.to_string();
+    }
+    let mut title: String = first_line.chars().take(TITLE_CHARS).collect();
+    title.push('…');
+    title
+}
+
+/// Split text into paragraph blocks that fit Notion's rich text limit
+fn paragraphs(text: &str) -> Vec<String> {
+    let mut blocks = Vec::new();
+    for paragraph in text.split("\n\n").map(str::trim).filter(|p| !p.is_empty()) {
+        let chars: Vec<char> = paragraph.chars().collect();
+        for piece in chars.chunks(MAX_TEXT_LEN) {
+            blocks.push(piece.iter().collect());
+        }
+    }
+    blocks
+}
@@ -0,0 +1,320 @@
+/// Spelled-out numbers and dates to digits in transcripts ("vinte e cinco de março" →
+/// "25 de março"). The words are per language: add a `NumberWords` and list it in `rules_for`.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NumberStyle {
+    Off,
+    Dates, // Only the day and year of dates
+    All,   // Every number, dates included
+}
+
+impl NumberStyle {
+    /// Parse the `number_normalization` setting ("off" | "dates" | "all")
+    pub fn from_setting(value: &str) -> Self {
+        match value {
+            "dates" => NumberStyle::Dates,
+            "all" => NumberStyle::All,
+            _ => NumberStyle::Off,
+        }
+    }
+}
+
+/// How one language spells numbers. Words are given lowercase.
+pub trait NumberWords: Sync {
+    /// Value of a number word below a thousand ("vinte" → 20, "trezentos" → 300)
+    fn small(&self, word: &str) -> Option<u64>;
+    /// Multiplier of a scale word ("mil" → 1000)
+    fn scale(&self, word: &str) -> Option<u64>;
+    /// Word joining the parts of a number ("vinte e cinco")
+    fn is_joiner(&self, word: &str) -> bool;
+    /// Words for "one" that are usually an article ("um carro"), left alone outside dates
+    fn is_article(&self, word: &str) -> bool;
+    /// Day written as an ordinal ("primeiro" de maio) and how it's abbreviated ("1º")
+    fn ordinal_day(&self, word: &str) -> Option<&'static str>;
+    fn is_month(&self, word: &str) -> bool;
+    /// Word between day, month and year ("de")
+    fn is_date_joiner(&self, word: &str) -> bool;
+    fn thousands_separator(&self) -> char;
+}
+
+/// Rules for a language code ("pt", "pt-BR"); None when numbers in it aren't normalized
+pub fn rules_for(language: &str) -> Option<&'static dyn NumberWords> {
+    match language.split(['-', '_']).next().unwrap_or("").to_lowercase().as_str() {
+        "pt" => Some(&PortugueseWords),
+        _ => None,
+    }
+}
+
+pub struct PortugueseWords;
+
+impl NumberWords for PortugueseWords {
+    fn small(&self, word: &str) -> Option<u64> {
+        let value = match word {
+            "zero" => 0,
+            "um" | "uma" => 1,
+            "dois" | "duas" => 2,
+            "três" | "tres" => 3,
+            "quatro" => 4,
+            "cinco" => 5,
+            "seis" => 6,
+            "sete" => 7,
+            "oito" => 8,
+            "nove" => 9,
+            "dez" => 10,
+            "onze" => 11,
+            "doze" => 12,
+            "treze" => 13,
+            "catorze" | "quatorze" => 14,
+            "quinze" => 15,
+            "dezesseis" => 16,
+            "dezessete" => 17,
+            "dezoito" => 18,
+            "dezenove" => 19,
+            "vinte" => 20,
+            "trinta" => 30,
+            "quarenta" => 40,
+            "cinquenta" => 50,
+            "sessenta" => 60,
+            "setenta" => 70,
+            "oitenta" => 80,
+            "noventa" => 90,
+            "cem" | "cento" => 100,
+            "duzentos" | "duzentas" => 200,
+            "trezentos" | "trezentas" => 300,
+            "quatrocentos" | "quatrocentas" => 400,
+            "quinhentos" | "quinhentas" => 500,
+            "seiscentos" | "seiscentas" => 600,
+            "setecentos" | "setecentas" => 700,
+            "oitocentos" | "oitocentas" => 800,
+            "novecentos" | "novecentas" => 900,
+            _ => return None,
+        };
+        Some(value)
+    }
+
+    fn scale(&self, word: &str) -> Option<u64> {
+        match word {
+            "mil" => Some(1_000),
+            "milhão" | "milhao" | "milhões" | "milhoes" => Some(1_000_000),
+            "bilhão" | "bilhao" | "bilhões" | "bilhoes" => Some(1_000_000_000),
+            _ => None,
+        }
+    }
+
+    fn is_joiner(&self, word: &str) -> bool {
+        word == "e"
+    }
+
+    fn is_article(&self, word: &str) -> bool {
+        matches!(word, "um" | "uma")
+    }
+
+    fn ordinal_day(&self, word: &str) -> Option<&'static str> {
+        (word == "primeiro").then_some("1º")
+    }
+
+    fn is_month(&self, word: &str) -> bool {
+        matches!(
+            word,
+            "janeiro" | "fevereiro" | "março" | "marco" | "abril" | "maio" | "junho" | "julho" | "agosto"
+                | "setembro" | "outubro" | "novembro" | "dezembro"
+        )
+    }
+
+    fn is_date_joiner(&self, word: &str) -> bool {
+        word == "de"
+    }
+
+    fn thousands_separator(&self) -> char {
+        '.'
+    }
+}
+
+/// A word of the text: byte range and lowercase form
+struct Word {
+    start: usize,
+    end: usize,
+    lower: String,
+}
+
+/// Replace spelled-out numbers in `text` per `style`
+pub fn normalize(text: &str, style: NumberStyle, rules: &dyn NumberWords) -> String {
+    if style == NumberStyle::Off {
+        return text.to_string();
+    }
+
+    let words = split_words(text);
+    // Numbers only span words separated by spaces ("vinte, cinco" is two numbers)
+    let mut run_end = vec![0; words.len()];
+    for i in (0..words.len()).rev() {
+        let joined = i + 1 < words.len() && text[words[i].end..words[i + 1].start].trim().is_empty();
+        run_end[i] = if joined { run_end[i + 1] } else { i + 1 };
+    }
+    let lower: Vec<&str> = words.iter().map(|w| w.lower.as_str()).collect();
+
+    let mut replacements: Vec<(usize, usize, String)> = Vec::new();
+    let mut i = 0;
+    while i < words.len() {
+        let run = &lower[i..run_end[i]];
+        if let Some(date) = parse_date(run, rules) {
+            replacements.push((words[i].start, words[i + date.day_len - 1].end, date.day));
+            if let Some((offset, len, year)) = date.year {
+                replacements.push((words[i + offset].start, words[i + offset + len - 1].end, year.to_string()));
+            }
+            i += date.len;
+            continue;
+        }
+        // Skipped whole in `Dates` style, so "trinta e dois de março" doesn't become "trinta e 2"
+        if let Some((value, len)) = parse_number(run, rules) {
+            if style == NumberStyle::All {
+                let scale = rules.scale(run[len - 1]).filter(|scale| *scale >= 1_000_000 && len > 1);
+                match scale {
+                    // "dois milhões" reads better as "2 milhões" than as seven digits
+                    Some(scale) if value % scale == 0 && value / scale < 1000 => {
+                        replacements.push((words[i].start, words[i + len - 2].end, (value / scale).to_string()));
+                    }
+                    _ if len == 1 && rules.is_article(run[0]) => {}
+                    _ => {
+                        let digits = format_number(value, rules.thousands_separator());
+                        replacements.push((words[i].start, words[i + len - 1].end, digits));
+                    }
+                }
+            }
+            i += len;
+            continue;
+        }
+        i += 1;
+    }
+
+    let mut result = String::with_capacity(text.len());
+    let mut last = 0;
+    for (start, end, replacement) in replacements {
+        result.push_str(&text[last..start]);
+        result.push_str(&replacement);
+        last = end;
+    }
+    result.push_str(&text[last..]);
+    result
+}
+
+fn split_words(text: &str) -> Vec<Word> {
+    let mut words = Vec::new();
+    let mut start = None;
+    for (i, c) in text.char_indices() {
+        match (c.is_alphabetic(), start) {
+            (true, None) => start = Some(i),
+            (false, Some(s)) => {
+                words.push(Word { start: s, end: i, lower: text[s..i].to_lowercase() });
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(s) = start {
+        words.push(Word { start: s, end: text.len(), lower: text[s..].to_lowercase() });
+    }
+    words
+}
+
+/// Number spelled out at the start of `words`: its value and how many words it takes.
+/// Parts must come in decreasing order ("cento e vinte e cinco"), so two numbers
+/// said in a row ("cinco seis") stay apart.
+fn parse_number(words: &[&str], rules: &dyn NumberWords) -> Option<(u64, usize)> {
+    let mut total = 0;
+    let mut group = 0; // Below the last scale word
+    let mut below = 1000; // Parts of `group` so far only allow smaller ones next
+    let mut last_scale = u64::MAX;
+    let mut len = 0;
+
+    let mut i = 0;
+    while i < words.len() {
+        // "e" only belongs to the number when a part follows it
+        let part = if len > 0 && rules.is_joiner(words[i]) { i + 1 } else { i };
+        let Some(word) = words.get(part) else { break };
+
+        if let Some(value) = rules.small(word) {
+            let place = match value {
+                100.. => 100,
+                10.. => 10,
+                _ => 1,
+            };
+            if place >= below || (value == 0 && len > 0) {
+                break;
+            }
+            group += value;
+            below = match value {
+                100.. => 100,
+                20.. => 10,
+                _ => 1,
+            };
+        } else if let Some(scale) = rules.scale(word) {
+            // A bare "mil" is a thousand; "milhões" needs a count ("dois milhões")
+            if part != i || scale >= last_scale || (group == 0 && (len > 0 || scale != 1000)) {
+                break;
+            }
+            total += group.max(1) * scale;
+            group = 0;
+            below = 1000;
+            last_scale = scale;
+        } else {
+            break;
+        }
+        i = part + 1;
+        len = i;
+    }
+
+    (len > 0).then_some((total + group, len))
+}
+
+struct ParsedDate {
+    day: String,
+    day_len: usize,
+    year: Option<(usize, usize, u64)>, // Offset and length of the spelled-out year, and its value
+    len: usize,
+}
+
+/// "vinte e cinco de março [de dois mil e vinte e cinco]" at the start of `words`
+fn parse_date(words: &[&str], rules: &dyn NumberWords) -> Option<ParsedDate> {
+    let (day, day_len) = match rules.ordinal_day(words.first()?) {
+        Some(ordinal) => (ordinal.to_string(), 1),
+        None => {
+            let (value, len) = parse_number(words, rules)?;
+            if !(1..=31).contains(&value) {
+                return None;
+            }
+            (value.to_string(), len)
+        }
+    };
+    if !rules.is_date_joiner(words.get(day_len)?) || !rules.is_month(words.get(day_len + 1)?) {
+        return None;
+    }
+    let mut len = day_len + 2;
+
+    let year = match words.get(len) {
+        Some(joiner) if rules.is_date_joiner(joiner) => parse_number(&words[len + 1..], rules)
+            .filter(|(value, _)| (1000..3000).contains(value))
+            .map(|(value, year_len)| (len + 1, year_len, value)),
+        _ => None,
+    };
+    if let Some((offset, year_len, _)) = year {
+        len = offset + year_len;
+    }
+
+    Some(ParsedDate { day, day_len, year, len })
+}
+
+/// Digits with thousands separators from 10 000 up (years and "1500" stay unseparated)
+fn format_number(value: u64, separator: char) -> String {
+    let digits = value.to_string();
+    if value < 10_000 {
+        return digits;
+    }
+    let mut result = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            result.push(separator);
+        }
+        result.push(c);
+    }
+    result
+}
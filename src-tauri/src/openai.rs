@@ -1,5 +1,9 @@
 use serde::{Deserialize, Serialize};
 use serde_json::json;
+use crate::proxy::{self, ProxySettings};
+use crate::rate_limit::{RateLimitEvent, RateLimiter};
+use std::sync::Mutex;
+use std::time::Duration;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TranscriptionResponse {
@@ -10,7 +14,54 @@ pub struct TranscriptionResponse {
 pub struct VerboseTranscriptionResponse {
     pub text: String,
     #[serde(default)]
+    pub language: Option<String>, // Detected language as an English name, e.g. "portuguese"
+    #[serde(default)]
     pub words: Vec<WordSegment>,
+    #[serde(default)]
+    pub segments: Vec<DecodedSegment>,
+}
+
+/// Whisper's decoding statistics for a stretch of audio
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DecodedSegment {
+    pub start: f64,
+    pub end: f64,
+    pub avg_logprob: f64,
+    pub compression_ratio: f64,
+    pub no_speech_prob: f64,
+}
+
+/// Whisper's own fallback rule: a segment this repetitive (compressible) or this improbable
+/// is unreliable and worth decoding again at a higher temperature
+const MAX_COMPRESSION_RATIO: f64 = 2.4;
+const MIN_AVG_LOGPROB: f64 = -1.0;
+
+impl DecodedSegment {
+    fn unreliable(&self) -> bool {
+        self.compression_ratio > MAX_COMPRESSION_RATIO || self.avg_logprob < MIN_AVG_LOGPROB
+    }
+
+    /// Silence or noise Whisper made words up for
+    fn no_speech(&self, threshold: f64) -> bool {
+        self.no_speech_prob > threshold && self.avg_logprob < MIN_AVG_LOGPROB
+    }
+}
+
+impl VerboseTranscriptionResponse {
+    /// Mean log probability over the segments (higher = more confident)
+    fn avg_logprob(&self) -> f64 {
+        if self.segments.is_empty() {
+            return 0.0;
+        }
+        self.segments.iter().map(|s| s.avg_logprob).sum::<f64>() / self.segments.len() as f64
+    }
+
+    fn unreliable(&self, no_speech_threshold: Option<f64>) -> bool {
+        self.segments
+            .iter()
+            .filter(|s| !no_speech_threshold.is_some_and(|t| s.no_speech(t)))
+            .any(DecodedSegment::unreliable)
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -22,32 +73,324 @@ pub struct WordSegment {
     pub probability: Option<f64>,
 }
 
+/// A word of the transcript and Whisper's confidence in it (0 to 1)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WordConfidence {
+    pub word: String,
+    pub probability: Option<f64>,
+}
+
+/// Transcribed text and the language it was spoken in (ISO 639-1 code when known)
+#[derive(Debug, Clone)]
+pub struct Transcript {
+    pub text: String,
+    pub language: Option<String>,
+    pub words: Vec<WordConfidence>, // Words of `text` with their confidence; empty when the model doesn't report it
+}
+
+/// ISO 639-1 code for a language name returned by Whisper's verbose_json (unknown names pass through)
+pub fn language_code(name: &str) -> String {
+    let name = name.trim().to_lowercase();
+    let code = match name.as_str() {
+        "portuguese" => "pt",
+        "english" => "en",
+        "spanish" => "es",
+        "french" => "fr",
+        "german" => "de",
+        "italian" => "it",
+        "dutch" => "nl",
+        "japanese" => "ja",
+        "chinese" => "zh",
+        "korean" => "ko",
+        "russian" => "ru",
+        "arabic" => "ar",
+        _ => return name,
+    };
+    code.to_string()
+}
+
+pub const DEFAULT_LANGUAGE: &str = "pt";
+pub const TTS_MODEL: &str = "tts-1";
+pub const TTS_VOICE: &str = "nova";
+/// Voices offered by the speech endpoint
+pub const TTS_VOICES: &[&str] = &["alloy", "ash", "coral", "echo", "fable", "nova", "onyx", "sage", "shimmer"];
+/// Range of the speech endpoint's `speed` parameter (1.0 = normal)
+pub const TTS_SPEED_RANGE: (f32, f32) = (0.25, 4.0);
+pub const DEFAULT_TRANSCRIPTION_MODEL: &str = "whisper-1";
+/// Batch transcription models. The gpt-4o ones only return plain json (no word-level probabilities).
+pub const TRANSCRIPTION_MODELS: &[&str] = &["whisper-1", "gpt-4o-transcribe", "gpt-4o-mini-transcribe"];
+pub const DEFAULT_SYSTEM_PROMPT: &str = "You are a helpful assistant. When the user asks you to write, rewrite, translate, or improve a message, email, or text, respond with ONLY the final text, no introduction, no explanation. If the request is a question or needs an explanation, answer normally. Never use markdown formatting in your responses. Never use em dashes in your responses.";
+
+/// Runtime-configurable request options (swapped together when switching profiles)
+struct ClientConfig {
+    language: String,      // Whisper language hint, empty = auto-detect
+    system_prompt: String,
+    transcription_model: String,
+}
+
+/// Advanced Whisper decoding options (whisper-1 only: the gpt-4o transcription models don't
+/// return the statistics they rely on)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct WhisperDecoding {
+    pub temperature: f32, // 0 = always pick the most likely words
+    pub temperature_sweep: bool, // Decode again warmer while the result looks unreliable
+    pub no_speech_threshold: Option<f32>, // Drop segments at least this likely to be silence; None keeps them
+    pub confidence_threshold: f32, // Words below this probability are low-confidence
+    pub keep_low_confidence: bool, // Keep them in the text (reported, to be underlined) instead of dropping them
+}
+
+impl Default for WhisperDecoding {
+    fn default() -> Self {
+        Self {
+            temperature: 0.0,
+            temperature_sweep: false,
+            no_speech_threshold: None,
+            confidence_threshold: 0.7,
+            keep_low_confidence: false,
+        }
+    }
+}
+
+impl WhisperDecoding {
+    const SWEEP_STEP: f32 = 0.2;
+    const SWEEP_ATTEMPTS: usize = 3; // Each one is another upload
+
+    /// Temperatures to try in order
+    fn temperatures(&self) -> Vec<f32> {
+        let attempts = if self.temperature_sweep { Self::SWEEP_ATTEMPTS } else { 1 };
+        (0..attempts)
+            .map(|i| self.temperature + Self::SWEEP_STEP * i as f32)
+            .filter(|t| *t <= 1.0)
+            .collect()
+    }
+}
+
+/// Per-request time limits in seconds; None waits indefinitely.
+/// A request that times out fails like any network error (and goes to the offline queue).
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct RequestTimeouts {
+    pub transcription_secs: Option<u64>, // Whisper / gpt-4o-transcribe uploads
+    pub response_secs: Option<u64>,      // Prompt and post-processing calls
+    pub tts_secs: Option<u64>,
+}
+
+impl Default for RequestTimeouts {
+    fn default() -> Self {
+        Self {
+            transcription_secs: Some(60),
+            response_secs: Some(120), // Web search can take a while
+            tts_secs: Some(30),
+        }
+    }
+}
+
 pub struct OpenAIClient {
     api_key: String,
-    client: reqwest::Client,
+    client: Mutex<reqwest::Client>, // Rebuilt when the proxy changes
+    config: Mutex<ClientConfig>,
+    timeouts: Mutex<RequestTimeouts>,
+    decoding: Mutex<WhisperDecoding>,
+    rate_limiter: RateLimiter,
+}
+
+fn with_timeout(request: reqwest::RequestBuilder, secs: Option<u64>) -> reqwest::RequestBuilder {
+    match secs {
+        Some(secs) => request.timeout(Duration::from_secs(secs)),
+        None => request,
+    }
+}
+
+/// Error message for a failed send, spelling out timeouts
+fn send_error(context: &str, e: reqwest::Error, secs: Option<u64>) -> String {
+    if e.is_timeout() {
+        format!("{}: timed out after {}s", context, secs.unwrap_or_default())
+    } else {
+        format!("{}: {}", context, e)
+    }
 }
 
 impl OpenAIClient {
     pub fn new(api_key: String) -> Self {
         Self {
             api_key,
-            client: reqwest::Client::new(),
+            client: Mutex::new(reqwest::Client::new()),
+            timeouts: Mutex::new(RequestTimeouts::default()),
+            decoding: Mutex::new(WhisperDecoding::default()),
+            rate_limiter: RateLimiter::new(),
+            config: Mutex::new(ClientConfig {
+                language: DEFAULT_LANGUAGE.to_string(),
+                system_prompt: DEFAULT_SYSTEM_PROMPT.to_string(),
+                transcription_model: DEFAULT_TRANSCRIPTION_MODEL.to_string(),
+            }),
         }
     }
 
-    /// Transcribe audio using Whisper API with confidence filtering
-    pub async fn transcribe_audio(&self, audio_data: Vec<f32>, sample_rate: u32) -> Result<String, String> {
+    /// Replace language, system prompt and transcription model in one step. None restores the default.
+    pub fn apply_config(&self, language: Option<String>, system_prompt: Option<String>, transcription_model: Option<String>) {
+        let mut config = self.config.lock().unwrap();
+        config.language = language.unwrap_or_else(|| DEFAULT_LANGUAGE.to_string());
+        config.system_prompt = system_prompt
+            .filter(|p| !p.trim().is_empty())
+            .unwrap_or_else(|| DEFAULT_SYSTEM_PROMPT.to_string());
+        config.transcription_model = transcription_model
+            .filter(|m| TRANSCRIPTION_MODELS.contains(&m.as_str()))
+            .unwrap_or_else(|| DEFAULT_TRANSCRIPTION_MODEL.to_string());
+        println!(
+            "⚙️ OpenAI client config: language='{}', system prompt {} chars, transcription model {}",
+            config.language, config.system_prompt.len(), config.transcription_model
+        );
+    }
+
+    /// Route requests through `proxy` (None = direct). Requests already in flight keep their connection.
+    pub fn set_proxy(&self, proxy: Option<&ProxySettings>) {
+        *self.client.lock().unwrap() = proxy::http_client(proxy);
+    }
+
+    pub fn set_timeouts(&self, timeouts: RequestTimeouts) {
+        *self.timeouts.lock().unwrap() = timeouts;
+    }
+
+    pub fn set_whisper_decoding(&self, decoding: WhisperDecoding) {
+        *self.decoding.lock().unwrap() = decoding;
+    }
+
+    /// Called when requests start being held back because of OpenAI rate limits
+    pub fn set_rate_limit_listener(&self, listener: impl Fn(RateLimitEvent) + Send + 'static) {
+        self.rate_limiter.set_listener(listener);
+    }
+
+    fn http(&self) -> reqwest::Client {
+        self.client.lock().unwrap().clone()
+    }
+
+    /// Currently selected batch transcription model
+    pub fn transcription_model(&self) -> String {
+        self.config.lock().unwrap().transcription_model.clone()
+    }
+
+    /// Language hint sent with transcriptions (None = auto-detect)
+    pub fn language(&self) -> Option<String> {
+        Some(self.config.lock().unwrap().language.clone()).filter(|l| !l.is_empty())
+    }
+
+    /// Instructions sent with every prompt
+    pub fn system_prompt(&self) -> String {
+        self.config.lock().unwrap().system_prompt.clone()
+    }
+
+    /// Transcribe audio with the selected model (Whisper gets confidence filtering).
+    /// `prompt` is preceding text used to prime spelling/casing and sentence continuation.
+    pub async fn transcribe_audio(&self, audio_data: Vec<f32>, sample_rate: u32, prompt: Option<&str>) -> Result<String, String> {
+        self.transcribe_detailed(audio_data, sample_rate, prompt).await.map(|t| t.text)
+    }
+
+    /// `transcribe_audio` plus the spoken language: detected by Whisper, otherwise the language hint
+    pub async fn transcribe_detailed(&self, audio_data: Vec<f32>, sample_rate: u32, prompt: Option<&str>) -> Result<Transcript, String> {
+        let model = self.config.lock().unwrap().transcription_model.clone();
+        self.transcribe_with_model(audio_data, sample_rate, prompt, &model).await
+    }
+
+    /// `transcribe_detailed` with a given model instead of the configured one
+    pub async fn transcribe_with_model(&self, audio_data: Vec<f32>, sample_rate: u32, prompt: Option<&str>, model: &str) -> Result<Transcript, String> {
         println!("🔄 Transcribing audio... ({} samples at {}Hz)", audio_data.len(), sample_rate);
 
         // Convert f32 audio to WAV format
         let wav_data = self.audio_to_wav(audio_data, sample_rate)?;
 
-        // Call Whisper API with Portuguese language hint and verbose_json for word-level confidence
-        let form = reqwest::multipart::Form::new()
-            .text("model", "whisper-1")
-            .text("language", "pt")
-            .text("response_format", "verbose_json")
-            .text("timestamp_granularities[]", "word")
+        let language = self.config.lock().unwrap().language.clone();
+        let prompt = prompt.filter(|p| !p.trim().is_empty());
+        if let Some(prompt) = prompt {
+            println!("🧩 Priming Whisper with {} chars of context", prompt.chars().count());
+        }
+
+        if model != "whisper-1" {
+            // No word-level probabilities from gpt-4o-*-transcribe, so no confidence filtering
+            let response = self.send_transcription(wav_data, model, &language, prompt, None).await?;
+            let result: TranscriptionResponse = response
+                .json()
+                .await
+                .map_err(|e| format!("Failed to parse response: {}", e))?;
+            let text = result.text.trim().to_string();
+            println!("✅ Transcription ({}): {}", model, text);
+            let language = Some(language).filter(|l| !l.is_empty());
+            return Ok(Transcript { text, language, words: Vec::new() });
+        }
+
+        let decoding = *self.decoding.lock().unwrap();
+        let no_speech_threshold = decoding.no_speech_threshold.map(f64::from);
+        let mut best: Option<VerboseTranscriptionResponse> = None;
+        for temperature in decoding.temperatures() {
+            let response = self.send_transcription(wav_data.clone(), model, &language, prompt, Some(temperature)).await;
+            let result: Result<VerboseTranscriptionResponse, String> = match response {
+                Ok(response) => response.json().await.map_err(|e| format!("Failed to parse response: {}", e)),
+                Err(e) => Err(e),
+            };
+            let result = match (result, &best) {
+                (Ok(result), _) => result,
+                // A failed retry doesn't lose the result already in hand
+                (Err(e), Some(_)) => {
+                    eprintln!("⚠️ Retry at temperature {:.1} failed: {}", temperature, e);
+                    break;
+                }
+                (Err(e), None) => return Err(e),
+            };
+            let unreliable = result.unreliable(no_speech_threshold);
+            if best.as_ref().map_or(true, |b| result.avg_logprob() > b.avg_logprob()) {
+                best = Some(result);
+            }
+            if !unreliable {
+                break;
+            }
+            println!("🌡️ Unreliable decoding at temperature {:.1}", temperature);
+        }
+        let result = best.ok_or("No temperature to decode with")?;
+
+        // Words inside segments Whisper rates as silence were made up from noise
+        let silent: Vec<(f64, f64)> = match no_speech_threshold {
+            Some(threshold) => result.segments.iter().filter(|s| s.no_speech(threshold)).map(|s| (s.start, s.end)).collect(),
+            None => Vec::new(),
+        };
+        if !silent.is_empty() {
+            println!("🔇 Dropping {} segment(s) that look like silence", silent.len());
+        }
+
+        // Words below the confidence threshold are dropped, or kept and reported with their probability
+        let confidence_threshold = f64::from(decoding.confidence_threshold);
+        let words: Vec<WordConfidence> = result.words
+            .iter()
+            .filter(|w| !silent.iter().any(|(start, end)| w.start >= *start && w.end <= *end))
+            .filter(|w| match w.probability {
+                Some(prob) if prob < confidence_threshold => {
+                    println!("⚠️ Low confidence ({:.2}%): '{}'", prob * 100.0, w.word);
+                    decoding.keep_low_confidence
+                }
+                _ => true, // Keep if no probability (fallback)
+            })
+            .map(|w| WordConfidence { word: w.word.clone(), probability: w.probability })
+            .collect();
+
+        let filtered_text = words.iter().map(|w| w.word.as_str()).collect::<Vec<_>>().join(" ");
+
+        println!("📊 Original: {} words", result.words.len());
+        println!("📊 Kept: {} words (threshold: {:.0}%)", words.len(), confidence_threshold * 100.0);
+        println!("✅ Transcription: {}", filtered_text);
+
+        let language = result.language.as_deref().map(language_code).or(Some(language).filter(|l| !l.is_empty()));
+        Ok(Transcript { text: filtered_text, language, words })
+    }
+
+    /// Upload one transcription request. Whisper (`temperature` set) answers in verbose_json
+    /// with word timings and segment statistics.
+    async fn send_transcription(
+        &self,
+        wav_data: Vec<u8>,
+        model: &str,
+        language: &str,
+        prompt: Option<&str>,
+        temperature: Option<f32>,
+    ) -> Result<reqwest::Response, String> {
+        let mut form = reqwest::multipart::Form::new()
+            .text("model", model.to_string())
             .part(
                 "file",
                 reqwest::multipart::Part::bytes(wav_data)
@@ -55,52 +398,39 @@ impl OpenAIClient {
                     .mime_str("audio/wav")
                     .map_err(|e| format!("Failed to create multipart: {}", e))?,
             );
+        match temperature {
+            Some(temperature) => {
+                // verbose_json for word-level confidence and segment statistics
+                form = form
+                    .text("response_format", "verbose_json")
+                    .text("timestamp_granularities[]", "word")
+                    .text("timestamp_granularities[]", "segment")
+                    .text("temperature", temperature.to_string());
+            }
+            None => form = form.text("response_format", "json"),
+        }
+        if !language.is_empty() {
+            form = form.text("language", language.to_string());
+        }
+        if let Some(prompt) = prompt {
+            form = form.text("prompt", prompt.to_string());
+        }
 
-        let response = self
-            .client
-            .post("https://api.openai.com/v1/audio/transcriptions")
+        let timeout = self.timeouts.lock().unwrap().transcription_secs;
+        self.rate_limiter.wait("transcriptions").await;
+        let response = with_timeout(self.http().post("https://api.openai.com/v1/audio/transcriptions"), timeout)
             .header("Authorization", format!("Bearer {}", self.api_key))
             .multipart(form)
             .send()
             .await
-            .map_err(|e| format!("Failed to send request: {}", e))?;
+            .map_err(|e| send_error("Failed to send request", e, timeout))?;
+        self.rate_limiter.observe("transcriptions", &response);
 
         if !response.status().is_success() {
             let error_text = response.text().await.unwrap_or_default();
             return Err(format!("API error: {}", error_text));
         }
-
-        let result: VerboseTranscriptionResponse = response
-            .json()
-            .await
-            .map_err(|e| format!("Failed to parse response: {}", e))?;
-
-        // Filter words by confidence threshold (0.7 = 70%)
-        let confidence_threshold = 0.7;
-        let filtered_words: Vec<String> = result.words
-            .iter()
-            .filter(|w| {
-                if let Some(prob) = w.probability {
-                    if prob < confidence_threshold {
-                        println!("⚠️ Low confidence ({:.2}%): '{}'", prob * 100.0, w.word);
-                        false
-                    } else {
-                        true
-                    }
-                } else {
-                    true // Keep if no probability (fallback)
-                }
-            })
-            .map(|w| w.word.clone())
-            .collect();
-
-        let filtered_text = filtered_words.join(" ");
-
-        println!("📊 Original: {} words", result.words.len());
-        println!("📊 Filtered: {} words (threshold: {:.0}%)", filtered_words.len(), confidence_threshold * 100.0);
-        println!("✅ Transcription: {}", filtered_text);
-
-        Ok(filtered_text)
+        Ok(response)
     }
 
     /// Post-process text with GPT-4o-mini
@@ -126,15 +456,16 @@ impl OpenAIClient {
             "temperature": 0.3
         });
 
-        let response = self
-            .client
-            .post("https://api.openai.com/v1/chat/completions")
+        let timeout = self.timeouts.lock().unwrap().response_secs;
+        self.rate_limiter.wait("chat").await;
+        let response = with_timeout(self.http().post("https://api.openai.com/v1/chat/completions"), timeout)
             .header("Authorization", format!("Bearer {}", self.api_key))
             .header("Content-Type", "application/json")
             .json(&body)
             .send()
             .await
-            .map_err(|e| format!("Failed to send request: {}", e))?;
+            .map_err(|e| send_error("Failed to send request", e, timeout))?;
+        self.rate_limiter.observe("chat", &response);
 
         if !response.status().is_success() {
             let error_text = response.text().await.unwrap_or_default();
@@ -162,15 +493,52 @@ impl OpenAIClient {
         println!("🤖 Sending prompt to {} (history: {} messages, image: {})...", model, history.len(), image_data.is_some());
         println!("📝 Prompt: {}", prompt);
 
-        // Map model names to their correct identifiers
-        let api_model = match model {
-            "gpt-4o-mini" => "gpt-4o-mini",
-            "gpt-4o" => "gpt-4.1",
-            "gpt-4.1" => "gpt-4.1",
-            _ => model
-        };
+        let body = self.prompt_body(prompt, model, history, image_data);
+        let timeout = self.timeouts.lock().unwrap().response_secs;
+        self.rate_limiter.wait("responses").await;
+        let response = with_timeout(self.http().post("https://api.openai.com/v1/responses"), timeout)
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .header("Content-Type", "application/json")
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| send_error("Failed to send request", e, timeout))?;
+        self.rate_limiter.observe("responses", &response);
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(format!("API error ({}): {}", status, error_text));
+        }
+
+        let result: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse response: {}", e))?;
 
-        let system_prompt = "You are a helpful assistant. When the user asks you to write, rewrite, translate, or improve a message, email, or text, respond with ONLY the final text, no introduction, no explanation. If the request is a question or needs an explanation, answer normally. Never use markdown formatting in your responses. Never use em dashes in your responses.";
+        // Log if web search was used
+        if let Some(outputs) = result["output"].as_array() {
+            for output in outputs {
+                if output["type"] == "web_search_call" {
+                    println!("🌐 Web search was used for this query");
+                    if let Some(action) = output.get("action") {
+                        println!("🔍 Search action: {:?}", action);
+                    }
+                }
+            }
+        }
+
+        let response_text = response_output_text(&result)
+            .ok_or("No response text found in API response")?;
+        println!("✅ Response from {} (web search): {}", model, response_text);
+        Ok(response_text)
+    }
+
+    /// Responses API request for a prompt: history + user message (with image) and web search
+    fn prompt_body(&self, prompt: &str, model: &str, history: &[crate::db::ConversationMessage], image_data: Option<&str>) -> serde_json::Value {
+        let api_model = api_model(model);
+
+        let system_prompt = self.config.lock().unwrap().system_prompt.clone();
 
         // Build input array: history messages + current prompt
         let mut input: Vec<serde_json::Value> = history.iter().map(|msg| {
@@ -204,7 +572,7 @@ impl OpenAIClient {
             }));
         }
 
-        let body = json!({
+        json!({
             "model": api_model,
             "tools": [
                 {"type": "web_search"}
@@ -212,17 +580,34 @@ impl OpenAIClient {
             "tool_choice": "auto",
             "instructions": system_prompt,
             "input": input
-        });
+        })
+    }
 
-        let response = self
-            .client
-            .post("https://api.openai.com/v1/responses")
+    /// `send_prompt` with the answer streamed: `on_delta` gets each piece of text as it's
+    /// generated, and the complete response is returned at the end
+    pub async fn send_prompt_streaming(
+        &self,
+        prompt: &str,
+        model: &str,
+        history: &[crate::db::ConversationMessage],
+        image_data: Option<&str>,
+        on_delta: &mut (dyn FnMut(&str) + Send),
+    ) -> Result<String, String> {
+        println!("🤖 Streaming prompt to {} (history: {} messages, image: {})...", model, history.len(), image_data.is_some());
+        println!("📝 Prompt: {}", prompt);
+
+        let mut body = self.prompt_body(prompt, model, history, image_data);
+        body["stream"] = json!(true);
+        let timeout = self.timeouts.lock().unwrap().response_secs;
+        self.rate_limiter.wait("responses").await;
+        let mut response = with_timeout(self.http().post("https://api.openai.com/v1/responses"), timeout)
             .header("Authorization", format!("Bearer {}", self.api_key))
             .header("Content-Type", "application/json")
             .json(&body)
             .send()
             .await
-            .map_err(|e| format!("Failed to send request: {}", e))?;
+            .map_err(|e| send_error("Failed to send request", e, timeout))?;
+        self.rate_limiter.observe("responses", &response);
 
         if !response.status().is_success() {
             let status = response.status();
@@ -230,77 +615,117 @@ impl OpenAIClient {
             return Err(format!("API error ({}): {}", status, error_text));
         }
 
-        let result: serde_json::Value = response
-            .json()
-            .await
-            .map_err(|e| format!("Failed to parse response: {}", e))?;
-
-        // Log if web search was used
-        if let Some(outputs) = result["output"].as_array() {
-            for output in outputs {
-                if output["type"] == "web_search_call" {
-                    println!("🌐 Web search was used for this query");
-                    if let Some(action) = output.get("action") {
-                        println!("🔍 Search action: {:?}", action);
+        // Server-sent events, one JSON object per `data:` line. Bytes are buffered up to the
+        // newline so a UTF-8 character split between network chunks is decoded whole.
+        let mut pending: Vec<u8> = Vec::new();
+        let mut streamed = String::new();
+        let mut completed: Option<serde_json::Value> = None;
+        while let Some(bytes) = response.chunk().await.map_err(|e| send_error("Failed to read response stream", e, timeout))? {
+            pending.extend_from_slice(&bytes);
+            while let Some(newline) = pending.iter().position(|&b| b == b'\n') {
+                let line: Vec<u8> = pending.drain(..=newline).collect();
+                let line = String::from_utf8_lossy(&line);
+                let Some(data) = line.trim().strip_prefix("data:") else { continue };
+                let Ok(event) = serde_json::from_str::<serde_json::Value>(data.trim()) else { continue };
+                match event["type"].as_str().unwrap_or("") {
+                    "response.output_text.delta" => {
+                        let delta = event["delta"].as_str().unwrap_or("");
+                        streamed.push_str(delta);
+                        on_delta(delta);
                     }
+                    "response.web_search_call.in_progress" => println!("🌐 Web search was used for this query"),
+                    "response.completed" => completed = Some(event["response"].clone()),
+                    "response.failed" | "error" => {
+                        let message = event["response"]["error"]["message"].as_str()
+                            .or(event["message"].as_str())
+                            .unwrap_or("unknown error");
+                        return Err(format!("API error: {}", message));
+                    }
+                    _ => {}
                 }
             }
         }
 
-        // Extract output_text from Responses API response
-        let response_text = result["output_text"]
-            .as_str()
-            .unwrap_or("")
-            .trim()
-            .to_string();
+        let response_text = completed.as_ref()
+            .and_then(response_output_text)
+            .or_else(|| Some(streamed.trim().to_string()).filter(|text| !text.is_empty()))
+            .ok_or("No response text found in API response")?;
+        println!("✅ Streamed response from {}: {}", model, response_text);
+        Ok(response_text)
+    }
 
-        if response_text.is_empty() {
-            // Fallback: try to extract from output array
-            if let Some(outputs) = result["output"].as_array() {
-                for output in outputs {
-                    if output["type"] == "message" {
-                        if let Some(content) = output["content"].as_array() {
-                            for item in content {
-                                if item["type"] == "output_text" {
-                                    let text = item["text"].as_str().unwrap_or("").trim();
-                                    if !text.is_empty() {
-                                        println!("✅ Response from {} (web search): {}", model, text);
-                                        return Ok(text.to_string());
-                                    }
-                                }
-                            }
-                        }
-                    }
+    /// Send prompt and get back JSON matching `schema` (Responses API structured outputs, strict).
+    /// No web search or history: the output is meant for automation, not conversation.
+    pub async fn send_structured_prompt(&self, prompt: &str, model: &str, schema: &serde_json::Value) -> Result<serde_json::Value, String> {
+        println!("🧩 Sending structured prompt to {}...", model);
+        println!("📝 Prompt: {}", prompt);
+
+        let body = json!({
+            "model": api_model(model),
+            "instructions": "Turn the user's dictated request into a JSON object that follows the schema. Use the language the user spoke for free-text values.",
+            "input": prompt,
+            "text": {
+                "format": {
+                    "type": "json_schema",
+                    "name": "dicta_output",
+                    "schema": schema,
+                    "strict": true
                 }
             }
-            return Err("No response text found in API response".to_string());
+        });
+
+        let timeout = self.timeouts.lock().unwrap().response_secs;
+        self.rate_limiter.wait("responses").await;
+        let response = with_timeout(self.http().post("https://api.openai.com/v1/responses"), timeout)
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .header("Content-Type", "application/json")
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| send_error("Failed to send request", e, timeout))?;
+        self.rate_limiter.observe("responses", &response);
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(format!("API error ({}): {}", status, error_text));
         }
 
-        println!("✅ Response from {} (web search): {}", model, response_text);
-        Ok(response_text)
+        let result: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse response: {}", e))?;
+        let response_text = response_output_text(&result)
+            .ok_or("No response text found in API response")?;
+        let output: serde_json::Value = serde_json::from_str(&response_text)
+            .map_err(|e| format!("Model returned invalid JSON: {}", e))?;
+        println!("✅ Structured response from {}: {}", model, output);
+        Ok(output)
     }
 
     /// Generate speech audio from text using OpenAI TTS API
-    pub async fn speak_text(&self, text: &str) -> Result<Vec<u8>, String> {
+    pub async fn speak_text(&self, text: &str, voice: &str, speed: f32) -> Result<Vec<u8>, String> {
         let preview: String = text.chars().take(80).collect();
         println!("🔊 Generating TTS for: {}...", preview);
 
         let body = json!({
-            "model": "tts-1",
+            "model": TTS_MODEL,
             "input": text,
-            "voice": "nova",
+            "voice": voice,
+            "speed": speed.clamp(TTS_SPEED_RANGE.0, TTS_SPEED_RANGE.1),
             "response_format": "mp3"
         });
 
-        let response = self
-            .client
-            .post("https://api.openai.com/v1/audio/speech")
+        let timeout = self.timeouts.lock().unwrap().tts_secs;
+        self.rate_limiter.wait("speech").await;
+        let response = with_timeout(self.http().post("https://api.openai.com/v1/audio/speech"), timeout)
             .header("Authorization", format!("Bearer {}", self.api_key))
             .header("Content-Type", "application/json")
             .json(&body)
             .send()
             .await
-            .map_err(|e| format!("TTS request failed: {}", e))?;
+            .map_err(|e| send_error("TTS request failed", e, timeout))?;
+        self.rate_limiter.observe("speech", &response);
 
         if !response.status().is_success() {
             let status = response.status();
@@ -442,3 +867,59 @@ pub fn split_into_tts_chunks(text: &str) -> Vec<String> {
 
     chunks
 }
+
+/// Collects a streamed response and hands out the text up to its last finished sentence,
+/// so speech can start before the whole answer has arrived
+#[derive(Debug, Default)]
+pub struct SentenceBuffer {
+    pending: String,
+}
+
+impl SentenceBuffer {
+    /// Add streamed text; returns the complete sentences it finishes, if any
+    pub fn push(&mut self, delta: &str) -> Option<String> {
+        self.pending.push_str(delta);
+        // Same boundary as split_into_tts_chunks, but the following space must have arrived
+        let end = self.pending
+            .char_indices()
+            .zip(self.pending.chars().skip(1))
+            .filter(|((_, c), next)| matches!(c, '.' | '!' | '?') && matches!(next, ' ' | '\n'))
+            .map(|((i, c), _)| i + c.len_utf8())
+            .last()?;
+        let sentences: String = self.pending.drain(..end).collect();
+        Some(sentences.trim().to_string()).filter(|s| !s.is_empty())
+    }
+
+    /// What's left once the stream ended (the last sentence often has no trailing space)
+    pub fn finish(self) -> Option<String> {
+        Some(self.pending.trim().to_string()).filter(|s| !s.is_empty())
+    }
+}
+
+/// Map model names to their correct identifiers
+fn api_model(model: &str) -> &str {
+    match model {
+        "gpt-4o-mini" => "gpt-4o-mini",
+        "gpt-4o" => "gpt-4.1",
+        "gpt-4.1" => "gpt-4.1",
+        _ => model
+    }
+}
+
+/// Text of a Responses API reply: `output_text`, or the first output_text item of a message
+fn response_output_text(result: &serde_json::Value) -> Option<String> {
+    let text = result["output_text"].as_str().unwrap_or("").trim();
+    if !text.is_empty() {
+        return Some(text.to_string());
+    }
+    result["output"]
+        .as_array()?
+        .iter()
+        .filter(|output| output["type"] == "message")
+        .filter_map(|output| output["content"].as_array())
+        .flatten()
+        .filter(|item| item["type"] == "output_text")
+        .map(|item| item["text"].as_str().unwrap_or("").trim())
+        .find(|text| !text.is_empty())
+        .map(str::to_string)
+}
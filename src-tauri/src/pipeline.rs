@@ -0,0 +1,1103 @@
+use crate::db::{ConversationMessage, SessionDiagnostics};
+use crate::realtime::TranscriptSegment;
+use crate::email::EmailSettings;
+use crate::events::{emit_app_event, AppEvent};
+use crate::json_output::{self, JsonOutputSettings};
+use crate::numbers::{self, NumberStyle};
+use crate::openai::{OpenAIClient, SentenceBuffer, Transcript, WordConfidence};
+use crate::plain_text;
+use crate::plugins::Hook;
+use crate::profanity;
+use crate::realtime::{self, RealtimeSession};
+use crate::session::SessionId;
+use crate::voice_commands::{self, VoiceCommandSettings};
+use crate::{AppState, NotificationKind};
+use std::collections::BTreeSet;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::Notify;
+use tauri::{AppHandle, Manager};
+
+/// The AI calls a session makes. Implemented by the OpenAI client; the pipeline only
+/// goes through this trait so it can be driven by a fake client.
+pub trait AiClient {
+    async fn transcribe(&self, audio: Vec<f32>, sample_rate: u32, prompt: Option<&str>) -> Result<Transcript, String>;
+
+    async fn prompt(&self, prompt: &str, model: &str, history: &[ConversationMessage], image: Option<&str>) -> Result<String, String>;
+
+    /// `prompt` with `on_delta` called for each piece of the response as it's generated
+    async fn prompt_streaming(
+        &self,
+        prompt: &str,
+        model: &str,
+        history: &[ConversationMessage],
+        image: Option<&str>,
+        on_delta: &mut (dyn FnMut(&str) + Send),
+    ) -> Result<String, String>;
+
+    /// Rewrite a dictation with the given formatting (e.g. "formal")
+    async fn restyle(&self, text: &str, style: &str) -> Result<String, String>;
+
+    /// JSON object matching `schema` for a dictated request (JSON output mode)
+    async fn structured(&self, prompt: &str, model: &str, schema: &serde_json::Value) -> Result<serde_json::Value, String>;
+
+    /// Model name stored with transcriptions
+    fn transcription_label(&self) -> String;
+}
+
+impl AiClient for OpenAIClient {
+    async fn transcribe(&self, audio: Vec<f32>, sample_rate: u32, prompt: Option<&str>) -> Result<Transcript, String> {
+        self.transcribe_detailed(audio, sample_rate, prompt).await
+    }
+
+    async fn prompt(&self, prompt: &str, model: &str, history: &[ConversationMessage], image: Option<&str>) -> Result<String, String> {
+        self.send_prompt(prompt, model, history, image).await
+    }
+
+    async fn prompt_streaming(
+        &self,
+        prompt: &str,
+        model: &str,
+        history: &[ConversationMessage],
+        image: Option<&str>,
+        on_delta: &mut (dyn FnMut(&str) + Send),
+    ) -> Result<String, String> {
+        self.send_prompt_streaming(prompt, model, history, image, on_delta).await
+    }
+
+    async fn restyle(&self, text: &str, style: &str) -> Result<String, String> {
+        let prompt = format!(
+            "Rewrite the dictated text below with this formatting: {}. Keep its meaning and language. Reply with the resulting text only, without quotes or comments.\n\n\"\"\"\n{}\n\"\"\"",
+            style.trim(),
+            text.trim()
+        );
+        self.send_prompt(&prompt, "gpt-4o-mini", &[], None).await
+    }
+
+    async fn structured(&self, prompt: &str, model: &str, schema: &serde_json::Value) -> Result<serde_json::Value, String> {
+        self.send_structured_prompt(prompt, model, schema).await
+    }
+
+    fn transcription_label(&self) -> String {
+        crate::transcription_model_label(self)
+    }
+}
+
+/// Where the pipeline's results go: history, clipboard/paste target, frontend, speakers, offline queue
+pub trait Output {
+    /// Store the result in history; the id of its entry (None when nothing was stored, e.g. incognito)
+    fn save(&self, session_id: Option<SessionId>, delivery: &Delivery) -> Option<i64>;
+    fn history_updated(&self, session_id: Option<SessionId>);
+    /// `rich` also puts `text` (markdown) on the clipboard as HTML; `entry` is its history entry,
+    /// tagged with the app it's pasted into
+    fn paste(&self, entry: Option<i64>, text: &str, rich: bool) -> Result<(), String>;
+    fn paste_failed(&self, session_id: Option<SessionId>, text: &str, error: &str);
+    fn response_ready(&self, session_id: Option<SessionId>);
+    /// Read a result aloud if TTS is on and its auto-play settings allow this kind/length,
+    /// in the voice bound to `model` when there is one
+    fn speak(&self, kind: DeliveryKind, model: &str, text: &str);
+    /// Start reading a prompt response of `model` while it streams: finished sentences go to
+    /// the returned channel. None when it won't be read aloud (or not until it's complete,
+    /// e.g. when a `PromptResponse` hook may still change it).
+    fn speak_streaming(&self, model: &str) -> Option<UnboundedSender<String>>;
+    /// Whether results of this kind are normalized to plain text before saving and pasting
+    fn plain_text(&self, kind: DeliveryKind) -> bool;
+    /// How spelled-out numbers in transcriptions are written before pasting
+    fn number_style(&self) -> NumberStyle;
+    /// The user's own words to mask when the profanity filter is on; None when it's off
+    fn profanity_words(&self) -> Option<Vec<String>>;
+    /// Whether prompt responses are pasted with their markdown formatting rendered as HTML
+    fn rich_text(&self) -> bool;
+    /// Handle `text` as a voice command if it starts with the trigger word; true when it was one
+    fn run_voice_command(&self, session_id: Option<SessionId>, text: &str) -> bool;
+    /// Pass `text` through the enabled plugins' `hook`
+    fn run_hook(&self, hook: Hook, text: String) -> String;
+    /// Settings of JSON output mode when this session was started in it (consumes the session's target)
+    fn json_output(&self) -> Option<JsonOutputSettings>;
+    /// Settings of email mode when this session was started in it (consumes the session's target),
+    /// with the title of the window the email goes to
+    fn email(&self) -> Option<(EmailSettings, Option<String>)>;
+    /// Hand a JSON output result to automation: file, webhook and local API
+    fn deliver_json(&self, settings: &JsonOutputSettings, json: &serde_json::Value) -> Result<(), String>;
+    fn enqueue_prompt(&self, session_id: Option<SessionId>, mode: &str, prompt: &str, model: &str);
+    fn enqueue_audio(&self, session_id: Option<SessionId>, mode: &str, audio: Vec<f32>, model: &str);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeliveryKind {
+    Transcription,
+    Prompt,
+}
+
+impl DeliveryKind {
+    /// Value of the history row's `mode` column
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DeliveryKind::Transcription => "transcription",
+            DeliveryKind::Prompt => "prompt",
+        }
+    }
+}
+
+/// How the audio was captured, stored with the history row of a recording
+#[derive(Debug, Clone)]
+pub struct Capture {
+    pub clipping_pct: f32,
+    pub diagnostics: SessionDiagnostics,
+}
+
+/// Final text of a session and how to store it
+#[derive(Debug, Clone)]
+pub struct Delivery {
+    pub text: String,
+    pub model: String,
+    pub kind: DeliveryKind,
+    pub prompt: Option<String>, // Sent prompt, saved to the conversation with the response
+    pub duration_ms: Option<i64>,
+    pub capture: Option<Capture>,
+    pub speak: bool, // May be read aloud (see Output::speak); off for queue retries finishing later
+    pub segments: Vec<TranscriptSegment>, // Turns of a live session, stored under its entry
+    pub language: Option<String>, // Spoken language (ISO 639-1 when known)
+    pub original: Option<String>, // Text before restyling, repair and post-processing; kept as a revision when it differs
+    pub words: Vec<WordConfidence>, // Whisper's per-word confidence in the transcript, saved with it while unchanged
+}
+
+#[derive(Debug, Clone)]
+pub struct PromptRequest {
+    pub prompt: String,
+    pub model: String,
+    pub history: Vec<ConversationMessage>,
+    pub image: Option<String>, // base64 PNG
+    pub duration_ms: Option<i64>,
+    pub capture: Option<Capture>,
+    pub queue_mode: &'static str, // Offline queue mode used if the request fails
+    pub speak: bool,
+    pub language: Option<String>, // Language the prompt was spoken in, stored with the response
+}
+
+/// A stopped batch recording: transcript is delivered as is, or sent to `prompt_model` first
+#[derive(Debug, Clone)]
+pub struct RecordingRequest {
+    pub prompt_model: Option<String>,
+    pub selection: Option<String>,
+    pub style: Option<String>, // Formatting for the transcript when not prompting
+    pub screen_context: Option<String>,
+    pub history: Vec<ConversationMessage>,
+    pub duration_ms: Option<i64>,
+    pub capture: Option<Capture>,
+}
+
+/// A session stuck in its API calls holds back the ones after it at most this long
+const TURN_WAIT_LIMIT: Duration = Duration::from_secs(180);
+
+/// Gives each session a turn when it's submitted (recording stopped, prompt sent), so results
+/// are pasted and read aloud in that order even when a later session's API calls finish first
+#[derive(Default)]
+pub struct DeliveryOrder {
+    turns: Mutex<Turns>,
+    advanced: Notify,
+}
+
+#[derive(Default)]
+struct Turns {
+    next: u64,
+    current: u64,
+    finished: BTreeSet<u64>, // Out of order, waiting for `current` to finish
+}
+
+impl DeliveryOrder {
+    fn take_turn(self: &Arc<Self>) -> Turn {
+        let mut turns = self.turns.lock().unwrap();
+        let number = turns.next;
+        turns.next += 1;
+        Turn { number, order: self.clone() }
+    }
+}
+
+/// A session's place in its `DeliveryOrder`. Dropping it (delivered, failed, nothing to say)
+/// lets the next session go.
+struct Turn {
+    number: u64,
+    order: Arc<DeliveryOrder>,
+}
+
+impl Turn {
+    fn is_current(&self) -> bool {
+        self.order.turns.lock().unwrap().current == self.number
+    }
+
+    async fn wait(&self) {
+        let wait = async {
+            loop {
+                let advanced = self.order.advanced.notified();
+                if self.is_current() {
+                    return;
+                }
+                advanced.await;
+            }
+        };
+        if tokio::time::timeout(TURN_WAIT_LIMIT, wait).await.is_err() {
+            eprintln!("{} ⚠️ Earlier session still running after {:?}, delivering out of order", crate::ts(), TURN_WAIT_LIMIT);
+        }
+    }
+}
+
+impl Drop for Turn {
+    fn drop(&mut self) {
+        let mut turns = self.order.turns.lock().unwrap();
+        turns.finished.insert(self.number);
+        loop {
+            let current = turns.current;
+            if !turns.finished.remove(&current) {
+                break;
+            }
+            turns.current += 1;
+        }
+        drop(turns);
+        self.order.advanced.notify_waiters();
+    }
+}
+
+/// transcribe → maybe prompt → save → paste → TTS for one session
+pub struct Pipeline<C, O> {
+    client: Arc<C>,
+    output: O,
+    session_id: Option<SessionId>,
+    turn: Option<Turn>,
+}
+
+impl<C: AiClient, O: Output> Pipeline<C, O> {
+    pub fn new(client: Arc<C>, output: O, session_id: Option<SessionId>) -> Self {
+        Self { client, output, session_id, turn: None }
+    }
+
+    /// Deliver only after the sessions that took a turn in `order` before this one.
+    /// The API calls still run right away.
+    pub fn in_order(mut self, order: &Arc<DeliveryOrder>) -> Self {
+        self.turn = Some(order.take_turn());
+        self
+    }
+
+    async fn wait_turn(&self) {
+        if let Some(turn) = &self.turn {
+            if !turn.is_current() {
+                println!("{} [{}] ⏳ Waiting for an earlier session to deliver first", crate::ts(), self.tag());
+                turn.wait().await;
+            }
+        }
+    }
+
+    fn tag(&self) -> String {
+        self.session_id.map(|id| id.short()).unwrap_or_else(|| "no session".to_string())
+    }
+
+    pub async fn transcribe(&self, audio: Vec<f32>, sample_rate: u32, prompt: Option<&str>) -> Result<Transcript, String> {
+        self.client.transcribe(audio, sample_rate, prompt).await
+    }
+
+    /// Deliver a batch recording's transcript (prompting first in prompt mode).
+    /// Audio that failed to transcribe is saved to the offline queue.
+    pub async fn finish_recording(&self, transcription: Result<Transcript, String>, audio: Vec<f32>, request: RecordingRequest) {
+        let Transcript { text, language, words } = match transcription {
+            Ok(transcript) => transcript,
+            Err(e) => {
+                eprintln!("{} [{}] ❌ Transcription error: {}", crate::ts(), self.tag(), e);
+                let mode = if request.prompt_model.is_some() { "whisper-prompt" } else { "whisper-transcribe" };
+                let model = request.prompt_model.as_deref().unwrap_or("whisper");
+                self.output.enqueue_audio(self.session_id, mode, audio, model);
+                return;
+            }
+        };
+        println!("{} [{}] ✨ Transcribed: {}", crate::ts(), self.tag(), text);
+        let heard = text.clone();
+        let text = self.transcript(text);
+        // The words are of what Whisper heard, not of what a hook made of it
+        let words = if text == heard { words } else { Vec::new() };
+
+        match request.prompt_model {
+            Some(model) => {
+                println!("{} [{}] 🤖 Prompt mode active with model: {}", crate::ts(), self.tag(), model);
+                self.prompt_or_queue(PromptRequest {
+                    prompt: crate::prompt_with_selection(&text, request.selection.as_deref()),
+                    model,
+                    history: request.history,
+                    image: request.screen_context,
+                    duration_ms: request.duration_ms,
+                    capture: request.capture,
+                    queue_mode: "whisper-prompt",
+                    speak: true,
+                    language,
+                })
+                .await;
+            }
+            None => {
+                let delivery = Delivery {
+                    model: self.client.transcription_label(),
+                    text,
+                    kind: DeliveryKind::Transcription,
+                    prompt: None,
+                    duration_ms: request.duration_ms,
+                    capture: request.capture,
+                    speak: true,
+                    segments: Vec::new(),
+                    language,
+                    original: None,
+                    words,
+                };
+                self.dictation(delivery, request.style).await;
+            }
+        }
+    }
+
+    /// A finished transcript after the plugins' `on_transcription` hooks
+    pub fn transcript(&self, text: String) -> String {
+        self.output.run_hook(Hook::Transcription, text)
+    }
+
+    /// Send a prompt and deliver the response; the error is returned, nothing is queued
+    pub async fn prompt(&self, request: PromptRequest) -> Result<(), String> {
+        self.answer(request, None, &mut false).await
+    }
+
+    /// Native prompt mode: the Realtime session that recorded the prompt answers it from the
+    /// audio, so the transcript isn't sent in a second request. Falls back to `prompt_or_queue`
+    /// (Responses API with the transcript) if the session can't answer.
+    pub async fn prompt_realtime(&self, request: PromptRequest, session: &RealtimeSession, instructions: &str) {
+        let native = PromptRequest { model: realtime::NATIVE_PROMPT_MODEL.to_string(), ..request.clone() };
+        let mut spoken = false;
+        if let Err(e) = self.answer(native, Some((session, instructions)), &mut spoken).await {
+            eprintln!("{} [{}] ⚠️ Realtime answer failed, sending the transcript instead: {}", crate::ts(), self.tag(), e);
+            // Once part of the answer has been read aloud, the fallback isn't read over it
+            let speak = request.speak && !spoken;
+            self.prompt_or_queue(PromptRequest { speak, ..request }).await;
+        }
+    }
+
+    /// Get the response from the Realtime session when given, otherwise the client, and deliver it.
+    /// `spoken` is set as soon as a sentence of it goes to streamed speech.
+    async fn answer(&self, request: PromptRequest, realtime: Option<(&RealtimeSession, &str)>, spoken: &mut bool) -> Result<(), String> {
+        // Reading it aloud can start with the first sentence, while the rest is still generated.
+        // Not while an earlier session has yet to deliver: then it's read once delivered.
+        let first_in_line = self.turn.as_ref().map_or(true, |turn| turn.is_current());
+        let speech = if request.speak && first_in_line { self.output.speak_streaming(&request.model) } else { None };
+        let mut sentences = SentenceBuffer::default();
+        let mut on_delta = |delta: &str| {
+            if let (Some(speech), Some(text)) = (&speech, sentences.push(delta)) {
+                *spoken = true;
+                let _ = speech.send(text);
+            }
+        };
+        let response = match realtime {
+            Some((session, instructions)) => session.respond(instructions, &request.history, &mut on_delta).await?,
+            None if speech.is_some() => {
+                self.client
+                    .prompt_streaming(&request.prompt, &request.model, &request.history, request.image.as_deref(), &mut on_delta)
+                    .await?
+            }
+            None => {
+                self.client
+                    .prompt(&request.prompt, &request.model, &request.history, request.image.as_deref())
+                    .await?
+            }
+        };
+        if let (Some(speech), Some(text)) = (&speech, sentences.finish()) {
+            let _ = speech.send(text);
+        }
+        println!("{} [{}] ✨ GPT Response: {}", crate::ts(), self.tag(), response);
+        let response = self.output.run_hook(Hook::PromptResponse, response);
+        self.deliver(Delivery {
+            text: response,
+            model: request.model,
+            kind: DeliveryKind::Prompt,
+            prompt: Some(request.prompt),
+            duration_ms: request.duration_ms,
+            capture: request.capture,
+            speak: request.speak && speech.is_none(), // Already being read
+            segments: Vec::new(),
+            language: request.language,
+            original: None,
+            words: Vec::new(),
+        })
+        .await;
+        Ok(())
+    }
+
+    /// `prompt`, saving the request to the offline queue if it fails
+    pub async fn prompt_or_queue(&self, request: PromptRequest) {
+        let (mode, prompt, model) = (request.queue_mode, request.prompt.clone(), request.model.clone());
+        if let Err(e) = self.prompt(request).await {
+            eprintln!("{} [{}] ❌ GPT prompt error: {}", crate::ts(), self.tag(), e);
+            self.output.enqueue_prompt(self.session_id, mode, &prompt, &model);
+        }
+    }
+
+    /// `deliver` a live dictation, unless it's a voice command ("computador, abrir ..."),
+    /// rewritten with `style` first when the target app asks for one.
+    /// Queue retries use `deliver` directly: commands are not replayed later.
+    pub async fn dictation(&self, mut delivery: Delivery, style: Option<String>) {
+        if let Some(settings) = self.output.json_output() {
+            return self.structured(delivery, settings).await;
+        }
+        if let Some((settings, window_title)) = self.output.email() {
+            return self.email(delivery, settings, window_title.as_deref()).await;
+        }
+        if self.output.run_voice_command(self.session_id, &delivery.text) {
+            return;
+        }
+        if let Some(style) = style {
+            match self.client.restyle(&delivery.text, &style).await {
+                Ok(text) => {
+                    println!("{} [{}] 🎨 Restyled ({}): {}", crate::ts(), self.tag(), style, text);
+                    let transcript = std::mem::replace(&mut delivery.text, text);
+                    delivery.original.get_or_insert(transcript);
+                }
+                // Paste what was said rather than nothing
+                Err(e) => eprintln!("{} [{}] ⚠️ Restyle failed, keeping transcript: {}", crate::ts(), self.tag(), e),
+            }
+        }
+        self.deliver(delivery).await;
+    }
+
+    /// JSON output mode: turn the dictation into JSON matching the configured schema and hand it
+    /// to automation instead of pasting. The JSON is saved to history, not to the conversation.
+    async fn structured(&self, delivery: Delivery, settings: JsonOutputSettings) {
+        let schema = match settings.parsed_schema() {
+            Ok(schema) => schema,
+            Err(e) => {
+                eprintln!("{} [{}] ❌ JSON output schema: {}", crate::ts(), self.tag(), e);
+                return;
+            }
+        };
+        let json = match self.client.structured(&delivery.text, &settings.model, &schema).await {
+            Ok(json) => json,
+            Err(e) => {
+                eprintln!("{} [{}] ❌ JSON output failed: {}", crate::ts(), self.tag(), e);
+                return;
+            }
+        };
+        self.wait_turn().await;
+        self.output.save(self.session_id, &Delivery {
+            text: json.to_string(),
+            model: settings.model.clone(),
+            kind: DeliveryKind::Prompt,
+            prompt: None,
+            segments: Vec::new(),
+            original: Some(delivery.original.clone().unwrap_or_else(|| delivery.text.clone())),
+            words: Vec::new(),
+            ..delivery
+        });
+        self.output.history_updated(self.session_id);
+        match self.output.deliver_json(&settings, &json) {
+            Ok(()) => println!("{} [{}] 🧩 JSON output delivered", crate::ts(), self.tag()),
+            Err(e) => eprintln!("{} [{}] ❌ JSON output delivery failed: {}", crate::ts(), self.tag(), e),
+        }
+        self.output.response_ready(self.session_id);
+    }
+
+    /// Email mode: the model writes the dictated notes out as an email, pasted like a prompt response.
+    /// No conversation history: each email stands alone, and isn't added to it either.
+    async fn email(&self, delivery: Delivery, settings: EmailSettings, window_title: Option<&str>) {
+        println!("{} [{}] ✉️ Writing an email with {}", crate::ts(), self.tag(), settings.model);
+        let prompt = settings.prompt(&delivery.text, window_title);
+        let response = match self.client.prompt(&prompt, &settings.model, &[], None).await {
+            Ok(response) => response,
+            Err(e) => {
+                eprintln!("{} [{}] ❌ Email prompt error: {}", crate::ts(), self.tag(), e);
+                self.output.enqueue_prompt(self.session_id, "text-prompt", &prompt, &settings.model);
+                return;
+            }
+        };
+        println!("{} [{}] ✨ Email: {}", crate::ts(), self.tag(), response);
+        self.deliver(Delivery {
+            text: self.output.run_hook(Hook::PromptResponse, response),
+            model: settings.model,
+            kind: DeliveryKind::Prompt,
+            prompt: None, // Not recorded in the conversation
+            speak: false,
+            segments: Vec::new(),
+            original: None,
+            words: Vec::new(),
+            ..delivery
+        })
+        .await;
+    }
+
+    /// Save, notify, paste, play the ready sound and (optionally) read the result aloud
+    pub async fn deliver(&self, mut delivery: Delivery) {
+        self.wait_turn().await;
+        let original = delivery.original.take().unwrap_or_else(|| delivery.text.clone());
+        let plain = self.output.plain_text(delivery.kind);
+        if plain {
+            delivery.text = plain_text::normalize(&delivery.text);
+        }
+        if delivery.kind == DeliveryKind::Transcription {
+            let rules = delivery.language.as_deref().and_then(numbers::rules_for);
+            if let Some(rules) = rules {
+                delivery.text = numbers::normalize(&delivery.text, self.output.number_style(), rules);
+            }
+            if let Some(extra) = self.output.profanity_words() {
+                let language = delivery.language.as_deref();
+                delivery.text = profanity::mask(&delivery.text, language, &extra);
+                for segment in &mut delivery.segments {
+                    segment.text = profanity::mask(&segment.text, language, &extra);
+                }
+            }
+        }
+        delivery.text = self.output.run_hook(Hook::BeforePaste, delivery.text);
+        if original != delivery.text {
+            delivery.original = Some(original);
+        }
+        let rich = delivery.kind == DeliveryKind::Prompt && !plain && self.output.rich_text();
+        let entry = self.output.save(self.session_id, &delivery);
+        self.output.history_updated(self.session_id);
+        match self.output.paste(entry, &delivery.text, rich) {
+            Ok(()) => println!("{} [{}] ✅ {} auto-pasted", crate::ts(), self.tag(), delivery.kind.as_str()),
+            Err(e) => self.output.paste_failed(self.session_id, &delivery.text, &e),
+        }
+        self.output.response_ready(self.session_id);
+        if delivery.speak {
+            self.output.speak(delivery.kind, &delivery.model, &delivery.text);
+        }
+    }
+}
+
+/// The real outputs: database, paste target, webview events, TTS and offline queue
+pub struct AppOutput {
+    app: AppHandle,
+}
+
+impl AppOutput {
+    pub fn new(app: &AppHandle) -> Self {
+        Self { app: app.clone() }
+    }
+}
+
+impl Output for AppOutput {
+    fn save(&self, session_id: Option<SessionId>, delivery: &Delivery) -> Option<i64> {
+        let state = self.app.try_state::<AppState>()?;
+        let database = &state.database;
+        let timestamp = crate::now_ms();
+        let cost = crate::estimate_cost_cents(&delivery.model, delivery.duration_ms, &delivery.text);
+        let entry = match crate::record_transcription(database, session_id, &delivery.text, timestamp, delivery.duration_ms, Some(&delivery.model), Some(cost), Some(delivery.kind.as_str())) {
+            Ok(Some(id)) => {
+                crate::generate_title(&self.app, id, &delivery.text);
+                if let Some(capture) = &delivery.capture {
+                    let _ = database.set_transcription_clipping(id, capture.clipping_pct as f64);
+                    let _ = database.save_session_diagnostics(id, &capture.diagnostics);
+                }
+                if let Some(language) = &delivery.language {
+                    let _ = database.set_transcription_language(id, language);
+                }
+                if !delivery.segments.is_empty() {
+                    if let Err(e) = database.save_transcription_segments(id, &delivery.segments) {
+                        eprintln!("⚠️ Failed to save session turns: {}", e);
+                    }
+                }
+                // Only while the entry's text is still what the words were heard as
+                if delivery.original.is_none() && delivery.words.iter().any(|w| w.probability.is_some()) {
+                    if let Err(e) = database.set_transcription_confidence(id, &delivery.words) {
+                        eprintln!("⚠️ Failed to save word confidence: {}", e);
+                    }
+                }
+                if let Some(original) = &delivery.original {
+                    let revisions = database.add_transcription_revision(id, original, "original", Some(&delivery.model), timestamp)
+                        .and_then(|_| database.add_transcription_revision(id, &delivery.text, "post_processed", None, timestamp));
+                    if let Err(e) = revisions {
+                        eprintln!("⚠️ Failed to save revisions: {}", e);
+                    }
+                }
+                Some(id)
+            }
+            Ok(None) => None,
+            Err(e) => {
+                eprintln!("❌ Failed to save to database: {}", e);
+                None
+            }
+        };
+        if let Some(prompt) = &delivery.prompt {
+            crate::record_conversation(database, prompt, &delivery.text, timestamp);
+            crate::emit_conversation_updated(&self.app, session_id);
+        }
+        *state.last_transcription.lock().unwrap() = Some(delivery.text.clone());
+        entry
+    }
+
+    fn history_updated(&self, session_id: Option<SessionId>) {
+        if let Some(window) = self.app.get_webview_window("main") {
+            let _ = emit_app_event(&window, AppEvent::HistoryUpdated { session_id });
+        }
+    }
+
+    fn paste(&self, entry: Option<i64>, text: &str, rich: bool) -> Result<(), String> {
+        crate::paste_or_review(&self.app, entry, text, rich)
+    }
+
+    fn paste_failed(&self, session_id: Option<SessionId>, text: &str, error: &str) {
+        crate::report_paste_failure(&self.app, session_id, text, error);
+    }
+
+    fn response_ready(&self, session_id: Option<SessionId>) {
+        crate::emit_response_ready(&self.app, session_id);
+    }
+
+    fn speak(&self, kind: DeliveryKind, model: &str, text: &str) {
+        let Some(state) = self.app.try_state::<AppState>() else {
+            return;
+        };
+        // Assistant mode answers out loud even with TTS off: that's the whole conversation
+        let assistant = kind == DeliveryKind::Prompt && crate::assistant_active(&self.app);
+        if !(*state.tts_enabled.lock().unwrap() || assistant) || crate::is_quiet_time(&self.app) {
+            return;
+        }
+        if assistant || crate::TtsAutoPlay::load(&state.database).allows(kind, text) {
+            let voice = crate::tts_voice_for(&state.database, (kind == DeliveryKind::Prompt).then_some(model));
+            crate::speak_aloud_as(&self.app, &state, crate::TtsText::Full(text.to_string()), voice);
+        }
+    }
+
+    fn speak_streaming(&self, model: &str) -> Option<UnboundedSender<String>> {
+        let state = self.app.try_state::<AppState>()?;
+        let assistant = crate::assistant_active(&self.app);
+        if !(*state.tts_enabled.lock().unwrap() || assistant) || crate::is_quiet_time(&self.app) {
+            return None;
+        }
+        // A response hook may rewrite the text: read what it delivers, not the raw deltas
+        if !crate::tts_streaming_enabled(&state.database) || state.plugins.has_hook(&state.database, Hook::PromptResponse) {
+            return None;
+        }
+        let auto_play = crate::TtsAutoPlay::load(&state.database);
+        if !(assistant || auto_play.prompt_responses) {
+            return None;
+        }
+        let min_chars = if assistant { 0 } else { auto_play.min_chars };
+        let voice = crate::tts_voice_for(&state.database, Some(model));
+        Some(crate::speak_streamed(&self.app, voice, min_chars))
+    }
+
+    fn plain_text(&self, kind: DeliveryKind) -> bool {
+        self.app
+            .try_state::<AppState>()
+            .and_then(|state| state.database.load_setting(&format!("plain_text_{}", kind.as_str())).ok().flatten())
+            .is_some_and(|v| v == "true")
+    }
+
+    fn number_style(&self) -> NumberStyle {
+        self.app
+            .try_state::<AppState>()
+            .and_then(|state| state.database.load_setting("number_normalization").ok().flatten())
+            .map_or(NumberStyle::Off, |v| NumberStyle::from_setting(&v))
+    }
+
+    fn profanity_words(&self) -> Option<Vec<String>> {
+        let state = self.app.try_state::<AppState>()?;
+        if !state.database.load_setting("profanity_filter").ok().flatten().is_some_and(|v| v == "true") {
+            return None;
+        }
+        Some(crate::profanity_custom_words(&state.database))
+    }
+
+    fn rich_text(&self) -> bool {
+        self.app
+            .try_state::<AppState>()
+            .and_then(|state| state.database.load_setting("rich_text_prompt").ok().flatten())
+            .is_some_and(|v| v == "true")
+    }
+
+    fn run_voice_command(&self, session_id: Option<SessionId>, text: &str) -> bool {
+        let Some(state) = self.app.try_state::<AppState>() else {
+            return false;
+        };
+        let settings = VoiceCommandSettings::load(&state.database);
+        if !settings.enabled {
+            return false;
+        }
+        let Some(command) = voice_commands::strip_trigger(text, &settings.trigger) else {
+            return false;
+        };
+
+        let intents = state.database.load_voice_intents().unwrap_or_default();
+        match voice_commands::route(&command, &intents) {
+            Some(intent) => {
+                println!("🗣️ Voice command '{}' → {} {}", command, intent.action, intent.value);
+                let argument = voice_commands::argument(text, &settings.trigger, intent);
+                if let Err(e) = voice_commands::execute(&self.app, intent, argument) {
+                    eprintln!("❌ Voice command failed: {}", e);
+                    crate::notify(&self.app, NotificationKind::VoiceCommand, "Dicta - Comando de voz", &e);
+                }
+            }
+            None => {
+                eprintln!("🗣️ Unknown voice command: '{}'", command);
+                let body = format!("Comando não reconhecido: \"{}\"", command);
+                crate::notify(&self.app, NotificationKind::VoiceCommand, "Dicta - Comando de voz", &body);
+            }
+        }
+        crate::emit_response_ready(&self.app, session_id);
+        true
+    }
+
+    fn run_hook(&self, hook: Hook, text: String) -> String {
+        match self.app.try_state::<AppState>() {
+            Some(state) => state.plugins.run(&state.database, hook, text),
+            None => text,
+        }
+    }
+
+    fn json_output(&self) -> Option<JsonOutputSettings> {
+        let state = self.app.try_state::<AppState>()?;
+        let mut target = state.output_target.lock().unwrap();
+        if *target != crate::OutputTarget::Json {
+            return None;
+        }
+        *target = crate::OutputTarget::Paste;
+        Some(JsonOutputSettings::load(&state.database))
+    }
+
+    fn email(&self) -> Option<(EmailSettings, Option<String>)> {
+        let state = self.app.try_state::<AppState>()?;
+        let mut target = state.output_target.lock().unwrap();
+        if *target != crate::OutputTarget::Email {
+            return None;
+        }
+        *target = crate::OutputTarget::Paste;
+        let window_title = crate::active_window::foreground().map(|window| window.title);
+        Some((EmailSettings::load(&state.database), window_title))
+    }
+
+    fn deliver_json(&self, settings: &JsonOutputSettings, json: &serde_json::Value) -> Result<(), String> {
+        // Served by the local API (GET /json/last) and pushed to its WebSocket clients
+        let state = self.app.try_state::<AppState>();
+        if let Some(state) = &state {
+            *state.last_json_output.lock().unwrap() = Some((crate::now_ms(), json.clone()));
+        }
+        if let Some(url) = settings.webhook_url.clone() {
+            let json = json.clone();
+            let proxy = state.and_then(|state| crate::load_proxy_settings(&state.database));
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = json_output::post_webhook(&url, &json, proxy.as_ref()).await {
+                    eprintln!("❌ JSON output webhook failed: {}", e);
+                }
+            });
+        }
+        if let Some(path) = &settings.file_path {
+            let path = json_output::append_to_file(path, json)?;
+            println!("🧩 JSON output appended to {}", path.display());
+        }
+        Ok(())
+    }
+
+    fn enqueue_prompt(&self, session_id: Option<SessionId>, mode: &str, prompt: &str, model: &str) {
+        let Some(state) = self.app.try_state::<AppState>() else {
+            return;
+        };
+        let database = &state.database;
+        if database.count_queue().unwrap_or(0) >= crate::queue_max_items(database) {
+            crate::emit_queue_full(&self.app);
+            return;
+        }
+        let session_id = session_id.map(|id| id.to_string());
+        let _ = database.enqueue_item(mode, None, Some(prompt), model, crate::now_ms(), session_id.as_deref());
+        crate::emit_item_queued(&self.app, database);
+    }
+
+    fn enqueue_audio(&self, session_id: Option<SessionId>, mode: &str, audio: Vec<f32>, model: &str) {
+        let Some(state) = self.app.try_state::<AppState>() else {
+            return;
+        };
+        let database = &state.database;
+        if database.count_queue().unwrap_or(0) >= crate::queue_max_items(database) {
+            crate::emit_queue_full(&self.app);
+            return;
+        }
+        match crate::queue::save_audio_to_wav(audio, &state.queue_dir) {
+            Ok(wav_path) => {
+                let session_id = session_id.map(|id| id.to_string());
+                let _ = database.enqueue_item(mode, Some(wav_path.to_str().unwrap_or("")), None, model, crate::now_ms(), session_id.as_deref());
+                crate::emit_item_queued(&self.app, database);
+            }
+            Err(e) => eprintln!("❌ Failed to save audio to queue: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Answers every call from canned values; `prompt_delay` holds prompts back to finish sessions out of order
+    #[derive(Default)]
+    struct FakeClient {
+        transcript: String,
+        prompt_delay: Duration,
+        fail_prompt: bool,
+    }
+
+    impl AiClient for FakeClient {
+        async fn transcribe(&self, _audio: Vec<f32>, _sample_rate: u32, _prompt: Option<&str>) -> Result<Transcript, String> {
+            Ok(Transcript { text: self.transcript.clone(), language: None, words: Vec::new() })
+        }
+
+        async fn prompt(&self, prompt: &str, _model: &str, _history: &[ConversationMessage], _image: Option<&str>) -> Result<String, String> {
+            tokio::time::sleep(self.prompt_delay).await;
+            if self.fail_prompt {
+                return Err("offline".to_string());
+            }
+            Ok(format!("answer: {}", prompt))
+        }
+
+        async fn prompt_streaming(
+            &self,
+            prompt: &str,
+            model: &str,
+            history: &[ConversationMessage],
+            image: Option<&str>,
+            on_delta: &mut (dyn FnMut(&str) + Send),
+        ) -> Result<String, String> {
+            let response = self.prompt(prompt, model, history, image).await?;
+            on_delta(&response);
+            Ok(response)
+        }
+
+        async fn restyle(&self, text: &str, _style: &str) -> Result<String, String> {
+            Ok(text.to_uppercase())
+        }
+
+        async fn structured(&self, prompt: &str, _model: &str, _schema: &serde_json::Value) -> Result<serde_json::Value, String> {
+            Ok(serde_json::json!({ "text": prompt }))
+        }
+
+        fn transcription_label(&self) -> String {
+            "fake-whisper".to_string()
+        }
+    }
+
+    #[derive(Default)]
+    struct Log {
+        saved: Vec<Delivery>,
+        pasted: Vec<String>,
+        queued_prompts: Vec<(String, String, String)>, // Mode, prompt, model
+        queued_audio: Vec<String>,                     // Mode
+        commands: Vec<String>,
+    }
+
+    /// Records what the pipeline hands to the app. Clones share the log.
+    #[derive(Clone, Default)]
+    struct RecordingOutput {
+        log: Arc<Mutex<Log>>,
+        command_trigger: Option<&'static str>,
+    }
+
+    impl Output for RecordingOutput {
+        fn save(&self, _session_id: Option<SessionId>, delivery: &Delivery) -> Option<i64> {
+            let mut log = self.log.lock().unwrap();
+            log.saved.push(delivery.clone());
+            Some(log.saved.len() as i64)
+        }
+
+        fn history_updated(&self, _session_id: Option<SessionId>) {}
+
+
+        fn paste(&self, _entry: Option<i64>, text: &str, _rich: bool) -> Result<(), String> {
+            self.log.lock().unwrap().pasted.push(text.to_string());
+            Ok(())
+        }
+
+        fn paste_failed(&self, _session_id: Option<SessionId>, _text: &str, _error: &str) {}
+
+        fn response_ready(&self, _session_id: Option<SessionId>) {}
+
+        fn speak(&self, _kind: DeliveryKind, _model: &str, _text: &str) {}
+
+        fn speak_streaming(&self, _model: &str) -> Option<UnboundedSender<String>> {
+            None
+        }
+
+        fn plain_text(&self, _kind: DeliveryKind) -> bool {
+            false
+        }
+
+        fn number_style(&self) -> NumberStyle {
+            NumberStyle::Off
+        }
+
+        fn profanity_words(&self) -> Option<Vec<String>> {
+            None
+        }
+
+        fn rich_text(&self) -> bool {
+            false
+        }
+
+        fn run_voice_command(&self, _session_id: Option<SessionId>, text: &str) -> bool {
+            match self.command_trigger {
+                Some(trigger) if text.starts_with(trigger) => {
+                    self.log.lock().unwrap().commands.push(text.to_string());
+                    true
+                }
+                _ => false,
+            }
+        }
+
+        fn run_hook(&self, _hook: Hook, text: String) -> String {
+            text
+        }
+
+        fn json_output(&self) -> Option<JsonOutputSettings> {
+            None
+        }
+
+        fn email(&self) -> Option<(EmailSettings, Option<String>)> {
+            None
+        }
+
+        fn deliver_json(&self, _settings: &JsonOutputSettings, _json: &serde_json::Value) -> Result<(), String> {
+            Ok(())
+        }
+
+        fn enqueue_prompt(&self, _session_id: Option<SessionId>, mode: &str, prompt: &str, model: &str) {
+            self.log.lock().unwrap().queued_prompts.push((mode.to_string(), prompt.to_string(), model.to_string()));
+        }
+
+        fn enqueue_audio(&self, _session_id: Option<SessionId>, mode: &str, _audio: Vec<f32>, _model: &str) {
+            self.log.lock().unwrap().queued_audio.push(mode.to_string());
+        }
+    }
+
+    fn recording(prompt_model: Option<&str>) -> RecordingRequest {
+        RecordingRequest {
+            prompt_model: prompt_model.map(str::to_string),
+            selection: None,
+            style: None,
+            screen_context: None,
+            history: Vec::new(),
+            duration_ms: Some(1200),
+            capture: None,
+        }
+    }
+
+    fn prompt_request(prompt: &str) -> PromptRequest {
+        PromptRequest {
+            prompt: prompt.to_string(),
+            model: "gpt-test".to_string(),
+            history: Vec::new(),
+            image: None,
+            duration_ms: None,
+            capture: None,
+            queue_mode: "text-prompt",
+            speak: false,
+            language: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn transcribe_then_deliver() {
+        let output = RecordingOutput::default();
+        let client = FakeClient { transcript: "hello world".to_string(), ..Default::default() };
+        let pipeline = Pipeline::new(Arc::new(client), output.clone(), None);
+
+        let transcription = pipeline.transcribe(vec![0.0; 1600], 16000, None).await;
+        pipeline.finish_recording(transcription, Vec::new(), recording(None)).await;
+
+        let log = output.log.lock().unwrap();
+        assert_eq!(log.pasted, ["hello world"]);
+        assert_eq!(log.saved.len(), 1);
+        assert_eq!(log.saved[0].kind, DeliveryKind::Transcription);
+        assert_eq!(log.saved[0].model, "fake-whisper");
+        assert_eq!(log.saved[0].duration_ms, Some(1200));
+        assert!(log.queued_prompts.is_empty() && log.queued_audio.is_empty());
+    }
+
+    #[tokio::test]
+    async fn transcribe_then_prompt_then_deliver() {
+        let output = RecordingOutput::default();
+        let client = FakeClient { transcript: "summarize this".to_string(), ..Default::default() };
+        let pipeline = Pipeline::new(Arc::new(client), output.clone(), None);
+
+        let transcription = pipeline.transcribe(vec![0.0; 1600], 16000, None).await;
+        pipeline.finish_recording(transcription, Vec::new(), recording(Some("gpt-test"))).await;
+
+        let log = output.log.lock().unwrap();
+        assert_eq!(log.pasted, ["answer: summarize this"]);
+        assert_eq!(log.saved.len(), 1);
+        assert_eq!(log.saved[0].kind, DeliveryKind::Prompt);
+        assert_eq!(log.saved[0].model, "gpt-test");
+        assert_eq!(log.saved[0].prompt.as_deref(), Some("summarize this"));
+    }
+
+    #[tokio::test]
+    async fn failed_prompt_is_queued() {
+        let output = RecordingOutput::default();
+        let client = FakeClient { transcript: "summarize this".to_string(), fail_prompt: true, ..Default::default() };
+        let pipeline = Pipeline::new(Arc::new(client), output.clone(), None);
+
+        let transcription = pipeline.transcribe(vec![0.0; 1600], 16000, None).await;
+        pipeline.finish_recording(transcription, Vec::new(), recording(Some("gpt-test"))).await;
+
+        let log = output.log.lock().unwrap();
+        assert!(log.pasted.is_empty() && log.saved.is_empty());
+        assert_eq!(
+            log.queued_prompts,
+            [("whisper-prompt".to_string(), "summarize this".to_string(), "gpt-test".to_string())]
+        );
+    }
+
+    #[tokio::test]
+    async fn failed_transcription_queues_the_audio() {
+        let output = RecordingOutput::default();
+        let pipeline = Pipeline::new(Arc::new(FakeClient::default()), output.clone(), None);
+
+        pipeline.finish_recording(Err("offline".to_string()), vec![0.0; 1600], recording(None)).await;
+
+        let log = output.log.lock().unwrap();
+        assert!(log.pasted.is_empty());
+        assert_eq!(log.queued_audio, ["whisper-transcribe"]);
+    }
+
+    #[tokio::test]
+    async fn voice_command_is_not_delivered() {
+        let output = RecordingOutput { command_trigger: Some("computador"), ..Default::default() };
+        let client = FakeClient { transcript: "computador, abrir notas".to_string(), ..Default::default() };
+        let pipeline = Pipeline::new(Arc::new(client), output.clone(), None);
+
+        let transcription = pipeline.transcribe(vec![0.0; 1600], 16000, None).await;
+        pipeline.finish_recording(transcription, Vec::new(), recording(None)).await;
+
+        let log = output.log.lock().unwrap();
+        assert_eq!(log.commands, ["computador, abrir notas"]);
+        assert!(log.pasted.is_empty() && log.saved.is_empty());
+    }
+
+    #[tokio::test]
+    async fn restyled_dictation_keeps_the_original() {
+        let output = RecordingOutput::default();
+        let client = FakeClient { transcript: "hello world".to_string(), ..Default::default() };
+        let pipeline = Pipeline::new(Arc::new(client), output.clone(), None);
+
+        let transcription = pipeline.transcribe(vec![0.0; 1600], 16000, None).await;
+        let request = RecordingRequest { style: Some("formal".to_string()), ..recording(None) };
+        pipeline.finish_recording(transcription, Vec::new(), request).await;
+
+        let log = output.log.lock().unwrap();
+        assert_eq!(log.pasted, ["HELLO WORLD"]);
+        assert_eq!(log.saved[0].text, "HELLO WORLD");
+        assert_eq!(log.saved[0].original.as_deref(), Some("hello world"));
+    }
+
+    #[tokio::test]
+    async fn sessions_deliver_in_submission_order() {
+        let order = Arc::new(DeliveryOrder::default());
+        let output = RecordingOutput::default();
+        let slow = FakeClient { prompt_delay: Duration::from_millis(200), ..Default::default() };
+        let first = Pipeline::new(Arc::new(slow), output.clone(), None).in_order(&order);
+        let second = Pipeline::new(Arc::new(FakeClient::default()), output.clone(), None).in_order(&order);
+
+        // The second session's response arrives first; each pipeline (and its turn) is dropped once it's done
+        let (a, b) = tokio::join!(
+            async move { first.prompt(prompt_request("first")).await },
+            async move { second.prompt(prompt_request("second")).await },
+        );
+        assert!(a.is_ok() && b.is_ok());
+
+        assert_eq!(output.log.lock().unwrap().pasted, ["answer: first", "answer: second"]);
+    }
+}
@@ -0,0 +1,168 @@
+/// Turn model output into plain text for pasting: strips markdown syntax (headings, emphasis,
+/// code, links, quotes, rules), normalizes typographic quotes/dashes and collapses whitespace.
+/// Code (fenced blocks and `inline` spans) is kept exactly as written, minus the fences/backticks.
+/// Deterministic, so the same response always pastes the same way.
+pub fn normalize(text: &str) -> String {
+    let mut lines = Vec::new();
+    let mut in_fence = false;
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("```") {
+            in_fence = !in_fence;
+            continue; // Fence markers carry no text
+        }
+        if in_fence {
+            lines.push(line.trim_end().to_string());
+            continue;
+        }
+        if is_rule(trimmed) {
+            continue; // Horizontal rules neither
+        }
+        let line = strip_line_prefix(trimmed);
+        let line = strip_inline(&line);
+        lines.push(collapse_spaces(&normalize_punctuation(&line)));
+    }
+
+    // At most one blank line between paragraphs
+    let mut out = String::new();
+    let mut blank = false;
+    for line in lines {
+        if line.is_empty() {
+            blank = !out.is_empty();
+            continue;
+        }
+        if !out.is_empty() {
+            out.push_str(if blank { "\n\n" } else { "\n" });
+        }
+        out.push_str(&line);
+        blank = false;
+    }
+    out
+}
+
+/// "---", "***", "___" (3+ of the same char, spaces allowed)
+pub(crate) fn is_rule(line: &str) -> bool {
+    let chars: Vec<char> = line.chars().filter(|c| !c.is_whitespace()).collect();
+    chars.len() >= 3 && matches!(chars[0], '-' | '*' | '_') && chars.iter().all(|c| *c == chars[0])
+}
+
+/// Headings and blockquotes lose their marker; "*"/"+" bullets become "-"
+fn strip_line_prefix(line: &str) -> String {
+    let mut line = line;
+    while let Some(rest) = line.strip_prefix('>') {
+        line = rest.trim_start();
+    }
+    let hashes = line.chars().take_while(|c| *c == '#').count();
+    if (1..=6).contains(&hashes) && line[hashes..].starts_with(' ') {
+        return line[hashes..].trim_start().to_string();
+    }
+    for bullet in ["* ", "+ "] {
+        if let Some(rest) = line.strip_prefix(bullet) {
+            return format!("- {}", rest.trim_start());
+        }
+    }
+    line.to_string()
+}
+
+/// Emphasis markers, inline code backticks, links and images. Markers are only removed in
+/// pairs that open and close on word boundaries, so "2 * 3", "a*b" and snake_case stay as they
+/// are; underscores only when they wrap several words, so `__init__` does too.
+fn strip_inline(line: &str) -> String {
+    let chars: Vec<char> = line.chars().collect();
+    let mut out = String::with_capacity(line.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '`' => {
+                // Code span: its text is kept exactly, only the backticks go
+                if let Some(end) = chars[i + 1..].iter().position(|&ch| ch == '`').map(|p| i + 1 + p) {
+                    out.extend(&chars[i + 1..end]);
+                    i = end + 1;
+                    continue;
+                }
+            }
+            '*' | '_' => {
+                let run = chars[i..].iter().take_while(|&&ch| ch == c).count().min(3);
+                if let Some(end) = closing_marker(&chars, i, run) {
+                    let inner: String = chars[i + run..end].iter().collect();
+                    if c == '*' || inner.contains(' ') {
+                        out.push_str(&strip_inline(&inner));
+                        i = end + run;
+                        continue;
+                    }
+                }
+                out.extend(&chars[i..i + run]);
+                i += run;
+                continue;
+            }
+            '!' if chars.get(i + 1) == Some(&'[') => {
+                if let Some((label, _, end)) = parse_link(&chars, i + 1) {
+                    out.push_str(&label);
+                    i = end;
+                    continue;
+                }
+            }
+            '[' => {
+                if let Some((label, url, end)) = parse_link(&chars, i) {
+                    if label == url {
+                        out.push_str(&url);
+                    } else {
+                        out.push_str(&format!("{} ({})", label, url));
+                    }
+                    i = end;
+                    continue;
+                }
+            }
+            _ => {}
+        }
+        out.push(c);
+        i += 1;
+    }
+    out
+}
+
+/// For an emphasis marker of `len` chars at `start`: where its matching closing marker is.
+/// The opening one must follow a word boundary and touch text; the closing one the reverse.
+fn closing_marker(chars: &[char], start: usize, len: usize) -> Option<usize> {
+    let marker = &chars[start..start + len];
+    let boundary = |ch: Option<&char>| ch.map_or(true, |ch| !ch.is_alphanumeric() && !marker.contains(ch));
+    let opens = boundary(start.checked_sub(1).and_then(|j| chars.get(j)))
+        && chars.get(start + len).is_some_and(|ch| !ch.is_whitespace() && !marker.contains(ch));
+    if !opens {
+        return None;
+    }
+    let last = chars.len().checked_sub(len)?;
+    (start + len + 1..=last).find(|&j| {
+        &chars[j..j + len] == marker && !chars[j - 1].is_whitespace() && boundary(chars.get(j + len))
+    })
+}
+
+/// "[label](url)" starting at `start`: (label, url, index after the closing parenthesis)
+pub(crate) fn parse_link(chars: &[char], start: usize) -> Option<(String, String, usize)> {
+    let close = start + chars[start..].iter().position(|&c| c == ']')?;
+    if chars.get(close + 1) != Some(&'(') {
+        return None;
+    }
+    let end = close + 1 + chars[close + 1..].iter().position(|&c| c == ')')?;
+    let label: String = chars[start + 1..close].iter().collect();
+    let url: String = chars[close + 2..end].iter().collect();
+    Some((label, url, end + 1))
+}
+
+fn normalize_punctuation(line: &str) -> String {
+    line.chars()
+        .map(|c| match c {
+            '“' | '”' | '„' | '«' | '»' => "\"".to_string(),
+            '‘' | '’' | '‚' => "'".to_string(),
+            '—' | '–' | '‒' => "-".to_string(),
+            '…' => "...".to_string(),
+            '\u{a0}' | '\u{202f}' | '\t' => " ".to_string(),
+            c => c.to_string(),
+        })
+        .collect()
+}
+
+fn collapse_spaces(line: &str) -> String {
+    line.split_whitespace().collect::<Vec<_>>().join(" ")
+}
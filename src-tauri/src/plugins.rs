@@ -0,0 +1,191 @@
+use crate::db::Database;
+use rhai::{CallFnOptions, Dynamic, Engine, Scope, AST};
+use serde::Serialize;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::AppHandle;
+use tauri_plugin_notification::NotificationExt;
+
+/// Stops runaway scripts (infinite loops) instead of freezing the pipeline
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+/// Points of the pipeline a plugin can hook by defining a Rhai function of the same name
+/// taking the text. Returning a string replaces the text; returning nothing keeps it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hook {
+    Transcription,  // Transcript, before it's pasted or sent as a prompt
+    BeforePaste,    // Final text of any session, before it's saved and pasted
+    PromptResponse, // Model response, before it's delivered
+}
+
+impl Hook {
+    pub const ALL: [Hook; 3] = [Hook::Transcription, Hook::BeforePaste, Hook::PromptResponse];
+
+    pub fn function_name(self) -> &'static str {
+        match self {
+            Hook::Transcription => "on_transcription",
+            Hook::BeforePaste => "on_before_paste",
+            Hook::PromptResponse => "on_prompt_response",
+        }
+    }
+}
+
+/// A `.rhai` file from the plugins folder
+struct Plugin {
+    name: String, // File name without extension
+    ast: Result<AST, String>,
+}
+
+impl Plugin {
+    fn hooks(&self) -> Vec<Hook> {
+        let Ok(ast) = &self.ast else {
+            return Vec::new();
+        };
+        Hook::ALL
+            .into_iter()
+            .filter(|hook| ast.iter_functions().any(|f| f.name == hook.function_name() && f.params.len() == 1))
+            .collect()
+    }
+}
+
+/// Shown in the settings UI
+#[derive(Debug, Clone, Serialize)]
+pub struct PluginInfo {
+    pub name: String,
+    pub enabled: bool,
+    pub hooks: Vec<&'static str>,
+    pub error: Option<String>, // Compile error; the plugin is skipped until fixed and reloaded
+}
+
+struct Loaded {
+    engine: Engine,
+    plugins: Vec<Plugin>,
+}
+
+/// User scripts loaded from the plugins folder. Each plugin runs only when enabled
+/// (`plugin_enabled_<name>` setting, off by default since scripts can run anything they're given).
+pub struct PluginHost {
+    dir: PathBuf,
+    loaded: Mutex<Option<Loaded>>,
+}
+
+impl PluginHost {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir, loaded: Mutex::new(None) }
+    }
+
+    pub fn dir(&self) -> &PathBuf {
+        &self.dir
+    }
+
+    /// (Re)compile every `.rhai` file in the plugins folder
+    pub fn load(&self, app: &AppHandle) {
+        if let Err(e) = std::fs::create_dir_all(&self.dir) {
+            eprintln!("⚠️ Failed to create plugins folder {}: {}", self.dir.display(), e);
+        }
+        let engine = new_engine(app);
+        let mut files: Vec<PathBuf> = std::fs::read_dir(&self.dir)
+            .map(|entries| entries.flatten().map(|e| e.path()).collect())
+            .unwrap_or_default();
+        files.retain(|path| path.extension().is_some_and(|ext| ext == "rhai"));
+        files.sort();
+
+        let plugins: Vec<Plugin> = files
+            .iter()
+            .map(|path| {
+                let name = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+                let ast = engine.compile_file(path.clone()).map_err(|e| e.to_string());
+                if let Err(e) = &ast {
+                    eprintln!("❌ Plugin '{}' failed to compile: {}", name, e);
+                }
+                Plugin { name, ast }
+            })
+            .collect();
+        println!("🧩 Loaded {} plugin(s) from {}", plugins.len(), self.dir.display());
+        *self.loaded.lock().unwrap() = Some(Loaded { engine, plugins });
+    }
+
+    pub fn list(&self, database: &Database) -> Vec<PluginInfo> {
+        let loaded = self.loaded.lock().unwrap();
+        let Some(loaded) = loaded.as_ref() else {
+            return Vec::new();
+        };
+        loaded
+            .plugins
+            .iter()
+            .map(|plugin| PluginInfo {
+                name: plugin.name.clone(),
+                enabled: is_enabled(database, &plugin.name),
+                hooks: plugin.hooks().into_iter().map(Hook::function_name).collect(),
+                error: plugin.ast.as_ref().err().cloned(),
+            })
+            .collect()
+    }
+
+    /// Whether any enabled plugin defines `hook`
+    pub fn has_hook(&self, database: &Database, hook: Hook) -> bool {
+        let loaded = self.loaded.lock().unwrap();
+        loaded.as_ref().is_some_and(|loaded| {
+            loaded.plugins.iter().any(|plugin| plugin.hooks().contains(&hook) && is_enabled(database, &plugin.name))
+        })
+    }
+
+    /// Pass `text` through `hook` of every enabled plugin, in file name order.
+    /// A failing plugin is logged and skipped, so a bad script never loses a dictation.
+    pub fn run(&self, database: &Database, hook: Hook, text: String) -> String {
+        let loaded = self.loaded.lock().unwrap();
+        let Some(loaded) = loaded.as_ref() else {
+            return text;
+        };
+        let mut text = text;
+        for plugin in &loaded.plugins {
+            let Ok(ast) = &plugin.ast else { continue };
+            if !plugin.hooks().contains(&hook) || !is_enabled(database, &plugin.name) {
+                continue;
+            }
+            // Plugins are function libraries: top-level statements aren't re-run on every call
+            let options = CallFnOptions::new().eval_ast(false);
+            let result = loaded.engine.call_fn_with_options::<Dynamic>(options, &mut Scope::new(), ast, hook.function_name(), (text.clone(),));
+            match result {
+                Ok(value) if value.is_unit() => {}
+                Ok(value) => match value.into_string() {
+                    Ok(new_text) => {
+                        println!("🧩 Plugin '{}' {} changed the text", plugin.name, hook.function_name());
+                        text = new_text;
+                    }
+                    Err(type_name) => eprintln!("⚠️ Plugin '{}' {} returned {}, expected a string", plugin.name, hook.function_name(), type_name),
+                },
+                Err(e) => eprintln!("❌ Plugin '{}' {} failed: {}", plugin.name, hook.function_name(), e),
+            }
+        }
+        text
+    }
+}
+
+/// Engine with the functions plugins can call besides Rhai's standard library:
+/// `print`/`debug` go to the log and `notify(title, body)` shows a Windows notification
+fn new_engine(app: &AppHandle) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine.on_print(|text| println!("🧩 [plugin] {}", text));
+    engine.on_debug(|text, source, pos| println!("🧩 [plugin {}:{}] {}", source.unwrap_or("?"), pos, text));
+    let app = app.clone();
+    engine.register_fn("notify", move |title: &str, body: &str| {
+        if let Err(e) = app.notification().builder().title(title).body(body).show() {
+            eprintln!("⚠️ Plugin notification failed: {}", e);
+        }
+    });
+    engine
+}
+
+fn setting_key(name: &str) -> String {
+    format!("plugin_enabled_{}", name)
+}
+
+pub fn is_enabled(database: &Database, name: &str) -> bool {
+    database.load_setting(&setting_key(name)).ok().flatten().as_deref() == Some("true")
+}
+
+pub fn set_enabled(database: &Database, name: &str, enabled: bool) -> rusqlite::Result<()> {
+    database.save_setting(&setting_key(name), if enabled { "true" } else { "false" })
+}
@@ -0,0 +1,65 @@
+/// Mask profanity in transcripts before they're saved and pasted ("merda" → "m****").
+/// Word lists are local and per language; a transcript in a language without a list
+/// (or with no language known) is checked against all of them.
+
+const PORTUGUESE: &[&str] = &[
+    "porra", "porras", "caralho", "caralhos", "merda", "merdas", "bosta", "bostas", "foda", "fodas", "foder",
+    "fodido", "fodida", "fodase", "puta", "putas", "puto", "putos", "putaria", "cacete", "buceta", "boceta",
+    "cu", "cuzão", "arrombado", "arrombada", "desgraçado", "desgraçada", "viado", "babaca", "otário", "otária",
+    "piroca",
+];
+
+const ENGLISH: &[&str] = &[
+    "fuck", "fucks", "fucking", "fucked", "fucker", "fuckers", "motherfucker", "motherfuckers", "shit", "shits",
+    "shitty", "bullshit", "bitch", "bitches", "asshole", "assholes", "bastard", "bastards", "cunt", "cunts",
+    "dickhead", "whore", "slut",
+];
+
+const SPANISH: &[&str] = &[
+    "mierda", "mierdas", "puta", "putas", "puto", "joder", "jodido", "jodida", "cabrón", "cabron", "cabrones",
+    "coño", "pendejo", "pendeja", "gilipollas",
+];
+
+/// Built-in list for a language code ("pt", "pt-BR")
+fn words_for(language: &str) -> Option<&'static [&'static str]> {
+    match language.split(['-', '_']).next().unwrap_or("").to_lowercase().as_str() {
+        "pt" => Some(PORTUGUESE),
+        "en" => Some(ENGLISH),
+        "es" => Some(SPANISH),
+        _ => None,
+    }
+}
+
+/// Replace every listed word in `text` with its first letter and asterisks. `extra` are the
+/// user's own words, masked whatever the language.
+pub fn mask(text: &str, language: Option<&str>, extra: &[String]) -> String {
+    let lists: Vec<&[&str]> = match language.and_then(words_for) {
+        Some(list) => vec![list],
+        None => vec![PORTUGUESE, ENGLISH, SPANISH],
+    };
+    let listed = |word: &str| {
+        lists.iter().any(|list| list.contains(&word)) || extra.iter().any(|w| w.trim().to_lowercase() == word)
+    };
+
+    let mut result = String::with_capacity(text.len());
+    let mut word = String::new();
+    for c in text.chars().chain(std::iter::once(' ')) {
+        if c.is_alphabetic() {
+            word.push(c);
+            continue;
+        }
+        if !word.is_empty() {
+            if listed(&word.to_lowercase()) {
+                let mut chars = word.chars();
+                result.extend(chars.next());
+                result.extend(chars.map(|_| '*'));
+            } else {
+                result.push_str(&word);
+            }
+            word.clear();
+        }
+        result.push(c);
+    }
+    result.pop(); // The space chained to flush the last word
+    result
+}
@@ -0,0 +1,129 @@
+use base64::{engine::general_purpose, Engine as _};
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// Upper bound for the proxy's reply to CONNECT (status line + headers)
+const MAX_CONNECT_RESPONSE: usize = 8192;
+
+/// Outbound proxy for OpenAI traffic (HTTP calls and the Realtime WebSocket)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProxySettings {
+    pub url: String, // e.g. "http://proxy.corp:8080"; empty = direct connection
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    /// Comma-separated hosts that skip the proxy ("localhost, .corp.local, *")
+    #[serde(default)]
+    pub bypass: String,
+}
+
+impl ProxySettings {
+    /// Proxy URL with a scheme ("proxy:8080" means http://proxy:8080)
+    fn parsed_url(&self) -> Result<reqwest::Url, String> {
+        let url = self.url.trim();
+        let url = if url.contains("://") { url.to_string() } else { format!("http://{}", url) };
+        reqwest::Url::parse(&url).map_err(|e| format!("Invalid proxy URL '{}': {}", self.url, e))
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let url = self.parsed_url()?;
+        if url.host_str().is_none() {
+            return Err(format!("Proxy URL '{}' has no host", self.url));
+        }
+        Ok(())
+    }
+
+    fn credentials(&self) -> Option<(&str, &str)> {
+        let username = self.username.as_deref().filter(|u| !u.is_empty())?;
+        Some((username, self.password.as_deref().unwrap_or("")))
+    }
+
+    /// Whether `host` is on the bypass list. Entries match the host and its subdomains;
+    /// a leading "." or "*." is optional and "*" bypasses everything.
+    pub fn bypasses(&self, host: &str) -> bool {
+        let host = host.to_lowercase();
+        self.bypass
+            .split(',')
+            .map(|entry| entry.trim().to_lowercase())
+            .filter(|entry| !entry.is_empty())
+            .any(|entry| {
+                if entry == "*" {
+                    return true;
+                }
+                let domain = entry.trim_start_matches("*.").trim_start_matches('.');
+                host == domain || host.ends_with(&format!(".{}", domain))
+            })
+    }
+
+    pub fn to_reqwest(&self) -> Result<reqwest::Proxy, String> {
+        let mut proxy = reqwest::Proxy::all(self.parsed_url()?).map_err(|e| format!("Invalid proxy: {}", e))?;
+        if let Some((username, password)) = self.credentials() {
+            proxy = proxy.basic_auth(username, password);
+        }
+        Ok(proxy.no_proxy(reqwest::NoProxy::from_string(&self.bypass)))
+    }
+
+    /// Open a TCP tunnel to `host:port` through the proxy (HTTP CONNECT), for the WebSocket
+    /// connection, which doesn't go through reqwest
+    pub async fn tunnel(&self, host: &str, port: u16) -> Result<TcpStream, String> {
+        let url = self.parsed_url()?;
+        let proxy_host = url.host_str().ok_or("Proxy URL has no host")?;
+        let proxy_port = url.port_or_known_default().unwrap_or(8080);
+
+        let mut stream = TcpStream::connect((proxy_host, proxy_port))
+            .await
+            .map_err(|e| format!("Failed to connect to proxy {}:{}: {}", proxy_host, proxy_port, e))?;
+
+        let mut request = format!("CONNECT {host}:{port} HTTP/1.1\r\nHost: {host}:{port}\r\n");
+        if let Some((username, password)) = self.credentials() {
+            let token = general_purpose::STANDARD.encode(format!("{}:{}", username, password));
+            request.push_str(&format!("Proxy-Authorization: Basic {}\r\n", token));
+        }
+        request.push_str("\r\n");
+        stream
+            .write_all(request.as_bytes())
+            .await
+            .map_err(|e| format!("Failed to send CONNECT: {}", e))?;
+
+        // Read byte by byte so nothing after the headers is consumed from the tunnel
+        let mut response = Vec::new();
+        let mut byte = [0u8; 1];
+        while !response.ends_with(b"\r\n\r\n") {
+            if response.len() >= MAX_CONNECT_RESPONSE {
+                return Err("Proxy sent an oversized CONNECT response".to_string());
+            }
+            let read = stream
+                .read(&mut byte)
+                .await
+                .map_err(|e| format!("Failed to read CONNECT response: {}", e))?;
+            if read == 0 {
+                return Err("Proxy closed the connection during CONNECT".to_string());
+            }
+            response.push(byte[0]);
+        }
+
+        let response = String::from_utf8_lossy(&response);
+        let status_line = response.lines().next().unwrap_or_default();
+        if status_line.split_whitespace().nth(1) != Some("200") {
+            return Err(format!("Proxy refused CONNECT: {}", status_line));
+        }
+        Ok(stream)
+    }
+}
+
+/// HTTP client routed through `proxy` (direct when None or when the proxy settings are invalid)
+pub fn http_client(proxy: Option<&ProxySettings>) -> reqwest::Client {
+    let mut builder = reqwest::Client::builder();
+    if let Some(proxy) = proxy {
+        match proxy.to_reqwest() {
+            Ok(proxy) => builder = builder.proxy(proxy),
+            Err(e) => eprintln!("⚠️ {} (connecting directly)", e),
+        }
+    }
+    builder.build().unwrap_or_else(|e| {
+        eprintln!("⚠️ Failed to build HTTP client: {} (using defaults)", e);
+        reqwest::Client::new()
+    })
+}
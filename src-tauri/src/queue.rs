@@ -1,8 +1,13 @@
-use std::path::PathBuf;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 use std::net::TcpStream;
-use std::time::Duration;
+use std::time::{Duration, SystemTime};
 
-pub const MAX_QUEUE_SIZE: i64 = 3;
+/// Defaults for the `queue_max_items` / `queue_max_disk_mb` settings
+pub const DEFAULT_MAX_QUEUE_SIZE: i64 = 3;
+pub const DEFAULT_MAX_DISK_MB: u64 = 200;
+/// Files younger than this may be about to be enqueued, so cleanup leaves them alone
+const ORPHAN_GRACE: Duration = Duration::from_secs(60);
 
 /// Save raw PCM f32 audio to a WAV file in the queue directory
 pub fn save_audio_to_wav(audio: Vec<f32>, dir: &PathBuf) -> Result<PathBuf, String> {
@@ -123,3 +128,62 @@ pub fn delete_wav_file(path: &str) {
         println!("🗑️ Deleted queue WAV: {}", path);
     }
 }
+
+/// Overwrite a WAV file with zeros before deleting it, for recordings that must not be recoverable
+pub fn scrub_wav_file(path: &str) {
+    if let Ok(len) = std::fs::metadata(path).map(|m| m.len()) {
+        if let Err(e) = std::fs::write(path, vec![0u8; len as usize]) {
+            eprintln!("⚠️ Failed to overwrite queue WAV {}: {}", path, e);
+        }
+    }
+    delete_wav_file(path);
+}
+
+/// Delete queue WAVs that no pending item points at (left behind when a row was removed
+/// abnormally), then remove the oldest remaining ones until the directory fits in `max_bytes`.
+/// Returns the paths removed for the size limit, so the caller can drop their queue rows.
+pub fn cleanup_dir(dir: &Path, referenced: &[String], max_bytes: u64) -> Vec<String> {
+    let referenced: HashSet<&str> = referenced
+        .iter()
+        .filter_map(|path| Path::new(path).file_name().and_then(|n| n.to_str()))
+        .collect();
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            eprintln!("⚠️ Failed to read queue directory: {}", e);
+            return Vec::new();
+        }
+    };
+
+    let mut kept = Vec::new(); // (modified, size, path)
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !name.starts_with("queue_") || !name.ends_with(".wav") {
+            continue;
+        }
+        let Ok(metadata) = entry.metadata() else { continue };
+        let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+        let path = entry.path().to_string_lossy().to_string();
+
+        if referenced.contains(name.as_str()) {
+            kept.push((modified, metadata.len(), path));
+        } else if modified.elapsed().unwrap_or_default() >= ORPHAN_GRACE {
+            println!("🧹 Removing orphaned queue WAV: {}", name);
+            delete_wav_file(&path);
+        }
+    }
+
+    let mut total: u64 = kept.iter().map(|(_, size, _)| size).sum();
+    kept.sort_by_key(|(modified, _, _)| *modified);
+    let mut evicted = Vec::new();
+    for (_, size, path) in kept {
+        if total <= max_bytes {
+            break;
+        }
+        println!("🧹 Queue audio over {} MB, dropping oldest: {}", max_bytes / (1024 * 1024), path);
+        delete_wav_file(&path);
+        total -= size;
+        evicted.push(path);
+    }
+    evicted
+}
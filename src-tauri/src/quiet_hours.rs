@@ -0,0 +1,28 @@
+use chrono::NaiveTime;
+use windows::Win32::UI::Shell::{
+    SHQueryUserNotificationState, QUNS_BUSY, QUNS_PRESENTATION_MODE, QUNS_QUIET_TIME, QUNS_RUNNING_D3D_FULL_SCREEN,
+};
+
+/// Whether `now` falls in the [start, end) window; windows crossing midnight (22:00-07:00) wrap around
+pub fn in_window(now: NaiveTime, start: NaiveTime, end: NaiveTime) -> bool {
+    if start <= end {
+        now >= start && now < end
+    } else {
+        now >= start || now < end
+    }
+}
+
+/// Whether Windows itself is suppressing notifications: Focus Assist / quiet time,
+/// presentation mode or a full-screen app
+pub fn system_do_not_disturb() -> bool {
+    match unsafe { SHQueryUserNotificationState() } {
+        Ok(state) => matches!(
+            state,
+            QUNS_BUSY | QUNS_RUNNING_D3D_FULL_SCREEN | QUNS_PRESENTATION_MODE | QUNS_QUIET_TIME
+        ),
+        Err(e) => {
+            eprintln!("⚠️ Failed to query notification state: {}", e);
+            false
+        }
+    }
+}
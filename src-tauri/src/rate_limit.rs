@@ -0,0 +1,126 @@
+use reqwest::header::HeaderMap;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Wait used after a 429 that carries no retry hint
+const DEFAULT_BACKOFF: Duration = Duration::from_secs(2);
+/// Never hold requests longer than this, whatever the headers say
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RateLimitEvent {
+    pub endpoint: String,
+    pub retry_after_ms: u64,
+    /// true when a request was rejected (429), false when the quota just ran out
+    pub rejected: bool,
+}
+
+/// Holds back requests to an endpoint after OpenAI says its quota is spent (429 or
+/// `x-ratelimit-remaining-*: 0`), so rapid-fire dictations wait instead of failing one after another
+pub struct RateLimiter {
+    blocked_until: Mutex<HashMap<&'static str, Instant>>,
+    listener: Mutex<Option<Box<dyn Fn(RateLimitEvent) + Send>>>,
+}
+
+impl RateLimiter {
+    pub fn new() -> Self {
+        Self { blocked_until: Mutex::new(HashMap::new()), listener: Mutex::new(None) }
+    }
+
+    /// Called whenever requests to an endpoint start being delayed
+    pub fn set_listener(&self, listener: impl Fn(RateLimitEvent) + Send + 'static) {
+        *self.listener.lock().unwrap() = Some(Box::new(listener));
+    }
+
+    /// Sleep until `endpoint` may be called again
+    pub async fn wait(&self, endpoint: &'static str) {
+        let until = self.blocked_until.lock().unwrap().get(endpoint).copied();
+        if let Some(until) = until {
+            let remaining = until.saturating_duration_since(Instant::now());
+            if !remaining.is_zero() {
+                println!("⏳ Rate limited, delaying {} request by {} ms", endpoint, remaining.as_millis());
+                tokio::time::sleep(remaining).await;
+            }
+        }
+    }
+
+    /// Read the rate-limit headers of a response and block the endpoint if the quota is spent
+    pub fn observe(&self, endpoint: &'static str, response: &reqwest::Response) {
+        let headers = response.headers();
+        let get = |name: &str| header(headers, name);
+        let rejected = response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS;
+
+        let delay = if rejected {
+            get("retry-after-ms")
+                .and_then(|v| v.parse::<u64>().ok())
+                .map(Duration::from_millis)
+                .or_else(|| get("retry-after").and_then(|v| v.parse::<f64>().ok()).map(Duration::from_secs_f64))
+                .or_else(|| longest_reset(headers))
+                .unwrap_or(DEFAULT_BACKOFF)
+        } else {
+            let exhausted = ["x-ratelimit-remaining-requests", "x-ratelimit-remaining-tokens"]
+                .iter()
+                .any(|name| get(name) == Some("0"));
+            match exhausted.then(|| longest_reset(headers)).flatten() {
+                Some(delay) => delay,
+                None => return,
+            }
+        };
+        let delay = delay.min(MAX_BACKOFF);
+
+        self.blocked_until.lock().unwrap().insert(endpoint, Instant::now() + delay);
+        println!("🚦 {} rate limit hit, holding requests for {} ms", endpoint, delay.as_millis());
+
+        if let Some(listener) = self.listener.lock().unwrap().as_ref() {
+            listener(RateLimitEvent {
+                endpoint: endpoint.to_string(),
+                retry_after_ms: delay.as_millis() as u64,
+                rejected,
+            });
+        }
+    }
+}
+
+fn header<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name).and_then(|v| v.to_str().ok())
+}
+
+/// Longest of the request and token quota resets
+fn longest_reset(headers: &HeaderMap) -> Option<Duration> {
+    ["x-ratelimit-reset-requests", "x-ratelimit-reset-tokens"]
+        .iter()
+        .filter_map(|name| header(headers, name).and_then(parse_reset))
+        .max()
+}
+
+/// Parse OpenAI's reset durations: "20ms", "1.5s", "6m0s", "1h2m3s"
+fn parse_reset(value: &str) -> Option<Duration> {
+    let mut total = 0.0f64;
+    let mut number = String::new();
+    let mut chars = value.trim().chars().peekable();
+    while let Some(c) = chars.next() {
+        if c.is_ascii_digit() || c == '.' {
+            number.push(c);
+            continue;
+        }
+        let amount: f64 = number.parse().ok()?;
+        number.clear();
+        let seconds = match c {
+            'h' => amount * 3600.0,
+            'm' if chars.peek() == Some(&'s') => {
+                chars.next();
+                amount / 1000.0
+            }
+            'm' => amount * 60.0,
+            's' => amount,
+            _ => return None,
+        };
+        total += seconds;
+    }
+    if !number.is_empty() {
+        return None; // Trailing number without a unit
+    }
+    Some(Duration::from_secs_f64(total))
+}
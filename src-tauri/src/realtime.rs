@@ -1,12 +1,21 @@
-use tokio_tungstenite::{connect_async, tungstenite::protocol::Message, tungstenite::client::IntoClientRequest};
+use crate::db::ConversationMessage;
+use crate::proxy::ProxySettings;
+use tokio_tungstenite::{client_async_tls, connect_async, tungstenite::protocol::Message, tungstenite::client::IntoClientRequest};
 use futures_util::{SinkExt, StreamExt};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::Mutex;
 use base64::{Engine as _, engine::general_purpose};
 
 const REALTIME_API_URL: &str = "wss://api.openai.com/v1/realtime?model=gpt-4o-realtime-preview-2024-12-17";
+const REALTIME_API_HOST: &str = "api.openai.com";
+/// Model stored with prompts answered natively by the Realtime session
+pub const NATIVE_PROMPT_MODEL: &str = "gpt-4o-realtime";
+/// Longest wait for the next event of a native prompt response
+const RESPONSE_EVENT_TIMEOUT: Duration = Duration::from_secs(30);
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TranscriptionDelta {
@@ -20,13 +29,58 @@ pub struct TranscriptionCompleted {
     pub transcript: String,
 }
 
+/// One turn of a live session. The preview gets it with every delta (`text` is the turn so far)
+/// and once more when the turn completes, corrected; completed turns are stored under the
+/// session's history entry.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranscriptSegment {
+    pub item_id: String, // Realtime conversation item, or "local-N" for whisper.cpp utterances
+    pub seq: u64,        // Order of the event in the session: a higher seq replaces a lower one
+    pub text: String,
+    #[serde(rename = "final")]
+    pub is_final: bool,
+    pub timestamp: i64, // When the event was received (ms)
+}
+
+/// Turns the deltas and completions of one live session into TranscriptSegments
+#[derive(Default)]
+pub struct SegmentTracker {
+    seq: u64,
+    pending: HashMap<String, String>, // Text so far of the turns still being transcribed
+}
+
+impl SegmentTracker {
+    pub fn delta(&mut self, item_id: &str, delta: &str) -> TranscriptSegment {
+        let text = self.pending.entry(item_id.to_string()).or_default();
+        text.push_str(delta);
+        let text = text.clone();
+        self.segment(item_id, text, false)
+    }
+
+    pub fn completed(&mut self, item_id: &str, transcript: &str) -> TranscriptSegment {
+        self.pending.remove(item_id);
+        self.segment(item_id, transcript.trim().to_string(), true)
+    }
+
+    fn segment(&mut self, item_id: &str, text: String, is_final: bool) -> TranscriptSegment {
+        self.seq += 1;
+        TranscriptSegment { item_id: item_id.to_string(), seq: self.seq, text, is_final, timestamp: crate::now_ms() }
+    }
+}
+
 pub struct RealtimeClient {
     api_key: String,
+    proxy: std::sync::Mutex<Option<ProxySettings>>,
 }
 
 impl RealtimeClient {
     pub fn new(api_key: String) -> Self {
-        Self { api_key }
+        Self { api_key, proxy: std::sync::Mutex::new(None) }
+    }
+
+    /// Tunnel future connections through `proxy` (None = direct)
+    pub fn set_proxy(&self, proxy: Option<ProxySettings>) {
+        *self.proxy.lock().unwrap() = proxy;
     }
 
     pub async fn connect(&self) -> Result<RealtimeSession, String> {
@@ -51,9 +105,19 @@ impl RealtimeClient {
                 .map_err(|e| format!("Failed to parse beta header: {}", e))?
         );
 
-        let (ws_stream, _) = connect_async(request)
-            .await
-            .map_err(|e| format!("Failed to connect: {}", e))?;
+        let proxy = self.proxy.lock().unwrap().clone().filter(|p| !p.bypasses(REALTIME_API_HOST));
+        let (ws_stream, _) = match proxy {
+            Some(proxy) => {
+                println!("🌐 Connecting through proxy {}", proxy.url);
+                let tunnel = proxy.tunnel(REALTIME_API_HOST, 443).await?;
+                client_async_tls(request, tunnel)
+                    .await
+                    .map_err(|e| format!("Failed to connect: {}", e))?
+            }
+            None => connect_async(request)
+                .await
+                .map_err(|e| format!("Failed to connect: {}", e))?,
+        };
 
         println!("✅ Connected to Realtime API");
 
@@ -62,6 +126,8 @@ impl RealtimeClient {
         Ok(RealtimeSession {
             write: Arc::new(Mutex::new(write)),
             read: Arc::new(Mutex::new(read)),
+            items: std::sync::Mutex::new(Vec::new()),
+            stale_items: std::sync::Mutex::new(HashSet::new()),
         })
     }
 }
@@ -69,13 +135,69 @@ impl RealtimeClient {
 pub struct RealtimeSession {
     write: Arc<Mutex<futures_util::stream::SplitSink<tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>, Message>>>,
     read: Arc<Mutex<futures_util::stream::SplitStream<tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>>>>,
+    items: std::sync::Mutex<Vec<String>>, // Conversation items created so far (deleted by `reset`)
+    stale_items: std::sync::Mutex<HashSet<String>>, // Items of earlier recordings; their late events are ignored
 }
 
 impl RealtimeSession {
-    /// Configure the session for transcription-only mode
-    pub async fn configure_transcription(&self) -> Result<(), String> {
-        println!("⚙️ Configuring transcription session...");
+    /// Make a session kept from an earlier recording ready for a new one: drop the audio still
+    /// buffered and the conversation items of earlier recordings and prompts
+    pub async fn reset(&self) -> Result<(), String> {
+        let items = std::mem::take(&mut *self.items.lock().unwrap());
+        let mut write = self.write.lock().await;
+        write.send(Message::Text(json!({"type": "input_audio_buffer.clear"}).to_string()))
+            .await
+            .map_err(|e| format!("Failed to clear audio buffer: {}", e))?;
+        for item_id in &items {
+            let message = json!({
+                "type": "conversation.item.delete",
+                "item_id": item_id
+            });
+            write.send(Message::Text(message.to_string()))
+                .await
+                .map_err(|e| format!("Failed to delete conversation item: {}", e))?;
+        }
+        println!("♻️ Realtime session reset ({} items deleted)", items.len());
+        self.stale_items.lock().unwrap().extend(items);
+        Ok(())
+    }
+
+    /// Let the server drop a turn that's been transcribed: its conversation item is deleted.
+    /// Keeps long sessions from accumulating audio server-side. The input buffer is left alone:
+    /// clearing it could drop the start of the next utterance before VAD reports it.
+    pub async fn release_turn(&self, item_id: &str) -> Result<(), String> {
+        let mut write = self.write.lock().await;
+        let message = json!({
+            "type": "conversation.item.delete",
+            "item_id": item_id
+        });
+        write.send(Message::Text(message.to_string()))
+            .await
+            .map_err(|e| format!("Failed to delete conversation item: {}", e))?;
+        self.items.lock().unwrap().retain(|id| id != item_id);
+        Ok(())
+    }
+
+    /// Remember the items the server adds to the conversation, for `reset`
+    fn track_item(&self, event: &serde_json::Value) {
+        if event["type"] == "conversation.item.created" {
+            if let Some(id) = event["item"]["id"].as_str() {
+                self.items.lock().unwrap().push(id.to_string());
+            }
+        }
+    }
+
+    fn is_stale(&self, event: &serde_json::Value) -> bool {
+        event["item_id"].as_str().is_some_and(|id| self.stale_items.lock().unwrap().contains(id))
+    }
 
+    /// Configure the session for transcription-only mode.
+    /// `noise_reduction` is "near_field" (headset, laptop up close) or "far_field" (distant mic).
+    pub async fn configure_transcription(&self, noise_reduction: Option<&str>) -> Result<(), String> {
+        println!("⚙️ Configuring transcription session (noise reduction: {})...", noise_reduction.unwrap_or("off"));
+
+        // null turns it off, which also resets a reused session configured with it
+        let noise_reduction = noise_reduction.map(|kind| json!({ "type": kind }));
         let config = json!({
             "type": "session.update",
             "session": {
@@ -84,11 +206,13 @@ impl RealtimeSession {
                 "input_audio_transcription": {
                     "model": "whisper-1"
                 },
+                "input_audio_noise_reduction": noise_reduction,
                 "turn_detection": {
                     "type": "server_vad",
                     "threshold": 0.5,
                     "prefix_padding_ms": 300,
-                    "silence_duration_ms": 500
+                    "silence_duration_ms": 500,
+                    "create_response": false // Answers are only requested by native prompt mode
                 }
             }
         });
@@ -146,6 +270,11 @@ impl RealtimeSession {
                 Ok(Message::Text(text)) => {
                     if let Ok(event) = serde_json::from_str::<serde_json::Value>(&text) {
                         let event_type = event["type"].as_str().unwrap_or("");
+                        self.track_item(&event);
+                        if self.is_stale(&event) {
+                            println!("📥 Ignoring {} of an earlier recording", event_type);
+                            continue;
+                        }
 
                         match event_type {
                             "conversation.item.input_audio_transcription.delta" => {
@@ -207,6 +336,88 @@ impl RealtimeSession {
         Ok(())
     }
 
+    /// Answer the speech sent in this session with text (native prompt mode), so the prompt
+    /// isn't transcribed and sent again. `history` is inserted before the speech as earlier
+    /// turns; the response is streamed to `on_delta` and returned complete.
+    /// Call once the event listener has stopped, or it would take the response events.
+    pub async fn respond(&self, instructions: &str, history: &[ConversationMessage], on_delta: &mut (dyn FnMut(&str) + Send)) -> Result<String, String> {
+        println!("🤖 Asking the Realtime session to answer (history: {} messages)...", history.len());
+        {
+            let mut write = self.write.lock().await;
+            for (i, message) in history.iter().enumerate() {
+                // "root" puts the first one at the start of the conversation, the rest follow it
+                let previous = if i == 0 { "root".to_string() } else { format!("history_{}", i - 1) };
+                let content_type = if message.role == "assistant" { "text" } else { "input_text" };
+                let item = json!({
+                    "type": "conversation.item.create",
+                    "previous_item_id": previous,
+                    "item": {
+                        "id": format!("history_{}", i),
+                        "type": "message",
+                        "role": message.role,
+                        "content": [{"type": content_type, "text": message.content}]
+                    }
+                });
+                write.send(Message::Text(item.to_string()))
+                    .await
+                    .map_err(|e| format!("Failed to send history: {}", e))?;
+            }
+            let request = json!({
+                "type": "response.create",
+                "response": {
+                    "modalities": ["text"],
+                    "instructions": instructions
+                }
+            });
+            write.send(Message::Text(request.to_string()))
+                .await
+                .map_err(|e| format!("Failed to request response: {}", e))?;
+        }
+
+        let mut read = self.read.lock().await;
+        let mut text = String::new();
+        loop {
+            let msg = tokio::time::timeout(RESPONSE_EVENT_TIMEOUT, read.next())
+                .await
+                .map_err(|_| format!("Realtime response timed out after {}s", RESPONSE_EVENT_TIMEOUT.as_secs()))?;
+            match msg {
+                Some(Ok(Message::Text(raw))) => {
+                    let Ok(event) = serde_json::from_str::<serde_json::Value>(&raw) else { continue };
+                    self.track_item(&event);
+                    match event["type"].as_str().unwrap_or("") {
+                        "response.text.delta" => {
+                            let delta = event["delta"].as_str().unwrap_or("");
+                            text.push_str(delta);
+                            on_delta(delta);
+                        }
+                        "response.done" => {
+                            let status = event["response"]["status"].as_str().unwrap_or("");
+                            if status != "completed" {
+                                return Err(format!("Realtime response {}: {}", status, event["response"]["status_details"]));
+                            }
+                            break;
+                        }
+                        "error" => {
+                            let error_msg = event["error"]["message"].as_str().unwrap_or("Unknown error");
+                            return Err(format!("API error: {}", error_msg));
+                        }
+                        _ => {}
+                    }
+                }
+                Some(Ok(Message::Close(_))) | None => return Err("WebSocket closed before the response finished".to_string()),
+                Some(Err(e)) => return Err(format!("WebSocket error: {}", e)),
+                Some(Ok(_)) => {}
+            }
+        }
+
+        let text = text.trim().to_string();
+        if text.is_empty() {
+            return Err("Realtime response was empty".to_string());
+        }
+        println!("✅ Realtime response: {}", text);
+        Ok(text)
+    }
+
     /// Close the WebSocket connection
     pub async fn close(&self) -> Result<(), String> {
         let mut write = self.write.lock().await;
@@ -0,0 +1,158 @@
+use crate::plain_text::{is_rule, parse_link};
+
+/// Render model output (markdown) as an HTML fragment for the clipboard, so Word/Gmail keep
+/// headings, lists, bold/italic, code and links. Covers the subset GPT responses use; anything
+/// unrecognized is kept as escaped text.
+pub fn to_html(markdown: &str) -> String {
+    let mut html = String::new();
+    let mut paragraph: Vec<String> = Vec::new();
+    let mut list: Option<&str> = None; // "ul" or "ol" while inside a list
+    let mut code: Option<String> = None; // Fenced block being collected
+
+    for line in markdown.lines() {
+        let trimmed = line.trim();
+
+        if let Some(block) = code.as_mut() {
+            if trimmed.starts_with("```") {
+                html.push_str(&format!("<pre><code>{}</code></pre>", escape(block.trim_end_matches('\n'))));
+                code = None;
+            } else {
+                block.push_str(line);
+                block.push('\n');
+            }
+            continue;
+        }
+
+        let item = list_item(trimmed);
+        if trimmed.is_empty() || item.is_some() || trimmed.starts_with("```") || heading(trimmed).is_some() || trimmed.starts_with('>') {
+            flush_paragraph(&mut html, &mut paragraph);
+        }
+        if item.map(|(tag, _)| tag) != list && (item.is_some() || !trimmed.is_empty()) {
+            if let Some(tag) = list.take() {
+                html.push_str(&format!("</{}>", tag));
+            }
+        }
+
+        if trimmed.is_empty() {
+            continue;
+        }
+        if trimmed.starts_with("```") {
+            code = Some(String::new());
+        } else if let Some((tag, content)) = item {
+            if list.is_none() {
+                html.push_str(&format!("<{}>", tag));
+                list = Some(tag);
+            }
+            html.push_str(&format!("<li>{}</li>", inline(content)));
+        } else if let Some((level, content)) = heading(trimmed) {
+            html.push_str(&format!("<h{0}>{1}</h{0}>", level, inline(content)));
+        } else if let Some(content) = trimmed.strip_prefix('>') {
+            html.push_str(&format!("<blockquote>{}</blockquote>", inline(content.trim_start())));
+        } else if is_rule(trimmed) {
+            html.push_str("<hr>");
+        } else {
+            paragraph.push(inline(trimmed));
+        }
+    }
+
+    flush_paragraph(&mut html, &mut paragraph);
+    if let Some(tag) = list {
+        html.push_str(&format!("</{}>", tag));
+    }
+    if let Some(block) = code {
+        html.push_str(&format!("<pre><code>{}</code></pre>", escape(block.trim_end_matches('\n'))));
+    }
+    html
+}
+
+fn flush_paragraph(html: &mut String, paragraph: &mut Vec<String>) {
+    if !paragraph.is_empty() {
+        html.push_str(&format!("<p>{}</p>", paragraph.join("<br>")));
+        paragraph.clear();
+    }
+}
+
+/// "- item" / "* item" / "+ item" (ul) or "1. item" / "1) item" (ol)
+fn list_item(line: &str) -> Option<(&'static str, &str)> {
+    for bullet in ["- ", "* ", "+ "] {
+        if let Some(rest) = line.strip_prefix(bullet) {
+            if !is_rule(line) {
+                return Some(("ul", rest.trim_start()));
+            }
+        }
+    }
+    let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+    if digits > 0 {
+        let rest = &line[digits..];
+        if let Some(rest) = rest.strip_prefix(". ").or_else(|| rest.strip_prefix(") ")) {
+            return Some(("ol", rest.trim_start()));
+        }
+    }
+    None
+}
+
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let hashes = line.chars().take_while(|c| *c == '#').count();
+    if (1..=6).contains(&hashes) && line[hashes..].starts_with(' ') {
+        return Some((hashes, line[hashes..].trim()));
+    }
+    None
+}
+
+/// Bold, italic, inline code and links within one line
+fn inline(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '`' => {
+                if let Some(end) = chars[i + 1..].iter().position(|&ch| ch == '`').map(|p| i + 1 + p) {
+                    let code: String = chars[i + 1..end].iter().collect();
+                    out.push_str(&format!("<code>{}</code>", escape(&code)));
+                    i = end + 1;
+                    continue;
+                }
+            }
+            '*' | '_' => {
+                let marker = if chars.get(i + 1) == Some(&c) { format!("{0}{0}", c) } else { c.to_string() };
+                let start = i + marker.len();
+                // Needs text right after the opening marker ("2 * 3" and snake_case stay as they are)
+                let opens = chars.get(start).is_some_and(|ch| !ch.is_whitespace())
+                    && (c == '*' || i == 0 || !chars[i - 1].is_alphanumeric());
+                if opens {
+                    if let Some(end) = find(&chars, start, &marker) {
+                        let inner: String = chars[start..end].iter().collect();
+                        let tag = if marker.len() == 2 { "strong" } else { "em" };
+                        out.push_str(&format!("<{0}>{1}</{0}>", tag, inline(&inner)));
+                        i = end + marker.len();
+                        continue;
+                    }
+                }
+            }
+            '[' => {
+                if let Some((label, url, end)) = parse_link(&chars, i) {
+                    out.push_str(&format!("<a href=\"{}\">{}</a>", escape(&url), inline(&label)));
+                    i = end;
+                    continue;
+                }
+            }
+            _ => {}
+        }
+        out.push_str(&escape(&c.to_string()));
+        i += 1;
+    }
+    out
+}
+
+/// Index of the closing `marker` after `from` (at least one char in between, no space before it)
+fn find(chars: &[char], from: usize, marker: &str) -> Option<usize> {
+    let marker: Vec<char> = marker.chars().collect();
+    let last = chars.len().checked_sub(marker.len())?;
+    (from + 1..=last).find(|&j| chars[j..j + marker.len()] == marker[..] && !chars[j - 1].is_whitespace())
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
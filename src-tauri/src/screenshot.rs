@@ -0,0 +1,128 @@
+use base64::{engine::general_purpose, Engine as _};
+use windows::Win32::Foundation::{HWND, POINT, RECT};
+use windows::Win32::Graphics::Dwm::{DwmGetWindowAttribute, DWMWA_EXTENDED_FRAME_BOUNDS};
+use windows::Win32::Graphics::Gdi::*;
+use windows::Win32::UI::WindowsAndMessaging::{GetCursorPos, GetForegroundWindow, GetWindowRect};
+
+/// What to capture
+pub enum CaptureArea {
+    /// The monitor under the mouse cursor
+    Screen,
+    /// The foreground window (without its drop shadow)
+    Window,
+    /// Virtual-screen coordinates in physical pixels
+    Region { x: i32, y: i32, width: i32, height: i32 },
+}
+
+impl CaptureArea {
+    pub fn parse(mode: &str, x: Option<i32>, y: Option<i32>, width: Option<i32>, height: Option<i32>) -> Result<Self, String> {
+        match mode {
+            "full" | "screen" => Ok(Self::Screen),
+            "window" => Ok(Self::Window),
+            "region" => match (x, y, width, height) {
+                (Some(x), Some(y), Some(width), Some(height)) => Ok(Self::Region { x, y, width, height }),
+                _ => Err("Region capture needs x, y, width and height".to_string()),
+            },
+            _ => Err(format!("Unknown capture mode '{}'", mode)),
+        }
+    }
+}
+
+/// Capture the area and return it as a base64 PNG (the format send_prompt's `image_data` expects)
+pub fn capture(area: CaptureArea) -> Result<String, String> {
+    let rect = unsafe { area_rect(area)? };
+    let width = rect.right - rect.left;
+    let height = rect.bottom - rect.top;
+    if width <= 0 || height <= 0 {
+        return Err("Capture area is empty".to_string());
+    }
+
+    let bgra = unsafe { grab(rect.left, rect.top, width, height)? };
+    let rgb: Vec<u8> = bgra.chunks_exact(4).flat_map(|px| [px[2], px[1], px[0]]).collect();
+
+    let mut png_data = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut png_data, width as u32, height as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().map_err(|e| format!("PNG encoding failed: {}", e))?;
+        writer.write_image_data(&rgb).map_err(|e| format!("PNG encoding failed: {}", e))?;
+    }
+
+    println!("📸 Captured {}x{} screenshot ({} KB)", width, height, png_data.len() / 1024);
+    Ok(general_purpose::STANDARD.encode(png_data))
+}
+
+unsafe fn area_rect(area: CaptureArea) -> Result<RECT, String> {
+    match area {
+        CaptureArea::Screen => {
+            let mut cursor = POINT::default();
+            GetCursorPos(&mut cursor).map_err(|e| format!("GetCursorPos failed: {}", e))?;
+            let monitor = MonitorFromPoint(cursor, MONITOR_DEFAULTTONEAREST);
+            let mut info = MONITORINFO { cbSize: std::mem::size_of::<MONITORINFO>() as u32, ..Default::default() };
+            if !GetMonitorInfoW(monitor, &mut info).as_bool() {
+                return Err("GetMonitorInfoW failed".to_string());
+            }
+            Ok(info.rcMonitor)
+        }
+        CaptureArea::Window => {
+            let hwnd = GetForegroundWindow();
+            if hwnd.0.is_null() {
+                return Err("No foreground window".to_string());
+            }
+            let mut rect = RECT::default();
+            // Extended frame bounds exclude the invisible resize border/shadow that GetWindowRect includes
+            let bounds = DwmGetWindowAttribute(
+                hwnd,
+                DWMWA_EXTENDED_FRAME_BOUNDS,
+                &mut rect as *mut RECT as *mut _,
+                std::mem::size_of::<RECT>() as u32,
+            );
+            if bounds.is_err() {
+                GetWindowRect(hwnd, &mut rect).map_err(|e| format!("GetWindowRect failed: {}", e))?;
+            }
+            Ok(rect)
+        }
+        CaptureArea::Region { x, y, width, height } => Ok(RECT { left: x, top: y, right: x + width, bottom: y + height }),
+    }
+}
+
+/// Copy a screen rectangle into a top-down BGRA buffer
+unsafe fn grab(x: i32, y: i32, width: i32, height: i32) -> Result<Vec<u8>, String> {
+    let screen = GetDC(HWND::default());
+    let memory = CreateCompatibleDC(screen);
+    let bitmap = CreateCompatibleBitmap(screen, width, height);
+    let previous = SelectObject(memory, bitmap);
+
+    let blit = BitBlt(memory, 0, 0, width, height, screen, x, y, SRCCOPY | CAPTUREBLT);
+
+    let mut info = BITMAPINFO {
+        bmiHeader: BITMAPINFOHEADER {
+            biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
+            biWidth: width,
+            biHeight: -height, // Negative = top-down rows
+            biPlanes: 1,
+            biBitCount: 32,
+            biCompression: BI_RGB.0,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut pixels = vec![0u8; (width * height * 4) as usize];
+    let rows = if blit.is_ok() {
+        GetDIBits(memory, bitmap, 0, height as u32, Some(pixels.as_mut_ptr() as *mut _), &mut info, DIB_RGB_COLORS)
+    } else {
+        0
+    };
+
+    SelectObject(memory, previous);
+    let _ = DeleteObject(bitmap);
+    let _ = DeleteDC(memory);
+    ReleaseDC(HWND::default(), screen);
+
+    blit.map_err(|e| format!("BitBlt failed: {}", e))?;
+    if rows == 0 {
+        return Err("GetDIBits failed".to_string());
+    }
+    Ok(pixels)
+}
@@ -0,0 +1,49 @@
+use serde::{Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+/// Random (v4) UUID identifying one recording or prompt session. It is Copy so every task
+/// and callback spawned for the session can carry it into its log lines, events, the
+/// history row and queue items, which is what ties interleaved sessions apart when debugging.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SessionId(u128);
+
+impl SessionId {
+    pub fn new() -> Self {
+        let bits: u128 = rand::random();
+        // Version 4, RFC 4122 variant
+        Self((bits & !(0xf << 76) & !(0x3 << 62)) | (0x4 << 76) | (0x2 << 62))
+    }
+
+    /// First block of the UUID, enough to tell sessions apart in the log
+    pub fn short(&self) -> String {
+        format!("{:08x}", (self.0 >> 96) as u32)
+    }
+}
+
+impl fmt::Display for SessionId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let hex = format!("{:032x}", self.0);
+        write!(f, "{}-{}-{}-{}-{}", &hex[0..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..32])
+    }
+}
+
+impl FromStr for SessionId {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex: String = s.chars().filter(|c| *c != '-').collect();
+        if hex.len() != 32 {
+            return Err(format!("Invalid session id '{}'", s));
+        }
+        u128::from_str_radix(&hex, 16)
+            .map(Self)
+            .map_err(|_| format!("Invalid session id '{}'", s))
+    }
+}
+
+impl Serialize for SessionId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
@@ -0,0 +1,259 @@
+use crate::db::Database;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tauri::AppHandle;
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut};
+
+/// Everything a global hotkey can trigger. Each action's combo is stored in the
+/// `hotkey_<action>` setting, falling back to its default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HotkeyAction {
+    Cancel,
+    Transcribe,
+    PromptSelectedModel,
+    PromptGpt41,
+    PasteLast,
+    PromptInput,
+    ToggleTts,
+    TtsAction,
+    CycleModel,
+    ReadClipboard,
+    ReadSelection,
+    AppendToNote,
+    ScreenshotPrompt,
+    AskAboutScreen,
+    EditSelection,
+    FixGrammar,
+    ToggleIncognito,
+    JsonOutput,
+    TtsSpeedUp,
+    TtsSpeedDown,
+    AssistantMode,
+    AbortRequest,
+    EmailMode,
+    MuteMic,
+}
+
+impl HotkeyAction {
+    /// Cancel first: when loading, earlier actions win a conflicting combo
+    pub const ALL: [HotkeyAction; 24] = [
+        HotkeyAction::Cancel,
+        HotkeyAction::Transcribe,
+        HotkeyAction::PromptSelectedModel,
+        HotkeyAction::PromptGpt41,
+        HotkeyAction::PasteLast,
+        HotkeyAction::PromptInput,
+        HotkeyAction::ToggleTts,
+        HotkeyAction::TtsAction,
+        HotkeyAction::CycleModel,
+        HotkeyAction::ReadClipboard,
+        HotkeyAction::ReadSelection,
+        HotkeyAction::AppendToNote,
+        HotkeyAction::ScreenshotPrompt,
+        HotkeyAction::AskAboutScreen,
+        HotkeyAction::EditSelection,
+        HotkeyAction::FixGrammar,
+        HotkeyAction::ToggleIncognito,
+        HotkeyAction::JsonOutput,
+        HotkeyAction::TtsSpeedUp,
+        HotkeyAction::TtsSpeedDown,
+        HotkeyAction::AssistantMode,
+        HotkeyAction::AbortRequest,
+        HotkeyAction::EmailMode,
+        HotkeyAction::MuteMic,
+    ];
+
+    pub fn default_hotkey(self) -> &'static str {
+        match self {
+            HotkeyAction::Cancel => "Ctrl+Alt+Escape",
+            HotkeyAction::Transcribe => "Ctrl+Space",
+            HotkeyAction::PromptSelectedModel => "Ctrl+Shift+Space",
+            HotkeyAction::PromptGpt41 => "Ctrl+Alt+Space",
+            HotkeyAction::PasteLast => "Alt+Shift+Z",
+            HotkeyAction::PromptInput => "Ctrl+B",
+            HotkeyAction::ToggleTts => "Ctrl+Alt+S",
+            HotkeyAction::TtsAction => "Alt+Shift+S",
+            HotkeyAction::CycleModel => "Alt+Shift+M",
+            HotkeyAction::ReadClipboard => "Alt+Shift+C",
+            HotkeyAction::ReadSelection => "Alt+Shift+R",
+            HotkeyAction::AppendToNote => "Alt+Shift+N",
+            HotkeyAction::ScreenshotPrompt => "Alt+Shift+P",
+            HotkeyAction::AskAboutScreen => "Alt+Shift+Q",
+            HotkeyAction::EditSelection => "Alt+Shift+E",
+            HotkeyAction::FixGrammar => "Alt+Shift+G",
+            HotkeyAction::ToggleIncognito => "Alt+Shift+I",
+            HotkeyAction::JsonOutput => "Alt+Shift+J",
+            HotkeyAction::TtsSpeedUp => "Alt+Shift+Up",
+            HotkeyAction::TtsSpeedDown => "Alt+Shift+Down",
+            HotkeyAction::AssistantMode => "Alt+Shift+V",
+            HotkeyAction::AbortRequest => "Alt+Shift+X",
+            HotkeyAction::EmailMode => "Alt+Shift+W",
+            HotkeyAction::MuteMic => "Alt+Shift+K",
+        }
+    }
+
+    /// Shown in the settings UI
+    pub fn label(self) -> &'static str {
+        match self {
+            HotkeyAction::Cancel => "Cancelar gravação / parar leitura",
+            HotkeyAction::Transcribe => "Gravar e transcrever",
+            HotkeyAction::PromptSelectedModel => "Gravar prompt (modelo selecionado)",
+            HotkeyAction::PromptGpt41 => "Gravar prompt (GPT-4.1)",
+            HotkeyAction::PasteLast => "Colar última transcrição",
+            HotkeyAction::PromptInput => "Abrir janela de prompt",
+            HotkeyAction::ToggleTts => "Ligar/desligar leitura em voz alta",
+            HotkeyAction::TtsAction => "Ler última mensagem / parar leitura",
+            HotkeyAction::CycleModel => "Trocar modelo durante a gravação",
+            HotkeyAction::ReadClipboard => "Ler área de transferência",
+            HotkeyAction::ReadSelection => "Ler texto selecionado",
+            HotkeyAction::AppendToNote => "Ditar na nota",
+            HotkeyAction::ScreenshotPrompt => "Perguntar sobre captura de tela",
+            HotkeyAction::AskAboutScreen => "Perguntar sobre a janela ativa",
+            HotkeyAction::EditSelection => "Editar texto selecionado por voz",
+            HotkeyAction::FixGrammar => "Corrigir gramática da seleção",
+            HotkeyAction::ToggleIncognito => "Modo anônimo",
+            HotkeyAction::JsonOutput => "Ditar comando JSON (automação)",
+            HotkeyAction::TtsSpeedUp => "Leitura mais rápida",
+            HotkeyAction::TtsSpeedDown => "Leitura mais lenta",
+            HotkeyAction::AssistantMode => "Assistente por voz (conversa contínua)",
+            HotkeyAction::AbortRequest => "Cancelar resposta em andamento",
+            HotkeyAction::EmailMode => "Ditar e-mail",
+            HotkeyAction::MuteMic => "Silenciar/reativar o microfone da sessão ao vivo",
+        }
+    }
+
+    /// For the startup log ("Press <hotkey> to ...")
+    pub fn description(self) -> &'static str {
+        match self {
+            HotkeyAction::Cancel => "cancel recording / stop TTS",
+            HotkeyAction::Transcribe => "start/stop recording",
+            HotkeyAction::PromptSelectedModel => "record a prompt for the selected model",
+            HotkeyAction::PromptGpt41 => "record a prompt for GPT-4.1",
+            HotkeyAction::PasteLast => "paste last transcription",
+            HotkeyAction::PromptInput => "open prompt input window",
+            HotkeyAction::ToggleTts => "toggle TTS",
+            HotkeyAction::TtsAction => "stop TTS / read last message",
+            HotkeyAction::CycleModel => "cycle the prompt model while recording",
+            HotkeyAction::ReadClipboard => "read the clipboard aloud",
+            HotkeyAction::ReadSelection => "read the selected text aloud",
+            HotkeyAction::AppendToNote => "dictate into your note",
+            HotkeyAction::ScreenshotPrompt => "ask about a screenshot",
+            HotkeyAction::AskAboutScreen => "ask out loud about the active window",
+            HotkeyAction::EditSelection => "dictate an edit for the selected text",
+            HotkeyAction::FixGrammar => "fix the grammar of the selected text",
+            HotkeyAction::ToggleIncognito => "toggle incognito mode",
+            HotkeyAction::JsonOutput => "dictate a JSON command for automation",
+            HotkeyAction::TtsSpeedUp => "speak faster",
+            HotkeyAction::TtsSpeedDown => "speak slower",
+            HotkeyAction::AssistantMode => "start/stop the hands-free voice assistant",
+            HotkeyAction::AbortRequest => "cancel the request still waiting on the API",
+            HotkeyAction::EmailMode => "dictate an email",
+            HotkeyAction::MuteMic => "mute/unmute the mic of the live session",
+        }
+    }
+
+    pub fn setting_key(self) -> String {
+        let name = serde_json::to_value(self).ok().and_then(|v| v.as_str().map(str::to_string)).unwrap_or_default();
+        format!("hotkey_{}", name)
+    }
+
+    /// Configured combo, or the default when none is saved
+    pub fn load_hotkey(self, database: &Database) -> String {
+        database
+            .load_setting(&self.setting_key())
+            .ok()
+            .flatten()
+            .filter(|v| !v.trim().is_empty())
+            .unwrap_or_else(|| self.default_hotkey().to_string())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HotkeyBinding {
+    pub action: HotkeyAction,
+    pub label: &'static str,
+    pub hotkey: String,
+    pub default_hotkey: &'static str,
+}
+
+/// Registered global shortcuts and the action each one triggers
+pub struct ShortcutRegistry {
+    bindings: Mutex<Vec<(HotkeyAction, Shortcut, String)>>,
+}
+
+impl ShortcutRegistry {
+    pub fn new() -> Self {
+        Self { bindings: Mutex::new(Vec::new()) }
+    }
+
+    pub fn action_for(&self, shortcut: &Shortcut) -> Option<HotkeyAction> {
+        self.bindings
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|(_, bound, _)| bound == shortcut)
+            .map(|(action, _, _)| *action)
+    }
+
+    pub fn bindings(&self) -> Vec<HotkeyBinding> {
+        let bindings = self.bindings.lock().unwrap();
+        HotkeyAction::ALL
+            .iter()
+            .map(|&action| HotkeyBinding {
+                action,
+                label: action.label(),
+                hotkey: bindings
+                    .iter()
+                    .find(|(bound, _, _)| *bound == action)
+                    .map(|(_, _, hotkey)| hotkey.clone())
+                    .unwrap_or_default(), // Empty = failed to register
+                default_hotkey: action.default_hotkey(),
+            })
+            .collect()
+    }
+
+    /// Point `action` at `hotkey` (e.g. "Ctrl+Alt+Escape"), replacing its previous combo.
+    /// Fails without changing anything if the combo is invalid, taken by another action
+    /// or can't be registered with the OS.
+    pub fn bind(&self, app: &AppHandle, action: HotkeyAction, hotkey: &str) -> Result<(), String> {
+        let shortcut: Shortcut = hotkey.parse().map_err(|e| format!("Atalho inválido '{}': {}", hotkey, e))?;
+
+        let mut bindings = self.bindings.lock().unwrap();
+        if let Some((other, _, _)) = bindings.iter().find(|(bound, s, _)| *s == shortcut && *bound != action) {
+            return Err(format!("O atalho '{}' já está em uso por \"{}\"", hotkey, other.label()));
+        }
+        let current = bindings.iter().position(|(bound, _, _)| *bound == action);
+        if let Some(index) = current {
+            if bindings[index].1 == shortcut {
+                bindings[index].2 = hotkey.to_string();
+                return Ok(());
+            }
+        }
+
+        // Register the new one first so a failure keeps the old hotkey working
+        app.global_shortcut()
+            .register(shortcut)
+            .map_err(|e| format!("Failed to register hotkey '{}': {}", hotkey, e))?;
+        if let Some(index) = current {
+            let (_, old, _) = bindings.remove(index);
+            let _ = app.global_shortcut().unregister(old);
+        }
+        bindings.push((action, shortcut, hotkey.to_string()));
+        Ok(())
+    }
+
+    /// (Re)bind every action from settings; failures are logged and leave that action unbound
+    pub fn load_all(&self, app: &AppHandle, database: &Database) {
+        // Start from scratch so combos swapped between actions don't look like conflicts
+        for (_, old, _) in self.bindings.lock().unwrap().drain(..) {
+            let _ = app.global_shortcut().unregister(old);
+        }
+        for action in HotkeyAction::ALL {
+            let hotkey = action.load_hotkey(database);
+            if let Err(e) = self.bind(app, action, &hotkey) {
+                eprintln!("⚠️ {:?}: {}", action, e);
+            }
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use crate::db::Database;
+use rodio::buffer::SamplesBuffer;
+use serde::{Deserialize, Serialize};
+
+const DEFAULT_VOLUME: u32 = 50;
+const DEFAULT_LATENCY_MS: u32 = 40;
+/// Blocks waiting in the sink before new audio is dropped: past this, playback has fallen
+/// behind the mic (clock drift) and the delay would only grow
+const MAX_QUEUED_BLOCKS: usize = 3;
+
+/// Input monitoring: the mic is played back through the output while recording, so headset
+/// users hear themselves and can keep their voice at a steady level
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SidetoneSettings {
+    pub enabled: bool,
+    pub volume: u32,     // Percent of the captured level, 0-100
+    pub latency_ms: u32, // Audio collected before it's played; lower is closer to live but can crackle
+}
+
+impl SidetoneSettings {
+    pub fn load(database: &Database) -> Self {
+        let number = |key: &str, default: u32| {
+            database.load_setting(key).ok().flatten().and_then(|v| v.parse().ok()).unwrap_or(default)
+        };
+        Self {
+            enabled: database.load_setting("sidetone_enabled").ok().flatten().as_deref() == Some("true"),
+            volume: number("sidetone_volume", DEFAULT_VOLUME).min(100),
+            latency_ms: number("sidetone_latency_ms", DEFAULT_LATENCY_MS).clamp(10, 500),
+        }
+    }
+
+    pub fn save(&self, database: &Database) -> rusqlite::Result<()> {
+        database.save_setting("sidetone_enabled", if self.enabled { "true" } else { "false" })?;
+        database.save_setting("sidetone_volume", &self.volume.min(100).to_string())?;
+        database.save_setting("sidetone_latency_ms", &self.latency_ms.clamp(10, 500).to_string())
+    }
+}
+
+/// Plays captured mono samples on the default output. It owns the output stream, which is
+/// not Send, so it's created on the capture thread that feeds it.
+pub struct Sidetone {
+    _stream: rodio::OutputStream,
+    sink: rodio::Sink,
+    block: Vec<f32>, // Samples collected for the next block
+    latency_ms: u32,
+}
+
+impl Sidetone {
+    /// None when monitoring is off or there's no output device
+    pub fn start(settings: &SidetoneSettings) -> Option<Self> {
+        if !settings.enabled {
+            return None;
+        }
+        let (stream, handle) = match rodio::OutputStream::try_default() {
+            Ok(output) => output,
+            Err(e) => {
+                eprintln!("⚠️ Sidetone unavailable, no output device: {}", e);
+                return None;
+            }
+        };
+        let sink = match rodio::Sink::try_new(&handle) {
+            Ok(sink) => sink,
+            Err(e) => {
+                eprintln!("⚠️ Sidetone unavailable: {}", e);
+                return None;
+            }
+        };
+        sink.set_volume(settings.volume as f32 / 100.0);
+        println!("🎧 Sidetone on ({}%, {}ms)", settings.volume, settings.latency_ms);
+        Some(Self { _stream: stream, sink, block: Vec::new(), latency_ms: settings.latency_ms })
+    }
+
+    /// Queue captured audio; it's played in blocks of `latency_ms`
+    pub fn feed(&mut self, samples: impl IntoIterator<Item = f32>, sample_rate: u32) {
+        self.block.extend(samples);
+        let block_len = (sample_rate as usize * self.latency_ms as usize / 1000).max(1);
+        if self.block.len() < block_len {
+            return;
+        }
+        if self.sink.len() > MAX_QUEUED_BLOCKS {
+            self.block.clear();
+            return;
+        }
+        self.sink.append(SamplesBuffer::new(1, sample_rate, std::mem::take(&mut self.block)));
+    }
+}
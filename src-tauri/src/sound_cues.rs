@@ -0,0 +1,93 @@
+use rodio::buffer::SamplesBuffer;
+use rodio::Source;
+use std::f32::consts::PI;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+use std::time::Duration;
+
+const SAMPLE_RATE: u32 = 44100;
+/// Custom sounds longer than this don't delay the start of recording any further
+const MAX_WAIT: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy)]
+pub enum Cue {
+    Start,
+    Stop,
+    Cancel,
+    Paste,
+}
+
+impl Cue {
+    /// Built-in tone (same beeps the webview used to synthesize)
+    fn tone(self) -> Vec<f32> {
+        match self {
+            Cue::Start => sweep(800.0, 1000.0, 0.1, 0.15),
+            Cue::Stop => sweep(1000.0, 600.0, 0.15, 0.2),
+            Cue::Cancel => sweep(400.0, 400.0, 0.0, 0.1),
+            Cue::Paste => sweep(1320.0, 1320.0, 0.0, 0.08),
+        }
+    }
+}
+
+/// Sine wave gliding from `from` to `to` Hz over `ramp` seconds, fading out over `length` seconds
+fn sweep(from: f32, to: f32, ramp: f32, length: f32) -> Vec<f32> {
+    let total = (SAMPLE_RATE as f32 * length) as usize;
+    let mut phase = 0.0f32;
+    (0..total)
+        .map(|i| {
+            let t = i as f32 / SAMPLE_RATE as f32;
+            let freq = if t < ramp { from + (to - from) * t / ramp } else { to };
+            phase += 2.0 * PI * freq / SAMPLE_RATE as f32;
+            // Exponential decay to ~3% of the starting level
+            let gain = 0.03f32.powf(t / length);
+            phase.sin() * gain
+        })
+        .collect()
+}
+
+/// Play `cue` on the current default output device without blocking (works with every window hidden).
+/// `custom_file` replaces the built-in tone with any file rodio decodes (wav, mp3, ogg, flac).
+/// Returns roughly how long the sound lasts.
+pub fn play(cue: Cue, volume: f32, custom_file: Option<PathBuf>) -> Duration {
+    let custom = custom_file.and_then(|path| {
+        match File::open(&path).map_err(|e| e.to_string()).and_then(|f| rodio::Decoder::new(BufReader::new(f)).map_err(|e| e.to_string())) {
+            Ok(decoder) => Some(decoder),
+            Err(e) => {
+                eprintln!("⚠️ Failed to load sound {}: {} (using built-in tone)", path.display(), e);
+                None
+            }
+        }
+    });
+    let tone = SamplesBuffer::new(1, SAMPLE_RATE, cue.tone());
+    let duration = match &custom {
+        Some(decoder) => decoder.total_duration().unwrap_or(MAX_WAIT).min(MAX_WAIT),
+        None => tone.total_duration().unwrap_or_default(),
+    };
+
+    // Opened per cue so it follows the current default output device
+    std::thread::spawn(move || {
+        let (_stream, handle) = match rodio::OutputStream::try_default() {
+            Ok(pair) => pair,
+            Err(e) => {
+                eprintln!("❌ Failed to open audio output for sound cue: {}", e);
+                return;
+            }
+        };
+        let sink = match rodio::Sink::try_new(&handle) {
+            Ok(sink) => sink,
+            Err(e) => {
+                eprintln!("❌ Failed to create sink for sound cue: {}", e);
+                return;
+            }
+        };
+        sink.set_volume(volume);
+        match custom {
+            Some(decoder) => sink.append(decoder),
+            None => sink.append(tone),
+        }
+        sink.sleep_until_end();
+    });
+
+    duration
+}
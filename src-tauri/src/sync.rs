@@ -0,0 +1,368 @@
+use crate::audio_archive;
+use crate::db::{sync_key, Database, TranscriptionEntry};
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Nonce};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Name of the encrypted snapshot on the backend
+const SYNC_OBJECT: &str = "dicta-sync.bin";
+/// Header identifying the file format (bumped if the layout changes)
+const MAGIC: &[u8] = b"DICTASYNC1";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const PBKDF2_ROUNDS: u32 = 200_000;
+/// Tombstones older than this are dropped (every device has synced by then)
+const TOMBSTONE_MAX_AGE_MS: i64 = 90 * 24 * 60 * 60 * 1000;
+
+pub const DEFAULT_SYNC_INTERVAL_MINS: i64 = 15;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncBackend {
+    #[default]
+    WebDav,
+    S3,
+}
+
+/// User-provided sync target, stored as JSON in the `sync_config` setting
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SyncConfig {
+    pub enabled: bool,
+    pub backend: SyncBackend,
+    pub url: String,        // WebDAV folder URL, or S3 endpoint (e.g. https://s3.us-east-1.amazonaws.com)
+    pub bucket: String,     // S3 only
+    pub region: String,     // S3 only
+    pub username: String,   // WebDAV user / S3 access key id
+    pub password: String,   // WebDAV password / S3 secret access key
+    pub passphrase: String, // Encrypts the snapshot; must be the same on every device
+}
+
+/// `SyncConfig` as sent to the webview: the password and passphrase never leave the backend,
+/// only whether they're set
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncConfigView {
+    pub enabled: bool,
+    pub backend: SyncBackend,
+    pub url: String,
+    pub bucket: String,
+    pub region: String,
+    pub username: String,
+    pub password_set: bool,
+    pub passphrase_set: bool,
+}
+
+impl SyncConfig {
+    pub fn view(&self) -> SyncConfigView {
+        SyncConfigView {
+            enabled: self.enabled,
+            backend: self.backend,
+            url: self.url.clone(),
+            bucket: self.bucket.clone(),
+            region: self.region.clone(),
+            username: self.username.clone(),
+            password_set: !self.password.is_empty(),
+            passphrase_set: !self.passphrase.is_empty(),
+        }
+    }
+
+    /// Secrets left empty in an update from the webview (which never gets them back) keep their stored value
+    pub fn keep_secrets(&mut self, stored: &SyncConfig) {
+        if self.password.is_empty() {
+            self.password = stored.password.clone();
+        }
+        if self.passphrase.is_empty() {
+            self.passphrase = stored.passphrase.clone();
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SyncReport {
+    pub downloaded: usize,       // Transcriptions added from other devices
+    pub deleted: usize,          // Transcriptions removed because another device deleted them
+    pub settings_updated: usize, // Settings taken from another device
+}
+
+/// Everything that is synced. Encrypted as a whole before leaving the machine.
+#[derive(Default, Serialize, Deserialize)]
+struct Snapshot {
+    transcriptions: Vec<TranscriptionEntry>,
+    tombstones: HashMap<String, i64>,         // sync key -> deleted_at
+    settings: HashMap<String, (String, i64)>, // key -> (value, modified_at)
+}
+
+/// Pull the remote snapshot, merge it into the local database and push the merged result.
+/// Transcriptions are merged as a set (deletions win via tombstones); settings use the newest value.
+/// Archived audio of the entries it deletes is removed from `archive_dir`.
+pub async fn run(database: &Database, archive_dir: &Path, config: &SyncConfig, setting_keys: &[&str]) -> Result<SyncReport, String> {
+    if config.passphrase.is_empty() {
+        return Err("Sync passphrase is not set".to_string());
+    }
+    let client = reqwest::Client::new();
+    let now = chrono::Utc::now().timestamp_millis();
+    let mut report = SyncReport::default();
+
+    let remote = match download(&client, config).await? {
+        Some(data) => {
+            let json = decrypt(&data, &config.passphrase)?;
+            serde_json::from_slice::<Snapshot>(&json).map_err(|e| format!("Invalid sync snapshot: {}", e))?
+        }
+        None => Snapshot::default(),
+    };
+
+    // 1. Tombstones: union, keeping the latest deletion time
+    let mut tombstones = database.load_tombstones().map_err(|e| format!("Failed to load tombstones: {}", e))?;
+    for (key, deleted_at) in remote.tombstones {
+        let entry = tombstones.entry(key).or_insert(deleted_at);
+        *entry = (*entry).max(deleted_at);
+    }
+    tombstones.retain(|_, deleted_at| now - *deleted_at < TOMBSTONE_MAX_AGE_MS);
+
+    // 2. Transcriptions: drop what another device deleted, add what we don't have
+    let local = database.load_transcriptions(None, None).map_err(|e| format!("Failed to load transcriptions: {}", e))?;
+    let mut known: HashMap<String, TranscriptionEntry> = HashMap::new();
+    for entry in local {
+        let key = sync_key(entry.timestamp, &entry.text);
+        if tombstones.contains_key(&key) {
+            if let Some(id) = entry.id {
+                let orphaned = database.delete_transcription(id).map_err(|e| format!("Failed to delete transcription: {}", e))?;
+                if let Some(session_id) = orphaned {
+                    audio_archive::delete(archive_dir, &session_id);
+                }
+                report.deleted += 1;
+            }
+            continue;
+        }
+        known.insert(key, TranscriptionEntry { id: None, ..entry });
+    }
+    for entry in remote.transcriptions {
+        let key = sync_key(entry.timestamp, &entry.text);
+        if tombstones.contains_key(&key) || known.contains_key(&key) {
+            continue;
+        }
+        database
+            .save_transcription(
+                &entry.text,
+                entry.timestamp,
+                entry.duration_ms,
+                entry.model.as_deref(),
+                entry.cost_cents,
+                entry.mode.as_deref(),
+                entry.session_id.as_deref(),
+            )
+            .map_err(|e| format!("Failed to save transcription: {}", e))?;
+        report.downloaded += 1;
+        known.insert(key, entry);
+    }
+    // delete_transcription records its own tombstones; store the merged set afterwards
+    database.replace_tombstones(&tombstones).map_err(|e| format!("Failed to save tombstones: {}", e))?;
+
+    // 3. Settings: a value that changed since the last sync gets a fresh timestamp, newest wins
+    let mut last_synced: HashMap<String, (String, i64)> = database.load_setting("sync_settings_state")
+        .ok()
+        .flatten()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+    for key in setting_keys {
+        let Some(value) = database.load_setting(key).ok().flatten() else { continue };
+        match last_synced.get(*key) {
+            Some((synced, _)) if *synced == value => {}
+            _ => {
+                last_synced.insert(key.to_string(), (value, now));
+            }
+        }
+    }
+    for (key, (value, modified_at)) in remote.settings {
+        if !setting_keys.contains(&key.as_str()) {
+            continue;
+        }
+        let newer = last_synced.get(&key).map_or(true, |(_, local_at)| modified_at > *local_at);
+        if newer {
+            database.save_setting(&key, &value).map_err(|e| format!("Failed to save setting: {}", e))?;
+            report.settings_updated += 1;
+            last_synced.insert(key, (value, modified_at));
+        }
+    }
+    let state_json = serde_json::to_string(&last_synced).map_err(|e| e.to_string())?;
+    database.save_setting("sync_settings_state", &state_json).map_err(|e| format!("Failed to save sync state: {}", e))?;
+
+    // 4. Push the merged snapshot
+    let mut transcriptions: Vec<TranscriptionEntry> = known.into_values().collect();
+    transcriptions.sort_by_key(|t| t.timestamp);
+    let snapshot = Snapshot { transcriptions, tombstones, settings: last_synced };
+    let json = serde_json::to_vec(&snapshot).map_err(|e| format!("Failed to serialize snapshot: {}", e))?;
+    upload(&client, config, encrypt(&json, &config.passphrase)?).await?;
+
+    println!(
+        "🔄 Sync complete: {} transcriptions, +{} downloaded, -{} deleted, {} settings updated",
+        snapshot.transcriptions.len(),
+        report.downloaded,
+        report.deleted,
+        report.settings_updated
+    );
+    Ok(report)
+}
+
+// --- Encryption (AES-256-GCM, key derived from the passphrase with PBKDF2) ---
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> [u8; 32] {
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, PBKDF2_ROUNDS, &mut key);
+    key
+}
+
+fn encrypt(plaintext: &[u8], passphrase: &str) -> Result<Vec<u8>, String> {
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut salt);
+    OsRng.fill_bytes(&mut nonce);
+
+    let cipher = Aes256Gcm::new_from_slice(&derive_key(passphrase, &salt)).map_err(|e| e.to_string())?;
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), plaintext)
+        .map_err(|_| "Encryption failed".to_string())?;
+
+    let mut out = Vec::with_capacity(MAGIC.len() + SALT_LEN + NONCE_LEN + ciphertext.len());
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&salt);
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&ciphertext);
+    Ok(out)
+}
+
+fn decrypt(data: &[u8], passphrase: &str) -> Result<Vec<u8>, String> {
+    let header = MAGIC.len() + SALT_LEN + NONCE_LEN;
+    if data.len() < header || !data.starts_with(MAGIC) {
+        return Err("Remote file is not a Dicta sync snapshot".to_string());
+    }
+    let salt = &data[MAGIC.len()..MAGIC.len() + SALT_LEN];
+    let nonce = &data[MAGIC.len() + SALT_LEN..header];
+
+    let cipher = Aes256Gcm::new_from_slice(&derive_key(passphrase, salt)).map_err(|e| e.to_string())?;
+    cipher
+        .decrypt(Nonce::from_slice(nonce), &data[header..])
+        .map_err(|_| "Failed to decrypt sync snapshot (wrong passphrase?)".to_string())
+}
+
+// --- Backends ---
+
+fn object_url(config: &SyncConfig) -> String {
+    let base = config.url.trim_end_matches('/');
+    match config.backend {
+        SyncBackend::WebDav => format!("{}/{}", base, SYNC_OBJECT),
+        SyncBackend::S3 => format!("{}/{}/{}", base, config.bucket, SYNC_OBJECT), // Path-style addressing
+    }
+}
+
+/// Fetch the snapshot; None if nothing has been uploaded yet
+async fn download(client: &reqwest::Client, config: &SyncConfig) -> Result<Option<Vec<u8>>, String> {
+    let url = object_url(config);
+    let request = match config.backend {
+        SyncBackend::WebDav => client.get(&url).basic_auth(&config.username, Some(&config.password)),
+        SyncBackend::S3 => s3_signed(client.get(&url), "GET", &url, &[], config)?,
+    };
+    let response = request.send().await.map_err(|e| format!("Sync download failed: {}", e))?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    if !response.status().is_success() {
+        let status = response.status();
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(format!("Sync download error ({}): {}", status, error_text));
+    }
+    let bytes = response.bytes().await.map_err(|e| format!("Sync download failed: {}", e))?;
+    Ok(Some(bytes.to_vec()))
+}
+
+async fn upload(client: &reqwest::Client, config: &SyncConfig, body: Vec<u8>) -> Result<(), String> {
+    let url = object_url(config);
+    let request = match config.backend {
+        SyncBackend::WebDav => client.put(&url).basic_auth(&config.username, Some(&config.password)),
+        SyncBackend::S3 => s3_signed(client.put(&url), "PUT", &url, &body, config)?,
+    };
+    let response = request
+        .header("Content-Type", "application/octet-stream")
+        .body(body)
+        .send()
+        .await
+        .map_err(|e| format!("Sync upload failed: {}", e))?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(format!("Sync upload error ({}): {}", status, error_text));
+    }
+    Ok(())
+}
+
+fn hmac_sha256(key: &[u8], data: &str) -> Result<Vec<u8>, String> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).map_err(|e| e.to_string())?;
+    mac.update(data.as_bytes());
+    Ok(mac.finalize().into_bytes().to_vec())
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Add AWS Signature Version 4 headers (works with S3-compatible services like R2, B2, MinIO)
+fn s3_signed(
+    request: reqwest::RequestBuilder,
+    method: &str,
+    url: &str,
+    body: &[u8],
+    config: &SyncConfig,
+) -> Result<reqwest::RequestBuilder, String> {
+    let parsed = reqwest::Url::parse(url).map_err(|e| format!("Invalid S3 URL: {}", e))?;
+    let host = match (parsed.host_str(), parsed.port()) {
+        (Some(host), Some(port)) => format!("{}:{}", host, port),
+        (Some(host), None) => host.to_string(),
+        (None, _) => return Err("Invalid S3 URL: missing host".to_string()),
+    };
+    let region = if config.region.is_empty() { "us-east-1" } else { &config.region };
+
+    let now = chrono::Utc::now();
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = now.format("%Y%m%d").to_string();
+    let payload_hash = hex(&Sha256::digest(body));
+
+    let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+    let canonical_request = format!(
+        "{}\n{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+        method,
+        parsed.path(),
+        host,
+        payload_hash,
+        amz_date,
+        signed_headers,
+        payload_hash
+    );
+    let scope = format!("{}/{}/s3/aws4_request", date, region);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date,
+        scope,
+        hex(&Sha256::digest(canonical_request.as_bytes()))
+    );
+
+    let k_date = hmac_sha256(format!("AWS4{}", config.password).as_bytes(), &date)?;
+    let k_region = hmac_sha256(&k_date, region)?;
+    let k_service = hmac_sha256(&k_region, "s3")?;
+    let k_signing = hmac_sha256(&k_service, "aws4_request")?;
+    let signature = hex(&hmac_sha256(&k_signing, &string_to_sign)?);
+
+    Ok(request
+        .header("x-amz-date", amz_date)
+        .header("x-amz-content-sha256", payload_hash)
+        .header(
+            "Authorization",
+            format!(
+                "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+                config.username, scope, signed_headers, signature
+            ),
+        ))
+}
@@ -1,9 +1,21 @@
 use std::sync::Mutex;
+use windows::core::Interface;
 use windows::Win32::Media::Audio::*;
 use windows::Win32::Media::Audio::Endpoints::IAudioEndpointVolume;
 use windows::Win32::System::Com::*;
+use windows::Win32::UI::Shell::PropertiesSystem::PROPERTYKEY;
 
 static WAS_MUTED_BEFORE: Mutex<Option<bool>> = Mutex::new(None);
+static DUCKING: Mutex<Ducking> = Mutex::new(Ducking { originals: Vec::new(), holds: Vec::new() });
+static DUCKING_LISTENER: Mutex<Option<Box<dyn Fn(&[(String, f32)]) + Send>>> = Mutex::new(None);
+
+struct Ducking {
+    /// Volumes of other apps' audio sessions before ducking, by session instance identifier
+    /// (a process such as a browser can have several sessions, each with its own volume)
+    originals: Vec<(String, f32)>,
+    /// Active holds and the level each asked for
+    holds: Vec<(DuckOwner, f32)>,
+}
 
 /// Mute system audio output. Saves current mute state first so we can restore it later.
 pub fn mute_system_audio() -> Result<(), String> {
@@ -85,3 +97,284 @@ pub fn unmute_system_audio() -> Result<(), String> {
         }
     }
 }
+
+/// Audio sessions of other processes on the default output device (our own TTS is excluded),
+/// with the instance identifier that tells them apart
+unsafe fn other_audio_sessions() -> Result<Vec<(String, ISimpleAudioVolume)>, String> {
+    let _ = CoInitializeEx(None, COINIT_MULTITHREADED).ok();
+
+    let enumerator: IMMDeviceEnumerator =
+        CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_ALL)
+            .map_err(|e| format!("CoCreateInstance failed: {}", e))?;
+
+    let device = enumerator
+        .GetDefaultAudioEndpoint(eRender, eMultimedia)
+        .map_err(|e| format!("GetDefaultAudioEndpoint failed: {}", e))?;
+
+    let manager: IAudioSessionManager2 = device
+        .Activate(CLSCTX_ALL, None)
+        .map_err(|e| format!("Activate IAudioSessionManager2 failed: {}", e))?;
+
+    let sessions = manager
+        .GetSessionEnumerator()
+        .map_err(|e| format!("GetSessionEnumerator failed: {}", e))?;
+    let count = sessions
+        .GetCount()
+        .map_err(|e| format!("GetCount failed: {}", e))?;
+
+    let own_pid = std::process::id();
+    let mut result = Vec::new();
+    for i in 0..count {
+        let Ok(control) = sessions.GetSession(i) else { continue };
+        let Ok(control2) = control.cast::<IAudioSessionControl2>() else { continue };
+        let pid = control2.GetProcessId().unwrap_or(0);
+        // pid 0 is the system sounds session
+        if pid == own_pid || pid == 0 {
+            continue;
+        }
+        let Ok(identifier) = control2.GetSessionInstanceIdentifier() else { continue };
+        let id = identifier.to_string();
+        CoTaskMemFree(Some(identifier.0 as *const _));
+        let Ok(id) = id else { continue };
+        if let Ok(volume) = control.cast::<ISimpleAudioVolume>() {
+            result.push((id, volume));
+        }
+    }
+    Ok(result)
+}
+
+/// Who is holding other apps' volume down. TTS replies and recordings (with sidetone on)
+/// overlap, so each has its own hold and the volumes only come back when both are released.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DuckOwner {
+    Tts,
+    Recording,
+}
+
+/// Called with the saved original volumes whenever they change (empty once restored), so they
+/// can be persisted and put back by restore_saved_volumes if the app dies while ducking
+pub fn set_ducking_listener(listener: impl Fn(&[(String, f32)]) + Send + 'static) {
+    *DUCKING_LISTENER.lock().unwrap() = Some(Box::new(listener));
+}
+
+/// Put back the original volumes persisted by an earlier run that didn't restore them.
+/// Sessions that have ended since are skipped. Returns how many were restored.
+pub fn restore_saved_volumes(originals: &[(String, f32)]) -> Result<usize, String> {
+    let mut restored = 0;
+    unsafe {
+        for (id, volume) in other_audio_sessions()? {
+            let Some((_, original)) = originals.iter().find(|(saved_id, _)| *saved_id == id) else { continue };
+            if volume.SetMasterVolume(*original, std::ptr::null()).is_ok() {
+                restored += 1;
+            }
+        }
+    }
+    Ok(restored)
+}
+
+/// Lower other apps' volume to `level` (0.0-1.0) of its original value until `owner` calls
+/// restore_ducked_audio. With several holds the lowest level wins.
+pub fn duck_system_audio(owner: DuckOwner, level: f32) -> Result<(), String> {
+    let mut ducking = DUCKING.lock().unwrap();
+    ducking.holds.retain(|(o, _)| *o != owner);
+    ducking.holds.push((owner, level));
+    apply_ducking(&mut ducking)
+}
+
+/// Release `owner`'s hold. Volumes go back to their saved values once no hold is left; only
+/// the sessions that were ducked are touched.
+pub fn restore_ducked_audio(owner: DuckOwner) -> Result<(), String> {
+    let mut ducking = DUCKING.lock().unwrap();
+    if !ducking.holds.iter().any(|(o, _)| *o == owner) {
+        return Ok(());
+    }
+    ducking.holds.retain(|(o, _)| *o != owner);
+    apply_ducking(&mut ducking)
+}
+
+/// Set every other session to its original volume times the lowest held level, saving the
+/// original the first time a session is lowered
+fn apply_ducking(ducking: &mut Ducking) -> Result<(), String> {
+    let level = ducking.holds.iter().map(|(_, level)| *level).reduce(f32::min);
+    let saved = ducking.originals.len();
+
+    unsafe {
+        for (id, volume) in other_audio_sessions()? {
+            let saved = ducking.originals.iter().find(|(ducked_id, _)| *ducked_id == id).map(|(_, v)| *v);
+            match (level, saved) {
+                (Some(level), Some(original)) => {
+                    let _ = volume.SetMasterVolume(original * level, std::ptr::null());
+                }
+                (Some(level), None) => {
+                    let Ok(current) = volume.GetMasterVolume() else { continue };
+                    if volume.SetMasterVolume(current * level, std::ptr::null()).is_ok() {
+                        ducking.originals.push((id, current));
+                    }
+                }
+                (None, Some(original)) => {
+                    let _ = volume.SetMasterVolume(original, std::ptr::null());
+                }
+                (None, None) => {}
+            }
+        }
+    }
+
+    match level {
+        Some(level) => println!("🔉 Ducked {} audio sessions to {:.0}%", ducking.originals.len(), level * 100.0),
+        None => {
+            println!("🔊 Restored {} ducked audio sessions", ducking.originals.len());
+            ducking.originals.clear();
+        }
+    }
+    if ducking.originals.len() != saved || level.is_none() {
+        if let Some(listener) = DUCKING_LISTENER.lock().unwrap().as_ref() {
+            listener(&ducking.originals);
+        }
+    }
+    Ok(())
+}
+
+/// DEVPKEY_Device_FriendlyName / DEVPKEY_Device_EnumeratorName (same property set)
+const PKEY_DEVICE_FRIENDLY_NAME: PROPERTYKEY = PROPERTYKEY {
+    fmtid: windows::core::GUID::from_u128(0xa45c254e_df1c_4efd_8020_67d146a850e0),
+    pid: 14,
+};
+const PKEY_DEVICE_ENUMERATOR_NAME: PROPERTYKEY = PROPERTYKEY {
+    fmtid: windows::core::GUID::from_u128(0xa45c254e_df1c_4efd_8020_67d146a850e0),
+    pid: 24,
+};
+
+/// An active capture endpoint
+pub struct InputEndpoint {
+    pub id: String,   // Endpoint ID: unlike the name, it doesn't change with the port or language
+    pub name: String, // Same as cpal's device name
+    pub bluetooth: bool,
+}
+
+/// Active capture endpoints, with whether each one is a Bluetooth device
+/// (opening a Bluetooth headset's mic switches it to the low-quality hands-free profile)
+pub fn input_endpoints() -> Result<Vec<InputEndpoint>, String> {
+    unsafe {
+        let _ = CoInitializeEx(None, COINIT_MULTITHREADED).ok();
+
+        let enumerator: IMMDeviceEnumerator =
+            CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_ALL)
+                .map_err(|e| format!("CoCreateInstance failed: {}", e))?;
+        let collection = enumerator
+            .EnumAudioEndpoints(eCapture, DEVICE_STATE_ACTIVE)
+            .map_err(|e| format!("EnumAudioEndpoints failed: {}", e))?;
+        let count = collection.GetCount().map_err(|e| format!("GetCount failed: {}", e))?;
+
+        let mut endpoints = Vec::new();
+        for i in 0..count {
+            let Ok(device) = collection.Item(i) else { continue };
+            let Ok(properties) = device.OpenPropertyStore(STGM_READ) else { continue };
+            let name = properties.GetValue(&PKEY_DEVICE_FRIENDLY_NAME).map(|v| v.to_string()).unwrap_or_default();
+            let enumerator_name = properties.GetValue(&PKEY_DEVICE_ENUMERATOR_NAME).map(|v| v.to_string()).unwrap_or_default();
+            // BTHENUM (classic), BTHHFENUM (hands-free), BTHLEDEVICE (LE audio)
+            let bluetooth = enumerator_name.to_uppercase().starts_with("BTH") || name.contains("Hands-Free");
+            let id = endpoint_id(&device).unwrap_or_default();
+            endpoints.push(InputEndpoint { id, name, bluetooth });
+        }
+        Ok(endpoints)
+    }
+}
+
+/// Name of the default capture endpoint (what cpal opens when no mic is selected)
+pub fn default_input_name() -> Option<String> {
+    unsafe {
+        let _ = CoInitializeEx(None, COINIT_MULTITHREADED).ok();
+
+        let enumerator: IMMDeviceEnumerator = CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_ALL).ok()?;
+        let device = enumerator.GetDefaultAudioEndpoint(eCapture, eConsole).ok()?;
+        device_name(&device)
+    }
+}
+
+/// Endpoint ID of a device ("{0.0.1.00000000}.{guid}")
+pub fn endpoint_id(device: &IMMDevice) -> Option<String> {
+    unsafe {
+        let id = device.GetId().ok()?;
+        let value = id.to_string().ok();
+        CoTaskMemFree(Some(id.0 as *const _));
+        value
+    }
+}
+
+/// A display name without the instance number Windows adds to tell identical devices
+/// apart ("Microphone (2- USB Audio)"), lowercased
+fn normalize_device_name(name: &str) -> String {
+    let mut out = String::new();
+    let mut rest = name.trim();
+    while let Some(c) = rest.chars().next() {
+        let digits = rest.chars().take_while(|c| c.is_ascii_digit()).count();
+        if digits > 0 && (out.is_empty() || out.ends_with(['(', ' '])) && rest[digits..].starts_with("- ") {
+            rest = &rest[digits + 2..];
+            continue;
+        }
+        out.extend(c.to_lowercase());
+        rest = &rest[c.len_utf8()..];
+    }
+    out
+}
+
+/// The endpoint a saved microphone refers to now: by endpoint ID, then by name, then by name
+/// without instance numbers, then by the device part of the name ("(USB Audio)", which the
+/// Windows language doesn't change) when only one endpoint has it
+pub fn find_input<'a>(endpoints: &'a [InputEndpoint], id: Option<&str>, name: &str) -> Option<&'a InputEndpoint> {
+    if let Some(id) = id.filter(|id| !id.is_empty()) {
+        if let Some(endpoint) = endpoints.iter().find(|e| e.id == id) {
+            return Some(endpoint);
+        }
+    }
+    if let Some(endpoint) = endpoints.iter().find(|e| e.name.trim() == name.trim()) {
+        return Some(endpoint);
+    }
+    let normalized = normalize_device_name(name);
+    if let Some(endpoint) = endpoints.iter().find(|e| normalize_device_name(&e.name) == normalized) {
+        return Some(endpoint);
+    }
+    let device_part = |name: &str| name.find('(').map(|i| name[i..].to_string());
+    let device = device_part(&normalized)?;
+    let mut matches = endpoints.iter().filter(|e| device_part(&normalize_device_name(&e.name)).as_ref() == Some(&device));
+    match (matches.next(), matches.next()) {
+        (Some(endpoint), None) => Some(endpoint),
+        _ => None,
+    }
+}
+
+/// Friendly name of an endpoint, as cpal reports it
+pub fn device_name(device: &IMMDevice) -> Option<String> {
+    unsafe {
+        let properties = device.OpenPropertyStore(STGM_READ).ok()?;
+        properties.GetValue(&PKEY_DEVICE_FRIENDLY_NAME).ok().map(|v| v.to_string())
+    }
+}
+
+/// Capture endpoint whose friendly name matches `name` (trimmed), or the default one
+pub fn capture_device(name: Option<&str>) -> Result<IMMDevice, String> {
+    unsafe {
+        let _ = CoInitializeEx(None, COINIT_MULTITHREADED).ok();
+
+        let enumerator: IMMDeviceEnumerator =
+            CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_ALL)
+                .map_err(|e| format!("CoCreateInstance failed: {}", e))?;
+
+        if let Some(name) = name.map(str::trim) {
+            let collection = enumerator
+                .EnumAudioEndpoints(eCapture, DEVICE_STATE_ACTIVE)
+                .map_err(|e| format!("EnumAudioEndpoints failed: {}", e))?;
+            let count = collection.GetCount().map_err(|e| format!("GetCount failed: {}", e))?;
+            for i in 0..count {
+                let Ok(device) = collection.Item(i) else { continue };
+                if device_name(&device).is_some_and(|n| n.trim() == name) {
+                    return Ok(device);
+                }
+            }
+        }
+
+        enumerator
+            .GetDefaultAudioEndpoint(eCapture, eConsole)
+            .map_err(|e| format!("GetDefaultAudioEndpoint failed: {}", e))
+    }
+}
@@ -0,0 +1,61 @@
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Equal,
+    Removed,
+    Added,
+}
+
+/// A run of words that is unchanged, only in the original, or only in the new text
+#[derive(Debug, Clone, Serialize)]
+pub struct Change {
+    pub kind: ChangeKind,
+    pub text: String,
+}
+
+/// Word-level diff (longest common subsequence), with consecutive words of the same kind
+/// joined into one change so the UI can render it as strikethrough/highlighted runs
+pub fn word_diff(original: &str, new: &str) -> Vec<Change> {
+    let a: Vec<&str> = original.split_whitespace().collect();
+    let b: Vec<&str> = new.split_whitespace().collect();
+
+    // lcs[i][j] = LCS length of a[i..] and b[j..]
+    let mut lcs = vec![vec![0u32; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] { lcs[i + 1][j + 1] + 1 } else { lcs[i + 1][j].max(lcs[i][j + 1]) };
+        }
+    }
+
+    let mut changes: Vec<Change> = Vec::new();
+    let mut push = |kind: ChangeKind, word: &str| match changes.last_mut() {
+        Some(last) if last.kind == kind => {
+            last.text.push(' ');
+            last.text.push_str(word);
+        }
+        _ => changes.push(Change { kind, text: word.to_string() }),
+    };
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            push(ChangeKind::Equal, a[i]);
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            push(ChangeKind::Removed, a[i]);
+            i += 1;
+        } else {
+            push(ChangeKind::Added, b[j]);
+            j += 1;
+        }
+    }
+    for word in &a[i..] {
+        push(ChangeKind::Removed, word);
+    }
+    for word in &b[j..] {
+        push(ChangeKind::Added, word);
+    }
+    changes
+}
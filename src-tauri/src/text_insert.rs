@@ -0,0 +1,138 @@
+use windows::core::BSTR;
+use windows::Win32::System::Com::*;
+use windows::Win32::UI::Accessibility::*;
+
+/// Insert `text` at the caret of the focused control through UI Automation, replacing the
+/// selection like a paste would, without touching the clipboard.
+/// Fails for controls that don't expose both an editable ValuePattern and a TextPattern, or
+/// that reject SetValue (the caller falls back to Ctrl+V then); once SetValue succeeds it's Ok.
+pub fn insert(text: &str) -> Result<(), String> {
+    unsafe {
+        let _ = CoInitializeEx(None, COINIT_MULTITHREADED).ok();
+
+        let automation: IUIAutomation = CoCreateInstance(&CUIAutomation, None, CLSCTX_INPROC_SERVER)
+            .map_err(|e| format!("CoCreateInstance failed: {}", e))?;
+        let element = automation
+            .GetFocusedElement()
+            .map_err(|e| format!("GetFocusedElement failed: {}", e))?;
+
+        let value_pattern: IUIAutomationValuePattern = element
+            .GetCurrentPatternAs(UIA_ValuePatternId)
+            .map_err(|_| "Focused control has no ValuePattern".to_string())?;
+        if value_pattern.CurrentIsReadOnly().map(|r| r.as_bool()).unwrap_or(true) {
+            return Err("Focused control is read-only".to_string());
+        }
+        // ValuePattern can only replace the whole value; TextPattern tells where the caret is
+        let text_pattern: IUIAutomationTextPattern = element
+            .GetCurrentPatternAs(UIA_TextPatternId)
+            .map_err(|_| "Focused control has no TextPattern".to_string())?;
+
+        let (start, selected) = selection_offsets(&text_pattern)?;
+        let current: Vec<u16> = value_pattern
+            .CurrentValue()
+            .map_err(|e| format!("Failed to read control value: {}", e))?
+            .to_string()
+            .encode_utf16()
+            .collect();
+        if start + selected > current.len() {
+            return Err("Caret position doesn't match the control value".to_string());
+        }
+
+        let inserted: Vec<u16> = text.encode_utf16().collect();
+        let mut value = current[..start].to_vec();
+        value.extend_from_slice(&inserted);
+        value.extend_from_slice(&current[start + selected..]);
+        let value = String::from_utf16_lossy(&value);
+
+        value_pattern
+            .SetValue(&BSTR::from(value.as_str()))
+            .map_err(|e| format!("SetValue failed: {}", e))?;
+        // The value was set: failing now would make the caller paste the text a second time.
+        // Controls may normalize what they get (line endings, trimming), so a mismatch is only logged.
+        let written = value_pattern.CurrentValue().map(|v| v.to_string()).unwrap_or_default();
+        if written != value {
+            println!("⚠️ Control value differs from what was set after SetValue");
+        }
+
+        // SetValue usually moves the caret to the start or end; put it after the inserted text
+        if let Err(e) = place_caret(&text_pattern, (start + inserted.len()) as i32) {
+            println!("⚠️ Failed to restore caret: {}", e);
+        }
+
+        println!("⌨️ Inserted {} chars via UI Automation", text.chars().count());
+        Ok(())
+    }
+}
+
+/// Caret offset and selection length, in UTF-16 units from the start of the document
+unsafe fn selection_offsets(text_pattern: &IUIAutomationTextPattern) -> Result<(usize, usize), String> {
+    let selections = text_pattern
+        .GetSelection()
+        .map_err(|e| format!("GetSelection failed: {}", e))?;
+    if selections.Length().unwrap_or(0) == 0 {
+        return Err("Control has no caret".to_string());
+    }
+    let selection = selections
+        .GetElement(0)
+        .map_err(|e| format!("Failed to read selection: {}", e))?;
+
+    let before = text_pattern
+        .DocumentRange()
+        .map_err(|e| format!("DocumentRange failed: {}", e))?;
+    before
+        .MoveEndpointByRange(TextPatternRangeEndpoint_End, &selection, TextPatternRangeEndpoint_Start)
+        .map_err(|e| format!("MoveEndpointByRange failed: {}", e))?;
+
+    let before_text = before.GetText(-1).map_err(|e| format!("GetText failed: {}", e))?;
+    let selected_text = selection.GetText(-1).map_err(|e| format!("GetText failed: {}", e))?;
+    Ok((before_text.len(), selected_text.len()))
+}
+
+/// Collapse the selection to `offset` characters from the start of the document
+unsafe fn place_caret(text_pattern: &IUIAutomationTextPattern, offset: i32) -> Result<(), String> {
+    let range = text_pattern
+        .DocumentRange()
+        .map_err(|e| format!("DocumentRange failed: {}", e))?;
+    range
+        .MoveEndpointByRange(TextPatternRangeEndpoint_End, &range.Clone().map_err(|e| format!("Clone failed: {}", e))?, TextPatternRangeEndpoint_Start)
+        .map_err(|e| format!("MoveEndpointByRange failed: {}", e))?;
+    range
+        .Move(TextUnit_Character, offset)
+        .map_err(|e| format!("Move failed: {}", e))?;
+    range.Select().map_err(|e| format!("Select failed: {}", e))
+}
+
+/// Full text of the focused control, to tell whether a simulated paste landed.
+/// None when the control exposes neither a ValuePattern nor a TextPattern.
+pub fn focused_text() -> Option<String> {
+    unsafe {
+        let _ = CoInitializeEx(None, COINIT_MULTITHREADED).ok();
+
+        let automation: IUIAutomation = CoCreateInstance(&CUIAutomation, None, CLSCTX_INPROC_SERVER).ok()?;
+        let element = automation.GetFocusedElement().ok()?;
+
+        if let Ok(value_pattern) = element.GetCurrentPatternAs::<IUIAutomationValuePattern>(UIA_ValuePatternId) {
+            if let Ok(value) = value_pattern.CurrentValue() {
+                return Some(value.to_string());
+            }
+        }
+        let text_pattern: IUIAutomationTextPattern = element.GetCurrentPatternAs(UIA_TextPatternId).ok()?;
+        let text = text_pattern.DocumentRange().ok()?.GetText(-1).ok()?;
+        Some(text.to_string())
+    }
+}
+
+/// Whether the focused control is a password box (where dictated text must never be pasted)
+pub fn focused_is_password() -> bool {
+    unsafe {
+        let _ = CoInitializeEx(None, COINIT_MULTITHREADED).ok();
+
+        let Ok(automation) = CoCreateInstance::<_, IUIAutomation>(&CUIAutomation, None, CLSCTX_INPROC_SERVER) else {
+            return false;
+        };
+        automation
+            .GetFocusedElement()
+            .and_then(|element| element.CurrentIsPassword())
+            .is_ok_and(|password| password.as_bool())
+    }
+}
@@ -0,0 +1,69 @@
+/// Repair a live transcript put together from Realtime deltas: turns are joined without a
+/// space ("fim.Próxima"), punctuation can come after a stray space and a turn can start in
+/// lowercase. Deterministic, so the same deltas always give the same text.
+pub fn repair(text: &str) -> String {
+    capitalize_sentences(&fix_spacing(text))
+}
+
+/// Abbreviations whose period doesn't end the sentence ("Sr. Silva", "etc. e mais")
+const ABBREVIATIONS: &[&str] = &["sr", "sra", "srta", "dr", "dra", "prof", "profa", "etc", "ex", "vs", "mr", "mrs", "ms", "st"];
+
+/// One space after punctuation followed by a word, none before punctuation, single spaces.
+/// A period followed by lowercase is left alone: it's a file name, domain or decimal ("main.rs").
+fn fix_spacing(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len() + 16);
+    for (i, &c) in chars.iter().enumerate() {
+        let next = chars.get(i + 1).copied();
+        if c == ' ' {
+            // Drop doubled spaces and the space before closing punctuation
+            if out.is_empty() || out.ends_with([' ', '\n']) || next.is_some_and(|n| matches!(n, '.' | ',' | '!' | '?' | ';' | ':' | ')')) {
+                continue;
+            }
+        }
+        out.push(c);
+        let Some(next) = next.filter(|n| n.is_alphabetic()) else { continue };
+        let needs_space = match c {
+            '!' | '?' | ',' | ';' => true,
+            '.' => next.is_uppercase() && !chars[..i].ends_with(&['.']), // Not inside "..."
+            _ => false,
+        };
+        if needs_space {
+            out.push(' ');
+        }
+    }
+    out.trim_end().to_string()
+}
+
+/// Uppercase the first letter of the text and of each sentence
+fn capitalize_sentences(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut sentence_start = true;
+    let mut word = String::new(); // Word before the current position, for abbreviations
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c.is_alphanumeric() {
+            if sentence_start && c.is_alphabetic() {
+                out.extend(c.to_uppercase());
+            } else {
+                out.push(c);
+            }
+            sentence_start = false;
+            word.push(c);
+            continue;
+        }
+        out.push(c);
+        match c {
+            '!' | '?' | '\n' => sentence_start = true,
+            // An ellipsis is a pause, the sentence goes on; so does an abbreviation
+            '.' if chars.peek() != Some(&'.') && !out.ends_with("..") => {
+                sentence_start = !ABBREVIATIONS.contains(&word.to_lowercase().as_str())
+                    && chars.peek().map_or(true, |n| n.is_whitespace());
+            }
+            c if c.is_whitespace() || c == '"' || c == '(' => {}
+            _ => sentence_start = false,
+        }
+        word.clear();
+    }
+    out
+}
@@ -0,0 +1,77 @@
+use crate::db::Database;
+use serde::{Deserialize, Serialize};
+
+/// Titles longer than this are cut at a word boundary
+const MAX_TITLE_CHARS: usize = 60;
+
+/// How history entries get their title, stored in the `title_mode` setting
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TitleMode {
+    Off,
+    /// First sentence of the text, no API call
+    #[default]
+    Local,
+    /// Short title written by gpt-4o-mini (falls back to Local if the call fails)
+    Gpt,
+}
+
+impl TitleMode {
+    pub fn load(database: &Database) -> Self {
+        match database.load_setting("title_mode").ok().flatten().as_deref() {
+            Some("off") => TitleMode::Off,
+            Some("gpt") => TitleMode::Gpt,
+            _ => TitleMode::Local,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TitleMode::Off => "off",
+            TitleMode::Local => "local",
+            TitleMode::Gpt => "gpt",
+        }
+    }
+}
+
+/// Prompt asking for a title of `text`
+pub fn gpt_prompt(text: &str) -> String {
+    format!(
+        "Write a title of at most 6 words for the dictated text below, in the same language. Reply with the title only, without quotes or final punctuation.\n\n\"\"\"\n{}\n\"\"\"",
+        text.trim()
+    )
+}
+
+/// First sentence (or line) of `text`, shortened to MAX_TITLE_CHARS on a word boundary
+pub fn local_title(text: &str) -> String {
+    let first_line = text.trim().lines().next().unwrap_or("").trim();
+    let sentence = first_line
+        .char_indices()
+        .find(|(i, c)| matches!(c, '.' | '!' | '?') && first_line[i + c.len_utf8()..].starts_with(' '))
+        .map(|(i, c)| &first_line[..i + c.len_utf8()])
+        .unwrap_or(first_line);
+    clean(sentence)
+}
+
+/// Trim quotes/markdown the model or the text may add and enforce the length limit
+pub fn clean(title: &str) -> String {
+    let title = title.trim().trim_matches(|c: char| matches!(c, '"' | '\'' | '“' | '”' | '#' | '*')).trim();
+    if title.chars().count() <= MAX_TITLE_CHARS {
+        return title.to_string();
+    }
+    let mut out = String::new();
+    for word in title.split_whitespace() {
+        if out.chars().count() + word.chars().count() + 1 > MAX_TITLE_CHARS {
+            break;
+        }
+        if !out.is_empty() {
+            out.push(' ');
+        }
+        out.push_str(word);
+    }
+    if out.is_empty() {
+        out = title.chars().take(MAX_TITLE_CHARS).collect(); // One huge "word" (URL, code)
+    }
+    out.push('…');
+    out
+}
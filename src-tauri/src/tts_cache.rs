@@ -0,0 +1,91 @@
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+pub const DEFAULT_MAX_CACHE_MB: u64 = 100;
+
+/// Cache file for a TTS request: sha256 of (model, voice, text) so any change
+/// to the request produces a different entry
+fn cache_path(dir: &Path, text: &str, voice: &str, model: &str) -> PathBuf {
+    let mut hasher = Sha256::new();
+    hasher.update(model.as_bytes());
+    hasher.update([0u8]);
+    hasher.update(voice.as_bytes());
+    hasher.update([0u8]);
+    hasher.update(text.as_bytes());
+    let hash: String = hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect();
+    dir.join(format!("{}.mp3", hash))
+}
+
+/// Cached MP3 for this text/voice/model, if any
+pub fn load(dir: &Path, text: &str, voice: &str, model: &str) -> Option<Vec<u8>> {
+    let path = cache_path(dir, text, voice, model);
+    let audio = std::fs::read(&path).ok()?;
+    // Refresh mtime so eviction drops least recently used entries first
+    if let Ok(file) = std::fs::File::options().append(true).open(&path) {
+        let _ = file.set_modified(std::time::SystemTime::now());
+    }
+    println!("💽 TTS cache hit ({} bytes)", audio.len());
+    Some(audio)
+}
+
+/// Store an MP3 and evict old entries beyond `max_bytes`
+pub fn store(dir: &Path, text: &str, voice: &str, model: &str, audio: &[u8], max_bytes: u64) {
+    let path = cache_path(dir, text, voice, model);
+    if let Err(e) = std::fs::write(&path, audio) {
+        eprintln!("⚠️ Failed to write TTS cache entry: {}", e);
+        return;
+    }
+    evict(dir, max_bytes);
+}
+
+/// Delete least recently used entries until the cache fits in `max_bytes`
+fn evict(dir: &Path, max_bytes: u64) {
+    let Ok(entries) = std::fs::read_dir(dir) else { return };
+    let mut files: Vec<(PathBuf, u64, std::time::SystemTime)> = entries
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let meta = e.metadata().ok()?;
+            meta.is_file().then(|| (e.path(), meta.len(), meta.modified().unwrap_or(std::time::UNIX_EPOCH)))
+        })
+        .collect();
+
+    let mut total: u64 = files.iter().map(|(_, size, _)| size).sum();
+    if total <= max_bytes {
+        return;
+    }
+
+    files.sort_by_key(|(_, _, modified)| *modified);
+    for (path, size, _) in files {
+        if total <= max_bytes {
+            break;
+        }
+        if std::fs::remove_file(&path).is_ok() {
+            total -= size;
+        }
+    }
+    println!("🧹 TTS cache trimmed to {} KB", total / 1024);
+}
+
+/// Total size of the cache in bytes
+pub fn size(dir: &Path) -> u64 {
+    std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .filter_map(|e| e.metadata().ok())
+                .filter(|m| m.is_file())
+                .map(|m| m.len())
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+/// Remove every cached entry
+pub fn clear(dir: &Path) -> Result<(), String> {
+    let entries = std::fs::read_dir(dir).map_err(|e| format!("Failed to read TTS cache: {}", e))?;
+    for entry in entries.filter_map(|e| e.ok()) {
+        let _ = std::fs::remove_file(entry.path());
+    }
+    println!("🧹 TTS cache cleared");
+    Ok(())
+}
@@ -0,0 +1,83 @@
+use crate::db::Database;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::{AppHandle, Url};
+use tauri_plugin_updater::{Update, UpdaterExt};
+
+/// How often the background task looks for a new release
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+
+const STABLE_ENDPOINT: &str = "https://github.com/raphabazan/dicta/releases/latest/download/latest.json";
+// Rolling "beta" release, re-pointed at every pre-release build
+const BETA_ENDPOINT: &str = "https://github.com/raphabazan/dicta/releases/download/beta/latest.json";
+
+/// Which builds the updater offers, stored in the `update_channel` setting
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdateChannel {
+    #[default]
+    Stable,
+    Beta,
+}
+
+impl UpdateChannel {
+    pub fn load(database: &Database) -> Self {
+        match database.load_setting("update_channel").ok().flatten().as_deref() {
+            Some("beta") => UpdateChannel::Beta,
+            _ => UpdateChannel::Stable,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            UpdateChannel::Stable => "stable",
+            UpdateChannel::Beta => "beta",
+        }
+    }
+
+    fn endpoint(self) -> &'static str {
+        match self {
+            UpdateChannel::Stable => STABLE_ENDPOINT,
+            UpdateChannel::Beta => BETA_ENDPOINT,
+        }
+    }
+}
+
+/// A newer build than the running one
+#[derive(Debug, Clone, Serialize)]
+pub struct UpdateInfo {
+    pub version: String,
+    pub current_version: String,
+    pub notes: Option<String>,
+    pub channel: UpdateChannel,
+}
+
+/// Ask the channel's endpoint for a newer build (None when up to date)
+pub async fn check(app: &AppHandle, channel: UpdateChannel) -> Result<Option<Update>, String> {
+    let endpoint = Url::parse(channel.endpoint()).map_err(|e| format!("Invalid update endpoint: {}", e))?;
+    app.updater_builder()
+        .endpoints(vec![endpoint])
+        .map_err(|e| format!("Failed to configure updater: {}", e))?
+        .build()
+        .map_err(|e| format!("Failed to build updater: {}", e))?
+        .check()
+        .await
+        .map_err(|e| format!("Update check failed: {}", e))
+}
+
+pub fn info(update: &Update, channel: UpdateChannel) -> UpdateInfo {
+    UpdateInfo {
+        version: update.version.clone(),
+        current_version: update.current_version.clone(),
+        notes: update.body.clone(),
+        channel,
+    }
+}
+
+/// Download and install `update`; the caller restarts the app afterwards
+pub async fn install(update: Update) -> Result<(), String> {
+    update
+        .download_and_install(|_, _| {}, || println!("📦 Update downloaded, installing..."))
+        .await
+        .map_err(|e| format!("Failed to install update: {}", e))
+}
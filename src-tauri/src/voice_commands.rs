@@ -0,0 +1,171 @@
+use crate::db::{Database, VoiceIntent};
+use serde::{Deserialize, Serialize};
+use std::os::windows::process::CommandExt;
+use std::process::Command;
+use tauri::AppHandle;
+use windows::core::HSTRING;
+use windows::Win32::Foundation::HWND;
+use windows::Win32::UI::WindowsAndMessaging::{MessageBoxW, IDYES, MB_ICONQUESTION, MB_TOPMOST, MB_YESNO};
+
+const DEFAULT_TRIGGER: &str = "computador";
+const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+/// Command mode: dictations starting with the trigger word are routed to an intent instead of pasted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VoiceCommandSettings {
+    pub enabled: bool,
+    pub trigger: String, // e.g. "computador" in "Computador, abrir navegador"
+}
+
+impl VoiceCommandSettings {
+    pub fn load(database: &Database) -> Self {
+        Self {
+            enabled: database.load_setting("voice_commands_enabled").ok().flatten().as_deref() == Some("true"),
+            trigger: database
+                .load_setting("voice_command_trigger")
+                .ok()
+                .flatten()
+                .filter(|t| !t.trim().is_empty())
+                .unwrap_or_else(|| DEFAULT_TRIGGER.to_string()),
+        }
+    }
+
+    pub fn save(&self, database: &Database) -> rusqlite::Result<()> {
+        database.save_setting("voice_commands_enabled", if self.enabled { "true" } else { "false" })?;
+        database.save_setting("voice_command_trigger", self.trigger.trim())
+    }
+}
+
+/// What an intent does. Values may contain `{text}`, replaced by whatever was said after the phrase.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntentAction {
+    OpenApp,     // Program, file, folder or URL, opened like the Run dialog does
+    TypeSnippet, // Pasted into the focused app
+    Shell,       // cmd.exe command line, only run after the user confirms it
+}
+
+impl IntentAction {
+    pub fn parse(action: &str) -> Result<Self, String> {
+        match action {
+            "open_app" => Ok(IntentAction::OpenApp),
+            "type_snippet" => Ok(IntentAction::TypeSnippet),
+            "shell" => Ok(IntentAction::Shell),
+            other => Err(format!("Ação desconhecida '{}' (use open_app, type_snippet ou shell)", other)),
+        }
+    }
+}
+
+/// Lowercase, without accents or punctuation and with single spaces, so
+/// "Computador, abra o Navegador." compares equal to "computador abra o navegador"
+fn normalize(text: &str) -> String {
+    let folded: String = text
+        .to_lowercase()
+        .chars()
+        .map(|c| match c {
+            'á' | 'à' | 'â' | 'ã' | 'ä' => 'a',
+            'é' | 'è' | 'ê' | 'ë' => 'e',
+            'í' | 'ì' | 'î' | 'ï' => 'i',
+            'ó' | 'ò' | 'ô' | 'õ' | 'ö' => 'o',
+            'ú' | 'ù' | 'û' | 'ü' => 'u',
+            'ç' => 'c',
+            c if c.is_alphanumeric() => c,
+            _ => ' ',
+        })
+        .collect();
+    folded.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// The command after the trigger word, or None when `text` isn't a voice command
+pub fn strip_trigger(text: &str, trigger: &str) -> Option<String> {
+    let text = normalize(text);
+    let trigger = normalize(trigger);
+    if trigger.is_empty() {
+        return None;
+    }
+    let rest = text.strip_prefix(&trigger)?;
+    if !rest.is_empty() && !rest.starts_with(' ') {
+        return None; // "computadores ..." is not the trigger
+    }
+    Some(rest.trim().to_string())
+}
+
+/// The intent whose phrase starts the command (longest phrase wins)
+pub fn route<'a>(command: &str, intents: &'a [VoiceIntent]) -> Option<&'a VoiceIntent> {
+    intents
+        .iter()
+        .filter_map(|intent| {
+            let phrase = normalize(&intent.phrase);
+            let rest = command.strip_prefix(&phrase)?;
+            (rest.is_empty() || rest.starts_with(' ')).then(|| (intent, phrase.len()))
+        })
+        .max_by_key(|(_, len)| *len)
+        .map(|(intent, _)| intent)
+}
+
+/// What was said after the trigger word and `intent`'s phrase, as transcribed: `{text}` keeps
+/// the casing, accents and punctuation that matching ignores
+pub fn argument<'t>(text: &'t str, trigger: &str, intent: &VoiceIntent) -> &'t str {
+    let skip = word_count(trigger) + word_count(&intent.phrase);
+    // Words as `normalize` splits them: runs of letters and digits
+    let mut words = 0;
+    let mut in_word = false;
+    for (i, c) in text.char_indices() {
+        let word_char = c.is_alphanumeric();
+        if word_char && !in_word {
+            words += 1;
+        } else if !word_char && in_word && words == skip {
+            return trim_argument(&text[i..]);
+        }
+        in_word = word_char;
+    }
+    ""
+}
+
+fn word_count(text: &str) -> usize {
+    normalize(text).split_whitespace().count()
+}
+
+/// Drop the separator after the phrase ("digite: ...") and the sentence's final period,
+/// which aren't part of a name, path or snippet
+fn trim_argument(argument: &str) -> &str {
+    argument
+        .trim_start_matches(|c: char| c.is_whitespace() || matches!(c, ',' | ';' | ':'))
+        .trim_end()
+        .trim_end_matches('.')
+        .trim_end()
+}
+
+/// Ask before running a shell command (blocks until the user answers)
+fn confirm(command_line: &str) -> bool {
+    let text = HSTRING::from(format!("Executar este comando?\n\n{}", command_line));
+    let caption = HSTRING::from("Dicta - Comando de voz");
+    unsafe { MessageBoxW(HWND::default(), &text, &caption, MB_YESNO | MB_ICONQUESTION | MB_TOPMOST) == IDYES }
+}
+
+/// Run `intent` with `argument` (what was said after its phrase)
+pub fn execute(app: &AppHandle, intent: &VoiceIntent, argument: &str) -> Result<(), String> {
+    let value = intent.value.replace("{text}", argument);
+    match IntentAction::parse(&intent.action)? {
+        IntentAction::OpenApp => {
+            // `start` resolves app names, paths and URLs; the empty title keeps quoted targets intact
+            Command::new("cmd")
+                .args(["/C", "start", "", &value])
+                .creation_flags(CREATE_NO_WINDOW)
+                .spawn()
+                .map_err(|e| format!("Failed to open '{}': {}", value, e))?;
+        }
+        IntentAction::TypeSnippet => crate::auto_paste_text(app, &value)?,
+        IntentAction::Shell => {
+            if !confirm(&value) {
+                println!("🗣️ Shell command not confirmed: {}", value);
+                return Ok(());
+            }
+            Command::new("cmd")
+                .args(["/C", &value])
+                .creation_flags(CREATE_NO_WINDOW)
+                .spawn()
+                .map_err(|e| format!("Failed to run '{}': {}", value, e))?;
+        }
+    }
+    Ok(())
+}
@@ -1,15 +1,53 @@
 import { useState, useEffect, useRef } from "react";
 import { listen } from "@tauri-apps/api/event";
 import { invoke } from "@tauri-apps/api/core";
-import { playStartSound, playStopSound, playCancelSound, playResponseSound } from "./sounds";
-import { check } from "@tauri-apps/plugin-updater";
-import { relaunch } from "@tauri-apps/plugin-process";
+import { listenAppEvent, type TranscriptSegment, type UpdateInfo, type WordConfidence } from "./events";
+import { playCancelSound, playResponseSound } from "./sounds";
 
 interface TranscriptionEntry {
+  id?: number | null;
+  session_id?: string | null;
   text: string;
   timestamp: number;
+  title?: string | null;
+  language?: string | null;
+  mode?: string | null; // "transcription", "prompt" or "cancelled"
+  word_confidence?: WordConfidence[] | null; // Whisper's confidence in each word, when reported
 }
 
+interface TranscriptionRevision {
+  id: number;
+  transcription_id: number;
+  text: string;
+  kind: string; // "original", "post_processed", "cleaned", "edited" or "retranscription"
+  model?: string | null;
+  created_at: number;
+}
+
+const REVISION_KINDS: Record<string, string> = {
+  original: "Original",
+  post_processed: "Pós-processado",
+  cleaned: "Revisado",
+  edited: "Editado",
+  retranscription: "Re-transcrito",
+};
+
+type TitleMode = "off" | "local" | "gpt";
+
+interface TtsAutoPlay {
+  transcriptions: boolean;
+  prompt_responses: boolean;
+  min_chars: number; // 0 = speak everything
+}
+
+interface TtsVoice {
+  voice: string;
+  speed: number | null; // null = follow the global speed
+}
+
+// Prompt models that can have their own TTS voice
+const VOICE_PROMPT_MODELS = ["gpt-4o-mini", "gpt-4.1"];
+
 interface StatsData {
   total_words: number;
   total_transcriptions: number;
@@ -25,6 +63,68 @@ interface PendingQueueItem {
   model: string;
   created_at: number;
   retry_count: number;
+  session_id: string | null;
+}
+
+interface RequestTimeouts {
+  transcription_secs: number | null;
+  response_secs: number | null;
+  tts_secs: number | null;
+}
+
+interface WhisperDecoding {
+  temperature: number;
+  temperature_sweep: boolean;
+  no_speech_threshold: number | null; // null keeps segments that look like silence
+  confidence_threshold: number;
+  keep_low_confidence: boolean; // underlined in the history instead of dropped
+}
+
+interface SidetoneSettings {
+  enabled: boolean;
+  volume: number; // 0-100
+  latency_ms: number;
+}
+
+interface HotkeyBinding {
+  action: string;
+  label: string;
+  hotkey: string; // empty when the combo couldn't be registered
+  default_hotkey: string;
+}
+
+interface PlainTextSettings {
+  transcription: boolean;
+  prompt: boolean;
+}
+
+interface ProfanitySettings {
+  enabled: boolean;
+  words: string[];
+}
+
+interface PluginInfo {
+  name: string;
+  enabled: boolean;
+  hooks: string[];
+  error: string | null;
+}
+
+interface PluginsOverview {
+  dir: string;
+  plugins: PluginInfo[];
+}
+
+interface QueueLimits {
+  max_items: number;
+  max_disk_mb: number;
+}
+
+interface ProxySettings {
+  url: string;
+  username: string | null;
+  password: string | null;
+  bypass: string;
 }
 
 function App() {
@@ -33,23 +133,81 @@ function App() {
   const [currentView, setCurrentView] = useState<"home" | "history" | "queue" | "stats" | "settings">("home");
   const [availableMicrophones, setAvailableMicrophones] = useState<string[]>([]);
   const [selectedMicrophone, setSelectedMicrophone] = useState<string>("");
+  const [microphonePriority, setMicrophonePriority] = useState<string[]>([]); // Preferred mics, first connected one wins
+  const [sidetone, setSidetone] = useState<SidetoneSettings>({ enabled: false, volume: 50, latency_ms: 40 });
   const [transcriptionHistory, setTranscriptionHistory] = useState<TranscriptionEntry[]>([]);
   const [useRealtimeAPI, setUseRealtimeAPI] = useState(true); // Toggle between Whisper and Realtime
-  const [currentTranscript, setCurrentTranscript] = useState(""); // Real-time transcript display
+  const [realtimeNativePrompt, setRealtimeNativePrompt] = useState(false);
+  const [realtimeTextRepair, setRealtimeTextRepair] = useState(true);
+  const [realtimeSessionGrace, setRealtimeSessionGrace] = useState(60);
+  const [maxRecordingMinutes, setMaxRecordingMinutes] = useState(6);
+  const [realtimeNoiseReduction, setRealtimeNoiseReduction] = useState("off");
+  // Real-time transcript display: turns in spoken order (`order` is the seq of the turn's first event)
+  const [liveSegments, setLiveSegments] = useState<(TranscriptSegment & { order: number })[]>([]);
+  const currentTranscript = liveSegments.map((s) => s.text.trim()).filter(Boolean).join(" ");
+  const liveTranscriptRef = useRef<HTMLDivElement>(null);
   const [statsData, setStatsData] = useState<StatsData | null>(null);
   const [statsRange, setStatsRange] = useState<"today" | "7days" | "month" | "year" | "all">("month");
   const [showStatsDetails, setShowStatsDetails] = useState(false);
   const [ttsEnabled, setTtsEnabled] = useState(false);
+  const [ttsQueue, setTtsQueue] = useState(false);
+  const [ttsStreamResponses, setTtsStreamResponses] = useState(true);
+  const [ttsSpeed, setTtsSpeed] = useState(1);
+  const [ttsVoices, setTtsVoices] = useState<string[]>([]);
+  const [ttsVoice, setTtsVoice] = useState("nova");
+  const [ttsModelVoices, setTtsModelVoices] = useState<Record<string, TtsVoice>>({});
+  const [ttsAutoPlay, setTtsAutoPlay] = useState<TtsAutoPlay>({
+    transcriptions: false,
+    prompt_responses: true,
+    min_chars: 0,
+  });
+  const [reviewBeforePaste, setReviewBeforePaste] = useState(false);
+  const [restoreFocusBeforePaste, setRestoreFocusBeforePaste] = useState(true);
+  const [pasteOnClick, setPasteOnClick] = useState(false);
+  const [richTextPaste, setRichTextPaste] = useState(false);
+  const [numberNormalization, setNumberNormalization] = useState("off");
+  const [profanity, setProfanity] = useState<ProfanitySettings>({ enabled: false, words: [] });
+  const [profanityWords, setProfanityWords] = useState("");
+  const [plugins, setPlugins] = useState<PluginsOverview>({ dir: "", plugins: [] });
+  const [plainText, setPlainText] = useState<PlainTextSettings>({ transcription: false, prompt: false });
+  const [incognito, setIncognito] = useState(false);
+  const [archiveAudio, setArchiveAudio] = useState(false);
+  const [clipboardHistoryExclusion, setClipboardHistoryExclusion] = useState(true);
+  const [retranscribeModel, setRetranscribeModel] = useState("gpt-4o-transcribe");
+  const [retranscriptions, setRetranscriptions] = useState<Record<number, string>>({});
+  const [revisions, setRevisions] = useState<Record<number, TranscriptionRevision[]>>({});
+  const [assistantMode, setAssistantMode] = useState(false);
+  const [bluetoothMicFallback, setBluetoothMicFallback] = useState(false);
+  const [exclusiveCapture, setExclusiveCapture] = useState(false);
+  const [hotkeys, setHotkeys] = useState<HotkeyBinding[]>([]);
+  const [hotkeyError, setHotkeyError] = useState("");
+  const [queueLimits, setQueueLimits] = useState<QueueLimits>({ max_items: 3, max_disk_mb: 200 });
+  const [requestTimeouts, setRequestTimeouts] = useState<RequestTimeouts>({ transcription_secs: 60, response_secs: 120, tts_secs: 30 });
+  const [whisperDecoding, setWhisperDecoding] = useState<WhisperDecoding>({
+    temperature: 0,
+    temperature_sweep: false,
+    no_speech_threshold: null,
+    confidence_threshold: 0.7,
+    keep_low_confidence: false,
+  });
+  const [proxySettings, setProxySettings] = useState<ProxySettings>({ url: "", username: null, password: null, bypass: "" });
   const [queueCount, setQueueCount] = useState(0);
+  const [isOnline, setIsOnline] = useState(true);
+  const [failedPaste, setFailedPaste] = useState<string | null>(null);
   const [queueRetrying, setQueueRetrying] = useState(false);
   const [queueItems, setQueueItems] = useState<PendingQueueItem[]>([]);
   const [updateAvailable, setUpdateAvailable] = useState(false);
   const [updateVersion, setUpdateVersion] = useState("");
   const [isUpdating, setIsUpdating] = useState(false);
   const [updateProgress, setUpdateProgress] = useState("");
+  const [updateChannel, setUpdateChannel] = useState<"stable" | "beta">("stable");
+  const [titleMode, setTitleMode] = useState<TitleMode>("local");
 
   // Refs to always have current values inside event listener closures
   const isRecordingRef = useRef(false);
+  const [languageFilter, setLanguageFilter] = useState(""); // "" = all languages
+  const languageFilterRef = useRef(""); // Read by history reloads triggered from event listeners
+  const [historyLanguages, setHistoryLanguages] = useState<string[]>([]);
   const isStartingRef = useRef(false);
   const isStoppingRef = useRef(false);
 
@@ -61,11 +219,7 @@ function App() {
 
     try {
       isStartingRef.current = true;
-      setCurrentTranscript(""); // Reset transcript
-
-      // Play start sound and wait for it to finish before backend mutes system audio
-      playStartSound();
-      await new Promise(resolve => setTimeout(resolve, 200));
+      setLiveSegments([]); // Reset transcript
 
       // Query backend for current mode (source of truth)
       const useRealtime = await invoke<boolean>("get_use_realtime");
@@ -103,9 +257,6 @@ function App() {
       isStoppingRef.current = true;
       setStatus("⏳ Processing...");
 
-      // Play stop sound
-      playStopSound();
-
       // Query backend for current mode (source of truth)
       const useRealtime = await invoke<boolean>("get_use_realtime");
       console.log(`🎯 Backend mode for stop: ${useRealtime ? 'Realtime' : 'Whisper'}`);
@@ -138,8 +289,11 @@ function App() {
 
   const loadTranscriptionHistory = async () => {
     try {
-      const history = await invoke<TranscriptionEntry[]>("get_transcription_history");
+      const history = await invoke<TranscriptionEntry[]>("get_transcription_history", {
+        language: languageFilterRef.current || null,
+      });
       setTranscriptionHistory(history); // Already ordered by backend (most recent first)
+      invoke<string[]>("get_history_languages").then((v) => setHistoryLanguages(v)).catch(() => {});
     } catch (error) {
       console.error("Failed to load history:", error);
     }
@@ -171,6 +325,9 @@ function App() {
       const mics = await invoke<string[]>("list_microphones");
       setAvailableMicrophones(mics);
 
+      invoke<string[]>("get_microphone_priority").then((v) => setMicrophonePriority(v)).catch(() => {});
+      invoke<SidetoneSettings>("get_sidetone_settings").then((v) => setSidetone(v)).catch(() => {});
+
       const selected = await invoke<string | null>("get_selected_microphone");
       if (selected) {
         setSelectedMicrophone(selected);
@@ -182,6 +339,179 @@ function App() {
     }
   };
 
+  const saveProxySettings = async () => {
+    try {
+      await invoke("set_proxy_settings", { settings: proxySettings });
+      setStatus(proxySettings.url.trim() ? "🌐 Proxy salvo!" : "🌐 Proxy desativado");
+      setTimeout(() => setStatus("Ready"), 2000);
+    } catch (error) {
+      console.error("Failed to save proxy settings:", error);
+      setStatus(`❌ ${error}`);
+    }
+  };
+
+  // History text with the words Whisper was unsure of underlined, when its confidence was reported
+  const withConfidence = (entry: TranscriptionEntry) => {
+    if (!entry.word_confidence?.length) return entry.text;
+    return entry.word_confidence.map((w, i) => {
+      const low = w.probability !== null && w.probability < whisperDecoding.confidence_threshold;
+      return (
+        <span key={i}>
+          {i > 0 && " "}
+          {low ? (
+            <span
+              className="underline decoration-wavy decoration-yellow-500"
+              title={`Confiança: ${Math.round((w.probability ?? 0) * 100)}%`}
+            >
+              {w.word}
+            </span>
+          ) : (
+            w.word
+          )}
+        </span>
+      );
+    });
+  };
+
+  const updateWhisperDecoding = async (changes: Partial<WhisperDecoding>) => {
+    const updated = { ...whisperDecoding, ...changes };
+    setWhisperDecoding(updated);
+    try {
+      await invoke("set_whisper_decoding", { decoding: updated });
+    } catch (error) {
+      console.error("Failed to save Whisper decoding settings:", error);
+    }
+  };
+
+  // Empty or 0 = no limit
+  const updateRequestTimeout = async (key: keyof RequestTimeouts, value: string) => {
+    const secs = parseInt(value, 10);
+    const updated = { ...requestTimeouts, [key]: secs > 0 ? secs : null };
+    setRequestTimeouts(updated);
+    try {
+      await invoke("set_request_timeouts", { timeouts: updated });
+    } catch (error) {
+      console.error("Failed to save request timeouts:", error);
+    }
+  };
+
+  // Empty combo = back to the default
+  const updateHotkey = async (action: string, hotkey: string) => {
+    try {
+      await invoke("set_hotkey", { action, hotkey });
+      setHotkeyError("");
+    } catch (error) {
+      setHotkeyError(String(error));
+    }
+    invoke<HotkeyBinding[]>("get_hotkeys").then((v) => setHotkeys(v)).catch(() => {});
+  };
+
+  const togglePlugin = async (plugin: PluginInfo) => {
+    try {
+      await invoke("set_plugin_enabled", { name: plugin.name, enabled: !plugin.enabled });
+      setPlugins((prev) => ({
+        ...prev,
+        plugins: prev.plugins.map((p) => (p.name === plugin.name ? { ...p, enabled: !p.enabled } : p)),
+      }));
+    } catch (error) {
+      console.error("Failed to toggle plugin:", error);
+    }
+  };
+
+  const reloadPlugins = async () => {
+    try {
+      setPlugins(await invoke<PluginsOverview>("reload_plugins"));
+    } catch (error) {
+      console.error("Failed to reload plugins:", error);
+    }
+  };
+
+  const togglePlainText = async (key: keyof PlainTextSettings) => {
+    const updated = { ...plainText, [key]: !plainText[key] };
+    setPlainText(updated);
+    try {
+      await invoke("set_plain_text_settings", { settings: updated });
+    } catch (error) {
+      console.error("Failed to save plain text settings:", error);
+    }
+  };
+
+  const updateQueueLimit = async (key: keyof QueueLimits, value: string) => {
+    const n = parseInt(value, 10);
+    if (!(n > 0)) return;
+    const updated = { ...queueLimits, [key]: n };
+    setQueueLimits(updated);
+    try {
+      await invoke("set_queue_limits", { limits: updated });
+    } catch (error) {
+      console.error("Failed to save queue limits:", error);
+    }
+  };
+
+  const updateTtsModelVoice = async (model: string, voice: string | null, speed: number | null) => {
+    try {
+      await invoke("set_tts_model_voice", { model, voice, speed });
+      setTtsModelVoices(await invoke<Record<string, TtsVoice>>("get_tts_model_voices"));
+    } catch (error) {
+      console.error("Failed to save TTS voice:", error);
+    }
+  };
+
+  const updateTtsAutoPlay = async (changes: Partial<TtsAutoPlay>) => {
+    const settings = { ...ttsAutoPlay, ...changes };
+    setTtsAutoPlay(settings);
+    try {
+      await invoke("set_tts_auto_play", { settings });
+    } catch (error) {
+      console.error("Failed to save TTS auto-play settings:", error);
+    }
+  };
+
+  const selectTitleMode = async (mode: TitleMode) => {
+    setTitleMode(mode);
+    try {
+      await invoke("set_title_mode", { mode });
+    } catch (error) {
+      console.error("Failed to save title mode:", error);
+    }
+  };
+
+  const selectUpdateChannel = async (channel: "stable" | "beta") => {
+    setUpdateChannel(channel);
+    try {
+      await invoke("set_update_channel", { channel });
+      const update = await invoke<UpdateInfo | null>("check_for_updates");
+      if (update) showUpdate(update);
+    } catch (error) {
+      console.error("Failed to switch update channel:", error);
+    }
+  };
+
+  const saveMicrophonePriority = async (devices: string[]) => {
+    setMicrophonePriority(devices);
+    try {
+      await invoke("set_microphone_priority", { devices });
+    } catch (error) {
+      console.error("Failed to save microphone priority:", error);
+    }
+  };
+
+  const saveSidetone = async (settings: SidetoneSettings) => {
+    setSidetone(settings);
+    try {
+      await invoke("set_sidetone_settings", { settings });
+    } catch (error) {
+      console.error("Failed to save sidetone settings:", error);
+    }
+  };
+
+  const moveMicrophone = (index: number, offset: number) => {
+    const devices = [...microphonePriority];
+    const [device] = devices.splice(index, 1);
+    devices.splice(index + offset, 0, device);
+    saveMicrophonePriority(devices);
+  };
+
   const selectMicrophone = async (deviceName: string) => {
     try {
       await invoke("set_selected_microphone", { deviceName });
@@ -227,13 +557,23 @@ function App() {
     }
 
     try {
-      const data = await invoke<StatsData>("get_statistics", { fromTs, toTs: now });
+      const data = await invoke<StatsData>("get_statistics", {
+        fromTs,
+        toTs: now,
+        language: languageFilterRef.current || null,
+      });
       setStatsData(data);
     } catch (error) {
       console.error("Failed to load stats:", error);
     }
   };
 
+  // Keep the end of the live transcript in view as it grows
+  useEffect(() => {
+    const el = liveTranscriptRef.current;
+    if (el) el.scrollTop = el.scrollHeight;
+  }, [currentTranscript]);
+
   useEffect(() => {
     if (currentView === "stats") {
       loadStats(statsRange);
@@ -241,22 +581,79 @@ function App() {
     if (currentView === "queue") {
       loadQueueItems();
     }
-  }, [currentView, statsRange]);
+  }, [currentView, statsRange, languageFilter]);
+
+  const selectLanguageFilter = (language: string) => {
+    languageFilterRef.current = language;
+    setLanguageFilter(language);
+    loadTranscriptionHistory();
+  };
+
+  const languageSelect = historyLanguages.length > 1 && (
+    <select
+      value={languageFilter}
+      onChange={(e) => selectLanguageFilter(e.target.value)}
+      title="Filtrar por idioma"
+      className="px-2 py-1 bg-gray-700 text-white rounded border border-gray-600 focus:border-blue-500 focus:outline-none text-xs"
+    >
+      <option value="">Todos os idiomas</option>
+      {historyLanguages.map((language) => (
+        <option key={language} value={language}>
+          {language.toUpperCase()}
+        </option>
+      ))}
+    </select>
+  );
 
   useEffect(() => {
     // Load history and microphones on mount
     loadTranscriptionHistory();
     loadMicrophones();
     invoke<boolean>("get_tts_enabled").then((v) => setTtsEnabled(v)).catch(() => {});
+    invoke<TtsAutoPlay>("get_tts_auto_play").then((v) => setTtsAutoPlay(v)).catch(() => {});
+    invoke<boolean>("get_tts_queue").then((v) => setTtsQueue(v)).catch(() => {});
+    invoke<boolean>("get_realtime_native_prompt").then((v) => setRealtimeNativePrompt(v)).catch(() => {});
+    invoke<boolean>("get_realtime_text_repair").then((v) => setRealtimeTextRepair(v)).catch(() => {});
+    invoke<number>("get_realtime_session_grace").then((v) => setRealtimeSessionGrace(v)).catch(() => {});
+    invoke<number>("get_max_recording_secs").then((v) => setMaxRecordingMinutes(Math.round(v / 60))).catch(() => {});
+    invoke<string>("get_realtime_noise_reduction").then((v) => setRealtimeNoiseReduction(v)).catch(() => {});
+    invoke<boolean>("get_tts_stream_responses").then((v) => setTtsStreamResponses(v)).catch(() => {});
+    invoke<number>("get_tts_speed").then((v) => setTtsSpeed(v)).catch(() => {});
+    invoke<string[]>("get_tts_voices").then((v) => setTtsVoices(v)).catch(() => {});
+    invoke<string>("get_tts_voice").then((v) => setTtsVoice(v)).catch(() => {});
+    invoke<Record<string, TtsVoice>>("get_tts_model_voices").then((v) => setTtsModelVoices(v)).catch(() => {});
+    invoke<boolean>("get_review_before_paste").then((v) => setReviewBeforePaste(v)).catch(() => {});
+    invoke<boolean>("get_restore_focus_before_paste").then((v) => setRestoreFocusBeforePaste(v)).catch(() => {});
+    invoke<boolean>("get_paste_on_click").then((v) => setPasteOnClick(v)).catch(() => {});
+    invoke<boolean>("get_rich_text_paste").then((v) => setRichTextPaste(v)).catch(() => {});
+    invoke<string>("get_number_normalization").then((v) => setNumberNormalization(v)).catch(() => {});
+    invoke<ProfanitySettings>("get_profanity_settings")
+      .then((v) => {
+        setProfanity(v);
+        setProfanityWords(v.words.join(", "));
+      })
+      .catch(() => {});
+    invoke<TitleMode>("get_title_mode").then((v) => setTitleMode(v)).catch(() => {});
+    invoke<PluginsOverview>("get_plugins").then((v) => setPlugins(v)).catch(() => {});
+    invoke<PlainTextSettings>("get_plain_text_settings").then((v) => setPlainText(v)).catch(() => {});
+    invoke<boolean>("get_incognito").then((v) => setIncognito(v)).catch(() => {});
+    invoke<boolean>("get_archive_session_audio").then((v) => setArchiveAudio(v)).catch(() => {});
+    invoke<boolean>("get_clipboard_history_exclusion").then((v) => setClipboardHistoryExclusion(v)).catch(() => {});
+    invoke<boolean>("get_assistant_mode").then((v) => setAssistantMode(v)).catch(() => {});
+    invoke<boolean>("get_bluetooth_mic_fallback").then((v) => setBluetoothMicFallback(v)).catch(() => {});
+    invoke<boolean>("get_exclusive_capture").then((v) => setExclusiveCapture(v)).catch(() => {});
+    invoke<ProxySettings>("get_proxy_settings").then((v) => setProxySettings(v)).catch(() => {});
+    invoke<HotkeyBinding[]>("get_hotkeys").then((v) => setHotkeys(v)).catch(() => {});
+    invoke<boolean>("get_network_status").then((v) => setIsOnline(v)).catch(() => {});
+    invoke<QueueLimits>("get_queue_limits").then((v) => setQueueLimits(v)).catch(() => {});
+    invoke<RequestTimeouts>("get_request_timeouts").then((v) => setRequestTimeouts(v)).catch(() => {});
+    invoke<WhisperDecoding>("get_whisper_decoding").then((v) => setWhisperDecoding(v)).catch(() => {});
     invoke<number>("get_queue_count").then((v) => setQueueCount(v)).catch(() => {});
 
-    // Check for updates
-    check().then((update) => {
-      if (update?.available) {
-        setUpdateAvailable(true);
-        setUpdateVersion(update.version);
-        console.log("🔄 Update available:", update.version);
-      }
+    // Check for updates (the backend keeps checking in the background and emits update-available)
+    invoke<"stable" | "beta">("get_update_channel").then((v) => setUpdateChannel(v)).catch(() => {});
+    invoke<UpdateInfo | null>("check_for_updates").then((update) => {
+      if (update) showUpdate(update);
     }).catch((e) => {
       // Don't block app if update check fails (offline, etc.)
       console.log("Update check skipped:", e);
@@ -267,7 +664,7 @@ function App() {
     console.log("🎧 Setting up event listeners");
 
     // Listen for hotkey events - uses refs to avoid stale closure values
-    const unlistenHotkey = listen("toggle-recording", async () => {
+    const unlistenHotkey = listenAppEvent("toggle-recording", async () => {
       console.log(`✅ Hotkey event received (recording=${isRecordingRef.current}, stopping=${isStoppingRef.current}, starting=${isStartingRef.current})`);
 
       if (isRecordingRef.current) {
@@ -282,15 +679,14 @@ function App() {
     });
 
     // Listen for widget stop event
-    const unlistenWidgetStop = listen("widget-stop-recording", async () => {
+    const unlistenWidgetStop = listenAppEvent("widget-stop-recording", async () => {
       console.log("🛑 Widget stop event received");
       await stopRecording();
     });
 
     // Listen for widget cancel event
-    const unlistenWidgetCancel = listen("recording-cancelled", async () => {
+    const unlistenWidgetCancel = listenAppEvent("recording-cancelled", async () => {
       console.log("❌ Widget cancel event received");
-      playCancelSound();
       isRecordingRef.current = false;
       isStartingRef.current = false;
       isStoppingRef.current = false;
@@ -306,15 +702,27 @@ function App() {
     });
 
     // Listen for history updates
-    const unlistenHistory = listen("history-updated", async () => {
+    const unlistenHistory = listenAppEvent("history-updated", async () => {
       console.log("📚 History updated, reloading...");
       await loadTranscriptionHistory();
     });
 
     // Listen for realtime transcription deltas
-    const unlistenDelta = listen<string>("transcription-delta", (event) => {
-      console.log("📝 Delta received:", event.payload);
-      setCurrentTranscript((prev) => prev + event.payload);
+    const unlistenDelta = listenAppEvent("transcription-delta", (event) => {
+      const { segment } = event.payload;
+      console.log("📝 Delta received:", event.payload.session_id, segment.item_id, segment.seq);
+      setLiveSegments((prev) => {
+        const index = prev.findIndex((s) => s.item_id === segment.item_id);
+        if (index === -1) {
+          // A new turn: keep the list in event order, even if it arrived late
+          return [...prev, { ...segment, order: segment.seq }].sort((a, b) => a.order - b.order);
+        }
+        if (prev[index].seq > segment.seq) {
+          return prev; // Out of order: a newer version of this turn is already shown
+        }
+        // Replace in place (corrected text); the turn keeps its position
+        return prev.map((s, i) => (i === index ? { ...segment, order: s.order } : s));
+      });
     });
 
     // Listen for transcription completion
@@ -355,20 +763,75 @@ function App() {
       }
     });
 
+    // Listen for auto-pastes that didn't land (the text stays on the clipboard)
+    const unlistenPasteFailed = listenAppEvent("paste-failed", (event) => {
+      setFailedPaste(event.payload.text);
+    });
+
+    // Listen for results held until the next click
+    const unlistenReadyToPaste = listenAppEvent("ready-to-paste", () => {
+      setStatus("🖱️ Pronto para colar - clique onde quer o texto");
+    });
+
+    // Listen for pastes refused because the focus was on a password field
+    const unlistenPasswordField = listenAppEvent("password-field-focused", () => {
+      setStatus("🔒 Campo de senha - texto não colado, está na área de transferência");
+      setTimeout(() => setStatus("Ready"), 5000);
+    });
+
+    // Listen for requests cancelled with abort_current_request
+    const unlistenRequestCancelled = listenAppEvent("request-cancelled", () => {
+      setStatus("🛑 Resposta cancelada");
+      setTimeout(() => setStatus("Ready"), 3000);
+    });
+
+    // Listen for silent recordings (nothing was transcribed or saved)
+    const unlistenNoSpeech = listenAppEvent("no-speech-detected", () => {
+      setStatus("Nenhuma fala detectada");
+      setTimeout(() => setStatus("Ready"), 3000);
+    });
+
+    // Listen for Bluetooth mics (capturing drops the headset to hands-free quality)
+    const unlistenBluetoothMic = listenAppEvent("bluetooth-mic-warning", (event) => {
+      const { device, replacement } = event.payload;
+      setStatus(
+        replacement
+          ? `🎧 ${device} é Bluetooth - gravando com ${replacement}`
+          : `🎧 ${device} é Bluetooth - a qualidade do áudio vai cair`
+      );
+      setTimeout(() => setStatus("Ready"), 5000);
+    });
+
+    // Listen for OpenAI rate limits (requests are delayed, not dropped)
+    const unlistenRateLimited = listenAppEvent("rate-limited", (event) => {
+      const seconds = Math.ceil(event.payload.retry_after_ms / 1000);
+      setStatus(`⏳ Limite de requisições da OpenAI - aguardando ${seconds}s`);
+      setTimeout(() => setStatus("Ready"), event.payload.retry_after_ms);
+    });
+
     // Listen for response-ready (play notification sound)
-    const unlistenResponse = listen("response-ready", () => {
+    const unlistenResponse = listenAppEvent("response-ready", () => {
       console.log("🔔 Response ready, playing notification sound");
       playResponseSound();
     });
 
+    // Listen for incognito toggles (from Alt+Shift+I hotkey or tray)
+    const unlistenIncognito = listenAppEvent("incognito-toggled", (event) => {
+      setIncognito(event.payload);
+    });
+    const unlistenAssistant = listenAppEvent("assistant-mode-changed", (event) => {
+      setAssistantMode(event.payload);
+    });
+
     // Listen for TTS toggle events (from Ctrl+Alt+S hotkey)
-    const unlistenTts = listen<boolean>("tts-toggled", (event) => {
+    const unlistenTts = listenAppEvent("tts-toggled", (event) => {
       console.log("🔊 TTS toggled:", event.payload);
       setTtsEnabled(event.payload);
     });
+    const unlistenTtsSpeed = listenAppEvent("tts-speed-changed", (event) => setTtsSpeed(event.payload));
 
     // Listen for queue events
-    const unlistenQueueUpdated = listen<number>("queue-updated", (event) => {
+    const unlistenQueueUpdated = listenAppEvent("queue-updated", (event) => {
       console.log("📋 Queue updated:", event.payload);
       setQueueCount(event.payload);
       invoke<PendingQueueItem[]>("get_queue_items")
@@ -376,32 +839,39 @@ function App() {
         .catch(() => {});
     });
 
-    const unlistenQueueFull = listen("queue-full", () => {
+    const unlistenQueueFull = listenAppEvent("queue-full", () => {
       console.log("⚠️ Queue full");
-      setStatus("Fila offline cheia (max 3)");
+      setStatus("Fila offline cheia");
       setTimeout(() => setStatus("Ready"), 3000);
     });
 
-    const unlistenQueueCompleted = listen("queue-item-completed", () => {
+    const unlistenQueueCompleted = listenAppEvent("queue-item-completed", () => {
       console.log("✅ Queue item completed");
       invoke<number>("get_queue_count").then((v) => setQueueCount(v)).catch(() => {});
     });
 
+    // Listen for connectivity changes
+    const unlistenNetworkOnline = listenAppEvent("network-online", () => setIsOnline(true));
+    const unlistenNetworkOffline = listenAppEvent("network-offline", () => setIsOnline(false));
+
+    // Listen for updates found by the background check
+    const unlistenUpdate = listenAppEvent("update-available", (event) => showUpdate(event.payload));
+
     // Listen for offline recording (no internet, mic still recording locally)
-    const unlistenRecordingOffline = listen<string>("recording-offline", (event) => {
-      console.warn("📡 Recording offline:", event.payload);
+    const unlistenRecordingOffline = listenAppEvent("recording-offline", (event) => {
+      console.warn("📡 Recording offline:", event.payload.session_id, event.payload.message);
       setStatus("Sem conexão — gravando localmente");
     });
 
     // Listen for recording errors (connection drops, API failures)
-    const unlistenRecordingError = listen<string>("recording-error", (event) => {
-      console.error("❌ Recording error:", event.payload);
+    const unlistenRecordingError = listenAppEvent("recording-error", (event) => {
+      console.error("❌ Recording error:", event.payload.session_id, event.payload.message);
       isRecordingRef.current = false;
       isStartingRef.current = false;
       isStoppingRef.current = false;
       setIsRecording(false);
-      setStatus(`Erro: ${event.payload}`);
-      setCurrentTranscript("");
+      setStatus(`Erro: ${event.payload.message}`);
+      setLiveSegments([]);
       playCancelSound();
       setTimeout(() => setStatus("Ready"), 5000);
     });
@@ -414,8 +884,21 @@ function App() {
       unlistenHistory.then((fn) => fn());
       unlistenDelta.then((fn) => fn());
       unlistenTranscription.then((fn) => fn());
+      unlistenNoSpeech.then((fn) => fn());
+      unlistenPasteFailed.then((fn) => fn());
+      unlistenRequestCancelled.then((fn) => fn());
+      unlistenReadyToPaste.then((fn) => fn());
+      unlistenPasswordField.then((fn) => fn());
+      unlistenBluetoothMic.then((fn) => fn());
+      unlistenRateLimited.then((fn) => fn());
+      unlistenNetworkOnline.then((fn) => fn());
+      unlistenNetworkOffline.then((fn) => fn());
+      unlistenUpdate.then((fn) => fn());
+      unlistenIncognito.then((fn) => fn());
+      unlistenAssistant.then((fn) => fn());
       unlistenResponse.then((fn) => fn());
       unlistenTts.then((fn) => fn());
+      unlistenTtsSpeed.then((fn) => fn());
       unlistenQueueUpdated.then((fn) => fn());
       unlistenQueueFull.then((fn) => fn());
       unlistenQueueCompleted.then((fn) => fn());
@@ -450,21 +933,18 @@ function App() {
     }
   };
 
+  const showUpdate = (update: UpdateInfo) => {
+    console.log(`🔄 Update available: ${update.version} (${update.channel})`);
+    setUpdateAvailable(true);
+    setUpdateVersion(update.version);
+  };
+
   const applyUpdate = async () => {
     setIsUpdating(true);
-    setUpdateProgress("Baixando...");
+    setUpdateProgress("Baixando e instalando...");
     try {
-      const update = await check();
-      if (update?.available) {
-        await update.downloadAndInstall((event) => {
-          if (event.event === "Started" && event.data.contentLength) {
-            setUpdateProgress(`Baixando... (${Math.round(event.data.contentLength / 1024)}KB)`);
-          } else if (event.event === "Finished") {
-            setUpdateProgress("Instalando...");
-          }
-        });
-        await relaunch();
-      }
+      // Restarts the app once installed
+      await invoke("install_update");
     } catch (e) {
       console.error("Update failed:", e);
       setUpdateProgress("Falha na atualização. Tente novamente.");
@@ -599,6 +1079,79 @@ function App() {
                   </button>
                 </div>
               </div>
+              <label className="flex items-center justify-between text-sm text-gray-400 border-b border-gray-700 pb-4">
+                Duração máxima da gravação (minutos)
+                <input
+                  type="number"
+                  min={1}
+                  value={maxRecordingMinutes}
+                  onChange={async (e) => {
+                    const minutes = Math.max(1, parseInt(e.target.value) || 1);
+                    setMaxRecordingMinutes(minutes);
+                    await invoke("set_max_recording_secs", { secs: minutes * 60 });
+                  }}
+                  className="w-20 px-2 py-1 bg-gray-700 text-white rounded border border-gray-600 focus:border-blue-500 focus:outline-none text-xs"
+                />
+              </label>
+              {useRealtimeAPI && (
+                <label className="flex items-center justify-between text-sm text-gray-400 border-b border-gray-700 pb-4">
+                  Redução de ruído
+                  <select
+                    value={realtimeNoiseReduction}
+                    onChange={async (e) => {
+                      setRealtimeNoiseReduction(e.target.value);
+                      await invoke("set_realtime_noise_reduction", { mode: e.target.value });
+                    }}
+                    className="px-2 py-1 bg-gray-700 text-white rounded border border-gray-600 focus:border-blue-500 focus:outline-none text-xs"
+                  >
+                    <option value="off">Desligada</option>
+                    <option value="near_field">Microfone próximo (headset)</option>
+                    <option value="far_field">Microfone distante (notebook, sala)</option>
+                  </select>
+                </label>
+              )}
+              {useRealtimeAPI && (
+                <label className="flex items-center justify-between text-sm text-gray-400 border-b border-gray-700 pb-4">
+                  Manter a conexão aberta após parar (segundos, 0 = não)
+                  <input
+                    type="number"
+                    min={0}
+                    value={realtimeSessionGrace}
+                    onChange={async (e) => {
+                      const secs = Math.max(0, parseInt(e.target.value) || 0);
+                      setRealtimeSessionGrace(secs);
+                      await invoke("set_realtime_session_grace", { secs });
+                    }}
+                    className="w-20 px-2 py-1 bg-gray-700 text-white rounded border border-gray-600 focus:border-blue-500 focus:outline-none text-xs"
+                  />
+                </label>
+              )}
+              {useRealtimeAPI && (
+                <label className="flex items-center justify-between text-sm text-gray-400 border-b border-gray-700 pb-4">
+                  Prompts respondidos direto pelo Realtime (mais rápido, sem busca na web)
+                  <input
+                    type="checkbox"
+                    checked={realtimeNativePrompt}
+                    onChange={async (e) => {
+                      setRealtimeNativePrompt(e.target.checked);
+                      await invoke("set_realtime_native_prompt", { enabled: e.target.checked });
+                    }}
+                  />
+                </label>
+              )}
+              {useRealtimeAPI && (
+                <label className="flex items-center justify-between text-sm text-gray-400 border-b border-gray-700 pb-4">
+                  Corrigir espaços e maiúsculas entre os trechos transcritos
+                  <input
+                    type="checkbox"
+                    checked={realtimeTextRepair}
+                    onChange={async (e) => {
+                      setRealtimeTextRepair(e.target.checked);
+                      await invoke("set_realtime_text_repair", { enabled: e.target.checked });
+                    }}
+                  />
+                </label>
+              )}
 
               {/* TTS Toggle */}
               <div className="flex items-center justify-between border-b border-gray-700 pb-4">
@@ -618,6 +1171,306 @@ function App() {
                   {ttsEnabled ? "ON" : "OFF"}
                 </button>
               </div>
+              {ttsEnabled && (
+                <div className="space-y-2 border-b border-gray-700 pb-4">
+                  <label className="flex items-center justify-between text-sm text-gray-400">
+                    Ler respostas do GPT
+                    <input
+                      type="checkbox"
+                      checked={ttsAutoPlay.prompt_responses}
+                      onChange={(e) => updateTtsAutoPlay({ prompt_responses: e.target.checked })}
+                    />
+                  </label>
+                  <label className="flex items-center justify-between text-sm text-gray-400">
+                    Ler transcrições
+                    <input
+                      type="checkbox"
+                      checked={ttsAutoPlay.transcriptions}
+                      onChange={(e) => updateTtsAutoPlay({ transcriptions: e.target.checked })}
+                    />
+                  </label>
+                  <label className="flex items-center justify-between text-sm text-gray-400">
+                    Velocidade ({ttsSpeed.toFixed(2)}x, Alt+Shift+↑/↓)
+                    <input
+                      type="range"
+                      min={0.5}
+                      max={2}
+                      step={0.25}
+                      value={ttsSpeed}
+                      onChange={async (e) => {
+                        const speed = await invoke<number>("set_tts_speed", { speed: parseFloat(e.target.value) });
+                        setTtsSpeed(speed);
+                      }}
+                    />
+                  </label>
+                  <label className="flex items-center justify-between text-sm text-gray-400">
+                    Voz
+                    <select
+                      value={ttsVoice}
+                      onChange={async (e) => {
+                        setTtsVoice(e.target.value);
+                        await invoke("set_tts_voice", { voice: e.target.value });
+                      }}
+                      className="px-2 py-1 bg-gray-700 text-white rounded border border-gray-600 focus:border-blue-500 focus:outline-none text-xs"
+                    >
+                      {ttsVoices.map((voice) => (
+                        <option key={voice} value={voice}>{voice}</option>
+                      ))}
+                    </select>
+                  </label>
+                  {VOICE_PROMPT_MODELS.map((model) => {
+                    const bound = ttsModelVoices[model];
+                    return (
+                      <label key={model} className="flex items-center justify-between text-sm text-gray-400">
+                        Voz das respostas ({model})
+                        <span className="flex items-center gap-2">
+                          <select
+                            value={bound?.voice ?? ""}
+                            onChange={(e) => updateTtsModelVoice(model, e.target.value || null, bound?.speed ?? null)}
+                            className="px-2 py-1 bg-gray-700 text-white rounded border border-gray-600 focus:border-blue-500 focus:outline-none text-xs"
+                          >
+                            <option value="">Voz padrão</option>
+                            {ttsVoices.map((voice) => (
+                              <option key={voice} value={voice}>{voice}</option>
+                            ))}
+                          </select>
+                          {bound && (
+                            <select
+                              value={bound.speed ?? ""}
+                              onChange={(e) => updateTtsModelVoice(model, bound.voice, e.target.value ? parseFloat(e.target.value) : null)}
+                              className="px-2 py-1 bg-gray-700 text-white rounded border border-gray-600 focus:border-blue-500 focus:outline-none text-xs"
+                            >
+                              <option value="">Velocidade global</option>
+                              {[0.75, 1, 1.25, 1.5, 1.75, 2].map((speed) => (
+                                <option key={speed} value={speed}>{speed.toFixed(2)}x</option>
+                              ))}
+                            </select>
+                          )}
+                        </span>
+                      </label>
+                    );
+                  })}
+                  <label className="flex items-center justify-between text-sm text-gray-400">
+                    Ler respostas enquanto são geradas
+                    <input
+                      type="checkbox"
+                      checked={ttsStreamResponses}
+                      onChange={async (e) => {
+                        setTtsStreamResponses(e.target.checked);
+                        await invoke("set_tts_stream_responses", { enabled: e.target.checked });
+                      }}
+                    />
+                  </label>
+                  <label className="flex items-center justify-between text-sm text-gray-400">
+                    Enfileirar leituras (não interromper)
+                    <input
+                      type="checkbox"
+                      checked={ttsQueue}
+                      onChange={async (e) => {
+                        setTtsQueue(e.target.checked);
+                        await invoke("set_tts_queue", { enabled: e.target.checked });
+                      }}
+                    />
+                  </label>
+                  <label className="flex items-center justify-between text-sm text-gray-400">
+                    Só textos a partir de (caracteres)
+                    <input
+                      type="number"
+                      min={0}
+                      value={ttsAutoPlay.min_chars}
+                      onChange={(e) => updateTtsAutoPlay({ min_chars: Math.max(0, parseInt(e.target.value) || 0) })}
+                      className="w-20 px-2 py-1 bg-gray-700 text-white rounded border border-gray-600 focus:border-blue-500 focus:outline-none text-sm"
+                    />
+                  </label>
+                </div>
+              )}
+
+              {/* Review Before Paste Toggle */}
+              <div className="flex items-center justify-between border-b border-gray-700 pb-4">
+                <span className="text-sm text-gray-400">Revisar antes de colar:</span>
+                <button
+                  onClick={async () => {
+                    const newVal = !reviewBeforePaste;
+                    setReviewBeforePaste(newVal);
+                    await invoke("set_review_before_paste", { enabled: newVal });
+                  }}
+                  className={`px-3 py-1 rounded text-xs transition-colors ${
+                    reviewBeforePaste
+                      ? "bg-green-600 text-white"
+                      : "bg-gray-700 text-gray-300 hover:bg-gray-600"
+                  }`}
+                >
+                  {reviewBeforePaste ? "ON" : "OFF"}
+                </button>
+              </div>
+
+              {/* Restore Focus Toggle */}
+              <div className="flex items-center justify-between border-b border-gray-700 pb-4">
+                <span className="text-sm text-gray-400" title="Cola na janela que estava ativa quando a gravação começou, mesmo que você tenha clicado em outra">
+                  Colar na janela onde a gravação começou:
+                </span>
+                <button
+                  onClick={async () => {
+                    const newVal = !restoreFocusBeforePaste;
+                    setRestoreFocusBeforePaste(newVal);
+                    await invoke("set_restore_focus_before_paste", { enabled: newVal });
+                  }}
+                  className={`px-3 py-1 rounded text-xs transition-colors ${
+                    restoreFocusBeforePaste
+                      ? "bg-green-600 text-white"
+                      : "bg-gray-700 text-gray-300 hover:bg-gray-600"
+                  }`}
+                >
+                  {restoreFocusBeforePaste ? "ON" : "OFF"}
+                </button>
+              </div>
+
+              {/* Paste On Click Toggle */}
+              <div className="flex items-center justify-between border-b border-gray-700 pb-4">
+                <span className="text-sm text-gray-400" title="Depois da transcrição, espera o próximo clique (ou o atalho de colar a última transcrição) e cola onde estiver o foco">
+                  Colar no próximo clique:
+                </span>
+                <button
+                  onClick={async () => {
+                    const newVal = !pasteOnClick;
+                    setPasteOnClick(newVal);
+                    await invoke("set_paste_on_click", { enabled: newVal });
+                  }}
+                  className={`px-3 py-1 rounded text-xs transition-colors ${
+                    pasteOnClick
+                      ? "bg-green-600 text-white"
+                      : "bg-gray-700 text-gray-300 hover:bg-gray-600"
+                  }`}
+                >
+                  {pasteOnClick ? "ON" : "OFF"}
+                </button>
+              </div>
+
+              {/* Incognito Toggle */}
+              <div className="flex items-center justify-between border-b border-gray-700 pb-4">
+                <span className="text-sm text-gray-400">Modo anônimo (Alt+Shift+I):</span>
+                <button
+                  onClick={async () => {
+                    const newVal = !incognito;
+                    setIncognito(newVal);
+                    await invoke("set_incognito", { enabled: newVal });
+                  }}
+                  className={`px-3 py-1 rounded text-xs transition-colors ${
+                    incognito
+                      ? "bg-violet-600 text-white"
+                      : "bg-gray-700 text-gray-300 hover:bg-gray-600"
+                  }`}
+                >
+                  {incognito ? "ON" : "OFF"}
+                </button>
+              </div>
+
+              {/* Clipboard History Exclusion Toggle */}
+              <div className="flex items-center justify-between border-b border-gray-700 pb-4">
+                <span
+                  className="text-sm text-gray-400"
+                  title="Os ditados colados não aparecem no histórico da área de transferência (Win+V) nem em gerenciadores como o Ditto"
+                >
+                  Ocultar ditados do histórico da área de transferência:
+                </span>
+                <button
+                  onClick={async () => {
+                    const newVal = !clipboardHistoryExclusion;
+                    setClipboardHistoryExclusion(newVal);
+                    await invoke("set_clipboard_history_exclusion", { enabled: newVal });
+                  }}
+                  className={`px-3 py-1 rounded text-xs transition-colors ${
+                    clipboardHistoryExclusion
+                      ? "bg-green-600 text-white"
+                      : "bg-gray-700 text-gray-300 hover:bg-gray-600"
+                  }`}
+                >
+                  {clipboardHistoryExclusion ? "ON" : "OFF"}
+                </button>
+              </div>
+
+              {/* Audio Archive Toggle */}
+              <div className="flex items-center justify-between border-b border-gray-700 pb-4">
+                <span
+                  className="text-sm text-gray-400"
+                  title="Guarda o áudio de cada gravação para poder transcrevê-la de novo com outro modelo"
+                >
+                  Arquivar áudio das gravações:
+                </span>
+                <button
+                  onClick={async () => {
+                    const newVal = !archiveAudio;
+                    setArchiveAudio(newVal);
+                    await invoke("set_archive_session_audio", { enabled: newVal });
+                  }}
+                  className={`px-3 py-1 rounded text-xs transition-colors ${
+                    archiveAudio
+                      ? "bg-green-600 text-white"
+                      : "bg-gray-700 text-gray-300 hover:bg-gray-600"
+                  }`}
+                >
+                  {archiveAudio ? "ON" : "OFF"}
+                </button>
+              </div>
+
+              {/* Voice Assistant Toggle */}
+              <div className="flex items-center justify-between border-b border-gray-700 pb-4">
+                <span
+                  className="text-sm text-gray-400"
+                  title="Responde em voz alta e volta a ouvir; fale por cima da resposta para interromper"
+                >
+                  Assistente por voz (Alt+Shift+V):
+                </span>
+                <button
+                  onClick={async () => {
+                    await invoke("set_assistant_mode", { enabled: !assistantMode });
+                  }}
+                  className={`px-3 py-1 rounded text-xs transition-colors ${
+                    assistantMode
+                      ? "bg-green-600 text-white"
+                      : "bg-gray-700 text-gray-300 hover:bg-gray-600"
+                  }`}
+                >
+                  {assistantMode ? "ON" : "OFF"}
+                </button>
+              </div>
+
+              {/* Network Indicator */}
+              {!isOnline && (
+                <div className="border-b border-gray-700 pb-4 text-sm text-red-400">
+                  📡 Sem conexão — falhas vão para a fila e serão enviadas quando a rede voltar
+                </div>
+              )}
+
+              {/* Failed Paste */}
+              {failedPaste && (
+                <div className="flex items-center justify-between gap-2 border-b border-gray-700 pb-4">
+                  <span className="text-sm text-yellow-400 truncate" title={failedPaste}>
+                    Não foi possível colar: "{failedPaste.slice(0, 40)}{failedPaste.length > 40 ? "…" : ""}"
+                  </span>
+                  <div className="flex gap-2 shrink-0">
+                    <button
+                      onClick={async () => {
+                        try {
+                          await invoke("retry_last_paste");
+                          setFailedPaste(null);
+                        } catch (error) {
+                          console.error("Retry paste failed:", error);
+                        }
+                      }}
+                      className="px-3 py-1 rounded text-xs bg-yellow-600 text-white hover:bg-yellow-500 transition-colors"
+                    >
+                      Tentar colar de novo
+                    </button>
+                    <button
+                      onClick={() => setFailedPaste(null)}
+                      className="px-2 py-1 rounded text-xs bg-gray-700 text-gray-300 hover:bg-gray-600 transition-colors"
+                    >
+                      ✕
+                    </button>
+                  </div>
+                </div>
+              )}
 
               {/* Offline Queue Indicator */}
               {queueCount > 0 && (
@@ -649,10 +1502,12 @@ function App() {
                   </div>
 
                   {/* Real-time transcript display */}
-                  {useRealtimeAPI && currentTranscript && (
+                  {currentTranscript && (
                     <div className="mt-3 pt-3 border-t border-red-500/30">
                       <p className="text-xs text-gray-400 mb-1">Live Transcript:</p>
-                      <p className="text-sm text-white">{currentTranscript}</p>
+                      <div ref={liveTranscriptRef} className="max-h-40 overflow-y-auto">
+                        <p className="text-sm text-white">{currentTranscript}</p>
+                      </div>
                     </div>
                   )}
                 </div>
@@ -676,7 +1531,41 @@ function App() {
           </>
         ) : currentView === "history" ? (
           <div className="bg-gray-800 rounded-lg p-6">
-            <h2 className="text-xl font-semibold mb-4">Histórico de Transcrições</h2>
+            <div className="flex items-center justify-between mb-4">
+              <h2 className="text-xl font-semibold">Histórico de Transcrições</h2>
+              {languageSelect}
+              <button
+                onClick={async () => {
+                  try {
+                    await invoke("forget_last_transcription");
+                    setStatus("🧹 Último ditado apagado");
+                  } catch (error) {
+                    setStatus(`❌ ${error}`);
+                  }
+                  setTimeout(() => setStatus("Ready"), 2000);
+                }}
+                title="Apaga o último ditado do histórico, da conversa e da fila"
+                className="px-3 py-1 rounded text-xs bg-gray-700 text-gray-300 hover:bg-gray-600 transition-colors"
+              >
+                🧹 Esquecer último
+              </button>
+            </div>
+
+            {archiveAudio && (
+              <label className="flex items-center justify-end gap-2 text-xs text-gray-400 mb-3">
+                Re-transcrever com:
+                <select
+                  value={retranscribeModel}
+                  onChange={(e) => setRetranscribeModel(e.target.value)}
+                  className="bg-gray-700 text-white rounded px-2 py-1"
+                >
+                  <option value="gpt-4o-transcribe">gpt-4o-transcribe</option>
+                  <option value="gpt-4o-mini-transcribe">gpt-4o-mini-transcribe</option>
+                  <option value="whisper-1">whisper-1</option>
+                  <option value="local">Whisper local</option>
+                </select>
+              </label>
+            )}
 
             {transcriptionHistory.length === 0 ? (
               <p className="text-gray-400 text-center py-8">Nenhuma transcrição ainda</p>
@@ -689,15 +1578,99 @@ function App() {
                   >
                     <div className="flex items-start justify-between gap-4">
                       <div className="flex-1">
-                        <p className="text-sm text-gray-400 mb-2">{formatDate(entry.timestamp)}</p>
-                        <p className="text-white">{entry.text}</p>
+                        <p className="text-sm text-gray-400 mb-2">
+                          {formatDate(entry.timestamp)}
+                          {entry.language && <span className="ml-2 text-xs uppercase text-gray-500">{entry.language}</span>}
+                          {entry.mode === "cancelled" && <span className="ml-2 text-xs text-red-400">cancelado</span>}
+                        </p>
+                        {entry.title && <p className="text-white font-semibold mb-1">{entry.title}</p>}
+                        <p className={entry.title ? "text-gray-300 text-sm line-clamp-3" : "text-white"}>
+                          {withConfidence(entry)}
+                        </p>
+                        {entry.id != null && retranscriptions[entry.id] && (
+                          <p className="text-sm text-gray-300 mt-2 border-l-2 border-gray-500 pl-2">
+                            <span className="text-xs text-gray-500 mr-2">{retranscribeModel}</span>
+                            {retranscriptions[entry.id]}
+                          </p>
+                        )}
+                        {entry.id != null && revisions[entry.id] && (
+                          <div className="mt-2 space-y-1">
+                            {revisions[entry.id].length === 0 && (
+                              <p className="text-xs text-gray-500">Nenhuma outra versão</p>
+                            )}
+                            {revisions[entry.id].map((revision) => (
+                              <div key={revision.id} className="flex items-start gap-2 text-sm">
+                                <span className="text-xs text-gray-500 w-28 flex-shrink-0">
+                                  {REVISION_KINDS[revision.kind] ?? revision.kind}
+                                  {revision.model && <span className="block">{revision.model}</span>}
+                                </span>
+                                <span className="flex-1 text-gray-300">{revision.text}</span>
+                                {revision.text !== entry.text && (
+                                  <button
+                                    onClick={async () => {
+                                      try {
+                                        // history-updated reloads the entry
+                                        await invoke("restore_transcription_revision", { revisionId: revision.id });
+                                      } catch (error) {
+                                        setStatus(`❌ ${error}`);
+                                        setTimeout(() => setStatus("Ready"), 3000);
+                                      }
+                                    }}
+                                    className="px-2 py-0.5 bg-gray-600 hover:bg-gray-500 rounded text-xs"
+                                  >
+                                    Restaurar
+                                  </button>
+                                )}
+                              </div>
+                            ))}
+                          </div>
+                        )}
+                      </div>
+                      <div className="flex flex-col gap-2 flex-shrink-0">
+                        <button
+                          onClick={() => copyToClipboard(entry.text)}
+                          className="px-3 py-1 bg-blue-600 hover:bg-blue-700 rounded text-sm transition-colors"
+                        >
+                          📋 Copiar
+                        </button>
+                        {entry.id != null && (
+                          <button
+                            onClick={async () => {
+                              const id = entry.id as number;
+                              if (revisions[id]) {
+                                setRevisions(({ [id]: _, ...rest }) => rest);
+                                return;
+                              }
+                              const list = await invoke<TranscriptionRevision[]>("list_transcription_revisions", { id });
+                              setRevisions((prev) => ({ ...prev, [id]: list }));
+                            }}
+                            title="Versões desta transcrição: original, pós-processada, revisada, editada"
+                            className="px-3 py-1 bg-gray-600 hover:bg-gray-500 rounded text-sm transition-colors"
+                          >
+                            🕘 Versões
+                          </button>
+                        )}
+                        {archiveAudio && entry.id != null && entry.session_id && (
+                          <button
+                            onClick={async () => {
+                              const id = entry.id as number;
+                              setStatus("🔁 Transcrevendo de novo...");
+                              try {
+                                const text = await invoke<string>("retranscribe", { id, model: retranscribeModel });
+                                setRetranscriptions((prev) => ({ ...prev, [id]: text }));
+                                setStatus("Ready");
+                              } catch (error) {
+                                setStatus(`❌ ${error}`);
+                                setTimeout(() => setStatus("Ready"), 3000);
+                              }
+                            }}
+                            title="Transcreve de novo o áudio arquivado desta gravação"
+                            className="px-3 py-1 bg-gray-600 hover:bg-gray-500 rounded text-sm transition-colors"
+                          >
+                            🔁 Re-transcrever
+                          </button>
+                        )}
                       </div>
-                      <button
-                        onClick={() => copyToClipboard(entry.text)}
-                        className="px-3 py-1 bg-blue-600 hover:bg-blue-700 rounded text-sm transition-colors flex-shrink-0"
-                      >
-                        📋 Copiar
-                      </button>
                     </div>
                   </div>
                 ))}
@@ -830,6 +1803,7 @@ function App() {
             <div className="flex items-center justify-between">
               <h2 className="text-xl font-semibold">Estatísticas</h2>
               <div className="flex gap-1">
+                {languageSelect}
                 {([
                   ["today", "Hoje"],
                   ["7days", "7 Dias"],
@@ -946,12 +1920,501 @@ function App() {
                 )}
               </div>
 
+              <div>
+                <label className="block text-sm text-gray-400 mb-1">Ordem de preferência</label>
+                <p className="text-xs text-gray-500 mb-2">
+                  Cada gravação usa o primeiro desta lista que estiver conectado; vazia, usa o microfone acima
+                </p>
+                {microphonePriority.map((mic, index) => (
+                  <div key={mic} className="flex items-center gap-2 mb-1 text-sm">
+                    <span className="text-gray-500 w-4">{index + 1}.</span>
+                    <span className={`flex-1 ${availableMicrophones.includes(mic) ? "text-white" : "text-gray-500"}`}>
+                      {mic}
+                    </span>
+                    <button
+                      onClick={() => moveMicrophone(index, -1)}
+                      disabled={index === 0}
+                      className="px-2 py-0.5 bg-gray-700 hover:bg-gray-600 rounded text-xs disabled:opacity-30"
+                    >
+                      ↑
+                    </button>
+                    <button
+                      onClick={() => moveMicrophone(index, 1)}
+                      disabled={index === microphonePriority.length - 1}
+                      className="px-2 py-0.5 bg-gray-700 hover:bg-gray-600 rounded text-xs disabled:opacity-30"
+                    >
+                      ↓
+                    </button>
+                    <button
+                      onClick={() => saveMicrophonePriority(microphonePriority.filter((m) => m !== mic))}
+                      className="px-2 py-0.5 bg-gray-700 hover:bg-gray-600 rounded text-xs"
+                    >
+                      ✕
+                    </button>
+                  </div>
+                ))}
+                <select
+                  value=""
+                  onChange={(e) => e.target.value && saveMicrophonePriority([...microphonePriority, e.target.value])}
+                  className="w-full mt-1 px-4 py-2 bg-gray-700 text-white rounded border border-gray-600 focus:border-blue-500 focus:outline-none text-sm"
+                >
+                  <option value="">Adicionar microfone...</option>
+                  {availableMicrophones
+                    .filter((mic) => !microphonePriority.includes(mic))
+                    .map((mic) => (
+                      <option key={mic} value={mic}>
+                        {mic}
+                      </option>
+                    ))}
+                </select>
+              </div>
+
+              <div>
+                <div className="flex items-center justify-between">
+                  <div>
+                    <span className="text-sm text-gray-300">Ouvir o microfone (sidetone)</span>
+                    <p className="text-xs text-gray-500">
+                      Toca sua voz no fone enquanto grava; os outros apps ficam em silêncio em vez do áudio ser mutado
+                    </p>
+                  </div>
+                  <button
+                    onClick={() => saveSidetone({ ...sidetone, enabled: !sidetone.enabled })}
+                    className={`px-3 py-1 rounded text-xs transition-colors ${
+                      sidetone.enabled
+                        ? "bg-green-600 text-white"
+                        : "bg-gray-700 text-gray-300 hover:bg-gray-600"
+                    }`}
+                  >
+                    {sidetone.enabled ? "ON" : "OFF"}
+                  </button>
+                </div>
+                {sidetone.enabled && (
+                  <div className="flex items-center gap-4 mt-2 text-sm text-gray-400">
+                    <label className="flex items-center gap-2 flex-1">
+                      Volume
+                      <input
+                        type="range"
+                        min={0}
+                        max={100}
+                        value={sidetone.volume}
+                        onChange={(e) => setSidetone({ ...sidetone, volume: Number(e.target.value) })}
+                        onMouseUp={() => saveSidetone(sidetone)}
+                        className="flex-1"
+                      />
+                      <span className="w-10 text-right">{sidetone.volume}%</span>
+                    </label>
+                    <label className="flex items-center gap-2">
+                      Atraso
+                      <input
+                        type="number"
+                        min={10}
+                        max={500}
+                        step={10}
+                        value={sidetone.latency_ms}
+                        onChange={(e) => saveSidetone({ ...sidetone, latency_ms: Number(e.target.value) })}
+                        className="w-20 px-2 py-1 bg-gray-700 text-white rounded border border-gray-600 focus:border-blue-500 focus:outline-none"
+                      />
+                      ms
+                    </label>
+                  </div>
+                )}
+              </div>
+
+              <div className="flex items-center justify-between">
+                <div>
+                  <span className="text-sm text-gray-300">Evitar microfone Bluetooth</span>
+                  <p className="text-xs text-gray-500">
+                    Grava com outro microfone para o fone continuar em alta qualidade
+                  </p>
+                </div>
+                <button
+                  onClick={async () => {
+                    const newVal = !bluetoothMicFallback;
+                    setBluetoothMicFallback(newVal);
+                    await invoke("set_bluetooth_mic_fallback", { enabled: newVal });
+                  }}
+                  className={`px-3 py-1 rounded text-xs transition-colors ${
+                    bluetoothMicFallback
+                      ? "bg-green-600 text-white"
+                      : "bg-gray-700 text-gray-300 hover:bg-gray-600"
+                  }`}
+                >
+                  {bluetoothMicFallback ? "ON" : "OFF"}
+                </button>
+              </div>
+
+              <div className="flex items-center justify-between">
+                <div>
+                  <span className="text-sm text-gray-300">Modo exclusivo (baixa latência)</span>
+                  <p className="text-xs text-gray-500">
+                    Realtime: abre o microfone em modo exclusivo; outros apps não podem usá-lo durante a gravação
+                  </p>
+                </div>
+                <button
+                  onClick={async () => {
+                    const newVal = !exclusiveCapture;
+                    setExclusiveCapture(newVal);
+                    await invoke("set_exclusive_capture", { enabled: newVal });
+                  }}
+                  className={`px-3 py-1 rounded text-xs transition-colors ${
+                    exclusiveCapture
+                      ? "bg-green-600 text-white"
+                      : "bg-gray-700 text-gray-300 hover:bg-gray-600"
+                  }`}
+                >
+                  {exclusiveCapture ? "ON" : "OFF"}
+                </button>
+              </div>
+
               <button
                 onClick={loadMicrophones}
                 className="px-4 py-2 bg-gray-700 hover:bg-gray-600 rounded text-sm transition-colors"
               >
                 🔄 Recarregar Microfones
               </button>
+
+              <div className="border-t border-gray-700 pt-4 space-y-2">
+                <label className="block text-sm font-medium text-gray-300">Proxy</label>
+                <input
+                  type="text"
+                  value={proxySettings.url}
+                  onChange={(e) => setProxySettings({ ...proxySettings, url: e.target.value })}
+                  placeholder="http://proxy.empresa:8080 (vazio = conexão direta)"
+                  className="w-full px-3 py-2 bg-gray-700 text-white rounded border border-gray-600 focus:border-blue-500 focus:outline-none text-sm"
+                />
+                <div className="flex gap-2">
+                  <input
+                    type="text"
+                    value={proxySettings.username ?? ""}
+                    onChange={(e) => setProxySettings({ ...proxySettings, username: e.target.value || null })}
+                    placeholder="Usuário"
+                    className="flex-1 px-3 py-2 bg-gray-700 text-white rounded border border-gray-600 focus:border-blue-500 focus:outline-none text-sm"
+                  />
+                  <input
+                    type="password"
+                    value={proxySettings.password ?? ""}
+                    onChange={(e) => setProxySettings({ ...proxySettings, password: e.target.value || null })}
+                    placeholder="Senha"
+                    className="flex-1 px-3 py-2 bg-gray-700 text-white rounded border border-gray-600 focus:border-blue-500 focus:outline-none text-sm"
+                  />
+                </div>
+                <input
+                  type="text"
+                  value={proxySettings.bypass}
+                  onChange={(e) => setProxySettings({ ...proxySettings, bypass: e.target.value })}
+                  placeholder="Sem proxy para: localhost, .empresa.local"
+                  className="w-full px-3 py-2 bg-gray-700 text-white rounded border border-gray-600 focus:border-blue-500 focus:outline-none text-sm"
+                />
+                <button
+                  onClick={saveProxySettings}
+                  className="px-4 py-2 bg-gray-700 hover:bg-gray-600 rounded text-sm transition-colors"
+                >
+                  Salvar Proxy
+                </button>
+              </div>
+
+              <div className="border-t border-gray-700 pt-4 space-y-2">
+                <label className="block text-sm font-medium text-gray-300">Tempo limite das requisições (s)</label>
+                <p className="text-xs text-gray-500">
+                  Requisições travadas falham e vão para a fila offline. Vazio = sem limite
+                </p>
+                {([
+                  ["transcription_secs", "Transcrição"],
+                  ["response_secs", "Respostas"],
+                  ["tts_secs", "Leitura em voz alta"],
+                ] as [keyof RequestTimeouts, string][]).map(([key, label]) => (
+                  <div key={key} className="flex items-center justify-between">
+                    <span className="text-sm text-gray-400">{label}</span>
+                    <input
+                      type="number"
+                      min={0}
+                      value={requestTimeouts[key] ?? ""}
+                      onChange={(e) => updateRequestTimeout(key, e.target.value)}
+                      className="w-24 px-2 py-1 bg-gray-700 text-white rounded border border-gray-600 focus:border-blue-500 focus:outline-none text-sm"
+                    />
+                  </div>
+                ))}
+              </div>
+
+              <div className="border-t border-gray-700 pt-4 space-y-2">
+                <label className="block text-sm font-medium text-gray-300">Decodificação do Whisper (avançado)</label>
+                <p className="text-xs text-gray-500">
+                  Só para o modelo whisper-1. Em áudio ruidoso, tentar de novo com temperatura maior costuma melhorar o resultado
+                </p>
+                <div className="flex items-center justify-between">
+                  <span className="text-sm text-gray-400">Temperatura (0 a 1)</span>
+                  <input
+                    type="number"
+                    min={0}
+                    max={1}
+                    step={0.1}
+                    value={whisperDecoding.temperature}
+                    onChange={(e) => updateWhisperDecoding({ temperature: Math.min(1, Math.max(0, parseFloat(e.target.value) || 0)) })}
+                    className="w-24 px-2 py-1 bg-gray-700 text-white rounded border border-gray-600 focus:border-blue-500 focus:outline-none text-sm"
+                  />
+                </div>
+                <div className="flex items-center justify-between">
+                  <span className="text-sm text-gray-400">Tentar mais quente se o resultado for duvidoso</span>
+                  <button
+                    onClick={() => updateWhisperDecoding({ temperature_sweep: !whisperDecoding.temperature_sweep })}
+                    className={`px-3 py-1 rounded text-xs transition-colors ${
+                      whisperDecoding.temperature_sweep
+                        ? "bg-green-600 text-white"
+                        : "bg-gray-700 text-gray-300 hover:bg-gray-600"
+                    }`}
+                  >
+                    {whisperDecoding.temperature_sweep ? "ON" : "OFF"}
+                  </button>
+                </div>
+                <div className="flex items-center justify-between">
+                  <span className="text-sm text-gray-400">Confiança mínima por palavra (0 a 1)</span>
+                  <input
+                    type="number"
+                    min={0}
+                    max={1}
+                    step={0.05}
+                    value={whisperDecoding.confidence_threshold}
+                    onChange={(e) => updateWhisperDecoding({ confidence_threshold: Math.min(1, Math.max(0, parseFloat(e.target.value) || 0)) })}
+                    className="w-24 px-2 py-1 bg-gray-700 text-white rounded border border-gray-600 focus:border-blue-500 focus:outline-none text-sm"
+                  />
+                </div>
+                <div className="flex items-center justify-between">
+                  <span className="text-sm text-gray-400">Manter palavras abaixo dela (sublinhadas no histórico)</span>
+                  <button
+                    onClick={() => updateWhisperDecoding({ keep_low_confidence: !whisperDecoding.keep_low_confidence })}
+                    className={`px-3 py-1 rounded text-xs transition-colors ${
+                      whisperDecoding.keep_low_confidence
+                        ? "bg-green-600 text-white"
+                        : "bg-gray-700 text-gray-300 hover:bg-gray-600"
+                    }`}
+                  >
+                    {whisperDecoding.keep_low_confidence ? "ON" : "OFF"}
+                  </button>
+                </div>
+                <div className="flex items-center justify-between">
+                  <span className="text-sm text-gray-400">Descartar trechos de silêncio acima de (0 a 1, vazio = não)</span>
+                  <input
+                    type="number"
+                    min={0}
+                    max={1}
+                    step={0.1}
+                    value={whisperDecoding.no_speech_threshold ?? ""}
+                    onChange={(e) => {
+                      const value = parseFloat(e.target.value);
+                      updateWhisperDecoding({ no_speech_threshold: isNaN(value) ? null : Math.min(1, Math.max(0, value)) });
+                    }}
+                    className="w-24 px-2 py-1 bg-gray-700 text-white rounded border border-gray-600 focus:border-blue-500 focus:outline-none text-sm"
+                  />
+                </div>
+              </div>
+
+              <div className="border-t border-gray-700 pt-4 space-y-2">
+                <label className="block text-sm font-medium text-gray-300">Texto simples ao colar</label>
+                <p className="text-xs text-gray-500">
+                  Remove markdown (negrito, títulos, links) e normaliza aspas, travessões e espaços
+                </p>
+                {([
+                  ["transcription", "Transcrições"],
+                  ["prompt", "Respostas do GPT"],
+                ] as [keyof PlainTextSettings, string][]).map(([key, label]) => (
+                  <div key={key} className="flex items-center justify-between">
+                    <span className="text-sm text-gray-400">{label}</span>
+                    <button
+                      onClick={() => togglePlainText(key)}
+                      className={`px-3 py-1 rounded text-xs transition-colors ${
+                        plainText[key]
+                          ? "bg-green-600 text-white"
+                          : "bg-gray-700 text-gray-300 hover:bg-gray-600"
+                      }`}
+                    >
+                      {plainText[key] ? "ON" : "OFF"}
+                    </button>
+                  </div>
+                ))}
+                <div className="flex items-center justify-between">
+                  <span className="text-sm text-gray-400">Respostas com formatação (Word, Gmail)</span>
+                  <button
+                    onClick={async () => {
+                      const newVal = !richTextPaste;
+                      setRichTextPaste(newVal);
+                      await invoke("set_rich_text_paste", { enabled: newVal });
+                    }}
+                    disabled={plainText.prompt}
+                    className={`px-3 py-1 rounded text-xs transition-colors disabled:opacity-50 ${
+                      richTextPaste
+                        ? "bg-green-600 text-white"
+                        : "bg-gray-700 text-gray-300 hover:bg-gray-600"
+                    }`}
+                  >
+                    {richTextPaste ? "ON" : "OFF"}
+                  </button>
+                </div>
+              </div>
+
+              <div className="border-t border-gray-700 pt-4 space-y-2">
+                <label className="flex items-center justify-between text-sm text-gray-400">
+                  Números por extenso
+                  <select
+                    value={numberNormalization}
+                    onChange={async (e) => {
+                      setNumberNormalization(e.target.value);
+                      await invoke("set_number_normalization", { mode: e.target.value });
+                    }}
+                    className="px-2 py-1 bg-gray-700 text-white rounded border border-gray-600 focus:border-blue-500 focus:outline-none text-xs"
+                  >
+                    <option value="off">Manter como ditado</option>
+                    <option value="dates">Datas em dígitos</option>
+                    <option value="all">Todos em dígitos</option>
+                  </select>
+                </label>
+                <p className="text-xs text-gray-500">
+                  "vinte e cinco de março" vira "25 de março"; com todos, "mil e quinhentos reais" vira "1500 reais". Só para transcrições em português
+                </p>
+              </div>
+
+              <div className="border-t border-gray-700 pt-4 space-y-2">
+                <div className="flex items-center justify-between">
+                  <span className="text-sm font-medium text-gray-300">Filtro de palavrões</span>
+                  <button
+                    onClick={async () => {
+                      const updated = { ...profanity, enabled: !profanity.enabled };
+                      setProfanity(updated);
+                      await invoke("set_profanity_settings", { settings: updated });
+                    }}
+                    className={`px-3 py-1 rounded text-xs transition-colors ${
+                      profanity.enabled
+                        ? "bg-green-600 text-white"
+                        : "bg-gray-700 text-gray-300 hover:bg-gray-600"
+                    }`}
+                  >
+                    {profanity.enabled ? "ON" : "OFF"}
+                  </button>
+                </div>
+                <p className="text-xs text-gray-500">
+                  Mascara palavrões nas transcrições antes de salvar e colar ("m****"), com a lista do idioma falado (português, inglês, espanhol)
+                </p>
+                <input
+                  type="text"
+                  value={profanityWords}
+                  onChange={(e) => setProfanityWords(e.target.value)}
+                  onBlur={async () => {
+                    const words = profanityWords.split(",").map((w) => w.trim()).filter((w) => w);
+                    const updated = { ...profanity, words };
+                    setProfanity(updated);
+                    await invoke("set_profanity_settings", { settings: updated });
+                  }}
+                  disabled={!profanity.enabled}
+                  placeholder="Outras palavras, separadas por vírgula"
+                  className="w-full px-2 py-1 bg-gray-700 text-white rounded border border-gray-600 focus:border-blue-500 focus:outline-none text-sm disabled:opacity-50"
+                />
+              </div>
+
+              <div className="border-t border-gray-700 pt-4 space-y-2">
+                <div className="flex items-center justify-between">
+                  <label className="block text-sm font-medium text-gray-300">Plugins (Rhai)</label>
+                  <button
+                    onClick={reloadPlugins}
+                    className="px-3 py-1 rounded text-xs bg-gray-700 text-gray-300 hover:bg-gray-600 transition-colors"
+                  >
+                    Recarregar
+                  </button>
+                </div>
+                <p className="text-xs text-gray-500 break-all">
+                  Scripts .rhai em {plugins.dir} com on_transcription, on_before_paste ou on_prompt_response
+                </p>
+                {plugins.plugins.length === 0 && <p className="text-xs text-gray-500">Nenhum plugin encontrado</p>}
+                {plugins.plugins.map((plugin) => (
+                  <div key={plugin.name} className="flex items-center justify-between">
+                    <div className="min-w-0">
+                      <span className="text-sm text-gray-400">{plugin.name}</span>
+                      <p className={`text-xs truncate ${plugin.error ? "text-red-400" : "text-gray-500"}`}>
+                        {plugin.error ?? (plugin.hooks.join(", ") || "Nenhum hook definido")}
+                      </p>
+                    </div>
+                    <button
+                      onClick={() => togglePlugin(plugin)}
+                      disabled={plugin.error !== null}
+                      className={`px-3 py-1 rounded text-xs transition-colors disabled:opacity-50 ${
+                        plugin.enabled
+                          ? "bg-green-600 text-white"
+                          : "bg-gray-700 text-gray-300 hover:bg-gray-600"
+                      }`}
+                    >
+                      {plugin.enabled ? "ON" : "OFF"}
+                    </button>
+                  </div>
+                ))}
+              </div>
+
+              <div className="border-t border-gray-700 pt-4 space-y-2">
+                <label className="block text-sm font-medium text-gray-300">Fila offline</label>
+                <p className="text-xs text-gray-500">
+                  Quando o espaço acaba, os áudios mais antigos da fila são descartados
+                </p>
+                {([
+                  ["max_items", "Itens na fila"],
+                  ["max_disk_mb", "Espaço máximo (MB)"],
+                ] as [keyof QueueLimits, string][]).map(([key, label]) => (
+                  <div key={key} className="flex items-center justify-between">
+                    <span className="text-sm text-gray-400">{label}</span>
+                    <input
+                      type="number"
+                      min={1}
+                      value={queueLimits[key]}
+                      onChange={(e) => updateQueueLimit(key, e.target.value)}
+                      className="w-24 px-2 py-1 bg-gray-700 text-white rounded border border-gray-600 focus:border-blue-500 focus:outline-none text-sm"
+                    />
+                  </div>
+                ))}
+              </div>
+
+              <div className="border-t border-gray-700 pt-4 space-y-2">
+                <label className="block text-sm font-medium text-gray-300">Títulos do histórico</label>
+                <select
+                  value={titleMode}
+                  onChange={(e) => selectTitleMode(e.target.value as TitleMode)}
+                  className="w-full px-3 py-2 bg-gray-700 text-white rounded border border-gray-600 focus:border-blue-500 focus:outline-none text-sm"
+                >
+                  <option value="local">Primeira frase (local)</option>
+                  <option value="gpt">Gerado pelo GPT (gpt-4o-mini)</option>
+                  <option value="off">Desativado</option>
+                </select>
+              </div>
+
+              <div className="border-t border-gray-700 pt-4 space-y-2">
+                <label className="block text-sm font-medium text-gray-300">Atualizações</label>
+                <select
+                  value={updateChannel}
+                  onChange={(e) => selectUpdateChannel(e.target.value as "stable" | "beta")}
+                  className="w-full px-3 py-2 bg-gray-700 text-white rounded border border-gray-600 focus:border-blue-500 focus:outline-none text-sm"
+                >
+                  <option value="stable">Estável</option>
+                  <option value="beta">Beta (versões de teste, podem ter bugs)</option>
+                </select>
+              </div>
+
+              <div className="border-t border-gray-700 pt-4 space-y-2">
+                <label className="block text-sm font-medium text-gray-300">Atalhos</label>
+                <p className="text-xs text-gray-500">
+                  Ex.: Ctrl+Alt+Escape, Alt+Shift+Z. Vazio = padrão
+                </p>
+                {hotkeys.map((binding) => (
+                  <div key={binding.action} className="flex items-center justify-between gap-2">
+                    <span className="text-sm text-gray-400">{binding.label}</span>
+                    <input
+                      type="text"
+                      defaultValue={binding.hotkey}
+                      key={`${binding.action}-${binding.hotkey}`}
+                      placeholder={binding.default_hotkey}
+                      onBlur={(e) => {
+                        if (e.target.value.trim() !== binding.hotkey) updateHotkey(binding.action, e.target.value);
+                      }}
+                      className="w-40 px-2 py-1 bg-gray-700 text-white rounded border border-gray-600 focus:border-blue-500 focus:outline-none text-sm"
+                    />
+                  </div>
+                ))}
+                {hotkeyError && <p className="text-xs text-red-400">{hotkeyError}</p>}
+              </div>
             </div>
           </div>
         )}
@@ -0,0 +1,106 @@
+// Payloads of the events emitted by the backend (mirror of AppEvent in src-tauri/src/events.rs)
+
+import { listen, type EventCallback, type UnlistenFn } from "@tauri-apps/api/event";
+
+export interface RateLimitEvent {
+  endpoint: string;
+  retry_after_ms: number;
+  rejected: boolean; // true for a 429, false when the quota just ran out
+}
+
+export interface UpdateInfo {
+  version: string;
+  current_version: string;
+  notes: string | null;
+  channel: "stable" | "beta";
+}
+
+export interface MeetingEntry {
+  id: number;
+  started_at: number;
+  ended_at: number | null;
+  transcript: string;
+  rolling_summary: string;
+  final_summary: string | null;
+  action_items: string[];
+}
+
+// One turn of a live session: `text` is the turn so far; a higher seq replaces a lower one
+export interface TranscriptSegment {
+  item_id: string;
+  seq: number;
+  text: string;
+  final: boolean;
+  timestamp: number;
+}
+
+export interface WordConfidence {
+  word: string;
+  probability: number | null;
+}
+
+export interface AppEvents {
+  // Recording lifecycle
+  "toggle-recording": null;
+  "widget-stop-recording": null;
+  "recording-cancelled": null;
+  "recording-offline": { session_id: string; message: string };
+  "recording-error": { session_id: string; message: string };
+  "no-speech-detected": { session_id: string };
+  "transcription-delta": { session_id: string; delta: string; segment: TranscriptSegment };
+  "recording-elapsed": {
+    session_id: string;
+    elapsed_secs: number;
+    mode: "whisper" | "realtime";
+    warning_at_secs: number;
+    limit_secs: number;
+  };
+  "mic-clipping": number;
+  "mic-mute-toggled": { session_id: string | null; muted: boolean }; // null for a meeting
+  "bluetooth-mic-warning": { device: string; replacement: string | null };
+  "model-selected": string;
+
+  // Results
+  "history-updated": { session_id: string | null }; // null outside a session (delete, clear, import)
+  "conversation-updated": { session_id: string | null };
+  "review-text": string;
+  "paste-failed": { session_id: string | null; text: string };
+  "request-cancelled": { session_id: string };
+  "ready-to-paste": string; // held by "paste when I click" until the next click
+  "password-field-focused": null; // paste refused, the text is on the clipboard
+  "response-ready": { session_id: string | null };
+  "screenshot-attached": string; // base64 PNG
+
+  // Offline queue and network
+  "queue-updated": number;
+  "queue-full": null;
+  "queue-item-completed": number;
+  "network-online": null;
+  "network-offline": null;
+  "rate-limited": RateLimitEvent;
+  "update-available": UpdateInfo;
+
+  // Settings toggled outside the main window (hotkeys, tray)
+  "tts-toggled": boolean;
+  "tts-speed-changed": number;
+  "tts-toast-show": boolean;
+  "incognito-toggled": boolean;
+  "assistant-mode-changed": boolean;
+  "profile-switched": string;
+  "spend-alert": { spent_cents: number; threshold_cents: number };
+
+  // Meeting mode
+  "meeting-started": number;
+  "meeting-transcript": string;
+  "meeting-summary": string;
+  "meeting-finished": MeetingEntry;
+  "keyword-detected": { keyword: string; context: string };
+}
+
+// Typed `listen` for backend events
+export function listenAppEvent<K extends keyof AppEvents>(
+  name: K,
+  handler: EventCallback<AppEvents[K]>
+): Promise<UnlistenFn> {
+  return listen<AppEvents[K]>(name, handler);
+}
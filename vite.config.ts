@@ -13,6 +13,7 @@ export default defineConfig(async () => ({
         main: resolve(__dirname, "index.html"),
         widget: resolve(__dirname, "widget.html"),
         "prompt-input": resolve(__dirname, "prompt-input.html"),
+        "paste-review": resolve(__dirname, "paste-review.html"),
         "tts-widget": resolve(__dirname, "tts-widget.html"),
         "tts-toast": resolve(__dirname, "tts-toast.html"),
         warning: resolve(__dirname, "warning.html"),